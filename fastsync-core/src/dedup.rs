@@ -0,0 +1,260 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 重复上传过滤模块。
+ * 手机在网络不稳定时会重试上传，同一设备短时间内再次发送相同内容时只处理第一次，
+ * 不再重复落盘与弹出通知。按内容哈希记录最近接收的图片与短信，超过有效期或数量上限时淘汰最久未命中的记录。
+ * 剪贴板推送带有手机上的复制时间，按设备只记录最近一次：复制时间与内容都相同的视为重发，
+ * 复制时间早于最近一次的视为迟到的旧内容，均不再通知。
+ */
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 最多记录的内容数。
+pub const DEDUP_CAPACITY: usize = 100;
+
+/// 图片记录的有效期，超过后相同内容视为新的上传。
+pub const DEDUP_TTL: Duration = Duration::from_secs(10 * 60);
+
+struct RecentUpload {
+    device: Option<String>,
+    /// 内容的 SHA-256
+    hash: String,
+    /// 第一次接收时的内容标识
+    id: String,
+    received_at: Instant,
+}
+
+/// 最近接收的内容，由所有接口共享。
+pub struct RecentUploads {
+    enabled: AtomicBool,
+    ttl: RwLock<Duration>,
+    entries: Mutex<VecDeque<RecentUpload>>,
+}
+
+impl RecentUploads {
+    /// # Arguments
+    /// * `enabled` - 是否过滤重复上传，关闭后每次上传都会处理
+    /// * `ttl` - 记录的有效期
+    pub fn new(enabled: bool, ttl: Duration) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            ttl: RwLock::new(ttl),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 修改过滤设置，关闭时清空已有的记录。
+    ///
+    /// # Arguments
+    /// * `enabled` - 是否过滤重复上传
+    /// * `ttl` - 记录的有效期
+    pub fn configure(&self, enabled: bool, ttl: Duration) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        *self.ttl.write().unwrap() = ttl;
+        if !enabled {
+            self.entries.lock().unwrap().clear();
+        }
+    }
+
+    /// 查询同一设备是否在有效期内发送过相同内容，命中的记录移到队尾。
+    ///
+    /// # Arguments
+    /// * `device` - 发送内容的设备
+    /// * `hash` - 内容的 SHA-256
+    /// * `now` - 当前时间
+    ///
+    /// # Returns
+    /// 第一次接收时的内容标识
+    pub fn find(&self, device: Option<&str>, hash: &str, now: Instant) -> Option<String> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let ttl = *self.ttl.read().unwrap();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| now.duration_since(entry.received_at) < ttl);
+        let position = entries
+            .iter()
+            .position(|entry| entry.hash == hash && entry.device.as_deref() == device)?;
+        let entry = entries.remove(position)?;
+        let id = entry.id.clone();
+        entries.push_back(entry);
+        Some(id)
+    }
+
+    /// 记录一次成功接收的内容。
+    ///
+    /// # Arguments
+    /// * `device` - 发送内容的设备
+    /// * `hash` - 内容的 SHA-256
+    /// * `id` - 内容标识
+    /// * `now` - 当前时间
+    pub fn record(&self, device: Option<&str>, hash: &str, id: &str, now: Instant) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let ttl = *self.ttl.read().unwrap();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|entry| {
+            now.duration_since(entry.received_at) < ttl && !(entry.hash == hash && entry.device.as_deref() == device)
+        });
+        entries.push_back(RecentUpload {
+            device: device.map(str::to_string),
+            hash: hash.to_string(),
+            id: id.to_string(),
+            received_at: now,
+        });
+        while entries.len() > DEDUP_CAPACITY {
+            entries.pop_front();
+        }
+    }
+}
+
+/// 剪贴板推送的判定结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOrder {
+    /// 新的内容
+    New,
+    /// 与最近一次的复制时间和内容都相同
+    Duplicate,
+    /// 复制时间早于最近一次
+    Outdated {
+        /// 最近一次的复制时间（毫秒时间戳）
+        latest: i64,
+    },
+}
+
+/// 各设备最近一次剪贴板推送的复制时间与内容哈希。
+pub struct ClipboardPushes {
+    enforce_order: AtomicBool,
+    latest: Mutex<HashMap<Option<String>, (i64, String)>>,
+}
+
+impl ClipboardPushes {
+    /// # Arguments
+    /// * `enforce_order` - 是否丢弃复制时间早于最近一次的推送，手机时钟不可靠时关闭
+    pub fn new(enforce_order: bool) -> Self {
+        Self {
+            enforce_order: AtomicBool::new(enforce_order),
+            latest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 修改是否丢弃复制时间早于最近一次的推送。
+    pub fn set_enforce_order(&self, enforce_order: bool) {
+        self.enforce_order.store(enforce_order, Ordering::Relaxed);
+    }
+
+    /// 判定一次推送，新的内容记为该设备最近一次推送。没有复制时间（为 0）的推送总是视为新的内容。
+    ///
+    /// # Arguments
+    /// * `device` - 发送内容的设备
+    /// * `timestamp` - 手机上的复制时间（毫秒时间戳）
+    /// * `hash` - 文本的 SHA-256
+    pub fn check(&self, device: Option<&str>, timestamp: i64, hash: &str) -> PushOrder {
+        if timestamp == 0 {
+            return PushOrder::New;
+        }
+        let mut latest = self.latest.lock().unwrap();
+        let key = device.map(str::to_string);
+        if let Some((last_timestamp, last_hash)) = latest.get(&key) {
+            if *last_timestamp == timestamp && last_hash == hash {
+                return PushOrder::Duplicate;
+            }
+            if self.enforce_order.load(Ordering::Relaxed) && timestamp < *last_timestamp {
+                return PushOrder::Outdated { latest: *last_timestamp };
+            }
+        }
+        latest.insert(key, (timestamp, hash.to_string()));
+        PushOrder::New
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_repeated_uploads_from_the_same_device() {
+        let uploads = RecentUploads::new(true, DEDUP_TTL);
+        let now = Instant::now();
+        assert_eq!(uploads.find(Some("phone"), "hash", now), None);
+        uploads.record(Some("phone"), "hash", "first", now);
+        assert_eq!(uploads.find(Some("phone"), "hash", now).as_deref(), Some("first"));
+        assert_eq!(uploads.find(Some("tablet"), "hash", now), None);
+        assert_eq!(uploads.find(None, "hash", now), None);
+        assert_eq!(uploads.find(Some("phone"), "other", now), None);
+    }
+
+    #[test]
+    fn forgets_uploads_after_the_ttl() {
+        let uploads = RecentUploads::new(true, Duration::from_secs(60));
+        let now = Instant::now();
+        uploads.record(None, "hash", "first", now);
+        assert!(uploads.find(None, "hash", now + Duration::from_secs(59)).is_some());
+        assert!(uploads.find(None, "hash", now + Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_seen_upload() {
+        let uploads = RecentUploads::new(true, DEDUP_TTL);
+        let now = Instant::now();
+        for i in 0..DEDUP_CAPACITY {
+            uploads.record(None, &i.to_string(), &i.to_string(), now);
+        }
+        // 命中的记录移到队尾，不会被先淘汰
+        assert!(uploads.find(None, "0", now).is_some());
+        uploads.record(None, "new", "new", now);
+        assert!(uploads.find(None, "0", now).is_some());
+        assert!(uploads.find(None, "1", now).is_none());
+    }
+
+    #[test]
+    fn disabled_filter_never_matches() {
+        let uploads = RecentUploads::new(false, DEDUP_TTL);
+        let now = Instant::now();
+        uploads.record(None, "hash", "first", now);
+        assert_eq!(uploads.find(None, "hash", now), None);
+    }
+
+    #[test]
+    fn reconfigured_filter_uses_the_new_settings() {
+        let uploads = RecentUploads::new(true, Duration::from_secs(60));
+        let now = Instant::now();
+        uploads.record(None, "hash", "first", now);
+        uploads.configure(true, Duration::from_secs(10));
+        assert!(uploads.find(None, "hash", now + Duration::from_secs(10)).is_none());
+
+        uploads.record(None, "hash", "second", now);
+        uploads.configure(false, Duration::from_secs(10));
+        uploads.configure(true, Duration::from_secs(10));
+        assert_eq!(uploads.find(None, "hash", now), None);
+    }
+
+    #[test]
+    fn orders_clipboard_pushes() {
+        let pushes = ClipboardPushes::new(true);
+        assert_eq!(pushes.check(Some("phone"), 2_000, "a"), PushOrder::New);
+        assert_eq!(pushes.check(Some("phone"), 2_000, "a"), PushOrder::Duplicate);
+        assert_eq!(pushes.check(Some("phone"), 1_000, "b"), PushOrder::Outdated { latest: 2_000 });
+        assert_eq!(pushes.check(Some("tablet"), 1_000, "b"), PushOrder::New);
+        assert_eq!(pushes.check(Some("phone"), 2_000, "c"), PushOrder::New);
+        // 没有复制时间的推送不参与判定
+        assert_eq!(pushes.check(Some("phone"), 0, "c"), PushOrder::New);
+        assert_eq!(pushes.check(Some("phone"), 0, "c"), PushOrder::New);
+    }
+
+    #[test]
+    fn unordered_pushes_accept_older_content() {
+        let pushes = ClipboardPushes::new(false);
+        assert_eq!(pushes.check(None, 2_000, "a"), PushOrder::New);
+        assert_eq!(pushes.check(None, 1_000, "b"), PushOrder::New);
+        assert_eq!(pushes.check(None, 1_000, "b"), PushOrder::Duplicate);
+
+        pushes.set_enforce_order(true);
+        assert_eq!(pushes.check(None, 500, "c"), PushOrder::Outdated { latest: 1_000 });
+    }
+}
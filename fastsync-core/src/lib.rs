@@ -0,0 +1,18 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * FastSync 协议核心。
+ * 手机端与接收端之间的载荷格式、配对二维码、捕获时间校正与重复内容判定，
+ * 不依赖 HTTP 框架与系统接口，接收端与模拟器共用，手机端按相同规则实现。
+ */
+pub mod dedup;
+pub mod pairing;
+pub mod protocol;
+pub mod timeline;
+
+/// 手机端与接收端之间的接口协议版本，接口出现不兼容的改动时递增，`/info` 中返回。
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// mDNS 广播的服务类型。
+pub const MDNS_SERVICE_TYPE: &str = "_photosync._tcp.local.";
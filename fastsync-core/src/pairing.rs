@@ -0,0 +1,189 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 配对二维码载荷。
+ * 格式: `fastsync://pair?v=1&u=<base-url>&t=<one-time-token>&f=<fp16>&c=<sha256>&n=<name>`，
+ * 参数按 application/x-www-form-urlencoded 编码，顺序固定，`f` 仅在启用双向 TLS 时出现，
+ * `c` 仅在监听端口使用 HTTPS 时出现，手机端用它固定自签名证书。
+ * 手机端需按相同规则生成与解析，保证逐字节一致。
+ */
+use std::fmt;
+use std::str::FromStr;
+
+/// 当前载荷版本。
+pub const PAIRING_URI_VERSION: u32 = 1;
+
+const PREFIX: &str = "fastsync://pair?";
+
+/// 配对二维码中携带的信息。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairingUri {
+    /// 接收端地址，例如 `https://192.168.1.5:3000`
+    pub base_url: String,
+    /// 一次性配对令牌，在 `/pair/qr` 换取长期设备令牌
+    pub token: String,
+    /// mTLS CA 证书 SHA-256 指纹的前 16 个十六进制字符
+    pub fingerprint: Option<String>,
+    /// HTTPS 证书的 SHA-256 指纹，64 个十六进制字符
+    pub certificate: Option<String>,
+    /// 电脑名称
+    pub name: String,
+}
+
+impl fmt::Display for PairingUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.append_pair("v", &PAIRING_URI_VERSION.to_string());
+        query.append_pair("u", &self.base_url);
+        query.append_pair("t", &self.token);
+        if let Some(fingerprint) = &self.fingerprint {
+            query.append_pair("f", fingerprint);
+        }
+        if let Some(certificate) = &self.certificate {
+            query.append_pair("c", certificate);
+        }
+        query.append_pair("n", &self.name);
+        write!(f, "{}{}", PREFIX, query.finish())
+    }
+}
+
+impl FromStr for PairingUri {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(query) = s.strip_prefix(PREFIX) else {
+            anyhow::bail!("Not a FastSync pairing URI");
+        };
+
+        let mut version = None;
+        let mut base_url = None;
+        let mut token = None;
+        let mut fingerprint = None;
+        let mut certificate = None;
+        let mut name = None;
+
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let slot = match key.as_ref() {
+                "v" => &mut version,
+                "u" => &mut base_url,
+                "t" => &mut token,
+                "f" => &mut fingerprint,
+                "c" => &mut certificate,
+                "n" => &mut name,
+                // 未知参数留给后续版本扩展
+                _ => continue,
+            };
+            if slot.replace(value.into_owned()).is_some() {
+                anyhow::bail!("Duplicate `{}` parameter in pairing URI", key);
+            }
+        }
+
+        match version.as_deref() {
+            Some(v) if v == PAIRING_URI_VERSION.to_string() => {}
+            Some(v) => anyhow::bail!("Unsupported pairing URI version {}", v),
+            None => anyhow::bail!("Missing `v` parameter in pairing URI"),
+        }
+
+        let required = |value: Option<String>, key: &str| {
+            value
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow::anyhow!("Missing `{}` parameter in pairing URI", key))
+        };
+        let base_url = required(base_url, "u")?;
+        let token = required(token, "t")?;
+        let name = required(name, "n")?;
+
+        if let Some(fp) = &fingerprint {
+            if fp.len() != 16 || !fp.bytes().all(|b| b.is_ascii_hexdigit()) {
+                anyhow::bail!("Malformed certificate fingerprint in pairing URI");
+            }
+        }
+        if let Some(fp) = &certificate {
+            if fp.len() != 64 || !fp.bytes().all(|b| b.is_ascii_hexdigit()) {
+                anyhow::bail!("Malformed HTTPS certificate fingerprint in pairing URI");
+            }
+        }
+
+        Ok(Self {
+            base_url,
+            token,
+            fingerprint,
+            certificate,
+            name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FP16: &str = "0123456789abcdef";
+    const FP64: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789ABCDEF";
+
+    fn uri(fingerprint: Option<&str>, certificate: Option<&str>, name: &str) -> PairingUri {
+        PairingUri {
+            base_url: "https://192.168.1.5:3000".to_string(),
+            token: "Zm9vYmFy-_".to_string(),
+            fingerprint: fingerprint.map(str::to_string),
+            certificate: certificate.map(str::to_string),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn formats_parameters_in_fixed_order() {
+        assert_eq!(
+            uri(Some(FP16), None, "My PC").to_string(),
+            "fastsync://pair?v=1&u=https%3A%2F%2F192.168.1.5%3A3000&t=Zm9vYmFy-_&f=0123456789abcdef&n=My+PC"
+        );
+        assert_eq!(
+            uri(None, None, "书房电脑").to_string(),
+            "fastsync://pair?v=1&u=https%3A%2F%2F192.168.1.5%3A3000&t=Zm9vYmFy-_&n=%E4%B9%A6%E6%88%BF%E7%94%B5%E8%84%91"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let names = ["PC", "My PC", "书房电脑", "a&b=c+d%", "🖥️ desk"];
+        let fingerprints = [None, Some(FP16)];
+        let certificates = [None, Some(FP64)];
+        for name in names {
+            for fingerprint in fingerprints {
+                for certificate in certificates {
+                    let original = uri(fingerprint, certificate, name);
+                    let text = original.to_string();
+                    assert_eq!(text.parse::<PairingUri>().unwrap(), original, "{}", text);
+                    assert_eq!(text.parse::<PairingUri>().unwrap().to_string(), text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_parameters() {
+        let text = "fastsync://pair?v=1&u=http%3A%2F%2Fpc%3A3000&t=abc&n=PC&x=future";
+        assert_eq!(text.parse::<PairingUri>().unwrap().name, "PC");
+    }
+
+    #[test]
+    fn rejects_malformed_payloads() {
+        let cases = [
+            ("https://pair?v=1&u=x&t=abc&n=PC", "Not a FastSync"),
+            ("fastsync://pair?u=x&t=abc&n=PC", "Missing `v`"),
+            ("fastsync://pair?v=2&u=x&t=abc&n=PC", "Unsupported pairing URI version 2"),
+            ("fastsync://pair?v=1&t=abc&n=PC", "Missing `u`"),
+            ("fastsync://pair?v=1&u=x&t=&n=PC", "Missing `t`"),
+            ("fastsync://pair?v=1&u=x&t=abc", "Missing `n`"),
+            ("fastsync://pair?v=1&u=x&t=abc&t=def&n=PC", "Duplicate `t`"),
+            ("fastsync://pair?v=1&u=x&t=abc&f=0123&n=PC", "Malformed certificate fingerprint"),
+            ("fastsync://pair?v=1&u=x&t=abc&f=0123456789abcdeg&n=PC", "Malformed certificate fingerprint"),
+            ("fastsync://pair?v=1&u=x&t=abc&c=0123456789abcdef&n=PC", "Malformed HTTPS certificate"),
+        ];
+        for (text, expected) in cases {
+            let error = text.parse::<PairingUri>().unwrap_err().to_string();
+            assert!(error.contains(expected), "{}: {}", text, error);
+        }
+    }
+}
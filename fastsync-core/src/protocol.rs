@@ -0,0 +1,92 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 接口载荷。
+ * 手机端推送短信与剪贴板时的 JSON 请求体，接收端与模拟器共用，字段名即线上格式。
+ */
+use serde::{Deserialize, Serialize};
+
+/// 短信数据载荷结构体。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsPayload {
+    pub sender: String,
+    /// 手机通讯录中的联系人名称，通知标题优先显示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_name: Option<String>,
+    pub content: String,
+    /// 识别出的验证码，旧版手机端可能不发送，缺省为空
+    #[serde(default)]
+    pub code: String,
+    /// 短信在手机上收到的时间（毫秒时间戳），离线补发时用于排序，也接受 `timestamp` 字段名
+    #[serde(default, alias = "timestamp", skip_serializing_if = "Option::is_none")]
+    pub captured_at: Option<i64>,
+    /// 彩信附带的图片（Base64，可带 `data:image/...;base64,` 前缀），通知中显示为大图
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_base64: Option<String>,
+}
+
+impl SmsPayload {
+    /// 通知中显示的发送方：联系人名称，没有时为号码。
+    pub fn display_sender(&self) -> &str {
+        self.sender_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.sender)
+    }
+}
+
+/// 剪贴板数据载荷结构体。
+/// 用于反序列化接收到的 JSON 数据。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardPayload {
+    /// 剪贴板文本，发送图片时可省略
+    #[serde(default)]
+    pub text: String,
+    /// 复制时间（毫秒时间戳），缺省为 0，视为未提供并使用到达时间
+    #[serde(default)]
+    pub timestamp: i64,
+    /// 带格式文本的 HTML，复制时与 `text` 一起写入剪贴板；未提供 `text` 时从中提取纯文本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// 剪贴板中的图片（Base64，可带 `data:image/...;base64,` 前缀），提供时忽略 `text`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_base64: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sms_accepts_legacy_fields() {
+        let payload: SmsPayload =
+            serde_json::from_str(r#"{"sender":"95588","content":"验证码 123456","timestamp":1700000000000}"#).unwrap();
+        assert_eq!(payload.code, "");
+        assert_eq!(payload.captured_at, Some(1_700_000_000_000));
+        assert_eq!(payload.display_sender(), "95588");
+    }
+
+    #[test]
+    fn sms_prefers_contact_name() {
+        let mut payload: SmsPayload = serde_json::from_str(r#"{"sender":"95588","content":""}"#).unwrap();
+        payload.sender_name = Some("  ".to_string());
+        assert_eq!(payload.display_sender(), "95588");
+        payload.sender_name = Some("工商银行".to_string());
+        assert_eq!(payload.display_sender(), "工商银行");
+    }
+
+    #[test]
+    fn optional_fields_are_omitted() {
+        let payload = ClipboardPayload {
+            text: "hello".to_string(),
+            timestamp: 1,
+            html: None,
+            image_base64: None,
+        };
+        assert_eq!(serde_json::to_string(&payload).unwrap(), r#"{"text":"hello","timestamp":1}"#);
+        let payload: ClipboardPayload = serde_json::from_str(r#"{"html":"<b>x</b>"}"#).unwrap();
+        assert_eq!((payload.text.as_str(), payload.timestamp), ("", 0));
+    }
+}
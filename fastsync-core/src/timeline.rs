@@ -0,0 +1,204 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 捕获时间模块。
+ * 手机离线一段时间后补发的内容带有捕获时间，这里按测得的时钟偏差校正，
+ * 并把明显错误的时间退回到到达时间，历史记录与通知据此排序和显示。
+ */
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 捕获时间最多可以晚于到达时间的毫秒数，超过视为错误。
+const MAX_FUTURE_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// 早于该时间（2020-01-01T00:00:00Z）的捕获时间视为错误。
+const EARLIEST_VALID_MS: i64 = 1_577_836_800_000;
+
+/// 新测得的偏差在估计值中所占的权重（分母）。
+const SKEW_SMOOTHING: i64 = 4;
+
+/// 校正后的时间。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureTime {
+    /// 内容在手机上产生的时间（毫秒时间戳，已按时钟偏差校正）
+    pub captured_at: i64,
+    /// 到达接收端的时间（毫秒时间戳）
+    pub received_at: i64,
+    /// 手机提供的时间明显错误，`captured_at` 已退回到达时间
+    pub clamped: bool,
+}
+
+impl CaptureTime {
+    /// 校正手机提供的捕获时间。
+    ///
+    /// # Arguments
+    /// * `captured_at` - 手机提供的时间，未提供时使用到达时间
+    /// * `received_at` - 到达时间
+    /// * `skew_ms` - 接收端时钟减去手机时钟的估计偏差
+    pub fn reconcile(captured_at: Option<i64>, received_at: i64, skew_ms: i64) -> Self {
+        let Some(captured_at) = captured_at else {
+            return Self { captured_at: received_at, received_at, clamped: false };
+        };
+
+        let corrected = captured_at.saturating_add(skew_ms);
+        if corrected > received_at.saturating_add(MAX_FUTURE_MS) || corrected < EARLIEST_VALID_MS {
+            tracing::debug!("Clamping bogus capture time {} to arrival time {}", captured_at, received_at);
+            return Self { captured_at: received_at, received_at, clamped: true };
+        }
+        // 偏差估计不准时可能略晚于到达时间，显示上以到达时间为限
+        Self {
+            captured_at: corrected.min(received_at),
+            received_at,
+            clamped: false,
+        }
+    }
+
+    /// 相对于当前时间的描述，例如“刚刚”“5 分钟前”。
+    pub fn relative_to(&self, now: i64) -> String {
+        let minutes = (now - self.captured_at).max(0) / 60_000;
+        match minutes {
+            0 => "刚刚".to_string(),
+            1..=59 => format!("{} 分钟前", minutes),
+            60..=1439 => format!("{} 小时前", minutes / 60),
+            _ => {
+                let days = minutes / 1440;
+                if days < 7 {
+                    format!("{} 天前", days)
+                } else {
+                    self.local_time()
+                }
+            }
+        }
+    }
+
+    /// 本地时区的捕获时间，例如“05-01 08:30”。
+    pub fn local_time(&self) -> String {
+        DateTime::<Utc>::from_timestamp_millis(self.captured_at)
+            .map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    /// 是否明显早于到达时间，通知中需要注明捕获时间。
+    pub fn is_delayed(&self) -> bool {
+        self.received_at - self.captured_at >= 60_000
+    }
+}
+
+/// 各设备的时钟偏差估计，由 `/ping` 测量。
+#[derive(Default)]
+pub struct ClockSkew {
+    estimates: Mutex<HashMap<String, i64>>,
+}
+
+impl ClockSkew {
+    /// 记录一次测量并平滑更新估计值。
+    ///
+    /// # Arguments
+    /// * `origin` - 设备标识或对端 IP
+    /// * `client_time` - 手机发出请求时的时间
+    /// * `server_time` - 接收端收到请求时的时间
+    ///
+    /// # Returns
+    /// 更新后的偏差估计（毫秒）
+    pub fn observe(&self, origin: &str, client_time: i64, server_time: i64) -> i64 {
+        let sample = server_time - client_time;
+        let mut estimates = self.estimates.lock().unwrap();
+        let estimate = estimates
+            .entry(origin.to_string())
+            .and_modify(|skew| *skew += (sample - *skew) / SKEW_SMOOTHING)
+            .or_insert(sample);
+        *estimate
+    }
+
+    /// 设备的偏差估计，未测量过时为 0。
+    pub fn get(&self, origin: Option<&str>) -> i64 {
+        origin
+            .and_then(|origin| self.estimates.lock().unwrap().get(origin).copied())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-05-01T00:00:00Z
+    const NOW: i64 = 1_714_521_600_000;
+    const MINUTE: i64 = 60_000;
+    const DAY: i64 = 24 * 60 * MINUTE;
+
+    #[test]
+    fn reconciles_capture_times() {
+        // (手机提供的时间, 时钟偏差, 校正后的时间, 是否退回到达时间)
+        let cases = [
+            (None, 0, NOW, false),
+            (Some(NOW - 5 * MINUTE), 0, NOW - 5 * MINUTE, false),
+            (Some(NOW - 5 * MINUTE), 2 * MINUTE, NOW - 3 * MINUTE, false),
+            (Some(NOW + MINUTE), 0, NOW, false),
+            (Some(NOW + MINUTE), -2 * MINUTE, NOW - MINUTE, false),
+            (Some(NOW + DAY - 1), 0, NOW, false),
+            (Some(NOW + DAY + 1), 0, NOW, true),
+            (Some(NOW + 2 * DAY), -2 * DAY, NOW, false),
+            (Some(EARLIEST_VALID_MS), 0, EARLIEST_VALID_MS, false),
+            (Some(EARLIEST_VALID_MS - 1), 0, NOW, true),
+            (Some(0), 0, NOW, true),
+            (Some(i64::MAX), 0, NOW, true),
+            (Some(i64::MIN), 0, NOW, true),
+        ];
+        for (captured_at, skew, expected, clamped) in cases {
+            let time = CaptureTime::reconcile(captured_at, NOW, skew);
+            assert_eq!(
+                time,
+                CaptureTime { captured_at: expected, received_at: NOW, clamped },
+                "captured_at {:?}, skew {}",
+                captured_at,
+                skew
+            );
+        }
+    }
+
+    #[test]
+    fn describes_relative_time() {
+        let cases = [
+            (0, "刚刚"),
+            (59_999, "刚刚"),
+            (MINUTE, "1 分钟前"),
+            (59 * MINUTE, "59 分钟前"),
+            (60 * MINUTE, "1 小时前"),
+            (DAY - 1, "23 小时前"),
+            (DAY, "1 天前"),
+            (6 * DAY, "6 天前"),
+        ];
+        for (age, expected) in cases {
+            let time = CaptureTime { captured_at: NOW - age, received_at: NOW, clamped: false };
+            assert_eq!(time.relative_to(NOW), expected, "age {}", age);
+        }
+        let future = CaptureTime { captured_at: NOW + MINUTE, received_at: NOW, clamped: false };
+        assert_eq!(future.relative_to(NOW), "刚刚");
+        let old = CaptureTime { captured_at: NOW - 7 * DAY, received_at: NOW, clamped: false };
+        assert_eq!(old.relative_to(NOW), old.local_time());
+    }
+
+    #[test]
+    fn flags_delayed_items() {
+        let time = |age| CaptureTime { captured_at: NOW - age, received_at: NOW, clamped: false };
+        assert!(!time(MINUTE - 1).is_delayed());
+        assert!(time(MINUTE).is_delayed());
+    }
+
+    #[test]
+    fn smooths_skew_per_device() {
+        let skew = ClockSkew::default();
+        assert_eq!(skew.get(Some("phone")), 0);
+        assert_eq!(skew.observe("phone", 1_000, 5_000), 4_000);
+        // 新的测量只占四分之一
+        assert_eq!(skew.observe("phone", 1_000, 9_000), 5_000);
+        assert_eq!(skew.observe("tablet", 5_000, 1_000), -4_000);
+        assert_eq!(skew.get(Some("phone")), 5_000);
+        assert_eq!(skew.get(Some("tablet")), -4_000);
+        assert_eq!(skew.get(None), 0);
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 访问日志模块。
+ * 每个请求分配一个请求 ID（手机端可以通过 `X-Request-Id` 请求头自带），完成时记录一行访问日志：
+ * 方法、路径、来源 IP、设备名称、请求体字节数、状态码与耗时。
+ * 处理器在名为 `request` 的 span 中运行，同一次上传的解码与通知日志都带有该 ID，可以一起检索。
+ */
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
+use crate::auth::AuthenticatedDevice;
+use crate::pairing::GUEST_DEVICE_ID;
+use crate::state::AppState;
+
+/// 请求与响应中携带请求 ID 的头。
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 手机端自带的请求 ID 的最大长度，超过或含有其他字符时改用生成的 ID。
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// 当前请求的 ID，放在请求扩展中供处理器读取。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// 生成新的请求 ID：16 位十六进制字符。
+    pub fn generate() -> Self {
+        Self(hex::encode(rand::random::<[u8; 8]>()))
+    }
+
+    /// 使用请求头中的 ID，只接受字母、数字、`-` 与 `_`，避免日志被注入换行等字符。
+    fn from_header(value: &HeaderValue) -> Option<Self> {
+        let id = value.to_str().ok()?;
+        let valid = !id.is_empty()
+            && id.len() <= MAX_REQUEST_ID_LEN
+            && id.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_');
+        valid.then(|| Self(id.to_string()))
+    }
+}
+
+/// 心跳类接口只在 debug 级别记录，避免刷屏。
+fn is_quiet(method: &Method, path: &str) -> bool {
+    method == Method::GET && matches!(crate::versioning::unversioned(path), "/health" | "/ping")
+}
+
+/// 为请求分配 ID 并在完成时记录访问日志，位于所有路由之外，被拒绝的请求同样记录。
+/// 只记录路径而不记录查询参数，访客链接等会把令牌放在查询参数中。
+pub(crate) async fn access_log(State(state): State<AppState>, mut request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(RequestId::generate);
+    request.extensions_mut().insert(id.clone());
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or_else(|| "-".to_string(), |ConnectInfo(addr)| addr.ip().to_string());
+    // mTLS 连接与 WebSocket 转发的请求在进入时已带有设备标识，携带设备令牌的请求由令牌校验放在响应扩展中
+    let device = request.extensions().get::<AuthenticatedDevice>().cloned();
+
+    // 统计处理器实际读取的请求体字节数，分块传输的请求没有 Content-Length
+    let received = Arc::new(AtomicU64::new(0));
+    let request = request.map(|body| {
+        let received = received.clone();
+        Body::from_stream(body.into_data_stream().inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                received.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }
+        }))
+    });
+
+    let span = tracing::info_span!("request", id = %id.0);
+    let mut response = next.run(request).instrument(span.clone()).await;
+    if let Ok(value) = HeaderValue::from_str(&id.0) {
+        response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    let device = device
+        .or_else(|| response.extensions().get::<AuthenticatedDevice>().cloned())
+        .map_or_else(|| "-".to_string(), |AuthenticatedDevice(id)| device_name(&state, &id));
+    let status = response.status().as_u16();
+    let bytes = received.load(Ordering::Relaxed);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let _entered = span.enter();
+    if response.status().is_server_error() {
+        tracing::warn!(%method, %path, %ip, %device, bytes, status, duration_ms, "Request failed");
+    } else if is_quiet(&method, &path) {
+        tracing::debug!(%method, %path, %ip, %device, bytes, status, duration_ms, "Request completed");
+    } else {
+        tracing::info!(%method, %path, %ip, %device, bytes, status, duration_ms, "Request completed");
+    }
+    response
+}
+
+/// 设备的显示名称，已解除配对的设备使用其标识。
+fn device_name(state: &AppState, id: &str) -> String {
+    if id == GUEST_DEVICE_ID {
+        return GUEST_DEVICE_ID.to_string();
+    }
+    state.devices.get(id).map_or_else(|| id.to_string(), |device| device.name)
+}
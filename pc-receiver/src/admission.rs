@@ -0,0 +1,212 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 准入控制模块。
+ * 连续大量上传（例如同步整个相册）时，处理中的请求与待显示的通知会不断堆积，
+ * 超过上限后载荷接口直接返回 503 与建议的重试时间，而不是接收处理不了的内容。
+ */
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+use crate::state::AppState;
+
+/// 每个排队项目预计的处理时间，用于估算重试时间。
+const PER_ITEM_ESTIMATE: Duration = Duration::from_millis(250);
+
+/// 建议重试时间的下限与上限。
+const MIN_RETRY_AFTER: Duration = Duration::from_secs(1);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// 处理管线的上限，达到任一上限即进入饱和状态，
+/// 降到上限的四分之三以下才恢复接收，避免在边界上来回切换。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineLimits {
+    /// 处理中的请求与待显示的通知总数上限
+    pub max_queue_depth: usize,
+    /// 处理中的请求体总字节数上限
+    pub max_pending_bytes: u64,
+}
+
+impl Default for PipelineLimits {
+    fn default() -> Self {
+        Self {
+            max_queue_depth: 32,
+            max_pending_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// 某一时刻的管线指标。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PipelineMetrics {
+    /// 处理中的请求与待显示的通知总数
+    pub queue_depth: usize,
+    /// 处理中的请求体总字节数
+    pub pending_bytes: u64,
+}
+
+impl PipelineLimits {
+    /// 按当前指标决定是否饱和，带滞回。
+    ///
+    /// # Arguments
+    /// * `saturated` - 上一次的判定结果
+    /// * `metrics` - 当前指标
+    pub fn is_saturated(&self, saturated: bool, metrics: PipelineMetrics) -> bool {
+        if saturated {
+            metrics.queue_depth > self.max_queue_depth * 3 / 4 || metrics.pending_bytes > self.max_pending_bytes / 4 * 3
+        } else {
+            metrics.queue_depth >= self.max_queue_depth || metrics.pending_bytes >= self.max_pending_bytes
+        }
+    }
+
+    /// 按排队深度估算的重试时间。
+    pub fn retry_after(&self, metrics: PipelineMetrics) -> Duration {
+        (PER_ITEM_ESTIMATE * metrics.queue_depth as u32).clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER)
+    }
+}
+
+/// 管线计数，由所有接口共享。配置重新载入时替换上限，计数保留。
+#[derive(Default)]
+pub(crate) struct Pipeline {
+    limits: RwLock<PipelineLimits>,
+    in_flight: AtomicUsize,
+    pending_bytes: AtomicU64,
+    saturated: AtomicBool,
+}
+
+impl Pipeline {
+    pub(crate) fn new(limits: PipelineLimits) -> Self {
+        Self {
+            limits: RwLock::new(limits),
+            ..Default::default()
+        }
+    }
+
+    /// 正在使用的上限。
+    pub(crate) fn limits(&self) -> PipelineLimits {
+        *self.limits.read().unwrap()
+    }
+
+    /// 替换上限，下一次判定时按新的上限计算。
+    pub(crate) fn set_limits(&self, limits: PipelineLimits) {
+        *self.limits.write().unwrap() = limits;
+    }
+
+    /// 当前指标。
+    ///
+    /// # Arguments
+    /// * `queued_notifications` - 待显示的通知数
+    pub(crate) fn metrics(&self, queued_notifications: usize) -> PipelineMetrics {
+        PipelineMetrics {
+            queue_depth: self.in_flight.load(Ordering::Relaxed) + queued_notifications,
+            pending_bytes: self.pending_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 更新并返回饱和状态。
+    pub(crate) fn update(&self, metrics: PipelineMetrics) -> bool {
+        let was = self.saturated.load(Ordering::Relaxed);
+        let now = self.limits().is_saturated(was, metrics);
+        if now != was {
+            self.saturated.store(now, Ordering::Relaxed);
+            if now {
+                tracing::warn!("Pipeline saturated: {:?}", metrics);
+            } else {
+                tracing::info!("Pipeline recovered: {:?}", metrics);
+            }
+        }
+        now
+    }
+}
+
+/// 请求处理完毕时归还计数。
+struct InFlight<'a> {
+    pipeline: &'a Pipeline,
+    bytes: u64,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.pipeline.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.pipeline.pending_bytes.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// 管线饱和时拒绝载荷请求，否则计入处理中直到响应返回。
+pub(crate) async fn admission_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let metrics = state.pipeline.metrics(state.notifications.pending());
+    if state.pipeline.update(metrics) {
+        let retry_after = state.pipeline.limits().retry_after(metrics);
+        tracing::warn!("Rejected {} while the pipeline is saturated", request.uri().path());
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+            Json(json!({ "error": "overloaded", "retry_after_ms": retry_after.as_millis() as u64 })),
+        )
+            .into_response();
+    }
+
+    let bytes = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    state.pipeline.in_flight.fetch_add(1, Ordering::Relaxed);
+    state.pipeline.pending_bytes.fetch_add(bytes, Ordering::Relaxed);
+    let _in_flight = InFlight {
+        pipeline: &state.pipeline,
+        bytes,
+    };
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(queue_depth: usize, pending_bytes: u64) -> PipelineMetrics {
+        PipelineMetrics { queue_depth, pending_bytes }
+    }
+
+    #[test]
+    fn saturation_has_hysteresis() {
+        let limits = PipelineLimits {
+            max_queue_depth: 8,
+            max_pending_bytes: 1000,
+        };
+        let cases = [
+            (false, metrics(7, 0), false),
+            (false, metrics(8, 0), true),
+            (false, metrics(0, 1000), true),
+            (true, metrics(7, 0), true),
+            (true, metrics(6, 0), false),
+            (true, metrics(0, 751), true),
+            (true, metrics(0, 750), false),
+        ];
+        for (saturated, metrics, expected) in cases {
+            assert_eq!(limits.is_saturated(saturated, metrics), expected, "{} {:?}", saturated, metrics);
+        }
+    }
+
+    #[test]
+    fn retry_after_follows_the_queue_depth() {
+        let limits = PipelineLimits::default();
+        let cases = [(0, MIN_RETRY_AFTER), (8, Duration::from_secs(2)), (1000, MAX_RETRY_AFTER)];
+        for (depth, expected) in cases {
+            assert_eq!(limits.retry_after(metrics(depth, 0)), expected, "depth {}", depth);
+        }
+    }
+}
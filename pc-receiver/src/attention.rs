@@ -0,0 +1,287 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 紧急提醒模块。
+ * 显示器休眠时来电、验证码等紧急通知无人可见：按配置唤醒显示器，
+ * 并让托盘图标闪烁直到用户处理或通知过期。是否唤醒、何时停止闪烁在这里判定，
+ * 系统调用由 `DisplayWaker` 实现。
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// 紧急内容的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrgentKind {
+    /// 正在响铃的来电
+    Call,
+    /// 带验证码的短信
+    SmsCode,
+}
+
+impl std::str::FromStr for UrgentKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "call" => Ok(UrgentKind::Call),
+            "sms_code" => Ok(UrgentKind::SmsCode),
+            other => anyhow::bail!("Unknown urgent kind {:?}, expected call or sms_code", other),
+        }
+    }
+}
+
+/// 紧急提醒配置，默认不对任何类型生效。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttentionConfig {
+    /// 需要唤醒显示器的类型
+    pub wake_display: Vec<UrgentKind>,
+    /// 上述类型到达时是否让托盘图标闪烁
+    pub flash_tray: bool,
+}
+
+/// 唤醒显示器的系统调用。
+pub trait DisplayWaker: Send + Sync {
+    /// 唤醒显示器并重置空闲计时。
+    fn wake(&self) -> anyhow::Result<()>;
+}
+
+/// 当前平台的显示器唤醒实现，非 Windows 平台上不做任何事。
+pub struct SystemDisplayWaker;
+
+#[cfg(all(windows, feature = "notifications"))]
+impl DisplayWaker for SystemDisplayWaker {
+    fn wake(&self) -> anyhow::Result<()> {
+        use windows::Win32::System::Power::{SetThreadExecutionState, ES_DISPLAY_REQUIRED};
+        use windows::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_MOVE, MOUSEINPUT,
+        };
+
+        // 不带 ES_CONTINUOUS 时只重置一次空闲计时，不会阻止之后正常休眠
+        if unsafe { SetThreadExecutionState(ES_DISPLAY_REQUIRED) }.0 == 0 {
+            anyhow::bail!("SetThreadExecutionState failed");
+        }
+
+        // 已经关闭的显示器不一定响应上面的调用，再发送一次零位移的鼠标移动
+        let input = INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dwFlags: MOUSEEVENTF_MOVE,
+                    ..Default::default()
+                },
+            },
+        };
+        if unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) } == 0 {
+            anyhow::bail!("SendInput failed: {}", windows::core::Error::from_win32());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(all(windows, feature = "notifications")))]
+impl DisplayWaker for SystemDisplayWaker {
+    fn wake(&self) -> anyhow::Result<()> {
+        tracing::debug!("Waking the display is not supported on this platform");
+        Ok(())
+    }
+}
+
+/// 紧急提醒状态，由所有接口共享。
+pub(crate) struct Attention {
+    config: RwLock<AttentionConfig>,
+    waker: Arc<dyn DisplayWaker>,
+    /// 等待处理的紧急通知及其过期时间
+    pending: Mutex<HashMap<u64, Instant>>,
+    next_id: AtomicU64,
+}
+
+impl Attention {
+    pub(crate) fn new(config: AttentionConfig, waker: Arc<dyn DisplayWaker>) -> Self {
+        Self {
+            config: RwLock::new(config),
+            waker,
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 替换唤醒与闪烁的设置，正在闪烁的通知不受影响。
+    pub(crate) fn reconfigure(&self, config: AttentionConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// 紧急通知到达时按配置唤醒显示器并开始闪烁。
+    ///
+    /// # Arguments
+    /// * `kind` - 紧急类型
+    /// * `expires_in` - 通知的保留时长，超过后停止闪烁
+    /// * `paused` - 是否处于计划暂停中，暂停期间从不唤醒
+    /// * `now` - 当前时间
+    ///
+    /// # Returns
+    /// 开始闪烁时返回标识，用户处理通知后传给 `acknowledge`
+    pub(crate) fn on_urgent(&self, kind: UrgentKind, expires_in: Duration, paused: bool, now: Instant) -> Option<u64> {
+        let config = self.config.read().unwrap().clone();
+        if paused || !config.wake_display.contains(&kind) {
+            return None;
+        }
+
+        tracing::info!("Waking the display for urgent {:?}", kind);
+        if let Err(e) = self.waker.wake() {
+            tracing::warn!("Failed to wake the display: {:?}", e);
+        }
+
+        if !config.flash_tray {
+            return None;
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().unwrap().insert(id, now + expires_in);
+        Some(id)
+    }
+
+    /// 用户已处理通知，停止对应的闪烁。
+    pub(crate) fn acknowledge(&self, id: u64) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+
+    /// 是否仍有未处理且未过期的紧急通知，同时清除已过期的记录。
+    pub(crate) fn is_flashing(&self, now: Instant) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, expires_at| *expires_at > now);
+        !pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 记录唤醒次数，可以模拟系统调用失败。
+    #[derive(Default)]
+    struct CountingWaker {
+        wakes: AtomicUsize,
+        fail: bool,
+    }
+
+    impl DisplayWaker for CountingWaker {
+        fn wake(&self) -> anyhow::Result<()> {
+            self.wakes.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                anyhow::bail!("simulated wake failure");
+            }
+            Ok(())
+        }
+    }
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn attention(wake_display: Vec<UrgentKind>, flash_tray: bool) -> (Attention, Arc<CountingWaker>) {
+        let waker = Arc::new(CountingWaker::default());
+        let config = AttentionConfig { wake_display, flash_tray };
+        (Attention::new(config, waker.clone()), waker)
+    }
+
+    #[test]
+    fn wakes_only_for_configured_kinds_outside_pauses() {
+        let now = Instant::now();
+        let cases = [
+            (vec![UrgentKind::Call], UrgentKind::Call, false, 1),
+            (vec![UrgentKind::Call], UrgentKind::SmsCode, false, 0),
+            (vec![UrgentKind::Call, UrgentKind::SmsCode], UrgentKind::SmsCode, false, 1),
+            (vec![], UrgentKind::Call, false, 0),
+            // 计划暂停期间从不唤醒
+            (vec![UrgentKind::Call, UrgentKind::SmsCode], UrgentKind::Call, true, 0),
+        ];
+        for (wake_display, kind, paused, wakes) in cases {
+            let (attention, waker) = attention(wake_display.clone(), true);
+            let id = attention.on_urgent(kind, MINUTE, paused, now);
+            assert_eq!(waker.wakes.load(Ordering::SeqCst), wakes, "{:?} {:?} {}", wake_display, kind, paused);
+            assert_eq!(id.is_some(), wakes == 1, "{:?} {:?} {}", wake_display, kind, paused);
+            assert_eq!(attention.is_flashing(now), wakes == 1);
+        }
+    }
+
+    #[test]
+    fn flashing_is_optional() {
+        let now = Instant::now();
+        let (attention, waker) = attention(vec![UrgentKind::Call], false);
+        assert_eq!(attention.on_urgent(UrgentKind::Call, MINUTE, false, now), None);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+        assert!(!attention.is_flashing(now));
+    }
+
+    #[test]
+    fn wake_failure_still_flashes() {
+        let now = Instant::now();
+        let waker = Arc::new(CountingWaker { fail: true, ..Default::default() });
+        let config = AttentionConfig { wake_display: vec![UrgentKind::Call], flash_tray: true };
+        let attention = Attention::new(config, waker.clone());
+        assert!(attention.on_urgent(UrgentKind::Call, MINUTE, false, now).is_some());
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+        assert!(attention.is_flashing(now));
+    }
+
+    #[test]
+    fn flashing_stops_on_acknowledge_or_expiry() {
+        let now = Instant::now();
+        let (attention, _) = attention(vec![UrgentKind::Call, UrgentKind::SmsCode], true);
+        let call = attention.on_urgent(UrgentKind::Call, MINUTE, false, now).unwrap();
+        let code = attention.on_urgent(UrgentKind::SmsCode, 5 * MINUTE, false, now).unwrap();
+        assert_ne!(call, code);
+
+        // 处理来电后验证码仍在闪烁
+        attention.acknowledge(call);
+        assert!(attention.is_flashing(now + MINUTE));
+        // 重复确认或确认未知标识不影响其他通知
+        attention.acknowledge(call);
+        attention.acknowledge(999);
+        assert!(attention.is_flashing(now + 4 * MINUTE));
+        // 验证码通知过期后停止
+        assert!(!attention.is_flashing(now + 5 * MINUTE));
+        attention.acknowledge(code);
+        assert!(!attention.is_flashing(now));
+    }
+
+    #[test]
+    fn expiry_is_per_notification() {
+        let now = Instant::now();
+        let (attention, _) = attention(vec![UrgentKind::Call], true);
+        attention.on_urgent(UrgentKind::Call, MINUTE, false, now).unwrap();
+        let later = attention.on_urgent(UrgentKind::Call, MINUTE, false, now + Duration::from_secs(30)).unwrap();
+        assert!(attention.is_flashing(now + MINUTE));
+        assert!(attention.is_flashing(now + MINUTE + Duration::from_secs(29)));
+        assert!(!attention.is_flashing(now + MINUTE + Duration::from_secs(30)));
+        attention.acknowledge(later);
+    }
+
+    #[test]
+    fn reconfigure_applies_to_later_notifications_only() {
+        let now = Instant::now();
+        let (attention, waker) = attention(vec![UrgentKind::Call], true);
+        attention.on_urgent(UrgentKind::Call, MINUTE, false, now).unwrap();
+
+        attention.reconfigure(AttentionConfig::default());
+        // 已在闪烁的通知继续闪烁，新的来电不再唤醒
+        assert!(attention.is_flashing(now));
+        assert_eq!(attention.on_urgent(UrgentKind::Call, MINUTE, false, now), None);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+
+        attention.reconfigure(AttentionConfig { wake_display: vec![UrgentKind::SmsCode], flash_tray: false });
+        assert_eq!(attention.on_urgent(UrgentKind::SmsCode, MINUTE, false, now), None);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn parses_kinds() {
+        assert_eq!(" call ".parse::<UrgentKind>().unwrap(), UrgentKind::Call);
+        assert_eq!("sms_code".parse::<UrgentKind>().unwrap(), UrgentKind::SmsCode);
+        assert!("sms".parse::<UrgentKind>().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 操作审计模块。
+ * 每个收到的内容被保存、复制、忽略或自动保存时都会留下一条记录，
+ * 记录同时写入访问日志 (`fastsync::audit`)、内存中的最近列表、JSON Lines 文件与短信历史数据库。
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use fastsync_core::timeline::CaptureTime;
+use crate::sms_history::SmsHistory;
+
+/// 内存中保留的最近记录条数。
+const MAX_RECENT: usize = 500;
+
+/// 一条审计记录。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// 收到的内容的标识，同一内容的多次操作共用
+    pub item_id: String,
+    /// photo / sms / clipboard 等
+    pub item_type: String,
+    /// 来源设备，已配对设备为设备标识，否则为对端 IP
+    pub device: Option<String>,
+    /// save / copy / copy_code / ignore / auto_save 等
+    pub action: String,
+    /// 操作涉及的文件路径
+    pub path: Option<PathBuf>,
+    /// 操作失败时的错误信息
+    pub error: Option<String>,
+    /// 操作时间（毫秒时间戳）
+    pub timestamp: i64,
+    /// 内容在手机上产生的时间（已校正），历史记录按此排序
+    #[serde(default)]
+    pub captured_at: Option<i64>,
+    /// 内容到达接收端的时间
+    #[serde(default)]
+    pub received_at: Option<i64>,
+    /// 手机提供的时间明显错误，已退回到达时间
+    #[serde(default)]
+    pub clock_clamped: bool,
+}
+
+impl AuditRecord {
+    /// 排序使用的时间：优先使用捕获时间，旧记录使用操作时间。
+    pub fn sort_time(&self) -> i64 {
+        self.captured_at.unwrap_or(self.timestamp)
+    }
+}
+
+/// 审计日志。
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    recent: Mutex<VecDeque<AuditRecord>>,
+    /// 同时写入的历史数据库
+    history: Option<Arc<SmsHistory>>,
+}
+
+impl AuditLog {
+    /// 创建审计日志，并从已有文件中载入最近的记录。
+    ///
+    /// # Arguments
+    /// * `path` - JSON Lines 文件路径，为 None 时只保留在内存中
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut recent = VecDeque::new();
+        if let Some(file) = path.as_ref().and_then(|p| std::fs::File::open(p).ok()) {
+            for line in std::io::BufReader::new(file).lines().map_while(Result::ok) {
+                if let Ok(record) = serde_json::from_str::<AuditRecord>(&line) {
+                    recent.push_front(record);
+                    recent.truncate(MAX_RECENT);
+                }
+            }
+        }
+
+        Self {
+            path,
+            recent: Mutex::new(recent),
+            history: None,
+        }
+    }
+
+    /// 同时写入历史数据库。JSON Lines 文件中没有记录时，从数据库载入最近的记录。
+    pub fn with_history(mut self, history: Arc<SmsHistory>) -> Self {
+        let recent = self.recent.get_mut().unwrap();
+        if recent.is_empty() {
+            match history.audit_records(MAX_RECENT) {
+                Ok(records) => recent.extend(records),
+                Err(e) => tracing::error!("Failed to load audit records from SMS history: {:?}", e),
+            }
+        }
+        self.history = Some(history);
+        self
+    }
+
+    /// 记录文件路径。
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// 写入一条记录，文件写入失败只记录日志，不影响调用方。
+    pub fn record(&self, record: AuditRecord) {
+        tracing::info!(
+            target: "fastsync::audit",
+            "{} {} {} from {} path={:?} error={:?}",
+            record.item_type,
+            record.item_id,
+            record.action,
+            record.device.as_deref().unwrap_or("unknown"),
+            record.path,
+            record.error
+        );
+
+        if let Some(path) = &self.path {
+            if let Err(e) = append_line(path, &record) {
+                tracing::error!("Failed to write audit record to {:?}: {:?}", path, e);
+            }
+        }
+
+        if let Some(history) = &self.history {
+            history.record_audit(&record);
+        }
+
+        if let Ok(mut recent) = self.recent.lock() {
+            recent.push_front(record);
+            recent.truncate(MAX_RECENT);
+        }
+    }
+
+    /// 最近的记录，按捕获时间排序，最新的在前。
+    pub fn recent(&self, limit: usize) -> Vec<AuditRecord> {
+        let mut records: Vec<AuditRecord> = self
+            .recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default();
+        // 补发的内容捕获时间较早，稳定排序保证同一时间的记录仍按写入顺序
+        records.sort_by_key(|record| std::cmp::Reverse(record.sort_time()));
+        records.truncate(limit);
+        records
+    }
+}
+
+/// 默认的记录文件路径：`<本地数据目录>/FastSync/audit.jsonl`。
+pub fn default_audit_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("FastSync").join("audit.jsonl"))
+}
+
+/// 某个收到的内容的审计句柄，在通知按钮回调中按操作记录。
+#[derive(Clone)]
+pub struct ItemAudit {
+    log: Arc<AuditLog>,
+    item_id: String,
+    item_type: String,
+    device: Option<String>,
+    capture: Option<CaptureTime>,
+}
+
+impl ItemAudit {
+    /// 为新收到的内容生成标识。
+    pub(crate) fn new(log: Arc<AuditLog>, item_type: &str, device: Option<String>) -> Self {
+        Self {
+            log,
+            item_id: hex::encode(rand::random::<[u8; 8]>()),
+            item_type: item_type.to_string(),
+            device,
+            capture: None,
+        }
+    }
+
+    /// 附带内容的捕获时间，之后的每条记录都会带上。
+    pub fn with_capture(mut self, capture: CaptureTime) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// 内容标识。
+    pub fn item_id(&self) -> &str {
+        &self.item_id
+    }
+
+    /// 内容的捕获时间。
+    pub fn capture(&self) -> Option<CaptureTime> {
+        self.capture
+    }
+
+    /// 记录一次操作。
+    ///
+    /// # Arguments
+    /// * `action` - 操作名称
+    /// * `path` - 操作涉及的文件
+    /// * `error` - 失败原因，成功时为 None
+    pub fn record(&self, action: &str, path: Option<&Path>, error: Option<String>) {
+        self.log.record(AuditRecord {
+            item_id: self.item_id.clone(),
+            item_type: self.item_type.clone(),
+            device: self.device.clone(),
+            action: action.to_string(),
+            path: path.map(Path::to_path_buf),
+            error,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            captured_at: self.capture.map(|capture| capture.captured_at),
+            received_at: self.capture.map(|capture| capture.received_at),
+            clock_clamped: self.capture.is_some_and(|capture| capture.clamped),
+        });
+    }
+
+    /// 按操作结果记录，失败时附带错误信息。
+    pub fn record_result<T>(&self, action: &str, path: Option<&Path>, result: &anyhow::Result<T>) {
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        self.record(action, path, error);
+    }
+}
+
+fn append_line(path: &Path, record: &AuditRecord) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
@@ -0,0 +1,201 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 令牌校验中间件。
+ * 数据接口要求 `Authorization: Bearer <token>`，令牌可以是配置文件中共用的访问令牌，
+ * 也可以是配对时签发的设备令牌，后者在每次到达时按当前配对状态校验。
+ * 关闭 `require_token` 时未携带令牌的旧版手机端仍然放行。
+ * mTLS 连接在握手后已带上设备标识，这里同样按当前配对状态复核。
+ * 设备管理接口使用 `admin_auth`：只接受本机请求与共用的访问令牌，设备令牌与访客令牌无权访问。
+ * 同一 IP 反复携带错误令牌时按配对限流的方式逐步锁定。
+ */
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use crate::handlers::pair::retry_later;
+use crate::pairing::{ThrottleConfig, TokenStatus, Verdict};
+use crate::state::AppState;
+
+/// 令牌校验失败的限流参数，正常手机端不会连续出错，只需挡住逐个猜测令牌的来源。
+pub(crate) const AUTH_THROTTLE: ThrottleConfig = ThrottleConfig {
+    free_attempts: 5,
+    base_lockout: Duration::from_secs(5),
+    max_lockout: Duration::from_secs(15 * 60),
+    decay: Duration::from_secs(15 * 60),
+    // 令牌校验失败不暂停所有来源，避免一台设备拖累其他手机
+    breaker_threshold: usize::MAX,
+    breaker_window: Duration::ZERO,
+    breaker_cooldown: Duration::ZERO,
+};
+
+/// 通过令牌校验的设备标识，放在请求扩展中供处理器读取。
+#[derive(Debug, Clone)]
+pub(crate) struct AuthenticatedDevice(pub String);
+
+/// 校验访问令牌或设备令牌，已解除配对与未知令牌返回不同的错误，便于手机端提示重新配对。
+pub(crate) async fn device_auth(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let required = state.config.read().unwrap().server.require_token;
+    authorize(state, request, next, required).await
+}
+
+/// 校验设备管理接口：本机请求直接放行，其他来源必须携带共用的访问令牌。
+/// 设备令牌只用于收发数据，持有者不能列出或解除其他设备，返回 403。
+pub(crate) async fn admin_auth(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if is_local_admin(request.extensions().get::<ConnectInfo<SocketAddr>>()) {
+        return next.run(request).await;
+    }
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    if let Some(ip) = ip {
+        if let Verdict::LockedOut { retry_after } = state.auth_failures.lock().unwrap().check(ip, Instant::now()) {
+            return retry_later(StatusCode::TOO_MANY_REQUESTS, "locked", retry_after);
+        }
+    }
+
+    let Some(token) = bearer_token(&request) else {
+        return reject(&state, &request, ip, "missing token");
+    };
+    let shared = state.config.read().unwrap().server.token.clone();
+    if shared.is_some_and(|shared| shared.matches(&token)) {
+        record_success(&state, ip);
+        return next.run(request).await;
+    }
+    match state.pairing.authenticate(&token) {
+        TokenStatus::Valid(_) => forbidden(),
+        TokenStatus::Revoked => reject(&state, &request, ip, "revoked"),
+        TokenStatus::Expired => reject(&state, &request, ip, "expired"),
+        TokenStatus::Unknown => reject(&state, &request, ip, "unknown token"),
+    }
+}
+
+/// 校验请求携带的令牌。
+///
+/// # Arguments
+/// * `required` - 未携带令牌时是否拒绝
+async fn authorize(state: AppState, mut request: Request, next: Next, required: bool) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    if let Some(ip) = ip {
+        if let Verdict::LockedOut { retry_after } = state.auth_failures.lock().unwrap().check(ip, Instant::now()) {
+            return retry_later(StatusCode::TOO_MANY_REQUESTS, "locked", retry_after);
+        }
+    }
+
+    let Some(token) = bearer_token(&request) else {
+        if let Some(AuthenticatedDevice(device_id)) = request.extensions().get::<AuthenticatedDevice>() {
+            // 连接建立后设备被解除配对时，同一连接上的后续请求立即失效
+            if state.devices.get(device_id).is_none() {
+                return reject(&state, &request, ip, "revoked");
+            }
+            return next.run(request).await;
+        }
+        if required {
+            return reject(&state, &request, ip, "missing token");
+        }
+        return next.run(request).await;
+    };
+
+    let shared = state.config.read().unwrap().server.token.clone();
+    if shared.is_some_and(|shared| shared.matches(&token)) {
+        record_success(&state, ip);
+        return next.run(request).await;
+    }
+
+    match state.pairing.authenticate(&token) {
+        TokenStatus::Valid(device_id) => {
+            record_success(&state, ip);
+            request.extensions_mut().insert(AuthenticatedDevice(device_id.clone()));
+            // 访问日志位于令牌校验之外，从响应扩展中读取设备
+            let mut response = next.run(request).await;
+            response.extensions_mut().insert(AuthenticatedDevice(device_id));
+            response
+        }
+        TokenStatus::Revoked => reject(&state, &request, ip, "revoked"),
+        TokenStatus::Expired => reject(&state, &request, ip, "expired"),
+        TokenStatus::Unknown => reject(&state, &request, ip, "unknown token"),
+    }
+}
+
+/// `Authorization: Bearer <token>` 中的令牌。
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|value| value.trim().to_string())
+}
+
+/// 仅允许本机发起的管理请求，托盘等本地入口直接调用库接口。
+pub(crate) fn is_local_admin(request_addr: Option<&ConnectInfo<SocketAddr>>) -> bool {
+    request_addr.is_some_and(|ConnectInfo(addr)| addr.ip().is_loopback())
+}
+
+/// 记录来源并计入该 IP 的失败次数后返回 401。
+fn reject(state: &AppState, request: &Request, ip: Option<IpAddr>, error: &str) -> Response {
+    let source = ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+    tracing::warn!("Rejected {} {} from {}: {}", request.method(), request.uri().path(), source, error);
+    if let Some(ip) = ip {
+        let mut failures = state.auth_failures.lock().unwrap();
+        failures.record_failure(ip, Instant::now());
+        if let Verdict::LockedOut { retry_after } = failures.check(ip, Instant::now()) {
+            tracing::warn!("Locked out {} for {:?} after repeated token failures", ip, retry_after);
+        }
+    }
+    unauthorized(error)
+}
+
+fn record_success(state: &AppState, ip: Option<IpAddr>) {
+    if let Some(ip) = ip {
+        state.auth_failures.lock().unwrap().record_success(ip);
+    }
+}
+
+fn forbidden() -> Response {
+    (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response()
+}
+
+fn unauthorized(error: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, Json(json!({ "error": error }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pairing::PairingThrottle;
+
+    #[test]
+    fn repeated_token_failures_lock_out_for_longer() {
+        let mut failures = PairingThrottle::new(AUTH_THROTTLE);
+        let ip = IpAddr::from([192, 168, 1, 20]);
+        let mut now = Instant::now();
+        for _ in 0..4 {
+            failures.record_failure(ip, now);
+            assert_eq!(failures.check(ip, now), Verdict::Allowed);
+        }
+
+        let cases = [5, 10, 20, 40, 80, 160, 320, 640, 900];
+        for (attempt, expected) in cases.into_iter().enumerate() {
+            // 令牌失败不触发全局熔断
+            assert!(!failures.record_failure(ip, now), "lockout {}", attempt + 1);
+            let retry_after = Duration::from_secs(expected);
+            assert_eq!(failures.check(ip, now), Verdict::LockedOut { retry_after }, "lockout {}", attempt + 1);
+            now += retry_after;
+        }
+        // 最长的锁定结束时已过衰减期，计数重新开始
+        failures.record_failure(ip, now);
+        assert_eq!(failures.check(ip, now), Verdict::Allowed);
+    }
+}
@@ -0,0 +1,293 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 最小 HTTP/1.1 客户端，只覆盖模拟器与命令行发送需要的请求，可以在发送请求体的中途断开连接。
+ */
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// 上传时每次写入的块大小。
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 指向接收端的客户端。
+pub struct Client {
+    host: String,
+    port: u16,
+    timeout: Duration,
+    token: Option<String>,
+}
+
+/// 接收端的响应。
+pub struct Response {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+/// 请求体。
+#[derive(Clone, Copy)]
+pub enum Body<'a> {
+    /// 内存中的数据
+    Bytes(&'a [u8]),
+    /// 以 multipart 表单流式上传的文件
+    File(&'a Path),
+}
+
+impl Client {
+    /// 从 `http://host:port` 形式的地址创建客户端。
+    ///
+    /// # Arguments
+    /// * `url` - 接收端地址
+    /// * `timeout` - 单次连接、读写的超时时间
+    pub fn from_url(url: &str, timeout: Duration) -> anyhow::Result<Self> {
+        let Some(authority) = url.strip_prefix("http://") else {
+            anyhow::bail!("Only http:// URLs are supported: {}", url);
+        };
+        let authority = authority.trim_end_matches('/');
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse()?),
+            None => (authority, 80),
+        };
+
+        Ok(Self {
+            host: host.trim_start_matches('[').trim_end_matches(']').to_string(),
+            port,
+            timeout,
+            token: None,
+        })
+    }
+
+    /// 携带设备令牌发送请求。
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// 之后的请求改用新的设备令牌，例如场景中配对成功后。
+    pub fn set_token(&mut self, token: String) {
+        self.token = Some(token);
+    }
+
+    /// `host:port` 形式的地址。
+    pub fn authority(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    /// 设备令牌。
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// 连接接收端。
+    pub async fn connect(&self) -> anyhow::Result<TcpStream> {
+        Ok(self
+            .timed("connect", TcpStream::connect((self.host.as_str(), self.port)))
+            .await??)
+    }
+
+    /// 发送 GET 请求并读取完整响应。
+    pub async fn get(&self, path: &str) -> anyhow::Result<Response> {
+        self.exchange("GET", path, &[], &[], None)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Request to {} was not sent", path))
+    }
+
+    /// 发送请求并读取完整响应。
+    ///
+    /// # Arguments
+    /// * `method` - 请求方法
+    /// * `path` - 请求路径
+    /// * `headers` - 额外的请求头
+    /// * `body` - 请求体
+    /// * `cut_after` - 只发送请求体的前若干字节后断开连接，模拟传输中途断网
+    ///
+    /// # Returns
+    /// 完整发送时的响应，中途断开时为 None
+    pub async fn exchange(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, String)],
+        body: &[u8],
+        cut_after: Option<usize>,
+    ) -> anyhow::Result<Option<Response>> {
+        let mut stream = self.connect().await?;
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            method,
+            path,
+            self.host,
+            self.port,
+            body.len()
+        );
+        for (name, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(token) = &self.token {
+            head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        head.push_str("\r\n");
+        self.timed(path, stream.write_all(head.as_bytes())).await??;
+
+        if let Some(cut) = cut_after.filter(|cut| *cut < body.len()) {
+            self.timed(path, stream.write_all(&body[..cut])).await??;
+            // 不发送剩余内容直接关闭，接收端读取请求体时出错
+            drop(stream);
+            return Ok(None);
+        }
+        self.timed(path, stream.write_all(body)).await??;
+        let mut response = Vec::new();
+        self.timed(path, stream.read_to_end(&mut response)).await??;
+        parse_response(path, response).map(Some)
+    }
+
+    /// 发送 POST 请求。
+    ///
+    /// # Returns
+    /// 响应状态码
+    pub async fn post(&self, path: &str, content_type: &str, body: &[u8]) -> anyhow::Result<u16> {
+        Ok(self.request(path, content_type, Body::Bytes(body), |_, _| {}).await?.status)
+    }
+
+    /// 发送 POST 请求并读取完整响应，上传过程中按块回调进度。
+    ///
+    /// # Arguments
+    /// * `path` - 请求路径
+    /// * `content_type` - 内存请求体的 Content-Type，文件上传时忽略
+    /// * `body` - 请求体
+    /// * `on_progress` - 进度回调，参数为已发送与总字节数
+    pub async fn request(
+        &self,
+        path: &str,
+        content_type: &str,
+        body: Body<'_>,
+        mut on_progress: impl FnMut(u64, u64),
+    ) -> anyhow::Result<Response> {
+        let mut stream = self.connect().await?;
+
+        let (content_type, prefix, mut reader, suffix): (String, Vec<u8>, Box<dyn AsyncRead + Unpin + Send>, Vec<u8>) =
+            match body {
+                Body::Bytes(data) => (content_type.to_string(), data.to_vec(), Box::new(tokio::io::empty()), Vec::new()),
+                Body::File(file) => {
+                    let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+                    let (content_type, prefix, suffix) = multipart_envelope(&file_name, guess_mime(file));
+                    let reader = tokio::fs::File::open(file)
+                        .await
+                        .map_err(|e| anyhow::anyhow!("Failed to open {:?}: {}", file, e))?;
+                    (content_type, prefix, Box::new(reader), suffix)
+                }
+            };
+        let file_len = match body {
+            Body::File(file) => tokio::fs::metadata(file).await?.len(),
+            Body::Bytes(_) => 0,
+        };
+        let total = prefix.len() as u64 + file_len + suffix.len() as u64;
+
+        let mut head = format!(
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            path, self.host, self.port, content_type, total
+        );
+        if let Some(token) = &self.token {
+            head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        head.push_str("\r\n");
+
+        self.timed(path, stream.write_all(head.as_bytes())).await??;
+        self.timed(path, stream.write_all(&prefix)).await??;
+        let mut sent = prefix.len() as u64;
+        on_progress(sent, total);
+
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = reader.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            self.timed(path, stream.write_all(&chunk[..read])).await??;
+            sent += read as u64;
+            on_progress(sent, total);
+        }
+        self.timed(path, stream.write_all(&suffix)).await??;
+        on_progress(total, total);
+
+        let mut response = Vec::new();
+        self.timed(path, stream.read_to_end(&mut response)).await??;
+        parse_response(path, response)
+    }
+
+    /// 为单次 I/O 加上超时，大文件上传不受整体时长限制。
+    async fn timed<F: std::future::Future>(&self, path: &str, future: F) -> anyhow::Result<F::Output> {
+        tokio::time::timeout(self.timeout, future)
+            .await
+            .map_err(|_| anyhow::anyhow!("Request to {} timed out after {:?}", path, self.timeout))
+    }
+}
+
+/// 解析状态码并去掉响应头。
+fn parse_response(path: &str, response: Vec<u8>) -> anyhow::Result<Response> {
+    let status_line = response.split(|b| *b == b'\n').next().unwrap_or_default();
+    let status = std::str::from_utf8(status_line)?
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Malformed HTTP response from {}", path))?;
+
+    let body = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|end| response[end + 4..].to_vec())
+        .unwrap_or_default();
+    Ok(Response { status, body })
+}
+
+/// 构造只包含一个 `data` 文件字段的 multipart 表单。
+///
+/// # Returns
+/// (Content-Type, 请求体)
+pub fn multipart_file(file_name: &str, data: &[u8]) -> (String, Vec<u8>) {
+    let (content_type, mut body, suffix) = multipart_envelope(file_name, "application/octet-stream");
+    body.extend_from_slice(data);
+    body.extend_from_slice(&suffix);
+    (content_type, body)
+}
+
+/// multipart 表单中文件内容前后的部分。
+///
+/// # Returns
+/// (Content-Type, 文件内容之前的部分, 文件内容之后的部分)
+fn multipart_envelope(file_name: &str, mime: &str) -> (String, Vec<u8>, Vec<u8>) {
+    let boundary = format!("fastsync-sim-{}", chrono::Utc::now().timestamp_millis());
+    let prefix = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"data\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+        boundary,
+        file_name.replace('"', "_"),
+        mime
+    );
+    let suffix = format!("\r\n--{}--\r\n", boundary);
+    (
+        format!("multipart/form-data; boundary={}", boundary),
+        prefix.into_bytes(),
+        suffix.into_bytes(),
+    )
+}
+
+/// 按扩展名推断 MIME，供接收端的内容策略判断。
+fn guess_mime(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "heic" => "image/heic",
+        "txt" => "text/plain",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,432 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 手机端模拟器。
+ * 通过 mDNS 发现接收端（或直接指定地址），按场景文件依次配对、推送图片、短信与剪贴板、
+ * 分块上传大文件（可在中途断开后续传），以及经 WebSocket 发送消息并等待回执，
+ * 任一步骤的响应或回执与期望不符时以非零状态码退出，可作为端到端测试工具。
+ * 也提供 `pair`、`send-file`、`send-text`、`send-clipboard` 子命令，供另一台电脑直接发送。
+ *
+ * 用法: fastsync-sim [--url http://host:port] [--token <访问令牌>] [--timeout <秒>] <scenario>
+ *
+ * 退出码: 0 成功，1 响应不符合期望或被拒绝，2 出错，3 令牌无效需重新配对，4 未发现接收端
+ */
+use sha2::Digest;
+use std::path::Path;
+use std::time::Duration;
+use fastsync_core::protocol::{ClipboardPayload, SmsPayload};
+
+mod client;
+mod scenario;
+mod send;
+mod store;
+#[cfg(test)]
+mod testing;
+mod ws;
+
+use client::Client;
+use scenario::{Action, Step};
+
+/// 分块上传时每个分块的大小。
+const CHUNK_SIZE: usize = 256 * 1024;
+
+const USAGE: &str = "Usage: fastsync-sim [--url http://host:port] [--token <access token>] [--timeout <seconds>] <scenario>\n       \
+    fastsync-sim pair --pin <PIN> [--to <name|url>] [--name <device name>]\n       \
+    fastsync-sim send-file <path> [--to <name|url>]\n       \
+    fastsync-sim send-text <text> [--to <name|url>]\n       \
+    fastsync-sim send-clipboard [--to <name|url>]";
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+pub const EXIT_UNAUTHORIZED: i32 = 3;
+pub const EXIT_NOT_FOUND: i32 = 4;
+
+/// 选定的接收端。
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub url: String,
+    /// mDNS 实例名称，显式指定地址时为 None
+    pub name: Option<String>,
+}
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match send::parse(&args) {
+        Ok(Some((command, options))) => send::run(command, options),
+        Ok(None) => run_scenario(args).map(|passed| if passed { EXIT_OK } else { EXIT_FAILED }),
+        Err(e) => Err(e.context(USAGE)),
+    };
+
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            tracing::error!("{:#}", e);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+}
+
+/// 解析参数并执行场景。
+///
+/// # Returns
+/// 所有步骤是否都符合期望
+fn run_scenario(args: Vec<String>) -> anyhow::Result<bool> {
+    let mut url = None;
+    let mut token = None;
+    let mut timeout = Duration::from_secs(10);
+    let mut scenario_path = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--url" => url = args.next(),
+            "--token" => token = Some(args.next().ok_or_else(|| anyhow::anyhow!(USAGE))?),
+            "--timeout" => {
+                let secs = args.next().and_then(|s| s.parse().ok());
+                timeout = Duration::from_secs(secs.ok_or_else(|| anyhow::anyhow!(USAGE))?);
+            }
+            _ if scenario_path.is_none() && !arg.starts_with("--") => scenario_path = Some(arg),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+
+    let scenario_path = scenario_path.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let source = std::fs::read_to_string(&scenario_path)?;
+    let base_dir = Path::new(&scenario_path).parent().unwrap_or(Path::new("."));
+    let steps = scenario::parse(&source, base_dir)?;
+
+    let url = match url {
+        Some(url) => url,
+        None => discover(timeout, None)?
+            .map(|target| target.url)
+            .ok_or_else(|| anyhow::anyhow!("No receiver found via mDNS, pass --url instead"))?,
+    };
+    tracing::info!("Using receiver at {}", url);
+    let client = Client::from_url(&url, timeout)?.with_token(token);
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let failures = rt.block_on(Runner::new(client, timeout).run(&steps));
+    tracing::info!("{} steps, {} failed", steps.len(), failures);
+    Ok(failures == 0)
+}
+
+/// 依次执行场景步骤，保存配对得到的令牌与 WebSocket 连接。
+struct Runner {
+    client: Client,
+    timeout: Duration,
+    session: Option<ws::Session>,
+}
+
+impl Runner {
+    fn new(client: Client, timeout: Duration) -> Self {
+        Self {
+            client,
+            timeout,
+            session: None,
+        }
+    }
+
+    /// 执行所有步骤。
+    ///
+    /// # Returns
+    /// 与期望不符或出错的步骤数
+    async fn run(&mut self, steps: &[Step]) -> usize {
+        let mut failures = 0;
+        for step in steps {
+            match self.run_step(step).await {
+                Ok(None) => {}
+                Ok(Some(status)) if status == step.expect_status => {
+                    tracing::info!("line {}: {} as expected", step.line, status);
+                }
+                Ok(Some(status)) => {
+                    tracing::error!("line {}: expected {}, got {}", step.line, step.expect_status, status);
+                    failures += 1;
+                }
+                Err(e) => {
+                    tracing::error!("line {}: {:#}", step.line, e);
+                    failures += 1;
+                }
+            }
+        }
+        failures
+    }
+
+    /// 执行单个步骤。
+    ///
+    /// # Returns
+    /// 请求步骤返回响应或回执的状态码，等待步骤返回 None
+    async fn run_step(&mut self, step: &Step) -> anyhow::Result<Option<u16>> {
+        let client = &self.client;
+        let status = match &step.action {
+            Action::Photo(path) => {
+                let data = std::fs::read(path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", path, e))?;
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let (content_type, body) = client::multipart_file(&file_name, &data);
+                client.post("/v1/upload", &content_type, &body).await?
+            }
+            Action::Sms { sender, code, content } => {
+                let payload = sms_payload(sender, code, content);
+                client.post("/v1/sms", "application/json", &serde_json::to_vec(&payload)?).await?
+            }
+            Action::Clipboard(text) => {
+                let payload = clipboard_payload(text);
+                client.post("/v1/clipboard", "application/json", &serde_json::to_vec(&payload)?).await?
+            }
+            Action::Wait(duration) => {
+                tokio::time::sleep(*duration).await;
+                return Ok(None);
+            }
+            Action::Pair(pin) => {
+                let body = serde_json::json!({ "pin": pin, "device_name": "fastsync-sim" });
+                let response = client
+                    .request("/v1/pair", "application/json", client::Body::Bytes(&serde_json::to_vec(&body)?), |_, _| {})
+                    .await?;
+                if response.status == 200 {
+                    let token = serde_json::from_slice::<serde_json::Value>(&response.body)?["device_token"]
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| anyhow::anyhow!("Pairing response has no device_token"))?;
+                    self.client.set_token(token);
+                    // 已有的连接使用旧的令牌
+                    self.session = None;
+                }
+                response.status
+            }
+            Action::Chunked { path, disconnect_at } => chunked_upload(client, path, *disconnect_at).await?,
+            Action::WsSms { sender, code, content } => {
+                let payload = serde_json::to_value(sms_payload(sender, code, content))?;
+                self.session().await?.send("sms", payload).await?
+            }
+            Action::WsClipboard(text) => {
+                let payload = serde_json::to_value(clipboard_payload(text))?;
+                self.session().await?.send("clipboard", payload).await?
+            }
+        };
+        Ok(Some(status))
+    }
+
+    /// 当前的 WebSocket 连接，第一次使用时建立。
+    async fn session(&mut self) -> anyhow::Result<&mut ws::Session> {
+        if self.session.is_none() {
+            self.session = Some(ws::Session::connect(&self.client, self.timeout).await?);
+        }
+        Ok(self.session.as_mut().expect("session was just connected"))
+    }
+}
+
+fn sms_payload(sender: &str, code: &str, content: &str) -> SmsPayload {
+    SmsPayload {
+        sender: sender.to_string(),
+        sender_name: None,
+        content: content.to_string(),
+        code: code.to_string(),
+        captured_at: Some(chrono::Utc::now().timestamp_millis()),
+        image_base64: None,
+    }
+}
+
+fn clipboard_payload(text: &str) -> ClipboardPayload {
+    ClipboardPayload {
+        text: text.to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        html: None,
+        image_base64: None,
+    }
+}
+
+/// 分块上传文件。发送到 `disconnect_at` 所在的分块时只发送到该位置就断开连接，
+/// 随后按 `/upload/status` 报告的已收到字节数续传，与手机在网络切换后的做法相同。
+///
+/// # Returns
+/// `/upload/complete` 的状态码，登记或分块被拒绝时为该请求的状态码
+async fn chunked_upload(client: &Client, path: &Path, disconnect_at: Option<u64>) -> anyhow::Result<u16> {
+    let data = std::fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", path, e))?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let init = serde_json::json!({
+        "file_name": file_name,
+        "size": data.len(),
+        "sha256": hex::encode(sha2::Sha256::digest(&data)),
+    });
+    let headers = [("Content-Type", "application/json".to_string())];
+    let response = client
+        .exchange("POST", "/v1/upload/init", &headers, &serde_json::to_vec(&init)?, None)
+        .await?
+        .expect("requests without a cut are always sent");
+    if response.status != 200 {
+        return Ok(response.status);
+    }
+    let body: serde_json::Value = serde_json::from_slice(&response.body)?;
+    let id = body["id"].as_str().ok_or_else(|| anyhow::anyhow!("Upload init response has no id"))?.to_string();
+    let mut offset = body["bytes"].as_u64().unwrap_or(0);
+    let mut disconnect_at = disconnect_at.filter(|at| *at < data.len() as u64);
+    // 接收端察觉断开之前，被中断的分块仍占用上传，续传的分块返回 409 busy
+    let mut busy_retries = 50;
+
+    while offset < data.len() as u64 {
+        let start = offset as usize;
+        let chunk = &data[start..(start + CHUNK_SIZE).min(data.len())];
+        let cut = disconnect_at
+            .filter(|at| *at >= offset && *at < offset + chunk.len() as u64)
+            .map(|at| (at - offset) as usize);
+        let headers = [("Upload-Offset", offset.to_string())];
+        let path = format!("/v1/upload/chunk/{}", id);
+        match client.exchange("PUT", &path, &headers, chunk, cut).await? {
+            Some(response) if response.status == 200 => offset += chunk.len() as u64,
+            Some(response) if response.status == 409 && busy_retries > 0 => {
+                busy_retries -= 1;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                offset = resume_offset(client, &id).await?;
+            }
+            Some(response) => return Ok(response.status),
+            None => {
+                tracing::info!("Disconnected after {} bytes, resuming", offset + cut.unwrap_or(0) as u64);
+                disconnect_at = None;
+                offset = resume_offset(client, &id).await?;
+            }
+        }
+    }
+
+    let path = format!("/v1/upload/complete/{}", id);
+    let response = client.exchange("POST", &path, &[], &[], None).await?.expect("complete is always sent");
+    Ok(response.status)
+}
+
+/// 断开后查询接收端已收到的字节数。
+async fn resume_offset(client: &Client, id: &str) -> anyhow::Result<u64> {
+    let response = client.get(&format!("/v1/upload/status/{}", id)).await?;
+    if response.status != 200 {
+        anyhow::bail!("Upload status failed with {}", response.status);
+    }
+    let body: serde_json::Value = serde_json::from_slice(&response.body)?;
+    Ok(body["bytes"].as_u64().unwrap_or(0))
+}
+
+/// 通过 mDNS 查找局域网内的接收端。
+///
+/// # Arguments
+/// * `timeout` - 最长等待时间
+/// * `wanted` - 期望的实例名称（不区分大小写的前缀匹配），为 None 时取第一个
+#[cfg(feature = "mdns")]
+pub fn discover(timeout: Duration, wanted: Option<&str>) -> anyhow::Result<Option<Target>> {
+    use mdns_sd::{ServiceDaemon, ServiceEvent};
+
+    tracing::info!("Browsing {} for up to {:?}", fastsync_core::MDNS_SERVICE_TYPE, timeout);
+    let mdns = ServiceDaemon::new()?;
+    let receiver = mdns.browse(fastsync_core::MDNS_SERVICE_TYPE)?;
+    let deadline = std::time::Instant::now() + timeout;
+
+    let found = loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        match receiver.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                let name = info
+                    .get_fullname()
+                    .trim_end_matches(fastsync_core::MDNS_SERVICE_TYPE)
+                    .trim_end_matches('.')
+                    .to_string();
+                let matches = wanted.is_none_or(|wanted| name.to_lowercase().starts_with(&wanted.to_lowercase()));
+                // 模拟器只支持明文 HTTP，接收端启用 HTTPS 时改用兼容端口
+                let port = match info.get_property_val_str("scheme") {
+                    Some("https") => info.get_property_val_str("http_port").and_then(|port| port.parse().ok()),
+                    _ => Some(info.get_port()),
+                };
+                let Some(port) = port.filter(|_| matches) else {
+                    if matches {
+                        tracing::warn!("{} only serves HTTPS, set server.http_port to reach it", info.get_fullname());
+                    }
+                    continue;
+                };
+                if let Some(ip) = info.get_addresses_v4().into_iter().next() {
+                    tracing::info!("Discovered {}", info.get_fullname());
+                    break Some(Target {
+                        url: format!("http://{}:{}", ip, port),
+                        name: Some(name),
+                    });
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break None,
+        }
+    };
+
+    if let Ok(status) = mdns.shutdown() {
+        let _ = status.recv_timeout(Duration::from_secs(1));
+    }
+    Ok(found)
+}
+
+/// 未启用 `mdns` 特性时必须显式指定地址。
+#[cfg(not(feature = "mdns"))]
+pub fn discover(_timeout: Duration, _wanted: Option<&str>) -> anyhow::Result<Option<Target>> {
+    anyhow::bail!("mDNS discovery requires the mdns feature, pass --url instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// 约 1 MB、难以压缩的 PNG，分成多个分块上传。
+    fn large_png(dir: &Path) -> PathBuf {
+        let mut seed = 0x2545_f491u32;
+        let image = image::RgbaImage::from_fn(512, 512, |_, _| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let [r, g, b, _] = seed.to_le_bytes();
+            image::Rgba([r, g, b, 255])
+        });
+        let path = dir.join("large.png");
+        image.save(&path).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn scenario_runs_against_a_local_receiver() {
+        let receiver = testing::spawn().await;
+        let image = large_png(&receiver.dir);
+        let size = std::fs::metadata(&image).unwrap().len();
+        assert!(size > 2 * CHUNK_SIZE as u64, "{} bytes", size);
+
+        let source = format!(
+            "clipboard 未配对\nexpect 401\n\
+             pair 000000\nexpect 401\n\
+             pair {}\n\
+             sms 10086 123456 您的验证码是 123456\n\
+             chunked {} disconnect {}\n\
+             ws clipboard 经 WebSocket 发送\n\
+             ws sms 10010 - 没有验证码\n",
+            receiver.server.pairing_pin(),
+            image.display(),
+            CHUNK_SIZE + 1000,
+        );
+        let steps = scenario::parse_with_input(&source, &receiver.dir, &b""[..]).unwrap();
+        let client = Client::from_url(&receiver.url, Duration::from_secs(10)).unwrap();
+        let failures = Runner::new(client, Duration::from_secs(10)).run(&steps).await;
+        assert_eq!(failures, 0);
+
+        assert_eq!(receiver.server.devices().len(), 1);
+        // 短信、续传完成的图片与经 WebSocket 发送的剪贴板与短信各一条通知
+        let groups: Vec<_> = receiver.notifications(4).await.into_iter().map(|n| n.group).collect();
+        assert_eq!(groups.len(), 4, "{:?}", groups);
+        for group in ["sms", "photo", "clipboard"] {
+            assert!(groups.iter().any(|g| g == group), "{:?}", groups);
+        }
+    }
+
+    #[tokio::test]
+    async fn unexpected_statuses_are_counted() {
+        let receiver = testing::spawn().await;
+        let steps = scenario::parse_with_input("clipboard x\nwait 1\nchunked missing.bin", &receiver.dir, &b""[..]).unwrap();
+        let client = Client::from_url(&receiver.url, Duration::from_secs(5)).unwrap();
+        // 未配对时剪贴板返回 401，文件不存在时出错
+        assert_eq!(Runner::new(client, Duration::from_secs(5)).run(&steps).await, 2);
+    }
+}
@@ -0,0 +1,303 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 模拟场景文件解析。
+ *
+ * 每行一个步骤，`#` 开头为注释，`expect` 修改上一个步骤期望的状态码（默认 200）。
+ * 短信内容为 `-` 时从标准输入读取一行，便于由其他程序生成内容：
+ *
+ * ```text
+ * pair 123456
+ * photo ./sample.jpg
+ * sms 10086 123456 您的验证码是 123456
+ * sms 10010 - 没有验证码的普通短信
+ * sms 10010 - -
+ * clipboard 任意文本
+ * chunked ./large.mp4 disconnect 300000
+ * ws sms 10086 - 经 WebSocket 发送的短信
+ * ws clipboard 经 WebSocket 发送的文本
+ * wait 500
+ * photo ./not-exist.bin
+ * expect 400
+ * ```
+ */
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// 单个动作。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// 通过 `/upload` 发送图片文件
+    Photo(PathBuf),
+    /// 通过 `/sms` 发送短信，验证码为 `-` 时表示没有验证码
+    Sms { sender: String, code: String, content: String },
+    /// 通过 `/clipboard` 发送剪贴板文本
+    Clipboard(String),
+    /// 等待一段时间再执行下一步
+    Wait(Duration),
+    /// 用托盘显示的 PIN 配对，之后的步骤使用签发的设备令牌
+    Pair(String),
+    /// 经 `/upload/init` 与 `/upload/chunk` 分块上传文件，可在指定位置断开一次后从接收端记录的位置续传
+    Chunked { path: PathBuf, disconnect_at: Option<u64> },
+    /// 经 WebSocket 连接发送短信并等待回执
+    WsSms { sender: String, code: String, content: String },
+    /// 经 WebSocket 连接发送剪贴板文本并等待回执
+    WsClipboard(String),
+}
+
+/// 场景中的一个步骤。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// 所在行号，用于报告失败位置
+    pub line: usize,
+    pub action: Action,
+    /// 期望的 HTTP 状态码
+    pub expect_status: u16,
+}
+
+/// 解析场景文本，`-` 表示的短信内容从标准输入读取。
+///
+/// # Arguments
+/// * `source` - 场景文件内容
+/// * `base_dir` - 相对路径的基准目录，通常为场景文件所在目录
+pub fn parse(source: &str, base_dir: &std::path::Path) -> anyhow::Result<Vec<Step>> {
+    parse_with_input(source, base_dir, std::io::stdin().lock())
+}
+
+/// 解析场景文本，`-` 表示的短信内容依次从 `input` 读取一行。
+///
+/// # Arguments
+/// * `source` - 场景文件内容
+/// * `base_dir` - 相对路径的基准目录
+/// * `input` - 短信内容的来源
+pub fn parse_with_input(source: &str, base_dir: &std::path::Path, mut input: impl BufRead) -> anyhow::Result<Vec<Step>> {
+    let mut steps: Vec<Step> = Vec::new();
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let (command, rest) = match text.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (text, ""),
+        };
+
+        let action = match command {
+            "photo" => {
+                if rest.is_empty() {
+                    anyhow::bail!("line {}: photo requires a file path", line);
+                }
+                Action::Photo(base_dir.join(rest))
+            }
+            "sms" => {
+                let (sender, code, content) = parse_sms(rest, line, &mut input)?;
+                Action::Sms { sender, code, content }
+            }
+            "clipboard" => Action::Clipboard(rest.to_string()),
+            "pair" => {
+                if rest.is_empty() || rest.contains(char::is_whitespace) {
+                    anyhow::bail!("line {}: expected `pair <PIN>`", line);
+                }
+                Action::Pair(rest.to_string())
+            }
+            "chunked" => {
+                let (path, disconnect_at) = match rest.rsplit_once(" disconnect ") {
+                    Some((path, offset)) => {
+                        let offset: u64 = offset
+                            .trim()
+                            .parse()
+                            .map_err(|_| anyhow::anyhow!("line {}: disconnect requires a byte offset", line))?;
+                        (path.trim(), Some(offset))
+                    }
+                    None => (rest, None),
+                };
+                if path.is_empty() {
+                    anyhow::bail!("line {}: chunked requires a file path", line);
+                }
+                Action::Chunked {
+                    path: base_dir.join(path),
+                    disconnect_at,
+                }
+            }
+            "ws" => match rest.split_once(char::is_whitespace) {
+                Some(("sms", rest)) => {
+                    let (sender, code, content) = parse_sms(rest.trim(), line, &mut input)?;
+                    Action::WsSms { sender, code, content }
+                }
+                Some(("clipboard", text)) => Action::WsClipboard(text.trim().to_string()),
+                _ => anyhow::bail!("line {}: expected `ws sms ...` or `ws clipboard <text>`", line),
+            },
+            "wait" => {
+                let millis: u64 = rest
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("line {}: wait requires milliseconds", line))?;
+                Action::Wait(Duration::from_millis(millis))
+            }
+            "expect" => {
+                let status: u16 = rest
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("line {}: expect requires a status code", line))?;
+                match steps.last_mut() {
+                    Some(step) if !matches!(step.action, Action::Wait(_)) => step.expect_status = status,
+                    _ => anyhow::bail!("line {}: expect must follow a request step", line),
+                }
+                continue;
+            }
+            other => anyhow::bail!("line {}: unknown command `{}`", line, other),
+        };
+
+        steps.push(Step {
+            line,
+            action,
+            expect_status: 200,
+        });
+    }
+
+    Ok(steps)
+}
+
+/// 解析 `<sender> <code|-> <content|->`。
+///
+/// # Returns
+/// (发送方, 验证码, 内容)，没有验证码时验证码为空
+fn parse_sms(rest: &str, line: usize, input: &mut impl BufRead) -> anyhow::Result<(String, String, String)> {
+    let mut parts = rest.splitn(3, char::is_whitespace);
+    let sender = parts.next().unwrap_or_default();
+    let code = parts.next().unwrap_or_default();
+    let content = parts.next().unwrap_or_default().trim();
+    if sender.is_empty() || code.is_empty() || content.is_empty() {
+        anyhow::bail!("line {}: expected `sms <sender> <code|-> <content|->`", line);
+    }
+    let content = if content == "-" {
+        let mut read = String::new();
+        if input.read_line(&mut read)? == 0 {
+            anyhow::bail!("line {}: no more SMS content on standard input", line);
+        }
+        read.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        content.to_string()
+    };
+    let code = if code == "-" { String::new() } else { code.to_string() };
+    Ok((sender.to_string(), code, content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn parse_str(source: &str) -> anyhow::Result<Vec<Step>> {
+        parse_with_input(source, Path::new("/scenarios"), &b""[..])
+    }
+
+    fn actions(source: &str) -> Vec<(usize, Action, u16)> {
+        parse_str(source)
+            .unwrap()
+            .into_iter()
+            .map(|step| (step.line, step.action, step.expect_status))
+            .collect()
+    }
+
+    fn sms(sender: &str, code: &str, content: &str) -> Action {
+        Action::Sms {
+            sender: sender.to_string(),
+            code: code.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let source = "# 注释\n\n   # 缩进的注释\nclipboard hello # 不是注释\n  wait 250  \n";
+        assert_eq!(
+            actions(source),
+            vec![
+                (4, Action::Clipboard("hello # 不是注释".to_string()), 200),
+                (5, Action::Wait(Duration::from_millis(250)), 200),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_every_action() {
+        let source = "pair 123456\n\
+            photo a.jpg\n\
+            sms 10086 123456 您的验证码是 123456\n\
+            sms 10010 - 没有验证码\n\
+            clipboard\n\
+            chunked big.bin\n\
+            chunked my video.mp4 disconnect 300000\n\
+            ws sms 10086 - 经 WebSocket\n\
+            ws clipboard 文本\n";
+        assert_eq!(
+            actions(source).into_iter().map(|(_, action, _)| action).collect::<Vec<_>>(),
+            vec![
+                Action::Pair("123456".to_string()),
+                Action::Photo(PathBuf::from("/scenarios/a.jpg")),
+                sms("10086", "123456", "您的验证码是 123456"),
+                sms("10010", "", "没有验证码"),
+                Action::Clipboard(String::new()),
+                Action::Chunked { path: PathBuf::from("/scenarios/big.bin"), disconnect_at: None },
+                Action::Chunked { path: PathBuf::from("/scenarios/my video.mp4"), disconnect_at: Some(300000) },
+                Action::WsSms { sender: "10086".to_string(), code: String::new(), content: "经 WebSocket".to_string() },
+                Action::WsClipboard("文本".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn expect_applies_to_the_previous_request() {
+        let cases = [
+            ("photo a.jpg\nexpect 415", Ok(vec![415])),
+            ("pair 1\nexpect 401\nclipboard x", Ok(vec![401, 200])),
+            // 注释与空行不影响 expect 的归属
+            ("ws clipboard x\n# 回执\n\nexpect 413", Ok(vec![413])),
+            ("expect 200", Err("line 1: expect must follow a request step")),
+            ("clipboard x\nwait 10\nexpect 200", Err("line 3: expect must follow a request step")),
+            ("clipboard x\nexpect ok", Err("line 2: expect requires a status code")),
+        ];
+        for (source, expected) in cases {
+            let result = parse_str(source)
+                .map(|steps| steps.iter().map(|step| step.expect_status).collect::<Vec<_>>())
+                .map_err(|e| e.to_string());
+            assert_eq!(result, expected.map_err(str::to_string), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn reads_sms_content_from_input() {
+        let source = "sms 10086 123456 -\nclipboard x\nws sms 10010 - -\n";
+        let steps = parse_with_input(source, Path::new("."), &b"first line\r\nsecond line\n"[..]).unwrap();
+        assert_eq!(steps[0].action, sms("10086", "123456", "first line"));
+        assert_eq!(
+            steps[2].action,
+            Action::WsSms { sender: "10010".to_string(), code: String::new(), content: "second line".to_string() }
+        );
+
+        let error = parse_with_input("clipboard x\nsms 10086 - -", Path::new("."), &b""[..]).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: no more SMS content on standard input");
+    }
+
+    #[test]
+    fn errors_report_the_line_number() {
+        let cases = [
+            ("photo", "line 1: photo requires a file path"),
+            ("clipboard ok\nsms 10086 123", "line 2: expected `sms <sender> <code|-> <content|->`"),
+            ("\n\nwait soon", "line 3: wait requires milliseconds"),
+            ("pair", "line 1: expected `pair <PIN>`"),
+            ("pair 12 34", "line 1: expected `pair <PIN>`"),
+            ("chunked a.bin disconnect half", "line 1: disconnect requires a byte offset"),
+            ("ws photo a.jpg", "line 1: expected `ws sms ...` or `ws clipboard <text>`"),
+            ("# ok\nupload a.jpg", "line 2: unknown command `upload`"),
+        ];
+        for (source, expected) in cases {
+            let error = parse_str(source).unwrap_err();
+            assert_eq!(error.to_string(), expected, "{:?}", source);
+        }
+    }
+}
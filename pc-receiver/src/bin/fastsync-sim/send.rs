@@ -0,0 +1,408 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 命令行发送。
+ * 让另一台电脑无需手机应用即可向接收端推送文件、文字或本机剪贴板：
+ *
+ * ```text
+ * fastsync-sim pair --pin 123456 [--to <名称|地址>] [--name <本机名称>]
+ * fastsync-sim send-file <路径> [--to <名称|地址>]
+ * fastsync-sim send-text <文字> [--to <名称|地址>]
+ * fastsync-sim send-clipboard [--to <名称|地址>]
+ * ```
+ */
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use fastsync::ClipboardBackend;
+use fastsync_core::protocol::ClipboardPayload;
+use crate::client::{Body, Client, Response};
+use crate::store::{StoredReceiver, TokenStore};
+use crate::{discover, Target, EXIT_ERROR, EXIT_FAILED, EXIT_NOT_FOUND, EXIT_OK, EXIT_UNAUTHORIZED};
+
+/// 发送子命令。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// 用托盘显示的 PIN 配对并保存令牌
+    Pair { pin: String, name: Option<String> },
+    /// 通过 `/upload` 发送文件
+    SendFile(PathBuf),
+    /// 通过 `/clipboard` 发送文字
+    SendText(String),
+    /// 读取本机剪贴板并发送
+    SendClipboard,
+}
+
+/// 子命令的公共参数。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// 接收端地址或 mDNS 名称，未指定时使用发现的第一个接收端
+    pub to: Option<String>,
+    pub timeout: Duration,
+}
+
+/// 解析子命令参数，第一个参数不是子命令时返回 None。
+///
+/// # Arguments
+/// * `args` - 不含程序名的命令行参数
+pub fn parse(args: &[String]) -> anyhow::Result<Option<(Command, Options)>> {
+    let Some(name) = args.first() else {
+        return Ok(None);
+    };
+    if !matches!(name.as_str(), "pair" | "send-file" | "send-text" | "send-clipboard") {
+        return Ok(None);
+    }
+
+    let mut options = Options {
+        to: None,
+        timeout: Duration::from_secs(10),
+    };
+    let mut pin = None;
+    let mut device_name = None;
+    let mut positional = Vec::new();
+
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let mut value = || rest.next().cloned().ok_or_else(|| anyhow::anyhow!("{} requires a value", arg));
+        match arg.as_str() {
+            "--to" | "--url" => options.to = Some(value()?),
+            "--timeout" => options.timeout = Duration::from_secs(value()?.parse()?),
+            "--pin" if name == "pair" => pin = Some(value()?),
+            "--name" if name == "pair" => device_name = Some(value()?),
+            _ if arg.starts_with("--") => anyhow::bail!("Unknown option {} for {}", arg, name),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let command = match (name.as_str(), positional.as_slice()) {
+        ("pair", []) => Command::Pair {
+            pin: pin.ok_or_else(|| anyhow::anyhow!("pair requires --pin"))?,
+            name: device_name,
+        },
+        ("send-file", [path]) => Command::SendFile(PathBuf::from(path)),
+        ("send-text", [_, ..]) => Command::SendText(positional.join(" ")),
+        ("send-clipboard", []) => Command::SendClipboard,
+        _ => anyhow::bail!("Wrong arguments for {}", name),
+    };
+    Ok(Some((command, options)))
+}
+
+/// 执行子命令。
+///
+/// # Returns
+/// 进程退出码
+pub fn run(command: Command, options: Options) -> anyhow::Result<i32> {
+    let Some(target) = resolve_target(options.to.as_deref(), |wanted| discover(options.timeout, wanted))? else {
+        tracing::error!("No receiver found via mDNS, pass --to http://host:port instead");
+        return Ok(EXIT_NOT_FOUND);
+    };
+    tracing::info!("Using receiver at {}", target.url);
+
+    let mut store = TokenStore::load()?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    rt.block_on(execute(command, &target, &mut store, options.timeout, &*fastsync::default_clipboard()))
+}
+
+/// 确定接收端：`http://` 地址直接使用，否则按名称（或不限名称）经 mDNS 发现。
+///
+/// # Arguments
+/// * `to` - `--to` 的值
+/// * `discover` - 以期望的名称前缀执行发现
+///
+/// # Returns
+/// 未发现接收端时为 None
+fn resolve_target(
+    to: Option<&str>,
+    discover: impl FnOnce(Option<&str>) -> anyhow::Result<Option<Target>>,
+) -> anyhow::Result<Option<Target>> {
+    match to {
+        Some(url) if url.starts_with("http://") => Ok(Some(Target {
+            url: url.trim_end_matches('/').to_string(),
+            name: None,
+        })),
+        wanted => discover(wanted),
+    }
+}
+
+/// 向选定的接收端发送子命令对应的请求，配对成功时保存令牌。
+///
+/// # Arguments
+/// * `command` - 子命令
+/// * `target` - 接收端
+/// * `store` - 令牌存储
+/// * `timeout` - 单次连接、读写的超时时间
+/// * `clipboard` - `send-clipboard` 读取的本机剪贴板
+///
+/// # Returns
+/// 进程退出码
+async fn execute(
+    command: Command,
+    target: &Target,
+    store: &mut TokenStore,
+    timeout: Duration,
+    clipboard: &dyn ClipboardBackend,
+) -> anyhow::Result<i32> {
+    let token = store
+        .find(&target.url, target.name.as_deref())
+        .map(|receiver| receiver.token.clone());
+    let client = Client::from_url(&target.url, timeout)?.with_token(token);
+
+    let response = match command {
+        Command::Pair { pin, name } => {
+            let name = name.unwrap_or_else(|| {
+                hostname::get()
+                    .map(|h| h.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| "FastSync CLI".into())
+            });
+            let body = serde_json::json!({ "pin": pin, "device_name": name });
+            let response = client
+                .request("/v1/pair", "application/json", Body::Bytes(&serde_json::to_vec(&body)?), |_, _| {})
+                .await?;
+            if response.status == 200 {
+                let token = serde_json::from_slice::<serde_json::Value>(&response.body)?["device_token"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("Pairing response has no device_token"))?;
+                store.insert(StoredReceiver {
+                    url: target.url.clone(),
+                    name: target.name.clone(),
+                    token,
+                });
+                store.save()?;
+                println!("Paired with {}", target.name.as_deref().unwrap_or(&target.url));
+            } else if response.status == 401 {
+                tracing::error!("Pairing rejected: {}", String::from_utf8_lossy(&response.body));
+                return Ok(EXIT_FAILED);
+            }
+            response
+        }
+        Command::SendFile(path) => {
+            let response = client.request("/v1/upload", "", Body::File(&path), print_progress).await?;
+            eprintln!();
+            response
+        }
+        Command::SendText(text) => send_text(&client, text).await?,
+        Command::SendClipboard => {
+            let text = clipboard.get_text()?;
+            if text.is_empty() {
+                anyhow::bail!("Local clipboard has no text");
+            }
+            send_text(&client, text).await?
+        }
+    };
+
+    Ok(exit_code(&response))
+}
+
+/// 通过 `/clipboard` 发送文字。
+async fn send_text(client: &Client, text: String) -> anyhow::Result<Response> {
+    let payload = ClipboardPayload {
+        text,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        html: None,
+        image_base64: None,
+    };
+    client
+        .request("/v1/clipboard", "application/json", Body::Bytes(&serde_json::to_vec(&payload)?), |_, _| {})
+        .await
+}
+
+/// 在标准错误上打印上传进度。
+fn print_progress(sent: u64, total: u64) {
+    let percent = (sent * 100).checked_div(total).unwrap_or(100);
+    eprint!("\r{:>3}% {}/{} bytes", percent, sent, total);
+    let _ = std::io::stderr().flush();
+}
+
+/// 按响应状态码确定退出码并输出原因。
+fn exit_code(response: &Response) -> i32 {
+    let reason = String::from_utf8_lossy(&response.body);
+    match response.status {
+        200..=299 => EXIT_OK,
+        401 => {
+            tracing::error!("Receiver rejected the device token ({}), run `fastsync-sim pair --pin <PIN>` first", reason);
+            EXIT_UNAUTHORIZED
+        }
+        400..=499 => {
+            tracing::error!("Receiver rejected the request with {}: {}", response.status, reason);
+            EXIT_FAILED
+        }
+        status => {
+            tracing::error!("Receiver failed with {}: {}", status, reason);
+            EXIT_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use fastsync::RecordingClipboard;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        let cases = [
+            ("pair --pin 123456", Command::Pair { pin: "123456".into(), name: None }),
+            (
+                "pair --name Laptop --pin 123456 --to Desk",
+                Command::Pair { pin: "123456".into(), name: Some("Laptop".into()) },
+            ),
+            ("send-file ./a.png --timeout 3", Command::SendFile(PathBuf::from("./a.png"))),
+            ("send-text hello world --url http://10.0.0.2:8080", Command::SendText("hello world".into())),
+            ("send-clipboard", Command::SendClipboard),
+        ];
+        for (line, expected) in cases {
+            let (command, _) = parse(&args(line)).unwrap().unwrap();
+            assert_eq!(command, expected, "{:?}", line);
+        }
+
+        let (_, options) = parse(&args("send-clipboard --to Desk --timeout 3")).unwrap().unwrap();
+        assert_eq!(options, Options { to: Some("Desk".into()), timeout: Duration::from_secs(3) });
+        let (_, options) = parse(&args("send-clipboard")).unwrap().unwrap();
+        assert_eq!(options, Options { to: None, timeout: Duration::from_secs(10) });
+    }
+
+    #[test]
+    fn leaves_scenarios_to_the_simulator() {
+        for line in ["", "scenario.txt", "--url http://10.0.0.2:8080 scenario.txt"] {
+            assert_eq!(parse(&args(line)).unwrap(), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let cases = [
+            ("pair", "pair requires --pin"),
+            ("pair --pin", "--pin requires a value"),
+            ("send-file --name x a.png", "Unknown option --name for send-file"),
+            ("send-file a.png b.png", "Wrong arguments for send-file"),
+            ("send-text", "Wrong arguments for send-text"),
+            ("send-clipboard extra", "Wrong arguments for send-clipboard"),
+        ];
+        for (line, message) in cases {
+            let error = parse(&args(line)).unwrap_err();
+            assert_eq!(error.to_string(), message, "{:?}", line);
+        }
+        assert!(parse(&args("send-text hi --timeout soon")).is_err());
+    }
+
+    #[test]
+    fn explicit_urls_skip_discovery() {
+        let target = resolve_target(Some("http://10.0.0.2:8080/"), |_| panic!("discovered")).unwrap().unwrap();
+        assert_eq!(target.url, "http://10.0.0.2:8080");
+        assert_eq!(target.name, None);
+    }
+
+    #[test]
+    fn names_are_passed_to_discovery() {
+        for to in [None, Some("Desk")] {
+            let mut wanted = None;
+            let target = resolve_target(to, |name| {
+                wanted = Some(name.map(str::to_string));
+                Ok(Some(Target {
+                    url: "http://10.0.0.2:8080".into(),
+                    name: Some("Desk-PC".into()),
+                }))
+            })
+            .unwrap()
+            .unwrap();
+            assert_eq!(wanted, Some(to.map(str::to_string)));
+            assert_eq!(target.name.as_deref(), Some("Desk-PC"));
+        }
+        assert!(resolve_target(Some("Desk"), |_| Ok(None)).unwrap().is_none());
+    }
+
+    #[test]
+    fn exit_codes_follow_the_status() {
+        let cases = [
+            (200, EXIT_OK),
+            (204, EXIT_OK),
+            (401, EXIT_UNAUTHORIZED),
+            (413, EXIT_FAILED),
+            (500, EXIT_ERROR),
+            (503, EXIT_ERROR),
+        ];
+        for (status, expected) in cases {
+            let response = Response { status, body: Vec::new() };
+            assert_eq!(exit_code(&response), expected, "{}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn pairs_and_sends_to_a_local_receiver() {
+        let receiver = testing::spawn().await;
+        let target = Target {
+            url: receiver.url.clone(),
+            name: Some("Desk".into()),
+        };
+        let path = receiver.dir.join("sender_tokens.dat");
+        let mut store = TokenStore::open(Some(path.clone())).unwrap();
+        let clipboard = RecordingClipboard::new();
+        let timeout = Duration::from_secs(10);
+
+        // 未配对时接收端拒绝，退出码提示重新配对
+        let code = execute(Command::SendText("hi".into()), &target, &mut store, timeout, &clipboard).await.unwrap();
+        assert_eq!(code, EXIT_UNAUTHORIZED);
+
+        let wrong = Command::Pair { pin: "000000".into(), name: None };
+        assert_eq!(execute(wrong, &target, &mut store, timeout, &clipboard).await.unwrap(), EXIT_FAILED);
+        assert!(!path.exists());
+
+        let pair = Command::Pair {
+            pin: receiver.server.pairing_pin(),
+            name: Some("Laptop".into()),
+        };
+        assert_eq!(execute(pair, &target, &mut store, timeout, &clipboard).await.unwrap(), EXIT_OK);
+        let devices = receiver.server.devices();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "Laptop");
+        // 令牌已写入文件，之后的命令重新读取即可使用
+        let mut store = TokenStore::open(Some(path)).unwrap();
+        assert!(store.find(&receiver.url, None).is_some());
+
+        let code = execute(Command::SendText("你好".into()), &target, &mut store, timeout, &clipboard).await.unwrap();
+        assert_eq!(code, EXIT_OK);
+
+        clipboard.set_current_text("来自本机剪贴板");
+        let code = execute(Command::SendClipboard, &target, &mut store, timeout, &clipboard).await.unwrap();
+        assert_eq!(code, EXIT_OK);
+
+        let file = receiver.dir.join("photo.png");
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255])).save(&file).unwrap();
+        let code = execute(Command::SendFile(file), &target, &mut store, timeout, &clipboard).await.unwrap();
+        assert_eq!(code, EXIT_OK);
+
+        // 两段文字各一条剪贴板通知，图片一条通知
+        let shown = receiver.notifications(3).await;
+        assert_eq!(shown.len(), 3, "{:?}", shown);
+        let texts: Vec<String> = shown
+            .iter()
+            .filter(|notification| notification.group == "clipboard")
+            .map(|notification| notification.body.join("\n"))
+            .collect();
+        assert_eq!(texts.len(), 2, "{:?}", shown);
+        assert!(texts[0].contains("你好"), "{:?}", texts);
+        assert!(texts[1].contains("来自本机剪贴板"), "{:?}", texts);
+    }
+
+    #[tokio::test]
+    async fn empty_clipboard_is_not_sent() {
+        let receiver = testing::spawn().await;
+        let target = Target { url: receiver.url.clone(), name: None };
+        let mut store = TokenStore::open(None).unwrap();
+        let clipboard = RecordingClipboard::new();
+        clipboard.set_current_text("");
+
+        let result = execute(Command::SendClipboard, &target, &mut store, Duration::from_secs(10), &clipboard).await;
+        assert_eq!(result.unwrap_err().to_string(), "Local clipboard has no text");
+        assert!(receiver.notifier.shown().is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 发送端令牌存储。
+ * `pair` 成功后按接收端地址与名称保存设备令牌，之后的发送命令自动携带。
+ * Windows 上用 DPAPI 加密整个文件，只有当前用户能解密；Unix 上限制文件权限为仅本人可读。
+ * 两者都不可用的构建（如未启用 `notifications`、`clipboard` 特性的 Windows 构建）拒绝保存令牌，
+ * 而不是以明文写入。
+ */
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 一个已配对的接收端。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredReceiver {
+    /// 配对时使用的地址
+    pub url: String,
+    /// mDNS 实例名称，地址变化后按名称匹配
+    pub name: Option<String>,
+    pub token: String,
+}
+
+/// 令牌存储。
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TokenStore {
+    receivers: Vec<StoredReceiver>,
+    /// 存储文件路径，没有本地数据目录时为 None
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl TokenStore {
+    /// 读取本地数据目录中的令牌存储，文件不存在时返回空存储。
+    pub fn load() -> anyhow::Result<Self> {
+        Self::open(store_path())
+    }
+
+    /// 读取指定路径的令牌存储，文件不存在时返回空存储。
+    ///
+    /// # Arguments
+    /// * `path` - 存储文件路径，`save` 写回同一文件
+    pub fn open(path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self {
+                path: Some(path),
+                ..Self::default()
+            });
+        }
+
+        let data = unprotect(&std::fs::read(&path)?)
+            .map_err(|e| anyhow::anyhow!("Failed to decrypt token store {:?}: {:#}", path, e))?;
+        Ok(Self {
+            path: Some(path),
+            ..serde_json::from_slice(&data)?
+        })
+    }
+
+    /// 写回令牌存储。
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No local data directory for the token store"))?;
+        let data = protect(&serde_json::to_vec(self)?)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, data)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+
+    /// 按地址查找令牌，找不到时按名称查找。
+    ///
+    /// # Arguments
+    /// * `url` - 接收端地址
+    /// * `name` - mDNS 实例名称
+    pub fn find(&self, url: &str, name: Option<&str>) -> Option<&StoredReceiver> {
+        let url = url.trim_end_matches('/');
+        self.receivers
+            .iter()
+            .find(|receiver| receiver.url == url)
+            .or_else(|| {
+                let name = name?;
+                self.receivers
+                    .iter()
+                    .find(|receiver| receiver.name.as_deref() == Some(name))
+            })
+    }
+
+    /// 保存令牌，同一地址或名称的旧记录被替换。
+    pub fn insert(&mut self, receiver: StoredReceiver) {
+        let url = receiver.url.trim_end_matches('/').to_string();
+        self.receivers.retain(|existing| {
+            existing.url != url && (receiver.name.is_none() || existing.name != receiver.name)
+        });
+        self.receivers.push(StoredReceiver { url, ..receiver });
+    }
+}
+
+/// 令牌存储文件路径。
+fn store_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("FastSync").join("sender_tokens.dat"))
+}
+
+/// 用 DPAPI 加密，数据与当前 Windows 用户绑定。
+#[cfg(all(windows, any(feature = "notifications", feature = "clipboard")))]
+fn protect(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    use windows::Win32::Security::Cryptography::{CryptProtectData, CRYPT_INTEGER_BLOB};
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptProtectData(&input, windows::core::w!("FastSync sender tokens"), None, None, None, 0, &mut output)?;
+        Ok(take_blob(output))
+    }
+}
+
+/// 用 DPAPI 解密。
+#[cfg(all(windows, any(feature = "notifications", feature = "clipboard")))]
+fn unprotect(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    use windows::Win32::Security::Cryptography::{CryptUnprotectData, CRYPT_INTEGER_BLOB};
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptUnprotectData(&input, None, None, None, None, 0, &mut output)?;
+        Ok(take_blob(output))
+    }
+}
+
+/// 复制 DPAPI 分配的输出并释放原内存。
+#[cfg(all(windows, any(feature = "notifications", feature = "clipboard")))]
+unsafe fn take_blob(blob: windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+
+    let data = std::slice::from_raw_parts(blob.pbData, blob.cbData as usize).to_vec();
+    LocalFree(HLOCAL(blob.pbData as *mut core::ffi::c_void));
+    data
+}
+
+/// Unix 上以明文保存，由 `save` 设置的文件权限保护。
+#[cfg(unix)]
+fn protect(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    Ok(data.to_vec())
+}
+
+#[cfg(unix)]
+fn unprotect(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    Ok(data.to_vec())
+}
+
+/// 没有 DPAPI 也没有文件权限可用时拒绝保存，避免令牌以明文落盘。
+#[cfg(not(any(unix, all(windows, any(feature = "notifications", feature = "clipboard")))))]
+fn protect(_data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("This build cannot protect the token store, rebuild with the notifications or clipboard feature")
+}
+
+#[cfg(not(any(unix, all(windows, any(feature = "notifications", feature = "clipboard")))))]
+fn unprotect(_data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!("This build cannot decrypt the token store, rebuild with the notifications or clipboard feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receiver(url: &str, name: Option<&str>, token: &str) -> StoredReceiver {
+        StoredReceiver {
+            url: url.to_string(),
+            name: name.map(str::to_string),
+            token: token.to_string(),
+        }
+    }
+
+    #[test]
+    fn finds_by_url_then_by_name() {
+        let mut store = TokenStore::default();
+        store.insert(receiver("http://10.0.0.2:8080/", Some("Desk"), "a"));
+        store.insert(receiver("http://10.0.0.3:8080", None, "b"));
+
+        let cases = [
+            ("http://10.0.0.2:8080", None, Some("a")),
+            ("http://10.0.0.3:8080/", Some("Desk"), Some("b")),
+            // 地址变化后按名称找到
+            ("http://10.0.0.9:8080", Some("Desk"), Some("a")),
+            ("http://10.0.0.9:8080", Some("Laptop"), None),
+        ];
+        for (url, name, expected) in cases {
+            let found = store.find(url, name).map(|r| r.token.as_str());
+            assert_eq!(found, expected, "{:?} {:?}", url, name);
+        }
+
+        // 同名接收端换了地址，旧记录被替换
+        store.insert(receiver("http://10.0.0.9:8080", Some("Desk"), "c"));
+        assert_eq!(store.receivers.len(), 2);
+        assert_eq!(store.find("http://10.0.0.2:8080", None).map(|r| r.token.as_str()), None);
+    }
+
+    #[test]
+    fn saves_and_reopens() {
+        let dir = std::env::temp_dir().join(format!("fastsync-sim-store-{}", std::process::id()));
+        let path = dir.join("nested").join("sender_tokens.dat");
+
+        let mut store = TokenStore::open(Some(path.clone())).unwrap();
+        assert!(store.receivers.is_empty());
+        store.insert(receiver("http://10.0.0.2:8080", Some("Desk"), "secret"));
+        store.save().unwrap();
+
+        let reopened = TokenStore::open(Some(path.clone())).unwrap();
+        assert_eq!(reopened.find("http://10.0.0.2:8080", None).unwrap().token, "secret");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 没有数据目录时可以读取空存储，但不能保存
+        assert!(TokenStore::open(None).unwrap().save().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
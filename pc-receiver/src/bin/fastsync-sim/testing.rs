@@ -0,0 +1,80 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 测试用的本机接收端：使用记录型的通知与剪贴板后端，数据文件写入独立的临时目录，
+ * 在本机随机端口上提供与正式服务相同的路由。
+ */
+use fastsync::{FastSyncServer, MockNotifier, Notification, RecordingClipboard, RunMode};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 接收端的共用访问令牌。
+pub const TOKEN: &str = "sim-test-token";
+
+static DIR_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 运行中的接收端，丢弃时删除临时目录。
+pub struct Receiver {
+    pub server: FastSyncServer,
+    pub url: String,
+    pub notifier: Arc<MockNotifier>,
+    pub dir: PathBuf,
+}
+
+impl Receiver {
+    /// 等待至少 `count` 条通知显示，返回已显示的通知。
+    pub async fn notifications(&self, count: usize) -> Vec<Notification> {
+        for _ in 0..100 {
+            if self.notifier.shown().len() >= count {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        self.notifier.shown()
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// 启动接收端。
+pub async fn spawn() -> Receiver {
+    let dir = std::env::temp_dir().join(format!(
+        "fastsync-sim-test-{}-{}",
+        std::process::id(),
+        DIR_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let notifier = Arc::new(MockNotifier::new());
+    let server = FastSyncServer::builder()
+        .port(0)
+        .mdns(false)
+        .https(false)
+        .access_token(TOKEN)
+        .audit_log(dir.join("audit.jsonl"))
+        .sms_history(dir.join("sms_history.db"))
+        .devices_file(dir.join("devices.json"))
+        .port_hint_file(dir.join("port.json"))
+        .quick_save_dir(dir.join("quick"))
+        .mode(RunMode::Desktop)
+        .notifier(notifier.clone())
+        .clipboard(Arc::new(RecordingClipboard::new()))
+        .build();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let service = server.router().into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, service).await });
+    Receiver {
+        server,
+        url: format!("http://{}", addr),
+        notifier,
+        dir,
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 最小 WebSocket 客户端，模拟手机经 `/v1/ws` 保持的连接。
+ * 只收发文本帧：发送短信与剪贴板消息并等待对应的 `response` 回执，
+ * 期间收到电脑发来的短信回复时按手机的做法回复 `reply_sent`。
+ */
+use base64::Engine;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::client::Client;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// 已建立的连接。
+pub struct Session {
+    stream: TcpStream,
+    timeout: Duration,
+    next_id: u64,
+    /// 已回复 `reply_sent` 的短信回复数
+    pub replies_answered: usize,
+}
+
+impl Session {
+    /// 以客户端的地址与设备令牌建立连接，并读取第一条 `hello` 消息。
+    ///
+    /// # Arguments
+    /// * `client` - 指向接收端的客户端
+    /// * `timeout` - 握手与等待回执的超时时间
+    pub async fn connect(client: &Client, timeout: Duration) -> anyhow::Result<Self> {
+        let mut stream = client.connect().await?;
+        let key = base64::engine::general_purpose::STANDARD.encode(rand::random::<[u8; 16]>());
+        let mut head = format!(
+            "GET /v1/ws HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+            client.authority(),
+            key
+        );
+        if let Some(token) = client.token() {
+            head.push_str(&format!("Authorization: Bearer {}\r\n", token));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).await?;
+
+        // 逐字节读取响应头，之后的字节属于 WebSocket 帧
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            let byte = tokio::time::timeout(timeout, stream.read_u8()).await??;
+            response.push(byte);
+        }
+        let status_line = String::from_utf8_lossy(&response);
+        let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+        if status != "101" {
+            anyhow::bail!("WebSocket upgrade rejected with {}", status);
+        }
+
+        let mut session = Self {
+            stream,
+            timeout,
+            next_id: 1,
+            replies_answered: 0,
+        };
+        let hello = session.next_message().await?;
+        if hello["type"] != "hello" {
+            anyhow::bail!("Expected hello, got {}", hello);
+        }
+        Ok(session)
+    }
+
+    /// 发送一条消息并等待对应的 `response` 回执。
+    ///
+    /// # Arguments
+    /// * `kind` - 消息类型，`sms` 或 `clipboard`
+    /// * `payload` - 与对应 REST 接口相同的请求体
+    ///
+    /// # Returns
+    /// 回执中的状态码
+    pub async fn send(&mut self, kind: &str, payload: Value) -> anyhow::Result<u16> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.write_text(&json!({ "type": kind, "id": id, "payload": payload }).to_string()).await?;
+        loop {
+            let message = self.next_message().await?;
+            match message["type"].as_str() {
+                Some("response") if message["id"] == id => {
+                    return message["status"]
+                        .as_u64()
+                        .and_then(|status| u16::try_from(status).ok())
+                        .ok_or_else(|| anyhow::anyhow!("Receipt without status: {}", message));
+                }
+                Some("error") if message["id"] == id || message["id"].is_null() => {
+                    anyhow::bail!("Receiver rejected the message: {}", message);
+                }
+                // 其他消息（剪贴板变化、其他请求的回执）不影响本次等待
+                _ => {}
+            }
+        }
+    }
+
+    /// 读取下一条 JSON 消息，自动回复 Ping 与短信回复。
+    async fn next_message(&mut self) -> anyhow::Result<Value> {
+        loop {
+            let (opcode, payload) = tokio::time::timeout(self.timeout, self.read_frame())
+                .await
+                .map_err(|_| anyhow::anyhow!("No message from the receiver within {:?}", self.timeout))??;
+            match opcode {
+                OPCODE_TEXT => {
+                    let message: Value = serde_json::from_slice(&payload)?;
+                    if message["type"] == "sms_reply" {
+                        tracing::info!("Answering SMS reply {} to {}", message["id"], message["to"]);
+                        self.write_text(&json!({ "type": "reply_sent", "reply": message["id"] }).to_string())
+                            .await?;
+                        self.replies_answered += 1;
+                    }
+                    return Ok(message);
+                }
+                OPCODE_PING => self.write_frame(OPCODE_PONG, &payload).await?,
+                OPCODE_CLOSE => anyhow::bail!("Receiver closed the WebSocket session"),
+                _ => {}
+            }
+        }
+    }
+
+    /// 读取一帧，返回操作码与内容。接收端发来的帧不带掩码，也不会分片发送 JSON 消息。
+    async fn read_frame(&mut self) -> anyhow::Result<(u8, Vec<u8>)> {
+        let mut header = [0u8; 2];
+        self.stream.read_exact(&mut header).await?;
+        let opcode = header[0] & 0x0F;
+        let len = match header[1] & 0x7F {
+            126 => u64::from(self.stream.read_u16().await?),
+            127 => self.stream.read_u64().await?,
+            len => u64::from(len),
+        };
+        let mut payload = vec![0u8; usize::try_from(len)?];
+        self.stream.read_exact(&mut payload).await?;
+        Ok((opcode, payload))
+    }
+
+    async fn write_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.write_frame(OPCODE_TEXT, text.as_bytes()).await
+    }
+
+    /// 发送一帧，客户端发出的帧必须加掩码。
+    async fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> anyhow::Result<()> {
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len if len <= usize::from(u16::MAX) => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        let mask: [u8; 4] = rand::random();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        self.stream.write_all(&frame).await?;
+        Ok(())
+    }
+}
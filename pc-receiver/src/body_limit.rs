@@ -0,0 +1,147 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 请求体大小限制模块。
+ * 图片上传与 JSON 接口使用不同的上限。请求头中的 `Content-Length` 已超过上限时直接返回 413，
+ * 响应中给出允许的最大字节数，手机端据此压缩或改用分块上传。
+ * 拒绝前先读完（丢弃）请求体，否则连接会在手机仍在发送时被重置，手机收不到响应。
+ */
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::handlers::response::{UploadError, UploadResponse};
+
+/// 超过上限的请求体最多读取的时长，超过后直接断开连接。
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 请求体大小上限（字节）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BodyLimits {
+    /// 图片上传等载荷接口的上限
+    pub upload: usize,
+    /// 剪贴板、短信等 JSON 接口的上限
+    pub json: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            upload: 200 * 1024 * 1024,
+            json: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl BodyLimits {
+    /// 读取环境变量 `FASTSYNC_MAX_UPLOAD_MB` 与 `FASTSYNC_MAX_JSON_MB` 覆盖默认值。
+    ///
+    /// # Returns
+    /// 覆盖后的上限，变量不是有效数字时报错
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::default().with_env()
+    }
+
+    /// 读取环境变量 `FASTSYNC_MAX_UPLOAD_MB` 与 `FASTSYNC_MAX_JSON_MB` 覆盖当前值，例如配置文件中的上限。
+    ///
+    /// # Returns
+    /// 覆盖后的上限，变量不是有效数字时报错
+    pub fn with_env(mut self) -> anyhow::Result<Self> {
+        if let Some(upload) = env_megabytes("FASTSYNC_MAX_UPLOAD_MB")? {
+            self.upload = upload;
+        }
+        if let Some(json) = env_megabytes("FASTSYNC_MAX_JSON_MB")? {
+            self.json = json;
+        }
+        Ok(self)
+    }
+}
+
+/// 读取以 MB 为单位的环境变量。
+fn env_megabytes(name: &str) -> anyhow::Result<Option<usize>> {
+    match std::env::var(name) {
+        Ok(value) => {
+            let megabytes: usize = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid {} value {:?}", name, value))?;
+            Ok(Some(megabytes * 1024 * 1024))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// 请求体超过上限时的响应。
+///
+/// # Arguments
+/// * `limit` - 允许的最大字节数
+/// * `attempted` - 请求声明的字节数，未知时省略
+pub fn too_large(limit: usize, attempted: Option<u64>) -> UploadResponse {
+    let response = UploadResponse::failure(UploadError::TooLarge).with_detail("limit", limit);
+    match attempted {
+        Some(attempted) => response.with_detail("attempted", attempted),
+        None => response,
+    }
+}
+
+/// 读取请求体的错误是否因为超过上限，例如解压后的请求体超过上限。
+pub(crate) fn exceeds_limit(error: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error.is::<http_body_util::LengthLimitError>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// 按 `Content-Length` 提前拒绝超过上限的请求。未声明长度的请求由读取请求体时的限制处理。
+///
+/// # Arguments
+/// * `limit` - 该组接口的上限
+pub(crate) async fn body_limit_guard(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    let Some(declared) = declared.filter(|&declared| declared > limit as u64) else {
+        return next.run(request).await;
+    };
+
+    tracing::warn!(
+        "Rejected {} with {} bytes, limit is {} bytes",
+        request.uri().path(),
+        declared,
+        limit
+    );
+    // 声明了 100-continue 的客户端尚未发送请求体，可以直接拒绝
+    let expects_continue = request
+        .headers()
+        .get(header::EXPECT)
+        .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"100-continue"));
+    if !expects_continue {
+        drain(request).await;
+    }
+    too_large(limit, Some(declared)).into_response()
+}
+
+/// 读完并丢弃请求体，超时后放弃。
+async fn drain(request: Request) {
+    let mut stream = request.into_body().into_data_stream();
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+        while let Some(Ok(_)) = stream.next().await {}
+    })
+    .await;
+    if drained.is_err() {
+        tracing::debug!("Gave up draining an oversized request after {:?}", DRAIN_TIMEOUT);
+    }
+}
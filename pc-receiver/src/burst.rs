@@ -0,0 +1,130 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 连拍分组模块。
+ * 手机一次选择多张图片逐张上传时，同一设备在聚合窗口内收到的图片归为一组：
+ * 第一张照常弹出通知，之后的图片只更新同一条“收到 N 张图片”的分组通知，不再逐张弹出。
+ * 窗口按请求开始的时间判断，窗口结束时仍在上传的图片同样归入该组。
+ * 组内图片的临时文件由分组统一保管，最后一张到达后超过通知有效期才删除，
+ * 分组通知的按钮因此始终能取到每一张图片。
+ */
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::audit::ItemAudit;
+use crate::temp_files;
+
+/// 默认的聚合窗口。
+pub const DEFAULT_BURST_WINDOW: Duration = Duration::from_secs(3);
+
+/// 分组中的一张图片。
+#[derive(Clone)]
+pub(crate) struct BurstImage {
+    /// 图片当前路径，移出临时目录后更新为新路径
+    pub path: Arc<Mutex<PathBuf>>,
+    /// 手机上的文件名主体
+    pub stem: Option<String>,
+    /// 该图片的审计句柄
+    pub audit: ItemAudit,
+}
+
+/// 一张图片加入分组后的结果。
+pub(crate) struct Arrival {
+    /// 分组标识，取第一张图片的标识
+    pub id: String,
+    /// 分组中的所有图片，按到达顺序排列，只有一张时为分组的第一张
+    pub images: Vec<BurstImage>,
+}
+
+struct Burst {
+    id: String,
+    last_arrival: Instant,
+    images: Arc<Mutex<Vec<BurstImage>>>,
+    /// 每到达一张图片加一，删除任务据此判断期间是否有新图片
+    generation: Arc<AtomicU64>,
+}
+
+/// 各设备当前的分组，由所有接口共享。聚合窗口取自配置，每张图片到达时传入。
+#[derive(Default)]
+pub(crate) struct BurstTracker {
+    bursts: Mutex<HashMap<String, Burst>>,
+}
+
+impl BurstTracker {
+    /// 将一张图片加入来源设备的分组，开始时间超出窗口时另起一组。
+    /// 分组接管图片的临时文件，最后一张到达 `keep_for` 之后删除。
+    ///
+    /// # Arguments
+    /// * `origin` - 来源设备
+    /// * `started` - 上传请求开始的时间
+    /// * `image` - 收到的图片
+    /// * `window` - 聚合窗口
+    /// * `keep_for` - 分组通知的有效期
+    ///
+    /// # Returns
+    /// 分组标识与组内的所有图片
+    pub(crate) fn arrive(self: &Arc<Self>, origin: &str, started: Instant, image: BurstImage, window: Duration, keep_for: Duration) -> Arrival {
+        let now = Instant::now();
+        let mut bursts = self.bursts.lock().unwrap();
+        let joins = bursts
+            .get(origin)
+            .is_some_and(|burst| started <= burst.last_arrival + window);
+        if !joins {
+            let id = image.audit.item_id().to_string();
+            bursts.insert(
+                origin.to_string(),
+                Burst {
+                    id,
+                    last_arrival: now,
+                    images: Arc::new(Mutex::new(Vec::new())),
+                    generation: Arc::new(AtomicU64::new(0)),
+                },
+            );
+        }
+
+        let burst = bursts.get_mut(origin).expect("burst was just inserted");
+        burst.last_arrival = now;
+        burst.images.lock().unwrap().push(image);
+        let generation = burst.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let arrival = Arrival {
+            id: burst.id.clone(),
+            images: burst.images.lock().unwrap().clone(),
+        };
+        if arrival.images.len() > 1 {
+            tracing::debug!("Image joined burst {} from {} ({} images)", arrival.id, origin, arrival.images.len());
+        }
+
+        // 窗口内再有图片到达时由新的任务负责删除
+        let id = burst.id.clone();
+        let images = burst.images.clone();
+        let current = burst.generation.clone();
+        let delay = keep_for + window;
+        drop(bursts);
+        let tracker = self.clone();
+        let origin = origin.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if current.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            {
+                // 设备可能已经开始了新的分组，只移除本组
+                let mut bursts = tracker.bursts.lock().unwrap();
+                if bursts.get(&origin).is_some_and(|burst| burst.id == id) {
+                    bursts.remove(&origin);
+                }
+            }
+            let paths: Vec<PathBuf> = images
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|image| image.path.lock().unwrap().clone())
+                .collect();
+            let _ = tokio::task::spawn_blocking(move || temp_files::discard(&paths)).await;
+        });
+        arrival
+    }
+}
@@ -0,0 +1,250 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 平台能力矩阵。
+ * 所有"某平台是否支持某功能"的判断都集中在此处，`/info` 与处理器据此决定行为。
+ */
+use serde::Serialize;
+use crate::state::RunMode;
+
+/// 编译目标平台。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    Linux,
+    MacOs,
+    Other,
+}
+
+impl Platform {
+    /// 返回当前编译目标平台。
+    pub const fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "linux") {
+            Platform::Linux
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Other
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::Linux => "linux",
+            Platform::MacOs => "macos",
+            Platform::Other => "other",
+        }
+    }
+}
+
+/// 可用功能集合。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    /// 系统托盘
+    pub tray: bool,
+    /// 桌面通知
+    pub notifications: bool,
+    /// 通知上的交互按钮
+    pub notification_actions: bool,
+    /// 剪贴板文本
+    pub clipboard_text: bool,
+    /// 剪贴板图片
+    pub clipboard_image: bool,
+    /// 剪贴板文件列表 (CF_HDROP)
+    pub clipboard_files: bool,
+    /// 剪贴板 HTML
+    pub clipboard_html: bool,
+    /// 受系统保护的凭据存储 (DPAPI)
+    pub secure_storage: bool,
+    /// 模拟键盘输入 (SendInput)
+    pub input_injection: bool,
+}
+
+impl Capabilities {
+    const NONE: Capabilities = Capabilities {
+        tray: false,
+        notifications: false,
+        notification_actions: false,
+        clipboard_text: false,
+        clipboard_image: false,
+        clipboard_files: false,
+        clipboard_html: false,
+        secure_storage: false,
+        input_injection: false,
+    };
+
+    /// 平台能力矩阵。
+    ///
+    /// # Arguments
+    /// * `platform` - 目标平台
+    pub const fn for_platform(platform: Platform) -> Self {
+        match platform {
+            Platform::Windows => Capabilities {
+                tray: true,
+                notifications: true,
+                notification_actions: true,
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_files: true,
+                clipboard_html: true,
+                secure_storage: true,
+                input_injection: true,
+            },
+            Platform::Linux => Capabilities {
+                tray: true,
+                notifications: true,
+                notification_actions: true,
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_html: true,
+                ..Self::NONE
+            },
+            Platform::MacOs => Capabilities {
+                tray: true,
+                notifications: cfg!(feature = "macos"),
+                notification_actions: cfg!(feature = "macos"),
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_html: true,
+                ..Self::NONE
+            },
+            Platform::Other => Self::NONE,
+        }
+    }
+
+    /// 按编译时启用的 cargo 特性裁剪能力。
+    /// DPAPI 与 SendInput 来自 `windows` 依赖，只在启用了引入它的特性时可用。
+    pub const fn with_enabled_features(self) -> Self {
+        let notifications = cfg!(feature = "notifications");
+        let clipboard = cfg!(feature = "clipboard");
+        Capabilities {
+            tray: self.tray && cfg!(feature = "tray"),
+            notifications: self.notifications && notifications,
+            notification_actions: self.notification_actions && notifications,
+            clipboard_text: self.clipboard_text && clipboard,
+            clipboard_image: self.clipboard_image && clipboard,
+            clipboard_files: self.clipboard_files && clipboard,
+            clipboard_html: self.clipboard_html && clipboard,
+            secure_storage: self.secure_storage && (notifications || clipboard),
+            input_injection: self.input_injection && notifications,
+        }
+    }
+
+    /// 当前平台在指定运行模式下的可用功能，无桌面会话时禁用所有桌面相关能力。
+    ///
+    /// # Arguments
+    /// * `mode` - 运行模式
+    pub fn current(mode: RunMode) -> Self {
+        let platform = Self::for_platform(Platform::current()).with_enabled_features();
+        match mode {
+            RunMode::Desktop => platform,
+            RunMode::Headless | RunMode::Service => Self::NONE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_supports_everything() {
+        let windows = Capabilities::for_platform(Platform::Windows);
+        assert_eq!(
+            windows,
+            Capabilities {
+                tray: true,
+                notifications: true,
+                notification_actions: true,
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_files: true,
+                clipboard_html: true,
+                secure_storage: true,
+                input_injection: true,
+            }
+        );
+    }
+
+    #[test]
+    fn linux_has_no_file_clipboard_or_windows_apis() {
+        let linux = Capabilities::for_platform(Platform::Linux);
+        assert_eq!(
+            linux,
+            Capabilities {
+                tray: true,
+                notifications: true,
+                notification_actions: true,
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_html: true,
+                ..Capabilities::NONE
+            }
+        );
+    }
+
+    #[test]
+    fn macos_notifications_need_the_macos_feature() {
+        let macos = Capabilities::for_platform(Platform::MacOs);
+        assert_eq!(
+            macos,
+            Capabilities {
+                tray: true,
+                notifications: cfg!(feature = "macos"),
+                notification_actions: cfg!(feature = "macos"),
+                clipboard_text: true,
+                clipboard_image: true,
+                clipboard_html: true,
+                ..Capabilities::NONE
+            }
+        );
+    }
+
+    #[test]
+    fn other_platforms_support_nothing() {
+        assert_eq!(Capabilities::for_platform(Platform::Other), Capabilities::NONE);
+    }
+
+    #[test]
+    fn disabled_features_clear_their_flags() {
+        let notifications = cfg!(feature = "notifications");
+        let clipboard = cfg!(feature = "clipboard");
+        let all = Capabilities::for_platform(Platform::Windows).with_enabled_features();
+        let cases = [
+            ("tray", all.tray, cfg!(feature = "tray")),
+            ("notifications", all.notifications, notifications),
+            ("notification_actions", all.notification_actions, notifications),
+            ("clipboard_text", all.clipboard_text, clipboard),
+            ("clipboard_image", all.clipboard_image, clipboard),
+            ("clipboard_files", all.clipboard_files, clipboard),
+            ("clipboard_html", all.clipboard_html, clipboard),
+            ("secure_storage", all.secure_storage, notifications || clipboard),
+            ("input_injection", all.input_injection, notifications),
+        ];
+        for (name, actual, expected) in cases {
+            assert_eq!(actual, expected, "{}", name);
+        }
+
+        // 特性不会开启平台本身不支持的能力
+        assert_eq!(Capabilities::NONE.with_enabled_features(), Capabilities::NONE);
+        let linux = Capabilities::for_platform(Platform::Linux).with_enabled_features();
+        assert!(!linux.clipboard_files && !linux.secure_storage && !linux.input_injection);
+    }
+
+    #[test]
+    fn desktop_mode_uses_the_current_platform() {
+        let expected = Capabilities::for_platform(Platform::current()).with_enabled_features();
+        assert_eq!(Capabilities::current(RunMode::Desktop), expected);
+        assert_eq!(Capabilities::current(RunMode::Headless), Capabilities::NONE);
+    }
+
+    #[test]
+    fn service_mode_has_no_desktop_session() {
+        // 服务运行在会话 0，平台与特性支持的能力也不可用
+        assert_eq!(Capabilities::current(RunMode::Service), Capabilities::NONE);
+    }
+}
@@ -0,0 +1,426 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 命令行参数模块。
+ * 由 clap 解析参数并生成用法说明，未知参数、缺少取值或取值无效时由入口打印错误与用法并以退出码 2 结束，
+ * 不在启动中途 panic。设置类参数覆盖配置文件中的对应项。
+ */
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+use fastsync::{BindAddress, Config, MetadataStripping, UrgentKind};
+
+/// 服务控制管理器启动服务进程时传入的参数。
+pub const SERVICE_FLAG: &str = "--service";
+
+/// `service` 子命令的操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ServiceCommand {
+    Install,
+    Uninstall,
+    Start,
+    Stop,
+}
+
+/// 子命令。
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 安装、卸载、启动或停止 Windows 服务
+    Service {
+        #[arg(value_enum)]
+        command: ServiceCommand,
+    },
+}
+
+/// 入口要执行的操作。
+#[derive(Debug)]
+pub enum Invocation {
+    /// `service <操作>`：安装、卸载、启动或停止 Windows 服务
+    Service(ServiceCommand),
+    /// `--service`：由服务控制管理器启动，进入服务主函数
+    RunService,
+    /// 以桌面或无界面模式运行
+    Run(Box<Cli>),
+}
+
+impl Invocation {
+    /// 解析命令行参数，区分服务管理、服务进程与普通运行。
+    ///
+    /// # Arguments
+    /// * `args` - 包含程序名的参数
+    ///
+    /// # Returns
+    /// 要执行的操作，参数有误或指定 `--help` 时返回 clap 的错误，由入口打印并退出
+    pub fn try_parse_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let mut cli = Cli::try_parse_from(args)?;
+        Ok(match cli.command.take() {
+            Some(Command::Service { command }) => Invocation::Service(command),
+            None if cli.service => Invocation::RunService,
+            None => Invocation::Run(Box::new(cli)),
+        })
+    }
+}
+
+/// 解析后的命令行参数，未指定的项为 None 或 false。
+#[derive(Debug, Parser)]
+#[command(
+    name = "pc-receiver",
+    about = "FastSync 电脑接收端",
+    args_conflicts_with_subcommands = true,
+    disable_help_subcommand = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// 由服务控制管理器启动；服务以该参数为唯一启动参数安装，其他参数不适用于服务进程
+    #[arg(long = SERVICE_FLAG.trim_start_matches('-'), hide = true, exclusive = true)]
+    service: bool,
+    /// 配置文件，默认为 %APPDATA%\FastSync\config.toml
+    #[arg(long, value_name = "路径")]
+    pub config: Option<PathBuf>,
+    /// 无界面模式，不弹通知、不访问剪贴板
+    #[arg(long)]
+    pub headless: bool,
+    /// 桌面模式下不显示托盘图标
+    #[arg(long)]
+    pub no_tray: bool,
+    /// error / warn / info / debug / trace，默认 info
+    #[arg(long, value_name = "级别")]
+    pub log_level: Option<tracing::Level>,
+    /// 日志另写入该目录下按大小轮换的文件
+    #[arg(long, value_name = "目录")]
+    pub log_dir: Option<PathBuf>,
+    /// 开启日志文件，桌面模式默认开启
+    #[arg(long)]
+    pub log_file: bool,
+    /// 关闭日志文件
+    #[arg(long)]
+    pub no_log_file: bool,
+    #[command(flatten)]
+    overrides: Overrides,
+}
+
+/// 覆盖配置文件中对应项的参数。
+#[derive(Debug, Args)]
+#[command(next_help_heading = "以下参数覆盖配置文件中的对应项")]
+struct Overrides {
+    /// 监听端口，0 表示由系统分配
+    #[arg(long, value_name = "端口")]
+    port: Option<u16>,
+    /// 端口被占用时启动失败
+    #[arg(long)]
+    no_port_fallback: bool,
+    /// 监听地址，可以带端口，多个地址以逗号分隔
+    #[arg(long, value_name = "地址", value_delimiter = ',')]
+    bind: Option<Vec<BindAddress>>,
+    /// 不在局域网中广播服务
+    #[arg(long)]
+    no_mdns: bool,
+    /// 载荷接口只通过双向 TLS 提供
+    #[arg(long, value_name = "端口")]
+    mtls: Option<u16>,
+    /// 上传内容的大小上限
+    #[arg(long, value_name = "MB")]
+    max_upload_mb: Option<usize>,
+    /// JSON 请求的大小上限
+    #[arg(long, value_name = "MB")]
+    max_json_mb: Option<usize>,
+    /// 这些类型到达时唤醒显示器：call、sms_code，以逗号分隔
+    #[arg(long, value_name = "类型", value_delimiter = ',')]
+    wake_on: Option<Vec<UrgentKind>>,
+    /// 唤醒显示器时同时闪烁托盘图标
+    #[arg(long)]
+    flash_tray: bool,
+    /// 启动时即自动保存收到的图片
+    #[arg(long, value_name = "目录")]
+    auto_save_dir: Option<PathBuf>,
+    /// “快速保存”写入的目录
+    #[arg(long, value_name = "目录")]
+    quick_save_dir: Option<PathBuf>,
+    /// 连续上传的图片合并为一条通知的时间窗口
+    #[arg(long, value_name = "秒", value_parser = parse_seconds)]
+    burst_window: Option<Duration>,
+    /// 图片通知上显示“复制图片路径”按钮
+    #[arg(long)]
+    photo_path_actions: bool,
+    /// 不丢弃重复上传的图片
+    #[arg(long)]
+    allow_duplicates: bool,
+    /// 保存 JPEG 之前移除的元数据：off、gps 或 all
+    #[arg(long, value_name = "模式")]
+    strip_metadata: Option<MetadataStripping>,
+    /// 保存 HEIC 图片时转换为 JPEG
+    #[arg(long)]
+    heic_to_jpeg: bool,
+    /// 图片接口也接收其他类型的文件
+    #[arg(long)]
+    accept_files: bool,
+    /// 复制图片时同时解码的图片数
+    #[arg(long, value_name = "数量")]
+    decode_workers: Option<usize>,
+    /// 识别图片文字的语言
+    #[arg(long, value_name = "语言标签")]
+    ocr_lang: Option<String>,
+    /// 分块上传中断后保留已收到内容的时长
+    #[arg(long = "resume-window", value_name = "分钟")]
+    resume_window_mins: Option<u64>,
+    /// 临时图片的保留时长
+    #[arg(long = "temp-max-age", value_name = "小时")]
+    temp_max_age_hours: Option<u64>,
+    /// 验证码直接写入剪贴板
+    #[arg(long)]
+    auto_copy_codes: bool,
+    /// 短信历史最多保留的条数
+    #[arg(long, value_name = "条数")]
+    sms_history_rows: Option<usize>,
+    /// 短信历史最多保留的天数
+    #[arg(long, value_name = "天")]
+    sms_history_days: Option<u32>,
+    /// 同一短信重发时只通知一次的时长
+    #[arg(long, value_name = "秒")]
+    sms_dedup_seconds: Option<u64>,
+    /// 补发的短信超过该时长不再弹通知
+    #[arg(long, value_name = "分钟")]
+    sms_stale_minutes: Option<u64>,
+    /// 验证码通知的保留时长
+    #[arg(long, value_name = "秒")]
+    sms_code_expiry: Option<u64>,
+    /// 手机剪贴板直接写入剪贴板
+    #[arg(long)]
+    auto_apply_clipboard: bool,
+    /// 不丢弃复制时间较早的剪贴板推送
+    #[arg(long)]
+    ignore_clipboard_order: bool,
+    /// 在内存中保留的手机剪贴板条数
+    #[arg(long, value_name = "条数")]
+    clipboard_history: Option<usize>,
+    /// 文本超过该大小时提供“另存为文件”
+    #[arg(long, value_name = "KB")]
+    clipboard_file_kb: Option<usize>,
+    /// 隐藏疑似密码、令牌等敏感内容
+    #[arg(long)]
+    clipboard_secret_filter: bool,
+    /// 敏感内容复制后自动清空剪贴板的延迟
+    #[arg(long, value_name = "秒")]
+    clipboard_secret_clear: Option<u64>,
+}
+
+impl Cli {
+    /// 将设置类参数覆盖到配置上，未指定的项保持配置文件中的值。
+    ///
+    /// # Arguments
+    /// * `config` - 从配置文件载入的配置
+    pub fn apply(&self, config: &mut Config) {
+        let overrides = &self.overrides;
+        if let Some(port) = overrides.port {
+            config.server.port = port;
+        }
+        if overrides.no_port_fallback {
+            config.server.port_fallback = false;
+        }
+        if let Some(bind) = &overrides.bind {
+            config.server.bind = bind.clone();
+        }
+        if overrides.no_mdns {
+            config.server.mdns = false;
+        }
+        if let Some(port) = overrides.mtls {
+            config.server.mtls_port = Some(port);
+        }
+        if let Some(megabytes) = overrides.max_upload_mb {
+            config.server.max_upload = megabytes * 1024 * 1024;
+        }
+        if let Some(megabytes) = overrides.max_json_mb {
+            config.server.max_json = megabytes * 1024 * 1024;
+        }
+        if let Some(kinds) = &overrides.wake_on {
+            config.attention.wake_display = kinds.clone();
+        }
+        config.attention.flash_tray |= overrides.flash_tray;
+
+        if let Some(dir) = &overrides.auto_save_dir {
+            config.photos.auto_save_dir = Some(dir.clone());
+        }
+        if let Some(dir) = &overrides.quick_save_dir {
+            config.photos.quick_save_dir = Some(dir.clone());
+        }
+        if let Some(window) = overrides.burst_window {
+            config.photos.burst_window = window;
+        }
+        config.photos.path_actions |= overrides.photo_path_actions;
+        config.photos.deduplicate &= !overrides.allow_duplicates;
+        if let Some(mode) = overrides.strip_metadata {
+            config.photos.strip_metadata = mode;
+        }
+        config.photos.heic_to_jpeg |= overrides.heic_to_jpeg;
+        config.photos.accept_files |= overrides.accept_files;
+        if let Some(workers) = overrides.decode_workers {
+            config.photos.decode_workers = workers;
+        }
+        if let Some(language) = &overrides.ocr_lang {
+            config.photos.ocr_language = Some(language.clone());
+        }
+        if let Some(minutes) = overrides.resume_window_mins {
+            config.uploads.resume_window = Duration::from_secs(minutes * 60);
+        }
+        if let Some(hours) = overrides.temp_max_age_hours {
+            config.uploads.temp_max_age = Duration::from_secs(hours * 60 * 60);
+        }
+
+        config.sms.auto_copy_codes |= overrides.auto_copy_codes;
+        if let Some(rows) = overrides.sms_history_rows {
+            config.sms.history_rows = rows;
+        }
+        if let Some(days) = overrides.sms_history_days {
+            config.sms.history_days = days;
+        }
+        if let Some(seconds) = overrides.sms_dedup_seconds {
+            config.sms.dedup_window = Duration::from_secs(seconds);
+        }
+        if let Some(minutes) = overrides.sms_stale_minutes {
+            config.sms.stale_after = Duration::from_secs(minutes * 60);
+        }
+        if let Some(seconds) = overrides.sms_code_expiry {
+            config.sms.code_expiry = Duration::from_secs(seconds);
+        }
+
+        config.clipboard.auto_apply |= overrides.auto_apply_clipboard;
+        config.clipboard.ordering &= !overrides.ignore_clipboard_order;
+        if let Some(capacity) = overrides.clipboard_history {
+            config.clipboard.history = capacity;
+        }
+        if let Some(kilobytes) = overrides.clipboard_file_kb {
+            config.clipboard.file_threshold = kilobytes * 1024;
+        }
+        config.clipboard.secret_filter |= overrides.clipboard_secret_filter;
+        if let Some(seconds) = overrides.clipboard_secret_clear {
+            config.clipboard.secret_clear = Duration::from_secs(seconds);
+        }
+    }
+}
+
+/// 解析以秒为单位的时长，可以带小数。
+///
+/// # Arguments
+/// * `value` - 参数值
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("{}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    /// 以程序名开头解析参数。
+    fn parse<const N: usize>(args: [&str; N]) -> Result<Invocation, clap::Error> {
+        Invocation::try_parse_from(std::iter::once("pc-receiver").chain(args))
+    }
+
+    fn parse_cli<const N: usize>(args: [&str; N]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("pc-receiver").chain(args))
+    }
+
+    #[test]
+    fn service_commands_are_dispatched() {
+        let cases = [
+            ("install", ServiceCommand::Install),
+            ("uninstall", ServiceCommand::Uninstall),
+            ("start", ServiceCommand::Start),
+            ("stop", ServiceCommand::Stop),
+        ];
+        for (name, expected) in cases {
+            let invocation = parse(["service", name]).unwrap();
+            assert!(matches!(invocation, Invocation::Service(command) if command == expected), "{:?}", invocation);
+        }
+
+        assert_eq!(parse(["service", "restart"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert!(parse(["service"]).is_err());
+        assert!(parse(["service", "install", "--headless"]).is_err());
+        assert!(parse(["--headless", "service", "install"]).is_err());
+    }
+
+    #[test]
+    fn service_flag_starts_the_service_process() {
+        assert!(matches!(parse([SERVICE_FLAG]), Ok(Invocation::RunService)));
+        assert_eq!(parse(["--headless", SERVICE_FLAG]).unwrap_err().kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn other_arguments_run_the_receiver() {
+        let invocation = parse(["--headless", "--port", "4000"]).unwrap();
+        match invocation {
+            Invocation::Run(cli) => assert!(cli.headless && cli.overrides.port == Some(4000), "{:?}", cli),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(parse([]), Ok(Invocation::Run(_))));
+        // 普通参数中的 `service` 不是子命令
+        assert!(parse(["--config", "service"]).is_ok());
+        assert!(parse(["--prot", "3000"]).is_err());
+    }
+
+    #[test]
+    fn help_is_reported_as_an_error_for_the_entry_to_print() {
+        for flag in ["-h", "--help"] {
+            let error = parse([flag]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::DisplayHelp);
+            assert!(error.to_string().contains("--sms-history-rows"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn unknown_arguments_are_rejected() {
+        let error = parse_cli(["--headless", "--prot", "3000"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownArgument);
+        assert!(error.to_string().contains("--prot"), "{}", error);
+        assert!(parse_cli(["3000"]).is_err());
+    }
+
+    #[test]
+    fn missing_or_invalid_values_are_rejected() {
+        assert_eq!(parse_cli(["--port"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        let cases: [&[&str]; 5] = [
+            &["--port", "70000"],
+            &["--log-level", "loud"],
+            &["--bind", "0.0.0.0,nowhere"],
+            &["--burst-window", "-1"],
+            &["--strip-metadata", "some"],
+        ];
+        for args in cases {
+            let args = std::iter::once("pc-receiver").chain(args.iter().copied());
+            assert!(Cli::try_parse_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn arguments_override_config() {
+        let cli = parse_cli([
+            "--port", "4000",
+            "--no-mdns",
+            "--allow-duplicates",
+            "--sms-history-days", "7",
+            "--clipboard-file-kb", "2",
+            "--wake-on", "call,sms_code",
+            "--burst-window", "1.5",
+        ])
+        .unwrap();
+        let mut config = Config::default();
+        config.sms.history_rows = 10;
+        cli.apply(&mut config);
+        assert_eq!(config.server.port, 4000);
+        assert!(!config.server.mdns);
+        assert!(!config.photos.deduplicate);
+        assert_eq!(config.sms.history_days, 7);
+        assert_eq!(config.sms.history_rows, 10);
+        assert_eq!(config.clipboard.file_threshold, 2048);
+        assert_eq!(config.attention.wake_display, [UrgentKind::Call, UrgentKind::SmsCode]);
+        assert_eq!(config.photos.burst_window, Duration::from_millis(1500));
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 基于 arboard 的跨平台剪贴板后端。
+ */
+use std::borrow::Cow;
+use std::path::PathBuf;
+use super::{ClipboardBackend, ClipboardImage};
+
+/// 仅使用 arboard 的剪贴板后端。
+#[derive(Default)]
+pub struct ArboardClipboard;
+
+impl ClipboardBackend for ArboardClipboard {
+    fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        arboard::Clipboard::new()?.set_text(text.to_string())?;
+        Ok(())
+    }
+
+    fn set_image(&self, image: ClipboardImage) -> anyhow::Result<()> {
+        let image_data = arboard::ImageData {
+            width: image.width,
+            height: image.height,
+            bytes: Cow::Owned(image.rgba),
+        };
+        arboard::Clipboard::new()?.set_image(image_data)?;
+        Ok(())
+    }
+
+    fn set_files(&self, _paths: &[PathBuf]) -> anyhow::Result<()> {
+        anyhow::bail!("File lists are not supported by the arboard clipboard backend")
+    }
+
+    fn set_html(&self, html: &str, alt_text: &str) -> anyhow::Result<()> {
+        arboard::Clipboard::new()?.set_html(html, Some(alt_text))?;
+        Ok(())
+    }
+
+    fn get_text(&self) -> anyhow::Result<String> {
+        Ok(arboard::Clipboard::new()?.get_text()?)
+    }
+
+    #[cfg(windows)]
+    fn set_sensitive_text(&self, text: &str) -> anyhow::Result<()> {
+        use arboard::SetExtWindows;
+        // 不进入 Win+V 剪贴板历史，也不同步到其他设备
+        arboard::Clipboard::new()?.set().exclude_from_history().exclude_from_cloud().text(text)?;
+        Ok(())
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        arboard::Clipboard::new()?.clear()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 剪贴板历史模块。
+ * 在内存中保留最近收到的手机剪贴板文本，通知过期或被忽略后仍可通过 `GET /clipboard/history` 找回。
+ * 较长的文本在内存中只保留预览，全文写入临时目录下的 `fastsync_clipboard` 目录，记录淘汰时一并删除。
+ * 含有网址的记录附带 `link`，便于前端标记为链接。
+ * 历史不跨进程保留，启动时清空该目录中上次运行遗留的文件。
+ */
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// 默认保留的条数。
+pub const DEFAULT_CLIPBOARD_HISTORY: usize = 50;
+
+/// 超过该字节数的文本全文写入磁盘，内存中只保留预览。
+const INLINE_LIMIT: usize = 4 * 1024;
+
+/// 预览保留的字符数。
+const PREVIEW_CHARS: usize = 200;
+
+/// 一条剪贴板历史。
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ClipboardEntry {
+    pub id: u64,
+    /// 文本，`truncated` 时为开头部分的预览
+    pub text: String,
+    /// 是否只是预览，全文通过 `GET /clipboard/history/{id}` 读取
+    pub truncated: bool,
+    /// 全文的字符数
+    pub length: usize,
+    /// 在手机上复制的时间（毫秒时间戳），手机未提供时为到达时间
+    pub captured_at: i64,
+    /// 到达接收端的时间（毫秒时间戳）
+    pub received_at: i64,
+    /// 发送设备
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// 文本中第一个 http/https 网址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// 全文所在的文件
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+struct Entries {
+    next_id: u64,
+    items: VecDeque<ClipboardEntry>,
+}
+
+/// 最近收到的剪贴板文本。
+pub(crate) struct ClipboardHistory {
+    capacity: AtomicUsize,
+    dir: PathBuf,
+    entries: Mutex<Entries>,
+}
+
+impl ClipboardHistory {
+    /// # Arguments
+    /// * `capacity` - 保留的条数，为 0 时不记录
+    pub(crate) fn new(capacity: usize) -> Self {
+        let dir = std::env::temp_dir().join("fastsync_clipboard");
+        if capacity > 0 && dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                tracing::warn!("Failed to clear previous clipboard history files in {:?}: {}", dir, e);
+            }
+        }
+        Self {
+            capacity: AtomicUsize::new(capacity),
+            dir,
+            entries: Mutex::new(Entries {
+                next_id: 1,
+                items: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// 记录一条收到的文本，超过上限时删除最早的记录。较长的文本全文写入磁盘，会阻塞。
+    ///
+    /// # Arguments
+    /// * `text` - 剪贴板文本
+    /// * `captured_at` - 在手机上复制的时间（毫秒时间戳）
+    /// * `received_at` - 到达时间（毫秒时间戳）
+    /// * `device` - 发送设备
+    ///
+    /// # Returns
+    /// 记录的标识，未启用时为 None
+    pub(crate) fn record(&self, text: &str, captured_at: i64, received_at: i64, device: Option<String>) -> Option<u64> {
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        if capacity == 0 {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let id = entries.next_id;
+        entries.next_id += 1;
+
+        let mut entry = ClipboardEntry {
+            id,
+            text: text.to_string(),
+            truncated: false,
+            length: text.chars().count(),
+            captured_at,
+            received_at,
+            device,
+            link: super::find_url(text).map(str::to_string),
+            path: None,
+        };
+        if text.len() > INLINE_LIMIT {
+            let path = self.dir.join(format!("{}.txt", id));
+            let written = std::fs::create_dir_all(&self.dir).and_then(|()| std::fs::write(&path, text));
+            match written {
+                Ok(()) => {
+                    entry.text = text.chars().take(PREVIEW_CHARS).collect();
+                    entry.truncated = true;
+                    entry.path = Some(path);
+                }
+                // 写入失败时全文保留在内存中
+                Err(e) => tracing::warn!("Failed to write clipboard history entry to {:?}: {}", path, e),
+            }
+        }
+
+        entries.items.push_back(entry);
+        evict(&mut entries.items, capacity);
+        Some(id)
+    }
+
+    /// 修改保留的条数，超出的最早记录立即删除。
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        evict(&mut self.entries.lock().unwrap().items, capacity);
+    }
+
+    /// 按时间倒序返回记录，较长的文本只包含预览。
+    ///
+    /// # Arguments
+    /// * `limit` - 最多返回的条数
+    pub(crate) fn list(&self, limit: usize) -> Vec<ClipboardEntry> {
+        let entries = self.entries.lock().unwrap();
+        entries.items.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// 读取一条记录的全文，会阻塞。
+    ///
+    /// # Arguments
+    /// * `id` - 记录标识
+    ///
+    /// # Returns
+    /// 记录不存在或已被淘汰时为 None
+    pub(crate) fn get(&self, id: u64) -> Option<anyhow::Result<ClipboardEntry>> {
+        let mut entry = {
+            let entries = self.entries.lock().unwrap();
+            entries.items.iter().find(|entry| entry.id == id)?.clone()
+        };
+        if let Some(path) = entry.path.clone() {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => {
+                    entry.text = text;
+                    entry.truncated = false;
+                }
+                Err(e) => return Some(Err(anyhow::anyhow!("Failed to read {:?}: {}", path, e))),
+            }
+        }
+        Some(Ok(entry))
+    }
+}
+
+/// 删除超出保留条数的最早记录及其全文文件。
+fn evict(items: &mut VecDeque<ClipboardEntry>, capacity: usize) {
+    while items.len() > capacity {
+        if let Some(path) = items.pop_front().and_then(|evicted| evicted.path) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
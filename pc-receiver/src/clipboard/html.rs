@@ -0,0 +1,63 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 剪贴板 HTML 处理模块。
+ * 手机从网页复制的带格式文本以 HTML 发送，写入剪贴板前只保留正文片段，
+ * Windows 上由 arboard 加上 CF_HTML 所需的头部与偏移。手机未附带纯文本时从 HTML 中提取。
+ */
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// 换行的标签：`<br>` 与段落、列表项等块级元素的结束标签。
+static BREAKS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)<br\s*/?>|</(?:p|div|li|tr|h[1-6]|blockquote|pre)\s*>").expect("break pattern is valid")
+});
+
+/// 其余标签，以及脚本、样式与注释的全部内容。
+static TAGS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<script\b.*?</script\s*>|<style\b.*?</style\s*>|<!--.*?-->|<[^>]*>").expect("tag pattern is valid")
+});
+
+const START_FRAGMENT: &str = "<!--startfragment-->";
+const END_FRAGMENT: &str = "<!--endfragment-->";
+
+/// 取出要写入剪贴板的 HTML 片段。手机发来的可能是完整文档或已带片段标记的 CF_HTML 内容，
+/// 写入时外面还会再包一层 `<html><body>`，因此只保留标记之间或 `<body>` 之中的部分。
+///
+/// # Arguments
+/// * `html` - 手机发来的 HTML
+pub(crate) fn fragment(html: &str) -> &str {
+    // 只转换 ASCII 大小写，下标与原文一致
+    let lower = html.to_ascii_lowercase();
+    if let (Some(start), Some(end)) = (lower.find(START_FRAGMENT), lower.rfind(END_FRAGMENT)) {
+        if start + START_FRAGMENT.len() <= end {
+            return html[start + START_FRAGMENT.len()..end].trim();
+        }
+    }
+    if let Some(open) = lower.find("<body") {
+        if let Some(close) = lower[open..].find('>') {
+            let start = open + close + 1;
+            let end = lower.rfind("</body").filter(|&end| end >= start).unwrap_or(html.len());
+            return html[start..end].trim();
+        }
+    }
+    html.trim()
+}
+
+/// 从 HTML 中提取纯文本，作为粘贴到记事本等程序时的后备格式。
+///
+/// # Arguments
+/// * `html` - HTML 片段
+pub(crate) fn to_plain_text(html: &str) -> String {
+    let text = BREAKS.replace_all(html, "\n");
+    let text = TAGS.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n").trim().to_string()
+}
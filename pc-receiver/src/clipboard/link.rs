@@ -0,0 +1,61 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 剪贴板链接识别模块。
+ * 找出文本中第一个 http/https 网址，剪贴板通知据此提供“在浏览器打开”，剪贴板历史据此标记链接。
+ * 只识别 http 与 https，`javascript:`、`file:` 等其他协议以及主机名不合法的网址一律不算链接。
+ */
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// 网址候选：`http://` 或 `https://` 之后到空白、引号、尖括号或全角标点为止。
+/// 前面紧挨着字母数字时（如 `xhttp://`）不算。
+static CANDIDATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?:^|[^a-z0-9])(https?://[^\s<>"'`，。；！？、（）【】《》「」]+)"#).expect("url pattern is valid")
+});
+
+/// 合法的主机部分：域名（可含 Unicode）、IPv4 或方括号中的 IPv6，可带端口。
+static AUTHORITY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:\[[0-9a-f:.]+\]|[\p{L}\p{N}](?:[\p{L}\p{N}-]*[\p{L}\p{N}])?(?:\.[\p{L}\p{N}](?:[\p{L}\p{N}-]*[\p{L}\p{N}])?)*)(?::[0-9]{1,5})?$")
+        .expect("authority pattern is valid")
+});
+
+/// 网址末尾通常属于句子而不是网址的标点。
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', '\'', ')', ']', '}'];
+
+/// 找出文本中第一个可以在浏览器中打开的网址。
+///
+/// # Arguments
+/// * `text` - 剪贴板文本
+///
+/// # Returns
+/// 第一个 http/https 网址，没有时为 None
+pub(crate) fn find_url(text: &str) -> Option<&str> {
+    CANDIDATE
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1))
+        .map(|candidate| trim_trailing(candidate.as_str()))
+        .find(|candidate| is_valid(candidate))
+}
+
+/// 去掉末尾的句读，网址中配对的右括号保留（如维基百科的 `..._(消歧义)`）。
+fn trim_trailing(mut url: &str) -> &str {
+    while let Some(last) = url.chars().last().filter(|c| TRAILING.contains(c)) {
+        if last == ')' && url.matches('(').count() >= url.matches(')').count() {
+            break;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+    url
+}
+
+/// 检查网址的主机部分，排除 `http://`、`http://.com`、`http://user@evil` 之类的网址。
+fn is_valid(url: &str) -> bool {
+    let Some((_, rest)) = url.split_once("://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // 带用户名的网址常用于伪装真实主机，不提供打开
+    !authority.contains('@') && AUTHORITY.is_match(authority)
+}
@@ -0,0 +1,146 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 记录剪贴板操作的后端，用于测试与嵌入方检查写入的内容。
+ */
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use super::{ClipboardBackend, ClipboardImage};
+
+/// 一次剪贴板写入。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardWrite {
+    Text(String),
+    /// 图片的宽与高
+    Image { width: usize, height: usize },
+    Files(Vec<PathBuf>),
+    Html { html: String, alt_text: String },
+    SensitiveText(String),
+    Clear,
+}
+
+/// 不访问系统剪贴板，只记录每次写入的后端。读取时返回最近写入的文本，可模拟写入失败。
+#[derive(Default)]
+pub struct RecordingClipboard {
+    writes: Mutex<Vec<ClipboardWrite>>,
+    text: Mutex<Option<String>>,
+    /// 之后还需失败的写入次数
+    failures: AtomicUsize,
+}
+
+impl RecordingClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按时间顺序排列的所有写入。
+    pub fn writes(&self) -> Vec<ClipboardWrite> {
+        self.writes.lock().unwrap().clone()
+    }
+
+    /// 之后的 `times` 次写入返回错误，不记录写入，模拟剪贴板被其他程序占用。
+    pub fn fail_next(&self, times: usize) {
+        self.failures.store(times, Ordering::SeqCst);
+    }
+
+    /// 模拟用户在电脑上复制了文本，不记为写入。
+    pub fn set_current_text(&self, text: &str) {
+        *self.text.lock().unwrap() = Some(text.to_string());
+    }
+
+    fn record(&self, write: ClipboardWrite, text: Option<&str>) -> anyhow::Result<()> {
+        let failing = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+            .is_ok();
+        if failing {
+            anyhow::bail!("simulated clipboard failure: {:?}", write);
+        }
+        *self.text.lock().unwrap() = text.map(str::to_string);
+        self.writes.lock().unwrap().push(write);
+        Ok(())
+    }
+}
+
+impl ClipboardBackend for RecordingClipboard {
+    fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        self.record(ClipboardWrite::Text(text.to_string()), Some(text))
+    }
+
+    fn set_image(&self, image: ClipboardImage) -> anyhow::Result<()> {
+        self.record(ClipboardWrite::Image { width: image.width, height: image.height }, None)
+    }
+
+    fn set_files(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        self.record(ClipboardWrite::Files(paths.to_vec()), None)
+    }
+
+    fn supports_files(&self) -> bool {
+        true
+    }
+
+    fn set_html(&self, html: &str, alt_text: &str) -> anyhow::Result<()> {
+        let write = ClipboardWrite::Html { html: html.to_string(), alt_text: alt_text.to_string() };
+        self.record(write, Some(alt_text))
+    }
+
+    fn get_text(&self) -> anyhow::Result<String> {
+        self.text.lock().unwrap().clone().ok_or_else(|| anyhow::anyhow!("Clipboard does not contain text"))
+    }
+
+    fn set_sensitive_text(&self, text: &str) -> anyhow::Result<()> {
+        self.record(ClipboardWrite::SensitiveText(text.to_string()), Some(text))
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        self.record(ClipboardWrite::Clear, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_writes_in_order() {
+        let clipboard = RecordingClipboard::new();
+        assert!(clipboard.get_text().is_err());
+
+        clipboard.set_text("hello").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "hello");
+        clipboard.set_html("<b>hi</b>", "hi").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "hi");
+        clipboard.set_image(ClipboardImage { width: 2, height: 1, rgba: vec![0; 8] }).unwrap();
+        assert!(clipboard.get_text().is_err());
+        clipboard.clear().unwrap();
+
+        assert_eq!(
+            clipboard.writes(),
+            vec![
+                ClipboardWrite::Text("hello".to_string()),
+                ClipboardWrite::Html { html: "<b>hi</b>".to_string(), alt_text: "hi".to_string() },
+                ClipboardWrite::Image { width: 2, height: 1 },
+                ClipboardWrite::Clear,
+            ]
+        );
+    }
+
+    #[test]
+    fn simulates_failures() {
+        let clipboard = RecordingClipboard::new();
+        clipboard.fail_next(1);
+        assert!(clipboard.set_text("lost").is_err());
+        clipboard.set_text("kept").unwrap();
+        assert_eq!(clipboard.writes(), vec![ClipboardWrite::Text("kept".to_string())]);
+    }
+
+    #[test]
+    fn pc_copies_are_not_writes() {
+        let clipboard = RecordingClipboard::new();
+        clipboard.set_current_text("copied on the PC");
+        assert_eq!(clipboard.get_text().unwrap(), "copied on the PC");
+        assert!(clipboard.writes().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 剪贴板后端抽象模块。
+ * 处理器与工作线程统一通过 `ClipboardBackend` 访问系统剪贴板。
+ * `ClipboardWatch` 记录电脑剪贴板的变化，供手机端拉取；`ClipboardHistory` 保留最近收到的手机剪贴板，
+ * `find_url` 识别其中的网址，`html` 处理带格式的文本，`secret` 识别密码等敏感内容。
+ */
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "clipboard")]
+mod arboard_backend;
+mod history;
+pub(crate) mod html;
+mod link;
+mod mock;
+mod null;
+pub(crate) mod secret;
+mod watch;
+#[cfg(all(windows, feature = "clipboard"))]
+mod win32;
+
+#[cfg(feature = "clipboard")]
+pub use self::arboard_backend::ArboardClipboard;
+pub use self::history::DEFAULT_CLIPBOARD_HISTORY;
+pub(crate) use self::history::ClipboardHistory;
+pub(crate) use self::link::find_url;
+pub use self::mock::{ClipboardWrite, RecordingClipboard};
+pub use self::null::NullClipboard;
+pub(crate) use self::watch::{ClipboardSnapshot, ClipboardWatch, POLL_INTERVAL};
+#[cfg(all(windows, feature = "clipboard"))]
+pub use self::win32::WindowsClipboard;
+
+/// 已解码的 RGBA 图片数据。
+#[derive(Debug, Clone)]
+pub struct ClipboardImage {
+    pub width: usize,
+    pub height: usize,
+    /// 按行排列的 RGBA8 像素
+    pub rgba: Vec<u8>,
+}
+
+/// 剪贴板后端。
+pub trait ClipboardBackend: Send + Sync {
+    /// 写入纯文本。
+    fn set_text(&self, text: &str) -> anyhow::Result<()>;
+
+    /// 写入位图。
+    fn set_image(&self, image: ClipboardImage) -> anyhow::Result<()>;
+
+    /// 写入文件列表，粘贴到资源管理器或聊天软件时传输的是文件本身。
+    fn set_files(&self, paths: &[PathBuf]) -> anyhow::Result<()>;
+
+    /// 是否支持写入文件列表，不支持时通知中不提供"复制为文件"。
+    fn supports_files(&self) -> bool {
+        false
+    }
+
+    /// 写入 HTML，并附带纯文本作为后备格式。
+    fn set_html(&self, html: &str, alt_text: &str) -> anyhow::Result<()>;
+
+    /// 读取当前剪贴板中的文本。
+    fn get_text(&self) -> anyhow::Result<String>;
+
+    /// 写入密码等敏感文本。支持时不进入系统的剪贴板历史与云同步，默认与 `set_text` 相同。
+    fn set_sensitive_text(&self, text: &str) -> anyhow::Result<()> {
+        self.set_text(text)
+    }
+
+    /// 清空剪贴板，默认写入空文本。
+    fn clear(&self) -> anyhow::Result<()> {
+        self.set_text("")
+    }
+}
+
+/// 返回当前平台的默认剪贴板后端，未启用 `clipboard` 特性时为空实现。
+pub fn default_clipboard() -> Arc<dyn ClipboardBackend> {
+    #[cfg(all(windows, feature = "clipboard"))]
+    {
+        Arc::new(WindowsClipboard::new())
+    }
+    #[cfg(all(not(windows), feature = "clipboard"))]
+    {
+        Arc::new(ArboardClipboard)
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        Arc::new(NullClipboard)
+    }
+}
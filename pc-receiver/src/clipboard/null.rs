@@ -0,0 +1,33 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 空剪贴板后端，用于无桌面会话的运行环境。
+ */
+use std::path::PathBuf;
+use super::{ClipboardBackend, ClipboardImage};
+
+/// 拒绝所有剪贴板操作的后端。
+pub struct NullClipboard;
+
+impl ClipboardBackend for NullClipboard {
+    fn set_text(&self, _text: &str) -> anyhow::Result<()> {
+        anyhow::bail!("Clipboard is not available without a desktop session")
+    }
+
+    fn set_image(&self, _image: ClipboardImage) -> anyhow::Result<()> {
+        anyhow::bail!("Clipboard is not available without a desktop session")
+    }
+
+    fn set_files(&self, _paths: &[PathBuf]) -> anyhow::Result<()> {
+        anyhow::bail!("Clipboard is not available without a desktop session")
+    }
+
+    fn set_html(&self, _html: &str, _alt_text: &str) -> anyhow::Result<()> {
+        anyhow::bail!("Clipboard is not available without a desktop session")
+    }
+
+    fn get_text(&self) -> anyhow::Result<String> {
+        anyhow::bail!("Clipboard is not available without a desktop session")
+    }
+}
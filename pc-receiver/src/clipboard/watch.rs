@@ -0,0 +1,116 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 剪贴板变化监视模块。
+ * 桌面模式下定期读取电脑剪贴板中的文本，内容变化时递增序号，
+ * 手机端通过 `GET /clipboard?since=<序号>` 长轮询或经 WebSocket 连接接收推送，只在变化后收到新内容。
+ * 从手机同步到电脑的文本只更新比较基准，不计为变化，避免再传回手机。
+ */
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 读取剪贴板的间隔。
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 最近一次检测到的剪贴板文本。
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct ClipboardSnapshot {
+    /// 变化序号，每次变化加一，尚未读取到文本时为 0
+    pub seq: u64,
+    pub text: String,
+    /// 检测到变化的时间（毫秒时间戳）
+    pub timestamp: i64,
+}
+
+/// 电脑剪贴板的变化记录。
+pub(crate) struct ClipboardWatch {
+    latest: watch::Sender<ClipboardSnapshot>,
+    /// 剪贴板中当前的文本，包括从手机同步过来、不计为变化的文本
+    baseline: Mutex<Option<String>>,
+    /// 服务关闭时置位，结束等待中的长轮询
+    stopped: watch::Sender<bool>,
+}
+
+impl ClipboardWatch {
+    pub(crate) fn new() -> Self {
+        Self {
+            latest: watch::Sender::new(ClipboardSnapshot::default()),
+            baseline: Mutex::new(None),
+            stopped: watch::Sender::new(false),
+        }
+    }
+
+    /// 最近一次检测到的文本。
+    pub(crate) fn latest(&self) -> ClipboardSnapshot {
+        self.latest.borrow().clone()
+    }
+
+    /// 记录一次读取到的剪贴板文本。
+    ///
+    /// # Arguments
+    /// * `text` - 剪贴板中的文本
+    /// * `now` - 当前时间（毫秒时间戳）
+    ///
+    /// # Returns
+    /// 是否计为一次变化
+    pub(crate) fn observe(&self, text: String, now: i64) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        let Ok(mut baseline) = self.baseline.lock() else {
+            return false;
+        };
+        if baseline.as_deref() == Some(text.as_str()) {
+            return false;
+        }
+        *baseline = Some(text.clone());
+        self.latest.send_modify(|snapshot| {
+            snapshot.seq += 1;
+            snapshot.text = text;
+            snapshot.timestamp = now;
+        });
+        true
+    }
+
+    /// 将从手机同步过来的文本记为当前内容，之后读取到该文本时不计为变化。需在写入剪贴板之前调用。
+    ///
+    /// # Arguments
+    /// * `text` - 即将写入剪贴板的文本
+    pub(crate) fn remember(&self, text: &str) {
+        if let Ok(mut baseline) = self.baseline.lock() {
+            *baseline = Some(text.to_string());
+        }
+    }
+
+    /// 等待序号与 `since` 不同的内容。`since` 来自上次运行、大于当前序号时立即返回。
+    ///
+    /// # Arguments
+    /// * `since` - 手机端已收到的序号
+    /// * `timeout` - 最长等待时间
+    ///
+    /// # Returns
+    /// 最近一次检测到的文本，超时或服务关闭时序号可能仍等于 `since`
+    pub(crate) async fn wait_newer(&self, since: u64, timeout: Duration) -> ClipboardSnapshot {
+        let mut latest = self.latest.subscribe();
+        let mut stopped = self.stopped.subscribe();
+        tokio::select! {
+            _ = latest.wait_for(|snapshot| snapshot.seq != since) => {}
+            _ = stopped.wait_for(|stop| *stop) => {}
+            _ = tokio::time::sleep(timeout) => {}
+        }
+        self.latest()
+    }
+
+    /// 订阅之后的变化，用于向 WebSocket 连接推送。
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ClipboardSnapshot> {
+        self.latest.subscribe()
+    }
+
+    /// 结束所有等待中的长轮询。
+    pub(crate) fn stop(&self) {
+        self.stopped.send_replace(true);
+    }
+}
@@ -0,0 +1,217 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * Windows 剪贴板后端。
+ * 文本与 HTML 交给 arboard，图片与文件列表 (CF_HDROP) 直接调用 Win32 API。
+ * 图片同时写入 PNG、image/png、CF_DIBV5 与 CF_DIB：偏好 PNG 的程序拿到带透明通道的无损数据，
+ * 只认位图的旧程序仍能粘贴。
+ */
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+use std::os::windows::ffi::OsStrExt;
+use std::path::PathBuf;
+use windows::core::w;
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HWND},
+    System::{
+        DataExchange::{CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData},
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GHND},
+        Ole::{CF_DIB, CF_DIBV5, CF_HDROP},
+    },
+    UI::Shell::DROPFILES,
+};
+use super::{ArboardClipboard, ClipboardBackend, ClipboardImage};
+
+/// 组合 arboard 与 Win32 调用的剪贴板后端。
+#[derive(Default)]
+pub struct WindowsClipboard {
+    inner: ArboardClipboard,
+}
+
+impl WindowsClipboard {
+    pub fn new() -> Self {
+        Self { inner: ArboardClipboard }
+    }
+}
+
+impl ClipboardBackend for WindowsClipboard {
+    fn set_text(&self, text: &str) -> anyhow::Result<()> {
+        self.inner.set_text(text)
+    }
+
+    fn set_image(&self, image: ClipboardImage) -> anyhow::Result<()> {
+        set_image_formats(&image)
+    }
+
+    fn set_files(&self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        set_file_list(paths)
+    }
+
+    fn supports_files(&self) -> bool {
+        true
+    }
+
+    fn set_html(&self, html: &str, alt_text: &str) -> anyhow::Result<()> {
+        self.inner.set_html(html, alt_text)
+    }
+
+    fn get_text(&self) -> anyhow::Result<String> {
+        self.inner.get_text()
+    }
+
+    fn set_sensitive_text(&self, text: &str) -> anyhow::Result<()> {
+        self.inner.set_sensitive_text(text)
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        self.inner.clear()
+    }
+}
+
+/// 以 CF_HDROP 格式将文件列表写入剪贴板。
+///
+/// # Arguments
+/// * `paths` - 文件绝对路径列表
+fn set_file_list(paths: &[PathBuf]) -> anyhow::Result<()> {
+    // DROPFILES 头之后紧跟以 NUL 分隔、双 NUL 结尾的 UTF-16 路径列表
+    let mut wide: Vec<u16> = Vec::new();
+    for path in paths {
+        wide.extend(path.as_os_str().encode_wide());
+        wide.push(0);
+    }
+    wide.push(0);
+
+    let header_size = std::mem::size_of::<DROPFILES>();
+    let header = DROPFILES {
+        pFiles: header_size as u32,
+        fWide: true.into(),
+        ..Default::default()
+    };
+    let mut data = Vec::with_capacity(header_size + wide.len() * 2);
+    // SAFETY: DROPFILES 是只含整数字段的 repr(C) 结构体
+    data.extend_from_slice(unsafe { std::slice::from_raw_parts(&header as *const DROPFILES as *const u8, header_size) });
+    data.extend(wide.iter().flat_map(|unit| unit.to_le_bytes()));
+
+    write_formats(&[(CF_HDROP.0 as u32, data)])
+}
+
+/// 将图片以多种格式写入剪贴板。程序通常取第一个认识的格式，带透明通道的 PNG 排在最前。
+///
+/// # Arguments
+/// * `image` - 已解码的 RGBA 图片
+fn set_image_formats(image: &ClipboardImage) -> anyhow::Result<()> {
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png).write_image(&image.rgba, image.width as u32, image.height as u32, ExtendedColorType::Rgba8)?;
+
+    // SAFETY: 参数为静态的宽字符串
+    let (png_format, mime_format) = unsafe { (RegisterClipboardFormatW(w!("PNG")), RegisterClipboardFormatW(w!("image/png"))) };
+    let mut formats = Vec::with_capacity(4);
+    if png_format != 0 {
+        formats.push((png_format, png.clone()));
+    }
+    if mime_format != 0 {
+        formats.push((mime_format, png));
+    }
+    formats.push((CF_DIBV5.0 as u32, dib_v5(image)));
+    formats.push((CF_DIB.0 as u32, dib(image)));
+    write_formats(&formats)
+}
+
+/// 32 位 CF_DIBV5：BGRA 像素，透明度不预乘，声明为 sRGB 色彩空间。
+fn dib_v5(image: &ClipboardImage) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 124;
+    const BI_BITFIELDS: u32 = 3;
+    const LCS_SRGB: u32 = 0x7352_4742;
+    const LCS_GM_IMAGES: u32 = 4;
+
+    let row = image.width * 4;
+    let mut data = Vec::with_capacity(HEADER_SIZE as usize + row * image.height);
+    data.extend(HEADER_SIZE.to_le_bytes());
+    data.extend((image.width as i32).to_le_bytes());
+    data.extend((image.height as i32).to_le_bytes());
+    data.extend(1u16.to_le_bytes());
+    data.extend(32u16.to_le_bytes());
+    data.extend(BI_BITFIELDS.to_le_bytes());
+    data.extend(((row * image.height) as u32).to_le_bytes());
+    // 分辨率、调色板
+    data.extend([0u8; 16]);
+    // 红、绿、蓝、透明通道的掩码
+    for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+        data.extend(mask.to_le_bytes());
+    }
+    data.extend(LCS_SRGB.to_le_bytes());
+    // 端点与伽马，sRGB 下不使用
+    data.extend([0u8; 48]);
+    data.extend(LCS_GM_IMAGES.to_le_bytes());
+    // 色彩配置文件与保留字段
+    data.extend([0u8; 12]);
+
+    // 位图自下而上存储
+    for line in image.rgba.chunks_exact(row).rev() {
+        for pixel in line.chunks_exact(4) {
+            data.extend([pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+    }
+    data
+}
+
+/// 24 位 CF_DIB，透明部分合成到白色背景上。旧程序忽略透明通道时不会出现黑底或颜色发灰。
+fn dib(image: &ClipboardImage) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 40;
+
+    // 每行按 4 字节对齐
+    let row = (image.width * 3 + 3) & !3;
+    let mut data = Vec::with_capacity(HEADER_SIZE as usize + row * image.height);
+    data.extend(HEADER_SIZE.to_le_bytes());
+    data.extend((image.width as i32).to_le_bytes());
+    data.extend((image.height as i32).to_le_bytes());
+    data.extend(1u16.to_le_bytes());
+    data.extend(24u16.to_le_bytes());
+    // BI_RGB
+    data.extend(0u32.to_le_bytes());
+    data.extend(((row * image.height) as u32).to_le_bytes());
+    data.extend([0u8; 16]);
+
+    let blend = |channel: u8, alpha: u8| ((channel as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8;
+    for line in image.rgba.chunks_exact(image.width * 4).rev() {
+        let start = data.len();
+        for pixel in line.chunks_exact(4) {
+            let alpha = pixel[3];
+            data.extend([blend(pixel[2], alpha), blend(pixel[1], alpha), blend(pixel[0], alpha)]);
+        }
+        data.resize(start + row, 0);
+    }
+    data
+}
+
+/// 清空剪贴板后依次写入各格式的数据。
+///
+/// # Arguments
+/// * `formats` - 剪贴板格式与对应的数据
+fn write_formats(formats: &[(u32, Vec<u8>)]) -> anyhow::Result<()> {
+    unsafe {
+        OpenClipboard(HWND::default())?;
+        let result = EmptyClipboard().map_err(anyhow::Error::from).and_then(|_| {
+            for (format, data) in formats {
+                let hglobal = GlobalAlloc(GHND, data.len())?;
+                let ptr = GlobalLock(hglobal) as *mut u8;
+                if ptr.is_null() {
+                    let _ = GlobalFree(hglobal);
+                    anyhow::bail!("GlobalLock failed");
+                }
+                std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+                let _ = GlobalUnlock(hglobal);
+
+                // 成功后内存归系统所有，失败时需自行释放
+                if let Err(e) = SetClipboardData(*format, HANDLE(hglobal.0)) {
+                    let _ = GlobalFree(hglobal);
+                    return Err(e.into());
+                }
+            }
+            Ok(())
+        });
+        let _ = CloseClipboard();
+        result
+    }
+}
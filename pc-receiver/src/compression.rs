@@ -0,0 +1,398 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 请求与响应压缩模块。
+ * 手机端可以用 `Content-Encoding: gzip`（或 `deflate`）发送 JSON 接口与上传的请求体，
+ * 长短信补发与大段剪贴板文字通常能压缩到十分之一。请求体在处理器读取时边接收边解压，
+ * 大文件上传不会整体读入内存；解压后的大小同样受请求体上限约束，超过上限立即停止解压并返回 413，
+ * 避免压缩炸弹；其他编码返回 415。
+ * 历史与统计接口长度已知的响应在客户端接受 gzip 时压缩后返回。
+ */
+use axum::{
+    body::{Body, BodyDataStream, Bytes, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use flate2::write::{GzDecoder, GzEncoder, ZlibDecoder};
+use flate2::Compression;
+use futures::StreamExt;
+use std::io::{self, Write};
+use crate::handlers::response::{UploadError, UploadResponse};
+
+/// 支持的请求体编码。
+pub const SUPPORTED_ENCODINGS: &[&str] = &["gzip", "deflate"];
+
+/// 小于该大小的响应不压缩，压缩后反而可能更大。
+const MIN_COMPRESS_SIZE: usize = 1024;
+
+/// 大于该大小或长度未知的响应不压缩，避免为压缩把整个响应读入内存。
+const MAX_COMPRESS_SIZE: usize = 8 * 1024 * 1024;
+
+/// 每次写入解压器的压缩数据大小。deflate 的压缩比最高约 1000:1，
+/// 每段解压出的数据不超过约 1 MB，上限检查不会被单个数据块越过太多。
+const INPUT_SLICE: usize = 1024;
+
+/// 请求体的解压器，解压结果暂存在内部缓冲区中，每写入一段就取走。
+enum Decoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    /// 按 `Content-Encoding` 创建解压器，不支持的编码为 None。
+    fn new(encoding: &str) -> Option<Self> {
+        match encoding {
+            "gzip" | "x-gzip" => Some(Self::Gzip(GzDecoder::new(Vec::new()))),
+            "deflate" => Some(Self::Deflate(ZlibDecoder::new(Vec::new()))),
+            _ => None,
+        }
+    }
+
+    /// 写入一段压缩数据，返回解压出的数据。
+    fn decode(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip(decoder) => {
+                decoder.write_all(data)?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+            Self::Deflate(decoder) => {
+                decoder.write_all(data)?;
+                Ok(std::mem::take(decoder.get_mut()))
+            }
+        }
+    }
+
+    /// 压缩数据已全部写入，返回剩余的解压数据，数据不完整时报错。
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Gzip(decoder) => decoder.finish(),
+            Self::Deflate(decoder) => decoder.finish(),
+        }
+    }
+}
+
+/// 边接收边解压的请求体。
+struct Decoding {
+    input: BodyDataStream,
+    /// 全部输入解压完毕、出错或超过上限后为 None
+    decoder: Option<Decoder>,
+    /// 当前输入块中尚未写入解压器的部分
+    pending: Bytes,
+    /// 已解压的字节数
+    decoded: usize,
+    limit: usize,
+}
+
+impl Decoding {
+    /// 取下一段解压后的数据，没有更多数据时返回 None。
+    /// 超过上限的那段数据照常返回，由读取方的大小限制识别为超限；之后停止解压并返回错误。
+    async fn next_chunk(&mut self) -> Option<io::Result<Bytes>> {
+        if self.decoded > self.limit {
+            return Some(Err(io::Error::other("decompressed body exceeds the limit")));
+        }
+        loop {
+            let decoder = self.decoder.as_mut()?;
+            if self.pending.is_empty() {
+                match self.input.next().await {
+                    Some(Ok(chunk)) => self.pending = chunk,
+                    Some(Err(e)) => {
+                        self.decoder = None;
+                        return Some(Err(io::Error::other(e)));
+                    }
+                    None => {
+                        let rest = self.decoder.take()?.finish();
+                        return match rest {
+                            Ok(rest) if rest.is_empty() => None,
+                            Ok(rest) => {
+                                self.decoded += rest.len();
+                                Some(Ok(Bytes::from(rest)))
+                            }
+                            Err(e) => Some(Err(e)),
+                        };
+                    }
+                }
+                continue;
+            }
+
+            let slice = self.pending.split_to(self.pending.len().min(INPUT_SLICE));
+            match decoder.decode(&slice) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => {
+                    self.decoded += output.len();
+                    if self.decoded > self.limit {
+                        self.decoder = None;
+                    }
+                    return Some(Ok(Bytes::from(output)));
+                }
+                Err(e) => {
+                    self.decoder = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// 解压带有 `Content-Encoding` 的请求体，之后的中间件与处理器读取的是边接收边解压的内容，
+/// 解压后的字节数超过上限时读取出错，由处理器按超过大小限制返回 413。
+/// 第一段数据在交给处理器之前解压，不是有效压缩数据的请求体在这里直接拒绝。
+/// 位于令牌校验之后，未通过校验的请求不消耗解压的开销。
+///
+/// # Arguments
+/// * `limit` - 该组接口的请求体上限，按解压后的大小计算
+pub(crate) async fn request_decompression(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let encoding = request
+        .headers()
+        .get(header::CONTENT_ENCODING)
+        .map(|value| value.to_str().unwrap_or_default().trim().to_ascii_lowercase());
+    let Some(encoding) = encoding.filter(|encoding| encoding != "identity") else {
+        return next.run(request).await;
+    };
+    let Some(decoder) = Decoder::new(&encoding) else {
+        tracing::warn!("Rejected {} with unsupported Content-Encoding {:?}", request.uri().path(), encoding);
+        let mut response = UploadResponse::rejected(StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_encoding")
+            .with_detail("encoding", &encoding)
+            .with_detail("supported", SUPPORTED_ENCODINGS)
+            .into_response();
+        response
+            .headers_mut()
+            .insert(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip, deflate"));
+        return response;
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let mut decoding = Decoding {
+        input: body.into_data_stream(),
+        decoder: Some(decoder),
+        pending: Bytes::new(),
+        decoded: 0,
+        limit,
+    };
+    let first = match decoding.next_chunk().await {
+        Some(Ok(chunk)) => Some(chunk),
+        Some(Err(e)) => {
+            tracing::warn!("Failed to decompress {} body of {}: {}", encoding, parts.uri.path(), e);
+            return UploadResponse::failure(UploadError::DecodeFailed)
+                .with_detail("message", format!("invalid {} body", encoding))
+                .into_response();
+        }
+        None => None,
+    };
+
+    let rest = futures::stream::unfold(decoding, |mut decoding| async move {
+        let chunk = decoding.next_chunk().await?;
+        Some((chunk, decoding))
+    });
+    let stream = futures::stream::iter(first.map(Ok)).chain(rest);
+    // 解压后的长度未知，处理器按流读取，提取器的大小限制按解压后的字节数计算，与未压缩的请求一样得到 413
+    parts.headers.remove(header::CONTENT_ENCODING);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    next.run(Request::from_parts(parts, Body::from_stream(stream))).await
+}
+
+/// 客户端接受 gzip 时压缩响应体。已编码、出错、过小、过大或长度未知的响应原样返回。
+pub(crate) async fn response_compression(request: Request, next: Next) -> Response {
+    let accepts_gzip = request
+        .headers()
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            // q=0 表示明确拒绝该编码
+            let refused = params.any(|param| param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0));
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        });
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    if !accepts_gzip || !response.status().is_success() || response.headers().contains_key(header::CONTENT_ENCODING) {
+        return response;
+    }
+
+    // 只压缩长度已知的响应，流式响应不读入内存
+    let length = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .or_else(|| response.body().size_hint().exact());
+    let Some(length) = length.filter(|length| (MIN_COMPRESS_SIZE as u64..=MAX_COMPRESS_SIZE as u64).contains(length)) else {
+        return response;
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, length as usize).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for compression: {:?}", e);
+            return UploadError::Internal.into_response();
+        }
+    };
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::fast());
+    match encoder.write_all(&body).and_then(|()| encoder.finish()) {
+        Ok(compressed) => {
+            parts
+                .headers
+                .insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+            parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+            Response::from_parts(parts, Body::from(compressed))
+        }
+        Err(e) => {
+            tracing::error!("Failed to compress response: {:?}", e);
+            Response::from_parts(parts, Body::from(body))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::DefaultBodyLimit, routing::post, Router};
+    use flate2::write::ZlibEncoder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const LIMIT: usize = 1024 * 1024;
+
+    /// 返回读到的字节数，读取失败时返回对应的状态码。
+    async fn echo(body: Result<Bytes, axum::extract::rejection::BytesRejection>) -> Result<String, StatusCode> {
+        body.map(|body| body.len().to_string()).map_err(|rejection| rejection.status())
+    }
+
+    /// 与正式路由一样，提取器与解压中间件使用同一上限。
+    fn router() -> Router {
+        Router::new()
+            .route("/echo", post(echo))
+            .route_layer(axum::middleware::from_fn_with_state(LIMIT, request_decompression))
+            .layer(DefaultBodyLimit::max(LIMIT))
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn send(encoding: &str, body: Body) -> (StatusCode, String) {
+        let request = Request::post("/echo").header(header::CONTENT_ENCODING, encoding).body(body).unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn decodes_supported_encodings() {
+        let data = "长短信补发".repeat(10_000).into_bytes();
+        let cases = [
+            ("gzip", gzip(&data)),
+            ("x-gzip", gzip(&data)),
+            ("GZIP", gzip(&data)),
+            ("deflate", deflate(&data)),
+            ("identity", data.clone()),
+        ];
+        for (encoding, body) in cases {
+            let (status, length) = send(encoding, Body::from(body)).await;
+            assert_eq!((status, length), (StatusCode::OK, data.len().to_string()), "{}", encoding);
+        }
+        let (status, length) = send("gzip", Body::from(gzip(b""))).await;
+        assert_eq!((status, length.as_str()), (StatusCode::OK, "0"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_and_corrupt_bodies() {
+        let (status, body) = send("br", Body::from("data")).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert!(body.contains("unsupported_encoding"), "{}", body);
+
+        // 不是 gzip 数据，在交给处理器之前拒绝
+        let (status, body) = send("gzip", Body::from("plain text")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("invalid gzip body"), "{}", body);
+
+        // 数据中途损坏，处理器读取请求体时出错
+        let mut corrupt = gzip(&rand::random::<[u8; 32]>().repeat(4096));
+        let middle = corrupt.len() / 2;
+        corrupt[middle..middle + 64].fill(0xff);
+        let (status, _) = send("gzip", Body::from(corrupt)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn stops_reading_once_the_decoded_size_exceeds_the_limit() {
+        // 64 MB 的零压缩后约 64 KB，分成 1 KB 的块发送并记录被读取的块数
+        let compressed = gzip(&vec![0u8; 64 * 1024 * 1024]);
+        let chunks: Vec<Bytes> = compressed.chunks(1024).map(Bytes::copy_from_slice).collect();
+        let total = chunks.len();
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let counter = pulled.clone();
+        let stream = futures::stream::iter(chunks).map(move |chunk| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, io::Error>(chunk)
+        });
+
+        let (status, _) = send("gzip", Body::from_stream(stream)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        let pulled = pulled.load(Ordering::SeqCst);
+        assert!(pulled < total / 8, "read {} of {} chunks", pulled, total);
+    }
+
+    /// 按路径返回不同大小与形式的响应体。
+    async fn respond(request: Request) -> Response {
+        match request.uri().path() {
+            "/small" => "x".repeat(100).into_response(),
+            "/large" => "历史记录".repeat(1000).into_response(),
+            "/huge" => "x".repeat(MAX_COMPRESS_SIZE + 1).into_response(),
+            _ => {
+                let chunks = (0..4).map(|_| Ok::<_, io::Error>(Bytes::from("x".repeat(4096))));
+                let stream = futures::stream::iter(chunks);
+                Body::from_stream(stream).into_response()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn compresses_only_known_lengths_within_bounds() {
+        let router = Router::new()
+            .fallback(respond)
+            .layer(axum::middleware::from_fn(response_compression));
+        let cases = [
+            ("/small", "gzip", false),
+            ("/large", "gzip", true),
+            ("/large", "gzip;q=0", false),
+            ("/large", "identity", false),
+            ("/huge", "gzip", false),
+            ("/stream", "gzip", false),
+        ];
+        for (path, accept, compressed) in cases {
+            let request = Request::get(path).header(header::ACCEPT_ENCODING, accept).body(Body::empty()).unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let encoding = response.headers().get(header::CONTENT_ENCODING).cloned();
+            assert_eq!(encoding.is_some(), compressed, "{} {}", path, accept);
+            assert_eq!(response.headers()[header::VARY], "accept-encoding");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            if compressed {
+                let mut decoder = GzDecoder::new(Vec::new());
+                decoder.write_all(&body).unwrap();
+                assert_eq!(decoder.finish().unwrap(), "历史记录".repeat(1000).into_bytes());
+            }
+        }
+    }
+}
@@ -0,0 +1,933 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 配置文件模块。
+ * 端口、请求体上限、请求频率、保存目录、自动复制验证码、各类时间窗口，以及内容策略、计划暂停、
+ * 短信过滤与验证码规则等设置都写在 `%APPDATA%\FastSync\config.toml`，
+ * 首次运行时写入一份全部注释掉的默认配置，也可以用 `--config` 指定其他文件。命令行参数优先于配置文件。
+ * 已配对设备、实际监听端口等运行中产生的状态另存为单独的文件，不写入配置。
+ * 运行中修改文件或从托盘重新载入时，除 `[server]` 中的监听地址、端口、TLS 与请求体上限外的设置都立即生效。
+ * 访问令牌在首次运行时生成并写入 `[server]`，从托盘重新生成时只改写这一行，其余内容与注释保持不变。
+ */
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use crate::admission::PipelineLimits;
+use crate::attention::AttentionConfig;
+use crate::body_limit::BodyLimits;
+use crate::cors::CorsConfig;
+use crate::metadata::MetadataStripping;
+use crate::policy::ContentPolicy;
+use crate::schedule::{PauseSchedule, PauseWindow};
+use crate::sms_code::{CodeExtractor, CodeRules};
+use crate::sms_filter::SmsFilter;
+use crate::sms_history::SmsRetention;
+
+/// 生成的访问令牌的随机字节数。
+const ACCESS_TOKEN_BYTES: usize = 32;
+
+/// 手动填写的访问令牌的最短长度。
+const MIN_ACCESS_TOKEN_LEN: usize = 16;
+
+/// 图片聚合窗口的上限，更长的窗口会让不相关的图片合并到同一条通知中。
+const MAX_BURST_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// 旧版本中单独存放的规则文件，以及其内容在配置文件中对应的位置。
+const LEGACY_FILES: [(&str, &str); 4] = [
+    ("policy.json", "[policy]"),
+    ("pause_schedule.json", "[[pause_schedule]]"),
+    ("sms_codes.json", "[sms.codes]"),
+    ("sms_filter.json", "[sms.filter]"),
+];
+
+/// 首次运行时写入的默认配置，各项均为默认值并被注释掉。
+const DEFAULT_CONFIG: &str = r#"# FastSync 配置文件
+# 去掉行首的 # 并修改数值即可生效，未写出的项使用默认值；命令行参数优先于本文件。
+# 保存后自动重新载入，也可以在托盘菜单中选择“重新载入配置”。
+# 标注“需重启”的项修改后要重新启动 FastSync 才会生效。
+
+[server]
+# 监听地址，0.0.0.0 表示所有 IPv4 网卡，[::] 同时接受 IPv4 与 IPv6，127.0.0.1 只允许本机访问。
+# 可以写多个地址并带上各自的端口，未写端口的地址使用 port；只监听回环地址时不进行局域网广播（需重启）
+# bind = "0.0.0.0"
+# bind = ["127.0.0.1:3000", "[::1]:3000", "100.64.0.1"]
+# 未写端口的监听地址使用的端口（需重启）
+# port = 3000
+# 端口被其他程序占用时依次改用其后的 10 个端口，仍被占用时由系统分配；实际端口记在 port.json 中，
+# 下次启动时优先使用，手机缓存的地址在重启后仍然可用。关闭后端口被占用时启动失败（需重启）
+# port_fallback = true
+# 监听端口使用 HTTPS，首次运行时在本目录生成自签名证书，手机端按二维码中的指纹校验证书。
+# 启用 tls 特性的版本默认开启（需重启）
+# https = true
+# 启用 HTTPS 时另开的明文 HTTP 端口，仅供无法升级的旧版手机端使用，该端口上的内容不加密（需重启）
+# http_port = 3001
+# 图片等上传内容的大小上限，单位 MB（需重启）
+# max_upload_mb = 200
+# 剪贴板、短信等 JSON 请求的大小上限，单位 MB（需重启）
+# max_json_mb = 10
+# 手机端需携带的访问令牌，首次运行时自动生成，可在托盘中查看或重新生成
+# token = "..."
+# 是否拒绝未携带令牌的请求，仅在旧版手机端无法升级时临时关闭
+# require_token = true
+# 上传、短信与剪贴板请求携带 Idempotency-Key（或 request_id）时，在该时长内以相同的键重试
+# 直接返回第一次的结果，不再重复通知，单位秒，0 表示不处理
+# idempotency_ttl_secs = 600
+# 在局域网中广播服务，手机端可以自动发现本机；关闭后手机需扫码或手动填写地址（需重启）
+# mdns = true
+# 在该端口上启用双向 TLS，载荷接口只在该端口上提供，配对时签发客户端证书（需重启）
+# mtls_port = 3443
+
+[rate_limit]
+# 每台设备（未携带设备令牌时按来源 IP）每分钟的请求数上限，超过后返回 429，0 表示不限制。
+# 短时间内可以突发到上限，之后按每分钟的额度平均恢复
+# 图片与视频上传，同步相册时每张图片一次请求
+# uploads_per_min = 120
+# 短信，批量补发算一次
+# sms_per_min = 30
+# 剪贴板推送
+# clipboard_per_min = 60
+
+[photos]
+# 图片通知中“快速保存”写入的目录，默认为图片目录下的 FastSync
+# quick_save_dir = 'D:\Pictures\FastSync'
+# 同一设备连续上传的图片合并为一条通知的时间窗口，单位秒，0 表示每张图片单独通知
+# burst_window_secs = 3
+# 启动时即自动保存收到的图片到该目录，托盘菜单可随时关闭
+# auto_save_dir = 'D:\Pictures\FastSync'
+# 在图片通知上显示“复制图片路径”与“打开临时文件”按钮
+# path_actions = false
+# 丢弃同一设备在 10 分钟内重复上传的相同图片
+# deduplicate = true
+# 保存收到的 JPEG 之前移除元数据：off 不处理，gps 只移除位置信息，all 移除整个 EXIF
+# strip_metadata = "off"
+# 保存 HEIC 图片时转换为 JPEG，需要带 heic 特性的版本
+# heic_to_jpeg = false
+# 通用文件模式：图片接口也接收无法识别为图片的文件，通知只提供另存
+# accept_files = false
+# 复制图片时同时解码的图片数
+# decode_workers = 2
+# 识别图片文字的语言，例如 zh-Hans-CN，需已安装对应的 Windows 语言包，默认跟随系统
+# ocr_language = "zh-Hans-CN"
+
+[uploads]
+# 分块上传中断后保留已收到内容的时长，单位分钟
+# resume_window_mins = 60
+# 临时图片的保留时长，超过后由定期清理删除，单位小时
+# temp_max_age_hours = 24
+
+[sms]
+# 收到带验证码的短信时直接写入剪贴板
+# auto_copy_codes = false
+# 验证码通知在通知中心保留的时长，单位秒
+# code_expiry_secs = 30
+# 补发的短信超过该时长不再弹通知，单位分钟，0 表示不限制
+# stale_minutes = 10
+# 同一短信在该时长内重发时只通知一次，单位秒，0 表示不去重
+# dedup_secs = 120
+# 短信历史最多保留的条数与天数，任一超出即删除最旧的记录
+# history_rows = 5000
+# history_days = 30
+
+[sms.codes]
+# 手机端未识别验证码时从原文中提取，以下规则追加在内置规则之后
+# 追加的关键词，验证码需靠近关键词
+# keywords = ["激活码"]
+# 追加的正则表达式，优先于内置规则；验证码取名为 code 的分组
+# patterns = ['口令[:：]\s*(?P<code>[A-Z0-9]{6})']
+
+[sms.filter]
+# block 时命中屏蔽规则的短信不弹通知；allow 时只有命中放行规则的短信弹通知
+# mode = "block"
+# 弹出方式：loud 全部弹出，quiet 全部静默，smart 只有带验证码的短信弹出
+# popup = "smart"
+# 带验证码的短信总是弹通知
+# always_show_codes = true
+# 被过滤的短信仍写入短信历史
+# record_suppressed = true
+
+[sms.filter.block]
+# sender_prefixes = ["1069"]
+# content = ["退订回T", "回TD退订"]
+
+[sms.filter.allow]
+# senders = ["95588"]
+
+[sms.filter.aliases]
+# 号码的显示名称，手机端未提供联系人名称时通知标题显示该名称
+# "95588" = "工商银行"
+
+[clipboard]
+# 收到手机剪贴板时直接写入剪贴板，不弹出确认通知
+# auto_apply = false
+# 在内存中保留的手机剪贴板条数，0 表示不保留
+# history = 50
+# 文本超过该大小时通知中提供“另存为文件”，单位 KB，0 表示不提供
+# file_threshold_kb = 10
+# 隐藏疑似密码、令牌、银行卡号等敏感内容
+# secret_filter = false
+# 敏感内容复制后自动清空剪贴板的延迟，单位秒，0 表示不清空
+# secret_clear_secs = 30
+# 丢弃复制时间早于同一设备上一次推送的内容；手机时钟不可靠时可以关闭
+# ordering = true
+
+[attention]
+# 这些类型到达时唤醒显示器：call 来电，sms_code 验证码短信
+# wake_display = ["call", "sms_code"]
+# 同时闪烁托盘图标
+# flash_tray = false
+
+[pipeline]
+# 处理中的请求与待显示的通知总数超过该值时，上传接口返回 503 与建议的重试时间
+# max_queue_depth = 32
+# 处理中的请求体总字节数上限
+# max_pending_bytes = 268435456
+
+[cors]
+# 允许从这些网页直接调用 JSON 接口，为空时只允许同源访问
+# allowed_origins = ["http://localhost:5173"]
+# 允许携带 Cookie 等凭据
+# allow_credentials = false
+
+[policy]
+# 内容策略，未配置的项不做限制。全局大小上限（字节）与禁止的类型
+# max_bytes = 209715200
+# denied_types = [".exe", ".apk"]
+
+# 按接口（photo、file、clipboard 等）配置允许的类型、大小上限与允许的操作
+# [policy.endpoints.photo]
+# allowed_types = ["image/*"]
+# max_bytes = 20971520
+# allowed_actions = ["save", "copy"]
+
+# 计划暂停：时段内拒绝接收任何内容，结束时间不晚于开始时间时跨越午夜，可以写多段
+# [[pause_schedule]]
+# days = ["mon", "tue", "wed", "thu", "fri"]
+# start = "09:00:00"
+# end = "12:00:00"
+"#;
+
+/// 配置文件内容，未写出的项使用默认值。
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub rate_limit: RateLimitConfig,
+    pub photos: PhotoConfig,
+    pub uploads: UploadConfig,
+    pub sms: SmsConfig,
+    pub clipboard: ClipboardConfig,
+    pub attention: AttentionConfig,
+    pub pipeline: PipelineLimits,
+    pub cors: CorsConfig,
+    pub policy: ContentPolicy,
+    /// 计划暂停时段
+    pub pause_schedule: Vec<PauseWindow>,
+}
+
+/// `[server]`：监听地址、端口与请求体上限，修改后需重启。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// 监听地址，默认为所有 IPv4 网卡；文件中可以写一个地址或地址列表
+    #[serde(deserialize_with = "bind_addresses")]
+    pub bind: Vec<BindAddress>,
+    pub port: u16,
+    /// `port` 被占用时是否依次改用其后的端口，仍被占用时由系统分配
+    pub port_fallback: bool,
+    /// 监听端口是否使用 HTTPS（自签名证书），需要 `tls` 特性
+    pub https: bool,
+    /// 启用 HTTPS 时另开的明文 HTTP 端口，None 时不开启
+    pub http_port: Option<u16>,
+    /// 图片上传等载荷接口的上限（字节），文件中以 MB 填写
+    #[serde(rename = "max_upload_mb", deserialize_with = "megabytes")]
+    pub max_upload: usize,
+    /// 剪贴板、短信等 JSON 接口的上限（字节），文件中以 MB 填写
+    #[serde(rename = "max_json_mb", deserialize_with = "megabytes")]
+    pub max_json: usize,
+    /// 所有数据接口共用的访问令牌，None 时只接受配对设备的令牌
+    pub token: Option<AccessToken>,
+    /// 是否拒绝未携带令牌的请求
+    pub require_token: bool,
+    /// 幂等键的记录有效期，为 0 时不处理幂等键
+    #[serde(rename = "idempotency_ttl_secs", deserialize_with = "seconds")]
+    pub idempotency_ttl: Duration,
+    /// 是否通过 mDNS 广播服务，需要 `mdns` 特性
+    pub mdns: bool,
+    /// 启用双向 TLS 的端口，None 时不启用，需要 `tls` 特性
+    pub mtls_port: Option<u16>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        let limits = BodyLimits::default();
+        Self {
+            bind: vec![BindAddress::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED))],
+            port: crate::server::DEFAULT_PORT,
+            port_fallback: true,
+            https: cfg!(feature = "tls"),
+            http_port: None,
+            max_upload: limits.upload,
+            max_json: limits.json,
+            token: None,
+            require_token: true,
+            idempotency_ttl: crate::idempotency::DEFAULT_IDEMPOTENCY_TTL,
+            mdns: cfg!(feature = "mdns"),
+            mtls_port: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 配置的监听地址，未写端口的地址使用 `port`，重复的地址只保留一个。
+    /// 端口被占用而改用其他端口时，实际地址以 `FastSyncServer::local_addrs` 为准。
+    pub fn listen_addrs(&self) -> Vec<SocketAddr> {
+        crate::listen::resolve(&self.bind, self.port)
+    }
+
+    /// 请求体上限。
+    pub fn body_limits(&self) -> BodyLimits {
+        BodyLimits {
+            upload: self.max_upload,
+            json: self.max_json,
+        }
+    }
+}
+
+/// 一个监听地址，可以只写 IP，也可以带上端口，例如 `0.0.0.0`、`[::]`、`127.0.0.1:3000`、`[::1]:3000`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindAddress {
+    pub ip: IpAddr,
+    /// 未写端口时为 None，使用 `[server]` 中的 `port`
+    pub port: Option<u16>,
+}
+
+impl BindAddress {
+    /// 使用默认端口的地址。
+    pub fn new(ip: IpAddr) -> Self {
+        Self { ip, port: None }
+    }
+
+    /// 补全端口后的监听地址。
+    ///
+    /// # Arguments
+    /// * `port` - 未写端口时使用的端口
+    pub fn with_default_port(&self, port: u16) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port.unwrap_or(port))
+    }
+}
+
+impl From<SocketAddr> for BindAddress {
+    fn from(addr: SocketAddr) -> Self {
+        Self {
+            ip: addr.ip(),
+            port: Some(addr.port()),
+        }
+    }
+}
+
+impl FromStr for BindAddress {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> anyhow::Result<Self> {
+        let text = text.trim();
+        if let Ok(addr) = text.parse::<SocketAddr>() {
+            return Ok(addr.into());
+        }
+        // IPv6 地址可以不带端口写在方括号中，例如 `[::]`
+        let ip = text.strip_prefix('[').and_then(|ip| ip.strip_suffix(']')).unwrap_or(text);
+        ip.parse()
+            .map(Self::new)
+            .map_err(|_| anyhow::anyhow!("invalid bind address {:?}, expected an IP such as 0.0.0.0 or [::] with an optional :port", text))
+    }
+}
+
+impl fmt::Display for BindAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.ip, self.port) {
+            (ip, Some(port)) => SocketAddr::new(ip, port).fmt(f),
+            (IpAddr::V6(ip), None) => write!(f, "[{}]", ip),
+            (IpAddr::V4(ip), None) => ip.fmt(f),
+        }
+    }
+}
+
+/// 访问令牌，调试输出中不显示内容。
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct AccessToken(String);
+
+impl AccessToken {
+    /// 生成新的随机令牌。
+    pub fn generate() -> Self {
+        Self(crate::pairing::random_token(ACCESS_TOKEN_BYTES))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 以固定耗时比较请求中的令牌。
+    pub fn matches(&self, token: &str) -> bool {
+        crate::pairing::constant_time_eq(self.0.as_bytes(), token.as_bytes())
+    }
+}
+
+impl From<String> for AccessToken {
+    fn from(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken(..)")
+    }
+}
+
+/// `[rate_limit]`：各类接口每台设备每分钟的请求数上限，为 0 时不限制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// 图片与视频上传，包括分块上传的开始
+    pub uploads_per_min: u32,
+    /// 单条与批量短信
+    pub sms_per_min: u32,
+    /// 剪贴板推送
+    pub clipboard_per_min: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            uploads_per_min: 120,
+            sms_per_min: 30,
+            clipboard_per_min: 60,
+        }
+    }
+}
+
+/// `[photos]`：图片的保存目录与通知聚合。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PhotoConfig {
+    /// 图片通知中“快速保存”写入的目录，None 时为图片目录下的 FastSync
+    pub quick_save_dir: Option<PathBuf>,
+    /// 同一设备连续上传的图片合并为一条通知的时间窗口，为 0 时每张图片单独通知
+    #[serde(rename = "burst_window_secs", deserialize_with = "fractional_seconds")]
+    pub burst_window: Duration,
+    /// 启动时即自动保存到该目录，None 时桌面模式使用保存对话框
+    pub auto_save_dir: Option<PathBuf>,
+    /// 图片通知上是否显示“复制图片路径”与“打开临时文件”按钮
+    pub path_actions: bool,
+    /// 是否丢弃同一设备短时间内重复上传的相同图片
+    pub deduplicate: bool,
+    /// 收到的 JPEG 落盘后清理哪些元数据
+    pub strip_metadata: MetadataStripping,
+    /// 保存 HEIC 图片时是否转换为 JPEG，需要 `heic` 特性
+    pub heic_to_jpeg: bool,
+    /// 图片接口是否接收无法识别为图片的文件（通用文件模式）
+    pub accept_files: bool,
+    /// 复制图片时同时解码的图片数
+    pub decode_workers: usize,
+    /// 文字识别语言的 BCP-47 标签，None 时跟随系统语言
+    pub ocr_language: Option<String>,
+}
+
+impl Default for PhotoConfig {
+    fn default() -> Self {
+        Self {
+            quick_save_dir: None,
+            burst_window: crate::burst::DEFAULT_BURST_WINDOW,
+            auto_save_dir: None,
+            path_actions: false,
+            deduplicate: true,
+            strip_metadata: MetadataStripping::Off,
+            heic_to_jpeg: false,
+            accept_files: false,
+            decode_workers: crate::decode_pool::DEFAULT_DECODE_WORKERS,
+            ocr_language: None,
+        }
+    }
+}
+
+impl PhotoConfig {
+    /// “快速保存”实际写入的目录。
+    pub fn quick_save_dir(&self) -> PathBuf {
+        self.quick_save_dir
+            .clone()
+            .unwrap_or_else(crate::handlers::photo::default_auto_save_dir)
+    }
+}
+
+/// `[uploads]`：分块上传与临时文件。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UploadConfig {
+    /// 分块上传中断后保留已收到内容的时长
+    #[serde(rename = "resume_window_mins", deserialize_with = "minutes")]
+    pub resume_window: Duration,
+    /// 临时图片的保留时长
+    #[serde(rename = "temp_max_age_hours", deserialize_with = "hours")]
+    pub temp_max_age: Duration,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            resume_window: crate::resumable::DEFAULT_RESUME_WINDOW,
+            temp_max_age: crate::temp_files::DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+/// `[sms]`：验证码与短信通知。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmsConfig {
+    /// 收到带验证码的短信时是否直接写入剪贴板
+    pub auto_copy_codes: bool,
+    /// 验证码通知在通知中心保留的时长
+    #[serde(rename = "code_expiry_secs", deserialize_with = "seconds")]
+    pub code_expiry: Duration,
+    /// 短信在手机上收到后超过该时长才到达时不弹通知，为 0 时不限制
+    #[serde(rename = "stale_minutes", deserialize_with = "minutes")]
+    pub stale_after: Duration,
+    /// 同一短信重发时只通知一次的时长，为 0 时不去重
+    #[serde(rename = "dedup_secs", deserialize_with = "seconds")]
+    pub dedup_window: Duration,
+    /// 短信历史最多保留的条数
+    pub history_rows: usize,
+    /// 短信历史最多保留的天数
+    pub history_days: u32,
+    /// 验证码识别的补充规则
+    pub codes: CodeRules,
+    /// 短信通知的过滤规则，重新载入后立即生效
+    pub filter: SmsFilter,
+}
+
+impl Default for SmsConfig {
+    fn default() -> Self {
+        let retention = SmsRetention::default();
+        Self {
+            auto_copy_codes: false,
+            code_expiry: crate::handlers::sms::DEFAULT_SMS_CODE_EXPIRY,
+            stale_after: crate::handlers::sms::DEFAULT_SMS_STALE_AFTER,
+            dedup_window: crate::handlers::sms::DEFAULT_SMS_DEDUP_WINDOW,
+            history_rows: retention.max_rows,
+            history_days: retention.max_age_days,
+            codes: CodeRules::default(),
+            filter: SmsFilter::default(),
+        }
+    }
+}
+
+impl SmsConfig {
+    /// 短信历史的保留上限。
+    pub fn retention(&self) -> SmsRetention {
+        SmsRetention {
+            max_rows: self.history_rows,
+            max_age_days: self.history_days,
+        }
+    }
+}
+
+/// `[clipboard]`：手机剪贴板的写入方式。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    /// 收到手机剪贴板时是否直接写入剪贴板
+    pub auto_apply: bool,
+    /// 在内存中保留的手机剪贴板条数
+    pub history: usize,
+    /// 超过该字节数的文本另存为临时文件，通知中提供“另存为文件”，为 0 时不提供；文件中以 KB 填写
+    #[serde(rename = "file_threshold_kb", deserialize_with = "kilobytes")]
+    pub file_threshold: usize,
+    /// 是否隐藏疑似密码、令牌、银行卡号等敏感内容
+    pub secret_filter: bool,
+    /// 敏感内容复制后自动清空剪贴板的延迟，为 0 时不清空
+    #[serde(rename = "secret_clear_secs", deserialize_with = "seconds")]
+    pub secret_clear: Duration,
+    /// 是否丢弃复制时间早于同一设备上一次推送的内容
+    pub ordering: bool,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            auto_apply: false,
+            history: crate::clipboard::DEFAULT_CLIPBOARD_HISTORY,
+            file_threshold: crate::handlers::clipboard::DEFAULT_CLIPBOARD_FILE_THRESHOLD,
+            secret_filter: false,
+            secret_clear: crate::handlers::clipboard::DEFAULT_CLIPBOARD_SECRET_CLEAR,
+            ordering: true,
+        }
+    }
+}
+
+impl Config {
+    /// 检查取值范围，类型与单位已在解析时检查。
+    pub fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.server.bind.is_empty(), "server.bind must list at least one address");
+        anyhow::ensure!(self.server.max_upload > 0, "server.max_upload_mb must be greater than 0");
+        anyhow::ensure!(self.server.max_json > 0, "server.max_json_mb must be greater than 0");
+        if let Some(token) = &self.server.token {
+            anyhow::ensure!(
+                token.as_str().len() >= MIN_ACCESS_TOKEN_LEN,
+                "server.token must be at least {} characters",
+                MIN_ACCESS_TOKEN_LEN
+            );
+            anyhow::ensure!(
+                token.as_str().bytes().all(|byte| byte.is_ascii_graphic()),
+                "server.token must only contain printable ASCII characters without spaces"
+            );
+        }
+        if let Some(dir) = &self.photos.quick_save_dir {
+            anyhow::ensure!(dir.is_absolute(), "photos.quick_save_dir must be an absolute path, got {:?}", dir);
+        }
+        anyhow::ensure!(
+            self.photos.burst_window <= MAX_BURST_WINDOW,
+            "photos.burst_window_secs must not exceed {} seconds",
+            MAX_BURST_WINDOW.as_secs()
+        );
+        if let Some(dir) = &self.photos.auto_save_dir {
+            anyhow::ensure!(dir.is_absolute(), "photos.auto_save_dir must be an absolute path, got {:?}", dir);
+        }
+        anyhow::ensure!(self.photos.decode_workers > 0, "photos.decode_workers must be greater than 0");
+        anyhow::ensure!(!self.sms.code_expiry.is_zero(), "sms.code_expiry_secs must be greater than 0");
+        CodeExtractor::new(self.sms.codes.clone()).context("Invalid sms.codes")?;
+        PauseSchedule::new(self.pause_schedule.clone()).context("Invalid pause_schedule")?;
+        self.cors.layer().context("Invalid cors")?;
+        Ok(())
+    }
+
+    /// 用正在运行的配置替换需重启才能生效的设置，即 `[server]` 中的监听地址、端口、TLS 与请求体上限。
+    /// 其余设置由 `AppState::apply_config` 替换到各组件中。
+    ///
+    /// # Arguments
+    /// * `running` - 启动时使用的配置
+    ///
+    /// # Returns
+    /// 文件中已修改但要重启才生效的项
+    pub fn keep_restart_settings(&mut self, running: &Config) -> Vec<&'static str> {
+        let mut pending = Vec::new();
+        if self.server.bind != running.server.bind {
+            pending.push("server.bind");
+        }
+        if self.server.port != running.server.port || self.server.port_fallback != running.server.port_fallback {
+            pending.push("server.port / port_fallback");
+        }
+        if self.server.https != running.server.https || self.server.http_port != running.server.http_port {
+            pending.push("server.https / http_port");
+        }
+        if self.server.body_limits() != running.server.body_limits() {
+            pending.push("server.max_upload_mb / max_json_mb");
+        }
+        if self.server.mdns != running.server.mdns || self.server.mtls_port != running.server.mtls_port {
+            pending.push("server.mdns / mtls_port");
+        }
+        // 令牌与幂等键有效期在每次请求时读取，修改后立即生效
+        self.server = ServerConfig {
+            token: self.server.token.take(),
+            require_token: self.server.require_token,
+            idempotency_ttl: self.server.idempotency_ttl,
+            ..running.server.clone()
+        };
+        pending
+    }
+}
+
+impl Config {
+    /// 默认配置文件路径，位于漫游配置目录下的 `FastSync/config.toml`（Windows 上为 `%APPDATA%`）。
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("FastSync").join("config.toml"))
+    }
+
+    /// 读取配置文件，文件不存在时先写入带注释的默认配置，尚无访问令牌时生成一个写入文件。
+    ///
+    /// # Arguments
+    /// * `path` - 配置文件路径
+    pub fn load_or_create(path: &Path) -> anyhow::Result<Config> {
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).with_context(|| format!("Failed to create config directory {:?}", dir))?;
+            }
+            std::fs::write(path, DEFAULT_CONFIG).with_context(|| format!("Failed to write default config {:?}", path))?;
+            tracing::info!("Wrote default config to {:?}", path);
+        }
+        warn_legacy_files(path);
+        let mut config = load_config(path)?;
+        if config.server.token.is_none() {
+            let token = AccessToken::generate();
+            store_token(path, &token)?;
+            tracing::info!("Generated access token in {:?}", path);
+            config.server.token = Some(token);
+        }
+        Ok(config)
+    }
+}
+
+/// 从 TOML 文件读取配置并检查取值范围。
+///
+/// # Arguments
+/// * `path` - 配置文件路径
+pub fn load_config(path: &Path) -> anyhow::Result<Config> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read config {:?}", path))?;
+    let config: Config = toml::from_str(&text).with_context(|| format!("Invalid config {:?}", path))?;
+    config.validate().with_context(|| format!("Invalid config {:?}", path))?;
+    Ok(config)
+}
+
+/// 旧版本单独存放的规则文件已不再读取，提示用户将内容移入配置文件。
+fn warn_legacy_files(config_path: &Path) {
+    let Some(dir) = dirs::data_local_dir().map(|dir| dir.join("FastSync")) else {
+        return;
+    };
+    for (name, section) in LEGACY_FILES {
+        let legacy = dir.join(name);
+        if legacy.exists() {
+            tracing::warn!("{:?} is no longer read, move its settings into {} in {:?}", legacy, section, config_path);
+        }
+    }
+}
+
+/// 将访问令牌写入配置文件的 `[server]` 段，替换已有的 `token` 行，其余内容保持不变。
+///
+/// # Arguments
+/// * `path` - 配置文件路径
+/// * `token` - 要写入的令牌
+pub fn store_token(path: &Path, token: &AccessToken) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read config {:?}", path))?;
+    let line = format!("token = \"{}\"", token.as_str());
+
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let server = lines.iter().position(|line| line.split('#').next().unwrap_or_default().trim() == "[server]");
+    let section_end = |start: usize| {
+        lines[start + 1..]
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .map_or(lines.len(), |offset| start + 1 + offset)
+    };
+    match server {
+        Some(start) => {
+            let end = section_end(start);
+            let existing = lines[start + 1..end].iter().position(|line| {
+                let key = line.split('=').next().unwrap_or_default().trim();
+                key == "token"
+            });
+            match existing {
+                Some(offset) => lines[start + 1 + offset] = line,
+                None => lines.insert(start + 1, line),
+            }
+        }
+        None => {
+            lines.insert(0, String::new());
+            lines.insert(0, line);
+            lines.insert(0, "[server]".to_string());
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    toml::from_str::<Config>(&updated).with_context(|| format!("Failed to update token in {:?}", path))?;
+    std::fs::write(path, updated).with_context(|| format!("Failed to write config {:?}", path))
+}
+
+/// 运行中监视的配置文件，按修改时间判断是否需要重新载入。
+pub(crate) struct ConfigFile {
+    path: PathBuf,
+    /// 上次载入时文件的修改时间
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl ConfigFile {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Self {
+            path,
+            modified: Mutex::new(modified),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 文件在上次载入后是否被修改。
+    pub(crate) fn changed(&self) -> bool {
+        *self.modified.lock().unwrap() != modified_time(&self.path)
+    }
+
+    /// 读取文件并记下修改时间，文件有误时在再次修改前不再自动重试。
+    pub(crate) fn load(&self) -> anyhow::Result<Config> {
+        *self.modified.lock().unwrap() = modified_time(&self.path);
+        load_config(&self.path)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// `bind` 可以写成一个地址或地址列表。
+fn bind_addresses<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<BindAddress>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    let addrs = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(addr) => vec![addr],
+        OneOrMany::Many(addrs) => addrs,
+    };
+    addrs
+        .iter()
+        .map(|addr| addr.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+fn seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+fn fractional_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let seconds = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(seconds).map_err(serde::de::Error::custom)
+}
+
+fn minutes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let minutes = u64::deserialize(deserializer)?;
+    minutes
+        .checked_mul(60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| serde::de::Error::custom("value is too large"))
+}
+
+fn hours<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let hours = u64::deserialize(deserializer)?;
+    hours
+        .checked_mul(60 * 60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| serde::de::Error::custom("value is too large"))
+}
+
+fn kilobytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let kilobytes = usize::deserialize(deserializer)?;
+    kilobytes
+        .checked_mul(1024)
+        .ok_or_else(|| serde::de::Error::custom("value is too large"))
+}
+
+fn megabytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let megabytes = usize::deserialize(deserializer)?;
+    megabytes
+        .checked_mul(1024 * 1024)
+        .ok_or_else(|| serde::de::Error::custom("value is too large"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 合并进配置文件的规则段：内容策略、计划暂停、短信过滤与验证码规则。
+    const RULES: &str = r#"
+[sms.codes]
+keywords = ["激活码"]
+patterns = ['口令[:：]\s*(?P<code>[A-Z0-9]{6})']
+
+[sms.filter]
+mode = "allow"
+
+[sms.filter.allow]
+senders = ["95588"]
+
+[sms.filter.aliases]
+"95588" = "工商银行"
+
+[policy]
+denied_types = [".exe"]
+
+[policy.endpoints.photo]
+allowed_types = ["image/*"]
+max_bytes = 1024
+
+[[pause_schedule]]
+days = ["fri"]
+start = "22:00:00"
+end = "06:00:00"
+"#;
+
+    #[test]
+    fn default_template_uses_default_values() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn rule_sections_are_read_from_config() {
+        let config: Config = toml::from_str(RULES).unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.sms.codes.keywords, ["激活码"]);
+        assert_eq!(config.sms.filter.alias("95588"), Some("工商银行"));
+        assert_eq!(config.policy.denied_types, [".exe"]);
+        assert_eq!(config.policy.endpoints["photo"].max_bytes, Some(1024));
+        assert_eq!(config.pause_schedule.len(), 1);
+        assert_eq!(config.pause_schedule[0].days, [chrono::Weekday::Fri]);
+    }
+
+    #[test]
+    fn invalid_rules_fail_validation() {
+        let config: Config = toml::from_str("[sms.codes]\npatterns = ['(']\n").unwrap();
+        assert!(config.validate().is_err());
+        assert!(toml::from_str::<Config>("[policy]\nmax_size = 1\n").is_err());
+        assert!(toml::from_str::<Config>("[[pause_schedule]]\ndays = [\"fri\"]\nstart = \"25:00:00\"\nend = \"06:00:00\"\n").is_err());
+    }
+
+    #[test]
+    fn cors_origins_are_read_and_validated() {
+        let config: Config = toml::from_str("[cors]\nallowed_origins = [\"http://localhost:5173\"]\n").unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.cors.allowed_origins, ["http://localhost:5173"]);
+        assert!(!config.cors.allow_credentials);
+
+        let invalid = [
+            "[cors]\nallowed_origins = [\"localhost:5173\"]\n",
+            "[cors]\nallowed_origins = [\"http://localhost:5173/app\"]\n",
+            "[cors]\nallowed_origins = [\"*\", \"http://localhost:5173\"]\n",
+            "[cors]\nallowed_origins = [\"*\"]\nallow_credentials = true\n",
+        ];
+        for text in invalid {
+            let config: Config = toml::from_str(text).unwrap();
+            assert!(config.validate().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn only_server_settings_need_a_restart() {
+        let running = Config::default();
+        let mut reloaded: Config = toml::from_str(RULES).unwrap();
+        reloaded.server.port = 4000;
+        reloaded.server.max_upload = 1024;
+        reloaded.server.idempotency_ttl = Duration::from_secs(60);
+        reloaded.sms.dedup_window = Duration::from_secs(5);
+        reloaded.clipboard.history = 3;
+        let pending = reloaded.keep_restart_settings(&running);
+        assert_eq!(pending, ["server.port / port_fallback", "server.max_upload_mb / max_json_mb"]);
+        assert_eq!(reloaded.server.port, running.server.port);
+        assert_eq!(reloaded.server.max_upload, running.server.max_upload);
+        // 其余设置按文件中的值应用
+        assert_eq!(reloaded.server.idempotency_ttl, Duration::from_secs(60));
+        assert_eq!(reloaded.policy.denied_types, [".exe"]);
+        assert_eq!(reloaded.pause_schedule.len(), 1);
+        assert_eq!(reloaded.sms.codes.keywords, ["激活码"]);
+        assert_eq!(reloaded.sms.dedup_window, Duration::from_secs(5));
+        assert_eq!(reloaded.clipboard.history, 3);
+        assert_eq!(reloaded.sms.filter.alias("95588"), Some("工商银行"));
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 跨域配置模块。
+ * 默认只允许同源访问；配置允许的来源后，本地网页工具可以直接从浏览器调用 JSON 接口。
+ * CORS 层保存在共享状态中，每个请求使用当时的设置，配置重新载入后无需重建路由。
+ */
+use anyhow::{bail, Context};
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use tower::{Layer, ServiceExt};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use crate::state::AppState;
+
+/// 暴露给网页脚本的响应头。
+const EXPOSED_HEADERS: [&str; 1] = ["x-request-id"];
+
+/// 跨域配置。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// 允许的来源，例如 `http://localhost:5173`；为空时只允许同源访问
+    pub allowed_origins: Vec<String>,
+    /// 是否允许携带凭据（Cookie 等），开启时不能使用 `*`
+    pub allow_credentials: bool,
+}
+
+impl CorsConfig {
+    /// 校验配置并构造 CORS 层，未配置来源时返回 None。
+    ///
+    /// # Returns
+    /// 配置有误时返回错误，调用方应按同源处理
+    pub fn layer(&self) -> anyhow::Result<Option<CorsLayer>> {
+        if self.allowed_origins.is_empty() {
+            return Ok(None);
+        }
+
+        let wildcard = self.allowed_origins.iter().any(|origin| origin.trim() == "*");
+        let origin = if wildcard {
+            if self.allowed_origins.len() > 1 {
+                bail!("cors.allowed_origins cannot mix \"*\" with explicit origins");
+            }
+            if self.allow_credentials {
+                bail!("cors.allowed_origins cannot be \"*\" when credentials are allowed");
+            }
+            tracing::warn!("CORS allows any origin: any web page holding a device token can send to this PC");
+            AllowOrigin::any()
+        } else {
+            let origins = self
+                .allowed_origins
+                .iter()
+                .map(|origin| parse_origin(origin))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            AllowOrigin::list(origins)
+        };
+
+        // 携带 Authorization 的请求必然触发预检，需显式列出该请求头
+        let layer = CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+            .expose_headers(EXPOSED_HEADERS.map(HeaderName::from_static))
+            .allow_credentials(self.allow_credentials);
+        Ok(Some(layer))
+    }
+}
+
+/// 按正在使用的跨域设置处理请求，未配置来源时直接交给后续处理。
+pub(crate) async fn cors_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let cors = state.cors.read().unwrap().clone();
+    match cors {
+        Some(cors) => match cors.layer(next).oneshot(request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        },
+        None => next.run(request).await,
+    }
+}
+
+/// 解析并规范化来源，只接受 `scheme://host[:port]` 形式。
+fn parse_origin(origin: &str) -> anyhow::Result<HeaderValue> {
+    let origin = origin.trim().trim_end_matches('/');
+    let (scheme, host) = origin
+        .split_once("://")
+        .with_context(|| format!("Invalid CORS origin {:?}: missing scheme", origin))?;
+
+    if scheme != "http" && scheme != "https" {
+        bail!("Invalid CORS origin {:?}: scheme must be http or https", origin);
+    }
+    if host.is_empty() || host.contains(['/', '?', '#', '*']) {
+        bail!("Invalid CORS origin {:?}: expected scheme://host[:port]", origin);
+    }
+
+    HeaderValue::from_str(&origin.to_ascii_lowercase())
+        .with_context(|| format!("Invalid CORS origin {:?}", origin))
+}
@@ -0,0 +1,119 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 图片解码线程池模块。
+ * 复制图片需要解码整张图片，连续点击多条通知的"复制"时，解码任务排队交给固定数量的工作线程，
+ * 按点击顺序依次执行，不会同时解码多张大图占满内存与 CPU。工作线程按需创建，空闲一段时间后退出。
+ */
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// 默认的工作线程数。
+pub const DEFAULT_DECODE_WORKERS: usize = 2;
+
+/// 工作线程空闲超过该时间后退出。
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 排队中的解码任务。
+type Job = Box<dyn FnOnce() + Send>;
+
+/// 任务队列与工作线程计数。
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// 已创建的工作线程数
+    workers: usize,
+    /// 正在等待任务的工作线程数
+    idle: usize,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+/// 固定并发数的解码线程池，由所有图片通知共享。
+pub(crate) struct DecodePool {
+    max_workers: AtomicUsize,
+    shared: Arc<Shared>,
+}
+
+impl DecodePool {
+    /// # Arguments
+    /// * `max_workers` - 同时执行的解码任务数，至少为 1
+    pub(crate) fn new(max_workers: usize) -> Self {
+        Self {
+            max_workers: AtomicUsize::new(max_workers.max(1)),
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue::default()),
+                ready: Condvar::new(),
+            }),
+        }
+    }
+
+    /// 修改同时执行的解码任务数。调低后多出的工作线程在空闲超时后退出。
+    pub(crate) fn set_max_workers(&self, max_workers: usize) {
+        self.max_workers.store(max_workers.max(1), Ordering::Relaxed);
+    }
+
+    /// 提交解码任务，按提交顺序执行。没有空闲线程且未达到上限时创建新的工作线程。
+    ///
+    /// # Arguments
+    /// * `job` - 解码任务
+    pub(crate) fn submit(&self, job: impl FnOnce() + Send + 'static) {
+        // 任务在工作线程中沿用提交时的 span，解码日志带有所属请求的 ID
+        let span = tracing::Span::current();
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.jobs.push_back(Box::new(move || span.in_scope(job)));
+
+        if queue.idle >= queue.jobs.len() {
+            self.shared.ready.notify_one();
+            return;
+        }
+        if queue.workers < self.max_workers.load(Ordering::Relaxed) {
+            queue.workers += 1;
+            let shared = self.shared.clone();
+            let spawned = std::thread::Builder::new()
+                .name("fastsync-decode".to_string())
+                .spawn(move || run_worker(&shared));
+            match spawned {
+                Ok(_) => return,
+                Err(e) => {
+                    queue.workers -= 1;
+                    tracing::error!("Failed to start decode worker: {:?}", e);
+                }
+            }
+        }
+        tracing::info!("Image decode queue depth {} ({} workers busy)", queue.jobs.len(), queue.workers);
+    }
+}
+
+/// 工作线程主循环，依次取出任务执行，空闲超时后退出。
+fn run_worker(shared: &Shared) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue.idle += 1;
+                let (next, timeout) = shared.ready.wait_timeout(queue, IDLE_TIMEOUT).unwrap();
+                queue = next;
+                queue.idle -= 1;
+                if timeout.timed_out() && queue.jobs.is_empty() {
+                    queue.workers -= 1;
+                    return;
+                }
+            }
+        };
+
+        // 单个任务崩溃时不影响后续任务
+        if std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).is_err() {
+            tracing::error!("Image decode job panicked");
+        }
+    }
+}
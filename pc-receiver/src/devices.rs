@@ -0,0 +1,261 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 已配对设备登记表。
+ * 记录每台设备的名称、配对时间、最近访问时间与单独的设置（如是否自动保存图片），
+ * 保存在配置目录下的 `devices.json`，文件与内存中都只保存设备令牌的摘要。
+ * 通知标题中的设备名称、`/devices` 接口与托盘中的设备菜单都从这里读取。
+ */
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::auth::AuthenticatedDevice;
+use crate::state::AppState;
+
+/// 最近访问时间写入文件的最小间隔，避免每个请求都写一次文件。
+const LAST_SEEN_SAVE_INTERVAL_MS: i64 = 5 * 60 * 1000;
+
+/// 已配对的设备。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    /// 设备标识，用于 `DELETE /devices/{id}`
+    pub id: String,
+    /// 手机上报的设备名称
+    pub name: String,
+    /// 配对时间（毫秒时间戳）
+    pub paired_at: i64,
+    /// 最近一次携带令牌访问的时间（毫秒时间戳），配对后尚未访问时为 None
+    #[serde(default)]
+    pub last_seen: Option<i64>,
+    /// 该设备单独的设置
+    #[serde(default)]
+    pub settings: DeviceSettings,
+}
+
+/// 设备单独的设置，未设置的项沿用全局设置。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    /// 是否自动保存该设备发来的图片
+    pub auto_save: Option<bool>,
+}
+
+/// `devices.json` 中的一条记录。
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredDevice {
+    /// 设备令牌的 SHA-256 摘要
+    token_hash: String,
+    #[serde(flatten)]
+    device: PairedDevice,
+}
+
+/// 已配对设备登记表。
+#[derive(Default)]
+pub(crate) struct DeviceRegistry {
+    /// 保存位置，None 时只保存在内存中
+    path: Option<PathBuf>,
+    inner: Mutex<RegistryInner>,
+}
+
+#[derive(Default)]
+struct RegistryInner {
+    /// 设备令牌摘要及对应设备
+    devices: HashMap<String, PairedDevice>,
+    /// 已解除配对的设备令牌摘要
+    revoked: HashSet<String>,
+}
+
+impl DeviceRegistry {
+    /// 载入已配对设备，文件不存在时从空白开始，文件损坏时记录错误后从空白开始。
+    ///
+    /// # Arguments
+    /// * `path` - 保存位置，None 时只保存在内存中
+    pub(crate) fn open(path: Option<PathBuf>) -> Self {
+        let devices = path.as_deref().map(load_devices).unwrap_or_default();
+        if !devices.is_empty() {
+            tracing::info!("Loaded {} paired devices", devices.len());
+        }
+        Self {
+            path,
+            inner: Mutex::new(RegistryInner {
+                devices,
+                revoked: HashSet::new(),
+            }),
+        }
+    }
+
+    /// 登记新设备并签发设备令牌。
+    ///
+    /// # Arguments
+    /// * `device_name` - 手机上报的设备名称
+    pub(crate) fn add(&self, device_name: &str) -> String {
+        let device_token = crate::pairing::random_token(32);
+        let device = PairedDevice {
+            id: crate::pairing::random_token(6),
+            name: device_name.to_string(),
+            paired_at: chrono::Utc::now().timestamp_millis(),
+            last_seen: None,
+            settings: DeviceSettings::default(),
+        };
+        let mut inner = self.inner.lock().unwrap();
+        inner.devices.insert(token_hash(&device_token), device);
+        self.save(&inner);
+        device_token
+    }
+
+    /// 按设备令牌查找设备并记下访问时间。
+    pub(crate) fn authenticate(&self, token: &str) -> Option<PairedDevice> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut inner = self.inner.lock().unwrap();
+        let device = inner.devices.get_mut(&token_hash(token))?;
+        let stale = device
+            .last_seen
+            .is_none_or(|seen| now - seen >= LAST_SEEN_SAVE_INTERVAL_MS);
+        device.last_seen = Some(now);
+        let device = device.clone();
+        if stale {
+            self.save(&inner);
+        }
+        Some(device)
+    }
+
+    /// 令牌是否属于已解除配对的设备。
+    pub(crate) fn is_revoked(&self, token: &str) -> bool {
+        self.inner.lock().unwrap().revoked.contains(&token_hash(token))
+    }
+
+    /// 按标识查找设备。
+    pub(crate) fn get(&self, id: &str) -> Option<PairedDevice> {
+        let inner = self.inner.lock().unwrap();
+        inner.devices.values().find(|device| device.id == id).cloned()
+    }
+
+    /// 返回所有已配对设备，按配对时间排序。
+    pub(crate) fn list(&self) -> Vec<PairedDevice> {
+        let inner = self.inner.lock().unwrap();
+        let mut devices: Vec<PairedDevice> = inner.devices.values().cloned().collect();
+        devices.sort_by_key(|device| device.paired_at);
+        devices
+    }
+
+    /// 修改设备单独的设置。
+    ///
+    /// # Returns
+    /// 设备是否存在
+    pub(crate) fn update_settings(&self, id: &str, update: impl FnOnce(&mut DeviceSettings)) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(device) = inner.devices.values_mut().find(|device| device.id == id) else {
+            return false;
+        };
+        update(&mut device.settings);
+        self.save(&inner);
+        true
+    }
+
+    /// 解除配对，设备令牌随即失效。
+    ///
+    /// # Returns
+    /// 被移除的设备，设备不存在时返回 None
+    pub(crate) fn revoke(&self, id: &str) -> Option<PairedDevice> {
+        let mut inner = self.inner.lock().unwrap();
+        let token_hash = inner
+            .devices
+            .iter()
+            .find(|(_, device)| device.id == id)
+            .map(|(token_hash, _)| token_hash.clone())?;
+        let device = inner.devices.remove(&token_hash)?;
+        inner.revoked.insert(token_hash);
+        self.save(&inner);
+        Some(device)
+    }
+
+    /// 移除令牌从未交给手机的设备，不计入已解除配对。
+    ///
+    /// # Returns
+    /// 被移除的设备
+    pub(crate) fn discard(&self, device_token: &str) -> Option<PairedDevice> {
+        let mut inner = self.inner.lock().unwrap();
+        let device = inner.devices.remove(&token_hash(device_token))?;
+        self.save(&inner);
+        Some(device)
+    }
+
+    /// 写入已配对设备，失败时只记录错误，内存中的配对状态不受影响。
+    fn save(&self, inner: &RegistryInner) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut stored: Vec<StoredDevice> = inner
+            .devices
+            .iter()
+            .map(|(token_hash, device)| StoredDevice {
+                token_hash: token_hash.clone(),
+                device: device.clone(),
+            })
+            .collect();
+        stored.sort_by_key(|entry| entry.device.paired_at);
+
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| {
+                let json = serde_json::to_vec_pretty(&stored).map_err(std::io::Error::other)?;
+                std::fs::write(path, json)
+            });
+        if let Err(e) = written {
+            tracing::error!("Failed to save paired devices to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// 发起请求的已配对设备，未携带设备令牌（访问令牌、访客或旧版手机端）时为 None。
+/// 设备标识由令牌校验中间件放入请求扩展，这里再从登记表取出名称与设置。
+pub(crate) struct RequestingDevice(pub Option<PairedDevice>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for RequestingDevice {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let device = parts
+            .extensions
+            .get::<AuthenticatedDevice>()
+            .and_then(|AuthenticatedDevice(id)| state.devices.get(id));
+        Ok(Self(device))
+    }
+}
+
+/// 默认的已配对设备文件：`<漫游配置目录>/FastSync/devices.json`，Windows 上即 `%APPDATA%\FastSync`。
+pub fn default_devices_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("FastSync").join("devices.json"))
+}
+
+/// 读取已配对设备，键为设备令牌摘要。
+fn load_devices(path: &Path) -> HashMap<String, PairedDevice> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return HashMap::new(),
+        Err(e) => {
+            tracing::error!("Failed to read paired devices from {:?}: {}", path, e);
+            return HashMap::new();
+        }
+    };
+    match serde_json::from_str::<Vec<StoredDevice>>(&text) {
+        Ok(stored) => stored.into_iter().map(|entry| (entry.token_hash, entry.device)).collect(),
+        Err(e) => {
+            tracing::error!("Invalid paired devices file {:?}: {}", path, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// 设备令牌的摘要，内存与文件中都不保存令牌原文。
+fn token_hash(token: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,67 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 服务事件模块。
+ * 处理器在收到内容后发出事件，嵌入方可通过回调或广播流订阅。
+ */
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// 服务运行过程中产生的事件。
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ServerEvent {
+    /// 服务已开始监听
+    Started { addr: SocketAddr },
+    /// 收到图片，`path` 为临时文件或自动保存后的路径
+    PhotoReceived { path: PathBuf, size: usize },
+    /// 收到视频，`path` 为临时文件或自动保存后的路径
+    VideoReceived { path: PathBuf, size: usize },
+    /// 收到短信
+    SmsReceived { sender: String, content: String, code: String },
+    /// 收到剪贴板文本
+    ClipboardReceived { text: String },
+    /// 手机通过扫码完成配对
+    DevicePaired { name: String },
+    /// 设备被解除配对
+    DeviceRevoked { id: String, name: String },
+    /// 第三方处理器发出的事件
+    Custom { capability: String, data: serde_json::Value },
+    /// 服务已停止
+    Stopped,
+}
+
+/// 事件回调。
+pub type EventHandler = Arc<dyn Fn(&ServerEvent) + Send + Sync>;
+
+/// 事件总线，同时分发给注册的回调与广播订阅者。
+#[derive(Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<ServerEvent>,
+    handlers: Arc<Vec<EventHandler>>,
+}
+
+impl EventBus {
+    pub(crate) fn new(handlers: Vec<EventHandler>) -> Self {
+        let (sender, _) = broadcast::channel(64);
+        Self {
+            sender,
+            handlers: Arc::new(handlers),
+        }
+    }
+
+    /// 发出事件。没有订阅者时广播发送失败属于正常情况。
+    pub(crate) fn emit(&self, event: ServerEvent) {
+        for handler in self.handlers.iter() {
+            handler(&event);
+        }
+        let _ = self.sender.send(event);
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,56 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use crate::auth::is_local_admin;
+use crate::state::AppState;
+
+/// 单次最多返回的记录条数。
+const MAX_LIMIT: usize = 500;
+
+/// `GET /audit` 查询参数。
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    /// 返回条数，默认 50
+    pub limit: Option<usize>,
+    /// 只返回该时间（毫秒时间戳）之后的操作
+    pub since: Option<i64>,
+    /// 只返回该时间（毫秒时间戳）之前的操作
+    pub until: Option<i64>,
+}
+
+/// 返回最近的操作审计记录，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `query` - 查询参数
+pub async fn audit(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+
+    let limit = query.limit.unwrap_or(50).min(MAX_LIMIT);
+    let records: Vec<_> = state
+        .audit
+        .recent(MAX_LIMIT)
+        .into_iter()
+        .filter(|record| query.since.is_none_or(|since| record.timestamp >= since))
+        .filter(|record| query.until.is_none_or(|until| record.timestamp <= until))
+        .take(limit)
+        .collect();
+    Json(records).into_response()
+}
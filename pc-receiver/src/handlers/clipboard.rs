@@ -4,111 +4,666 @@
  *
  * 剪贴板处理器模块。
  * 负责接收手机端推送的剪贴板内容，并显示交互式通知。
+ * 启用自动同步时直接写入剪贴板，通知中提供“撤销”恢复之前的内容。
+ * 手机端也可通过 `GET /clipboard` 拉取电脑剪贴板中的文本。
+ * 收到的文本无论是否复制都记入剪贴板历史，本机可通过 `GET /clipboard/history` 找回。
+ * 文本中含有网址时通知提供“在浏览器打开”；较长的文本另存为临时文件，通知中提供“另存为文件”。
+ * 从网页复制的带格式文本附带 HTML，复制时同时写入 HTML 与纯文本，粘贴到 Word 等程序时保留格式。
+ * 启用敏感内容过滤时，疑似密码、令牌的文本只显示隐藏内容的通知，不记入历史，复制后自动清空剪贴板。
+ * 手机剪贴板中是图片时以 Base64 发送，与图片上传一样识别格式，通知中预览并提供复制。
  */
-use axum::{
-    extract::Json,
-    http::StatusCode,
-};
+use axum::extract::{ConnectInfo, FromRequest, Path, Query, Request, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use futures::future::BoxFuture;
 use serde::Deserialize;
-use windows::{
-    core::*,
-    Data::Xml::Dom::XmlDocument,
-    UI::Notifications::{ToastNotification, ToastNotificationManager},
-    Foundation::{DateTime, IReference, PropertyValue},
-};
-use crate::APP_ID;
-use crate::handlers::store_notification;
-
-/// 剪贴板数据载荷结构体。
-/// 用于反序列化接收到的 JSON 数据。
+use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use fastsync_core::dedup::PushOrder;
+use fastsync_core::protocol::ClipboardPayload;
+use fastsync_core::timeline::CaptureTime;
+use crate::audit::ItemAudit;
+use crate::auth::is_local_admin;
+use crate::clipboard::{find_url, html, secret, ClipboardBackend};
+use crate::events::ServerEvent;
+use crate::handlers::photo::ImageActions;
+use crate::handlers::response::{text_hash, UploadResponse};
+use crate::image_format::ImageFormat;
+use crate::notifier::{ActionHandler, Notification, NotificationAction};
+use crate::payload::{PayloadContext, PayloadHandler, PayloadOutcome};
+use crate::policy::ContentInfo;
+use crate::state::{AppState, RunMode};
+use crate::timings::Stage;
+use crate::validation::ValidJson;
+
+/// 默认的另存为文件阈值，超过该字节数的文本通知中提供“另存为文件”。
+pub const DEFAULT_CLIPBOARD_FILE_THRESHOLD: usize = 10 * 1024;
+
+/// 敏感内容复制后默认的自动清空延迟。
+pub const DEFAULT_CLIPBOARD_SECRET_CLEAR: Duration = Duration::from_secs(30);
+
+/// 通知中文本预览的字符数。
+const PREVIEW_CHARS: usize = 100;
+
+/// 长轮询默认的等待时间。
+const DEFAULT_WAIT: Duration = Duration::from_secs(25);
+
+/// 长轮询最长的等待时间。
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// `GET /clipboard` 查询参数。
+#[derive(Debug, Deserialize)]
+pub struct ClipboardQuery {
+    /// 手机端已收到的变化序号，提供时等待新的变化再返回
+    pub since: Option<u64>,
+    /// 最长等待秒数，默认 25，最多 60
+    pub wait: Option<u64>,
+}
+
+/// `GET /clipboard/history` 查询参数。
 #[derive(Debug, Deserialize)]
-pub struct ClipboardPayload {
-    pub text: String,
-    pub timestamp: i64,
+pub struct ClipboardHistoryQuery {
+    /// 返回条数，默认全部
+    pub limit: Option<usize>,
+}
+
+/// 内置剪贴板处理器，路由 `POST /clipboard`。
+pub(crate) struct ClipboardHandler;
+
+impl PayloadHandler for ClipboardHandler {
+    fn path(&self) -> &str {
+        "/clipboard"
+    }
+
+    fn capability(&self) -> &str {
+        "clipboard"
+    }
+
+    fn cross_origin(&self) -> bool {
+        true
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            match ValidJson::<ClipboardPayload>::from_request(request, &()).await {
+                Ok(ValidJson(payload)) => receive_clipboard(ctx, payload).await,
+                Err(rejection) => PayloadOutcome::new(UploadResponse::from(rejection)),
+            }
+        })
+    }
 }
 
 /// 处理剪贴板同步请求。
-/// 
-/// 接收手机端发送的剪贴板内容，并不直接写入系统剪贴板，
-/// 
+///
+/// 接收手机端发送的剪贴板内容，默认不直接写入系统剪贴板，由通知中的按钮确认；
+/// 启用自动同步时直接写入，写入失败时退回确认通知。
+///
 /// # 参数
+/// * `ctx` - 处理器上下文
 /// * `payload` - 包含剪贴板文本和时间戳的 JSON 数据
-pub async fn receive_clipboard(Json(payload): Json<ClipboardPayload>) -> StatusCode {
+async fn receive_clipboard(ctx: PayloadContext, mut payload: ClipboardPayload) -> PayloadOutcome {
+    ctx.mark(Stage::Received);
+    if let Some(encoded) = payload.image_base64.as_deref().filter(|encoded| !encoded.is_empty()) {
+        return receive_clipboard_image(ctx, encoded, payload.timestamp).await;
+    }
+    let html = payload.html.as_deref().map(html::fragment).filter(|html| !html.is_empty()).map(str::to_string);
+    if let Some(html) = html.as_deref().filter(|_| payload.text.is_empty()) {
+        payload.text = html::to_plain_text(html);
+    }
+    let content = ContentInfo {
+        mime: Some("text/plain"),
+        file_name: None,
+        size: payload.text.len() as u64,
+    };
+    if let Err(violation) = ctx.check_content("clipboard", &content) {
+        return PayloadOutcome::new(violation);
+    }
+
+    let hash = text_hash(&payload.text);
+    let capture = ctx.capture_time((payload.timestamp != 0).then_some(payload.timestamp));
+    let response = UploadResponse::success(content.size, Some(hash.clone()));
+    if let Some(reason) = check_order(&ctx, payload.timestamp, &hash, capture) {
+        return PayloadOutcome::new(response.with_detail(reason, true));
+    }
+
+    // 敏感内容不广播给事件订阅者、不记入历史，也不自动同步，只能在通知中点击复制
+    let config = ctx.state.config.read().unwrap().clipboard.clone();
+    if let Some(kind) = config.secret_filter.then(|| secret::detect(&payload.text)).flatten() {
+        tracing::info!("Received clipboard content that looks like a secret ({}), length: {}", kind.as_str(), payload.text.len());
+        let can_copy = ctx.action_allowed("clipboard", &content, "copy");
+        let (notification, on_action) = build_secret_notification(&ctx, payload.text, capture, can_copy);
+        return PayloadOutcome::new(response.with_detail("sensitive", true)).with_notification(notification, on_action);
+    }
+
     tracing::info!("Received clipboard content, length: {}", payload.text.len());
-    
+    ctx.emit(ServerEvent::ClipboardReceived { text: payload.text.clone() });
+
+    // 忽略或通知过期的内容之后仍可在历史中找回
+    let history = ctx.state.clipboard_history.clone();
+    let text = payload.text.clone();
+    let device = ctx.device.origin();
+    let _ = tokio::task::spawn_blocking(move || history.record(&text, capture.captured_at, capture.received_at, device)).await;
+
     // 显示通知，由用户交互决定是否写入剪贴板
-    if let Err(e) = show_clipboard_notification(&payload.text) {
-        tracing::error!("Failed to show clipboard notification: {:?}", e);
+    let can_copy = ctx.action_allowed("clipboard", &content, "copy");
+    let link = find_url(&payload.text)
+        .filter(|_| ctx.action_allowed("clipboard", &content, "open"))
+        .map(str::to_string);
+    let threshold = config.file_threshold;
+    let file = if threshold > 0 && payload.text.len() > threshold && ctx.action_allowed("clipboard", &content, "save") {
+        write_text_file(&payload.text).await
+    } else {
+        None
+    };
+    let audit = ctx.audit("clipboard").with_capture(capture);
+    if can_copy && config.auto_apply && ctx.state.mode == RunMode::Desktop {
+        if let Some(previous) = auto_apply(&ctx, &audit, &payload.text, html.as_deref()).await {
+            let (notification, on_action) = build_applied_notification(&ctx, audit, &payload.text, previous, link, file);
+            return PayloadOutcome::new(response.with_detail("applied", true)).with_notification(notification, on_action);
+        }
     }
-    
-    StatusCode::OK
+    let (notification, on_action) = build_clipboard_notification(&ctx, audit, &payload.text, html, link, file, can_copy);
+    PayloadOutcome::new(response).with_notification(notification, on_action)
 }
 
-/// 显示剪贴板同步通知。
-/// 
-/// 创建一个带有交互按钮的 Windows Toast 通知。
-fn show_clipboard_notification(text: &str) -> windows::core::Result<()> {
-    let toast_xml = XmlDocument::new()?;
-    
-    let preview = if text.chars().count() > 100 {
-        format!("{}...", text.chars().take(100).collect::<String>())
-    } else {
-        text.to_string()
+/// 将较长的文本写入临时文件，供通知中的“另存为文件”复制到用户选择的位置。
+///
+/// # Arguments
+/// * `text` - 剪贴板文本
+///
+/// # Returns
+/// 临时文件路径，写入失败时为 None，通知中不提供另存
+async fn write_text_file(text: &str) -> Option<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "fastsync_clipboard_{}.{}",
+        hex::encode(rand::random::<[u8; 8]>()),
+        crate::temp_files::TEXT_EXTENSION
+    ));
+    let text = text.to_string();
+    let target = path.clone();
+    let written = tokio::task::spawn_blocking(move || std::fs::write(&target, text))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result.map_err(anyhow::Error::from));
+    match written {
+        Ok(()) => Some(path),
+        Err(e) => {
+            tracing::warn!("Failed to write clipboard text to {:?}: {:#}", path, e);
+            None
+        }
+    }
+}
+
+/// 丢弃手机重连后重发的内容与迟到的旧内容。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `timestamp` - 手机上的复制时间
+/// * `hash` - 内容哈希
+/// * `capture` - 校正后的复制时间
+///
+/// # Returns
+/// 需要丢弃时返回原因（`duplicate` / `outdated`），作为响应中的字段
+fn check_order(ctx: &PayloadContext, timestamp: i64, hash: &str, capture: CaptureTime) -> Option<&'static str> {
+    match ctx.state.clipboard_pushes.check(ctx.device.origin().as_deref(), timestamp, hash) {
+        PushOrder::New => None,
+        PushOrder::Duplicate => {
+            tracing::info!("Clipboard content copied at {} was already received, skipped", timestamp);
+            Some("duplicate")
+        }
+        PushOrder::Outdated { latest } => {
+            tracing::info!("Dropping clipboard content copied at {}, older than the latest copy at {}", timestamp, latest);
+            ctx.audit("clipboard").with_capture(capture).record("outdated", None, None);
+            Some("outdated")
+        }
+    }
+}
+
+/// 处理手机剪贴板中的图片：与图片上传一样按文件头识别格式并检查内容策略，通知中预览并提供复制与保存。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `encoded` - Base64 编码的图片
+/// * `timestamp` - 手机上的复制时间
+async fn receive_clipboard_image(ctx: PayloadContext, encoded: &str, timestamp: i64) -> PayloadOutcome {
+    let capture = ctx.capture_time((timestamp != 0).then_some(timestamp));
+    if let Some(reason) = check_order(&ctx, timestamp, &text_hash(encoded), capture) {
+        return PayloadOutcome::new(UploadResponse::success(0, None).with_detail(reason, true));
+    }
+
+    let (path, format) = match crate::handlers::photo::receive_base64_image(&ctx, encoded).await {
+        Ok(image) => image,
+        Err(error) => return PayloadOutcome::new(UploadResponse::failure(error)),
     };
-    
-    let content_escaped = preview.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;");
-    
-    let xml_string = format!(r#"
-        <toast duration="short" activationType='background'>
-        <visual>
-            <binding template='ToastGeneric'>
-                <text>收到手机剪贴板</text>
-                <text>{}</text>
-            </binding>
-        </visual>
-        <actions>
-            <action content='复制' arguments='copy_clipboard' activationType="foreground"/>
-            <action content='忽略' arguments='ignore' activationType="foreground"/>
-        </actions>
-        </toast>
-    "#, content_escaped);
-
-    toast_xml.LoadXml(&HSTRING::from(xml_string))?;
-
-    let notification = ToastNotification::CreateToastNotification(&toast_xml)?;
-
-    notification.SetTag(&HSTRING::from("clipboard_sync"))?;
-    notification.SetGroup(&HSTRING::from("FastSync"))?;
-    
-    let now_unix_millis = chrono::Utc::now().timestamp_millis();
-    let expiration_millis = now_unix_millis + 30_000; 
-    let expiration_ticks = (expiration_millis * 10_000) + 116444736000000000;
-    
-    let expiry_time = DateTime { UniversalTime: expiration_ticks };
-    let expiry_inspectable = PropertyValue::CreateDateTime(expiry_time)?;
-    let expiry_reference: IReference<DateTime> = expiry_inspectable.cast()?;
-    notification.SetExpirationTime(&expiry_reference)?;
+    let content = ContentInfo {
+        mime: Some(format.mime()),
+        file_name: None,
+        size: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+    };
+    if let Err(violation) = ctx.check_content("clipboard", &content) {
+        let _ = std::fs::remove_file(&path);
+        return PayloadOutcome::new(violation);
+    }
 
-    let text_content = text.to_string();
-    notification.Activated(&windows::Foundation::TypedEventHandler::new(move |_sender, args: &Option<IInspectable>| {
-        if let Some(args) = args {
-            let args: windows::UI::Notifications::ToastActivatedEventArgs = args.cast()?;
-            let arguments = args.Arguments()?.to_string();
-            
-            if arguments == "copy_clipboard" {
-                tracing::info!("Copy clipboard action clicked");
-                crate::handlers::photo::copy_text_to_clipboard(&text_content);
-            } else if arguments == "ignore" {
-                tracing::info!("Ignore clipboard action clicked");
+    tracing::info!("Received clipboard image ({}), {} bytes", format.mime(), content.size);
+    let (notification, on_action) = build_image_notification(&ctx, &content, format, path, capture);
+    PayloadOutcome::new(UploadResponse::success(content.size, None)).with_notification(notification, on_action)
+}
+
+/// 构建剪贴板图片通知，大图预览图片，按钮与图片通知的复制、保存相同。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `content` - 图片的类型与大小
+/// * `format` - 图片格式
+/// * `path` - 临时图片路径
+/// * `capture` - 手机上的复制时间
+fn build_image_notification(
+    ctx: &PayloadContext,
+    content: &ContentInfo,
+    format: ImageFormat,
+    path: PathBuf,
+    capture: CaptureTime,
+) -> (Notification, ActionHandler) {
+    let audit = ctx.audit("clipboard").with_capture(capture);
+    let mut notification = Notification::new(&format!("clipboard_{}", audit.item_id()), &ctx.device.received_title("剪贴板图片"));
+    notification.group = "clipboard".to_string();
+    notification.hero_image = Some(path.clone());
+    if capture.is_delayed() {
+        notification.body.push(format!("复制于 {}", capture.relative_to(capture.received_at)));
+    }
+    notification.expires_in = Duration::from_secs(30);
+
+    let image_actions = ImageActions::new(ctx, "clipboard", content, format, Arc::new(Mutex::new(path)), None).retain(&["copy", "save"]);
+    image_actions.add_to(&mut notification);
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if image_actions.handle_action(arguments, &audit) {
+            return;
+        }
+        if arguments == "ignore" {
+            tracing::info!("Ignore clipboard image action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
+
+    (notification, on_action)
+}
+
+/// 构建隐藏内容的敏感剪贴板通知，复制时不进入系统剪贴板历史，按设置延迟清空剪贴板。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `text` - 剪贴板文本，不会显示
+/// * `capture` - 手机上的复制时间
+/// * `can_copy` - 内容策略是否允许复制
+fn build_secret_notification(ctx: &PayloadContext, text: String, capture: CaptureTime, can_copy: bool) -> (Notification, ActionHandler) {
+    let audit = ctx.audit("clipboard").with_capture(capture);
+    let clear_after = ctx.state.config.read().unwrap().clipboard.secret_clear;
+    let mut notification = Notification::new(&format!("clipboard_{}", audit.item_id()), "收到可能的敏感内容，点击复制");
+    notification.group = "clipboard".to_string();
+    notification.body.push(if clear_after.is_zero() {
+        "内容已隐藏".to_string()
+    } else {
+        format!("内容已隐藏，复制 {} 秒后自动清空剪贴板", clear_after.as_secs())
+    });
+    if can_copy {
+        notification.actions.push(NotificationAction::new("copy_clipboard", "复制"));
+    }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    notification.expires_in = Duration::from_secs(30);
+
+    let clipboard = ctx.clipboard();
+    let watch = ctx.state.clipboard_watch.clone();
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "copy_clipboard" && can_copy {
+            tracing::info!("Copy sensitive clipboard action clicked");
+            watch.remember(&text);
+            let result = clipboard.set_sensitive_text(&text);
+            audit.record_result("copy", None, &result);
+            if result.is_ok() && !clear_after.is_zero() {
+                schedule_clear(clipboard.clone(), text.clone(), clear_after, audit.clone());
+            }
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore sensitive clipboard action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
+
+    (notification, on_action)
+}
+
+/// 延迟清空剪贴板。到时剪贴板中已是其他内容时不清空。
+///
+/// # Arguments
+/// * `clipboard` - 剪贴板后端
+/// * `text` - 写入的敏感文本
+/// * `delay` - 延迟
+/// * `audit` - 该内容的审计句柄
+fn schedule_clear(clipboard: Arc<dyn ClipboardBackend>, text: String, delay: Duration, audit: ItemAudit) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        if clipboard.get_text().ok().as_deref() != Some(text.as_str()) {
+            tracing::info!("Clipboard changed since the sensitive content was copied, not clearing");
+            return;
+        }
+        let result = clipboard.clear();
+        match &result {
+            Ok(()) => tracing::info!("Cleared sensitive content from the clipboard"),
+            Err(e) => tracing::warn!("Failed to clear sensitive content from the clipboard: {:#}", e),
+        }
+        audit.record_result("clear", None, &result);
+    });
+}
+
+/// 不等确认直接写入剪贴板。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `audit` - 该内容的审计记录，写入成功与失败都会记录
+/// * `text` - 手机剪贴板中的文本
+/// * `html` - 带格式文本的 HTML 片段
+///
+/// # Returns
+/// 写入成功时返回之前剪贴板中的文本（不是文本时为 None），写入失败时返回 None
+async fn auto_apply(ctx: &PayloadContext, audit: &ItemAudit, text: &str, html: Option<&str>) -> Option<Option<String>> {
+    let clipboard = ctx.clipboard();
+    let watch = ctx.state.clipboard_watch.clone();
+    let text = text.to_string();
+    let html = html.map(str::to_string);
+    let result = tokio::task::spawn_blocking(move || {
+        let previous = clipboard.get_text().ok();
+        // 来自手机的文本不再传回手机
+        watch.remember(&text);
+        write_clipboard(clipboard.as_ref(), &text, html.as_deref()).map(|()| previous)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    audit.record_result("auto_apply", None, &result);
+    match result {
+        Ok(previous) => {
+            tracing::info!("Phone clipboard applied automatically");
+            Some(previous)
+        }
+        Err(e) => {
+            tracing::warn!("Failed to apply phone clipboard, asking for confirmation instead: {:#}", e);
+            None
+        }
+    }
+}
+
+/// 写入手机剪贴板的内容。带格式时同时写入 HTML 与纯文本，粘贴到 Word、Outlook 时保留链接与粗体，
+/// 粘贴到记事本时仍是纯文本；写入 HTML 失败时退回纯文本。
+///
+/// # Arguments
+/// * `clipboard` - 剪贴板后端
+/// * `text` - 纯文本
+/// * `html` - 带格式文本的 HTML 片段
+fn write_clipboard(clipboard: &dyn ClipboardBackend, text: &str, html: Option<&str>) -> anyhow::Result<()> {
+    if let Some(html) = html {
+        match clipboard.set_html(html, text) {
+            Ok(()) => {
+                tracing::info!("HTML copied to clipboard successfully");
+                return Ok(());
             }
+            Err(e) => tracing::warn!("Failed to set clipboard HTML, copying plain text instead: {:#}", e),
+        }
+    }
+    crate::handlers::photo::copy_text_to_clipboard(clipboard, text)
+}
+
+/// 构建剪贴板已自动写入的通知，之前的内容是文本时提供“撤销”。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `audit` - 已记录自动写入的审计记录
+/// * `text` - 已写入的文本
+/// * `previous` - 写入前剪贴板中的文本
+/// * `link` - 文本中可以打开的网址
+/// * `file` - 较长文本的临时文件，提供时通知中提供“另存为文件”
+fn build_applied_notification(
+    ctx: &PayloadContext,
+    audit: ItemAudit,
+    text: &str,
+    previous: Option<String>,
+    link: Option<String>,
+    file: Option<PathBuf>,
+) -> (Notification, ActionHandler) {
+    let title = format!("剪贴板已同步 ({} 字符)", text.chars().count());
+    let mut notification = Notification::new(&format!("clipboard_{}", audit.item_id()), &title);
+    notification.group = "clipboard".to_string();
+    notification.body.push(preview(text));
+    notification.body.extend(summary(text, None));
+    if link.is_some() {
+        notification.actions.push(NotificationAction::new("open_link", "在浏览器打开"));
+    }
+    if let Some(path) = &file {
+        notification.actions.push(NotificationAction::new("save_file", "另存为文件"));
+        notification.temp_files.push(path.clone());
+    }
+    if previous.is_some() {
+        notification.actions.push(NotificationAction::new("undo", "撤销"));
+    }
+    notification.expires_in = Duration::from_secs(10);
+
+    let clipboard = ctx.clipboard();
+    let watch = ctx.state.clipboard_watch.clone();
+    let applied = text.to_string();
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if let Some(url) = link.as_deref().filter(|_| arguments == "open_link") {
+            open_link(url, &audit);
+            return;
+        }
+        if let Some(path) = file.as_deref().filter(|_| arguments == "save_file") {
+            save_text_file(path, &audit);
+            return;
         }
-        Ok(())
-    }))?;
+        let Some(previous) = previous.as_deref().filter(|_| arguments == "undo") else {
+            return;
+        };
+        tracing::info!("Undo clipboard sync clicked");
+        // 之后又复制过其他内容时不覆盖
+        if clipboard.get_text().ok().as_deref() != Some(applied.as_str()) {
+            tracing::info!("Clipboard changed since the sync, nothing to undo");
+            audit.record("undo", None, Some("changed".to_string()));
+            return;
+        }
+        // 恢复的是电脑上原有的内容，同样不传回手机
+        watch.remember(previous);
+        let result = crate::handlers::photo::copy_text_to_clipboard(clipboard.as_ref(), previous);
+        audit.record_result("undo", None, &result);
+    });
+
+    (notification, on_action)
+}
+
+/// 通知中显示的文本预览：连续的空白（包括换行）合并为一个空格，超过 100 个字符时截断。
+fn preview(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() > PREVIEW_CHARS {
+        format!("{}...", collapsed.chars().take(PREVIEW_CHARS).collect::<String>())
+    } else {
+        collapsed
+    }
+}
+
+/// 预览之后的第二行：文本较长、预览不完整时显示行数与大小（如“3,412 行, 198 KB”），
+/// 复制时间明显早于到达时间时附上复制时间。Toast 最多显示三行文字，两者合为一行。
+///
+/// # Arguments
+/// * `text` - 剪贴板文本
+/// * `captured` - 复制时间的相对描述，未延迟时为 None
+fn summary(text: &str, captured: Option<String>) -> Option<String> {
+    let counts = (text.chars().count() > PREVIEW_CHARS)
+        .then(|| format!("{} 行, {}", group_digits(text.lines().count()), format_size(text.len())));
+    match (counts, captured) {
+        (Some(counts), Some(captured)) => Some(format!("{} · {}", counts, captured)),
+        (counts, captured) => counts.or(captured),
+    }
+}
 
-    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?;
-    notifier.Show(&notification)?;
-    
-    store_notification("clipboard", notification);
-    
-    Ok(())
+/// 按千位加逗号，如 3412 显示为“3,412”。
+fn group_digits(value: usize) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// 以 B、KB 或 MB 显示字节数。
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1048576 => format!("{} KB", bytes.div_ceil(1024)),
+        _ => format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+/// 弹出保存对话框，将较长文本的临时文件复制到用户选择的位置，默认文件名带有时间。
+fn save_text_file(path: &std::path::Path, audit: &ItemAudit) {
+    tracing::info!("Save clipboard text as file action clicked");
+    let file_name = format!("FastSync_{}.txt", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    match crate::handlers::photo::save_copy_dialog(path, &file_name, Some(("Text", &["txt"]))) {
+        Ok(Some(saved)) => audit.record("save", Some(&saved), None),
+        Ok(None) => audit.record("save", None, Some("cancelled".to_string())),
+        Err(e) => audit.record("save", None, Some(format!("{:#}", e))),
+    }
+}
+
+/// 用默认浏览器打开剪贴板中的网址。
+fn open_link(url: &str, audit: &ItemAudit) {
+    tracing::info!("Open link action clicked: {}", url);
+    let result = crate::handlers::photo::open_file(url);
+    audit.record_result("open_link", None, &result);
+}
+
+/// 构建剪贴板同步通知。
+///
+/// 创建一个带有交互按钮的通知，内容策略不允许复制时只保留"忽略"。
+/// 文本中含有网址时在复制之后提供"在浏览器打开"，打开第一个网址；
+/// 较长的文本另存为临时文件时提供"另存为文件"。带格式的文本复制时同时写入 HTML。
+fn build_clipboard_notification(
+    ctx: &PayloadContext,
+    audit: ItemAudit,
+    text: &str,
+    html: Option<String>,
+    link: Option<String>,
+    file: Option<PathBuf>,
+    can_copy: bool,
+) -> (Notification, ActionHandler) {
+    let mut notification = Notification::new(&format!("clipboard_{}", audit.item_id()), &ctx.device.received_title("剪贴板"));
+    notification.group = "clipboard".to_string();
+    notification.body.push(preview(text));
+    let captured = audit
+        .capture()
+        .filter(|capture| capture.is_delayed())
+        .map(|capture| format!("复制于 {}", capture.relative_to(capture.received_at)));
+    notification.body.extend(summary(text, captured));
+    if can_copy {
+        notification.actions.push(NotificationAction::new("copy_clipboard", "复制"));
+    }
+    if let Some(path) = &file {
+        notification.actions.push(NotificationAction::new("save_file", "另存为文件"));
+        notification.temp_files.push(path.clone());
+    }
+    if link.is_some() {
+        notification.actions.push(NotificationAction::new("open_link", "在浏览器打开"));
+    }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    notification.expires_in = Duration::from_secs(30);
+
+    let clipboard = ctx.clipboard();
+    let watch = ctx.state.clipboard_watch.clone();
+    let text_content = text.to_string();
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "copy_clipboard" && can_copy {
+            tracing::info!("Copy clipboard action clicked");
+            // 来自手机的文本不再传回手机
+            watch.remember(&text_content);
+            let result = write_clipboard(clipboard.as_ref(), &text_content, html.as_deref());
+            audit.record_result("copy", None, &result);
+        } else if let Some(url) = link.as_deref().filter(|_| arguments == "open_link") {
+            open_link(url, &audit);
+        } else if let Some(path) = file.as_deref().filter(|_| arguments == "save_file") {
+            save_text_file(path, &audit);
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore clipboard action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
+
+    (notification, on_action)
+}
+
+/// 返回电脑剪贴板中的文本 `{seq, text, timestamp}`。
+///
+/// 不带 `since` 时立即返回最近一次检测到的文本；带 `since` 时等待序号变化，
+/// 超时后返回的序号与 `since` 相同，手机端直接发起下一次请求即可。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `query` - 查询参数
+pub async fn current(State(state): State<AppState>, Query(query): Query<ClipboardQuery>) -> Response {
+    let snapshot = match query.since {
+        Some(since) => {
+            let wait = query.wait.map_or(DEFAULT_WAIT, Duration::from_secs).min(MAX_WAIT);
+            state.clipboard_watch.wait_newer(since, wait).await
+        }
+        None => state.clipboard_watch.latest(),
+    };
+    Json(snapshot).into_response()
+}
+
+/// 按时间倒序返回最近收到的手机剪贴板，较长的文本只包含预览，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `query` - 查询参数
+pub async fn history(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<ClipboardHistoryQuery>,
+) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+    Json(state.clipboard_history.list(query.limit.unwrap_or(usize::MAX))).into_response()
+}
+
+/// 返回一条剪贴板历史的全文，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `id` - 记录标识
+pub async fn history_entry(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(id): Path<u64>,
+) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+    let history = state.clipboard_history.clone();
+    let entry = tokio::task::spawn_blocking(move || history.get(id)).await.ok().flatten();
+    match entry {
+        Some(Ok(entry)) => Json(entry).into_response(),
+        Some(Err(e)) => {
+            tracing::error!("Failed to read clipboard history entry {}: {:#}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "history_unavailable" }))).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": "not_found" }))).into_response(),
+    }
 }
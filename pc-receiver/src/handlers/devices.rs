@@ -0,0 +1,48 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use crate::devices::RequestingDevice;
+use crate::state::AppState;
+
+/// 列出已配对设备，发起请求的设备标记为 `current`，保持 WebSocket 连接的设备标记为 `connected`。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `requesting` - 发起请求的设备
+pub async fn list_devices(State(state): State<AppState>, RequestingDevice(requesting): RequestingDevice) -> Response {
+    let current = requesting.map(|device| device.id);
+    let devices: Vec<serde_json::Value> = state
+        .devices
+        .list()
+        .into_iter()
+        .map(|device| {
+            let is_current = current.as_deref() == Some(device.id.as_str());
+            let mut value = json!(device);
+            value["current"] = json!(is_current);
+            value["connected"] = json!(state.sessions.is_connected(&device.id));
+            value
+        })
+        .collect();
+    Json(json!({ "devices": devices })).into_response()
+}
+
+/// 解除设备配对。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `id` - 设备标识
+pub async fn revoke_device(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    if state.revoke_device(&id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (StatusCode::NOT_FOUND, Json(json!({ "error": "unknown device" }))).into_response()
+    }
+}
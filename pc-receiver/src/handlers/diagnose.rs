@@ -0,0 +1,63 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use crate::auth::is_local_admin;
+use crate::state::AppState;
+
+/// 以 Prometheus 文本格式返回各处理阶段的耗时直方图与管线指标，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+pub async fn metrics(State(state): State<AppState>, connect_info: Option<ConnectInfo<SocketAddr>>) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+
+    let metrics = state.pipeline.metrics(state.notifications.pending());
+    let mut body = String::new();
+    state.timings.render_prometheus(&mut body);
+    let _ = writeln!(body, "# TYPE fastsync_queue_depth gauge");
+    let _ = writeln!(body, "fastsync_queue_depth {}", metrics.queue_depth);
+    let _ = writeln!(body, "# TYPE fastsync_pending_bytes gauge");
+    let _ = writeln!(body, "fastsync_pending_bytes {}", metrics.pending_bytes);
+    let _ = writeln!(body, "# TYPE fastsync_notification_display_failures_total counter");
+    let _ = writeln!(body, "fastsync_notification_display_failures_total {}", state.notifications.failures());
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+/// 返回排查问题所需的运行状态，包括最近一个内容的各阶段耗时，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+pub async fn diagnose(State(state): State<AppState>, connect_info: Option<ConnectInfo<SocketAddr>>) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+
+    let metrics = state.pipeline.metrics(state.notifications.pending());
+    Json(json!({
+        "mode": state.mode.as_str(),
+        "saturated": state.pipeline.update(metrics),
+        "queue_depth": metrics.queue_depth,
+        "pending_bytes": metrics.pending_bytes,
+        "notification_display_failures": state.notifications.failures(),
+        "storage_warning": state.storage.warning(),
+        "suppressed_by": state.missed.active(),
+        "last_item": state.timings.last(),
+        "startup": state.startup.lock().unwrap().clone(),
+    }))
+    .into_response()
+}
@@ -0,0 +1,23 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::extract::{Json, State};
+use serde_json::{json, Value};
+use crate::state::AppState;
+
+/// 健康检查，返回服务状态、运行时长、运行模式与处理管线是否饱和。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+pub async fn health(State(state): State<AppState>) -> Json<Value> {
+    let metrics = state.pipeline.metrics(state.notifications.pending());
+    Json(json!({
+        "status": "ok",
+        "uptime_secs": state.started_at.elapsed().as_secs(),
+        "mode": state.mode.as_str(),
+        "saturated": state.pipeline.update(metrics),
+        "queue_depth": metrics.queue_depth,
+        "pending_bytes": metrics.pending_bytes,
+    }))
+}
@@ -0,0 +1,81 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::extract::{Json, State};
+use serde_json::{json, Value};
+use crate::capabilities::{Capabilities, Platform};
+use crate::state::AppState;
+use crate::versioning::{CURRENT_VERSION, SUPPORTED_VERSIONS};
+
+/// 与处理器无关、始终提供的接口，路径相对于版本前缀。
+const BUILTIN_ENDPOINTS: &[&str] = &[
+    "GET /health",
+    "GET /info",
+    "GET /ping",
+    "POST /pair",
+    "POST /pair/qr",
+    "POST /pair/request",
+    "GET /pair/status/:id",
+    "POST /upload/init",
+    "PUT /upload/chunk/:id",
+    "GET /upload/status/:id",
+    "GET /clipboard",
+    "GET /sms/outbox",
+    "POST /sms/outbox/:id/ack",
+    "GET /photos/:id/thumb",
+    "GET /devices",
+    "DELETE /devices/:id",
+    "GET /ws",
+];
+
+/// 返回服务版本、协议版本、运行平台、可用功能与接口，供手机端在配对前判断。
+/// 无需令牌即可访问，因此不包含访问令牌与已配对设备。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+pub async fn info(State(state): State<AppState>) -> Json<Value> {
+    let hostname = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "FastSync".into());
+    let endpoints: Vec<&str> = BUILTIN_ENDPOINTS
+        .iter()
+        .copied()
+        .chain(state.endpoints.iter().map(String::as_str))
+        .collect();
+    let (auth_required, rate_limit, idempotency_ttl) = {
+        let config = state.config.read().unwrap();
+        (config.server.require_token, config.rate_limit, config.server.idempotency_ttl)
+    };
+
+    Json(json!({
+        "app": "FastSync",
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": crate::PROTOCOL_VERSION,
+        // 接口挂在版本前缀下，endpoints 中的路径相对于 api_base；无前缀的旧路径已弃用
+        "api_versions": SUPPORTED_VERSIONS,
+        "api_base": format!("/{}", CURRENT_VERSION),
+        "hostname": hostname,
+        "platform": Platform::current().as_str(),
+        "mode": state.mode.as_str(),
+        "capabilities": Capabilities::current(state.mode),
+        "features": state.features.as_slice(),
+        "endpoints": endpoints,
+        "max_upload_bytes": state.body_limits.upload,
+        "max_json_bytes": state.body_limits.json,
+        // 请求体可用的 Content-Encoding，上限按解压后的大小计算
+        "request_encodings": crate::compression::SUPPORTED_ENCODINGS,
+        "auth_required": auth_required,
+        // 每分钟的请求数上限，0 表示不限制
+        "rate_limits": {
+            "upload": rate_limit.uploads_per_min,
+            "sms": rate_limit.sms_per_min,
+            "clipboard": rate_limit.clipboard_per_min,
+        },
+        // 上传、短信与剪贴板请求的幂等键，0 表示不处理
+        "idempotency": {
+            "header": "Idempotency-Key",
+            "ttl_secs": idempotency_ttl.as_secs(),
+        },
+    }))
+}
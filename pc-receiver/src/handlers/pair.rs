@@ -0,0 +1,311 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{ConnectInfo, Json, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::events::ServerEvent;
+use crate::notifier::{Notification, NotificationAction};
+use crate::pairing::{PairRequestOutcome, PairRequestStatus, PinOutcome, TokenStatus, GUEST_DEVICE_ID, PAIR_REQUEST_TTL};
+use crate::state::{AppState, RunMode};
+use crate::validation::ValidJson;
+
+/// 扫码配对请求。
+#[derive(Debug, Deserialize)]
+pub struct PairQrRequest {
+    /// 二维码中的一次性令牌
+    pub token: String,
+    /// 手机设备名称
+    #[serde(default)]
+    pub device_name: String,
+}
+
+/// 用二维码中的一次性令牌换取长期设备令牌，每个一次性令牌只能兑换一次。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `request` - 配对请求
+pub async fn pair_qr(State(state): State<AppState>, ValidJson(request): ValidJson<PairQrRequest>) -> Response {
+    match state.pairing.redeem(&request.token, &request.device_name) {
+        Ok(device_token) => {
+            tracing::info!("Device paired via QR: {}", request.device_name);
+            let response = paired_response(&state, &device_token, &request.device_name);
+            state.events.emit(ServerEvent::DevicePaired { name: request.device_name });
+            response
+        }
+        Err(e) => {
+            tracing::warn!("QR pairing rejected: {}", e.as_str());
+            (StatusCode::UNAUTHORIZED, Json(json!({ "error": e.as_str() }))).into_response()
+        }
+    }
+}
+
+/// PIN 配对请求。
+#[derive(Debug, Deserialize)]
+pub struct PairPinRequest {
+    /// 托盘上显示的 6 位 PIN
+    pub pin: String,
+    /// 手机设备名称
+    #[serde(default)]
+    pub device_name: String,
+}
+
+/// 用托盘显示的 PIN 换取设备令牌，按来源 IP 限流并在失败过多时全局熔断。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `request` - 配对请求
+pub async fn pair_pin(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    ValidJson(request): ValidJson<PairPinRequest>,
+) -> Response {
+    let ip = connect_info
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    let outcome = state.pairing.pair_with_pin(ip, &request.pin, &request.device_name);
+    tracing::info!(target: "fastsync::audit", "PIN pairing attempt from {}: {}", ip, outcome.as_str());
+
+    match outcome {
+        PinOutcome::Paired(device_token) => {
+            tracing::info!("Device paired via PIN: {}", request.device_name);
+            let response = paired_response(&state, &device_token, &request.device_name);
+            state.events.emit(ServerEvent::DevicePaired { name: request.device_name });
+            response
+        }
+        PinOutcome::InvalidPin { breaker_tripped } => {
+            if breaker_tripped {
+                tracing::warn!("Too many failed pairing attempts, PIN pairing paused");
+                show_pairing_paused_notification(&state);
+            }
+            (StatusCode::UNAUTHORIZED, Json(json!({ "error": "invalid pin" }))).into_response()
+        }
+        PinOutcome::LockedOut(retry_after) => retry_later(StatusCode::TOO_MANY_REQUESTS, "locked", retry_after),
+        PinOutcome::CircuitOpen(retry_after) => {
+            retry_later(StatusCode::SERVICE_UNAVAILABLE, "pairing disabled", retry_after)
+        }
+    }
+}
+
+/// 设备名称的最大字符数，过长的名称在通知中截断。
+const MAX_DEVICE_NAME_CHARS: usize = 64;
+
+/// 确认式配对请求。
+#[derive(Debug, Deserialize)]
+pub struct PairRequestBody {
+    /// 手机设备名称，显示在确认通知中
+    #[serde(default)]
+    pub device_name: String,
+}
+
+/// 发起需在电脑上确认的配对，返回请求标识与 PIN，手机显示 PIN 并轮询 `/pair/status/{id}`。
+/// 携带有效设备令牌的手机已配对，直接返回 `paired`。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `headers` - 请求头，用于识别已配对设备
+/// * `request` - 配对请求
+pub async fn request_pairing(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<PairRequestBody>,
+) -> Response {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if let Some(TokenStatus::Valid(device_id)) = token.map(|token| state.pairing.authenticate(token.trim())) {
+        if device_id != GUEST_DEVICE_ID {
+            return Json(json!({ "status": "paired", "device_id": device_id })).into_response();
+        }
+    }
+
+    // 没有桌面会话时无法弹出确认通知
+    if state.mode != RunMode::Desktop {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "confirmation unavailable" }))).into_response();
+    }
+
+    let ip = connect_info
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let device_name = device_display_name(&request.device_name);
+
+    match state.pairing.request_pairing(ip, &device_name) {
+        PairRequestOutcome::Created { id, pin } => {
+            tracing::info!(target: "fastsync::audit", "Pairing request from {} ({})", ip, device_name);
+            show_pair_request_notification(&state, &id, &device_name, &pin);
+            (
+                StatusCode::ACCEPTED,
+                Json(json!({ "id": id, "pin": pin, "expires_in": PAIR_REQUEST_TTL.as_secs() })),
+            )
+                .into_response()
+        }
+        PairRequestOutcome::Busy => retry_later(StatusCode::TOO_MANY_REQUESTS, "request pending", PAIR_REQUEST_TTL),
+        PairRequestOutcome::LockedOut(retry_after) => retry_later(StatusCode::TOO_MANY_REQUESTS, "locked", retry_after),
+        PairRequestOutcome::CircuitOpen(retry_after) => {
+            retry_later(StatusCode::SERVICE_UNAVAILABLE, "pairing disabled", retry_after)
+        }
+    }
+}
+
+/// 查询配对请求的状态，接受后返回设备令牌，结果只返回一次。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址，须与发起请求的地址一致
+/// * `id` - 请求标识
+pub async fn pair_status(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Path(id): Path<String>,
+) -> Response {
+    let ip = connect_info
+        .map(|ConnectInfo(addr)| addr.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    match state.pairing.request_status(&id, ip) {
+        PairRequestStatus::Pending(remaining) => {
+            Json(json!({ "status": "pending", "expires_in": remaining.as_secs() })).into_response()
+        }
+        PairRequestStatus::Accepted { device_token, device_name } => {
+            tracing::info!("Device paired via confirmation: {}", device_name);
+            let response = match paired_body(&state, &device_token, &device_name) {
+                Some(mut body) => {
+                    body["status"] = json!("accepted");
+                    Json(body).into_response()
+                }
+                None => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            };
+            state.events.emit(ServerEvent::DevicePaired { name: device_name });
+            response
+        }
+        PairRequestStatus::Rejected => (StatusCode::FORBIDDEN, Json(json!({ "status": "rejected" }))).into_response(),
+        PairRequestStatus::Expired => (StatusCode::GONE, Json(json!({ "status": "expired" }))).into_response(),
+        PairRequestStatus::Unknown => {
+            (StatusCode::NOT_FOUND, Json(json!({ "error": "unknown request" }))).into_response()
+        }
+    }
+}
+
+/// 通知中显示的设备名称，去掉控制字符并限制长度。
+fn device_display_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_DEVICE_NAME_CHARS)
+        .collect();
+    match name.trim() {
+        "" => "未命名设备".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// 弹出配对确认通知，不受免打扰影响。
+fn show_pair_request_notification(state: &AppState, id: &str, device_name: &str, pin: &str) {
+    let mut notification = Notification::new(&format!("pair_request_{}", id), "配对请求");
+    notification
+        .body
+        .push(format!("设备 ‘{}’ 请求配对，PIN: {}", device_name, pin));
+    notification.body.push("请确认手机上显示的 PIN 与此一致".to_string());
+    notification.actions.push(NotificationAction::new("accept", "接受"));
+    notification.actions.push(NotificationAction::new("reject", "拒绝"));
+    notification.expires_in = PAIR_REQUEST_TTL;
+    notification.long_duration = true;
+
+    let pairing = state.pairing.clone();
+    let id = id.to_string();
+    let on_action = Arc::new(move |action: &str| {
+        let accept = match action {
+            "accept" => true,
+            "reject" => false,
+            _ => return,
+        };
+        match pairing.decide_request(&id, accept) {
+            Some(name) => tracing::info!(target: "fastsync::audit", "Pairing request from {} {}", name, action),
+            None => tracing::info!("Pairing request already expired"),
+        }
+    });
+
+    let notifier = state.notifier.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notifier.show(notification, on_action) {
+            tracing::error!("Failed to show pairing request: {:?}", e);
+        }
+    });
+}
+
+/// 构造配对成功响应，启用 mTLS 时附带为该设备签发的客户端证书。
+fn paired_response(state: &AppState, device_token: &str, device_name: &str) -> Response {
+    match paired_body(state, device_token, device_name) {
+        Some(body) => Json(body).into_response(),
+        None => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// 配对成功响应的内容，签发客户端证书失败时撤销该设备并返回 None。
+#[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+fn paired_body(state: &AppState, device_token: &str, device_name: &str) -> Option<serde_json::Value> {
+    let mut body = json!({ "device_token": device_token });
+    // 共用的访问令牌只经配对下发，不出现在 mDNS 广播中
+    if let Some(token) = &state.config.read().unwrap().server.token {
+        body["access_token"] = json!(token.as_str());
+    }
+
+    #[cfg(feature = "tls")]
+    if let Some(authority) = &state.tls {
+        let TokenStatus::Valid(device_id) = state.pairing.authenticate(device_token) else {
+            return None;
+        };
+        match authority.issue_client_cert(&device_id, device_name) {
+            Ok(certificate) => {
+                body["device_id"] = json!(device_id);
+                body["mtls"] = json!(certificate);
+            }
+            Err(e) => {
+                tracing::error!("Failed to issue client certificate: {:?}", e);
+                state.revoke_device(&device_id);
+                return None;
+            }
+        }
+    }
+
+    Some(body)
+}
+
+/// 构造带 `Retry-After` 的错误响应。
+pub(crate) fn retry_later(status: StatusCode, error: &str, retry_after: Duration) -> Response {
+    // 向上取整，避免客户端提前重试
+    let secs = (retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1);
+    (
+        status,
+        [(header::RETRY_AFTER, secs.to_string())],
+        Json(json!({ "error": error, "retry_after": secs })),
+    )
+        .into_response()
+}
+
+/// 熔断时提醒用户有设备在反复尝试配对。
+fn show_pairing_paused_notification(state: &AppState) {
+    let mut notification = Notification::new("pairing_alert", "配对已暂停");
+    notification.body.push("短时间内配对失败次数过多，15 分钟内不再接受 PIN 配对".to_string());
+    notification.long_duration = true;
+
+    let notifier = state.notifier.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notifier.show(notification, Arc::new(|_: &str| {})) {
+            tracing::error!("Failed to show pairing alert: {:?}", e);
+        }
+    });
+}
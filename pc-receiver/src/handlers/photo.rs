@@ -3,278 +3,2340 @@
  * @Date: 2026-02-19
  */
 use axum::{
-    extract::Multipart,
-    http::StatusCode,
-};
-use std::sync::{Arc, Mutex, OnceLock};
-use windows::{
-    core::*,
-    Data::Xml::Dom::XmlDocument,
-    UI::Notifications::{ToastNotification, ToastNotificationManager},
-    Foundation::{DateTime, IReference, PropertyValue},
+    extract::{multipart::MultipartError, FromRequest, Multipart, Request},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
 };
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::{Stream, StreamExt, TryStreamExt};
+use image::codecs::jpeg::JpegEncoder;
+use image::metadata::Orientation;
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageDecoder, ImageReader, RgbImage, RgbaImage};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
 use zune_jpeg::JpegDecoder;
-use crate::handlers::store_notification;
+use zune_png::zune_core::bit_depth::BitDepth;
+use zune_png::zune_core::colorspace::ColorSpace;
+use zune_png::{InterlaceMethod, PngDecoder};
+use fastsync_core::timeline::CaptureTime;
+use crate::audit::ItemAudit;
+use crate::body_limit;
+use crate::burst::{Arrival, BurstImage};
+use crate::clipboard::{ClipboardBackend, ClipboardImage};
+use crate::decode_pool::DecodePool;
+use crate::events::ServerEvent;
+use crate::handlers::response::{UploadError, UploadResponse};
+use crate::image_format::{detect_image_format, ImageFormat, SNIFF_LEN};
+use crate::metadata::{strip_jpeg_metadata, MetadataStripping, StripOutcome};
+use crate::notifier::{ActionHandler, Notification, NotificationAction, Notifier};
+use crate::payload::{PayloadContext, PayloadHandler, PayloadOutcome};
+use crate::policy::{ContentInfo, PolicyViolation};
+use crate::resumable::FinishedUpload;
+use crate::state::RunMode;
+use crate::storage::StorageIssue;
+use crate::temp_files;
+use crate::timings::Stage;
+use crate::video::{self, detect_video_format, VideoFormat};
+
+/// 通知大图预览最长边的像素数，超过该尺寸的图片会缩小后再交给通知。
+const PREVIEW_SIZE: u32 = 1280;
+
+/// 通知大图预览的 JPEG 质量。
+const PREVIEW_QUALITY: u8 = 80;
+
+/// HEIC 转换为 JPEG 时的质量。
+const TRANSCODE_QUALITY: u8 = 90;
+
+/// 通知大图允许的最大文件大小，超过后系统不显示图片。
+const MAX_HERO_BYTES: u64 = 3 * 1024 * 1024;
+
+/// 保存视频时对话框列出的扩展名。
+const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "webm", "mkv"];
+
+/// 手机端提供整个图片内容 SHA-256 的请求头。
+const CHECKSUM_HEADER: &str = "x-content-sha256";
+
+/// 原始请求体上传时提供文件名的请求头。
+const FILE_NAME_HEADER: &str = "x-file-name";
+
+/// 连拍分组通知的分组名，批量上传时不再逐张追加序号。
+const BURST_GROUP: &str = "photo_burst";
+
+/// 连拍分组通知的有效期，与单张图片的通知一致。
+const BURST_EXPIRES_IN: Duration = Duration::from_secs(30);
+
+/// 临时图片文件名的序号，同一毫秒内的并发上传不会写入同一个文件。
+static TEMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 内置图片处理器，路由 `POST /upload`。
+pub(crate) struct PhotoHandler;
+
+impl PayloadHandler for PhotoHandler {
+    fn path(&self) -> &str {
+        "/upload"
+    }
+
+    fn capability(&self) -> &str {
+        "photo"
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            let incoming = content_length(request.headers());
+            let checksum = checksum_header(request.headers());
+            match Multipart::from_request(request, &()).await {
+                Ok(multipart) => upload(ctx, multipart, incoming, checksum, false).await,
+                Err(rejection) => multipart_rejection(rejection),
+            }
+        })
+    }
+}
+
+/// 内置文件处理器，路由 `POST /upload/file`。
+/// 请求格式与 `/upload` 相同，但无论是否开启通用文件模式都接收任意文件：图片与视频照常处理，
+/// 其他文件的通知只提供另存，不会自动保存。浏览器上传页面使用此接口。
+pub(crate) struct FileUploadHandler;
+
+impl PayloadHandler for FileUploadHandler {
+    fn path(&self) -> &str {
+        "/upload/file"
+    }
+
+    fn capability(&self) -> &str {
+        "file"
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            let incoming = content_length(request.headers());
+            let checksum = checksum_header(request.headers());
+            match Multipart::from_request(request, &()).await {
+                Ok(multipart) => upload(ctx, multipart, incoming, checksum, true).await,
+                Err(rejection) => multipart_rejection(rejection),
+            }
+        })
+    }
+}
+
+/// 请求不是 multipart/form-data 时的处理结果。
+fn multipart_rejection(rejection: axum::extract::multipart::MultipartRejection) -> PayloadOutcome {
+    tracing::warn!("Rejected upload: {}", rejection.body_text());
+    let response = UploadResponse::failure(UploadError::DecodeFailed).with_detail("message", rejection.body_text());
+    PayloadOutcome::new(response)
+}
+
+/// 内置原始请求体上传处理器，路由 `PUT /upload/raw`。
+/// 请求体就是图片本身，类型取自 `Content-Type`，文件名取自可选的 `X-File-Name`，
+/// 不便构造 Multipart 表单的发送端（Tasker、HTTP Shortcuts 等）可以直接发送文件。
+pub(crate) struct RawUploadHandler;
+
+impl PayloadHandler for RawUploadHandler {
+    fn path(&self) -> &str {
+        "/upload/raw"
+    }
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+
+    fn capability(&self) -> &str {
+        "photo"
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(upload_raw(ctx, request))
+    }
+}
+
+/// 处理原始请求体上传，之后与表单上传的单张图片相同。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `request` - 请求体为图片内容的请求
+///
+/// # Returns
+/// 处理结果，缺少 `Content-Type` 或类型不受支持时为 415
+async fn upload_raw(ctx: PayloadContext, request: Request) -> PayloadOutcome {
+    let headers = request.headers();
+    let declared = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+    // 接收通用文件时任意类型都可以，否则声明的类型必须是支持的图片或视频格式
+    let accept_files = ctx.state.config.read().unwrap().photos.accept_files;
+    let supported = declared.as_deref().is_some_and(|mime| {
+        accept_files || ImageFormat::from_mime(mime).is_some() || VideoFormat::from_mime(mime).is_some()
+    });
+    if !supported {
+        tracing::warn!("Rejected raw upload with Content-Type {:?}", declared);
+        let expected: Vec<&str> = ImageFormat::ALL
+            .iter()
+            .map(ImageFormat::mime)
+            .chain(VideoFormat::ALL.iter().map(VideoFormat::mime))
+            .collect();
+        let response = UploadResponse::failure(UploadError::UnsupportedFormat)
+            .with_detail("field", "Content-Type")
+            .with_detail("expected", expected)
+            .with_detail("received", declared);
+        return PayloadOutcome::new(response);
+    }
+
+    let incoming = content_length(headers);
+    let checksum = checksum_header(headers);
+    let meta = UploadMeta {
+        mime: declared,
+        file_name: headers
+            .get(FILE_NAME_HEADER)
+            .and_then(|value| std::str::from_utf8(value.as_bytes()).ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty()),
+    };
+    let auto_save_dir = match auto_save_target(&ctx, incoming) {
+        Ok(dir) => dir,
+        Err(issue) => return PayloadOutcome::new(issue),
+    };
+
+    // 原始请求体不经过 Multipart 的大小限制，未声明长度的请求在读取时计数
+    let limit = ctx.state.body_limits.upload;
+    let mut received = 0;
+    let chunks = request.into_body().into_data_stream().map(move |chunk| {
+        let chunk = chunk.map_err(RawBodyError::Read)?;
+        received += chunk.len();
+        if received > limit {
+            return Err(RawBodyError::TooLarge);
+        }
+        Ok(chunk)
+    });
+    let field = match process_received_image(&ctx, chunks, meta, false).await {
+        Ok(field) => field,
+        Err(RawBodyError::TooLarge) => {
+            tracing::warn!("Raw upload exceeded {} bytes, rejected", limit);
+            return PayloadOutcome::new(body_limit::too_large(limit, None));
+        }
+        Err(RawBodyError::Read(e)) => {
+            tracing::warn!("Failed to read raw upload: {}", e);
+            let response = UploadResponse::failure(UploadError::DecodeFailed).with_detail("message", e.to_string());
+            return PayloadOutcome::new(response);
+        }
+    };
+    ctx.mark(Stage::Received);
+
+    let checksums = checksum.into_iter().collect();
+    accept_images(ctx, vec![field], checksums, None, auto_save_dir).await
+}
+
+/// 读取原始请求体失败的原因。
+enum RawBodyError {
+    /// 连接中断等读取错误
+    Read(axum::Error),
+    /// 超过载荷接口的请求体上限
+    TooLarge,
+}
+
+/// 请求声明的请求体大小，用于预检保存目录的剩余空间，未声明时为 0。
+fn content_length(headers: &HeaderMap) -> u64 {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+/// `X-Content-SHA256` 请求头的值。
+fn checksum_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CHECKSUM_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string())
+}
+
+/// 处理图片上传请求。表单中每个名为 `data` 或 `data[]` 的字段都是一张图片。
+/// 表单中的 `sha256` 字段按顺序对应各张图片；只有一张图片时也可以使用 `X-Content-SHA256` 请求头。
+/// 提供了校验值的图片与收到的内容不一致时拒绝该图片，手机端可以重新上传。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `multipart` - 包含图片数据的 Multipart 表单
+/// * `incoming` - 请求体大小，用于预检保存目录的剩余空间
+/// * `checksum` - `X-Content-SHA256` 请求头的值
+/// * `any_file` - 是否接收无法识别为图片的文件，`/upload/file` 始终接收
+///
+/// # Returns
+/// 处理结果，响应体为 `UploadResponse`（200 OK 表示至少接收了一张图片或内容与最近的上传重复，507 表示保存目录不可用）
+async fn upload(
+    ctx: PayloadContext,
+    mut multipart: Multipart,
+    incoming: u64,
+    checksum: Option<String>,
+    any_file: bool,
+) -> PayloadOutcome {
+    // 读取请求体之前先预检
+    let auto_save_dir = match auto_save_target(&ctx, incoming) {
+        Ok(dir) => dir,
+        Err(issue) => return PayloadOutcome::new(issue),
+    };
+
+    let mut images = Vec::new();
+    let mut checksums = Vec::new();
+    let mut captured_at = None;
+
+    // 请求体读取失败时整个请求作废，已写入的临时文件随之删除
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return multipart_failure(e, ctx.state.body_limits.upload),
+        };
+        let name = field.name().unwrap_or("").to_string();
+
+        if name == "data" || name == "data[]" {
+            let meta = UploadMeta {
+                mime: field.content_type().map(str::to_string),
+                file_name: field.file_name().map(str::to_string),
+            };
+            match process_received_image(&ctx, field, meta, any_file).await {
+                Ok(image) => images.push(image),
+                Err(e) => return multipart_failure(e, ctx.state.body_limits.upload),
+            }
+        } else if name == "sha256" {
+            match field.text().await {
+                Ok(text) => checksums.push(text.trim().to_string()),
+                Err(e) => return multipart_failure(e, ctx.state.body_limits.upload),
+            }
+        } else if name == "captured_at" {
+            captured_at = field.text().await.ok().and_then(|text| text.trim().parse().ok());
+        }
+    }
+
+    ctx.mark(Stage::Received);
+
+    if images.is_empty() {
+        tracing::error!("Missing data");
+        return PayloadOutcome::new(UploadResponse::failure(UploadError::MissingField).with_detail("field", "data"));
+    }
+
+    if let (true, Some(checksum)) = (checksums.is_empty(), checksum) {
+        if images.len() == 1 {
+            checksums.push(checksum);
+        } else {
+            tracing::warn!("{} header ignored for a batch of {} images", CHECKSUM_HEADER, images.len());
+        }
+    }
+    accept_images(ctx, images, checksums, captured_at, auto_save_dir).await
+}
+
+/// 自动保存目录不可用时桌面模式退回到通知中的保存对话框。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `incoming` - 即将写入的字节数
+///
+/// # Returns
+/// 可用的自动保存目录，未启用或已退回保存对话框时为 None
+fn auto_save_target(ctx: &PayloadContext, incoming: u64) -> Result<Option<PathBuf>, StorageIssue> {
+    let Some(dir) = ctx.state.auto_save.target_for(ctx.device.settings.auto_save) else {
+        let _ = ctx.state.storage.check(&std::env::temp_dir(), incoming);
+        return Ok(None);
+    };
+    if let Err(issue) = ctx.state.storage.check(&dir, incoming) {
+        let fallback = ctx.state.mode == RunMode::Desktop
+            && ctx.state.storage.check(&std::env::temp_dir(), incoming).is_ok();
+        if !fallback {
+            return Err(issue);
+        }
+        tracing::warn!("Auto-save directory unavailable, falling back to the save dialog");
+        return Ok(None);
+    }
+    Ok(Some(dir))
+}
+
+/// 按顺序处理已写入临时目录的图片：校验、去重、内容策略、自动保存或通知。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `images` - 各图片的接收结果、声明的类型与文件名
+/// * `checksums` - 手机端提供的 SHA-256，按顺序对应各图片
+/// * `captured_at` - 手机上的拍摄时间
+/// * `auto_save_dir` - 可用的自动保存目录
+///
+/// # Returns
+/// 处理结果，响应体为 `UploadResponse`
+async fn accept_images(
+    ctx: PayloadContext,
+    images: Vec<ReceivedField>,
+    checksums: Vec<String>,
+    captured_at: Option<i64>,
+    auto_save_dir: Option<PathBuf>,
+) -> PayloadOutcome {
+    let total = images.len();
+    let capture = ctx.capture_time(captured_at);
+    let device = ctx.device.device_id.clone();
+    let mut ids = Vec::new();
+    let mut bytes = 0;
+    let mut last_hash = None;
+    // 所有收到的图片都提供了校验值并且一致
+    let mut verified = true;
+    let mut failures = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut notifications = Vec::new();
+
+    for (index, (mut image, mime, file_name)) in images.into_iter().enumerate() {
+        let received_hash = image.as_ref().map(|image| image.hash.clone()).ok();
+        bytes += image.as_ref().map_or(0, |image| image.size);
+        last_hash.clone_from(&received_hash);
+
+        // 内容与校验值不一致时丢弃，临时文件随之删除
+        if let Some(actual) = &received_hash {
+            match checksums.get(index) {
+                Some(expected) if !expected.eq_ignore_ascii_case(actual) => {
+                    tracing::warn!(
+                        "Image {} of {} checksum mismatch: expected {}, received {}",
+                        index + 1,
+                        total,
+                        expected,
+                        actual
+                    );
+                    image = Err(ImageFailure::new(UploadError::ChecksumMismatch));
+                }
+                Some(_) => tracing::debug!("Image {} of {} checksum verified", index + 1, total),
+                None => verified = false,
+            }
+        }
+
+        // 重试上传的相同内容直接丢弃，临时文件随之删除
+        let hash = received_hash.filter(|_| image.is_ok());
+        if let Some(hash) = &hash {
+            if let Some(original) = ctx.state.uploads.find(device.as_deref(), hash, Instant::now()) {
+                tracing::info!("Image {} of {} duplicates {}, skipped", index + 1, total, original);
+                duplicates.push((index, original));
+                continue;
+            }
+        }
+
+        let content = ContentInfo {
+            mime: mime.as_deref(),
+            file_name: file_name.as_deref(),
+            size: image.as_ref().map_or(0, |image| image.size),
+        };
+        // 只有桌面模式会显示通知，其他模式不必识别
+        let recognized = match &image {
+            Ok(image) if image.format.is_some() && ctx.state.mode == RunMode::Desktop => {
+                recognize(&ctx, &image.file.path).await
+            }
+            _ => Recognized::default(),
+        };
+        let result = image.and_then(|image| accept_image(&ctx, image, &content, capture, auto_save_dir.as_deref(), recognized));
+        match result {
+            Ok((id, notification)) => {
+                if let Some(hash) = &hash {
+                    ctx.state.uploads.record(device.as_deref(), hash, &id, Instant::now());
+                }
+                ids.push(id);
+                if let Some((mut notification, on_action)) = notification {
+                    if notification.group == BURST_GROUP {
+                        // 同一请求中的分组通知只需显示最后一条
+                        notifications.retain(|(shown, _): &(Notification, ActionHandler)| shown.tag != notification.tag);
+                    } else if total > 1 {
+                        notification.title = format!("{} ({}/{})", notification.title, index + 1, total);
+                    }
+                    notifications.push((notification, on_action));
+                }
+            }
+            Err(failure) => {
+                tracing::warn!("Image {} of {} rejected: {}", index + 1, total, failure.reason);
+                failures.push((index, failure));
+            }
+        }
+    }
+
+    // 只有一张图片时才返回内容哈希，批量上传的哈希无法对应到具体的图片
+    let hash = if total == 1 { last_hash } else { None };
+
+    // 单张图片被拒绝时保持原有的响应
+    if total == 1 && ids.is_empty() {
+        if let Some((_, failure)) = failures.pop() {
+            return PayloadOutcome::new(failure.into_response(bytes, hash));
+        }
+        if let Some((_, original)) = duplicates.pop() {
+            let response = UploadResponse::success(bytes, hash)
+                .with_detail("verified", verified)
+                .with_detail("duplicate", true)
+                .with_detail("id", original);
+            return PayloadOutcome::new(response);
+        }
+    }
+
+    let response = match failures.first() {
+        Some((_, failure)) if ids.is_empty() && duplicates.is_empty() => UploadResponse::rejected(failure.status, failure.error),
+        _ => UploadResponse::success(0, None),
+    };
+    let mut response = response
+        .with_content(bytes, hash)
+        .with_detail("received", ids.len())
+        .with_detail("verified", verified && !ids.is_empty())
+        .with_detail(
+            "failed",
+            failures
+                .iter()
+                .map(|(index, failure)| json!({ "index": index, "error": failure.error, "reason": failure.reason }))
+                .collect::<Vec<_>>(),
+        )
+        .with_detail(
+            "duplicates",
+            duplicates
+                .iter()
+                .map(|(index, original)| json!({ "index": index, "id": original }))
+                .collect::<Vec<_>>(),
+        );
+    if total == 1 {
+        response = response.with_detail("id", &ids[0]);
+    }
+    response = response.with_detail("ids", ids);
+
+    let mut outcome = PayloadOutcome::new(response);
+    for (mut notification, on_action) in notifications {
+        if let Some(hero) = notification.hero_image.clone() {
+            let span = tracing::Span::current();
+            let preview = tokio::task::spawn_blocking(move || span.in_scope(|| hero_preview(&hero)))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|preview| preview);
+            match preview {
+                Ok(Some(preview)) => {
+                    notification.temp_files.push(preview.clone());
+                    notification.hero_image = Some(preview);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Hero preview unavailable, showing text-only toast: {:#}", e);
+                    notification.hero_image = None;
+                }
+            }
+        }
+        outcome = outcome.with_notification(notification, on_action);
+    }
+    outcome
+}
+
+/// 收到的一张图片：接收结果、类型与文件名。
+type ReceivedField = (Result<ReceivedImage, ImageFailure>, Option<String>, Option<String>);
+
+/// 批量上传中一张图片被拒绝的原因。
+struct ImageFailure {
+    status: StatusCode,
+    /// 响应中 `failed` 列表的错误标识
+    error: &'static str,
+    /// 具体原因，内容策略拒绝时为策略的原因标识
+    reason: &'static str,
+    /// 被内容策略拒绝时的具体原因
+    violation: Option<PolicyViolation>,
+}
+
+impl ImageFailure {
+    fn new(error: UploadError) -> Self {
+        Self {
+            status: error.status(),
+            error: error.code(),
+            reason: error.code(),
+            violation: None,
+        }
+    }
+
+    /// 只上传一张图片时直接返回的响应，内容策略的拒绝保持策略自身的响应。
+    ///
+    /// # Arguments
+    /// * `bytes` - 收到的字节数
+    /// * `hash` - 图片内容的 SHA-256
+    fn into_response(self, bytes: u64, hash: Option<String>) -> Response {
+        match self.violation {
+            Some(violation) => violation.into_response(),
+            None => UploadResponse::rejected(self.status, self.error)
+                .with_content(bytes, hash)
+                .into_response(),
+        }
+    }
+}
+
+impl From<PolicyViolation> for ImageFailure {
+    fn from(violation: PolicyViolation) -> Self {
+        Self {
+            status: violation.status(),
+            error: "policy",
+            reason: violation.reason(),
+            violation: Some(violation),
+        }
+    }
+}
+
+/// 按内容策略接收一张已写入临时目录的图片，自动保存或生成通知。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `image` - 已写入的临时图片
+/// * `content` - 图片的类型与大小
+/// * `capture` - 图片的拍摄时间
+/// * `auto_save_dir` - 可用的自动保存目录
+/// * `recognized` - 从图片中识别出的网址与文字
+///
+/// # Returns
+/// 图片标识，以及桌面模式下需要显示的通知
+fn accept_image(
+    ctx: &PayloadContext,
+    image: ReceivedImage,
+    content: &ContentInfo,
+    capture: CaptureTime,
+    auto_save_dir: Option<&Path>,
+    recognized: Recognized,
+) -> Result<(String, Option<(Notification, ActionHandler)>), ImageFailure> {
+    ctx.check_content("photo", content)?;
+
+    let audit = ctx.audit("photo").with_capture(capture);
+    if let Some(video) = image.video {
+        return accept_video(ctx, image, content, video, audit, auto_save_dir);
+    }
+
+    tracing::info!("Image received successfully, size: {} bytes", image.size);
+    let id = audit.item_id().to_string();
+    let size = image.size as usize;
+    let recognized = Recognized {
+        link: recognized.link.filter(|_| ctx.action_allowed("photo", content, "open_link")),
+        text: recognized.text.filter(|_| ctx.action_allowed("photo", content, "copy_text")),
+    };
+
+    // 通用文件不自动保存到图片目录，只提示用户另存
+    let Some(format) = image.format else {
+        let path = image.file.persist();
+        return Ok((id, Some(build_file_notification(ctx, content, audit, path))));
+    };
+
+    if let Some(dir) = auto_save_dir {
+        ctx.check_action("photo", content, "auto_save")?;
+        let result = auto_save_image(dir, &image.file.path, format, ctx.state.config.read().unwrap().photos.heic_to_jpeg);
+        audit.record_result("auto_save", result.as_deref().ok(), &result);
+        return match result {
+            Ok(path) => {
+                tracing::info!("Image auto-saved to {:?}", path);
+                image.file.persist();
+                ctx.emit(ServerEvent::PhotoReceived { path: path.clone(), size });
+                register_photo(ctx, &audit, Arc::new(Mutex::new(path.clone())), image.hash);
+                // 桌面模式下仍提示一次保存位置，非桌面模式没有通知
+                let notification =
+                    (ctx.state.mode == RunMode::Desktop).then(|| build_saved_notification(ctx, &path, None, audit, recognized));
+                Ok((id, notification))
+            }
+            Err(e) => {
+                tracing::error!("Failed to auto-save image: {:?}", e);
+                Err(ImageFailure::new(UploadError::IoError))
+            }
+        };
+    }
+
+    ctx.mark(Stage::Preview);
+    let image_path = image.file.persist();
+    ctx.emit(ServerEvent::PhotoReceived {
+        path: image_path.clone(),
+        size,
+    });
+    // 执行路径类操作后文件会移动到 received 目录，通知按钮与缩略图都使用新路径
+    let image_path = Arc::new(Mutex::new(image_path));
+    register_photo(ctx, &audit, image_path.clone(), image.hash);
+    let Some(arrival) = join_burst(ctx, content, &audit, &image_path) else {
+        // 不再传入 data，只传入路径
+        let notification = build_photo_notification(ctx, content, format, audit, capture, image_path, recognized);
+        return Ok((id, Some(notification)));
+    };
+    if arrival.images.len() > 1 {
+        return Ok((id, Some(build_burst_notification(ctx, content, &arrival))));
+    }
+
+    // 分组的第一张照常通知，使用分组的标签，之后的分组通知替换它；临时文件交由分组删除
+    let path = image_path.lock().unwrap().clone();
+    let (mut notification, on_action) = build_photo_notification(ctx, content, format, audit, capture, image_path, recognized);
+    notification.tag = burst_tag(&arrival.id);
+    notification.temp_files.retain(|temp| *temp != path);
+    Ok((id, Some((notification, on_action))))
+}
+
+/// 桌面模式下将未保存的图片加入来源设备的连拍分组。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `content` - 图片的类型与文件名
+/// * `audit` - 该图片的审计句柄
+/// * `image_path` - 图片当前路径
+///
+/// # Returns
+/// 加入后的分组，未启用分组时为 None
+fn join_burst(ctx: &PayloadContext, content: &ContentInfo, audit: &ItemAudit, image_path: &Arc<Mutex<PathBuf>>) -> Option<Arrival> {
+    let window = ctx.state.config.read().unwrap().photos.burst_window;
+    if ctx.state.mode != RunMode::Desktop || window.is_zero() {
+        return None;
+    }
+    let started = ctx.timings.lock().unwrap().started_at();
+    let image = BurstImage {
+        path: image_path.clone(),
+        stem: content.file_name.and_then(received_stem),
+        audit: audit.clone(),
+    };
+    let origin = ctx.device.origin().unwrap_or_default();
+    Some(ctx.state.bursts.arrive(&origin, started, image, window, BURST_EXPIRES_IN))
+}
+
+/// 接收一个已写入临时目录的视频，自动保存或生成通知。视频不解码，也不登记缩略图。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `video` - 已写入的临时视频
+/// * `content` - 视频的类型与大小
+/// * `format` - 视频格式
+/// * `audit` - 该视频的审计句柄
+/// * `auto_save_dir` - 可用的自动保存目录
+///
+/// # Returns
+/// 视频标识，以及桌面模式下需要显示的通知
+fn accept_video(
+    ctx: &PayloadContext,
+    video: ReceivedImage,
+    content: &ContentInfo,
+    format: VideoFormat,
+    audit: ItemAudit,
+    auto_save_dir: Option<&Path>,
+) -> Result<(String, Option<(Notification, ActionHandler)>), ImageFailure> {
+    tracing::info!("Video received successfully, size: {} bytes", video.size);
+    let id = audit.item_id().to_string();
+    let size = video.size as usize;
+
+    if let Some(dir) = auto_save_dir {
+        ctx.check_action("photo", content, "auto_save")?;
+        let result = auto_save_file(dir, &video.file.path, format.extension());
+        audit.record_result("auto_save", result.as_deref().ok(), &result);
+        return match result {
+            Ok(path) => {
+                tracing::info!("Video auto-saved to {:?}", path);
+                video.file.persist();
+                ctx.emit(ServerEvent::VideoReceived { path: path.clone(), size });
+                let notification = (ctx.state.mode == RunMode::Desktop)
+                    .then(|| build_saved_notification(ctx, &path, Some(format), audit, Recognized::default()));
+                Ok((id, notification))
+            }
+            Err(e) => {
+                tracing::error!("Failed to auto-save video: {:?}", e);
+                Err(ImageFailure::new(UploadError::IoError))
+            }
+        };
+    }
+
+    let path = video.file.persist();
+    ctx.emit(ServerEvent::VideoReceived { path: path.clone(), size });
+    Ok((id, Some(build_video_notification(ctx, content, format, audit, path))))
+}
+
+/// 读取表单失败时的响应，例如请求中断或超过大小限制。
+///
+/// # Arguments
+/// * `e` - 读取错误
+/// * `limit` - 载荷接口的请求体上限，超过时在响应中给出
+fn multipart_failure(e: MultipartError, limit: usize) -> PayloadOutcome {
+    tracing::warn!("Failed to read image upload: {}", e.body_text());
+    let response = if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        body_limit::too_large(limit, None)
+    } else {
+        UploadResponse::failure(UploadError::DecodeFailed)
+    };
+    PayloadOutcome::new(response.with_detail("message", e.body_text()))
+}
+
+/// 记录收到的图片以便手机拉取缩略图。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `audit` - 该图片的审计句柄，图片标识与审计记录一致
+/// * `path` - 图片文件路径
+/// * `hash` - 图片内容的 SHA-256
+fn register_photo(ctx: &PayloadContext, audit: &ItemAudit, path: Arc<Mutex<PathBuf>>, hash: String) {
+    ctx.state
+        .photos
+        .register(audit.item_id(), path, hash, ctx.device.device_id.clone());
+}
+
+/// 正在写入的临时图片，未调用 `persist` 就被丢弃时删除文件。
+/// 请求中断、读取失败或内容被拒绝时都会丢弃，不会留下写了一半的文件。
+struct PartialFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl PartialFile {
+    /// 保留文件并返回其路径。
+    fn persist(mut self) -> PathBuf {
+        self.keep = true;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to delete partial upload {:?}: {:?}", self.path, e);
+            }
+        } else {
+            tracing::debug!("Deleted partial upload {:?}", self.path);
+        }
+    }
+}
+
+/// 已完整写入临时目录的图片。
+struct ReceivedImage {
+    file: PartialFile,
+    size: u64,
+    /// 根据文件头识别出的格式，文件扩展名与之一致；视频与通用文件模式下收到的非图片为 None
+    format: Option<ImageFormat>,
+    /// 根据文件头识别出的视频格式，不是视频时为 None
+    video: Option<VideoFormat>,
+    /// 内容的 SHA-256，写入时同步计算
+    hash: String,
+}
+
+/// 上传时声明的图片信息。
+struct UploadMeta {
+    /// 声明的 MIME 类型
+    mime: Option<String>,
+    /// 手机上的文件名
+    file_name: Option<String>,
+}
+
+/// 接收一张图片并识别格式，表单上传与原始请求体上传共用，两者的行为保持一致。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `bytes` - 图片内容的数据块
+/// * `meta` - 声明的类型与文件名
+/// * `any_file` - 不论配置如何都接收无法识别为图片的文件
+///
+/// # Returns
+/// 交给 `accept_images` 的接收结果，格式不受支持或写入失败时其中为失败原因；读取请求体失败时整个请求作废
+async fn process_received_image<E>(
+    ctx: &PayloadContext,
+    bytes: impl Stream<Item = Result<Bytes, E>>,
+    meta: UploadMeta,
+    any_file: bool,
+) -> Result<ReceivedField, E> {
+    let UploadMeta { mut mime, file_name } = meta;
+    let (strip, accept_files) = {
+        let config = ctx.state.config.read().unwrap();
+        (config.photos.strip_metadata, config.photos.accept_files || any_file)
+    };
+    let image = match receive_image(bytes, strip, accept_files).await {
+        Ok(received) => {
+            // 图片的类型以文件头为准，声明的类型不可信，否则声明为 PNG 的 GIF 能绕过策略的类型限制
+            if let Some(format) = received.format {
+                mime = Some(format.mime().to_string());
+            } else if let Some(video) = received.video {
+                mime = Some(video.mime().to_string());
+            }
+            Ok(received)
+        }
+        Err(ReceiveError::Body(e)) => return Err(e),
+        Err(ReceiveError::Empty) => {
+            tracing::warn!("Upload {:?} is empty, rejected", file_name);
+            Err(ImageFailure::new(UploadError::MissingField))
+        }
+        Err(ReceiveError::UnsupportedFormat) => {
+            tracing::warn!("Upload {:?} is not a recognized image, rejected", file_name);
+            Err(ImageFailure::new(UploadError::UnsupportedFormat))
+        }
+        Err(ReceiveError::Write(e)) => {
+            tracing::error!("Failed to write uploaded image {:?}: {:?}", file_name, e);
+            Err(ImageFailure::new(UploadError::IoError))
+        }
+    };
+    Ok((image, mime, file_name))
+}
+
+/// 接收图片失败的原因。
+enum ReceiveError<E> {
+    /// 读取请求体失败，整个请求作废
+    Body(E),
+    /// 内容为空，手机端读取文件失败时常发送空的字段
+    Empty,
+    /// 文件头不是支持的图片格式
+    UnsupportedFormat,
+    /// 写入临时文件失败
+    Write(anyhow::Error),
+}
+
+/// 将图片内容逐块写入临时目录，不在内存中保留整张图片。
+/// 先读取文件头识别格式，临时文件使用对应的扩展名，系统按扩展名预览通知中的大图。
+///
+/// # Arguments
+/// * `chunks` - 表单中的 `data` 字段或原始请求体
+/// * `strip` - 写入完成后对 JPEG 执行的元数据清理
+/// * `accept_files` - 是否接收无法识别为图片的文件
+///
+/// # Returns
+/// 写入完成的临时文件
+async fn receive_image<E>(
+    chunks: impl Stream<Item = Result<Bytes, E>>,
+    strip: MetadataStripping,
+    accept_files: bool,
+) -> Result<ReceivedImage, ReceiveError<E>> {
+    use anyhow::Context;
+
+    let mut chunks = std::pin::pin!(chunks);
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    let mut pending = Vec::new();
+    while header.len() < SNIFF_LEN {
+        match chunks.try_next().await.map_err(ReceiveError::Body)? {
+            Some(chunk) => {
+                let take = (SNIFF_LEN - header.len()).min(chunk.len());
+                header.extend_from_slice(&chunk[..take]);
+                pending.push(chunk);
+            }
+            None => break,
+        }
+    }
+    if header.is_empty() {
+        return Err(ReceiveError::Empty);
+    }
+    let format = detect_image_format(&header);
+    let video = format.is_none().then(|| detect_video_format(&header)).flatten();
+    if format.is_none() && video.is_none() && !accept_files {
+        return Err(ReceiveError::UnsupportedFormat);
+    }
+
+    let partial = PartialFile {
+        path: temp_image_path(format, video),
+        keep: false,
+    };
+    let mut file = tokio::fs::File::create(&partial.path)
+        .await
+        .with_context(|| format!("Failed to create {:?}", partial.path))
+        .map_err(ReceiveError::Write)?;
+
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut pending = pending.into_iter();
+    loop {
+        let chunk = match pending.next() {
+            Some(chunk) => chunk,
+            None => match chunks.try_next().await.map_err(ReceiveError::Body)? {
+                Some(chunk) => chunk,
+                None => break,
+            },
+        };
+        hasher.update(&chunk);
+        size += chunk.len() as u64;
+        file.write_all(&chunk)
+            .await
+            .with_context(|| format!("Failed to write {:?}", partial.path))
+            .map_err(ReceiveError::Write)?;
+    }
+    file.flush()
+        .await
+        .with_context(|| format!("Failed to write {:?}", partial.path))
+        .map_err(ReceiveError::Write)?;
+    drop(file);
+
+    // 在交给保存对话框或自动保存之前清理，失败时整张图片作废，不留下可能含位置信息的文件
+    strip_metadata(&partial.path, format, strip)
+        .await
+        .map_err(ReceiveError::Write)?;
+
+    Ok(ReceivedImage {
+        file: partial,
+        size,
+        format,
+        video,
+        hash: hex::encode(hasher.finalize()),
+    })
+}
+
+/// 将已在内存中的图片写入临时目录，与上传的图片一样按文件头识别格式并清理元数据。短信附带的彩信图片使用。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `data` - 图片内容
+///
+/// # Returns
+/// 临时图片的路径与格式，不是支持的图片格式时报错
+pub(crate) async fn receive_image_data(ctx: &PayloadContext, data: Bytes) -> anyhow::Result<(PathBuf, ImageFormat)> {
+    let chunks = futures::stream::once(async { Ok::<_, std::convert::Infallible>(data) });
+    let strip = ctx.state.config.read().unwrap().photos.strip_metadata;
+    let image = match receive_image(chunks, strip, false).await {
+        Ok(image) => image,
+        Err(ReceiveError::Write(e)) => return Err(e),
+        Err(ReceiveError::Body(never)) => match never {},
+        Err(ReceiveError::Empty | ReceiveError::UnsupportedFormat) => anyhow::bail!("Attachment is not a recognized image"),
+    };
+    let Some(format) = image.format else {
+        anyhow::bail!("Attachment is not a recognized image");
+    };
+    Ok((image.file.persist(), format))
+}
+
+/// 解码 JSON 载荷中 Base64 编码的图片并写入临时目录，彩信与剪贴板图片共用。
+/// 接受带 `data:image/...;base64,` 前缀与换行的内容。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `encoded` - Base64 编码的图片
+///
+/// # Returns
+/// 临时图片的路径与格式，Base64 无效时为 `DecodeFailed`，不是支持的图片格式时为 `UnsupportedFormat`
+pub(crate) async fn receive_base64_image(ctx: &PayloadContext, encoded: &str) -> Result<(PathBuf, ImageFormat), UploadError> {
+    use base64::Engine;
+
+    // 去掉 data URL 的前缀与换行
+    let encoded = match encoded.strip_prefix("data:") {
+        Some(url) => url.split_once(',').map_or(url, |(_, data)| data),
+        None => encoded,
+    };
+    let encoded: String = encoded.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    let data = match base64::engine::general_purpose::STANDARD.decode(encoded) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("Failed to decode base64 image: {}", e);
+            return Err(UploadError::DecodeFailed);
+        }
+    };
+    match receive_image_data(ctx, Bytes::from(data)).await {
+        Ok(image) => Ok(image),
+        Err(e) => {
+            tracing::warn!("Ignoring base64 image: {:#}", e);
+            Err(UploadError::UnsupportedFormat)
+        }
+    }
+}
+
+/// 处理分块上传组装完成的文件：校验哈希、识别格式，之后与表单上传的单张图片相同。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `upload` - 已收到全部内容的上传
+///
+/// # Returns
+/// 处理结果，响应体为 `UploadResponse`
+pub(crate) async fn receive_assembled(ctx: PayloadContext, upload: FinishedUpload) -> PayloadOutcome {
+    let assembled = PartialFile {
+        path: upload.path,
+        keep: false,
+    };
+    if !upload.expected_hash.eq_ignore_ascii_case(&upload.hash) {
+        tracing::warn!(
+            "Resumable upload checksum mismatch: expected {}, received {}",
+            upload.expected_hash,
+            upload.hash
+        );
+        let response = UploadResponse::failure(UploadError::ChecksumMismatch).with_content(upload.size, Some(upload.hash));
+        return PayloadOutcome::new(response);
+    }
+
+    let auto_save_dir = match auto_save_target(&ctx, upload.size) {
+        Ok(dir) => dir,
+        Err(issue) => return PayloadOutcome::new(issue),
+    };
+
+    let mut header = [0u8; SNIFF_LEN];
+    let read = match read_header(&assembled.path, &mut header).await {
+        Ok(read) => read,
+        Err(e) => {
+            tracing::error!("Failed to read assembled upload {:?}: {:?}", assembled.path, e);
+            return PayloadOutcome::new(UploadResponse::failure(UploadError::IoError));
+        }
+    };
+    let format = detect_image_format(&header[..read]);
+    let video = format.is_none().then(|| detect_video_format(&header[..read])).flatten();
+    let (strip, accept_files) = {
+        let config = ctx.state.config.read().unwrap();
+        (config.photos.strip_metadata, config.photos.accept_files)
+    };
+    if format.is_none() && video.is_none() && !accept_files {
+        tracing::warn!("Assembled upload {} is not a recognized image, rejected", upload.file_name);
+        let response = UploadResponse::failure(UploadError::UnsupportedFormat).with_content(upload.size, Some(upload.hash));
+        return PayloadOutcome::new(response);
+    }
+
+    // 换成与表单上传相同的文件名，清理临时文件与通知大图都依赖扩展名
+    let target = temp_image_path(format, video);
+    let source = assembled.persist();
+    if let Err(e) = tokio::fs::rename(&source, &target).await {
+        tracing::error!("Failed to move assembled upload {:?}: {:?}", source, e);
+        let _ = tokio::fs::remove_file(&source).await;
+        return PayloadOutcome::new(UploadResponse::failure(UploadError::IoError));
+    }
+    let file = PartialFile { path: target, keep: false };
+    if let Err(e) = strip_metadata(&file.path, format, strip).await {
+        tracing::error!("{:#}", e);
+        return PayloadOutcome::new(UploadResponse::failure(UploadError::IoError));
+    }
+    ctx.mark(Stage::Received);
+
+    let image = ReceivedImage {
+        file,
+        size: upload.size,
+        format,
+        video,
+        hash: upload.hash,
+    };
+    let mime = format.map(|format| format.mime()).or(video.map(|video| video.mime()));
+    let field = (Ok(image), mime.map(str::to_string), Some(upload.file_name));
+    accept_images(ctx, vec![field], vec![upload.expected_hash], upload.captured_at, auto_save_dir).await
+}
+
+/// 读取文件开头的字节用于识别格式。
+///
+/// # Returns
+/// 实际读取的字节数，文件小于缓冲区时少于缓冲区长度
+async fn read_header(path: &Path, header: &mut [u8]) -> std::io::Result<usize> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut read = 0;
+    while read < header.len() {
+        match file.read(&mut header[read..]).await? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// 新临时文件的路径，同一毫秒内的并发上传使用不同的序号；既不是图片也不是视频的文件使用 `.bin` 扩展名。
+fn temp_image_path(format: Option<ImageFormat>, video: Option<VideoFormat>) -> PathBuf {
+    let extension = match (format, video) {
+        (Some(format), _) => format.extension(),
+        (None, Some(video)) => video.extension(),
+        (None, None) => temp_files::FILE_EXTENSION,
+    };
+    let file_name = format!(
+        "fastsync_{}_{}.{}",
+        chrono::Utc::now().timestamp_millis(),
+        TEMP_SEQUENCE.fetch_add(1, Ordering::Relaxed),
+        extension
+    );
+    std::env::temp_dir().join(file_name)
+}
+
+/// 按设置清理 JPEG 中的元数据，其他格式不做修改。
+///
+/// # Arguments
+/// * `path` - 已写入的图片
+/// * `format` - 图片格式，非图片文件为 None
+/// * `strip` - 要清理的元数据
+async fn strip_metadata(path: &Path, format: Option<ImageFormat>, strip: MetadataStripping) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    if strip == MetadataStripping::Off {
+        return Ok(());
+    }
+    if format != Some(ImageFormat::Jpeg) {
+        tracing::info!("Metadata kept in {:?}: only JPEG is stripped", path);
+        return Ok(());
+    }
+    let target = path.to_path_buf();
+    let outcome = tokio::task::spawn_blocking(move || strip_jpeg_metadata(&target, strip))
+        .await?
+        .with_context(|| format!("Failed to strip metadata from {:?}", path))?;
+    match outcome {
+        StripOutcome::Unchanged => tracing::info!("No metadata to strip in {:?}", path),
+        StripOutcome::GpsRemoved => tracing::info!("Removed GPS metadata from {:?}", path),
+        StripOutcome::MetadataRemoved => tracing::info!("Removed EXIF/XMP metadata from {:?}", path),
+    }
+    Ok(())
+}
+
+/// 将临时图片移动到自动保存目录，目录不存在时自动创建。
+///
+/// # Arguments
+/// * `dir` - 自动保存目录
+/// * `source` - 已写入的临时图片
+/// * `format` - 图片格式，决定文件扩展名
+/// * `transcode_heic` - HEIC 图片是否转换为 JPEG 保存
+///
+/// # Returns
+/// 保存的文件路径
+fn auto_save_image(dir: &Path, source: &Path, format: ImageFormat, transcode_heic: bool) -> anyhow::Result<PathBuf> {
+    use anyhow::Context;
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create auto-save directory {:?}", dir))?;
+
+    let stem = format!("FastSync_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    if should_transcode(format, transcode_heic) {
+        match std::fs::read(source).map_err(anyhow::Error::from).and_then(|data| transcode_to_jpeg(&data)) {
+            Ok(jpeg) => {
+                let file_path = unique_path(dir, &stem, ImageFormat::Jpeg.extension());
+                std::fs::write(&file_path, jpeg)
+                    .with_context(|| format!("Failed to write auto-saved image {:?}", file_path))?;
+                let _ = std::fs::remove_file(source);
+                return Ok(file_path);
+            }
+            Err(e) => tracing::warn!("Failed to convert HEIC to JPEG, saving the original: {:#}", e),
+        }
+    }
+    auto_save_file(dir, source, format.extension())
+}
+
+/// 将临时文件原样移动到自动保存目录，按当前时间命名。
+///
+/// # Arguments
+/// * `dir` - 自动保存目录
+/// * `source` - 已写入的临时文件
+/// * `extension` - 文件扩展名
+///
+/// # Returns
+/// 保存的文件路径
+fn auto_save_file(dir: &Path, source: &Path, extension: &str) -> anyhow::Result<PathBuf> {
+    use anyhow::Context;
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create auto-save directory {:?}", dir))?;
+    let stem = format!("FastSync_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+    let file_path = unique_path(dir, &stem, extension);
+    move_file(source, &file_path)
+        .with_context(|| format!("Failed to write auto-saved file {:?}", file_path))?;
+    Ok(file_path)
+}
+
+/// 不经对话框将图片复制到指定目录，目录不存在时创建，重名时追加序号而不覆盖。
+///
+/// # Arguments
+/// * `source` - 图片当前路径
+/// * `dir` - 目标目录
+/// * `stem` - 手机上的文件名主体，缺省时按当前时间命名
+/// * `format` - 图片格式，决定文件扩展名
+/// * `transcode_heic` - HEIC 图片是否转换为 JPEG 保存
+///
+/// # Returns
+/// 保存的文件路径
+fn save_image_into(
+    source: &Path,
+    dir: &Path,
+    stem: Option<&str>,
+    format: ImageFormat,
+    transcode_heic: bool,
+) -> anyhow::Result<PathBuf> {
+    use anyhow::Context;
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create directory {:?}", dir))?;
+    let stem = match stem {
+        Some(stem) => stem.to_string(),
+        None => format!("FastSync_{}", chrono::Local::now().format("%Y%m%d_%H%M%S")),
+    };
+    let data = std::fs::read(source).with_context(|| format!("Failed to read {:?}", source))?;
+    // 转换失败时仍保存原始文件
+    let transcoded = should_transcode(format, transcode_heic).then(|| transcode_to_jpeg(&data)).and_then(|result| {
+        result
+            .inspect_err(|e| tracing::warn!("Failed to convert HEIC to JPEG, saving the original: {:#}", e))
+            .ok()
+    });
+    let (data, format) = match &transcoded {
+        Some(jpeg) => (jpeg.as_slice(), ImageFormat::Jpeg),
+        None => (data.as_slice(), format),
+    };
+
+    let target = unique_path(dir, &stem, format.extension());
+    std::fs::write(&target, data).with_context(|| format!("Failed to write {:?}", target))?;
+    tracing::info!("Image saved to {:?}", target);
+    Ok(target)
+}
+
+/// 快速保存完成后弹出确认通知，显示保存位置并提供打开文件夹的按钮。
+///
+/// # Arguments
+/// * `notifier` - 通知后端
+/// * `item_id` - 图片标识，同一张图片重复保存时替换上一条确认
+/// * `path` - 保存后的文件路径
+fn show_quick_saved_notification(notifier: &dyn Notifier, item_id: &str, path: PathBuf) {
+    let dir = path.parent().unwrap_or(&path).to_path_buf();
+    let mut notification = Notification::new(&format!("quick_save_{}", item_id), "已快速保存");
+    notification.group = "photo".to_string();
+    notification.body.push(path.display().to_string());
+    notification.actions.push(NotificationAction::new("open_folder", "打开文件夹"));
+
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "open_folder" {
+            tracing::info!("Open quick-save folder action clicked");
+            if let Err(e) = open_file(&dir) {
+                tracing::error!("{:#}", e);
+            }
+        }
+    });
+    if let Err(e) = notifier.show(notification, on_action) {
+        tracing::error!("Failed to show quick save confirmation: {:?}", e);
+    }
+}
+
+/// 移动文件。源与目标可能不在同一分区，rename 失败时退回复制后删除源文件。
+fn move_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    if std::fs::rename(source, target).is_err() {
+        std::fs::copy(source, target)?;
+        let _ = std::fs::remove_file(source);
+    }
+    Ok(())
+}
 
-/// 处理图片上传请求。
+/// 生成目录内不与现有文件冲突的路径，冲突时追加 ` (1)`、` (2)` 等后缀。
 ///
 /// # Arguments
-/// * `multipart` - 包含图片数据的 Multipart 表单
+/// * `dir` - 目标目录
+/// * `stem` - 文件名（不含扩展名）
+/// * `extension` - 扩展名
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut candidate = dir.join(format!("{}.{}", stem, extension));
+    let mut index = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{} ({}).{}", stem, index, extension));
+        index += 1;
+    }
+    candidate
+}
+
+/// 构建自动保存后的提示通知，提供打开图片或视频与所在文件夹的按钮。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `path` - 保存后的文件路径
+/// * `video` - 保存的是视频时为其格式
+/// * `audit` - 该图片的审计句柄
+/// * `recognized` - 从图片中识别出的网址与文字
 ///
 /// # Returns
-/// HTTP 状态码（200 OK 表示接收成功）
-pub async fn upload(mut multipart: Multipart) -> StatusCode {
-    let mut image_data = None;
-
-    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
-        let name = field.name().unwrap_or("").to_string();
-        let data = field.bytes().await.unwrap_or_default();
+/// 通知描述与按钮回调
+fn build_saved_notification(
+    ctx: &PayloadContext,
+    path: &Path,
+    video: Option<VideoFormat>,
+    audit: ItemAudit,
+    recognized: Recognized,
+) -> (Notification, ActionHandler) {
+    let dir = path.parent().unwrap_or(path).to_path_buf();
+    let mut notification = match video {
+        Some(_) => Notification::new(&format!("video_{}", audit.item_id()), &ctx.device.received_title("视频")),
+        None => Notification::new(&format!("photo_{}", audit.item_id()), &ctx.device.received_title("图片")),
+    };
+    notification.group = if video.is_some() { "video" } else { "photo" }.to_string();
+    notification.body.push(format!("已自动保存到 {}", path.display()));
+    // 视频的大图由缩略图代替，无法生成缩略图时只显示文字
+    if video.is_none() || video::THUMBNAILS {
+        notification.hero_image = Some(path.to_path_buf());
+    }
+    notification.actions.push(NotificationAction::new("open", "打开"));
+    notification.actions.push(NotificationAction::new("open_folder", "打开文件夹"));
+    recognized.add_actions(&mut notification);
 
-        if name == "data" {
-            image_data = Some(data.to_vec());
+    let path = path.to_path_buf();
+    let clipboard = ctx.clipboard();
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if recognized.handle_action(arguments, clipboard.as_ref(), &audit) {
+            return;
         }
+        if arguments == "open" {
+            tracing::info!("Open action clicked");
+            // 文件被移动或删除时，资源管理器会静默打开默认位置，需提前判断
+            let result = if path.exists() {
+                open_file(&path)
+            } else {
+                Err(anyhow::anyhow!("{:?} no longer exists", path))
+            };
+            audit.record_result("open", Some(&path), &result);
+        } else if arguments == "open_folder" {
+            tracing::info!("Open folder action clicked");
+            let result = open_file(&dir);
+            audit.record_result("open_folder", Some(&dir), &result);
+        }
+    });
+
+    (notification, on_action)
+}
+
+/// 构建通用文件模式下收到非图片文件的通知，只提供另存与忽略。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `content` - 文件的类型、文件名与大小
+/// * `audit` - 该文件的审计句柄
+/// * `path` - 临时文件路径
+///
+/// # Returns
+/// 通知描述与按钮回调
+fn build_file_notification(ctx: &PayloadContext, content: &ContentInfo, audit: ItemAudit, path: PathBuf) -> (Notification, ActionHandler) {
+    // 只取文件名部分，手机端传来的名称可能带有目录
+    let file_name = content
+        .file_name
+        .and_then(|name| Path::new(name).file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("file")
+        .to_string();
+
+    let mut notification = Notification::new(&format!("file_{}", audit.item_id()), &ctx.device.received_title("文件"));
+    notification.group = "file".to_string();
+    notification.body.push(format!("{}（{} 字节）", file_name, content.size));
+    notification.long_duration = true;
+    notification.expires_in = Duration::from_secs(30);
+    let can_save = ctx.action_allowed("photo", content, "save");
+    if can_save {
+        notification.actions.push(NotificationAction::new("save", "保存"));
     }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    notification.temp_files.push(path.clone());
 
-    if let Some(data) = image_data {
-        tracing::info!("Image received successfully, size: {} bytes", data.len());
-        
-        tokio::spawn(async move {
-            if let Some(temp_file_path) = save_temp_image(&data) {
-                // 不再传入 data，只传入路径
-                if let Err(e) = show_notification_with_actions(temp_file_path) {
-                    tracing::error!("Failed to show notification: {:?}", e);
-                }
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "save" && can_save {
+            tracing::info!("Save file action clicked");
+            match save_copy_dialog(&path, &file_name, None) {
+                Ok(Some(saved)) => audit.record("save", Some(&saved), None),
+                Ok(None) => audit.record("save", None, Some("cancelled".to_string())),
+                Err(e) => audit.record("save", None, Some(format!("{:#}", e))),
             }
-        });
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore file action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
 
-        return StatusCode::OK;
-    } else {
-        tracing::error!("Missing data");
+    (notification, on_action)
+}
+
+/// 构建视频通知，提供保存、打开与忽略。视频无法复制到剪贴板，不提供复制类按钮。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `content` - 视频的类型、文件名与大小
+/// * `format` - 视频格式，决定保存对话框的默认扩展名
+/// * `audit` - 该视频的审计句柄
+/// * `path` - 临时文件路径
+///
+/// # Returns
+/// 通知描述与按钮回调
+fn build_video_notification(
+    ctx: &PayloadContext,
+    content: &ContentInfo,
+    format: VideoFormat,
+    audit: ItemAudit,
+    path: PathBuf,
+) -> (Notification, ActionHandler) {
+    // 默认沿用手机上的文件名，扩展名与实际格式一致
+    let file_name = format!(
+        "{}.{}",
+        content.file_name.and_then(received_stem).unwrap_or_else(|| "video".to_string()),
+        format.extension()
+    );
+
+    let mut notification = Notification::new(&format!("video_{}", audit.item_id()), &ctx.device.received_title("视频"));
+    notification.group = "video".to_string();
+    notification.body.push(format!("{}（{:.1} MB）", file_name, content.size as f64 / (1024.0 * 1024.0)));
+    if video::THUMBNAILS {
+        notification.hero_image = Some(path.clone());
+    }
+    notification.long_duration = true;
+    notification.expires_in = Duration::from_secs(30);
+    let can_save = ctx.action_allowed("photo", content, "save");
+    let can_open = ctx.action_allowed("photo", content, "open");
+    if can_save {
+        notification.actions.push(NotificationAction::new("save", "保存"));
     }
+    if can_open {
+        notification.actions.push(NotificationAction::new("open", "打开"));
+        // 播放器打开的是临时文件，通知显示期间不能被清理
+        temp_files::retain(&path, notification.expires_in);
+    }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    notification.temp_files.push(path.clone());
+
+    let video_path = Arc::new(Mutex::new(path));
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "save" && can_save {
+            tracing::info!("Save video action clicked");
+            let source = video_path.lock().unwrap().clone();
+            match save_copy_dialog(&source, &file_name, Some(("Video", &VIDEO_EXTENSIONS))) {
+                Ok(Some(saved)) => audit.record("save", Some(&saved), None),
+                Ok(None) => audit.record("save", None, Some("cancelled".to_string())),
+                Err(e) => audit.record("save", None, Some(format!("{:#}", e))),
+            }
+        } else if arguments == "open" && can_open {
+            tracing::info!("Open video action clicked");
+            // 先移出临时目录，通知关闭后临时文件被删除时播放器中的视频不受影响
+            let result = keep_received_file(&video_path, None).and_then(|path| open_file(&path).map(|_| path));
+            audit.record_result("open", result.as_deref().ok(), &result);
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore video action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
 
-    StatusCode::BAD_REQUEST
+    (notification, on_action)
 }
 
-/// 将图片数据保存到临时目录。
+/// 构建带有交互按钮的图片通知。
 ///
 /// # Arguments
-/// * `data` - 图片二进制数据
+/// * `ctx` - 处理器上下文
+/// * `content` - 图片的类型与大小，用于按内容策略筛选按钮
+/// * `format` - 图片格式，当前构建无法解码时不提供复制
+/// * `audit` - 该图片的审计句柄
+/// * `capture` - 图片的拍摄时间
+/// * `image_path` - 本地预览图片路径，执行路径类操作后更新为移动后的路径
+/// * `recognized` - 从图片中识别出的网址与文字
 ///
 /// # Returns
-/// 保存的文件绝对路径，若失败则返回 None
-fn save_temp_image(data: &[u8]) -> Option<String> {
-    use std::io::Write;
-    let temp_dir = std::env::temp_dir();
-    let file_name = format!("fastsync_{}.png", chrono::Utc::now().timestamp_millis());
-    let file_path = temp_dir.join(file_name);
-    
-    if let Ok(mut file) = std::fs::File::create(&file_path) {
-        if let Ok(_) = file.write_all(data) {
-            return Some(file_path.to_string_lossy().to_string());
-        }
+/// 通知描述与按钮回调
+fn build_photo_notification(
+    ctx: &PayloadContext,
+    content: &ContentInfo,
+    format: ImageFormat,
+    audit: ItemAudit,
+    capture: CaptureTime,
+    image_path: Arc<Mutex<PathBuf>>,
+    recognized: Recognized,
+) -> (Notification, ActionHandler) {
+    // 每张图片使用独立的标签，连续收到的图片不会互相替换
+    let mut notification = Notification::new(&format!("photo_{}", audit.item_id()), &ctx.device.received_title("图片"));
+    notification.group = "photo".to_string();
+    notification.hero_image = Some(image_path.lock().unwrap().clone());
+    notification.long_duration = true;
+    notification.expires_in = Duration::from_secs(30);
+    if capture.is_delayed() {
+        notification.body.push(format!("拍摄于 {}", capture.relative_to(capture.received_at)));
+    }
+    if !format.is_decodable() {
+        tracing::warn!("Received a {:?} image but this build cannot decode it (enable the heic feature); only saving the original is offered", format);
     }
-    None
+    // 复制为文件时沿用手机上的文件名，粘贴到聊天软件中显示的名称与手机一致
+    let original_stem = content.file_name.and_then(received_stem);
+    let image_actions = ImageActions::new(ctx, "photo", content, format, image_path, original_stem);
+    image_actions.add_to(&mut notification);
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    recognized.add_actions(&mut notification);
+
+    let clipboard = ctx.clipboard();
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if recognized.handle_action(arguments, clipboard.as_ref(), &audit) {
+            return;
+        }
+        if image_actions.handle_action(arguments, &audit) {
+            return;
+        }
+        if arguments == "ignore" {
+            tracing::info!("Ignore action clicked");
+            audit.record("ignore", None, None);
+        }
+    });
+
+    (notification, on_action)
 }
 
-/// 显示带有交互按钮的 Windows Toast 通知。
+/// 连拍分组通知的标签，分组的第一张图片也使用该标签，之后的分组通知替换它。
+fn burst_tag(burst_id: &str) -> String {
+    format!("photo_burst_{}", burst_id)
+}
+
+/// 构建连拍分组的通知：显示组内图片数与最新一张的预览，“全部保存”通过一次选择文件夹保存所有图片。
 ///
 /// # Arguments
-/// * `image_path` - 本地预览图片路径
+/// * `ctx` - 处理器上下文
+/// * `content` - 最新一张图片的类型与大小，用于判定按钮
+/// * `arrival` - 加入后的分组
 ///
 /// # Returns
-/// 操作结果 Result
-fn show_notification_with_actions(image_path: String) -> windows::core::Result<()> {
-    let toast_xml = XmlDocument::new()?;
-    
-    let image_xml = format!(r#"<image placement='hero' src='file:///{}'/>"#, image_path.replace("\\", "/"));
-    
-    let xml_string = format!(r#"
-        <toast duration="long" activationType='foreground'>
-        <visual>
-            <binding template='ToastGeneric'>
-                <text>收到手机图片</text>
-                {}
-            </binding>
-        </visual>
-        <actions>
-            <action content='保存' arguments='save' />
-            <action content='复制' arguments='copy' />
-            <action content='忽略' arguments='ignore' />
-        </actions>
-        </toast>
-    "#, image_xml);
-
-    toast_xml.LoadXml(&HSTRING::from(xml_string))?;
-
-    let notification = ToastNotification::CreateToastNotification(&toast_xml)?;
-
-    notification.SetTag(&HSTRING::from("CurrentPhoto"))?;
-    notification.SetGroup(&HSTRING::from("FastSync"))?;
-
-    let now_unix_millis = chrono::Utc::now().timestamp_millis();
-    let expiration_millis = now_unix_millis + 30_000; 
-    let expiration_ticks = (expiration_millis * 10_000) + 116444736000000000;
-    
-    let expiry_time = DateTime { UniversalTime: expiration_ticks };
-    
-    let expiry_inspectable = PropertyValue::CreateDateTime(expiry_time)?;
-    let expiry_reference: IReference<DateTime> = expiry_inspectable.cast()?;
-    notification.SetExpirationTime(&expiry_reference)?;
-
-    // 使用 image_path 而非 image_data
-    let image_path_clone = image_path.clone();
-    
-    notification.Activated(&windows::Foundation::TypedEventHandler::new(move |_sender, args: &Option<IInspectable>| {
-        if let Some(args) = args {
-            let args: windows::UI::Notifications::ToastActivatedEventArgs = args.cast()?;
-            let arguments = args.Arguments()?.to_string();
-            
-            // 按需读取文件
-            let load_data = || -> Option<Vec<u8>> {
-                 match std::fs::read(&image_path_clone) {
-                    Ok(d) => Some(d),
-                    Err(e) => {
-                        tracing::error!("Failed to read image file {:?}: {:?}", image_path_clone, e);
-                        None
+/// 通知描述与按钮回调
+fn build_burst_notification(ctx: &PayloadContext, content: &ContentInfo, arrival: &Arrival) -> (Notification, ActionHandler) {
+    let images = arrival.images.clone();
+    let title = match &ctx.device.device_name {
+        Some(name) => format!("来自 {} 的 {} 张图片", name, images.len()),
+        None => format!("收到 {} 张图片", images.len()),
+    };
+    let mut notification = Notification::new(&burst_tag(&arrival.id), &title);
+    notification.group = BURST_GROUP.to_string();
+    notification.hero_image = images.last().map(|image| image.path.lock().unwrap().clone());
+    notification.long_duration = true;
+    notification.expires_in = BURST_EXPIRES_IN;
+
+    let can_save = ctx.action_allowed("photo", content, "save");
+    let can_open = ctx.action_allowed("photo", content, "open");
+    if can_save {
+        notification.actions.push(NotificationAction::new("save_all", "全部保存"));
+    }
+    if can_open {
+        notification.actions.push(NotificationAction::new("open_folder", "打开文件夹"));
+    }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+    // 临时文件由分组保管，不登记到通知上
+
+    let transcode_heic = ctx.state.config.read().unwrap().photos.heic_to_jpeg;
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "save_all" && can_save {
+            tracing::info!("Save all action clicked ({} images)", images.len());
+            let dir = match pick_folder_dialog() {
+                Ok(Some(dir)) => dir,
+                Ok(None) => {
+                    for image in &images {
+                        image.audit.record("save", None, Some("cancelled".to_string()));
                     }
-                 }
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!("{:#}", e);
+                    return;
+                }
+            };
+            for image in &images {
+                let source = image.path.lock().unwrap().clone();
+                if !source.exists() {
+                    tracing::warn!("Burst image {:?} no longer exists, skipped", source);
+                    image.audit.record("save", None, Some("missing".to_string()));
+                    continue;
+                }
+                let format = source
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .and_then(ImageFormat::from_extension)
+                    .unwrap_or(ImageFormat::Png);
+                let result = save_image_into(&source, &dir, image.stem.as_deref(), format, transcode_heic);
+                image.audit.record_result("save", result.as_deref().ok(), &result);
+            }
+        } else if arguments == "open_folder" && can_open {
+            tracing::info!("Open folder action clicked ({} images)", images.len());
+            // 先移出临时目录，分组过期后文件仍在
+            let mut folder = None;
+            for image in &images {
+                let result = keep_received_file(&image.path, image.stem.as_deref());
+                image.audit.record_result("open_folder", result.as_deref().ok(), &result);
+                if let Ok(path) = result {
+                    folder = path.parent().map(Path::to_path_buf);
+                }
+            }
+            if let Some(folder) = folder {
+                if let Err(e) = open_file(&folder) {
+                    tracing::error!("{:#}", e);
+                }
+            }
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore action clicked");
+            for image in &images {
+                image.audit.record("ignore", None, None);
+            }
+        }
+    });
+
+    (notification, on_action)
+}
+
+/// 图片通知上的保存、复制与打开按钮，图片通知与带图片的彩信通知共用。
+pub(crate) struct ImageActions {
+    /// 按显示顺序排列的可用按钮
+    actions: Vec<&'static str>,
+    format: ImageFormat,
+    /// 图片当前路径，路径类按钮把文件移出临时目录后更新
+    image_path: Arc<Mutex<PathBuf>>,
+    /// 手机上的文件名主体，复制为文件与快速保存时使用
+    stem: Option<String>,
+    transcode_heic: bool,
+    quick_save_dir: PathBuf,
+    notifier: Arc<dyn Notifier>,
+    clipboard: Arc<dyn ClipboardBackend>,
+    decode_pool: Arc<DecodePool>,
+}
+
+impl ImageActions {
+    /// 按内容策略与当前设置确定可用的按钮。
+    ///
+    /// # Arguments
+    /// * `ctx` - 处理器上下文
+    /// * `capability` - 判定内容策略时使用的能力标识
+    /// * `content` - 图片的类型与文件名
+    /// * `format` - 图片格式
+    /// * `image_path` - 图片当前路径
+    /// * `stem` - 手机上的文件名主体
+    pub(crate) fn new(
+        ctx: &PayloadContext,
+        capability: &str,
+        content: &ContentInfo,
+        format: ImageFormat,
+        image_path: Arc<Mutex<PathBuf>>,
+        stem: Option<String>,
+    ) -> Self {
+        let clipboard = ctx.clipboard();
+        let enabled = [
+            ("save", true),
+            ("quick_save", true),
+            ("copy", format.is_decodable()),
+            ("copy_file", clipboard.supports_files()),
+            ("copy_path", ctx.state.config.read().unwrap().photos.path_actions),
+            ("open", true),
+        ];
+        let actions = enabled
+            .into_iter()
+            .filter(|&(action, enabled)| enabled && ctx.action_allowed(capability, content, action))
+            .map(|(action, _)| action)
+            .collect();
+        Self {
+            actions,
+            format,
+            image_path,
+            stem,
+            transcode_heic: ctx.state.config.read().unwrap().photos.heic_to_jpeg,
+            quick_save_dir: ctx.state.config.read().unwrap().photos.quick_save_dir(),
+            notifier: ctx.state.notifier.clone(),
+            clipboard,
+            decode_pool: ctx.state.decode_pool.clone(),
+        }
+    }
+
+    /// 只保留指定的按钮，按钮较多的通知用来避免超出 Windows 的按钮上限。
+    pub(crate) fn retain(mut self, keep: &[&str]) -> Self {
+        self.actions.retain(|action| keep.contains(action));
+        self
+    }
+
+    /// 添加按钮，并让通知过期或点击按钮后删除临时图片。需在设置通知有效期之后调用。
+    pub(crate) fn add_to(&self, notification: &mut Notification) {
+        for &action in &self.actions {
+            let label = match action {
+                "save" => "保存",
+                "quick_save" => "快速保存",
+                "copy" => "复制",
+                "copy_file" => "复制为文件",
+                "copy_path" => "复制图片路径",
+                _ => "打开",
             };
+            notification.actions.push(NotificationAction::new(action, label));
+        }
+        let path = self.image_path.lock().unwrap().clone();
+        // 路径类按钮会把临时文件交给外部程序，通知显示期间不能被清理
+        if self.actions.iter().any(|action| matches!(*action, "copy_file" | "copy_path" | "open")) {
+            temp_files::retain(&path, notification.expires_in);
+        }
+        // 通知过期或点击按钮后删除临时文件，路径类按钮已把文件移出临时目录时不受影响
+        notification.temp_files.push(path);
+    }
+
+    /// 处理图片按钮，完成后记录审计结果。
+    ///
+    /// # Returns
+    /// 是否为图片按钮
+    pub(crate) fn handle_action(&self, arguments: &str, audit: &ItemAudit) -> bool {
+        let Some(&action) = self.actions.iter().find(|&&action| action == arguments) else {
+            return false;
+        };
+        // 按需读取文件
+        let load_data = || -> Option<Vec<u8>> {
+            let path = self.image_path.lock().unwrap().clone();
+            match std::fs::read(&path) {
+                Ok(d) => Some(d),
+                Err(e) => {
+                    tracing::error!("Failed to read image file {:?}: {:?}", path, e);
+                    audit.record(action, Some(&path), Some(e.to_string()));
+                    None
+                }
+            }
+        };
 
-            if arguments == "save" {
+        match action {
+            "save" => {
                 tracing::info!("Save action clicked");
                 if let Some(data) = load_data() {
-                    save_file_dialog(&data);
+                    match save_file_dialog(&data, self.transcode_heic) {
+                        Ok(Some(path)) => audit.record("save", Some(&path), None),
+                        Ok(None) => audit.record("save", None, Some("cancelled".to_string())),
+                        Err(e) => audit.record("save", None, Some(format!("{:#}", e))),
+                    }
+                }
+            }
+            "quick_save" => {
+                tracing::info!("Quick save action clicked");
+                let source = self.image_path.lock().unwrap().clone();
+                let result = save_image_into(&source, &self.quick_save_dir, self.stem.as_deref(), self.format, self.transcode_heic);
+                audit.record_result("quick_save", result.as_deref().ok(), &result);
+                if let Ok(path) = result {
+                    show_quick_saved_notification(self.notifier.as_ref(), audit.item_id(), path);
                 }
-            } else if arguments == "copy" {
+            }
+            "copy" => {
                 tracing::info!("Copy action clicked");
                 if let Some(data) = load_data() {
-                    copy_to_clipboard(&data);
+                    copy_to_clipboard(&self.decode_pool, self.clipboard.clone(), data, audit.clone());
                 }
-            } else if arguments == "ignore" {
-                tracing::info!("Ignore action clicked");
-            }
-        }
-        Ok(())
-    }))?;
-    
-    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(crate::APP_ID))?;
-    notifier.Show(&notification)?;
-    
-    // 使用全局存储管理生命周期
-    store_notification("photo", notification);
-    
-    Ok(())
+            }
+            "copy_file" => {
+                tracing::info!("Copy as file action clicked");
+                // 剪贴板中只有路径，先移出临时目录，通知关闭后粘贴仍然有效
+                match keep_received_file(&self.image_path, self.stem.as_deref()) {
+                    Ok(path) => copy_file_to_clipboard(self.clipboard.clone(), path, audit.clone()),
+                    Err(e) => audit.record("copy_file", None, Some(format!("{:#}", e))),
+                }
+            }
+            "copy_path" => {
+                tracing::info!("Copy path action clicked");
+                match keep_received_file(&self.image_path, None) {
+                    Ok(path) => copy_path_to_clipboard(self.clipboard.clone(), path, audit.clone()),
+                    Err(e) => audit.record("copy_path", None, Some(format!("{:#}", e))),
+                }
+            }
+            _ => {
+                tracing::info!("Open action clicked");
+                // 先移出临时目录，通知关闭后临时文件被删除时查看器中的图片不受影响
+                let result = keep_received_file(&self.image_path, None).and_then(|path| open_file(&path).map(|_| path));
+                audit.record_result("open", result.as_deref().ok(), &result);
+            }
+        }
+        true
+    }
+}
+
+/// 从图片内容中识别出的信息，在通知中提供对应的按钮。
+#[derive(Debug, Default)]
+struct Recognized {
+    /// 二维码中的网址
+    link: Option<String>,
+    /// 识别出的文字
+    text: Option<String>,
+}
+
+impl Recognized {
+    /// 在通知中显示网址并添加"打开链接"与"复制文字"。两者排在最前，Windows 按钮过多时省略的是末尾的按钮。
+    fn add_actions(&self, notification: &mut Notification) {
+        let mut index = 0;
+        if let Some(link) = &self.link {
+            let preview = if link.chars().count() > 100 {
+                format!("{}...", link.chars().take(100).collect::<String>())
+            } else {
+                link.to_string()
+            };
+            notification.body.push(preview);
+            notification.actions.insert(index, NotificationAction::new("open_link", "打开链接"));
+            index += 1;
+        }
+        if self.text.is_some() {
+            notification.actions.insert(index, NotificationAction::new("copy_text", "复制文字"));
+        }
+    }
+
+    /// 处理"打开链接"与"复制文字"，完成后记录审计结果。
+    ///
+    /// # Returns
+    /// 是否为这两个按钮
+    fn handle_action(&self, arguments: &str, clipboard: &dyn ClipboardBackend, audit: &ItemAudit) -> bool {
+        match (arguments, &self.link, &self.text) {
+            ("open_link", Some(link), _) => {
+                tracing::info!("Open link action clicked");
+                let result = open_file(link);
+                audit.record_result("open_link", None, &result);
+                true
+            }
+            ("copy_text", _, Some(text)) => {
+                tracing::info!("Copy text action clicked");
+                let result = copy_text_to_clipboard(clipboard, text);
+                audit.record_result("copy_text", None, &result);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 同时识别图片中的二维码与文字，两者各自限时，失败时对应的结果为空。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `path` - 图片路径
+async fn recognize(ctx: &PayloadContext, path: &Path) -> Recognized {
+    let language = ctx.state.config.read().unwrap().photos.ocr_language.clone();
+    let (link, text) = tokio::join!(
+        crate::qr::find_link(path.to_path_buf()),
+        crate::ocr::find_text(path.to_path_buf(), language),
+    );
+    Recognized { link, text }
 }
 
-/// 将图片数据写入系统剪贴板。
+/// 在解码线程池中解码图片并写入系统剪贴板，完成后记录审计结果。
 ///
 /// # Arguments
+/// * `pool` - 解码线程池
+/// * `clipboard` - 剪贴板后端
 /// * `data` - 图片二进制数据
-fn copy_to_clipboard(data: &[u8]) {
-    let data_vec = data.to_vec();
-    
+/// * `audit` - 该图片的审计句柄
+fn copy_to_clipboard(pool: &DecodePool, clipboard: Arc<dyn ClipboardBackend>, data: Vec<u8>, audit: ItemAudit) {
+    pool.submit(move || {
+        let started = Instant::now();
+        let result = decode_image(&data).and_then(|(image_data, decoder_name)| {
+            // 记录解码耗时，便于比较各解码器的速度
+            tracing::info!(
+                "Decoded {}x{} image with {} in {} ms",
+                image_data.width,
+                image_data.height,
+                decoder_name,
+                started.elapsed().as_millis()
+            );
+            write_to_clipboard(clipboard.as_ref(), image_data, decoder_name)
+        });
+        audit.record_result("copy", None, &result);
+    });
+}
+
+/// 将文件的绝对路径作为文本写入剪贴板，完成后记录审计结果。
+///
+/// # Arguments
+/// * `clipboard` - 剪贴板后端
+/// * `path` - 文件路径
+/// * `audit` - 该图片的审计句柄
+fn copy_path_to_clipboard(clipboard: Arc<dyn ClipboardBackend>, path: PathBuf, audit: ItemAudit) {
+    let span = tracing::Span::current();
+    std::thread::spawn(move || {
+        let _entered = span.enter();
+        // 路径可能包含中文等非 ASCII 字符，不能有损转换，否则粘贴出的路径无法打开
+        let result = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Path {:?} is not valid Unicode", path))
+            .and_then(|text| copy_text_to_clipboard(clipboard.as_ref(), text));
+        audit.record_result("copy_path", Some(&path), &result);
+    });
+}
+
+/// 以 CF_HDROP 文件列表写入剪贴板，粘贴到资源管理器或聊天软件时传输原始文件，完成后记录审计结果。
+///
+/// # Arguments
+/// * `clipboard` - 剪贴板后端
+/// * `path` - 文件路径
+/// * `audit` - 该图片的审计句柄
+fn copy_file_to_clipboard(clipboard: Arc<dyn ClipboardBackend>, path: PathBuf, audit: ItemAudit) {
+    let span = tracing::Span::current();
     std::thread::spawn(move || {
-        let mut decoder = JpegDecoder::new(&data_vec);
+        let _entered = span.enter();
+        let result = clipboard.set_files(std::slice::from_ref(&path));
+        audit.record_result("copy_file", Some(&path), &result);
+    });
+}
+
+/// 将临时图片移动到稳定的 received 目录，已移动过时直接返回当前路径。
+///
+/// # Arguments
+/// * `current` - 图片当前路径，移动后更新为新路径
+/// * `stem` - 新文件名（不含扩展名），缺省时按当前时间命名
+///
+/// # Returns
+/// 移动后的文件路径
+fn keep_received_file(current: &Mutex<PathBuf>, stem: Option<&str>) -> anyhow::Result<PathBuf> {
+    use anyhow::Context;
+
+    let mut current = current.lock().unwrap();
+    if !current.exists() {
+        anyhow::bail!("{:?} no longer exists", current);
+    }
+    let dir = received_dir().context("No local data directory for received files")?;
+    if current.parent() == Some(dir.as_path()) {
+        return Ok(current.clone());
+    }
+
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let stem = match stem {
+        Some(stem) => stem.to_string(),
+        None => format!("FastSync_{}", chrono::Local::now().format("%Y%m%d_%H%M%S")),
+    };
+    let extension = current.extension().and_then(|e| e.to_str()).unwrap_or("png").to_string();
+    let target = unique_path(&dir, &stem, &extension);
+    move_file(current.as_path(), &target)
+        .with_context(|| format!("Failed to move {:?} to {:?}", current, target))?;
+
+    temp_files::release(current.as_path());
+    tracing::info!("Temp image moved to {:?}", target);
+    *current = target.clone();
+    Ok(target)
+}
+
+/// 取手机端文件名中可用作本地文件名的部分，去掉目录与扩展名，替换 Windows 不允许的字符。
+///
+/// # Returns
+/// 文件名主体，为空时返回 None
+fn received_stem(file_name: &str) -> Option<String> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?;
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_control() || r#"<>:"/\|?*"#.contains(c) { '_' } else { c })
+        .collect();
+    let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    (!stem.is_empty()).then(|| stem.to_string())
+}
+
+/// 默认的图片自动保存目录，位于个人图片目录下的 `FastSync`。
+pub fn default_auto_save_dir() -> PathBuf {
+    dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("FastSync")
+}
+
+/// 用户操作过的临时图片的保存目录，位于本地数据目录下的 `FastSync/received`。
+pub fn received_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("FastSync").join("received"))
+}
+
+/// 用系统默认程序打开文件或网址。
+pub(crate) fn open_file(path: impl AsRef<std::ffi::OsStr>) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    #[cfg(windows)]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let opener = "xdg-open";
+
+    std::process::Command::new(opener)
+        .arg(path)
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open {:?}: {}", path, e))?;
+    Ok(())
+}
+
+/// 解码图片，JPEG 优先使用 zune-jpeg，8 位非隔行的 PNG 优先使用 zune-png，失败时回退到 image-rs。
+/// 各条路径都按 EXIF 方向旋转或翻转，竖拍的照片复制后不会横过来。动图只取第一帧。
+///
+/// # Returns
+/// 解码后的图片与所用解码器名称
+pub(crate) fn decode_image(data: &[u8]) -> anyhow::Result<(ClipboardImage, &'static str)> {
+    // 其他格式直接交给 image-rs，GIF 与 WebP 动图只解码第一帧
+    let format = detect_image_format(data);
+    if format == Some(ImageFormat::Png) {
+        match decode_png(data) {
+            Ok(Some(image_data)) => return Ok((image_data, "zune-png")),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("zune-png decode failed (will fallback to image-rs): {:?}", e);
+            }
+        }
+    }
+    if format == Some(ImageFormat::Jpeg) {
+        let mut decoder = JpegDecoder::new(data);
         match decoder.decode() {
             Ok(pixels) => {
                 if let Some(info) = decoder.info() {
                     let width = info.width as usize;
                     let height = info.height as usize;
-                    
+                
                     let mut rgba_pixels = Vec::with_capacity(width * height * 4);
                     for chunk in pixels.chunks_exact(3) {
                         rgba_pixels.extend_from_slice(chunk);
                         rgba_pixels.push(255);
                     }
-                    
-                    let image_data = arboard::ImageData {
+                
+                    let image_data = ClipboardImage {
                         width,
                         height,
-                        bytes: std::borrow::Cow::Owned(rgba_pixels),
+                        rgba: rgba_pixels,
                     };
-                    
-                    write_to_clipboard(image_data, "zune-jpeg");
-                    return;
+                    let orientation = decoder
+                        .exif()
+                        .and_then(|exif| Orientation::from_exif_chunk(exif))
+                        .unwrap_or(Orientation::NoTransforms);
+                
+                    return Ok((apply_orientation(image_data, orientation)?, "zune-jpeg"));
                 }
             },
             Err(e) => {
                 tracing::warn!("zune-jpeg decode failed (will fallback to image-rs): {:?}", e);
             }
         }
+    }
 
-        match image::load_from_memory(&data_vec) {
-            Ok(img) => {
-                let rgba = img.to_rgba8();
-                let width = rgba.width() as usize;
-                let height = rgba.height() as usize;
-                let bytes = rgba.into_raw();
-                
-                let image_data = arboard::ImageData {
-                    width,
-                    height,
-                    bytes: std::borrow::Cow::Owned(bytes),
-                };
-                
-                write_to_clipboard(image_data, "image-rs");
-            },
-            Err(e) => tracing::error!("Failed to decode image with both decoders: {:?}", e),
+    let decoded = ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(image::ImageError::from)
+        .and_then(|reader| {
+            let mut decoder = reader.into_decoder()?;
+            let orientation = decoder.orientation()?;
+            let mut img = DynamicImage::from_decoder(decoder)?;
+            img.apply_orientation(orientation);
+            Ok(img)
+        });
+    match decoded {
+        Ok(img) => {
+            let rgba = img.to_rgba8();
+            let width = rgba.width() as usize;
+            let height = rgba.height() as usize;
+            let bytes = rgba.into_raw();
+            
+            let image_data = ClipboardImage {
+                width,
+                height,
+                rgba: bytes,
+            };
+            
+            Ok((image_data, "image-rs"))
+        },
+        Err(e) => {
+            tracing::error!("Failed to decode image with both decoders: {:?}", e);
+            Err(anyhow::anyhow!("Failed to decode image: {}", e))
         }
-    });
+    }
+}
+
+/// 使用 zune-png 解码 8 位、非隔行、非动图的 PNG，截图基本都属于这一类。
+///
+/// # Returns
+/// 解码后的图片，16 位、隔行扫描或 APNG 返回 None，交由 image-rs 处理
+fn decode_png(data: &[u8]) -> anyhow::Result<Option<ClipboardImage>> {
+    let mut decoder = PngDecoder::new(data);
+    decoder.decode_headers().map_err(|e| anyhow::anyhow!("{:?}", e))?;
+    let interlaced = decoder.get_info().is_some_and(|info| info.interlace_method != InterlaceMethod::Standard);
+    if decoder.get_depth() != Some(BitDepth::Eight) || interlaced || decoder.is_animated() {
+        return Ok(None);
+    }
+    let (Some((width, height)), Some(colorspace)) = (decoder.get_dimensions(), decoder.get_colorspace()) else {
+        return Ok(None);
+    };
+    let orientation = decoder
+        .get_info()
+        .and_then(|info| info.exif.as_deref())
+        .and_then(Orientation::from_exif_chunk)
+        .unwrap_or(Orientation::NoTransforms);
+
+    let pixels = decoder.decode_raw().map_err(|e| anyhow::anyhow!("{:?}", e))?;
+    let rgba = match colorspace {
+        ColorSpace::RGBA => pixels,
+        ColorSpace::RGB => pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        ColorSpace::Luma => pixels.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+        ColorSpace::LumaA => pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        _ => return Ok(None),
+    };
+    if rgba.len() != width * height * 4 {
+        anyhow::bail!("Unexpected buffer size {} for {}x{}", rgba.len(), width, height);
+    }
+
+    let image_data = ClipboardImage { width, height, rgba };
+    Ok(Some(apply_orientation(image_data, orientation)?))
+}
+
+/// 按 EXIF 方向旋转或翻转已解码的图片，没有方向信息时原样返回。
+///
+/// # Arguments
+/// * `image` - 已解码的图片
+/// * `orientation` - EXIF 方向
+fn apply_orientation(image: ClipboardImage, orientation: Orientation) -> anyhow::Result<ClipboardImage> {
+    if orientation == Orientation::NoTransforms {
+        return Ok(image);
+    }
+    let buffer = RgbaImage::from_raw(image.width as u32, image.height as u32, image.rgba)
+        .ok_or_else(|| anyhow::anyhow!("Decoded image has an unexpected size"))?;
+    let mut dynamic = DynamicImage::ImageRgba8(buffer);
+    dynamic.apply_orientation(orientation);
+    let rgba = dynamic.into_rgba8();
+    Ok(ClipboardImage {
+        width: rgba.width() as usize,
+        height: rgba.height() as usize,
+        rgba: rgba.into_raw(),
+    })
+}
+
+/// 生成通知大图使用的预览，保存与复制仍使用原图。
+/// 通知中的大图不会按 EXIF 旋转，尺寸或文件过大时也会被系统丢弃，动图也无法正常显示，
+/// 因此需要旋转、过大的图片与动图的第一帧会另存一份缩小的 JPEG。
+///
+/// # Arguments
+/// * `path` - 图片文件路径
+///
+/// # Returns
+/// 预览文件路径，原图可直接显示时为 None；无法解码时返回错误
+fn hero_preview(path: &Path) -> anyhow::Result<Option<PathBuf>> {
+    // 视频不能直接显示，总是另存一份缩略图
+    if VideoFormat::from_path(path).is_some() {
+        let image = video::thumbnail(path, PREVIEW_SIZE)?;
+        return write_preview(path, &image).map(Some);
+    }
+
+    let size = std::fs::metadata(path)?.len();
+    let animated = is_animated(path)?;
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let (width, height) = decoder.dimensions();
+    let oversized = width.max(height) > PREVIEW_SIZE || size > MAX_HERO_BYTES;
+    if orientation == Orientation::NoTransforms && !oversized && !animated {
+        return Ok(None);
+    }
+
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    if image.width() > PREVIEW_SIZE || image.height() > PREVIEW_SIZE {
+        image = image.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE);
+    }
+    tracing::debug!("Hero preview for {:?}: {}x{} -> {}x{}", path, width, height, image.width(), image.height());
+    write_preview(path, &image).map(Some)
+}
+
+/// 将预览图另存为临时目录中的 JPEG，文件名由原文件名派生。
+///
+/// # Returns
+/// 预览文件路径
+fn write_preview(path: &Path, image: &DynamicImage) -> anyhow::Result<PathBuf> {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("image");
+    let target = std::env::temp_dir().join(format!("fastsync_preview_{}.jpg", stem.trim_start_matches("fastsync_")));
+    let file = std::io::BufWriter::new(std::fs::File::create(&target)?);
+    JpegEncoder::new_with_quality(file, PREVIEW_QUALITY).encode_image(&flatten(image))?;
+    Ok(target)
+}
+
+/// 是否为多帧的 GIF 或 WebP 动图，GIF 只读取到第二帧为止。
+fn is_animated(path: &Path) -> anyhow::Result<bool> {
+    let file = || std::fs::File::open(path).map(std::io::BufReader::new);
+    let animated = match ImageReader::open(path)?.with_guessed_format()?.format() {
+        Some(image::ImageFormat::Gif) => GifDecoder::new(file()?)?.into_frames().take(2).count() > 1,
+        Some(image::ImageFormat::WebP) => WebPDecoder::new(file()?)?.has_animation(),
+        _ => false,
+    };
+    Ok(animated)
+}
+
+/// 是否需要把图片转换为 JPEG 保存：只转换 HEIC，且当前构建能够解码。
+fn should_transcode(format: ImageFormat, transcode_heic: bool) -> bool {
+    transcode_heic && format == ImageFormat::Heic && format.is_decodable()
+}
+
+/// 将图片解码后重新编码为 JPEG，用于把 HEIC 转换为通用格式，EXIF 等元数据不会保留。
+fn transcode_to_jpeg(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let image = ImageReader::new(std::io::Cursor::new(data)).with_guessed_format()?.decode()?;
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, TRANSCODE_QUALITY).encode_image(&flatten(&image))?;
+    Ok(jpeg)
+}
+
+/// 将透明像素合成到白色背景上，JPEG 没有透明通道，直接丢弃会变成黑色。
+fn flatten(image: &DynamicImage) -> RgbImage {
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
 }
 
 /// 将解码后的图片数据写入剪贴板。
 ///
 /// # Arguments
-/// * `image_data` - 已解码的图片数据 (arboard::ImageData)
+/// * `clipboard` - 剪贴板后端
+/// * `image_data` - 已解码的图片数据
 /// * `decoder_name` - 使用的解码器名称 (用于日志记录)
-fn write_to_clipboard(image_data: arboard::ImageData, decoder_name: &str) {
-    match arboard::Clipboard::new() {
-        Ok(mut clipboard) => {
-            if let Err(e) = clipboard.set_image(image_data) {
-                tracing::error!("Failed to set clipboard image: {:?}", e);
-            } else {
-                tracing::info!("Image copied successfully using {} decoder", decoder_name);
-            }
-        },
-        Err(e) => tracing::error!("Failed to initialize clipboard: {:?}", e),
+fn write_to_clipboard(clipboard: &dyn ClipboardBackend, image_data: ClipboardImage, decoder_name: &str) -> anyhow::Result<()> {
+    if let Err(e) = clipboard.set_image(image_data) {
+        tracing::error!("Failed to set clipboard image: {:?}", e);
+        return Err(e);
     }
+    tracing::info!("Image copied successfully using {} decoder", decoder_name);
+    Ok(())
 }
 
 /// 将文本写入系统剪贴板（公开给 SMS 使用）。
 ///
 /// # Arguments
+/// * `clipboard` - 剪贴板后端
 /// * `text` - 文本内容
-pub fn copy_text_to_clipboard(text: &str) {
-    match arboard::Clipboard::new() {
-        Ok(mut clipboard) => {
-            if let Err(e) = clipboard.set_text(text.to_string()) {
-                tracing::error!("Failed to set clipboard text: {:?}", e);
-            } else {
-                tracing::info!("Text copied to clipboard successfully");
-            }
-        },
-        Err(e) => tracing::error!("Failed to initialize clipboard: {:?}", e),
+pub fn copy_text_to_clipboard(clipboard: &dyn ClipboardBackend, text: &str) -> anyhow::Result<()> {
+    if let Err(e) = clipboard.set_text(text) {
+        tracing::error!("Failed to set clipboard text: {:?}", e);
+        return Err(e);
     }
+    tracing::info!("Text copied to clipboard successfully");
+    Ok(())
 }
 
-/// 弹出文件保存对话框并保存图片。
+/// 弹出文件保存对话框并保存图片，默认扩展名与图片的实际格式一致。
 ///
 /// # Arguments
 /// * `data` - 图片二进制数据
-fn save_file_dialog(data: &[u8]) {
+/// * `transcode_heic` - HEIC 图片是否转换为 JPEG 保存
+///
+/// # Returns
+/// 最终保存路径，用户取消时为 None
+#[cfg(feature = "notifications")]
+fn save_file_dialog(data: &[u8], transcode_heic: bool) -> anyhow::Result<Option<PathBuf>> {
+    let format = detect_image_format(data).unwrap_or(ImageFormat::Png);
+    // 转换失败时仍保存原始文件
+    let transcoded = should_transcode(format, transcode_heic).then(|| transcode_to_jpeg(data)).and_then(|result| {
+        result
+            .inspect_err(|e| tracing::warn!("Failed to convert HEIC to JPEG, saving the original: {:#}", e))
+            .ok()
+    });
+    let (data, format) = match &transcoded {
+        Some(jpeg) => (jpeg.as_slice(), ImageFormat::Jpeg),
+        None => (data, format),
+    };
+    let extension = format.extension();
     let task = rfd::FileDialog::new()
-        .set_file_name("image.png")
-        .add_filter("Image", &["png", "jpg", "jpeg"])
+        .set_file_name(format!("image.{}", extension))
+        .add_filter("Image", &[extension])
         .save_file();
 
-    if let Some(path) = task {
-        use std::io::Write;
-        if let Ok(mut file) = std::fs::File::create(&path) {
-            if let Err(e) = file.write_all(data) {
-                tracing::error!("Failed to write file to {:?}: {:?}", path, e);
-            } else {
-                tracing::info!("File saved successfully to {:?}", path);
-            }
-        }
+    let Some(path) = task else {
+        return Ok(None);
+    };
+
+    if let Err(e) = std::fs::write(&path, data) {
+        tracing::error!("Failed to write file to {:?}: {:?}", path, e);
+        anyhow::bail!("Failed to write {:?}: {}", path, e);
+    }
+    tracing::info!("File saved successfully to {:?}", path);
+    Ok(Some(path))
+}
+
+/// 弹出文件保存对话框，将临时文件复制到用户选择的位置，默认使用手机端的文件名。
+///
+/// # Arguments
+/// * `source` - 临时文件路径
+/// * `file_name` - 默认文件名
+/// * `filter` - 对话框的文件类型筛选：名称与扩展名列表
+///
+/// # Returns
+/// 最终保存路径，用户取消时为 None
+#[cfg(feature = "notifications")]
+pub(crate) fn save_copy_dialog(source: &Path, file_name: &str, filter: Option<(&str, &[&str])>) -> anyhow::Result<Option<PathBuf>> {
+    use anyhow::Context;
+
+    let mut dialog = rfd::FileDialog::new().set_file_name(file_name);
+    if let Some((name, extensions)) = filter {
+        dialog = dialog.add_filter(name, extensions);
     }
+    let Some(path) = dialog.save_file() else {
+        return Ok(None);
+    };
+    std::fs::copy(source, &path).with_context(|| format!("Failed to copy {:?} to {:?}", source, path))?;
+    tracing::info!("File saved successfully to {:?}", path);
+    Ok(Some(path))
+}
+
+/// 未启用 `notifications` 特性时没有保存对话框。
+#[cfg(not(feature = "notifications"))]
+pub(crate) fn save_copy_dialog(_source: &Path, _file_name: &str, _filter: Option<(&str, &[&str])>) -> anyhow::Result<Option<PathBuf>> {
+    anyhow::bail!("Save dialog is unavailable without the notifications feature")
+}
+
+/// 弹出选择文件夹对话框。
+///
+/// # Returns
+/// 选择的文件夹，用户取消时为 None
+#[cfg(feature = "notifications")]
+fn pick_folder_dialog() -> anyhow::Result<Option<PathBuf>> {
+    Ok(rfd::FileDialog::new().pick_folder())
+}
+
+/// 未启用 `notifications` 特性时没有选择文件夹对话框。
+#[cfg(not(feature = "notifications"))]
+fn pick_folder_dialog() -> anyhow::Result<Option<PathBuf>> {
+    anyhow::bail!("Folder dialog is unavailable without the notifications feature")
+}
+
+/// 未启用 `notifications` 特性时没有保存对话框。
+#[cfg(not(feature = "notifications"))]
+fn save_file_dialog(_data: &[u8], _transcode_heic: bool) -> anyhow::Result<Option<PathBuf>> {
+    anyhow::bail!("Save dialog is unavailable without the notifications feature")
 }
@@ -0,0 +1,56 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{ConnectInfo, Json, Query, State},
+    http::{header, HeaderMap},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use crate::pairing::TokenStatus;
+use crate::state::AppState;
+
+/// `GET /ping` 查询参数。
+#[derive(Debug, Deserialize)]
+pub struct PingQuery {
+    /// 手机发出请求时的毫秒时间戳
+    pub client_time: Option<i64>,
+}
+
+/// 返回接收端时间，携带 `client_time` 时同时测量该设备的时钟偏差。
+///
+/// 偏差按设备标识记录，未携带有效令牌时按对端 IP 记录，与处理器中的来源一致。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `headers` - 请求头，用于识别设备
+/// * `connect_info` - 对端地址
+/// * `query` - 查询参数
+pub async fn ping(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<PingQuery>,
+) -> Json<Value> {
+    let server_time = chrono::Utc::now().timestamp_millis();
+    let Some(client_time) = query.client_time else {
+        return Json(json!({ "server_time": server_time }));
+    };
+
+    let device_id = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| match state.pairing.authenticate(token.trim()) {
+            TokenStatus::Valid(device_id) => Some(device_id),
+            _ => None,
+        });
+    let origin = device_id.or_else(|| connect_info.map(|ConnectInfo(addr)| addr.ip().to_string()));
+
+    let skew = origin
+        .map(|origin| state.clock.observe(&origin, client_time, server_time))
+        .unwrap_or(server_time - client_time);
+    Json(json!({ "server_time": server_time, "skew_ms": skew }))
+}
@@ -0,0 +1,215 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 上传接口的响应模块。
+ * 图片、短信与剪贴板接口都返回相同结构的 JSON，手机端按 `error` 区分
+ * 缺少字段、内容过大、无法解析与写入失败，不必只凭状态码猜测原因。
+ * 处理请求时发生 panic 也返回同样结构的 500 响应，不会中断服务。
+ */
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::any::Any;
+use crate::validation::PayloadRejection;
+
+/// 上传失败的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadError {
+    /// 缺少必需的字段，例如表单中没有 `data` 或上传的内容为空
+    MissingField,
+    /// 请求体超过大小限制
+    TooLarge,
+    /// 请求体无法解析
+    DecodeFailed,
+    /// 内容不是支持的格式
+    UnsupportedFormat,
+    /// 写入或保存文件失败
+    IoError,
+    /// 收到的内容与手机端提供的 SHA-256 不一致，传输中被截断或损坏
+    ChecksumMismatch,
+    /// 处理请求时发生意外错误
+    Internal,
+}
+
+impl UploadError {
+    /// 机器可读的错误标识。
+    pub fn code(&self) -> &'static str {
+        match self {
+            UploadError::MissingField => "missing_field",
+            UploadError::TooLarge => "too_large",
+            UploadError::DecodeFailed => "decode_failed",
+            UploadError::UnsupportedFormat => "unsupported_format",
+            UploadError::IoError => "io_error",
+            UploadError::ChecksumMismatch => "checksum_mismatch",
+            UploadError::Internal => "internal_error",
+        }
+    }
+
+    /// 对应的 HTTP 状态码。
+    pub fn status(&self) -> StatusCode {
+        match self {
+            UploadError::MissingField | UploadError::DecodeFailed => StatusCode::BAD_REQUEST,
+            UploadError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            UploadError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UploadError::IoError | UploadError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            UploadError::ChecksumMismatch => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+/// 上传接口的响应体，接口特有的字段平铺在同一层。
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadResponse {
+    #[serde(skip)]
+    status: StatusCode,
+    /// 内容是否被接收
+    pub ok: bool,
+    /// 失败原因，成功时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+    /// 收到的内容字节数
+    pub bytes: u64,
+    /// 内容的 SHA-256，未收到完整内容时省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// 接口特有的字段
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+impl UploadResponse {
+    /// 接收成功的响应。
+    ///
+    /// # Arguments
+    /// * `bytes` - 收到的内容字节数
+    /// * `hash` - 内容的 SHA-256
+    pub fn success(bytes: u64, hash: Option<String>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            ok: true,
+            error: None,
+            bytes,
+            hash,
+            details: Map::new(),
+        }
+    }
+
+    /// 按失败原因生成响应，状态码由原因决定。
+    pub fn failure(error: UploadError) -> Self {
+        Self::rejected(error.status(), error.code())
+    }
+
+    /// 以指定的状态码与错误标识生成失败响应，用于内容策略等其他来源的拒绝。
+    ///
+    /// # Arguments
+    /// * `status` - HTTP 状态码
+    /// * `error` - 错误标识
+    pub fn rejected(status: StatusCode, error: &'static str) -> Self {
+        Self {
+            status,
+            ok: false,
+            error: Some(error),
+            bytes: 0,
+            hash: None,
+            details: Map::new(),
+        }
+    }
+
+    /// 设置收到的内容字节数与哈希。
+    pub fn with_content(mut self, bytes: u64, hash: Option<String>) -> Self {
+        self.bytes = bytes;
+        self.hash = hash;
+        self
+    }
+
+    /// 添加接口特有的字段。
+    ///
+    /// # Arguments
+    /// * `key` - 字段名
+    /// * `value` - 字段值
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.details.insert(key.to_string(), value);
+        }
+        self
+    }
+
+    /// 响应的 HTTP 状态码。
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl From<PayloadRejection> for UploadResponse {
+    /// JSON 载荷被拒绝时的响应，保留行列号与出错字段等细节。
+    fn from(rejection: PayloadRejection) -> Self {
+        match rejection {
+            PayloadRejection::UnsupportedMediaType => {
+                UploadResponse::failure(UploadError::UnsupportedFormat).with_detail("expected", "application/json")
+            }
+            PayloadRejection::Body { status, message } => {
+                let error = if status == StatusCode::PAYLOAD_TOO_LARGE {
+                    UploadError::TooLarge
+                } else {
+                    UploadError::DecodeFailed
+                };
+                UploadResponse::failure(error).with_detail("message", message)
+            }
+            PayloadRejection::Syntax { line, column, message } => UploadResponse::failure(UploadError::DecodeFailed)
+                .with_detail("line", line)
+                .with_detail("column", column)
+                .with_detail("message", message),
+            // JSON 合法但不符合载荷结构时按字段报告，手机端据此指出具体字段
+            PayloadRejection::Invalid(field) => {
+                let response = UploadResponse::rejected(StatusCode::UNPROCESSABLE_ENTITY, "invalid_payload")
+                    .with_detail("field", &field.field)
+                    .with_detail("reason", field.reason)
+                    .with_detail("message", &field.message);
+                match &field.expected {
+                    Some(expected) => response.with_detail("expected", expected),
+                    None => response,
+                }
+            }
+        }
+    }
+}
+
+impl IntoResponse for UploadResponse {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+impl IntoResponse for UploadError {
+    fn into_response(self) -> Response {
+        UploadResponse::failure(self).into_response()
+    }
+}
+
+/// 处理请求时发生 panic 的响应，只影响这一个请求，连接与其他请求照常处理。
+///
+/// # Arguments
+/// * `panic` - panic 携带的值
+///
+/// # Returns
+/// 500 响应，响应体为 `UploadResponse`
+pub(crate) fn panic_response(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic");
+    tracing::error!("Request handler panicked: {}", message);
+    UploadError::Internal.into_response()
+}
+
+/// 计算文本内容的 SHA-256，与图片的内容哈希格式一致。
+pub fn text_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
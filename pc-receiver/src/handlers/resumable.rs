@@ -0,0 +1,300 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 分块续传接口模块。
+ * `POST /upload/init` 登记文件，`PUT /upload/chunk/{id}` 按 `Upload-Offset` 写入分块，
+ * `GET /upload/status/{id}` 查询已收到的字节数，`POST /upload/complete/{id}` 校验哈希后交给图片处理流程。
+ */
+use axum::{
+    body::Body,
+    extract::{Extension, FromRequestParts, Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use serde::Deserialize;
+use sha2::Digest;
+use std::io::SeekFrom;
+use std::time::Instant;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use crate::auth::AuthenticatedDevice;
+use crate::handlers::response::{UploadError, UploadResponse};
+use crate::payload::{PayloadContext, PayloadHandler, PayloadOutcome};
+use crate::policy::ContentInfo;
+use crate::resumable::{ChunkSlot, SessionError};
+use crate::state::AppState;
+use crate::validation::{PayloadRejection, ValidJson};
+
+/// 分块在文件中的起始位置。
+const OFFSET_HEADER: &str = "upload-offset";
+
+/// 登记分块上传的请求体。
+#[derive(Debug, Clone, Deserialize)]
+pub struct InitRequest {
+    pub file_name: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 整个文件的 SHA-256
+    pub sha256: String,
+    /// 拍摄时间（毫秒时间戳）
+    #[serde(default)]
+    pub captured_at: Option<i64>,
+}
+
+/// 登记分块上传，返回上传标识与已收到的字节数。同一设备重复登记相同的文件时返回原来的上传。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `device` - 通过令牌校验的设备
+/// * `request` - 文件名、大小与哈希
+pub async fn init(
+    State(state): State<AppState>,
+    device: Option<Extension<AuthenticatedDevice>>,
+    request: Result<ValidJson<InitRequest>, PayloadRejection>,
+) -> Response {
+    let request = match request {
+        Ok(ValidJson(request)) => request,
+        Err(rejection) => return UploadResponse::from(rejection).into_response(),
+    };
+    let device = device.map(|Extension(AuthenticatedDevice(id))| id);
+
+    if request.size == 0 {
+        return UploadResponse::failure(UploadError::DecodeFailed)
+            .with_detail("field", "size")
+            .into_response();
+    }
+    if request.sha256.len() != 64 || !request.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return UploadResponse::failure(UploadError::DecodeFailed)
+            .with_detail("field", "sha256")
+            .into_response();
+    }
+
+    // 按登记的文件名与大小提前判定，不必传完才被拒绝
+    let content = ContentInfo {
+        mime: None,
+        file_name: Some(&request.file_name),
+        size: request.size,
+    };
+    if let Err(violation) = state.policy.check("photo", device.as_deref(), &content) {
+        return violation.into_response();
+    }
+    if let Err(issue) = state.storage.check(&std::env::temp_dir(), request.size) {
+        return issue.into_response();
+    }
+
+    let begun = state.resumable.begin(
+        device.as_deref(),
+        &request.file_name,
+        request.size,
+        &request.sha256,
+        request.captured_at,
+        Instant::now(),
+    );
+    match begun {
+        Ok(progress) => {
+            tracing::info!(
+                "Resumable upload {} for {} ({} bytes, {} received)",
+                progress.id,
+                request.file_name,
+                progress.size,
+                progress.received
+            );
+            UploadResponse::success(progress.received, None)
+                .with_detail("id", progress.id)
+                .with_detail("size", progress.size)
+                .with_detail("expires_in", state.resumable.window().as_secs())
+                .into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to start resumable upload: {:?}", e);
+            UploadResponse::failure(UploadError::IoError).into_response()
+        }
+    }
+}
+
+/// 查询分块上传已收到的字节数，手机端中断后从该位置继续。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `device` - 通过令牌校验的设备
+/// * `id` - 上传标识
+pub async fn status(
+    State(state): State<AppState>,
+    device: Option<Extension<AuthenticatedDevice>>,
+    Path(id): Path<String>,
+) -> Response {
+    let device = device.map(|Extension(AuthenticatedDevice(id))| id);
+    match state.resumable.progress(&id, device.as_deref()) {
+        Ok(progress) => UploadResponse::success(progress.received, None)
+            .with_detail("id", progress.id)
+            .with_detail("size", progress.size)
+            .with_detail("complete", progress.received == progress.size)
+            .into_response(),
+        Err(error) => session_failure(error).into_response(),
+    }
+}
+
+/// 写入一个分块。请求头 `Upload-Offset` 必须等于已收到的字节数，请求体中断时保留已写入的部分。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `device` - 通过令牌校验的设备
+/// * `id` - 上传标识
+/// * `headers` - 请求头
+/// * `body` - 分块内容
+pub async fn chunk(
+    State(state): State<AppState>,
+    device: Option<Extension<AuthenticatedDevice>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let device = device.map(|Extension(AuthenticatedDevice(id))| id);
+    let offset = headers
+        .get(OFFSET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    let Some(offset) = offset else {
+        return UploadResponse::failure(UploadError::MissingField)
+            .with_detail("field", "Upload-Offset")
+            .into_response();
+    };
+
+    let mut slot = match state.resumable.claim(&id, device.as_deref(), offset, Instant::now()) {
+        Ok(slot) => slot,
+        Err(error) => return session_failure(error).into_response(),
+    };
+    let size = slot.offset + slot.remaining;
+    let (written, result) = write_chunk(&mut slot, body).await;
+    let received = state
+        .resumable
+        .release(&id, written, slot.hasher, Instant::now())
+        .unwrap_or(offset + written);
+
+    let response = match result {
+        Ok(()) => {
+            tracing::debug!("Resumable upload {}: {} of {} bytes", id, received, size);
+            UploadResponse::success(received, None)
+        }
+        Err(ChunkError::TooLarge) => {
+            tracing::warn!("Resumable upload {} chunk exceeds the declared size", id);
+            UploadResponse::failure(UploadError::TooLarge)
+        }
+        Err(ChunkError::Body(e)) => {
+            tracing::warn!("Resumable upload {} interrupted at {} bytes: {}", id, received, e);
+            UploadResponse::failure(UploadError::DecodeFailed).with_detail("message", e.to_string())
+        }
+        Err(ChunkError::Write(e)) => {
+            tracing::error!("Failed to write resumable upload {}: {:?}", id, e);
+            UploadResponse::failure(UploadError::IoError)
+        }
+    };
+    response
+        .with_content(received, None)
+        .with_detail("id", id)
+        .with_detail("size", size)
+        .into_response()
+}
+
+/// 写入分块失败的原因。
+enum ChunkError {
+    /// 超过登记的文件大小
+    TooLarge,
+    /// 读取请求体失败
+    Body(axum::Error),
+    /// 写入临时文件失败
+    Write(std::io::Error),
+}
+
+/// 将请求体写入临时文件的指定位置，只有写入成功的部分计入哈希。
+///
+/// # Returns
+/// 写入的字节数与写入结果
+async fn write_chunk(slot: &mut ChunkSlot, body: Body) -> (u64, Result<(), ChunkError>) {
+    let mut file = match tokio::fs::OpenOptions::new().write(true).open(&slot.path).await {
+        Ok(file) => file,
+        Err(e) => return (0, Err(ChunkError::Write(e))),
+    };
+    if let Err(e) = file.seek(SeekFrom::Start(slot.offset)).await {
+        return (0, Err(ChunkError::Write(e)));
+    }
+
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    let mut result = Ok(());
+    while let Some(data) = stream.next().await {
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => {
+                result = Err(ChunkError::Body(e));
+                break;
+            }
+        };
+        if written + data.len() as u64 > slot.remaining {
+            result = Err(ChunkError::TooLarge);
+            break;
+        }
+        if let Err(e) = file.write_all(&data).await {
+            result = Err(ChunkError::Write(e));
+            break;
+        }
+        slot.hasher.update(&data);
+        written += data.len() as u64;
+    }
+
+    // 丢弃写了一半的数据块，文件长度始终等于已计入的字节数
+    let truncated = async {
+        file.flush().await?;
+        file.set_len(slot.offset + written).await
+    };
+    if let Err(e) = truncated.await {
+        tracing::warn!("Failed to truncate resumable upload {:?}: {:?}", slot.path, e);
+    }
+    (written, result)
+}
+
+/// 会话操作被拒绝时的响应。
+fn session_failure(error: SessionError) -> UploadResponse {
+    match error {
+        SessionError::NotFound => UploadResponse::rejected(StatusCode::NOT_FOUND, "not_found"),
+        SessionError::Busy => UploadResponse::rejected(StatusCode::CONFLICT, "busy"),
+        SessionError::OffsetMismatch { received } => {
+            UploadResponse::rejected(StatusCode::CONFLICT, "offset_mismatch").with_content(received, None)
+        }
+        SessionError::Incomplete { received } => {
+            UploadResponse::rejected(StatusCode::CONFLICT, "incomplete").with_content(received, None)
+        }
+    }
+}
+
+/// 内置分块上传完成处理器，路由 `POST /upload/complete/{id}`，与表单上传共用图片的处理流程与通知。
+pub(crate) struct ResumableCompleteHandler;
+
+impl PayloadHandler for ResumableCompleteHandler {
+    fn path(&self) -> &str {
+        "/upload/complete/:id"
+    }
+
+    fn capability(&self) -> &str {
+        "photo"
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            let (mut parts, _) = request.into_parts();
+            let id = match Path::<String>::from_request_parts(&mut parts, &()).await {
+                Ok(Path(id)) => id,
+                Err(rejection) => return PayloadOutcome::new(rejection),
+            };
+            match ctx.state.resumable.finish(&id, ctx.device.device_id.as_deref()) {
+                Ok(upload) => {
+                    tracing::info!("Resumable upload {} complete ({} bytes)", id, upload.size);
+                    crate::handlers::photo::receive_assembled(ctx, upload).await
+                }
+                Err(error) => PayloadOutcome::new(session_failure(error)),
+            }
+        })
+    }
+}
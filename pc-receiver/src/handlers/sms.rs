@@ -3,127 +3,632 @@
  * @Date: 2026-02-19
  */
 use axum::{
-    extract::Json,
+    extract::{ConnectInfo, FromRequest, Path, Query, Request, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
 };
-use serde::Deserialize;
-use windows::{
-    core::*,
-    Data::Xml::Dom::XmlDocument,
-    UI::Notifications::{ToastNotification, ToastNotificationManager},
-    Foundation::{DateTime, IReference, PropertyValue},
-};
-use crate::APP_ID;
-use crate::handlers::store_notification;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use fastsync_core::protocol::SmsPayload;
+use fastsync_core::timeline::CaptureTime;
+use crate::attention::UrgentKind;
+use crate::auth::is_local_admin;
+use crate::events::ServerEvent;
+use crate::handlers::photo::ImageActions;
+use crate::handlers::response::{text_hash, UploadError, UploadResponse};
+use crate::image_format::ImageFormat;
+use crate::notifier::{split_input, ActionHandler, Notification, NotificationAction, NotificationInput};
+use crate::payload::{PayloadContext, PayloadHandler, PayloadOutcome};
+use crate::policy::ContentInfo;
+use crate::realtime::ServerMessage;
+use crate::sms_history::{SmsHistoryFilter, SmsReply};
+use crate::state::{AppState, RunMode};
+use crate::timings::Stage;
+use crate::validation::ValidJson;
 
-/// 短信数据载荷结构体。
-#[derive(Debug, Deserialize)]
-pub struct SmsPayload {
-    pub sender: String,
-    pub content: String,
-    pub code: String,
+/// 手机端转发彩信时填入原文的占位文字，通知中不显示。
+const MMS_PLACEHOLDER: &str = "[图片]";
+
+/// 默认的短信去重窗口。
+pub const DEFAULT_SMS_DEDUP_WINDOW: Duration = Duration::from_secs(2 * 60);
+
+/// 默认的短信过期时间，手机端补发的短信超过该时长不再弹通知。
+pub const DEFAULT_SMS_STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// 默认的验证码通知保留时长。
+pub const DEFAULT_SMS_CODE_EXPIRY: Duration = Duration::from_secs(30);
+
+/// 内置短信处理器，路由 `POST /sms`。
+pub(crate) struct SmsHandler;
+
+impl PayloadHandler for SmsHandler {
+    fn path(&self) -> &str {
+        "/sms"
+    }
+
+    fn capability(&self) -> &str {
+        "sms"
+    }
+
+    fn cross_origin(&self) -> bool {
+        true
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            match ValidJson::<SmsPayload>::from_request(request, &()).await {
+                Ok(ValidJson(payload)) => receive_sms(ctx, payload).await,
+                Err(rejection) => PayloadOutcome::new(UploadResponse::from(rejection)),
+            }
+        })
+    }
+}
+
+/// 内置批量短信处理器，路由 `POST /sms/batch`。
+///
+/// 手机离线后恢复连接时一次补发积压的短信：未过期的验证码短信逐条弹出通知，
+/// 其余需要通知的短信合并为一条汇总通知，被过滤或已过期的短信只写入短信历史。
+pub(crate) struct SmsBatchHandler;
+
+impl PayloadHandler for SmsBatchHandler {
+    fn path(&self) -> &str {
+        "/sms/batch"
+    }
+
+    fn capability(&self) -> &str {
+        "sms_batch"
+    }
+
+    fn cross_origin(&self) -> bool {
+        true
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            match ValidJson::<Vec<SmsPayload>>::from_request(request, &()).await {
+                Ok(ValidJson(payloads)) => receive_sms_batch(ctx, payloads).await,
+                Err(rejection) => PayloadOutcome::new(UploadResponse::from(rejection)),
+            }
+        })
+    }
+}
+
+/// 批量上传中一条短信的处理结果，手机端据此将短信标记为已同步。
+#[derive(Debug, Clone, Serialize)]
+struct BatchItem {
+    /// 在请求数组中的位置
+    index: usize,
+    /// 是否已接收并写入短信历史
+    accepted: bool,
+    /// 通知方式：`toast` 单独通知 / `summary` 合并在汇总通知中 / `none` 不通知
+    notification: &'static str,
+    /// 不通知的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suppressed: Option<&'static str>,
 }
 
 /// 处理短信上传请求。
 ///
 /// # Arguments
+/// * `ctx` - 处理器上下文
 /// * `payload` - 包含短信信息的 JSON 数据
 ///
 /// # Returns
-/// HTTP 状态码
-pub async fn receive_sms(Json(payload): Json<SmsPayload>) -> StatusCode {
+/// 处理结果
+async fn receive_sms(ctx: PayloadContext, payload: SmsPayload) -> PayloadOutcome {
+    ctx.mark(Stage::Received);
+    let (payload, suppressed) = accept_sms(&ctx, payload);
+
+    let response = UploadResponse::success(payload.content.len() as u64, Some(text_hash(&payload.content)));
+    if suppressed == Some(DUPLICATE) {
+        return PayloadOutcome::new(response.with_detail("duplicate", true));
+    }
+    // 被过滤的短信照常返回成功，只是不弹通知
+    if let Some(reason) = suppressed {
+        return PayloadOutcome::new(response.with_detail("suppressed", reason));
+    }
+
+    let (image, response) = match receive_attachment(&ctx, &payload).await {
+        Ok(image) => (image, response),
+        Err(error) => (None, response.with_detail("image_error", error.code())),
+    };
+    // 非桌面模式不写入剪贴板
+    let auto_copy = ctx.state.mode == RunMode::Desktop && ctx.state.config.read().unwrap().sms.auto_copy_codes;
+    let copied = if !payload.code.is_empty() && auto_copy {
+        Some(auto_copy_code(&ctx, &payload).await)
+    } else {
+        None
+    };
+    let (notification, on_action) = build_sms_notification(&ctx, &payload, copied, image);
+    PayloadOutcome::new(response).with_notification(notification, on_action)
+}
+
+/// 将彩信附带的图片写入临时目录。图片无法解码或不是支持的格式时只记录日志，短信照常通知。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payload` - 短信数据载荷
+///
+/// # Returns
+/// 临时图片的路径与格式，没有附带图片时为 None
+async fn receive_attachment(ctx: &PayloadContext, payload: &SmsPayload) -> Result<Option<(PathBuf, ImageFormat)>, UploadError> {
+    let Some(encoded) = payload.image_base64.as_deref().filter(|encoded| !encoded.is_empty()) else {
+        return Ok(None);
+    };
+    let (path, format) = crate::handlers::photo::receive_base64_image(ctx, encoded).await.inspect_err(|e| {
+        tracing::warn!("MMS image from {} was not shown: {}", payload.sender, e.code());
+    })?;
+    tracing::info!("MMS image from {} saved to {:?}", payload.sender, path);
+    Ok(Some((path, format)))
+}
+
+/// 接收一条短信：补全验证码、发出事件、写入短信历史，并判定是否弹出通知。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payload` - 短信数据载荷
+///
+/// # Returns
+/// 补全验证码后的短信，以及不弹通知时的原因（`duplicate` / `blocked` / `not_allowed` / `stale`）
+fn accept_sms(ctx: &PayloadContext, mut payload: SmsPayload) -> (SmsPayload, Option<&'static str>) {
+    // 手机端超时重发的短信只处理第一次，不再写入历史
+    let key = dedup_key(&payload);
+    let device = ctx.device.origin();
+    if ctx.state.recent_sms.find(device.as_deref(), &key, Instant::now()).is_some() {
+        tracing::info!("SMS from {} is a retransmission, skipped", payload.sender);
+        return (payload, Some(DUPLICATE));
+    }
+    ctx.state.recent_sms.record(device.as_deref(), &key, &key, Instant::now());
+
     tracing::info!("Received SMS from {}: {}", payload.sender, payload.content);
-    
-    if let Err(e) = show_sms_notification(&payload) {
-        tracing::error!("Failed to show SMS notification: {:?}", e);
+    // 手机端未识别验证码时从原文中提取
+    if payload.code.is_empty() {
+        if let Some(code) = ctx.state.sms_codes.read().unwrap().extract(&payload.content) {
+            tracing::info!("Extracted verification code from SMS content");
+            payload.code = code;
+        }
+    }
+    ctx.emit(ServerEvent::SmsReceived {
+        sender: payload.sender.clone(),
+        content: payload.content.clone(),
+        code: payload.code.clone(),
+    });
+
+    let filter = ctx.state.config.read().unwrap().sms.filter.clone();
+    // 手机端未提供联系人名称时使用本地配置的名称
+    if payload.display_sender() == payload.sender {
+        if let Some(name) = filter.alias(&payload.sender) {
+            payload.sender_name = Some(name.to_string());
+        }
+    }
+    let suppressed = filter.check(&payload.sender, &payload.content, &payload.code);
+    let capture = ctx.capture_time(payload.captured_at);
+    if suppressed.is_none() || filter.record_suppressed {
+        ctx.state
+            .sms_history
+            .record(&payload.sender, &payload.content, &payload.code, capture, ctx.device.origin().as_deref());
+    }
+
+    if let Some(reason) = suppressed {
+        tracing::info!("Suppressed SMS notification from {} ({})", payload.sender, reason.as_str());
+        ctx.audit("sms").with_capture(capture).record("suppressed", None, Some(reason.as_str().to_string()));
+        return (payload, Some(reason.as_str()));
+    }
+    // 离线积压后补发的短信，其中的验证码多半已经失效
+    if is_stale(ctx, &payload, capture) {
+        tracing::info!(
+            "Skipping notification for stale SMS from {} (received on the phone {})",
+            payload.sender,
+            capture.relative_to(capture.received_at)
+        );
+        ctx.audit("sms").with_capture(capture).record("stale", None, None);
+        return (payload, Some("stale"));
+    }
+    (payload, None)
+}
+
+/// 处理批量短信上传请求。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payloads` - 按手机上收到的顺序排列的短信
+///
+/// # Returns
+/// 处理结果，响应的 `items` 中包含每条短信的处理结果
+async fn receive_sms_batch(ctx: PayloadContext, payloads: Vec<SmsPayload>) -> PayloadOutcome {
+    ctx.mark(Stage::Received);
+    tracing::info!("Received batch of {} SMS", payloads.len());
+    let bytes = payloads.iter().map(|payload| payload.content.len() as u64).sum();
+
+    let mut items = Vec::with_capacity(payloads.len());
+    let mut toasts = Vec::new();
+    let mut summarized = Vec::new();
+    for (index, payload) in payloads.into_iter().enumerate() {
+        let (payload, suppressed) = accept_sms(&ctx, payload);
+        let notification = match suppressed {
+            Some(_) => "none",
+            None if !payload.code.is_empty() => "toast",
+            None => "summary",
+        };
+        items.push(BatchItem {
+            index,
+            accepted: true,
+            notification,
+            suppressed,
+        });
+        match notification {
+            "toast" => toasts.push(payload),
+            "summary" => summarized.push(payload),
+            _ => {}
+        }
+    }
+
+    // 只有一条时不必汇总，照常单独通知
+    if summarized.len() == 1 {
+        if let Some(item) = items.iter_mut().find(|item| item.notification == "summary") {
+            item.notification = "toast";
+        }
+    }
+
+    let mut outcome = PayloadOutcome::new(UploadResponse::success(bytes, None).with_detail("items", &items));
+    // 非桌面模式不写入剪贴板
+    let auto_copy = ctx.state.mode == RunMode::Desktop && ctx.state.config.read().unwrap().sms.auto_copy_codes;
+    for payload in &toasts {
+        let image = receive_attachment(&ctx, payload).await.ok().flatten();
+        let copied = if auto_copy { Some(auto_copy_code(&ctx, payload).await) } else { None };
+        let (notification, on_action) = build_sms_notification(&ctx, payload, copied, image);
+        outcome = outcome.with_notification(notification, on_action);
     }
-    
-    StatusCode::OK
+    if let [payload] = summarized.as_slice() {
+        let image = receive_attachment(&ctx, payload).await.ok().flatten();
+        let (notification, on_action) = build_sms_notification(&ctx, payload, None, image);
+        outcome = outcome.with_notification(notification, on_action);
+    } else if !summarized.is_empty() {
+        let (notification, on_action) = build_summary_notification(&ctx, &summarized);
+        outcome = outcome.with_notification(notification, on_action);
+    }
+    outcome
+}
+
+/// 短信通知中回复输入框的标识。
+const REPLY_INPUT: &str = "reply_text";
+
+/// 汇总通知中列出的短信条数。
+const SUMMARY_PREVIEW: usize = 3;
+
+/// 构建批量短信的汇总通知。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payloads` - 合并到汇总通知中的短信
+///
+/// # Returns
+/// 通知描述与按钮回调
+fn build_summary_notification(ctx: &PayloadContext, payloads: &[SmsPayload]) -> (Notification, ActionHandler) {
+    let audit = ctx.audit("sms");
+    let mut notification = Notification::new(&format!("sms_batch_{}", audit.item_id()), &format!("收到 {} 条短信", payloads.len()));
+    notification.group = "sms".to_string();
+    notification.body.extend(
+        payloads
+            .iter()
+            .take(SUMMARY_PREVIEW)
+            .map(|payload| format!("{}：{}", payload.display_sender(), payload.content)),
+    );
+    if payloads.len() > SUMMARY_PREVIEW {
+        notification.body.push(format!("另有 {} 条，可在短信历史中查看", payloads.len() - SUMMARY_PREVIEW));
+    }
+    notification.long_duration = true;
+    notification.expires_in = Duration::from_secs(60);
+    // 带验证码的短信单独通知，汇总中只有普通短信
+    notification.quiet = ctx.state.config.read().unwrap().sms.filter.popup.is_quiet(false);
+    notification.actions.push(NotificationAction::new("copy_all", "复制全部"));
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+
+    let clipboard = ctx.clipboard();
+    let text = payloads
+        .iter()
+        .map(|payload| format!("{}：{}", payload.display_sender(), payload.content))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        if arguments == "copy_all" {
+            tracing::info!("Copy all SMS clicked");
+            let result = crate::handlers::photo::copy_text_to_clipboard(clipboard.as_ref(), &text);
+            audit.record_result("copy", None, &result);
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore SMS summary clicked");
+            audit.record("ignore", None, None);
+        }
+    });
+
+    (notification, on_action)
 }
 
-/// 显示带有交互按钮的 Windows Toast 通知 (短信)。
+/// 重发的短信不弹通知时的原因。
+const DUPLICATE: &str = "duplicate";
+
+/// 短信的去重键：发送方、原文、彩信图片与手机上收到的时间（精确到秒）。
+/// 手机端重发时这些都不变，服务方重复发送的相同验证码短信收到时间不同，不会被误判为重复。
+fn dedup_key(payload: &SmsPayload) -> String {
+    let second = payload.captured_at.map(|captured_at| captured_at.div_euclid(1000));
+    let image = payload.image_base64.as_deref().map(text_hash);
+    text_hash(&format!("{}\n{}\n{:?}\n{:?}", payload.sender, payload.content, second, image))
+}
+
+/// 短信是否早已在手机上收到，超过过期时间。没有时间戳或时间明显错误的短信不算过期。
 ///
 /// # Arguments
+/// * `ctx` - 处理器上下文
 /// * `payload` - 短信数据载荷
+/// * `capture` - 校正后的时间
+fn is_stale(ctx: &PayloadContext, payload: &SmsPayload, capture: CaptureTime) -> bool {
+    let stale_after = ctx.state.config.read().unwrap().sms.stale_after;
+    payload.captured_at.is_some()
+        && !capture.clamped
+        && !stale_after.is_zero()
+        && capture.received_at - capture.captured_at > stale_after.as_millis() as i64
+}
+
+/// 短信在手机上收到的时间：一小时内显示相对时间，更早的显示具体时间。
+fn received_label(capture: CaptureTime) -> String {
+    if capture.received_at - capture.captured_at < 60 * 60 * 1000 {
+        capture.relative_to(capture.received_at)
+    } else {
+        capture.local_time()
+    }
+}
+
+/// 不等点击直接将验证码写入剪贴板，失败时退回通知中的复制按钮。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payload` - 带验证码的短信
 ///
 /// # Returns
-/// 操作结果 Result
-fn show_sms_notification(payload: &SmsPayload) -> windows::core::Result<()> {
-    let toast_xml = XmlDocument::new()?;
-    
-    let sender_escaped = payload.sender.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;");
-    let content_escaped = payload.content.replace("&", "&amp;").replace("<", "&lt;").replace(">", "&gt;");
-    
-    let mut actions_xml = String::from(r#"
-            <action content='复制原文' arguments='copy_content'/>
-    "#);
-
-    if !payload.code.is_empty() {
-         actions_xml.push_str(r#"
-            <action content='复制验证码' arguments='copy_code'/>
-         "#);
-    }
-    
-    actions_xml.push_str(r#"
-            <action content='忽略' arguments='ignore'/>
-    "#);
-
-    let xml_string = format!(r#"
-        <toast duration="long" activationType='background'>
-        <visual>
-            <binding template='ToastGeneric'>
-                <text>收到手机短信 - {}</text>
-                <text>{}</text>
-            </binding>
-        </visual>
-        <actions>
-            {}
-        </actions>
-        </toast>
-    "#, sender_escaped, content_escaped, actions_xml);
-
-    toast_xml.LoadXml(&HSTRING::from(xml_string))?;
-
-    let notification = ToastNotification::CreateToastNotification(&toast_xml)?;
-
-    notification.SetTag(&HSTRING::from("sms_sync"))?;
-    notification.SetGroup(&HSTRING::from("FastSync"))?;
-
-    let now_unix_millis = chrono::Utc::now().timestamp_millis();
-    let expiration_millis = now_unix_millis + 60_000; 
-    let expiration_ticks = (expiration_millis * 10_000) + 116444736000000000;
-    
-    let expiry_time = DateTime { UniversalTime: expiration_ticks };
-    let expiry_inspectable = PropertyValue::CreateDateTime(expiry_time)?;
-    let expiry_reference: IReference<DateTime> = expiry_inspectable.cast()?;
-    notification.SetExpirationTime(&expiry_reference)?;
+/// 写入结果，失败时通知中仍提供复制按钮
+async fn auto_copy_code(ctx: &PayloadContext, payload: &SmsPayload) -> anyhow::Result<()> {
+    let clipboard = ctx.clipboard();
+    let code = payload.code.clone();
+    let result = tokio::task::spawn_blocking(move || crate::handlers::photo::copy_text_to_clipboard(clipboard.as_ref(), &code))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    match &result {
+        Ok(()) => tracing::info!("Clipboard modified automatically with the verification code from {}", payload.sender),
+        Err(e) => tracing::warn!("Failed to auto-copy verification code, offering the copy button instead: {:#}", e),
+    }
+    result
+}
 
+/// 构建带有交互按钮的通知 (短信)。
+///
+/// # Arguments
+/// * `ctx` - 处理器上下文
+/// * `payload` - 短信数据载荷
+/// * `auto_copy` - 自动复制验证码的结果，未尝试时为 None；已复制时只保留“忽略”与图片按钮
+/// * `image` - 彩信图片的临时路径与格式
+///
+/// # Returns
+/// 通知描述与按钮回调
+fn build_sms_notification(
+    ctx: &PayloadContext,
+    payload: &SmsPayload,
+    auto_copy: Option<anyhow::Result<()>>,
+    image: Option<(PathBuf, ImageFormat)>,
+) -> (Notification, ActionHandler) {
+    let capture = ctx.capture_time(payload.captured_at);
+    let audit = ctx.audit("sms").with_capture(capture);
+    if let Some(result) = &auto_copy {
+        audit.record_result("auto_copy_code", None, result);
+    }
+    let copied = matches!(auto_copy, Some(Ok(())));
+    let has_code = !payload.code.is_empty();
+    // 验证码单独作为标题行，不用展开通知即可看到
+    let title = if copied {
+        format!("验证码已复制: {}", payload.code)
+    } else if has_code {
+        format!("验证码: {}", payload.code)
+    } else if image.is_some() {
+        format!("{} - {}", ctx.device.received_title("彩信"), payload.display_sender())
+    } else {
+        format!("{} - {}", ctx.device.received_title("短信"), payload.display_sender())
+    };
+    // 每条短信使用独立的标签，连续收到的验证码不会互相替换
+    let mut notification = Notification::new(&format!("sms_{}", audit.item_id()), &title);
+    notification.group = "sms".to_string();
+    if has_code {
+        notification.body.push(format!("来自 {}", payload.display_sender()));
+    }
+    let has_text = !payload.content.trim().is_empty() && payload.content.trim() != MMS_PLACEHOLDER;
+    if has_text || image.is_none() {
+        notification.body.push(payload.content.clone());
+    }
+    // 手机端提供了时间时注明短信收到的时间
+    if payload.captured_at.is_some() && !capture.clamped {
+        notification.body.push(format!("收到于 {}", received_label(capture)));
+    }
+    notification.long_duration = true;
+    notification.expires_in = Duration::from_secs(60);
+    if has_code {
+        // 验证码很快失效，通知也提前从通知中心移除
+        notification.expires_in = ctx.state.config.read().unwrap().sms.code_expiry;
+        notification.urgent = Some(UrgentKind::SmsCode);
+    }
+    // 静默通知的按钮照常可用，在通知中心点击即可
+    notification.quiet = ctx.state.config.read().unwrap().sms.filter.popup.is_quiet(has_code);
+    // 复制验证码放在第一个按钮
+    if !copied && has_code {
+        notification.actions.push(NotificationAction::new("copy_code", "复制验证码"));
+    }
+
+    // 彩信图片使用与图片通知相同的保存与复制，其余图片按钮省略以免超出按钮上限
+    let image_actions = image.map(|(path, format)| {
+        notification.hero_image = Some(path.clone());
+        let content = ContentInfo {
+            mime: Some(format.mime()),
+            file_name: None,
+            size: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+        };
+        let actions = ImageActions::new(ctx, "sms", &content, format, Arc::new(Mutex::new(path)), None).retain(&["save", "copy"]);
+        actions.add_to(&mut notification);
+        actions
+    });
+    if !copied && has_text {
+        notification.actions.push(NotificationAction::new("copy_content", "复制原文"));
+    }
+    // 回复写入待发送队列，由手机端取走后发出
+    if !copied {
+        notification.inputs.push(NotificationInput::new(REPLY_INPUT, "输入回复内容"));
+        notification.actions.push(NotificationAction::new("reply", "回复").with_input(REPLY_INPUT));
+    }
+    notification.actions.push(NotificationAction::new("ignore", "忽略"));
+
+    let clipboard = ctx.clipboard();
+    let history = ctx.state.sms_history.clone();
+    let sessions = ctx.state.sessions.clone();
+    let sender = payload.sender.clone();
     let content = payload.content.clone();
     let code = payload.code.clone();
-    
-    notification.Activated(&windows::Foundation::TypedEventHandler::new(move |_sender, args: &Option<IInspectable>| {
-        if let Some(args) = args {
-            let args: windows::UI::Notifications::ToastActivatedEventArgs = args.cast()?;
-            let arguments = args.Arguments()?.to_string();
-            
-            if arguments == "copy_content" {
-                tracing::info!("Copy SMS content clicked");
-                crate::handlers::photo::copy_text_to_clipboard(&content);
-            } else if arguments == "copy_code" {
-                tracing::info!("Copy verification code clicked");
-                crate::handlers::photo::copy_text_to_clipboard(&code);
-            } else if arguments == "ignore" {
-                tracing::info!("Ignore SMS action clicked");
+
+    let on_action: ActionHandler = Arc::new(move |arguments: &str| {
+        let (arguments, input) = split_input(arguments);
+        if image_actions.as_ref().is_some_and(|actions| actions.handle_action(arguments, &audit)) {
+            return;
+        }
+        if arguments == "reply" {
+            let body = input.unwrap_or_default().trim();
+            if body.is_empty() {
+                tracing::info!("Empty SMS reply ignored");
+                return;
+            }
+            let now = chrono::Utc::now().timestamp_millis();
+            let result = history.enqueue_reply(&sender, body, now);
+            match &result {
+                Ok(id) => {
+                    tracing::info!("Queued reply {} to {} for the phone to send", id, sender);
+                    // 保持 WebSocket 连接的手机立即收到，其余仍通过 `GET /sms/outbox` 取走
+                    sessions.broadcast(ServerMessage::SmsReply(SmsReply {
+                        id: *id,
+                        to: sender.clone(),
+                        body: body.to_string(),
+                        created_at: now,
+                    }));
+                }
+                Err(e) => tracing::error!("Failed to queue SMS reply: {:#}", e),
             }
+            audit.record_result("reply", None, &result);
+        } else if arguments == "copy_content" {
+            tracing::info!("Copy SMS content clicked");
+            let result = crate::handlers::photo::copy_text_to_clipboard(clipboard.as_ref(), &content);
+            audit.record_result("copy", None, &result);
+        } else if arguments == "copy_code" {
+            tracing::info!("Copy verification code clicked");
+            let result = crate::handlers::photo::copy_text_to_clipboard(clipboard.as_ref(), &code);
+            audit.record_result("copy_code", None, &result);
+        } else if arguments == "ignore" {
+            tracing::info!("Ignore SMS action clicked");
+            audit.record("ignore", None, None);
         }
-        Ok(())
-    }))?;
-    
-    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?;
-    notifier.Show(&notification)?;
-    
-    // 使用全局存储管理生命周期
-    store_notification("sms", notification);
-    
-    Ok(())
+    });
+
+    (notification, on_action)
+}
+
+/// 单次最多返回的短信条数。
+const MAX_HISTORY_LIMIT: usize = 500;
+
+/// `GET /sms/history` 查询参数。
+#[derive(Debug, Deserialize)]
+pub struct SmsHistoryQuery {
+    /// 返回条数，默认 50
+    pub limit: Option<usize>,
+    /// 跳过的条数，用于分页
+    pub offset: Option<usize>,
+    /// 只返回该发送方的短信
+    pub sender: Option<String>,
+}
+
+/// 按时间倒序返回收到的短信历史，仅允许本机调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `connect_info` - 对端地址
+/// * `query` - 查询参数
+pub async fn history(
+    State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Query(query): Query<SmsHistoryQuery>,
+) -> Response {
+    if !is_local_admin(connect_info.as_ref()) {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "forbidden" }))).into_response();
+    }
+
+    let filter = SmsHistoryFilter {
+        limit: query.limit.unwrap_or(50).min(MAX_HISTORY_LIMIT),
+        offset: query.offset.unwrap_or(0),
+        sender: query.sender.filter(|sender| !sender.is_empty()),
+    };
+    let history = state.sms_history.clone();
+    let records = tokio::task::spawn_blocking(move || history.query(&filter))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|records| records);
+    match records {
+        Ok(records) => Json(records).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to query SMS history: {:#}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "history_unavailable" }))).into_response()
+        }
+    }
+}
+
+/// 返回等待手机端发送的短信回复，最早的在前。手机端发送后调用 `POST /sms/outbox/{id}/ack` 确认。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+pub async fn outbox(State(state): State<AppState>) -> Response {
+    let history = state.sms_history.clone();
+    let replies = tokio::task::spawn_blocking(move || history.pending_replies(chrono::Utc::now().timestamp_millis()))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|replies| replies);
+    match replies {
+        Ok(replies) => UploadResponse::success(0, None).with_detail("replies", replies).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to read SMS outbox: {:#}", e);
+            UploadResponse::failure(UploadError::IoError).into_response()
+        }
+    }
+}
+
+/// 确认手机端已发送一条回复，之后不再返回该回复。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `id` - 回复标识
+pub async fn ack_reply(State(state): State<AppState>, Path(id): Path<i64>) -> Response {
+    let history = state.sms_history.clone();
+    let acked = tokio::task::spawn_blocking(move || history.ack_reply(id, chrono::Utc::now().timestamp_millis()))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|acked| acked);
+    match acked {
+        Ok(true) => {
+            tracing::info!("Phone confirmed sending reply {}", id);
+            UploadResponse::success(0, None).with_detail("id", id).into_response()
+        }
+        // 不存在或已超过 24 小时被删除
+        Ok(false) => UploadResponse::rejected(StatusCode::NOT_FOUND, "not_found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to acknowledge SMS reply {}: {:#}", id, e);
+            UploadResponse::failure(UploadError::IoError).into_response()
+        }
+    }
 }
@@ -0,0 +1,95 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Instant;
+use crate::auth::AuthenticatedDevice;
+use crate::state::AppState;
+use crate::thumbnails::make_thumbnail;
+
+/// 返回已收到图片的 JPEG 缩略图，供手机确认接收端收到的内容，仅允许已配对的设备调用。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `device` - 通过令牌校验的设备
+/// * `id` - 上传图片时返回的标识
+/// * `headers` - 请求头，携带 `If-None-Match` 时按内容哈希判断是否未变
+pub async fn thumbnail(
+    State(state): State<AppState>,
+    device: Option<Extension<AuthenticatedDevice>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(Extension(AuthenticatedDevice(device_id))) = device else {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "missing token" }))).into_response();
+    };
+
+    let Some(photo) = state.photos.lookup(&id, Some(&device_id)) else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "not_found" }))).into_response();
+    };
+
+    let etag = format!("\"{}\"", photo.hash);
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    let thumbnail = match state.photos.cached(&id) {
+        Some(thumbnail) => thumbnail,
+        None => {
+            if let Err(retry_after) = state.photos.acquire_generation(Instant::now()) {
+                tracing::warn!("Thumbnail generation rate limit reached");
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after.as_secs().max(1).to_string())],
+                    Json(json!({ "error": "rate_limited", "retry_after_ms": retry_after.as_millis() as u64 })),
+                )
+                    .into_response();
+            }
+
+            let path = photo.path.clone();
+            let result = tokio::task::spawn_blocking(move || make_thumbnail(&std::fs::read(&path)?)).await;
+            match result {
+                Ok(Ok(thumbnail)) => {
+                    let thumbnail = Arc::new(thumbnail);
+                    state.photos.store(&id, thumbnail.clone());
+                    thumbnail
+                }
+                // 通知过期后临时图片已被删除
+                Ok(Err(e)) if e.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound) => {
+                    tracing::debug!("Thumbnail source {:?} no longer exists", photo.path);
+                    return (StatusCode::GONE, Json(json!({ "error": "gone" }))).into_response();
+                }
+                Ok(Err(e)) => {
+                    tracing::error!("Failed to generate thumbnail for {:?}: {:?}", photo.path, e);
+                    return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": "undecodable" }))).into_response();
+                }
+                Err(e) => {
+                    tracing::error!("Thumbnail task failed: {:?}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            }
+        }
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/jpeg".to_string()),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+        ],
+        thumbnail.as_ref().clone(),
+    )
+        .into_response()
+}
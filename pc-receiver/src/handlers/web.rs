@@ -0,0 +1,79 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 浏览器上传页面。
+ * 页面与脚本在编译时嵌入，访客凭托盘开放的短期令牌上传图片或发送文字；
+ * 没有访客链接时页面按 `/info` 判断是否需要令牌，需要时提示输入访问令牌。
+ * 页面与接口同源，不需要配置跨域。
+ */
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use crate::pairing::verification_emoji;
+use crate::state::AppState;
+
+const INDEX_HTML: &str = include_str!("../../web/index.html");
+const APP_JS: &str = include_str!("../../web/app.js");
+const APP_CSS: &str = include_str!("../../web/app.css");
+
+/// 页面只允许加载同源的脚本与样式，禁止被嵌入其他页面。
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; script-src 'self'; style-src 'self'; \
+    connect-src 'self'; img-src 'self' data:; form-action 'none'; base-uri 'none'; frame-ancestors 'none'";
+
+/// 返回上传页面。
+pub async fn index() -> Response {
+    asset(INDEX_HTML, "text/html; charset=utf-8")
+}
+
+/// 返回页面脚本。
+pub async fn app_js() -> Response {
+    asset(APP_JS, "text/javascript; charset=utf-8")
+}
+
+/// 返回页面样式。
+pub async fn app_css() -> Response {
+    asset(APP_CSS, "text/css; charset=utf-8")
+}
+
+/// 校验访客令牌，返回校验表情与剩余有效期（秒）。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `headers` - 请求头，需携带 `Authorization: Bearer <访客令牌>`
+pub async fn guest(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .unwrap_or_default();
+
+    match state.pairing.guest_remaining(token) {
+        Some(remaining) => Json(json!({
+            "emoji": verification_emoji(token),
+            "expires_in": remaining.as_secs(),
+        }))
+        .into_response(),
+        None => (StatusCode::UNAUTHORIZED, Json(json!({ "error": "invalid guest token" }))).into_response(),
+    }
+}
+
+/// 构造带安全响应头的静态资源响应。
+fn asset(body: &'static str, content_type: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (header::CONTENT_SECURITY_POLICY, HeaderValue::from_static(CONTENT_SECURITY_POLICY)),
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (header::REFERRER_POLICY, HeaderValue::from_static("no-referrer")),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-cache")),
+        ],
+        body,
+    )
+        .into_response()
+}
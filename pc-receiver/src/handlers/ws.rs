@@ -0,0 +1,441 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * WebSocket 接口。
+ * 手机经 `GET /ws` 建立连接后，原本 POST 的短信、剪贴板与图片可以作为带类型的 JSON 消息发送。
+ * 每条消息转换为对应 REST 接口的请求，经同一套路由处理，令牌校验、频率限制、载荷校验与处理器
+ * 都与 REST 接口相同，结果以 `response` 消息返回。图片先发送一条 `photo` 消息说明大小与类型，
+ * 随后以二进制帧发送内容，边收边写入临时文件。
+ */
+use axum::{
+    body::{Body, Bytes},
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Extension, Request, State,
+    },
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use futures::channel::mpsc as body_channel;
+use futures::SinkExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+use tower::ServiceExt;
+use crate::auth::AuthenticatedDevice;
+use crate::pairing::GUEST_DEVICE_ID;
+use crate::realtime::{Outgoing, ServerMessage, CLOSE_REVOKED};
+use crate::state::AppState;
+
+/// 服务端发送 Ping 的间隔。
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// 超过该时长未收到任何帧（包括 Pong）时认为连接已断开。
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 每条连接同时处理的消息数，达到后暂停读取新消息。
+const MAX_IN_FLIGHT: usize = 8;
+
+/// 图片内容等待写入时缓冲的二进制帧数。
+const BODY_BUFFER: usize = 4;
+
+/// 转换为 `response` 消息的响应体上限。
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// 处理 WebSocket 消息所用的路由，即 REST 接口本身。
+#[derive(Clone)]
+pub(crate) struct Loopback(pub Router);
+
+/// 手机发来的消息，`type` 字段区分种类，`id` 原样出现在对应的 `response` 消息中。
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// `payload` 与 `POST /sms` 的请求体相同
+    Sms {
+        #[serde(default)]
+        id: Option<Value>,
+        payload: Value,
+    },
+    /// `payload` 与 `POST /sms/batch` 的请求体相同
+    SmsBatch {
+        #[serde(default)]
+        id: Option<Value>,
+        payload: Value,
+    },
+    /// `payload` 与 `POST /clipboard` 的请求体相同
+    Clipboard {
+        #[serde(default)]
+        id: Option<Value>,
+        payload: Value,
+    },
+    /// 与 `PUT /upload/raw` 相同，内容随后以二进制帧发送，合计 `size` 字节
+    Photo {
+        #[serde(default)]
+        id: Option<Value>,
+        size: u64,
+        mime: String,
+        #[serde(default)]
+        file_name: Option<String>,
+        #[serde(default)]
+        sha256: Option<String>,
+    },
+    /// 手机已发送一条短信回复，与 `POST /sms/outbox/{reply}/ack` 相同
+    ReplySent {
+        #[serde(default)]
+        id: Option<Value>,
+        reply: i64,
+    },
+}
+
+/// 建立连接的请求中与身份相关的部分，由消息转换出的请求都携带这些信息。
+#[derive(Clone)]
+struct Client {
+    device: String,
+    authorization: Option<HeaderValue>,
+    remote_addr: Option<SocketAddr>,
+}
+
+impl Client {
+    /// 以连接的身份构造一个 REST 请求。
+    ///
+    /// # Arguments
+    /// * `method` - 请求方法
+    /// * `path` - 请求路径
+    /// * `length` - 请求体字节数
+    /// * `body` - 请求体
+    fn request(&self, method: Method, path: &str, length: u64, body: Body) -> Request {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::CONTENT_LENGTH, length)
+            .body(body)
+            .expect("internal request paths are valid");
+        if let Some(authorization) = &self.authorization {
+            request.headers_mut().insert(header::AUTHORIZATION, authorization.clone());
+        }
+        if let Some(addr) = self.remote_addr {
+            request.extensions_mut().insert(ConnectInfo(addr));
+        }
+        request.extensions_mut().insert(AuthenticatedDevice(self.device.clone()));
+        request
+    }
+
+    /// 以连接的身份构造一个 JSON 请求。
+    fn json_request(&self, path: &str, payload: &Value) -> Request {
+        let body = serde_json::to_vec(payload).unwrap_or_default();
+        let mut request = self.request(Method::POST, path, body.len() as u64, Body::from(body));
+        request
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        request
+    }
+}
+
+/// 正在接收内容的图片。
+struct PendingUpload {
+    id: Option<Value>,
+    remaining: u64,
+    body: body_channel::Sender<Result<Bytes, std::io::Error>>,
+}
+
+impl PendingUpload {
+    /// 中止接收，处理器读取请求体失败，已写入的临时文件随之删除。
+    async fn abort(mut self, reason: &str) {
+        tracing::warn!("WebSocket upload aborted with {} bytes missing: {}", self.remaining, reason);
+        let error = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, reason.to_string());
+        let _ = self.body.send(Err(error)).await;
+    }
+}
+
+/// 升级为 WebSocket 连接，仅接受已配对设备的设备令牌。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `loopback` - 处理消息所用的路由
+/// * `device` - 通过令牌校验的设备
+/// * `connect_info` - 对端地址
+/// * `headers` - 请求头，其中的令牌用于之后的每条消息
+/// * `ws` - 升级请求
+pub async fn upgrade(
+    State(state): State<AppState>,
+    Extension(Loopback(router)): Extension<Loopback>,
+    device: Option<Extension<AuthenticatedDevice>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let device = device
+        .map(|Extension(AuthenticatedDevice(device_id))| device_id)
+        .filter(|device_id| device_id != GUEST_DEVICE_ID);
+    let Some(device) = device else {
+        return (StatusCode::FORBIDDEN, Json(json!({ "error": "device token required" }))).into_response();
+    };
+
+    let client = Client {
+        device,
+        authorization: headers.get(header::AUTHORIZATION).cloned(),
+        remote_addr: connect_info.map(|ConnectInfo(addr)| addr),
+    };
+    ws.max_message_size(state.body_limits.json)
+        .on_upgrade(move |socket| run_session(state, router, client, socket))
+}
+
+/// 处理一条连接直到断开、被新连接取代或服务关闭。
+async fn run_session(state: AppState, router: Router, client: Client, mut socket: WebSocket) {
+    let Some((session, sender, mut outgoing)) = state.register_session(&client.device) else {
+        tracing::info!("Device {} was revoked before its WebSocket session started", client.device);
+        let _ = socket.send(Message::Close(Some(close_frame(CLOSE_REVOKED)))).await;
+        return;
+    };
+    tracing::info!("Device {} connected over WebSocket, session {}", client.device, session);
+
+    let mut clipboard = state.clipboard_watch.subscribe();
+    let hello = ServerMessage::Hello {
+        session,
+        device: client.device.clone(),
+        protocol: crate::PROTOCOL_VERSION,
+        clipboard_seq: clipboard.borrow_and_update().seq,
+    };
+    if send_message(&mut socket, &hello).await.is_ok() {
+        queue_pending_replies(&state, sender.clone());
+    }
+
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let mut upload: Option<PendingUpload> = None;
+    let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let close = loop {
+        tokio::select! {
+            frame = socket.recv() => {
+                let Some(Ok(frame)) = frame else {
+                    break None;
+                };
+                last_seen = Instant::now();
+                let reply = match frame {
+                    Message::Text(text) => {
+                        let permit = in_flight.clone().acquire_owned().await.expect("semaphore is never closed");
+                        handle_text(&router, &client, &sender, permit, &mut upload, &text).await
+                    }
+                    Message::Binary(data) => handle_binary(&mut upload, data).await,
+                    Message::Close(_) => break None,
+                    // Ping 由底层自动回复
+                    Message::Ping(_) | Message::Pong(_) => None,
+                };
+                if let Some(reply) = reply {
+                    if send_message(&mut socket, &reply).await.is_err() {
+                        break None;
+                    }
+                }
+            }
+            message = outgoing.recv() => match message {
+                Some(Outgoing::Message(message)) => {
+                    if send_message(&mut socket, &message).await.is_err() {
+                        break None;
+                    }
+                }
+                Some(Outgoing::Close(code, reason)) => break Some((code, reason)),
+                None => break None,
+            },
+            Ok(()) = clipboard.changed() => {
+                let snapshot = clipboard.borrow_and_update().clone();
+                if send_message(&mut socket, &ServerMessage::Clipboard(snapshot)).await.is_err() {
+                    break None;
+                }
+            }
+            _ = ping.tick() => {
+                if last_seen.elapsed() >= IDLE_TIMEOUT {
+                    tracing::info!("WebSocket session {} of device {} timed out", session, client.device);
+                    break None;
+                }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break None;
+                }
+            }
+        }
+    };
+
+    if let Some(pending) = upload.take() {
+        pending.abort("connection closed").await;
+    }
+    if let Some(close) = close {
+        let _ = socket.send(Message::Close(Some(close_frame(close)))).await;
+    }
+    state.sessions.unregister(&client.device, session);
+    tracing::info!("WebSocket session {} of device {} closed", session, client.device);
+}
+
+fn close_frame((code, reason): (u16, &'static str)) -> CloseFrame<'static> {
+    CloseFrame {
+        code,
+        reason: reason.into(),
+    }
+}
+
+/// 处理一条 JSON 消息。
+///
+/// # Arguments
+/// * `permit` - 同时处理的消息数额度，处理完成后释放
+/// * `upload` - 正在接收内容的图片
+/// * `text` - 消息内容
+///
+/// # Returns
+/// 需要立即回复的错误消息
+async fn handle_text(
+    router: &Router,
+    client: &Client,
+    sender: &mpsc::Sender<Outgoing>,
+    permit: tokio::sync::OwnedSemaphorePermit,
+    upload: &mut Option<PendingUpload>,
+    text: &str,
+) -> Option<ServerMessage> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::warn!("Invalid WebSocket message from {}: {}", client.device, e);
+            return Some(ServerMessage::Error {
+                id: None,
+                error: "decode_failed",
+                message: e.to_string(),
+            });
+        }
+    };
+
+    let (id, request) = match message {
+        ClientMessage::Sms { id, payload } => (id, client.json_request("/sms", &payload)),
+        ClientMessage::SmsBatch { id, payload } => (id, client.json_request("/sms/batch", &payload)),
+        ClientMessage::Clipboard { id, payload } => (id, client.json_request("/clipboard", &payload)),
+        ClientMessage::ReplySent { id, reply } => {
+            let path = format!("/sms/outbox/{}/ack", reply);
+            (id, client.request(Method::POST, &path, 0, Body::empty()))
+        }
+        ClientMessage::Photo {
+            id,
+            size,
+            mime,
+            file_name,
+            sha256,
+        } => {
+            if let Some(previous) = upload.take() {
+                previous.abort("superseded by a new photo").await;
+            }
+            let Ok(content_type) = HeaderValue::from_str(&mime) else {
+                return Some(ServerMessage::Error {
+                    id,
+                    error: "decode_failed",
+                    message: format!("invalid mime {:?}", mime),
+                });
+            };
+            let (body, receiver) = body_channel::channel(BODY_BUFFER);
+            let mut request = client.request(Method::PUT, "/upload/raw", size, Body::from_stream(receiver));
+            let headers = request.headers_mut();
+            headers.insert(header::CONTENT_TYPE, content_type);
+            if let Some(value) = file_name.and_then(|name| HeaderValue::from_bytes(name.as_bytes()).ok()) {
+                headers.insert("x-file-name", value);
+            }
+            if let Some(value) = sha256.and_then(|hash| HeaderValue::from_str(&hash).ok()) {
+                headers.insert("x-content-sha256", value);
+            }
+            // 内容为空时丢弃发送端即结束请求体
+            if size > 0 {
+                *upload = Some(PendingUpload {
+                    id: id.clone(),
+                    remaining: size,
+                    body,
+                });
+            }
+            (id, request)
+        }
+    };
+
+    let router = router.clone();
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        let response = router.oneshot(request).await;
+        // 先释放额度再排队发送，避免发送队列已满时与读取循环互相等待
+        drop(permit);
+        let message = match response {
+            Ok(response) => response_message(id, response).await,
+            Err(never) => match never {},
+        };
+        let _ = sender.send(Outgoing::Message(message)).await;
+    });
+    None
+}
+
+/// 将二进制帧写入正在接收的图片。
+///
+/// # Returns
+/// 需要立即回复的错误消息
+async fn handle_binary(upload: &mut Option<PendingUpload>, data: Vec<u8>) -> Option<ServerMessage> {
+    let Some(pending) = upload.as_mut() else {
+        return Some(ServerMessage::Error {
+            id: None,
+            error: "unexpected_binary",
+            message: "binary frames must follow a photo message".to_string(),
+        });
+    };
+    let length = data.len() as u64;
+    if length > pending.remaining {
+        let overflow = length - pending.remaining;
+        let pending = upload.take()?;
+        let id = pending.id.clone();
+        pending.abort("more data than the declared size").await;
+        return Some(ServerMessage::Error {
+            id,
+            error: "size_mismatch",
+            message: format!("received {} more bytes than declared", overflow),
+        });
+    }
+
+    pending.remaining -= length;
+    // 处理器已提前返回（例如类型不受支持）时丢弃其余内容
+    let _ = pending.body.send(Ok(Bytes::from(data))).await;
+    if pending.remaining == 0 {
+        *upload = None;
+    }
+    None
+}
+
+/// 将 REST 响应转换为 `response` 消息。
+async fn response_message(id: Option<Value>, response: Response) -> ServerMessage {
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), MAX_RESPONSE_BYTES)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or(Value::Null);
+    ServerMessage::Response { id, status, body }
+}
+
+/// 连接建立时推送尚未发送的短信回复。
+fn queue_pending_replies(state: &AppState, sender: mpsc::Sender<Outgoing>) {
+    let history = state.sms_history.clone();
+    tokio::spawn(async move {
+        let replies = tokio::task::spawn_blocking(move || history.pending_replies(chrono::Utc::now().timestamp_millis())).await;
+        match replies {
+            Ok(Ok(replies)) => {
+                for reply in replies {
+                    if sender.send(Outgoing::Message(ServerMessage::SmsReply(reply))).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(Err(e)) => tracing::error!("Failed to read SMS outbox: {:#}", e),
+            Err(e) => tracing::error!("SMS outbox task failed: {:?}", e),
+        }
+    });
+}
+
+/// 以文本帧发送消息。
+async fn send_message(socket: &mut WebSocket, message: &ServerMessage) -> Result<(), axum::Error> {
+    let Ok(text) = serde_json::to_string(message) else {
+        return Ok(());
+    };
+    socket.send(Message::Text(text)).await
+}
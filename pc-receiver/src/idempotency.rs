@@ -0,0 +1,438 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 幂等键模块。
+ * 手机端没有收到响应时会重试，按内容哈希去重并不总能认出重试（例如短信与剪贴板的重复发送）。
+ * 上传、短信与剪贴板请求可以携带 `Idempotency-Key` 请求头（也可以是查询参数或 JSON 中的 `request_id`），
+ * 同一设备在有效期内以相同的键重试时直接返回第一次的响应，不再落盘与弹出通知。
+ * 第一次请求仍在处理时重试返回 409；记录数量有上限，超过时淘汰最久未使用的。
+ * 同时记录第一次请求体的 SHA-256，以相同的键发送不同内容时返回 422，不会把别的请求的结果当作这次的。
+ */
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use crate::body_limit;
+use crate::handlers::pair::retry_later;
+use crate::handlers::response::UploadError;
+use crate::state::AppState;
+
+/// 默认的记录有效期，超过后相同的键视为新的请求。
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 最多记录的键数。
+pub const IDEMPOTENCY_CAPACITY: usize = 1024;
+
+/// 键的最大长度。
+const MAX_KEY_LEN: usize = 255;
+
+/// 请求头名称。
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// 重放的响应带有该响应头，值为 `true`。
+const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// 第一次请求仍在处理时建议的重试间隔。
+const IN_PROGRESS_RETRY: Duration = Duration::from_secs(1);
+
+/// 支持幂等键的请求：上传、短信与剪贴板推送。
+///
+/// # Arguments
+/// * `method` - 请求方法
+/// * `path` - 请求路径
+pub fn applies(method: &Method, path: &str) -> bool {
+    method != Method::GET && matches!(path, "/upload" | "/upload/raw" | "/upload/file" | "/sms" | "/sms/batch" | "/clipboard")
+}
+
+/// 记录的键：来源、方法、路径与客户端提供的键，不同设备或接口使用相同的键互不影响。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    client: String,
+    method: Method,
+    path: String,
+    key: String,
+}
+
+/// 保存的响应。
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    /// 第一次请求的请求体
+    request: BodyDigest,
+}
+
+/// 处理器读取过的请求体：长度与 SHA-256。处理器提前拒绝、没有读完请求体时只比较读过的部分。
+#[derive(Debug, Clone)]
+struct BodyDigest {
+    len: u64,
+    sha256: [u8; 32],
+    /// 是否读到了请求体末尾
+    complete: bool,
+}
+
+impl BodyDigest {
+    /// 读取重试的请求体，判断与第一次请求的内容是否相同。
+    async fn matches(&self, body: Body) -> bool {
+        let mut hasher = Sha256::new();
+        let mut len = 0u64;
+        let mut stream = body.into_data_stream();
+        while let Some(chunk) = stream.next().await {
+            let Ok(chunk) = chunk else {
+                return false;
+            };
+            let take = self.len.saturating_sub(len).min(chunk.len() as u64) as usize;
+            hasher.update(&chunk[..take]);
+            len += chunk.len() as u64;
+            if len > self.len && self.complete {
+                return false;
+            }
+            if len >= self.len && !self.complete {
+                break;
+            }
+        }
+        len >= self.len && <[u8; 32]>::from(hasher.finalize()) == self.sha256
+    }
+}
+
+/// 一边交给处理器一边计算摘要的请求体。
+#[derive(Default)]
+struct BodyRecorder {
+    hasher: Sha256,
+    len: u64,
+    complete: bool,
+    /// 读取请求体时连接中断
+    interrupted: bool,
+}
+
+impl BodyRecorder {
+    /// 包装请求体，处理器读取的每一块都计入摘要。
+    fn record(recorder: &Arc<Mutex<BodyRecorder>>, body: Body) -> Body {
+        let chunks = recorder.clone();
+        let end = recorder.clone();
+        let stream = body
+            .into_data_stream()
+            .inspect(move |chunk| {
+                let mut recorder = chunks.lock().unwrap();
+                match chunk {
+                    Ok(bytes) => {
+                        recorder.hasher.update(bytes);
+                        recorder.len += bytes.len() as u64;
+                    }
+                    Err(_) => recorder.interrupted = true,
+                }
+            })
+            .chain(futures::stream::poll_fn(move |_| {
+                end.lock().unwrap().complete = true;
+                Poll::Ready(None)
+            }));
+        Body::from_stream(stream)
+    }
+
+    fn digest(&self) -> BodyDigest {
+        BodyDigest {
+            len: self.len,
+            sha256: self.hasher.clone().finalize().into(),
+            complete: self.complete,
+        }
+    }
+}
+
+impl StoredResponse {
+    fn replay(&self) -> Response {
+        let mut response = Response::new(Body::from(self.body.clone()));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REPLAYED_HEADER), HeaderValue::from_static("true"));
+        response
+    }
+}
+
+#[derive(Debug)]
+enum Entry {
+    /// 第一次请求仍在处理
+    InFlight,
+    Done(StoredResponse),
+}
+
+#[derive(Debug)]
+struct Slot {
+    entry: Entry,
+    created: Instant,
+    /// 最近一次命中的时间，用于淘汰
+    used: Instant,
+}
+
+/// 查询的结果。
+enum Lookup {
+    /// 第一次出现，已登记为处理中
+    New,
+    Replay(StoredResponse),
+    InFlight,
+}
+
+/// 最近使用过的幂等键与对应的响应，由所有接口共享。
+#[derive(Debug, Default)]
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<Key, Slot>>,
+}
+
+impl IdempotencyStore {
+    /// 查询键，第一次出现时登记为处理中。
+    ///
+    /// # Arguments
+    /// * `key` - 记录的键
+    /// * `ttl` - 记录的有效期
+    /// * `now` - 当前时间
+    fn begin(&self, key: &Key, ttl: Duration, now: Instant) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(slot) = entries.get_mut(key).filter(|slot| now.duration_since(slot.created) < ttl) {
+            slot.used = now;
+            return match &slot.entry {
+                Entry::InFlight => Lookup::InFlight,
+                Entry::Done(response) => Lookup::Replay(response.clone()),
+            };
+        }
+
+        if !entries.contains_key(key) && entries.len() >= IDEMPOTENCY_CAPACITY {
+            entries.retain(|_, slot| now.duration_since(slot.created) < ttl);
+            if entries.len() >= IDEMPOTENCY_CAPACITY {
+                let oldest = entries
+                    .iter()
+                    .filter(|(_, slot)| matches!(slot.entry, Entry::Done(_)))
+                    .min_by_key(|(_, slot)| slot.used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(
+            key.clone(),
+            Slot {
+                entry: Entry::InFlight,
+                created: now,
+                used: now,
+            },
+        );
+        Lookup::New
+    }
+
+    /// 保存处理结果，传入 None 时删除登记，之后的重试重新处理。
+    fn finish(&self, key: &Key, response: Option<StoredResponse>) {
+        let mut entries = self.entries.lock().unwrap();
+        match response {
+            Some(response) => {
+                if let Some(slot) = entries.get_mut(key) {
+                    slot.entry = Entry::Done(response);
+                }
+            }
+            None => {
+                entries.remove(key);
+            }
+        }
+    }
+}
+
+/// 处理中的登记。请求未完成就被取消（例如连接断开）时删除登记，重试不会一直收到 409。
+struct Pending {
+    store: Arc<IdempotencyStore>,
+    key: Option<Key>,
+}
+
+impl Pending {
+    fn finish(mut self, response: Option<StoredResponse>) {
+        if let Some(key) = self.key.take() {
+            self.store.finish(&key, response);
+        }
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.store.finish(&key, None);
+        }
+    }
+}
+
+/// 可以重放的响应：成功与确定性的客户端错误。服务端错误、限流与超时等暂时性错误重试时重新处理。
+fn replayable(status: StatusCode) -> bool {
+    status.is_success()
+        || (status.is_client_error()
+            && !matches!(
+                status,
+                StatusCode::REQUEST_TIMEOUT | StatusCode::CONFLICT | StatusCode::TOO_MANY_REQUESTS
+            ))
+}
+
+/// 从请求头或查询参数中读取幂等键。
+///
+/// # Returns
+/// 未提供时为 Ok(None)，格式不正确时为 Err
+fn key_from_parts(request: &Request) -> Result<Option<String>, ()> {
+    if let Some(value) = request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        return value.to_str().map(|key| Some(key.to_string())).map_err(|_| ());
+    }
+    let query = request.uri().query().unwrap_or_default();
+    Ok(form_urlencoded::parse(query.as_bytes())
+        .find(|(name, _)| name == "request_id")
+        .map(|(_, key)| key.into_owned()))
+}
+
+/// JSON 请求体中的 `request_id` 字段，可以是字符串或数字。
+fn key_from_json(body: &[u8]) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    match value.get("request_id")? {
+        serde_json::Value::String(key) => Some(key.clone()),
+        serde_json::Value::Number(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+fn valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// 请求的内容类型是否为 JSON。
+fn is_json(request: &Request) -> bool {
+    request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"))
+}
+
+/// 相同的幂等键在有效期内重试时返回第一次的响应，不再交给处理器。
+/// 位于令牌校验之后、频率限制之前，重放的请求不消耗额度。
+pub(crate) async fn idempotency_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let ttl = state.config.read().unwrap().server.idempotency_ttl;
+    if ttl.is_zero() || !applies(request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let Ok(key) = key_from_parts(&request) else {
+        return invalid_key();
+    };
+    let (key, request) = match key {
+        Some(key) => (Some(key), request),
+        // JSON 请求体中的 request_id：请求头中给出的长度超过上限时不读取，交给大小限制处理；
+        // 压缩的请求体解压后长度未知，读取时同样受上限约束
+        None if is_json(&request) && declared_length(&request).is_none_or(|length| length <= state.body_limits.json) => {
+            let (parts, body) = request.into_parts();
+            let bytes = match axum::body::to_bytes(body, state.body_limits.json).await {
+                Ok(bytes) => bytes,
+                Err(e) if body_limit::exceeds_limit(&e) => {
+                    return body_limit::too_large(state.body_limits.json, None).into_response();
+                }
+                Err(_) => return UploadError::DecodeFailed.into_response(),
+            };
+            (key_from_json(&bytes), Request::from_parts(parts, Body::from(bytes)))
+        }
+        None => (None, request),
+    };
+    let Some(key) = key else {
+        return next.run(request).await;
+    };
+    if !valid_key(&key) {
+        return invalid_key();
+    }
+
+    let key = Key {
+        client: crate::rate_limit::client_id(&request),
+        method: request.method().clone(),
+        path: request.uri().path().to_string(),
+        key,
+    };
+    match state.idempotency.begin(&key, ttl, Instant::now()) {
+        Lookup::Replay(response) => {
+            if !response.request.matches(request.into_body()).await {
+                tracing::warn!("Idempotency key {:?} from {} was reused with a different body", key.key, key.client);
+                return key_reused();
+            }
+            tracing::info!("Replaying response for idempotency key {:?} from {}", key.key, key.client);
+            return response.replay();
+        }
+        Lookup::InFlight => {
+            tracing::info!("Idempotency key {:?} from {} is still being processed", key.key, key.client);
+            return retry_later(StatusCode::CONFLICT, "request_in_progress", IN_PROGRESS_RETRY);
+        }
+        Lookup::New => {}
+    }
+
+    let pending = Pending {
+        store: state.idempotency.clone(),
+        key: Some(key),
+    };
+    let recorder = Arc::new(Mutex::new(BodyRecorder::default()));
+    let request = request.map(|body| BodyRecorder::record(&recorder, body));
+    let response = next.run(request).await;
+    let (recorded, interrupted) = {
+        let recorder = recorder.lock().unwrap();
+        (recorder.digest(), recorder.interrupted)
+    };
+    // 连接中断时请求体读取失败，处理器返回的 400 不代表请求本身有误，不能重放给之后的重试
+    if !replayable(response.status()) || interrupted {
+        pending.finish(None);
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for idempotency: {:?}", e);
+            pending.finish(None);
+            return UploadError::Internal.into_response();
+        }
+    };
+    pending.finish(Some(StoredResponse {
+        status: parts.status,
+        headers: parts.headers.clone(),
+        body: body.clone(),
+        request: recorded,
+    }));
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn declared_length(request: &Request) -> Option<usize> {
+    request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+fn key_reused() -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        axum::Json(serde_json::json!({
+            "error": "idempotency_key_reused",
+            "message": "idempotency key was already used for a different request body",
+        })),
+    )
+        .into_response()
+}
+
+fn invalid_key() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        axum::Json(serde_json::json!({
+            "error": "invalid_idempotency_key",
+            "message": format!("idempotency key must be 1-{} printable ASCII characters", MAX_KEY_LEN),
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,207 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 图片格式识别模块。
+ * 根据文件头的魔数判断图片格式，不信任手机端声明的类型与文件名，
+ * 临时文件、自动保存文件与保存对话框都使用识别出的扩展名。
+ */
+use serde::Serialize;
+
+/// 识别格式所需的文件头长度，区分 WebM 与 MKV 需要读到 EBML 头中的 DocType。
+pub const SNIFF_LEN: usize = 64;
+
+/// 支持接收的图片格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    WebP,
+    Gif,
+    /// HEIC / HEIF
+    Heic,
+}
+
+impl ImageFormat {
+    /// 所有支持的格式。
+    pub const ALL: [ImageFormat; 5] = [
+        ImageFormat::Jpeg,
+        ImageFormat::Png,
+        ImageFormat::WebP,
+        ImageFormat::Gif,
+        ImageFormat::Heic,
+    ];
+
+    /// 文件扩展名（不含点）。
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::WebP => "webp",
+            ImageFormat::Gif => "gif",
+            ImageFormat::Heic => "heic",
+        }
+    }
+
+    /// MIME 类型。
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::WebP => "image/webp",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Heic => "image/heic",
+        }
+    }
+
+    /// 当前构建能否解码该格式，HEIC 需要启用 `heic` 特性。
+    pub fn is_decodable(&self) -> bool {
+        *self != ImageFormat::Heic || cfg!(feature = "heic")
+    }
+
+    /// 由扩展名反查格式，忽略大小写，`jpeg` 与 `jpg` 等价。
+    pub fn from_extension(extension: &str) -> Option<ImageFormat> {
+        let extension = extension.to_ascii_lowercase();
+        match extension.as_str() {
+            "jpeg" => Some(ImageFormat::Jpeg),
+            "heif" => Some(ImageFormat::Heic),
+            _ => Self::ALL.into_iter().find(|format| format.extension() == extension),
+        }
+    }
+
+    /// 由 MIME 类型反查格式，忽略大小写与参数，`image/jpg` 与 `image/heif` 分别视为 JPEG 与 HEIC。
+    pub fn from_mime(mime: &str) -> Option<ImageFormat> {
+        let mime = mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "image/jpg" => Some(ImageFormat::Jpeg),
+            "image/heif" => Some(ImageFormat::Heic),
+            _ => Self::ALL.into_iter().find(|format| format.mime() == mime),
+        }
+    }
+}
+
+/// 向 image-rs 注册 HEIC / HEIF 解码器，复制、通知预览与缩略图随之支持 HEIC。
+/// 重复调用没有副作用，未启用 `heic` 特性时不做任何事。
+pub fn register_decoders() {
+    #[cfg(feature = "heic")]
+    {
+        static REGISTER: std::sync::Once = std::sync::Once::new();
+        REGISTER.call_once(|| {
+            libheif_rs::integration::image::register_heif_decoding_hook();
+            libheif_rs::integration::image::register_heic_decoding_hook();
+        });
+    }
+}
+
+/// HEIF 容器中表示 HEIC 图片的主品牌。
+const HEIC_BRANDS: [&[u8; 4]; 8] = [b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+/// 根据文件头识别图片格式。
+///
+/// # Arguments
+/// * `header` - 文件开头的字节，至少需要 `SNIFF_LEN` 字节才能识别所有格式
+///
+/// # Returns
+/// 识别出的格式，无法识别时为 None
+pub fn detect_image_format(header: &[u8]) -> Option<ImageFormat> {
+    if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Some(ImageFormat::Jpeg);
+    }
+    if header.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Some(ImageFormat::Png);
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some(ImageFormat::Gif);
+    }
+    if header.len() >= 12 && header.starts_with(b"RIFF") && &header[8..12] == b"WEBP" {
+        return Some(ImageFormat::WebP);
+    }
+    if header.len() >= 12 && &header[4..8] == b"ftyp" && HEIC_BRANDS.iter().any(|brand| &header[8..12] == *brand) {
+        return Some(ImageFormat::Heic);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ISO BMFF 文件开头的 `ftyp` 盒，`brand` 为主品牌。
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut header = vec![0, 0, 0, 0x18];
+        header.extend_from_slice(b"ftyp");
+        header.extend_from_slice(brand);
+        header.extend_from_slice(&[0, 0, 0, 0]);
+        header.extend_from_slice(b"mif1heic");
+        header
+    }
+
+    #[test]
+    fn detects_formats_from_magic_bytes() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, b'I', b'H', b'D', b'R'];
+        let cases: Vec<(&str, Vec<u8>, Option<ImageFormat>)> = vec![
+            ("png", png.to_vec(), Some(ImageFormat::Png)),
+            ("jpeg jfif", vec![0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10, b'J', b'F', b'I', b'F'], Some(ImageFormat::Jpeg)),
+            ("jpeg exif", vec![0xFF, 0xD8, 0xFF, 0xE1, 0x12, 0x34, b'E', b'x', b'i', b'f'], Some(ImageFormat::Jpeg)),
+            ("gif87a", b"GIF87a\x01\x00\x01\x00".to_vec(), Some(ImageFormat::Gif)),
+            ("gif89a", b"GIF89a\x01\x00\x01\x00".to_vec(), Some(ImageFormat::Gif)),
+            ("webp", b"RIFF\x24\x00\x00\x00WEBPVP8 ".to_vec(), Some(ImageFormat::WebP)),
+            ("heic", ftyp(b"heic"), Some(ImageFormat::Heic)),
+            ("heif mif1", ftyp(b"mif1"), Some(ImageFormat::Heic)),
+            ("heic sequence", ftyp(b"hevc"), Some(ImageFormat::Heic)),
+            // 同一容器中的其他品牌与相近的文件头
+            ("mp4", ftyp(b"isom"), None),
+            ("avif", ftyp(b"avif"), None),
+            ("wav", b"RIFF\x24\x00\x00\x00WAVEfmt ".to_vec(), None),
+            ("gif88a", b"GIF88a\x01\x00".to_vec(), None),
+            ("png without crlf", vec![0x89, b'P', b'N', b'G', 0x0A, 0x0A, 0x1A, 0x0A], None),
+            ("text", b"hello, world".to_vec(), None),
+            ("pdf", b"%PDF-1.7\n".to_vec(), None),
+        ];
+        for (name, header, expected) in cases {
+            assert_eq!(detect_image_format(&header), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn truncated_headers_are_not_recognized() {
+        let cases: [(&str, &[u8]); 7] = [
+            ("empty", b""),
+            ("jpeg soi only", &[0xFF, 0xD8]),
+            ("png prefix", &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A]),
+            ("gif prefix", b"GIF89"),
+            ("webp without fourcc", b"RIFF\x24\x00\x00\x00WEB"),
+            ("ftyp without brand", b"\x00\x00\x00\x18ftyphei"),
+            ("riff only", b"RIFF"),
+        ];
+        for (name, header) in cases {
+            assert_eq!(detect_image_format(header), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn extensions_and_mime_types_round_trip() {
+        for format in ImageFormat::ALL {
+            assert_eq!(ImageFormat::from_extension(format.extension()), Some(format), "{:?}", format);
+            assert_eq!(ImageFormat::from_mime(format.mime()), Some(format), "{:?}", format);
+        }
+        let cases = [
+            ("JPEG", Some(ImageFormat::Jpeg)),
+            ("heif", Some(ImageFormat::Heic)),
+            ("tiff", None),
+        ];
+        for (extension, expected) in cases {
+            assert_eq!(ImageFormat::from_extension(extension), expected, "{}", extension);
+        }
+        let cases = [
+            ("image/jpg", Some(ImageFormat::Jpeg)),
+            ("IMAGE/PNG; charset=binary", Some(ImageFormat::Png)),
+            ("image/heif", Some(ImageFormat::Heic)),
+            ("application/octet-stream", None),
+        ];
+        for (mime, expected) in cases {
+            assert_eq!(ImageFormat::from_mime(mime), expected, "{}", mime);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * FastSync 接收端库。
+ * 提供接收手机推送的图片、短信与剪贴板内容的 HTTP 服务，通知与剪贴板通过可替换的后端实现。
+ *
+ * ```no_run
+ * # async fn run() -> anyhow::Result<()> {
+ * let server = fastsync::FastSyncServer::builder()
+ *     .port(3000)
+ *     .on_event(|event| println!("{:?}", event))
+ *     .build();
+ * server.start().await?;
+ * server.wait().await;
+ * # Ok(())
+ * # }
+ * ```
+ */
+#![warn(unnameable_types)]
+mod access_log;
+mod admission;
+mod attention;
+mod audit;
+mod auth;
+mod body_limit;
+mod burst;
+mod capabilities;
+mod clipboard;
+mod compression;
+mod config;
+mod cors;
+mod decode_pool;
+mod devices;
+mod events;
+mod handlers;
+mod idempotency;
+mod image_format;
+mod listen;
+#[cfg(feature = "mdns")]
+mod mdns;
+mod metadata;
+mod notifier;
+mod ocr;
+mod pairing;
+mod payload;
+mod policy;
+mod qr;
+mod rate_limit;
+mod realtime;
+mod resumable;
+mod schedule;
+mod selfcheck;
+mod server;
+mod sms_code;
+mod sms_filter;
+mod sms_history;
+mod state;
+mod storage;
+mod suppression;
+mod temp_files;
+mod thumbnails;
+mod timings;
+#[cfg(feature = "tls")]
+mod tls;
+mod validation;
+mod versioning;
+mod video;
+
+pub use server::{FastSyncServer, FastSyncServerBuilder, DEFAULT_PORT, DRAIN_TIMEOUT};
+pub use payload::{DeviceContext, NotificationRequest, PayloadContext, PayloadHandler, PayloadOutcome};
+pub use notifier::{
+    default_notifier, join_input, ActionHandler, MockNotifier, Notification, NotificationAction, NotificationInput, Notifier,
+};
+pub use clipboard::{default_clipboard, ClipboardBackend, ClipboardImage, ClipboardWrite, RecordingClipboard};
+pub use config::{
+    AccessToken, BindAddress, ClipboardConfig, Config, PhotoConfig, RateLimitConfig, ServerConfig, SmsConfig,
+    UploadConfig,
+};
+pub use handlers::response::{UploadError, UploadResponse};
+
+// 以下为上述类型的方法签名与配置项中用到的类型
+pub use admission::{PipelineLimits, PipelineMetrics};
+pub use attention::{AttentionConfig, DisplayWaker, UrgentKind};
+pub use audit::{AuditRecord, ItemAudit};
+pub use body_limit::BodyLimits;
+pub use cors::CorsConfig;
+pub use devices::{DeviceSettings, PairedDevice};
+pub use events::ServerEvent;
+pub use fastsync_core::pairing::PairingUri;
+pub use fastsync_core::protocol::{ClipboardPayload, SmsPayload};
+pub use fastsync_core::timeline::CaptureTime;
+pub use fastsync_core::{MDNS_SERVICE_TYPE, PROTOCOL_VERSION};
+pub use metadata::MetadataStripping;
+pub use pairing::{GuestAccess, PairingAttempt};
+pub use policy::{ContentInfo, ContentPolicy, EndpointRule, PolicyViolation, TypeRule};
+pub use schedule::{PauseSchedule, PauseWindow};
+pub use selfcheck::{CheckResult, CheckStatus, StartupReport};
+pub use sms_code::{CodeExtractor, CodeRules};
+pub use sms_filter::{FilterMode, PopupMode, SenderRules, SmsFilter, Suppression};
+pub use sms_history::SmsRetention;
+pub use state::RunMode;
+pub use suppression::{NotificationStateProbe, SuppressionSource};
+pub use temp_files::CleanupReport;
+pub use timings::{LastItem, Stage};
+pub use validation::{FieldError, PayloadRejection};
+
+/// Windows 通知使用的 AppUserModelId。
+pub const APP_ID: &str = "com.duoduojuzi.fastsync";
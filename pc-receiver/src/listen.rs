@@ -0,0 +1,198 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 监听端口模块。
+ * 依次监听配置的各个地址。配置的端口被其他程序占用时，未写端口的地址依次改用其后的几个端口，
+ * 仍被占用时由系统分配，不让启动失败。实际使用的端口记在配置目录下的 `port.json`，
+ * 下次启动时优先使用，手机缓存的地址在重启后仍然可用。
+ */
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+use crate::config::BindAddress;
+
+/// 配置的端口被占用时依次尝试的后续端口数，例如 3000 被占用时尝试 3001 到 3010。
+pub const FALLBACK_PORTS: u16 = 10;
+
+/// 上次启动实际使用的端口。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortHint {
+    /// 当时配置的端口，配置修改后不再使用这条记录
+    pub configured: u16,
+    /// 实际监听的端口
+    pub port: u16,
+}
+
+/// 默认的端口记录文件：`<漫游配置目录>/FastSync/port.json`，Windows 上即 `%APPDATA%\FastSync`。
+pub fn default_port_hint_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("FastSync").join("port.json"))
+}
+
+/// 读取端口记录，文件不存在或内容有误时为 None。
+pub fn load_port_hint(path: &Path) -> Option<PortHint> {
+    let text = std::fs::read(path).ok()?;
+    serde_json::from_slice(&text)
+        .inspect_err(|e| tracing::warn!("Ignoring invalid port hint {:?}: {}", path, e))
+        .ok()
+}
+
+/// 写入端口记录，失败时只记录日志。
+pub fn store_port_hint(path: &Path, hint: PortHint) {
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let json = serde_json::to_vec_pretty(&hint).map_err(std::io::Error::other)?;
+            std::fs::write(path, json)
+        });
+    if let Err(e) = written {
+        tracing::warn!("Failed to save port hint to {:?}: {}", path, e);
+    }
+}
+
+/// 依次尝试的端口：上次实际使用的端口、配置的端口、其后的 `FALLBACK_PORTS` 个端口，最后由系统分配。
+///
+/// # Arguments
+/// * `configured` - 配置的端口，为 0 时直接由系统分配
+/// * `hint` - 上次启动的端口记录，只在配置的端口未修改时使用
+/// * `fallback` - 是否允许改用其他端口，关闭时只尝试配置的端口
+pub fn candidate_ports(configured: u16, hint: Option<PortHint>, fallback: bool) -> Vec<u16> {
+    if configured == 0 || !fallback {
+        return vec![configured];
+    }
+    let mut ports = Vec::with_capacity(FALLBACK_PORTS as usize + 3);
+    if let Some(hint) = hint.filter(|hint| hint.configured == configured && hint.port != 0) {
+        ports.push(hint.port);
+    }
+    let following = (1..=FALLBACK_PORTS).filter_map(|offset| configured.checked_add(offset));
+    for port in std::iter::once(configured).chain(following).chain([0]) {
+        if !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+/// 补全端口并去掉重复的地址。
+///
+/// # Arguments
+/// * `addrs` - 配置的监听地址
+/// * `port` - 未写端口的地址使用的端口
+pub fn resolve(addrs: &[BindAddress], port: u16) -> Vec<SocketAddr> {
+    let mut resolved: Vec<SocketAddr> = Vec::with_capacity(addrs.len());
+    for addr in addrs.iter().map(|addr| addr.with_default_port(port)) {
+        if !resolved.contains(&addr) {
+            resolved.push(addr);
+        }
+    }
+    resolved
+}
+
+/// 某个地址无法监听。
+#[derive(Debug)]
+pub struct BindError {
+    pub addr: SocketAddr,
+    pub source: std::io::Error,
+}
+
+impl BindError {
+    /// 端口被其他程序占用。Windows 为 Hyper-V 等保留的端口范围报告为拒绝访问，同样可以换用其他端口。
+    fn port_taken(&self) -> bool {
+        matches!(
+            self.source.kind(),
+            std::io::ErrorKind::AddrInUse | std::io::ErrorKind::PermissionDenied
+        )
+    }
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hint = match self.source.kind() {
+            std::io::ErrorKind::AddrInUse => "the port is already used by another program",
+            std::io::ErrorKind::AddrNotAvailable => "no network interface has this address",
+            std::io::ErrorKind::PermissionDenied => "access to the port was denied",
+            _ => "check server.bind and server.port",
+        };
+        write!(f, "Failed to listen on {}: {} ({})", self.addr, self.source, hint)
+    }
+}
+
+// 说明中已包含系统错误，不再作为 source 重复输出
+impl std::error::Error for BindError {}
+
+/// 依次尝试候选端口，直到所有地址都能监听。只有未写端口的地址因端口被占用而失败时才换用下一个端口，
+/// 其他错误（例如网卡上没有该地址）直接返回。
+///
+/// # Arguments
+/// * `addrs` - 配置的监听地址，至少一个
+/// * `candidates` - 未写端口的地址依次尝试的端口，见 `candidate_ports`
+///
+/// # Returns
+/// 与去重后的地址顺序相同的监听器，以及未写端口的地址实际使用的端口
+pub(crate) fn bind_with_fallback(addrs: &[BindAddress], candidates: &[u16]) -> anyhow::Result<(Vec<TcpListener>, u16)> {
+    anyhow::ensure!(!addrs.is_empty(), "No listen address configured");
+    for (i, &port) in candidates.iter().enumerate() {
+        let resolved = resolve(addrs, port);
+        match bind_listeners(&resolved) {
+            Ok(listeners) if port == 0 => {
+                // 由系统分配时各地址沿用第一个分配的端口
+                let assigned = resolved
+                    .iter()
+                    .zip(&listeners)
+                    .find(|(addr, _)| addr.port() == 0)
+                    .and_then(|(_, listener)| listener.local_addr().ok())
+                    .map_or(0, |addr| addr.port());
+                return Ok((listeners, assigned));
+            }
+            Ok(listeners) => return Ok((listeners, port)),
+            Err(e) if e.port_taken() && e.addr.port() == port && i + 1 < candidates.len() => {
+                tracing::warn!("{}, trying the next port", e);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    anyhow::bail!("No port to listen on")
+}
+
+/// 依次监听各地址。监听 `[::]` 时关闭 IPV6_V6ONLY，同一端口同时接受 IPv4 连接；
+/// 端口为 0 的地址沿用第一个由系统分配的端口，使各地址上的服务端口一致。
+///
+/// # Arguments
+/// * `addrs` - 监听地址
+///
+/// # Returns
+/// 与地址顺序相同的监听器，任一地址失败时返回该地址与原因
+pub(crate) fn bind_listeners(addrs: &[SocketAddr]) -> Result<Vec<TcpListener>, BindError> {
+    let mut listeners = Vec::with_capacity(addrs.len());
+    let mut assigned_port = None;
+    for &addr in addrs {
+        let addr = match (addr.port(), assigned_port) {
+            (0, Some(port)) => SocketAddr::new(addr.ip(), port),
+            _ => addr,
+        };
+        let listener = bind_listener(addr).map_err(|source| BindError { addr, source })?;
+        if addr.port() == 0 {
+            assigned_port = listener.local_addr().ok().map(|local| local.port());
+        }
+        listeners.push(listener);
+    }
+    Ok(listeners)
+}
+
+fn bind_listener(addr: SocketAddr) -> std::io::Result<TcpListener> {
+    let socket = socket2::Socket::new(socket2::Domain::for_address(addr), socket2::Type::STREAM, Some(socket2::Protocol::TCP))?;
+    if let IpAddr::V6(ip) = addr.ip() {
+        // 系统对 IPv6 套接字是否同时接受 IPv4 的默认值不同（Windows 上默认不接受），这里明确设置
+        socket.set_only_v6(!ip.is_unspecified())?;
+    }
+    // 与 tokio 相同：非 Windows 系统上允许重启后立即重新监听 TIME_WAIT 中的端口
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
@@ -0,0 +1,117 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 日志文件模块。
+ * 托盘程序通常没有控制台，日志另写入配置目录下的 `logs\fastsync.log`。
+ * 文件超过大小上限时依次改名为 `fastsync.1.log`、`fastsync.2.log` ……，只保留最近的几个。
+ */
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 单个日志文件的默认大小上限。
+pub const DEFAULT_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 默认保留的旧日志文件数。
+pub const DEFAULT_LOG_FILES: usize = 5;
+
+/// 当前日志文件名。
+const LOG_FILE_NAME: &str = "fastsync.log";
+
+/// 默认的日志目录：`<漫游配置目录>/FastSync/logs`，Windows 上即 `%APPDATA%\FastSync\logs`。
+pub fn default_log_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("FastSync").join("logs"))
+}
+
+/// 按大小轮换的日志文件，配合 `Mutex` 作为 tracing 的输出。
+#[derive(Debug)]
+pub struct RotatingFile {
+    dir: PathBuf,
+    max_size: u64,
+    keep: usize,
+    /// 轮换期间为 None：Windows 上不能改名仍被打开的文件
+    file: Option<File>,
+    /// 当前文件已写入的字节数
+    written: u64,
+}
+
+impl RotatingFile {
+    /// 打开目录下的日志文件，不存在时创建，已有内容时追加。
+    ///
+    /// # Arguments
+    /// * `dir` - 日志目录，不存在时创建
+    /// * `max_size` - 单个文件的大小上限（字节）
+    /// * `keep` - 保留的旧文件数，0 表示超过上限时直接清空
+    pub fn open(dir: impl Into<PathBuf>, max_size: u64, keep: usize) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let file = open_append(&dir.join(LOG_FILE_NAME))?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            dir,
+            max_size,
+            keep,
+            file: Some(file),
+            written,
+        })
+    }
+
+    /// 当前日志文件的路径。
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE_NAME)
+    }
+
+    /// 第 `index` 个旧文件的路径，1 为最近的一个。
+    fn archive_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("fastsync.{}.log", index))
+    }
+
+    /// 把当前文件改名为 `fastsync.1.log`，更早的文件依次后移，超出保留数的删除。
+    fn rotate(&mut self) -> io::Result<()> {
+        let current = self.path();
+        self.file = None;
+        let moved = if self.keep > 0 {
+            let _ = std::fs::remove_file(self.archive_path(self.keep));
+            (1..self.keep)
+                .rev()
+                .map(|index| (self.archive_path(index), self.archive_path(index + 1)))
+                .filter(|(from, _)| from.exists())
+                .try_for_each(|(from, to)| std::fs::rename(from, to))
+                .and_then(|()| std::fs::rename(&current, self.archive_path(1)))
+        } else {
+            std::fs::remove_file(&current)
+        };
+        // 轮换失败时仍重新打开当前文件继续写入，不丢日志，再写满一个文件后重试
+        self.written = 0;
+        self.file = Some(open_append(&current)?);
+        moved
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 每条日志一次写入，在条目之间轮换，不会把一条日志拆到两个文件
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_size {
+            if let Err(e) = self.rotate() {
+                // 不能通过 tracing 记录，否则会再次进入这里
+                eprintln!("Failed to rotate log file in {:?}: {}", self.dir, e);
+            }
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(open_append(&self.path())?),
+        };
+        let written = file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), Write::flush)
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
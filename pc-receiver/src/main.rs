@@ -3,114 +3,225 @@
  * @Date: 2026-02-18
  */
 #![windows_subsystem = "windows"]
-use axum::{
-    extract::DefaultBodyLimit,
-    routing::post,
-    Router,
-};
-use std::net::SocketAddr;
-use mdns_sd::{ServiceDaemon, ServiceInfo};
-use std::collections::HashMap;
-use local_ip_address::local_ip;
-use winreg::enums::*;
-use winreg::RegKey;
+use fastsync::{Config, FastSyncServer, RunMode};
+use std::sync::Arc;
 
+mod cli;
+mod log_file;
+#[cfg(feature = "tray")]
 mod tray;
-mod handlers;
+#[cfg(windows)]
+mod service;
 
-pub const APP_ID: &str = "com.duoduojuzi.fastsync";
+/// 异步运行时工作线程的名称，用于区分请求处理中的 panic 与程序本身的致命错误。
+const WORKER_THREAD_NAME: &str = "fastsync-worker";
 
 /// 应用程序入口点。
 fn main() {
-    tracing_subscriber::fmt::init();
-
-    register_app_id();
-    clean_temp_files();
-
-    std::panic::set_hook(Box::new(|info| {
-        let msg = format!("程序发生致命错误:\n{}", info);
-        std::thread::spawn(move || {
-             rfd::MessageDialog::new()
-                .set_title("FastSync Error")
-                .set_description(&msg)
-                .set_level(rfd::MessageLevel::Error)
-                .show();
-        }).join().unwrap();
-    }));
+    // 参数有误时由 clap 打印错误与用法并以退出码 2 结束，--help 打印用法后以 0 结束
+    let invocation = cli::Invocation::try_parse_from(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    let cli = match invocation {
+        cli::Invocation::Run(cli) => *cli,
+        #[cfg(windows)]
+        cli::Invocation::Service(command) => {
+            tracing_subscriber::fmt::init();
+            std::process::exit(service::handle_command(command));
+        }
+        #[cfg(windows)]
+        cli::Invocation::RunService => {
+            service::init_logging();
+            if let Err(e) = service::run() {
+                tracing::error!("Failed to start service dispatcher: {:?}", e);
+            }
+            return;
+        }
+        #[cfg(not(windows))]
+        cli::Invocation::Service(command) => {
+            usage_error(format!("service {:?} is only supported on Windows", command))
+        }
+        #[cfg(not(windows))]
+        cli::Invocation::RunService => usage_error("Running as a service is only supported on Windows"),
+    };
+
+    // 运行模式必须在任何 WinRT 初始化之前确定
+    let mode = if cli.headless { RunMode::Headless } else { RunMode::Desktop };
+
+    // 日志另写入按大小轮换的文件，默认目录为 %APPDATA%\FastSync\logs。
+    // 托盘程序没有控制台，桌面模式默认写入，--no-log-file 关闭；无界面模式加 --log-file 开启
+    let log_dir = if cli.no_log_file {
+        None
+    } else {
+        cli.log_dir.clone().or_else(|| {
+            (mode == RunMode::Desktop || cli.log_file)
+                .then(crate::log_file::default_log_dir)
+                .flatten()
+        })
+    };
+    init_logging(cli.log_level.unwrap_or(tracing::Level::INFO), log_dir.as_deref());
+
+    if mode == RunMode::Desktop {
+        #[cfg(all(windows, feature = "notifications"))]
+        register_app_id();
+
+        // 请求中的 panic 由服务转为 500 响应，只记录日志，不弹出致命错误对话框
+        #[cfg(feature = "tray")]
+        std::panic::set_hook(Box::new(|info| {
+            if std::thread::current().name() == Some(WORKER_THREAD_NAME) {
+                tracing::error!("Panic in a worker thread: {}", info);
+                return;
+            }
+            show_fatal_error(format!("程序发生致命错误:\n{}", info));
+        }));
+    }
 
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
+        .thread_name(WORKER_THREAD_NAME)
         .build()
-        .unwrap();
+        .expect("Failed to create the async runtime");
 
-    rt.spawn(async {
-        start_mdns_broadcast();
+    // 配置文件 %APPDATA%\FastSync\config.toml，--config <路径> 使用其他文件，不存在时写入带注释的默认配置，首次运行时生成访问令牌。
+    // 文件有误时使用默认设置并在启动自检中提示；请求体上限的环境变量与命令行参数优先于配置文件，
+    // 但运行中重新载入配置后以文件为准
+    let config_path = cli.config.clone().or_else(Config::default_path);
+    let mut config = match config_path.as_deref().map(Config::load_or_create) {
+        Some(Ok(config)) => config,
+        Some(Err(e)) => {
+            tracing::error!("{:#}, using the default settings", e);
+            Config::default()
+        }
+        None => Config::default(),
+    };
+    // 环境变量 FASTSYNC_MAX_UPLOAD_MB / FASTSYNC_MAX_JSON_MB 覆盖配置文件中的请求体上限
+    match config.server.body_limits().with_env() {
+        Ok(limits) => {
+            config.server.max_upload = limits.upload;
+            config.server.max_json = limits.json;
+        }
+        Err(e) => usage_error(format!("{:#}", e)),
+    }
+    cli.apply(&mut config);
+    if let Err(e) = config.validate() {
+        usage_error(format!("{:#}", e));
+    }
 
-        let app = Router::new()
-            .route("/upload", post(handlers::photo::upload))
-            .route("/sms", post(handlers::sms::receive_sms))
-            .route("/clipboard", post(handlers::clipboard::receive_clipboard))
-            .layer(DefaultBodyLimit::max(50 * 1024 * 1024));
+    let mut builder = FastSyncServer::builder().config(config).mode(mode);
+    if let Some(path) = config_path {
+        builder = builder.config_file(path);
+    }
+    let server = Arc::new(builder.build());
 
-        let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-        tracing::info!("Server listening on {}", addr);
+    // --no-tray：桌面模式下不显示托盘图标，照常弹出通知，进程一直运行到被结束
+    let tray = mode == RunMode::Desktop && !cli.no_tray;
+    tracing::info!("Running in {} mode, tray {}", mode.as_str(), if tray { "enabled" } else { "disabled" });
 
-        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-        axum::serve(listener, app).await.unwrap();
+    // Ctrl-C 与托盘中的“退出”走同一优雅关闭流程
+    let stopper = server.clone();
+    rt.spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::info!("Ctrl-C received, shutting down");
+            stopper.shutdown();
+        }
     });
 
-    tray::run_event_loop();
+    match mode {
+        #[cfg(feature = "tray")]
+        RunMode::Desktop if tray => {
+            let background = server.clone();
+            rt.spawn(async move {
+                if let Err(e) = background.start().await {
+                    tracing::error!("Failed to start server: {:?}", e);
+                    show_fatal_error(format!("服务启动失败:\n{:#}", e));
+                    return;
+                }
+                background.wait().await;
+            });
+            tray::run_event_loop(server);
+        }
+        _ => rt.block_on(async {
+            if let Err(e) = server.start().await {
+                tracing::error!("Failed to start server: {:?}", e);
+                #[cfg(feature = "tray")]
+                if mode == RunMode::Desktop {
+                    show_fatal_error(format!("服务启动失败:\n{:#}", e));
+                }
+                std::process::exit(1);
+            }
+            server.wait().await;
+        }),
+    }
 }
 
-/// 注册 `_photosync._tcp.local.` mDNS 服务，广播主机名与 IP 地址。
-fn start_mdns_broadcast() {
-    let mdns = ServiceDaemon::new().expect("Failed to create mDNS daemon");
-    
-    let hostname = hostname::get()
-        .unwrap_or_else(|_| "fast-sync-pc".into())
-        .to_string_lossy()
-        .to_string();
-        
-    let service_type = "_photosync._tcp.local.";
-    let instance_name = format!("{}_fastsync", hostname);
-    
-    let my_ip = match local_ip() {
-        Ok(ip) => ip,
-        Err(e) => {
-            tracing::error!("Failed to get local IP address: {:?}", e);
-            return;
-        }
-    };
-    
-    let ip_str = my_ip.to_string();
-    let port = 3000;
-    
-    tracing::info!("Starting mDNS broadcast on IP: {}", ip_str);
+/// 打印参数错误与用法，以退出码 2 结束进程。
+///
+/// # Arguments
+/// * `error` - 错误描述
+fn usage_error(error: impl std::fmt::Display) -> ! {
+    use clap::CommandFactory;
+    cli::Cli::command().error(clap::error::ErrorKind::InvalidValue, error).exit()
+}
 
-    let properties: HashMap<String, String> = HashMap::new();
+/// 初始化日志：输出到控制台，指定目录时另写入该目录下按大小轮换的文件。
+///
+/// # Arguments
+/// * `level` - 日志级别
+/// * `log_dir` - 日志文件目录，None 时只输出到控制台
+fn init_logging(level: tracing::Level, log_dir: Option<&std::path::Path>) {
+    use crate::log_file::{RotatingFile, DEFAULT_LOG_FILES, DEFAULT_LOG_FILE_SIZE};
+    use tracing_subscriber::prelude::*;
 
-    let my_service = ServiceInfo::new(
-        service_type,
-        &instance_name,
-        &format!("{}.local.", instance_name),
-        &ip_str,
-        port,
-        Some(properties),
-    ).expect("valid service info");
+    let file = log_dir.map(|dir| RotatingFile::open(dir, DEFAULT_LOG_FILE_SIZE, DEFAULT_LOG_FILES));
+    let (file, error) = match file {
+        Some(Ok(file)) => (Some(file), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    let path = file.as_ref().map(RotatingFile::path);
+    let file_layer = file.map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(file))
+    });
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer())
+        .with(file_layer)
+        .with(tracing_subscriber::filter::LevelFilter::from_level(level))
+        .init();
 
-    mdns.register(my_service).expect("Failed to register mDNS service");
-    
-    Box::leak(Box::new(mdns));
-    
-    tracing::info!("mDNS service registered: {} ({}) @ {}:{}", instance_name, service_type, ip_str, port);
+    if let Some(path) = path {
+        tracing::info!("Writing logs to {:?}", path);
+    }
+    if let Some(e) = error {
+        tracing::warn!("Failed to open log file in {:?}, logging to the console only: {}", log_dir, e);
+    }
+}
+
+/// 在对话框中显示致命错误，用户关闭对话框后返回。
+///
+/// # Arguments
+/// * `message` - 错误描述
+#[cfg(feature = "tray")]
+fn show_fatal_error(message: String) {
+    let dialog = std::thread::spawn(move || {
+        rfd::MessageDialog::new()
+            .set_title("FastSync Error")
+            .set_description(&message)
+            .set_level(rfd::MessageLevel::Error)
+            .show();
+    });
+    let _ = dialog.join();
 }
 
 /// 注册应用程序 ID 并创建快捷方式，确保通知正常工作。
+#[cfg(all(windows, feature = "notifications"))]
 fn register_app_id() {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
     let exe_path = std::env::current_exe().unwrap_or_default();
     
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let path = format!("Software\\Classes\\AppUserModelId\\{}", APP_ID);
+    let path = format!("Software\\Classes\\AppUserModelId\\{}", fastsync::APP_ID);
     if let Ok((key, _)) = hkcu.create_subkey(&path) {
         let _ = key.set_value("DisplayName", &"FastSync Receiver");
     } else {
@@ -135,7 +246,7 @@ fn register_app_id() {
             shortcut_path.to_string_lossy(),
             exe_path.to_string_lossy(),
             exe_path.parent().unwrap_or(&exe_path).to_string_lossy(),
-            APP_ID
+            fastsync::APP_ID
         );
         
         let _ = std::process::Command::new("powershell")
@@ -145,28 +256,3 @@ fn register_app_id() {
         tracing::info!("Shortcut created/updated at: {:?}", shortcut_path);
     }
 }
-
-/// 启动时清理旧的临时图片文件。
-fn clean_temp_files() {
-    let temp_dir = std::env::temp_dir();
-    let mut count = 0;
-    
-    if let Ok(entries) = std::fs::read_dir(&temp_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with("fastsync_") && file_name.ends_with(".png") {
-                    if let Err(e) = std::fs::remove_file(&path) {
-                        tracing::warn!("Failed to delete temp file {:?}: {:?}", path, e);
-                    } else {
-                        count += 1;
-                    }
-                }
-            }
-        }
-    }
-    
-    if count > 0 {
-        tracing::info!("Cleaned up {} old temp files", count);
-    }
-}
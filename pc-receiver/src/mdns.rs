@@ -0,0 +1,123 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-02-18
+ */
+use anyhow::Context;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+use local_ip_address::local_ip;
+
+/// 退出时等待注销完成的最长时间。
+const UNREGISTER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 已注册的 mDNS 服务，退出时注销，其他电脑随即不再看到本机。
+pub(crate) struct MdnsBroadcast {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl MdnsBroadcast {
+    /// 注销服务并关闭 mDNS 守护线程，各步骤最多等待 `UNREGISTER_TIMEOUT`。
+    pub(crate) fn stop(self) {
+        match self.daemon.unregister(&self.fullname) {
+            Ok(status) => match status.recv_timeout(UNREGISTER_TIMEOUT) {
+                Ok(status) => tracing::info!("mDNS service {} unregistered: {:?}", self.fullname, status),
+                Err(e) => tracing::warn!("mDNS unregister of {} not confirmed: {}", self.fullname, e),
+            },
+            Err(e) => tracing::warn!("Failed to unregister mDNS service {}: {:?}", self.fullname, e),
+        }
+        match self.daemon.shutdown() {
+            Ok(status) => {
+                let _ = status.recv_timeout(UNREGISTER_TIMEOUT);
+            }
+            Err(e) => tracing::warn!("Failed to shut down mDNS daemon: {:?}", e),
+        }
+    }
+}
+
+/// 注册 `_photosync._tcp.local.` mDNS 服务，广播主机名与 IP 地址。
+/// 返回的句柄须在退出时调用 `stop`，直接丢弃时服务记录会在其他电脑上残留到过期。
+///
+/// # Arguments
+/// * `port` - 实际监听端口
+/// * `ips` - 只监听特定网卡时广播这些地址，为空时广播本机的局域网 IP
+/// * `features` - 已注册处理器的能力标识，写入 TXT 记录的 `features` 字段
+/// * `fingerprint` - HTTPS 证书的 SHA-256 指纹，写入 `fp` 字段；明文 HTTP 时为 None
+/// * `http_port` - 启用 HTTPS 时另开的明文 HTTP 端口，写入 `http_port` 字段
+pub(crate) fn start_mdns_broadcast(
+    port: u16,
+    ips: &[IpAddr],
+    features: &[String],
+    fingerprint: Option<&str>,
+    http_port: Option<u16>,
+) -> anyhow::Result<MdnsBroadcast> {
+    let mdns = ServiceDaemon::new().context("Failed to create mDNS daemon")?;
+    
+    let hostname = hostname::get()
+        .unwrap_or_else(|_| "fast-sync-pc".into())
+        .to_string_lossy()
+        .to_string();
+        
+    let service_type = crate::MDNS_SERVICE_TYPE;
+    let instance_name = format!("{}_fastsync", hostname);
+    
+    let ip_str = if ips.is_empty() {
+        local_ip().context("Failed to get local IP address")?.to_string()
+    } else {
+        ips.iter().map(IpAddr::to_string).collect::<Vec<_>>().join(",")
+    };
+    
+    tracing::info!("Starting mDNS broadcast on IP: {}", ip_str);
+
+    let properties = txt_properties(features, fingerprint, http_port);
+    let my_service = ServiceInfo::new(
+        service_type,
+        &instance_name,
+        &format!("{}.local.", instance_name),
+        &ip_str,
+        port,
+        Some(properties),
+    ).context("Invalid mDNS service info")?;
+
+    let fullname = my_service.get_fullname().to_string();
+    mdns.register(my_service).context("Failed to register mDNS service")?;
+    
+    tracing::info!("mDNS service registered: {} ({}) @ {}:{}", instance_name, service_type, ip_str, port);
+    Ok(MdnsBroadcast { daemon: mdns, fullname })
+}
+
+/// TXT 记录的内容。访问令牌只经配对流程传递，局域网中任何设备都能读到的 TXT 记录里不能出现。
+fn txt_properties(features: &[String], fingerprint: Option<&str>, http_port: Option<u16>) -> HashMap<String, String> {
+    let mut properties: HashMap<String, String> = HashMap::new();
+    properties.insert("features".to_string(), features.join(","));
+    properties.insert("scheme".to_string(), if fingerprint.is_some() { "https" } else { "http" }.to_string());
+    if let Some(fingerprint) = fingerprint {
+        properties.insert("fp".to_string(), fingerprint.to_string());
+    }
+    if let Some(http_port) = http_port {
+        properties.insert("http_port".to_string(), http_port.to_string());
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txt_record_carries_no_secrets() {
+        let features = vec!["photo".to_string(), "sms".to_string()];
+        let cases = [
+            (None, None, vec!["features", "scheme"]),
+            (Some("ab:cd"), Some(3001), vec!["features", "fp", "http_port", "scheme"]),
+        ];
+        for (fingerprint, http_port, expected) in cases {
+            let properties = txt_properties(&features, fingerprint, http_port);
+            let mut keys: Vec<_> = properties.keys().map(String::as_str).collect();
+            keys.sort_unstable();
+            assert_eq!(keys, expected, "{:?}", fingerprint);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 图片元数据清理模块。
+ * 收到的 JPEG 落盘后、转交保存对话框或自动保存之前，按配置移除 EXIF 中的 GPS 信息或整个 EXIF 与 XMP 段。
+ * 只改写文件头部的 APP1 段，压缩数据原样保留，不会重新编码。其他格式不做处理。
+ */
+use image::metadata::Orientation;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// 元数据清理方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataStripping {
+    /// 不做处理
+    #[default]
+    Off,
+    /// 只移除 EXIF 中的 GPS 信息
+    Gps,
+    /// 移除整个 EXIF 与 XMP 段，只保留拍摄方向
+    All,
+}
+
+impl std::str::FromStr for MetadataStripping {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "off" => Ok(MetadataStripping::Off),
+            "gps" => Ok(MetadataStripping::Gps),
+            "all" => Ok(MetadataStripping::All),
+            other => anyhow::bail!("Unknown metadata stripping {:?}, expected off, gps or all", other),
+        }
+    }
+}
+
+/// 一次清理的结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripOutcome {
+    /// 文件中没有需要移除的元数据
+    Unchanged,
+    /// 移除了 GPS 信息
+    GpsRemoved,
+    /// 移除了 EXIF 与 XMP 段
+    MetadataRemoved,
+}
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// EXIF 中指向 GPS IFD 的标签。
+const GPS_IFD_TAG: u16 = 0x8825;
+
+/// 文件头部的一个 APP1 段。
+struct App1Segment {
+    /// 段标记在文件中的起始位置
+    start: u64,
+    /// 含标记与长度字段的总长度
+    len: u64,
+    payload: Vec<u8>,
+}
+
+impl App1Segment {
+    fn is_exif(&self) -> bool {
+        self.payload.starts_with(EXIF_HEADER)
+    }
+
+    fn is_xmp(&self) -> bool {
+        self.payload.starts_with(XMP_HEADER)
+    }
+
+    /// 是否为清理后补写的只含拍摄方向的 EXIF 段，再次清理时保留。
+    fn is_orientation_only(&self) -> bool {
+        Orientation::from_exif_chunk(self.payload.get(EXIF_HEADER.len()..).unwrap_or_default())
+            .is_some_and(|orientation| orientation_segment(orientation)[4..] == self.payload[..])
+    }
+}
+
+/// 按配置清理 JPEG 文件中的元数据，文件在原位置被改写。
+///
+/// # Arguments
+/// * `path` - JPEG 文件路径
+/// * `mode` - 清理方式
+///
+/// # Returns
+/// 清理结果
+pub fn strip_jpeg_metadata(path: &Path, mode: MetadataStripping) -> anyhow::Result<StripOutcome> {
+    if mode == MetadataStripping::Off {
+        return Ok(StripOutcome::Unchanged);
+    }
+    let segments = read_app1_segments(path)?;
+
+    if mode == MetadataStripping::Gps {
+        let Some(exif) = segments.iter().find(|segment| segment.is_exif()) else {
+            return Ok(StripOutcome::Unchanged);
+        };
+        let mut tiff = exif.payload[EXIF_HEADER.len()..].to_vec();
+        match remove_gps(&mut tiff) {
+            Some(false) => return Ok(StripOutcome::Unchanged),
+            Some(true) => {
+                // 段长度不变，只覆盖 TIFF 数据
+                let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+                file.seek(SeekFrom::Start(exif.start + 4 + EXIF_HEADER.len() as u64))?;
+                file.write_all(&tiff)?;
+                file.flush()?;
+                return Ok(StripOutcome::GpsRemoved);
+            }
+            None => {
+                // 无法可靠解析时退回到移除整个 EXIF 段，宁可多删也不留下位置信息
+                tracing::warn!("Unparseable EXIF in {:?}, removing the whole block", path);
+                let exif = segments.into_iter().filter(|segment| segment.is_exif()).collect::<Vec<_>>();
+                rewrite_without(path, &exif)?;
+                return Ok(StripOutcome::MetadataRemoved);
+            }
+        }
+    }
+
+    let removed: Vec<App1Segment> = segments
+        .into_iter()
+        .filter(|segment| (segment.is_exif() && !segment.is_orientation_only()) || segment.is_xmp())
+        .collect();
+    if removed.is_empty() {
+        return Ok(StripOutcome::Unchanged);
+    }
+    rewrite_without(path, &removed)?;
+    Ok(StripOutcome::MetadataRemoved)
+}
+
+/// 读取第一个扫描段之前的所有 APP1 段。
+fn read_app1_segments(path: &Path) -> anyhow::Result<Vec<App1Segment>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut soi = [0u8; 2];
+    reader.read_exact(&mut soi)?;
+    anyhow::ensure!(soi == [0xFF, 0xD8], "Not a JPEG file");
+
+    let mut segments = Vec::new();
+    let mut position = 2u64;
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        anyhow::ensure!(marker[0] == 0xFF, "Malformed JPEG marker at {}", position);
+        // 扫描段之后是压缩数据，元数据段都在此之前
+        if marker[1] == 0xDA || marker[1] == 0xD9 {
+            break;
+        }
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        let length = u16::from_be_bytes(length) as u64;
+        anyhow::ensure!(length >= 2, "Malformed JPEG segment length at {}", position);
+
+        if marker[1] == 0xE1 {
+            let mut payload = vec![0u8; length as usize - 2];
+            reader.read_exact(&mut payload)?;
+            segments.push(App1Segment { start: position, len: length + 2, payload });
+        } else {
+            reader.seek_relative(length as i64 - 2)?;
+        }
+        position += length + 2;
+    }
+    Ok(segments)
+}
+
+/// 去掉指定的段后重写文件。整个 EXIF 被移除时补一个只含拍摄方向的 EXIF 段，避免图片显示方向出错。
+///
+/// # Arguments
+/// * `path` - JPEG 文件路径
+/// * `removed` - 要去掉的段，按文件中的顺序排列
+fn rewrite_without(path: &Path, removed: &[App1Segment]) -> anyhow::Result<()> {
+    let orientation = removed
+        .iter()
+        .find(|segment| segment.is_exif())
+        .and_then(|segment| Orientation::from_exif_chunk(&segment.payload[EXIF_HEADER.len()..]))
+        .filter(|orientation| *orientation != Orientation::NoTransforms);
+
+    let mut target_name = path.file_name().unwrap_or_default().to_os_string();
+    target_name.push(".strip");
+    let target = path.with_file_name(target_name);
+
+    let result = (|| -> anyhow::Result<()> {
+        let mut source = BufReader::new(File::open(path)?);
+        let mut output = BufWriter::new(File::create(&target)?);
+
+        std::io::copy(&mut (&mut source).take(2), &mut output)?;
+        if let Some(orientation) = orientation {
+            output.write_all(&orientation_segment(orientation))?;
+        }
+        let mut position = 2u64;
+        for segment in removed {
+            std::io::copy(&mut (&mut source).take(segment.start - position), &mut output)?;
+            source.seek_relative(segment.len as i64)?;
+            position = segment.start + segment.len;
+        }
+        std::io::copy(&mut source, &mut output)?;
+        output.flush()?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&target);
+        return Err(e);
+    }
+    std::fs::rename(&target, path)?;
+    Ok(())
+}
+
+/// 只含拍摄方向的 APP1 段。
+fn orientation_segment(orientation: Orientation) -> Vec<u8> {
+    let mut tiff = b"MM\0*".to_vec();
+    tiff.extend(8u32.to_be_bytes());
+    tiff.extend(1u16.to_be_bytes());
+    tiff.extend(0x0112u16.to_be_bytes());
+    tiff.extend(3u16.to_be_bytes());
+    tiff.extend(1u32.to_be_bytes());
+    tiff.extend((orientation.to_exif() as u16).to_be_bytes());
+    tiff.extend([0, 0]);
+    tiff.extend(0u32.to_be_bytes());
+
+    let length = (2 + EXIF_HEADER.len() + tiff.len()) as u16;
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend(length.to_be_bytes());
+    segment.extend_from_slice(EXIF_HEADER);
+    segment.extend(tiff);
+    segment
+}
+
+/// TIFF 数据的字节序。
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    fn u16(self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    fn put_u16(self, data: &mut [u8], offset: usize, value: u16) {
+        let bytes = match self {
+            Endian::Little => value.to_le_bytes(),
+            Endian::Big => value.to_be_bytes(),
+        };
+        data[offset..offset + 2].copy_from_slice(&bytes);
+    }
+}
+
+/// TIFF 字段类型的单个值长度。
+fn type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+/// 在 TIFF 数据中清零 GPS IFD 及其引用的数据，并从 IFD0 中移除指向它的标签，数据长度保持不变。
+///
+/// # Returns
+/// 是否移除了 GPS 信息，结构无法解析时为 None
+fn remove_gps(tiff: &mut [u8]) -> Option<bool> {
+    let endian = match tiff.get(0..4)? {
+        [b'I', b'I', 42, 0] => Endian::Little,
+        [b'M', b'M', 0, 42] => Endian::Big,
+        _ => return None,
+    };
+    let ifd0 = endian.u32(tiff, 4)? as usize;
+    let count = endian.u16(tiff, ifd0)? as usize;
+    let entries = ifd0 + 2;
+    // IFD0 的条目与其后的下一个 IFD 偏移
+    let ifd0_end = entries + count * 12 + 4;
+    tiff.get(ifd0..ifd0_end)?;
+
+    let Some(index) = (0..count).find(|i| endian.u16(tiff, entries + i * 12) == Some(GPS_IFD_TAG)) else {
+        return Some(false);
+    };
+    let gps = endian.u32(tiff, entries + index * 12 + 8)? as usize;
+
+    // 先清零 GPS 条目引用的数据，再清零 GPS IFD 本身
+    let gps_count = endian.u16(tiff, gps)? as usize;
+    let gps_end = gps + 2 + gps_count * 12 + 4;
+    tiff.get(gps..gps_end)?;
+    for i in 0..gps_count {
+        let entry = gps + 2 + i * 12;
+        let Some(size) = type_size(endian.u16(tiff, entry + 2)?) else {
+            continue;
+        };
+        let size = size.checked_mul(endian.u32(tiff, entry + 4)? as usize)?;
+        if size > 4 {
+            let offset = endian.u32(tiff, entry + 8)? as usize;
+            tiff.get_mut(offset..offset.checked_add(size)?)?.fill(0);
+        }
+    }
+    tiff[gps..gps_end].fill(0);
+
+    // 后面的条目与下一个 IFD 偏移前移一格
+    let entry = entries + index * 12;
+    tiff.copy_within(entry + 12..ifd0_end, entry);
+    tiff[ifd0_end - 12..ifd0_end].fill(0);
+    endian.put_u16(tiff, ifd0, (count - 1) as u16);
+    Some(true)
+}
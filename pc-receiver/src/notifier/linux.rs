@@ -0,0 +1,101 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 基于 notify-rust (freedesktop D-Bus) 的通知后端。
+ */
+use notify_rust::{Hint, Timeout, Urgency};
+use super::{ActionHandler, Notification, Notifier};
+
+/// Linux 桌面通知后端。
+pub struct LinuxNotifier;
+
+/// 把通知描述转换为 freedesktop 通知，不连接 D-Bus。
+fn build_toast(notification: &Notification) -> notify_rust::Notification {
+    let mut toast = notify_rust::Notification::new();
+    toast
+        .appname("FastSync")
+        .summary(&notification.title)
+        .body(&notification.body.join("\n"))
+        .timeout(Timeout::Milliseconds(notification.expires_in.as_millis() as u32));
+
+    if notification.quiet {
+        toast.urgency(Urgency::Low).hint(Hint::SuppressSound(true));
+    }
+
+    if let Some(path) = &notification.hero_image {
+        toast.hint(Hint::ImagePath(path.to_string_lossy().to_string()));
+    }
+
+    // freedesktop 通知没有输入框，提交输入框的按钮不显示
+    for action in notification.actions.iter().filter(|action| action.input.is_none()) {
+        toast.action(&action.id, &action.label);
+    }
+    toast
+}
+
+impl Notifier for LinuxNotifier {
+    fn show(&self, notification: Notification, on_action: ActionHandler) -> anyhow::Result<()> {
+        let handle = build_toast(&notification).show()?;
+
+        // wait_for_action 会阻塞直到通知被点击或关闭
+        std::thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                if action != "__closed" {
+                    on_action(action);
+                }
+            });
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use crate::notifier::NotificationAction;
+
+    #[test]
+    fn builds_toast_with_actions_and_hero_image() {
+        let mut notification = Notification::new("photo", "收到图片");
+        notification.body = vec!["来自 Pixel".to_string(), "1.2 MB".to_string()];
+        notification.hero_image = Some(PathBuf::from("/tmp/fastsync_1.png"));
+        notification.expires_in = Duration::from_secs(60);
+        notification.actions = vec![
+            NotificationAction::new("copy", "复制"),
+            NotificationAction::new("reply", "回复").with_input("text"),
+            NotificationAction::new("save", "保存"),
+        ];
+
+        let toast = build_toast(&notification);
+        assert_eq!(toast.appname, "FastSync");
+        assert_eq!(toast.summary, "收到图片");
+        assert_eq!(toast.body, "来自 Pixel\n1.2 MB");
+        assert_eq!(toast.timeout, Timeout::Milliseconds(60_000));
+        // 按钮按 (标识, 文字) 依次排列，提交输入框的按钮不显示
+        assert_eq!(toast.actions, vec!["copy", "复制", "save", "保存"]);
+        assert!(toast.hints.contains(&Hint::ImagePath("/tmp/fastsync_1.png".to_string())));
+        assert!(!toast.hints.contains(&Hint::SuppressSound(true)));
+    }
+
+    #[test]
+    fn quiet_toast_suppresses_sound() {
+        let mut notification = Notification::new("sms", "短信");
+        notification.quiet = true;
+
+        let toast = build_toast(&notification);
+        assert!(toast.hints.contains(&Hint::SuppressSound(true)));
+        assert!(toast.hints.contains(&Hint::Urgency(Urgency::Low)));
+        assert!(toast.actions.is_empty());
+    }
+
+    #[test]
+    fn constructs_without_a_session_bus() {
+        // 构建后端与查询是否允许通知都不连接 D-Bus
+        assert!(LinuxNotifier.is_enabled().unwrap());
+        assert!(crate::notifier::default_notifier().is_enabled().unwrap());
+    }
+}
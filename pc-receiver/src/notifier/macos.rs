@@ -0,0 +1,68 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 基于 mac-notification-sys (NSUserNotification/UNUserNotification) 的通知后端。
+ */
+use mac_notification_sys::{MainButton, NotificationResponse};
+use super::{ActionHandler, Notification, Notifier};
+
+/// macOS 通知后端。
+///
+/// macOS 通知只有一个主按钮和一个关闭按钮：`ignore` 映射为关闭按钮，
+/// 其余按钮只有一个时作为主按钮，多个时收进下拉菜单。
+pub struct MacNotifier;
+
+impl Notifier for MacNotifier {
+    fn show(&self, notification: Notification, on_action: ActionHandler) -> anyhow::Result<()> {
+        // send() 在等待点击时会阻塞，因此放到独立线程
+        std::thread::spawn(move || {
+            let body = notification.body.join("\n");
+            let hero_image = notification
+                .hero_image
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string());
+
+            let close_action = notification.actions.iter().find(|a| a.id == "ignore");
+            let labels: Vec<&str> = notification
+                .actions
+                .iter()
+                .filter(|a| a.id != "ignore" && a.input.is_none())
+                .map(|a| a.label.as_str())
+                .collect();
+
+            let mut toast = mac_notification_sys::Notification::new();
+            toast.title(&notification.title).message(&body);
+
+            if let Some(image) = &hero_image {
+                toast.content_image(image);
+            }
+
+            match labels.len() {
+                0 => {}
+                1 => {
+                    toast.main_button(MainButton::SingleAction(labels[0]));
+                }
+                _ => {
+                    toast.main_button(MainButton::DropdownActions("操作", &labels));
+                }
+            }
+
+            if let Some(action) = close_action {
+                toast.close_button(&action.label);
+            }
+
+            match toast.send() {
+                Ok(NotificationResponse::ActionButton(label)) | Ok(NotificationResponse::CloseButton(label)) => {
+                    if let Some(action) = notification.actions.iter().find(|a| a.label == label) {
+                        on_action(&action.id);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Failed to deliver macOS notification: {:?}", e),
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 记录通知的后端，用于测试与嵌入方检查通知内容。
+ */
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use super::{ActionHandler, Notification, Notifier};
+
+/// 不展示通知，只记录每次显示请求的后端。可模拟显示失败与按钮点击。
+pub struct MockNotifier {
+    shown: Mutex<Vec<(Notification, ActionHandler)>>,
+    attempts: AtomicUsize,
+    /// 之后还需失败的显示次数
+    failures: AtomicUsize,
+    enabled: AtomicBool,
+}
+
+impl Default for MockNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockNotifier {
+    pub fn new() -> Self {
+        Self {
+            shown: Mutex::new(Vec::new()),
+            attempts: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+            enabled: AtomicBool::new(true),
+        }
+    }
+
+    /// 之后的 `times` 次显示返回错误，不记录通知。
+    pub fn fail_next(&self, times: usize) {
+        self.failures.store(times, Ordering::SeqCst);
+    }
+
+    /// 设置 `is_enabled` 的返回值，模拟系统关闭了本应用的通知。
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    /// 显示请求的总次数，包括失败的请求。
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// 显示成功的通知，按显示顺序排列。
+    pub fn shown(&self) -> Vec<Notification> {
+        self.shown.lock().unwrap().iter().map(|(notification, _)| notification.clone()).collect()
+    }
+
+    /// 点击指定标签的最新一条通知上的按钮。
+    ///
+    /// # Arguments
+    /// * `tag` - 通知标签
+    /// * `arguments` - 传给回调的按钮标识，提交输入框时用 `join_input` 拼接
+    ///
+    /// # Returns
+    /// 是否找到该通知
+    pub fn click(&self, tag: &str, arguments: &str) -> bool {
+        let handler = self
+            .shown
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(notification, _)| notification.tag == tag)
+            .map(|(_, handler)| handler.clone());
+        match handler {
+            Some(handler) => {
+                handler(arguments);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Notifier for MockNotifier {
+    fn show(&self, notification: Notification, on_action: ActionHandler) -> anyhow::Result<()> {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        let failing = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+            .is_ok();
+        if failing {
+            anyhow::bail!("simulated notification failure: {}", notification.title);
+        }
+        self.shown.lock().unwrap().push((notification, on_action));
+        Ok(())
+    }
+
+    fn is_enabled(&self) -> anyhow::Result<bool> {
+        Ok(self.enabled.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn records_notifications_and_clicks() {
+        let notifier = MockNotifier::new();
+        let clicked = Arc::new(Mutex::new(Vec::new()));
+        let sink = clicked.clone();
+        notifier
+            .show(Notification::new("sms", "短信"), Arc::new(move |action: &str| sink.lock().unwrap().push(action.to_string())))
+            .unwrap();
+
+        assert_eq!(notifier.attempts(), 1);
+        assert_eq!(notifier.shown().len(), 1);
+        assert_eq!(notifier.shown()[0].title, "短信");
+        assert!(notifier.click("sms", "copy"));
+        assert!(!notifier.click("photo", "copy"));
+        assert_eq!(*clicked.lock().unwrap(), vec!["copy".to_string()]);
+    }
+
+    #[test]
+    fn simulates_failures() {
+        let notifier = MockNotifier::new();
+        notifier.fail_next(2);
+        for _ in 0..2 {
+            assert!(notifier.show(Notification::new("a", "a"), Arc::new(|_: &str| {})).is_err());
+        }
+        assert!(notifier.show(Notification::new("a", "a"), Arc::new(|_: &str| {})).is_ok());
+        assert_eq!(notifier.attempts(), 3);
+        assert_eq!(notifier.shown().len(), 1);
+
+        assert!(notifier.is_enabled().unwrap());
+        notifier.set_enabled(false);
+        assert!(!notifier.is_enabled().unwrap());
+    }
+}
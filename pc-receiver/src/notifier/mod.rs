@@ -0,0 +1,193 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 通知后端抽象模块。
+ * 处理器只描述通知内容与按钮，由具体平台的后端负责展示与回调。
+ */
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::attention::UrgentKind;
+
+mod mock;
+mod null;
+pub(crate) mod queue;
+// 只有 WinRT 后端使用，其他平台上仅供测试
+#[cfg_attr(not(all(windows, feature = "notifications")), allow(dead_code))]
+mod xml;
+#[cfg(all(windows, feature = "notifications"))]
+mod winrt;
+#[cfg(all(target_os = "linux", feature = "notifications"))]
+mod linux;
+#[cfg(all(target_os = "macos", feature = "macos"))]
+mod macos;
+
+pub use self::mock::MockNotifier;
+pub use self::null::NullNotifier;
+#[cfg(all(windows, feature = "notifications"))]
+pub use self::winrt::WindowsNotifier;
+#[cfg(all(target_os = "linux", feature = "notifications"))]
+pub use self::linux::LinuxNotifier;
+#[cfg(all(target_os = "macos", feature = "macos"))]
+pub use self::macos::MacNotifier;
+
+/// 通知上的交互按钮。
+#[derive(Debug, Clone)]
+pub struct NotificationAction {
+    /// 按钮标识，点击后原样传回回调
+    pub id: String,
+    /// 按钮显示文字
+    pub label: String,
+    /// 点击时一同提交的输入框标识
+    pub input: Option<String>,
+}
+
+impl NotificationAction {
+    pub fn new(id: &str, label: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            label: label.to_string(),
+            input: None,
+        }
+    }
+
+    /// 点击按钮时一同提交输入框中的内容，按钮显示在输入框旁边。
+    pub fn with_input(mut self, input: &str) -> Self {
+        self.input = Some(input.to_string());
+        self
+    }
+}
+
+/// 通知上的文本输入框。不支持输入框的后端不显示输入框，也不显示提交输入框的按钮。
+#[derive(Debug, Clone)]
+pub struct NotificationInput {
+    /// 输入框标识，与按钮的 `input` 对应
+    pub id: String,
+    /// 输入框为空时显示的提示
+    pub placeholder: String,
+}
+
+impl NotificationInput {
+    pub fn new(id: &str, placeholder: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            placeholder: placeholder.to_string(),
+        }
+    }
+}
+
+/// 与平台无关的通知描述。
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// 通知标签，同一标签的新通知会替换旧通知
+    pub tag: String,
+    /// 通知分组，每个分组在通知中心保留的通知数有上限，超出时移除最早的一条
+    pub group: String,
+    /// 标题行
+    pub title: String,
+    /// 正文行（原始文本，由后端负责转义）
+    pub body: Vec<String>,
+    /// 大图预览的本地路径
+    pub hero_image: Option<PathBuf>,
+    /// 交互按钮
+    pub actions: Vec<NotificationAction>,
+    /// 文本输入框
+    pub inputs: Vec<NotificationInput>,
+    /// 通知在通知中心保留的时长
+    pub expires_in: Duration,
+    /// 是否使用较长的弹窗显示时间
+    pub long_duration: bool,
+    /// 静默通知：不弹出横幅、不播放提示音，只进入通知中心
+    pub quiet: bool,
+    /// 紧急类型，按配置唤醒显示器并闪烁托盘图标
+    pub urgent: Option<UrgentKind>,
+    /// 通知过期或用户点击按钮后删除的临时文件
+    pub temp_files: Vec<PathBuf>,
+}
+
+impl Notification {
+    pub fn new(tag: &str, title: &str) -> Self {
+        Self {
+            tag: tag.to_string(),
+            group: "FastSync".to_string(),
+            title: title.to_string(),
+            body: Vec::new(),
+            hero_image: None,
+            actions: Vec::new(),
+            inputs: Vec::new(),
+            expires_in: Duration::from_secs(30),
+            long_duration: false,
+            quiet: false,
+            urgent: None,
+            temp_files: Vec::new(),
+        }
+    }
+}
+
+/// 按钮点击回调，参数为被点击按钮的 `id`。提交输入框的按钮在 `id` 之后附带输入内容，用 `split_input` 拆分。
+pub type ActionHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// 回调参数中按钮标识与输入内容之间的分隔符（ASCII 单元分隔符）。
+const INPUT_SEPARATOR: char = '\u{1f}';
+
+/// 拼接按钮标识与输入内容，作为提交输入框的按钮的回调参数。
+///
+/// # Arguments
+/// * `action` - 按钮标识
+/// * `input` - 输入框中的内容
+pub fn join_input(action: &str, input: &str) -> String {
+    format!("{}{}{}", action, INPUT_SEPARATOR, input)
+}
+
+/// 拆分按钮回调参数。
+///
+/// # Arguments
+/// * `arguments` - 回调参数
+///
+/// # Returns
+/// 按钮标识，以及按钮提交的输入内容
+pub fn split_input(arguments: &str) -> (&str, Option<&str>) {
+    match arguments.split_once(INPUT_SEPARATOR) {
+        Some((action, input)) => (action, Some(input)),
+        None => (arguments, None),
+    }
+}
+
+/// 通知后端。
+pub trait Notifier: Send + Sync {
+    /// 显示通知，并在用户点击按钮时调用 `on_action`。
+    ///
+    /// # Arguments
+    /// * `notification` - 通知描述
+    /// * `on_action` - 按钮点击回调
+    fn show(&self, notification: Notification, on_action: ActionHandler) -> anyhow::Result<()>;
+
+    /// 系统是否允许本应用弹出通知，用于启动自检。无法查询的后端视为允许。
+    fn is_enabled(&self) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// 返回当前平台的默认通知后端，未启用 `notifications` 特性时为空实现。
+pub fn default_notifier() -> Arc<dyn Notifier> {
+    #[cfg(all(windows, feature = "notifications"))]
+    {
+        Arc::new(WindowsNotifier)
+    }
+    #[cfg(all(target_os = "linux", feature = "notifications"))]
+    {
+        Arc::new(LinuxNotifier)
+    }
+    #[cfg(all(target_os = "macos", feature = "macos"))]
+    {
+        Arc::new(MacNotifier)
+    }
+    #[cfg(not(all(
+        feature = "notifications",
+        any(windows, target_os = "linux", all(target_os = "macos", feature = "macos"))
+    )))]
+    {
+        Arc::new(NullNotifier)
+    }
+}
@@ -0,0 +1,17 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 空通知后端，用于无桌面会话的运行环境。
+ */
+use super::{ActionHandler, Notification, Notifier};
+
+/// 不展示任何通知的后端，仅记录日志。
+pub struct NullNotifier;
+
+impl Notifier for NullNotifier {
+    fn show(&self, notification: Notification, _on_action: ActionHandler) -> anyhow::Result<()> {
+        tracing::debug!("Notification suppressed (no desktop session): {}", notification.title);
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 通知显示队列。
+ * 通知后端偶尔会暂时失败（例如刚登录时系统外壳尚未就绪），
+ * 失败的通知按退避重试，多次失败后转入操作记录，并以汇总通知告知用户。
+ */
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::Instrument;
+use crate::audit::{AuditLog, AuditRecord};
+use crate::payload::NotificationRequest;
+use crate::suppression::{MissedDigest, MissedTracker};
+use crate::timings::{Stage, TimingStats, Timings};
+use super::{Notification, NotificationAction, Notifier};
+
+/// 每条通知最多尝试显示的次数。
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 第一次重试前的等待时间，之后每次翻倍。
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// 两次汇总通知之间的最短间隔。
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 一次显示失败后的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RetryDecision {
+    /// 等待后重试
+    Retry(Duration),
+    /// 放弃显示，转入操作记录；`summary` 为需要汇总告知用户的条数
+    Divert { summary: Option<u32> },
+}
+
+/// 重试与汇总策略，只依赖传入的时间，不执行任何显示。
+#[derive(Debug)]
+pub(crate) struct RetryPolicy {
+    max_attempts: u32,
+    base_backoff: Duration,
+    summary_interval: Duration,
+    last_summary: Option<Instant>,
+    /// 上次汇总之后转入记录、尚未告知用户的条数
+    unreported: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(MAX_ATTEMPTS, BASE_BACKOFF, SUMMARY_INTERVAL)
+    }
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(max_attempts: u32, base_backoff: Duration, summary_interval: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_backoff,
+            summary_interval,
+            last_summary: None,
+            unreported: 0,
+        }
+    }
+
+    /// 一次显示失败后决定下一步。
+    ///
+    /// # Arguments
+    /// * `attempts` - 已经尝试的次数（含本次）
+    /// * `shutting_down` - 服务是否正在关闭，关闭时不再重试也不弹出汇总
+    /// * `now` - 当前时间
+    pub(crate) fn on_failure(&mut self, attempts: u32, shutting_down: bool, now: Instant) -> RetryDecision {
+        if !shutting_down && attempts < self.max_attempts {
+            return RetryDecision::Retry(self.base_backoff * 2u32.saturating_pow(attempts.saturating_sub(1)));
+        }
+
+        self.unreported += 1;
+        if shutting_down {
+            return RetryDecision::Divert { summary: None };
+        }
+
+        let due = self
+            .last_summary
+            .is_none_or(|last| now.duration_since(last) >= self.summary_interval);
+        if !due {
+            return RetryDecision::Divert { summary: None };
+        }
+        self.last_summary = Some(now);
+        RetryDecision::Divert {
+            summary: Some(std::mem::take(&mut self.unreported)),
+        }
+    }
+}
+
+/// 通知显示队列，由所有接口共享。
+pub(crate) struct NotificationQueue {
+    notifier: Arc<dyn Notifier>,
+    audit: Arc<AuditLog>,
+    policy: Mutex<RetryPolicy>,
+    shutdown: watch::Receiver<bool>,
+    timings: Arc<TimingStats>,
+    missed: Arc<MissedTracker>,
+    failures: AtomicU64,
+    /// 尚未显示成功或转入记录的通知数
+    pending: AtomicUsize,
+}
+
+impl NotificationQueue {
+    /// # Arguments
+    /// * `notifier` - 通知后端
+    /// * `audit` - 显示失败的通知转入的操作记录
+    /// * `shutdown` - 服务关闭信号
+    /// * `timings` - 显示完成或放弃时计入的耗时统计
+    /// * `missed` - 通知被抑制期间收到的内容
+    pub(crate) fn new(
+        notifier: Arc<dyn Notifier>,
+        audit: Arc<AuditLog>,
+        shutdown: watch::Receiver<bool>,
+        timings: Arc<TimingStats>,
+        missed: Arc<MissedTracker>,
+    ) -> Self {
+        Self {
+            notifier,
+            audit,
+            policy: Mutex::new(RetryPolicy::default()),
+            shutdown,
+            timings,
+            missed,
+            failures: AtomicU64::new(0),
+            pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// 转入操作记录的通知总数。
+    pub(crate) fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// 尚未显示成功或转入记录的通知数，计入准入控制的排队深度。
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// 在后台显示通知，失败时按策略重试。
+    ///
+    /// # Arguments
+    /// * `capability` - 发出通知的处理器能力标识
+    /// * `device` - 来源设备，写入操作记录
+    /// * `request` - 通知与按钮回调
+    /// * `timings` - 内容的阶段时间，显示完成或放弃后汇总
+    pub(crate) fn submit(
+        self: &Arc<Self>,
+        capability: String,
+        device: Option<String>,
+        mut request: NotificationRequest,
+        timings: Option<Timings>,
+    ) {
+        // 被抑制的通知仍交给系统（会进入通知中心），同时记为错过，抑制结束后汇总
+        if self.missed.record(&request.notification.group, &request.notification.title) {
+            self.record_missed(&capability, device.clone(), &request.notification);
+            if self.missed.holds_display() {
+                tracing::info!("Holding {} notification during do-not-disturb", capability);
+                if let Some(timings) = timings {
+                    self.timings.finish(&capability, &timings);
+                }
+                return;
+            }
+        }
+
+        // 点击任意按钮后通知即关闭，按钮回调执行完后删除临时文件
+        let temp_files = request.notification.temp_files.clone();
+        if !temp_files.is_empty() {
+            let on_action = request.on_action;
+            let discarded = temp_files.clone();
+            request.on_action = Arc::new(move |arguments: &str| {
+                on_action(arguments);
+                crate::temp_files::discard(&discarded);
+            });
+        }
+
+        let expires_in = request.notification.expires_in;
+        let queue = self.clone();
+        queue.pending.fetch_add(1, Ordering::Relaxed);
+        // 沿用提交时的 span，通知显示与重试的日志带有所属请求的 ID
+        let task = async move {
+            let shown = queue.run(&capability, device, request).await;
+            queue.pending.fetch_sub(1, Ordering::Relaxed);
+            if let Some(mut timings) = timings {
+                if shown {
+                    timings.mark(Stage::Shown);
+                }
+                queue.timings.finish(&capability, &timings);
+            }
+
+            // 通知从通知中心移除后按钮不再可用；未能显示的通知保留文件，供操作记录查看，之后由定期清理删除
+            if shown && !temp_files.is_empty() {
+                tokio::time::sleep(expires_in).await;
+                let _ = tokio::task::spawn_blocking(move || crate::temp_files::discard(&temp_files)).await;
+            }
+        };
+        tokio::spawn(task.instrument(tracing::Span::current()));
+    }
+
+    /// 显示一条通知直到成功或转入记录。
+    ///
+    /// # Returns
+    /// 是否显示成功
+    async fn run(&self, capability: &str, device: Option<String>, request: NotificationRequest) -> bool {
+        let mut shutdown = self.shutdown.clone();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match self.show(&request).await {
+                Ok(()) => return true,
+                Err(e) => e,
+            };
+
+            let shutting_down = *shutdown.borrow();
+            let decision = self
+                .policy
+                .lock()
+                .unwrap()
+                .on_failure(attempts, shutting_down, Instant::now());
+            match decision {
+                RetryDecision::Retry(delay) => {
+                    tracing::warn!("Failed to show {} notification (attempt {}), retrying in {:?}: {:?}", capability, attempts, delay, error);
+                    // 关闭时立即结束等待，不再重试，直接转入记录
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = shutdown.wait_for(|stop| *stop) => {
+                            self.divert(capability, device, &request.notification, &error, attempts);
+                            return false;
+                        }
+                    }
+                }
+                RetryDecision::Divert { summary } => {
+                    self.divert(capability, device, &request.notification, &error, attempts);
+                    if let Some(count) = summary {
+                        self.show_summary(count).await;
+                    }
+                    return false;
+                }
+            }
+        }
+    }
+
+    async fn show(&self, request: &NotificationRequest) -> anyhow::Result<()> {
+        let notifier = self.notifier.clone();
+        let notification = request.notification.clone();
+        let on_action = request.on_action.clone();
+        let span = tracing::Span::current();
+        tokio::task::spawn_blocking(move || span.in_scope(|| notifier.show(notification, on_action))).await?
+    }
+
+    /// 放弃显示，把通知转入操作记录。
+    fn divert(&self, capability: &str, device: Option<String>, notification: &Notification, error: &anyhow::Error, attempts: u32) {
+        tracing::error!("Giving up on {} notification after {} attempts: {:?}", capability, attempts, error);
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.audit.record(AuditRecord {
+            item_id: hex::encode(rand::random::<[u8; 8]>()),
+            item_type: capability.to_string(),
+            device,
+            action: "display_failed".to_string(),
+            path: notification.hero_image.clone(),
+            error: Some(format!("{}: {:#}", notification.title, error)),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            captured_at: None,
+            received_at: None,
+            clock_clamped: false,
+        });
+    }
+
+    /// 把抑制期间收到的内容写入操作记录，汇总通知的“查看”按钮按时间段查询这些记录。
+    fn record_missed(&self, capability: &str, device: Option<String>, notification: &Notification) {
+        self.audit.record(AuditRecord {
+            item_id: hex::encode(rand::random::<[u8; 8]>()),
+            item_type: capability.to_string(),
+            device,
+            action: "missed".to_string(),
+            path: notification.hero_image.clone(),
+            error: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            captured_at: None,
+            received_at: None,
+            clock_clamped: false,
+        });
+    }
+
+    /// 抑制结束后汇总告知错过的内容，只尝试一次。
+    ///
+    /// # Arguments
+    /// * `digest` - 错过的内容汇总
+    /// * `history_url` - 按抑制时段筛选的操作记录地址，为 None 时不显示“查看”按钮
+    pub(crate) async fn show_digest(&self, digest: &MissedDigest, history_url: Option<String>) {
+        let mut notification = Notification::new("missed_digest", &format!("免打扰期间收到 {}", digest.describe()));
+        notification.body.extend(digest.titles.iter().cloned());
+        if history_url.is_some() {
+            notification.actions.push(NotificationAction::new("view", "查看"));
+        }
+        let request = NotificationRequest {
+            notification,
+            on_action: Arc::new(move |arguments: &str| {
+                if let (Some(url), "view") = (&history_url, arguments) {
+                    if let Err(e) = crate::handlers::photo::open_file(url) {
+                        tracing::error!("Failed to open missed items: {:?}", e);
+                    }
+                }
+            }),
+        };
+        if let Err(e) = self.show(&request).await {
+            tracing::error!("Failed to show missed items digest: {:?}", e);
+        }
+    }
+
+    /// 告知用户有内容未能弹出通知，只尝试一次。
+    async fn show_summary(&self, count: u32) {
+        let mut notification = Notification::new("display_failed", "部分通知未能显示");
+        notification.body.push(format!("有 {} 条内容未能弹出通知，可在托盘“操作记录”中查看", count));
+        let request = NotificationRequest {
+            notification,
+            on_action: Arc::new(|_: &str| {}),
+        };
+        if let Err(e) = self.show(&request).await {
+            tracing::error!("Failed to show display failure summary: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::MockNotifier;
+
+    /// 使用记录型后端的队列，返回队列、后端与关闭信号的发送端。
+    fn queue() -> (Arc<NotificationQueue>, Arc<MockNotifier>, watch::Sender<bool>) {
+        let notifier = Arc::new(MockNotifier::new());
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let queue = NotificationQueue::new(
+            notifier.clone(),
+            Arc::new(AuditLog::new(None)),
+            shutdown_rx,
+            Arc::new(TimingStats::default()),
+            Arc::new(MissedTracker::default()),
+        );
+        (Arc::new(queue), notifier, shutdown_tx)
+    }
+
+    fn request(title: &str) -> NotificationRequest {
+        NotificationRequest {
+            notification: Notification::new("sms", title),
+            on_action: Arc::new(|_: &str| {}),
+        }
+    }
+
+    /// 等待队列中的通知全部显示或转入记录。
+    async fn drain(queue: &NotificationQueue) {
+        while queue.pending() > 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_until_the_last_attempt() {
+        let mut policy = RetryPolicy::new(4, Duration::from_secs(2), Duration::from_secs(60));
+        let now = Instant::now();
+        let cases = [
+            (1, RetryDecision::Retry(Duration::from_secs(2))),
+            (2, RetryDecision::Retry(Duration::from_secs(4))),
+            (3, RetryDecision::Retry(Duration::from_secs(8))),
+            (4, RetryDecision::Divert { summary: Some(1) }),
+        ];
+        for (attempts, expected) in cases {
+            assert_eq!(policy.on_failure(attempts, false, now), expected, "attempt {}", attempts);
+        }
+    }
+
+    #[test]
+    fn summaries_are_rate_limited() {
+        let mut policy = RetryPolicy::new(1, Duration::from_secs(2), Duration::from_secs(60));
+        let now = Instant::now();
+        assert_eq!(policy.on_failure(1, false, now), RetryDecision::Divert { summary: Some(1) });
+        assert_eq!(policy.on_failure(1, false, now + Duration::from_secs(10)), RetryDecision::Divert { summary: None });
+        assert_eq!(policy.on_failure(1, false, now + Duration::from_secs(20)), RetryDecision::Divert { summary: None });
+        // 间隔过后一次告知之前未汇总的全部条数
+        assert_eq!(policy.on_failure(1, false, now + Duration::from_secs(60)), RetryDecision::Divert { summary: Some(3) });
+    }
+
+    #[test]
+    fn shutdown_diverts_without_retry_or_summary() {
+        let mut policy = RetryPolicy::default();
+        assert_eq!(policy.on_failure(1, true, Instant::now()), RetryDecision::Divert { summary: None });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn transient_failure_is_retried_after_the_backoff() {
+        let (queue, notifier, _shutdown) = queue();
+        notifier.fail_next(1);
+        let start = tokio::time::Instant::now();
+
+        queue.submit("sms".to_string(), None, request("短信"), None);
+        drain(&queue).await;
+
+        assert_eq!(notifier.attempts(), 2);
+        assert_eq!(notifier.shown().len(), 1);
+        assert_eq!(queue.failures(), 0);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= BASE_BACKOFF && elapsed < BASE_BACKOFF * 2, "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn persistent_failure_is_dropped_and_summarized() {
+        let (queue, notifier, _shutdown) = queue();
+        // 最后一次失败之后的汇总通知显示成功
+        notifier.fail_next(MAX_ATTEMPTS as usize);
+        let start = tokio::time::Instant::now();
+
+        queue.submit("sms".to_string(), Some("Pixel".to_string()), request("短信"), None);
+        drain(&queue).await;
+
+        assert_eq!(notifier.attempts(), MAX_ATTEMPTS as usize + 1);
+        assert_eq!(queue.failures(), 1);
+        // 两次退避：2 秒与 4 秒
+        let elapsed = start.elapsed();
+        assert!(elapsed >= BASE_BACKOFF * 3 && elapsed < BASE_BACKOFF * 4, "{:?}", elapsed);
+
+        let shown = notifier.shown();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].tag, "display_failed");
+        let records = queue.audit.recent(10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].action, "display_failed");
+        assert_eq!(records[0].device.as_deref(), Some("Pixel"));
+        assert!(records[0].error.as_deref().unwrap_or_default().starts_with("短信"), "{:?}", records[0].error);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_during_backoff_drops_immediately() {
+        let (queue, notifier, shutdown) = queue();
+        notifier.fail_next(usize::MAX);
+
+        queue.submit("sms".to_string(), None, request("短信"), None);
+        while notifier.attempts() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let start = tokio::time::Instant::now();
+        shutdown.send(true).unwrap();
+        drain(&queue).await;
+
+        assert!(start.elapsed() < BASE_BACKOFF, "{:?}", start.elapsed());
+        assert_eq!(notifier.attempts(), 1);
+        assert_eq!(queue.failures(), 1);
+        assert!(notifier.shown().is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 基于 WinRT Toast 的通知后端。
+ */
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use windows::{
+    core::*,
+    Data::Xml::Dom::XmlDocument,
+    UI::Notifications::{NotificationSetting, ToastNotification, ToastNotificationManager},
+    Foundation::{DateTime, IPropertyValue, IReference, PropertyValue},
+};
+use crate::APP_ID;
+use super::xml::{escape_xml_attr, escape_xml_text};
+use super::{ActionHandler, Notification, Notifier};
+
+/// 每个分组最多保留的通知数。
+const MAX_STORED_PER_GROUP: usize = 10;
+
+/// Toast 最多显示的按钮数。
+const MAX_ACTIONS: usize = 5;
+
+/// 一个分组内按显示顺序排列的通知标签与对象。
+type StoredNotifications = VecDeque<(String, ToastNotification)>;
+
+/// 按分组保存的通知对象。释放对象后按钮回调不再触发。
+pub static NOTIFICATION_STORAGE: OnceLock<Mutex<HashMap<String, StoredNotifications>>> = OnceLock::new();
+
+/// 存储通知对象，用于后续操作。分组内超过上限时释放最早的通知并从通知中心移除，
+/// 避免留下点击后没有反应的通知。
+///
+/// # Arguments
+/// * `group` - 通知分组
+/// * `tag` - 通知的唯一标识符
+/// * `notification` - 要存储的 ToastNotification 对象
+pub fn store_notification(group: &str, tag: &str, notification: ToastNotification) {
+    let storage = NOTIFICATION_STORAGE.get_or_init(|| Mutex::new(HashMap::new()));
+    let Ok(mut map) = storage.lock() else {
+        return;
+    };
+    let stored = map.entry(group.to_string()).or_default();
+    stored.retain(|(existing, _)| existing != tag);
+    stored.push_back((tag.to_string(), notification));
+    while stored.len() > MAX_STORED_PER_GROUP {
+        let Some((evicted, _)) = stored.pop_front() else {
+            break;
+        };
+        let removed = ToastNotificationManager::History()
+            .and_then(|history| history.RemoveGroupedTagWithId(&HSTRING::from(&evicted), &HSTRING::from(group), &HSTRING::from(APP_ID)));
+        match removed {
+            Ok(()) => tracing::debug!("Removed notification {} from group {}", evicted, group),
+            Err(e) => tracing::warn!("Failed to remove notification {} from the action center: {:?}", evicted, e),
+        }
+    }
+}
+
+/// Windows Toast 通知后端。
+pub struct WindowsNotifier;
+
+impl Notifier for WindowsNotifier {
+    fn show(&self, notification: Notification, on_action: ActionHandler) -> anyhow::Result<()> {
+        show_toast(&notification, on_action)?;
+        Ok(())
+    }
+
+    fn is_enabled(&self) -> anyhow::Result<bool> {
+        let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?;
+        let setting = notifier.Setting()?;
+        if setting != NotificationSetting::Enabled {
+            tracing::warn!("Toast notifications are disabled: {:?}", setting);
+        }
+        Ok(setting == NotificationSetting::Enabled)
+    }
+}
+
+/// 根据通知描述构建 Toast XML。
+fn build_xml(notification: &Notification) -> String {
+    let mut texts = format!("<text>{}</text>", escape_xml_text(&notification.title));
+    for line in &notification.body {
+        texts.push_str(&format!("<text>{}</text>", escape_xml_text(line)));
+    }
+
+    let image_xml = match &notification.hero_image {
+        Some(path) => format!(
+            r#"<image placement='hero' src='file:///{}'/>"#,
+            escape_xml_attr(&path.to_string_lossy().replace("\\", "/"))
+        ),
+        None => String::new(),
+    };
+
+    // 超过 5 个按钮的 Toast 无法显示，多出的按钮（通常是排在最后的"忽略"）直接省略，关闭通知即可忽略
+    if notification.actions.len() > MAX_ACTIONS {
+        tracing::warn!(
+            "Toast {} has {} actions, only the first {} are shown",
+            notification.tag,
+            notification.actions.len(),
+            MAX_ACTIONS
+        );
+    }
+    let actions_xml: String = notification
+        .actions
+        .iter()
+        .take(MAX_ACTIONS)
+        .map(|a| format!(
+            r#"<action content='{}' arguments='{}' activationType="foreground"{}/>"#,
+            escape_xml_attr(&a.label),
+            escape_xml_attr(&a.id),
+            a.input
+                .as_ref()
+                .map(|input| format!(" hint-inputId='{}'", escape_xml_attr(input)))
+                .unwrap_or_default()
+        ))
+        .collect();
+    let inputs_xml: String = notification
+        .inputs
+        .iter()
+        .map(|input| format!(
+            r#"<input id='{}' type='text' placeHolderContent='{}'/>"#,
+            escape_xml_attr(&input.id),
+            escape_xml_attr(&input.placeholder)
+        ))
+        .collect();
+
+    let duration = if notification.long_duration { "long" } else { "short" };
+    let audio = if notification.quiet { "<audio silent='true'/>" } else { "" };
+
+    format!(r#"
+        <toast duration="{}" activationType='foreground'>
+        <visual>
+            <binding template='ToastGeneric'>
+                {}
+                {}
+            </binding>
+        </visual>
+        <actions>
+            {}
+            {}
+        </actions>
+        {}
+        </toast>
+    "#, duration, texts, image_xml, inputs_xml, actions_xml, audio)
+}
+
+/// 读取输入框中的内容，输入框为空时系统不传回该项，视为空字符串。
+///
+/// # Arguments
+/// * `args` - 通知激活参数
+/// * `input` - 输入框标识
+fn user_input(args: &windows::UI::Notifications::ToastActivatedEventArgs, input: &str) -> String {
+    let text = args
+        .UserInput()
+        .and_then(|values| values.Lookup(&HSTRING::from(input)))
+        .and_then(|value| value.cast::<IPropertyValue>())
+        .and_then(|value| value.GetString());
+    match text {
+        Ok(text) => text.to_string(),
+        Err(e) => {
+            tracing::debug!("No text in toast input {}: {:?}", input, e);
+            String::new()
+        }
+    }
+}
+
+/// 显示 Toast 通知并注册按钮回调。
+///
+/// # Arguments
+/// * `notification` - 通知描述
+/// * `on_action` - 按钮点击回调
+///
+/// # Returns
+/// 操作结果 Result
+fn show_toast(notification: &Notification, on_action: ActionHandler) -> windows::core::Result<()> {
+    let toast_xml = XmlDocument::new()?;
+    toast_xml.LoadXml(&HSTRING::from(build_xml(notification)))?;
+
+    let toast = ToastNotification::CreateToastNotification(&toast_xml)?;
+
+    toast.SetTag(&HSTRING::from(notification.tag.as_str()))?;
+    toast.SetGroup(&HSTRING::from(notification.group.as_str()))?;
+    if notification.quiet {
+        toast.SetSuppressPopup(true)?;
+    }
+
+    let now_unix_millis = chrono::Utc::now().timestamp_millis();
+    let expiration_millis = now_unix_millis + notification.expires_in.as_millis() as i64;
+    let expiration_ticks = (expiration_millis * 10_000) + 116444736000000000;
+
+    let expiry_time = DateTime { UniversalTime: expiration_ticks };
+    let expiry_inspectable = PropertyValue::CreateDateTime(expiry_time)?;
+    let expiry_reference: IReference<DateTime> = expiry_inspectable.cast()?;
+    toast.SetExpirationTime(&expiry_reference)?;
+
+    // 提交输入框的按钮标识与对应的输入框
+    let submits: HashMap<String, String> = notification
+        .actions
+        .iter()
+        .filter_map(|action| Some((action.id.clone(), action.input.clone()?)))
+        .collect();
+    toast.Activated(&windows::Foundation::TypedEventHandler::new(move |_sender, args: &Option<IInspectable>| {
+        if let Some(args) = args {
+            let args: windows::UI::Notifications::ToastActivatedEventArgs = args.cast()?;
+            let arguments = args.Arguments()?.to_string();
+            match submits.get(&arguments) {
+                Some(input) => on_action(&super::join_input(&arguments, &user_input(&args, input))),
+                None => on_action(&arguments),
+            }
+        }
+        Ok(())
+    }))?;
+
+    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?;
+    notifier.Show(&toast)?;
+
+    // 使用全局存储管理生命周期
+    store_notification(&notification.group, &notification.tag, toast);
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * Toast XML 的转义。
+ * 与平台无关，放在 WinRT 后端之外以便在所有平台上测试。
+ */
+
+/// XML 1.0 允许的字符。其余控制字符会让 `LoadXml` 失败，通知因此无法显示。
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// 转义 Toast XML 元素内容中的特殊字符，并去掉 XML 1.0 不允许的控制字符。
+/// 转义 `>` 后原文中的 `]]>` 也不会被当作 CDATA 结束标记。
+pub(crate) fn escape_xml_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars().filter(|&c| is_xml_char(c)) {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 转义 Toast XML 属性值中的特殊字符，单引号与双引号都会转义，属性使用哪种引号都安全。
+pub(crate) fn escape_xml_attr(text: &str) -> String {
+    escape_xml_text(text).replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_text_content() {
+        let cases = [
+            ("a & b", "a &amp; b"),
+            ("<script>", "&lt;script&gt;"),
+            ("]]>", "]]&gt;"),
+            ("&amp;", "&amp;amp;"),
+            // 元素内容中的引号无需转义
+            (r#"他说"好的"，It's"#, r#"他说"好的"，It's"#),
+            ("验证码 482916 😀👍🏽", "验证码 482916 😀👍🏽"),
+            ("中文・日本語・한국어", "中文・日本語・한국어"),
+            ("第一行\n第二行\r\n\t缩进", "第一行\n第二行\r\n\t缩进"),
+            // XML 1.0 不允许的字符被去掉
+            ("a\u{0}b\u{1}c\u{8}d\u{B}e\u{C}f\u{1F}g", "abcdefg"),
+            ("\u{FFFE}\u{FFFF}x", "x"),
+            ("", ""),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(escape_xml_text(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn escapes_attribute_values() {
+        let cases = [
+            (r#"say "hi""#, "say &quot;hi&quot;"),
+            ("it's", "it&apos;s"),
+            ("a&b<c>d", "a&amp;b&lt;c&gt;d"),
+            ("C:/照片/it's 😀.png", "C:/照片/it&apos;s 😀.png"),
+            ("x\u{7}y", "xy"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(escape_xml_attr(text), expected, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 文字识别模块。
+ * 收到文字截图时通过 Windows.Media.Ocr 识别其中的文字，通知中提供"复制文字"。
+ * 识别语言默认跟随系统，可通过配置指定；识别失败或超时时静默跳过，不影响原有的通知。
+ * 其他平台与未启用 `notifications` 特性的构建不做识别。
+ */
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 识别的时限，超过后放弃。
+pub const RECOGNIZE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 识别出的非空白字符少于该数量时视为没有文字，普通照片上零星的误识别不会产生按钮。
+const MIN_TEXT_CHARS: usize = 5;
+
+/// 识别图片中的文字。
+///
+/// # Arguments
+/// * `path` - 图片路径
+/// * `language` - 识别语言的 BCP-47 标签，例如 `zh-Hans-CN`，None 时使用系统语言
+///
+/// # Returns
+/// 按行排列的文字，文字过少时为 None
+pub fn recognize_text(path: &Path, language: Option<&str>) -> anyhow::Result<Option<String>> {
+    let text = recognize(path, language)?;
+    let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+    Ok((chars >= MIN_TEXT_CHARS).then_some(text))
+}
+
+/// 在后台线程识别文字，超过时限或失败时返回 None。
+///
+/// # Arguments
+/// * `path` - 图片路径
+/// * `language` - 识别语言，None 时使用系统语言
+pub(crate) async fn find_text(path: PathBuf, language: Option<String>) -> Option<String> {
+    if !cfg!(all(windows, feature = "notifications")) {
+        return None;
+    }
+    let recognized = tokio::time::timeout(
+        RECOGNIZE_TIMEOUT,
+        tokio::task::spawn_blocking(move || recognize_text(&path, language.as_deref())),
+    )
+    .await;
+    match recognized {
+        Ok(Ok(Ok(text))) => {
+            if let Some(text) = &text {
+                tracing::debug!("OCR recognized {} chars: {:?}", text.chars().count(), text);
+            }
+            text
+        }
+        Ok(Ok(Err(e))) => {
+            tracing::debug!("OCR skipped: {:#}", e);
+            None
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("OCR task failed: {:?}", e);
+            None
+        }
+        Err(_) => {
+            tracing::debug!("OCR timed out after {:?}", RECOGNIZE_TIMEOUT);
+            None
+        }
+    }
+}
+
+/// 调用 Windows.Media.Ocr 识别文字。
+#[cfg(all(windows, feature = "notifications"))]
+fn recognize(path: &Path, language: Option<&str>) -> anyhow::Result<String> {
+    use image::ImageReader;
+    use windows::core::HSTRING;
+    use windows::Globalization::Language;
+    use windows::Graphics::Imaging::{BitmapPixelFormat, SoftwareBitmap};
+    use windows::Media::Ocr::OcrEngine;
+    use windows::Storage::Streams::DataWriter;
+
+    let engine = match language {
+        Some(tag) => {
+            let language = Language::CreateLanguage(&HSTRING::from(tag))?;
+            if !OcrEngine::IsLanguageSupported(&language)? {
+                anyhow::bail!("OCR language {} is not installed", tag);
+            }
+            OcrEngine::TryCreateFromLanguage(&language)?
+        }
+        None => OcrEngine::TryCreateFromUserProfileLanguages()?,
+    };
+
+    let max = OcrEngine::MaxImageDimension()?;
+    let mut image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    if image.width() > max || image.height() > max {
+        image = image.thumbnail(max, max);
+    }
+    let mut pixels = image.to_rgba8();
+    for pixel in pixels.pixels_mut() {
+        pixel.0.swap(0, 2);
+    }
+    let writer = DataWriter::new()?;
+    writer.WriteBytes(pixels.as_raw())?;
+    let bitmap = SoftwareBitmap::CreateCopyFromBuffer(
+        &writer.DetachBuffer()?,
+        BitmapPixelFormat::Bgra8,
+        pixels.width() as i32,
+        pixels.height() as i32,
+    )?;
+
+    let result = engine.RecognizeAsync(&bitmap)?.get()?;
+    let mut lines = Vec::new();
+    for line in result.Lines()? {
+        let words = line
+            .Words()?
+            .into_iter()
+            .map(|word| word.Text().map(|text| text.to_string_lossy()))
+            .collect::<windows::core::Result<Vec<_>>>()?;
+        lines.push(join_words(&words));
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(not(all(windows, feature = "notifications")))]
+fn recognize(_path: &Path, _language: Option<&str>) -> anyhow::Result<String> {
+    anyhow::bail!("OCR is only available on Windows")
+}
+
+/// 拼接一行中的单词。OCR 将中日韩文字逐字拆成单词，相邻的两个单词都是中日韩文字时不插入空格。
+#[cfg(all(windows, feature = "notifications"))]
+fn join_words(words: &[String]) -> String {
+    let mut line = String::new();
+    for word in words {
+        let joined = line.chars().last().is_some_and(is_cjk) && word.chars().next().is_some_and(is_cjk);
+        if !line.is_empty() && !joined {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    line
+}
+
+/// 是否为中日韩文字或全角标点。
+#[cfg(all(windows, feature = "notifications"))]
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303F}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF00}'..='\u{FFEF}')
+}
@@ -0,0 +1,402 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 配对模块。
+ * 托盘生成带一次性令牌的二维码，手机扫码后在 `/pair/qr` 换取长期设备令牌；
+ * 也可在托盘查看 6 位 PIN，由手机在 `/pair` 提交，该入口受防爆破限流保护。
+ * 手机也可以在 `/pair/request` 发起配对请求，由电脑上的通知确认，见 `request` 模块。
+ * 没有安装应用的访客可由托盘临时开放浏览器上传，凭短期访客令牌访问。
+ * 配对成功的设备登记在 `devices` 模块的登记表中。
+ */
+use base64::Engine;
+use rand::{Rng, RngCore};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod request;
+mod throttle;
+
+pub use self::request::PAIR_REQUEST_TTL;
+pub(crate) use self::request::{PairRequestOutcome, PairRequestStatus};
+pub use self::throttle::{PairingThrottle, ThrottleConfig, Verdict};
+pub use fastsync_core::pairing::PairingUri;
+pub use crate::devices::PairedDevice;
+use crate::devices::DeviceRegistry;
+
+/// 一次性令牌与配对 PIN 的有效期。
+pub const ONE_TIME_TOKEN_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// 访客令牌的有效期。
+pub const GUEST_TOKEN_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 访客令牌对应的设备标识，可在内容策略中单独限制访客。
+pub const GUEST_DEVICE_ID: &str = "guest";
+
+/// 校验表情候选，托盘与网页显示同一组表情，供访客确认连上的是哪台电脑。
+const VERIFICATION_EMOJI: [&str; 32] = [
+    "🐶", "🐱", "🐭", "🐰", "🦊", "🐻", "🐼", "🐨", "🐯", "🦁", "🐮", "🐷", "🐸", "🐵", "🐔", "🐧",
+    "🍎", "🍊", "🍋", "🍉", "🍇", "🍓", "🍒", "🍑", "🚗", "🚀", "⛵", "🚲", "⚽", "🎸", "🎈", "🌙",
+];
+
+/// 保留的最近配对尝试条数。
+const MAX_ATTEMPTS: usize = 50;
+
+/// 一次性令牌兑换失败的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedeemError {
+    /// 令牌不存在
+    Invalid,
+    /// 令牌已过期
+    Expired,
+    /// 令牌已被兑换过
+    AlreadyUsed,
+}
+
+impl RedeemError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RedeemError::Invalid => "invalid",
+            RedeemError::Expired => "expired",
+            RedeemError::AlreadyUsed => "used",
+        }
+    }
+}
+
+/// 一次配对尝试的记录。
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingAttempt {
+    pub ip: IpAddr,
+    /// paired / invalid_pin / locked_out / circuit_open，确认式配对为 accepted / rejected / timed_out
+    pub outcome: &'static str,
+    /// 尝试时间（毫秒时间戳）
+    pub timestamp: i64,
+}
+
+/// 临时开放的浏览器上传权限。
+#[derive(Debug, Clone)]
+pub struct GuestAccess {
+    /// 访客令牌，网页以 Bearer 令牌提交
+    pub token: String,
+    /// 校验表情
+    pub emoji: String,
+    /// 有效期
+    pub expires_in: Duration,
+}
+
+/// PIN 配对的结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PinOutcome {
+    /// 配对成功，附带设备令牌
+    Paired(String),
+    /// PIN 错误或已过期，`breaker_tripped` 表示本次失败触发了全局熔断
+    InvalidPin { breaker_tripped: bool },
+    /// 该 IP 被锁定
+    LockedOut(Duration),
+    /// 全局熔断中
+    CircuitOpen(Duration),
+}
+
+/// 设备令牌的校验结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TokenStatus {
+    /// 有效，附带设备标识
+    Valid(String),
+    /// 设备已被解除配对
+    Revoked,
+    /// 访客令牌已过期
+    Expired,
+    /// 从未签发过的令牌
+    Unknown,
+}
+
+/// 配对令牌存储。
+#[derive(Default)]
+pub(crate) struct PairingStore {
+    inner: Mutex<PairingInner>,
+}
+
+#[derive(Default)]
+struct PairingInner {
+    /// 已配对设备登记表
+    devices: Arc<DeviceRegistry>,
+    /// 未兑换的一次性令牌及其签发时间
+    pending: HashMap<String, Instant>,
+    /// 已兑换的一次性令牌，用于区分重复使用与无效令牌
+    redeemed: HashSet<String>,
+    /// 当前有效的配对 PIN 及其签发时间
+    pin: Option<(String, Instant)>,
+    throttle: PairingThrottle,
+    /// 最近的配对尝试，最新的在前
+    attempts: VecDeque<PairingAttempt>,
+    /// 访客令牌及其签发时间
+    guests: HashMap<String, Instant>,
+    /// 等待用户确认的配对请求
+    requests: HashMap<String, request::PairRequest>,
+}
+
+impl PairingStore {
+    /// 配对成功的设备登记到 `devices` 中。
+    pub(crate) fn new(devices: Arc<DeviceRegistry>) -> Self {
+        Self {
+            inner: Mutex::new(PairingInner {
+                devices,
+                ..PairingInner::default()
+            }),
+        }
+    }
+
+    /// 签发一次性令牌，同时清理已过期的令牌。
+    pub(crate) fn issue_one_time(&self) -> String {
+        let token = random_token(16);
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .pending
+            .retain(|_, issued| now.duration_since(*issued) < ONE_TIME_TOKEN_TTL);
+        inner.pending.insert(token.clone(), now);
+        token
+    }
+
+    /// 兑换一次性令牌，成功时返回新签发的设备令牌。
+    ///
+    /// # Arguments
+    /// * `token` - 一次性令牌
+    /// * `device_name` - 手机上报的设备名称
+    pub(crate) fn redeem(&self, token: &str, device_name: &str) -> Result<String, RedeemError> {
+        self.redeem_at(token, device_name, Instant::now())
+    }
+
+    fn redeem_at(&self, token: &str, device_name: &str, now: Instant) -> Result<String, RedeemError> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let Some(issued) = inner.pending.remove(token) else {
+            return Err(if inner.redeemed.contains(token) {
+                RedeemError::AlreadyUsed
+            } else {
+                RedeemError::Invalid
+            });
+        };
+        if now.duration_since(issued) >= ONE_TIME_TOKEN_TTL {
+            return Err(RedeemError::Expired);
+        }
+
+        inner.redeemed.insert(token.to_string());
+        Ok(inner.add_device(device_name))
+    }
+
+    /// 生成新的 6 位配对 PIN，旧 PIN 随即失效。
+    pub(crate) fn issue_pin(&self) -> String {
+        let pin = format!("{:06}", rand::thread_rng().gen_range(0..1_000_000));
+        self.inner.lock().unwrap().pin = Some((pin.clone(), Instant::now()));
+        pin
+    }
+
+    /// 校验 PIN 并配对。限流判断、比对与记录在同一把锁内完成，并发尝试无法绕过计数。
+    ///
+    /// # Arguments
+    /// * `ip` - 请求来源
+    /// * `pin` - 手机提交的 PIN
+    /// * `device_name` - 手机上报的设备名称
+    pub(crate) fn pair_with_pin(&self, ip: IpAddr, pin: &str, device_name: &str) -> PinOutcome {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        let outcome = match inner.throttle.check(ip, now) {
+            Verdict::LockedOut { retry_after } => PinOutcome::LockedOut(retry_after),
+            Verdict::CircuitOpen { retry_after } => PinOutcome::CircuitOpen(retry_after),
+            Verdict::Allowed => {
+                let matches = match &inner.pin {
+                    Some((expected, issued)) => {
+                        // 先做完比对再判断有效期，耗时与 PIN 内容无关
+                        constant_time_eq(expected.as_bytes(), pin.as_bytes())
+                            && now.duration_since(*issued) < ONE_TIME_TOKEN_TTL
+                    }
+                    None => false,
+                };
+
+                if matches {
+                    inner.pin = None;
+                    inner.throttle.record_success(ip);
+                    PinOutcome::Paired(inner.add_device(device_name))
+                } else {
+                    let breaker_tripped = inner.throttle.record_failure(ip, now);
+                    if breaker_tripped {
+                        inner.pin = None;
+                    }
+                    PinOutcome::InvalidPin { breaker_tripped }
+                }
+            }
+        };
+
+        inner.record_attempt(ip, outcome.as_str());
+        outcome
+    }
+
+    /// 最近的配对尝试，最新的在前。
+    pub(crate) fn attempts(&self) -> Vec<PairingAttempt> {
+        self.inner.lock().unwrap().attempts.iter().cloned().collect()
+    }
+
+    /// 签发访客令牌，同时清理已过期的访客令牌。
+    pub(crate) fn issue_guest(&self) -> GuestAccess {
+        let token = random_token(16);
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .guests
+            .retain(|_, issued| now.duration_since(*issued) < GUEST_TOKEN_TTL);
+        inner.guests.insert(token.clone(), now);
+
+        GuestAccess {
+            emoji: verification_emoji(&token),
+            token,
+            expires_in: GUEST_TOKEN_TTL,
+        }
+    }
+
+    /// 有效访客令牌的剩余有效期。
+    pub(crate) fn guest_remaining(&self, token: &str) -> Option<Duration> {
+        self.guest_remaining_at(token, Instant::now())
+    }
+
+    /// 以 `now` 为当前时刻计算访客令牌的剩余有效期。
+    fn guest_remaining_at(&self, token: &str, now: Instant) -> Option<Duration> {
+        let inner = self.inner.lock().unwrap();
+        let issued = inner.guests.get(token)?;
+        GUEST_TOKEN_TTL
+            .checked_sub(now.saturating_duration_since(*issued))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// 校验设备令牌，每次请求都读取当前状态，解除配对立即生效。
+    pub(crate) fn authenticate(&self, token: &str) -> TokenStatus {
+        let inner = self.inner.lock().unwrap();
+        if let Some(device) = inner.devices.authenticate(token) {
+            TokenStatus::Valid(device.id)
+        } else if let Some(issued) = inner.guests.get(token) {
+            if issued.elapsed() < GUEST_TOKEN_TTL {
+                TokenStatus::Valid(GUEST_DEVICE_ID.to_string())
+            } else {
+                TokenStatus::Expired
+            }
+        } else if inner.devices.is_revoked(token) {
+            TokenStatus::Revoked
+        } else {
+            TokenStatus::Unknown
+        }
+    }
+}
+
+impl PairingInner {
+    /// 签发设备令牌并登记设备。
+    fn add_device(&mut self, device_name: &str) -> String {
+        self.devices.add(device_name)
+    }
+
+    /// 记录一次配对尝试，只保留最近的若干条。
+    fn record_attempt(&mut self, ip: IpAddr, outcome: &'static str) {
+        self.attempts.push_front(PairingAttempt {
+            ip,
+            outcome,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        });
+        self.attempts.truncate(MAX_ATTEMPTS);
+    }
+}
+
+impl PinOutcome {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PinOutcome::Paired(_) => "paired",
+            PinOutcome::InvalidPin { .. } => "invalid_pin",
+            PinOutcome::LockedOut(_) => "locked_out",
+            PinOutcome::CircuitOpen(_) => "circuit_open",
+        }
+    }
+}
+
+/// 与内容无关的等长比较，避免通过响应时间逐位猜测 PIN。
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 由令牌推导出 3 个校验表情。
+pub fn verification_emoji(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .take(3)
+        .map(|byte| VERIFICATION_EMOJI[*byte as usize % VERIFICATION_EMOJI.len()])
+        .collect()
+}
+
+/// 生成 URL 安全的随机令牌。
+///
+/// # Arguments
+/// * `len` - 随机字节数
+pub(crate) fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_time_token_redeems_once() {
+        let store = PairingStore::default();
+        let token = store.issue_one_time();
+
+        let device_token = store.redeem(&token, "Pixel").unwrap();
+        assert!(matches!(store.authenticate(&device_token), TokenStatus::Valid(_)));
+        assert_eq!(store.redeem(&token, "Pixel"), Err(RedeemError::AlreadyUsed));
+        assert_eq!(store.redeem("never-issued", "Pixel"), Err(RedeemError::Invalid));
+    }
+
+    #[test]
+    fn expired_one_time_token_is_rejected() {
+        let store = PairingStore::default();
+        let token = store.issue_one_time();
+        let now = Instant::now();
+
+        assert_eq!(
+            store.redeem_at(&token, "Pixel", now + ONE_TIME_TOKEN_TTL),
+            Err(RedeemError::Expired)
+        );
+        // 过期的令牌随即作废，不会在之后变为可用
+        assert_eq!(store.redeem_at(&token, "Pixel", now), Err(RedeemError::Invalid));
+    }
+
+    #[test]
+    fn token_just_inside_the_ttl_is_accepted() {
+        let store = PairingStore::default();
+        let token = store.issue_one_time();
+        let almost = Instant::now() + ONE_TIME_TOKEN_TTL - Duration::from_secs(1);
+        assert!(store.redeem_at(&token, "Pixel", almost).is_ok());
+    }
+
+    #[test]
+    fn guest_token_counts_down_and_expires() {
+        let store = PairingStore::default();
+        let access = store.issue_guest();
+        let now = Instant::now();
+
+        assert_eq!(access.emoji, verification_emoji(&access.token));
+        let remaining = store.guest_remaining_at(&access.token, now).unwrap();
+        assert!(remaining <= GUEST_TOKEN_TTL && remaining > GUEST_TOKEN_TTL - Duration::from_secs(5));
+        let later = store.guest_remaining_at(&access.token, now + Duration::from_secs(60)).unwrap();
+        assert!(later < remaining);
+        assert_eq!(store.guest_remaining_at(&access.token, now + GUEST_TOKEN_TTL), None);
+        assert_eq!(store.guest_remaining("never-issued"), None);
+        assert!(matches!(store.authenticate(&access.token), TokenStatus::Valid(id) if id == GUEST_DEVICE_ID));
+    }
+}
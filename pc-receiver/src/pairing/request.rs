@@ -0,0 +1,217 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 由电脑确认的配对请求。
+ * 手机提交设备名称后，电脑弹出带 4 位 PIN 的通知，手机同时显示该 PIN 供用户核对；
+ * 用户在通知中接受后签发设备令牌，手机轮询请求状态时取走。
+ * 请求 60 秒内未处理即失效，被拒绝或超时的请求计入该 IP 的配对失败次数。
+ */
+use rand::Rng;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use super::{random_token, PairingInner, PairingStore, Verdict};
+
+/// 配对请求等待确认的时长。
+pub const PAIR_REQUEST_TTL: Duration = Duration::from_secs(60);
+
+/// 同时等待确认的配对请求上限，每个 IP 同时只能有一个。
+pub const MAX_PENDING_REQUESTS: usize = 3;
+
+/// 一个配对请求。
+pub(super) struct PairRequest {
+    /// 发起请求的地址，只有同一地址可以查询状态
+    ip: IpAddr,
+    device_name: String,
+    created: Instant,
+    state: RequestState,
+}
+
+enum RequestState {
+    Pending,
+    /// 已接受，附带尚未被手机取走的设备令牌
+    Accepted(String),
+    Rejected,
+}
+
+/// 发起配对请求的结果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PairRequestOutcome {
+    /// 已登记，等待用户确认
+    Created { id: String, pin: String },
+    /// 该 IP 已有等待中的请求，或等待中的请求已达上限
+    Busy,
+    /// 该 IP 被锁定
+    LockedOut(Duration),
+    /// 全局熔断中
+    CircuitOpen(Duration),
+}
+
+/// 配对请求的当前状态。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PairRequestStatus {
+    /// 等待确认，附带剩余时间
+    Pending(Duration),
+    /// 已接受，附带设备令牌与设备名称，令牌只返回一次
+    Accepted { device_token: String, device_name: String },
+    Rejected,
+    Expired,
+    /// 请求不存在、已取走结果或来自其他地址
+    Unknown,
+}
+
+impl PairingStore {
+    /// 登记配对请求并生成 4 位 PIN。
+    ///
+    /// # Arguments
+    /// * `ip` - 请求来源
+    /// * `device_name` - 手机上报的设备名称
+    pub(crate) fn request_pairing(&self, ip: IpAddr, device_name: &str) -> PairRequestOutcome {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        inner.expire_requests(now);
+
+        let outcome = match inner.throttle.check(ip, now) {
+            Verdict::LockedOut { retry_after } => PairRequestOutcome::LockedOut(retry_after),
+            Verdict::CircuitOpen { retry_after } => PairRequestOutcome::CircuitOpen(retry_after),
+            Verdict::Allowed => {
+                let pending: Vec<IpAddr> = inner
+                    .requests
+                    .values()
+                    .filter(|request| matches!(request.state, RequestState::Pending))
+                    .map(|request| request.ip)
+                    .collect();
+                if pending.len() >= MAX_PENDING_REQUESTS || pending.contains(&ip) {
+                    PairRequestOutcome::Busy
+                } else {
+                    let id = random_token(16);
+                    let pin = format!("{:04}", rand::thread_rng().gen_range(0..10_000));
+                    inner.requests.insert(
+                        id.clone(),
+                        PairRequest {
+                            ip,
+                            device_name: device_name.to_string(),
+                            created: now,
+                            state: RequestState::Pending,
+                        },
+                    );
+                    PairRequestOutcome::Created { id, pin }
+                }
+            }
+        };
+
+        match &outcome {
+            PairRequestOutcome::LockedOut(_) => inner.record_attempt(ip, "locked_out"),
+            PairRequestOutcome::CircuitOpen(_) => inner.record_attempt(ip, "circuit_open"),
+            _ => {}
+        }
+        outcome
+    }
+
+    /// 用户在通知中接受或拒绝配对请求。
+    ///
+    /// # Arguments
+    /// * `id` - 请求标识
+    /// * `accept` - 是否接受
+    ///
+    /// # Returns
+    /// 请求仍在等待确认时返回设备名称，已失效时返回 None
+    pub(crate) fn decide_request(&self, id: &str, accept: bool) -> Option<String> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        inner.expire_requests(now);
+
+        let request = inner.requests.get(id)?;
+        if !matches!(request.state, RequestState::Pending) {
+            return None;
+        }
+        let (ip, device_name) = (request.ip, request.device_name.clone());
+
+        let state = if accept {
+            inner.throttle.record_success(ip);
+            inner.record_attempt(ip, "accepted");
+            RequestState::Accepted(inner.add_device(&device_name))
+        } else {
+            inner.throttle.record_failure(ip, now);
+            inner.record_attempt(ip, "rejected");
+            RequestState::Rejected
+        };
+        if let Some(request) = inner.requests.get_mut(id) {
+            request.state = state;
+        }
+        Some(device_name)
+    }
+
+    /// 查询配对请求的状态，接受、拒绝与超时的结果返回一次后即移除。
+    ///
+    /// # Arguments
+    /// * `id` - 请求标识
+    /// * `ip` - 查询来源，须与发起请求的地址一致
+    pub(crate) fn request_status(&self, id: &str, ip: IpAddr) -> PairRequestStatus {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+
+        let Some(request) = inner.requests.get(id).filter(|request| request.ip == ip) else {
+            return PairRequestStatus::Unknown;
+        };
+        let age = now.duration_since(request.created);
+        if let RequestState::Pending = request.state {
+            if age < PAIR_REQUEST_TTL {
+                return PairRequestStatus::Pending(PAIR_REQUEST_TTL - age);
+            }
+            inner.requests.remove(id);
+            inner.throttle.record_failure(ip, now);
+            inner.record_attempt(ip, "timed_out");
+            return PairRequestStatus::Expired;
+        }
+
+        let Some(request) = inner.requests.remove(id) else {
+            return PairRequestStatus::Unknown;
+        };
+        match request.state {
+            RequestState::Accepted(device_token) => PairRequestStatus::Accepted {
+                device_token,
+                device_name: request.device_name,
+            },
+            RequestState::Rejected => PairRequestStatus::Rejected,
+            RequestState::Pending => PairRequestStatus::Unknown,
+        }
+    }
+}
+
+impl PairingInner {
+    /// 清理超时的请求。等待中的请求计为一次失败；已接受但手机一直没有取走令牌的，撤销刚登记的设备。
+    fn expire_requests(&mut self, now: Instant) {
+        let expired: Vec<String> = self
+            .requests
+            .iter()
+            .filter(|(_, request)| {
+                let age = now.duration_since(request.created);
+                match request.state {
+                    RequestState::Pending => age >= PAIR_REQUEST_TTL,
+                    // 接受后留出一个有效期供手机取走结果
+                    RequestState::Accepted(_) | RequestState::Rejected => age >= PAIR_REQUEST_TTL * 2,
+                }
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in expired {
+            let Some(request) = self.requests.remove(&id) else {
+                continue;
+            };
+            match request.state {
+                RequestState::Pending => {
+                    self.throttle.record_failure(request.ip, now);
+                    self.record_attempt(request.ip, "timed_out");
+                }
+                RequestState::Accepted(device_token) => {
+                    if self.devices.discard(&device_token).is_some() {
+                        tracing::warn!("Pairing result for {} was never collected, device removed", request.device_name);
+                    }
+                }
+                RequestState::Rejected => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,337 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * PIN 配对的防爆破状态机。
+ * 所有方法都显式接收当前时间，不读取系统时钟，便于推演各种时间序列。
+ */
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// 同时跟踪的 IP 数上限，超过后先丢弃已过衰减期的记录，仍超过时丢弃最早结束锁定的。
+const MAX_TRACKED: usize = 1024;
+
+/// 限流参数。
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleConfig {
+    /// 每个 IP 在开始锁定前允许的失败次数
+    pub free_attempts: u32,
+    /// 首次锁定时长，此后每多失败一次翻倍
+    pub base_lockout: Duration,
+    /// 单个 IP 的最长锁定时长
+    pub max_lockout: Duration,
+    /// 距最近一次失败超过该时长后清零该 IP 的失败计数
+    pub decay: Duration,
+    /// 统计窗口内全局失败次数达到该值时熔断
+    pub breaker_threshold: usize,
+    /// 全局失败的统计窗口
+    pub breaker_window: Duration,
+    /// 熔断后暂停配对的时长
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            base_lockout: Duration::from_secs(5),
+            max_lockout: Duration::from_secs(60 * 60),
+            decay: Duration::from_secs(30 * 60),
+            breaker_threshold: 20,
+            breaker_window: Duration::from_secs(15 * 60),
+            breaker_cooldown: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// 是否允许本次尝试。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    /// 该 IP 被锁定
+    LockedOut { retry_after: Duration },
+    /// 全局熔断，所有来源暂停配对
+    CircuitOpen { retry_after: Duration },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IpState {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// 配对尝试限流器。
+#[derive(Debug)]
+pub struct PairingThrottle {
+    config: ThrottleConfig,
+    per_ip: HashMap<IpAddr, IpState>,
+    recent_failures: VecDeque<Instant>,
+    open_until: Option<Instant>,
+}
+
+impl Default for PairingThrottle {
+    fn default() -> Self {
+        Self::new(ThrottleConfig::default())
+    }
+}
+
+impl PairingThrottle {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            per_ip: HashMap::new(),
+            recent_failures: VecDeque::new(),
+            open_until: None,
+        }
+    }
+
+    /// 判断来自 `ip` 的尝试是否允许进行。
+    pub fn check(&mut self, ip: IpAddr, now: Instant) -> Verdict {
+        if let Some(until) = self.open_until {
+            if now < until {
+                return Verdict::CircuitOpen { retry_after: until - now };
+            }
+            self.open_until = None;
+        }
+
+        self.decay(ip, now);
+        match self.per_ip.get(&ip).and_then(|state| state.locked_until) {
+            Some(until) if now < until => Verdict::LockedOut { retry_after: until - now },
+            _ => Verdict::Allowed,
+        }
+    }
+
+    /// 记录一次失败。
+    ///
+    /// # Returns
+    /// 本次失败是否触发了全局熔断
+    pub fn record_failure(&mut self, ip: IpAddr, now: Instant) -> bool {
+        self.decay(ip, now);
+        let config = self.config;
+        if !self.per_ip.contains_key(&ip) && self.per_ip.len() >= MAX_TRACKED {
+            self.evict(now);
+        }
+
+        let state = self.per_ip.entry(ip).or_insert(IpState {
+            failures: 0,
+            last_failure: now,
+            locked_until: None,
+        });
+        state.failures += 1;
+        state.last_failure = now;
+        if state.failures >= config.free_attempts {
+            let exponent = (state.failures - config.free_attempts).min(16);
+            let lockout = config
+                .base_lockout
+                .saturating_mul(1 << exponent)
+                .min(config.max_lockout);
+            state.locked_until = Some(now + lockout);
+        }
+
+        while let Some(oldest) = self.recent_failures.front() {
+            if now.duration_since(*oldest) < config.breaker_window {
+                break;
+            }
+            self.recent_failures.pop_front();
+        }
+        self.recent_failures.push_back(now);
+
+        if self.recent_failures.len() >= config.breaker_threshold {
+            self.recent_failures.clear();
+            self.open_until = Some(now + config.breaker_cooldown);
+            return true;
+        }
+        false
+    }
+
+    /// 记录一次成功，清零该 IP 的失败计数。
+    pub fn record_success(&mut self, ip: IpAddr) {
+        self.per_ip.remove(&ip);
+    }
+
+    /// 长时间没有新的失败时清零计数。
+    fn decay(&mut self, ip: IpAddr, now: Instant) {
+        if self.per_ip.get(&ip).is_some_and(|state| expired(self.config, state, now)) {
+            self.per_ip.remove(&ip);
+        }
+    }
+
+    /// 为新的 IP 腾出位置。
+    fn evict(&mut self, now: Instant) {
+        let config = self.config;
+        self.per_ip.retain(|_, state| !expired(config, state, now));
+        if self.per_ip.len() >= MAX_TRACKED {
+            let oldest = self
+                .per_ip
+                .iter()
+                .min_by_key(|(_, state)| (state.locked_until.unwrap_or(state.last_failure), state.last_failure))
+                .map(|(ip, _)| *ip);
+            if let Some(oldest) = oldest {
+                self.per_ip.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// 已过衰减期且不在锁定中的记录可以丢弃。
+fn expired(config: ThrottleConfig, state: &IpState, now: Instant) -> bool {
+    now.duration_since(state.last_failure) >= config.decay && state.locked_until.is_none_or(|until| now >= until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([192, 168, 1, last])
+    }
+
+    fn config() -> ThrottleConfig {
+        ThrottleConfig {
+            free_attempts: 3,
+            base_lockout: 5 * SECOND,
+            max_lockout: 60 * SECOND,
+            decay: 600 * SECOND,
+            breaker_threshold: 10,
+            breaker_window: 60 * SECOND,
+            breaker_cooldown: 300 * SECOND,
+        }
+    }
+
+    fn locked(retry_after: Duration) -> Verdict {
+        Verdict::LockedOut { retry_after }
+    }
+
+    #[test]
+    fn free_attempts_do_not_lock() {
+        let mut throttle = PairingThrottle::new(config());
+        let start = Instant::now();
+        for i in 0..2 {
+            assert!(!throttle.record_failure(ip(1), start + i * SECOND));
+            assert_eq!(throttle.check(ip(1), start + i * SECOND), Verdict::Allowed, "failure {}", i + 1);
+        }
+        throttle.record_failure(ip(1), start + 2 * SECOND);
+        assert_eq!(throttle.check(ip(1), start + 2 * SECOND), locked(5 * SECOND));
+        // 其他来源不受影响
+        assert_eq!(throttle.check(ip(2), start + 2 * SECOND), Verdict::Allowed);
+    }
+
+    #[test]
+    fn lockout_doubles_up_to_the_cap() {
+        let mut throttle = PairingThrottle::new(config());
+        let mut now = Instant::now();
+        for _ in 0..2 {
+            throttle.record_failure(ip(1), now);
+        }
+
+        let cases = [5, 10, 20, 40, 60, 60];
+        for (attempt, expected) in cases.into_iter().enumerate() {
+            throttle.record_failure(ip(1), now);
+            let lockout = expected * SECOND;
+            assert_eq!(throttle.check(ip(1), now), locked(lockout), "lockout {}", attempt + 1);
+            assert_eq!(throttle.check(ip(1), now + lockout - SECOND), locked(SECOND), "lockout {}", attempt + 1);
+            now += lockout;
+            assert_eq!(throttle.check(ip(1), now), Verdict::Allowed, "lockout {}", attempt + 1);
+        }
+    }
+
+    #[test]
+    fn failures_decay_after_a_quiet_period() {
+        let start = Instant::now();
+        let cases = [
+            // 衰减期未满时，下一次失败继续加倍
+            (599, locked(10 * SECOND)),
+            // 衰减期满后计数清零，重新获得全部免费次数
+            (600, Verdict::Allowed),
+        ];
+        for (quiet_secs, expected) in cases {
+            let mut throttle = PairingThrottle::new(config());
+            for _ in 0..3 {
+                throttle.record_failure(ip(1), start);
+            }
+            let later = start + quiet_secs * SECOND;
+            throttle.record_failure(ip(1), later);
+            assert_eq!(throttle.check(ip(1), later), expected, "quiet for {}s", quiet_secs);
+        }
+    }
+
+    #[test]
+    fn success_clears_the_count() {
+        let mut throttle = PairingThrottle::new(config());
+        let now = Instant::now();
+        for _ in 0..2 {
+            throttle.record_failure(ip(1), now);
+        }
+        throttle.record_success(ip(1));
+        for _ in 0..2 {
+            throttle.record_failure(ip(1), now);
+        }
+        assert_eq!(throttle.check(ip(1), now), Verdict::Allowed);
+    }
+
+    #[test]
+    fn breaker_opens_for_everyone_and_closes_after_cooldown() {
+        let mut throttle = PairingThrottle::new(config());
+        let start = Instant::now();
+        // 分散在多个来源，单个来源都未锁定
+        for i in 0..9 {
+            assert!(!throttle.record_failure(ip(i), start + u32::from(i) * SECOND));
+        }
+        assert!(throttle.record_failure(ip(100), start + 9 * SECOND));
+
+        let opened = start + 9 * SECOND;
+        let retry_after = 300 * SECOND;
+        assert_eq!(throttle.check(ip(200), opened), Verdict::CircuitOpen { retry_after });
+        assert_eq!(
+            throttle.check(ip(200), opened + 299 * SECOND),
+            Verdict::CircuitOpen { retry_after: SECOND }
+        );
+        assert_eq!(throttle.check(ip(200), opened + retry_after), Verdict::Allowed);
+    }
+
+    #[test]
+    fn breaker_only_counts_failures_inside_the_window() {
+        let mut throttle = PairingThrottle::new(config());
+        let start = Instant::now();
+        for i in 0..9 {
+            throttle.record_failure(ip(i), start);
+        }
+        // 窗口外的失败不计入，本次失败不触发熔断
+        let later = start + 60 * SECOND;
+        assert!(!throttle.record_failure(ip(50), later));
+        assert_eq!(throttle.check(ip(51), later), Verdict::Allowed);
+    }
+
+    #[test]
+    fn tracked_ips_are_bounded() {
+        let many = |n: usize| (0..n as u32).map(|i| IpAddr::from((10 << 24 | i).to_be_bytes()));
+        let start = Instant::now();
+        let config = ThrottleConfig { breaker_threshold: usize::MAX, ..config() };
+
+        // 已过衰减期的记录在新来源到达时清理
+        let mut throttle = PairingThrottle::new(config);
+        for ip in many(MAX_TRACKED) {
+            throttle.record_failure(ip, start);
+        }
+        throttle.record_failure(ip(1), start + 600 * SECOND);
+        assert_eq!(throttle.per_ip.len(), 1);
+
+        // 都未过期时丢弃最早的记录，锁定中的来源保留
+        let mut throttle = PairingThrottle::new(config);
+        for _ in 0..3 {
+            throttle.record_failure(ip(1), start);
+        }
+        for ip in many(MAX_TRACKED - 1) {
+            throttle.record_failure(ip, start);
+        }
+        for ip in many(MAX_TRACKED + 100).skip(MAX_TRACKED) {
+            throttle.record_failure(ip, start + SECOND);
+        }
+        assert_eq!(throttle.per_ip.len(), MAX_TRACKED);
+        assert_eq!(throttle.check(ip(1), start + SECOND), locked(4 * SECOND));
+    }
+}
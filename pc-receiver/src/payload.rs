@@ -0,0 +1,271 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 载荷处理器扩展点。
+ * 内置的图片/短信/剪贴板处理器与第三方处理器都通过 `PayloadHandler` 注册到服务上。
+ */
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::Method,
+    response::{IntoResponse, Response},
+    routing::{on, MethodFilter, MethodRouter},
+};
+use futures::future::BoxFuture;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use fastsync_core::timeline::CaptureTime;
+use crate::audit::ItemAudit;
+use crate::auth::AuthenticatedDevice;
+use crate::clipboard::ClipboardBackend;
+use crate::devices::{DeviceSettings, RequestingDevice};
+use crate::events::ServerEvent;
+use crate::notifier::{ActionHandler, Notification};
+use crate::policy::{ContentInfo, PolicyViolation};
+use crate::state::{AppState, RunMode};
+use crate::timings::{Stage, Timings};
+
+/// 发起请求的设备信息。
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct DeviceContext {
+    /// 对端地址，嵌入方未启用 ConnectInfo 时为 None
+    pub remote_addr: Option<SocketAddr>,
+    /// 携带有效设备令牌时的设备标识
+    pub device_id: Option<String>,
+    /// 已配对设备的名称
+    pub device_name: Option<String>,
+    /// 已配对设备单独的设置
+    pub settings: DeviceSettings,
+}
+
+impl DeviceContext {
+    /// 通知标题，已配对设备显示为“来自 <名称> 的<内容>”，其他来源显示为“收到手机<内容>”。
+    ///
+    /// # Arguments
+    /// * `kind` - 内容类型，例如“图片”
+    pub fn received_title(&self, kind: &str) -> String {
+        match &self.device_name {
+            Some(name) => format!("来自 {} 的{}", name, kind),
+            None => format!("收到手机{}", kind),
+        }
+    }
+
+    /// 用于日志与审计的来源描述，优先使用设备标识。
+    pub fn origin(&self) -> Option<String> {
+        self.device_id
+            .clone()
+            .or_else(|| self.remote_addr.map(|addr| addr.ip().to_string()))
+    }
+}
+
+/// 传给处理器的上下文。
+#[derive(Clone)]
+pub struct PayloadContext {
+    /// 发起请求的设备
+    pub device: DeviceContext,
+    pub(crate) state: AppState,
+    /// 该内容的阶段时间，处理结束后由服务汇总
+    pub(crate) timings: Arc<Mutex<Timings>>,
+}
+
+impl PayloadContext {
+    /// 剪贴板后端，可在通知按钮回调中使用。
+    pub fn clipboard(&self) -> Arc<dyn ClipboardBackend> {
+        self.state.clipboard.clone()
+    }
+
+    /// 为新收到的内容创建审计句柄，之后对该内容的每个操作都应记录一次。
+    ///
+    /// # Arguments
+    /// * `item_type` - 内容类型，通常与能力标识相同
+    pub fn audit(&self, item_type: &str) -> ItemAudit {
+        ItemAudit::new(self.state.audit.clone(), item_type, self.device.origin())
+    }
+
+    /// 按该设备的时钟偏差校正手机提供的捕获时间。
+    ///
+    /// # Arguments
+    /// * `captured_at` - 手机提供的毫秒时间戳，未提供时使用到达时间
+    pub fn capture_time(&self, captured_at: Option<i64>) -> CaptureTime {
+        let skew = self.state.clock.get(self.device.origin().as_deref());
+        CaptureTime::reconcile(captured_at, chrono::Utc::now().timestamp_millis(), skew)
+    }
+
+    /// 按内容策略判定内容是否可以接收，违规时计数，处理器应直接以其作为响应返回。
+    ///
+    /// # Arguments
+    /// * `capability` - 接口的能力标识
+    /// * `content` - 待判定的内容
+    pub fn check_content(&self, capability: &str, content: &ContentInfo) -> Result<(), PolicyViolation> {
+        self.state.policy.check(capability, self.device.device_id.as_deref(), content)
+    }
+
+    /// 按内容策略判定即将执行的操作是否被允许，违规时计数。
+    ///
+    /// # Arguments
+    /// * `capability` - 接口的能力标识
+    /// * `content` - 操作的内容
+    /// * `action` - 操作名称，与审计记录中的操作一致
+    pub fn check_action(&self, capability: &str, content: &ContentInfo, action: &str) -> Result<(), PolicyViolation> {
+        self.state
+            .policy
+            .check_action(capability, self.device.device_id.as_deref(), content, action)
+    }
+
+    /// 操作是否被内容策略允许，不计数，用于决定显示哪些通知按钮。
+    pub fn action_allowed(&self, capability: &str, content: &ContentInfo, action: &str) -> bool {
+        self.state
+            .policy
+            .policy()
+            .check_action(capability, self.device.device_id.as_deref(), content, action)
+            .is_ok()
+    }
+
+    /// 记录内容到达某个处理阶段，用于统计从接收到通知显示的耗时。
+    ///
+    /// # Arguments
+    /// * `stage` - 刚完成的阶段
+    pub fn mark(&self, stage: Stage) {
+        self.timings.lock().unwrap().mark(stage);
+    }
+
+    /// 发出服务事件。
+    pub fn emit(&self, event: ServerEvent) {
+        self.state.events.emit(event);
+    }
+}
+
+/// 处理器请求显示的通知。
+pub struct NotificationRequest {
+    pub notification: Notification,
+    pub on_action: ActionHandler,
+}
+
+/// 处理结果：返回给客户端的响应，以及需要显示的通知。
+pub struct PayloadOutcome {
+    pub response: Response,
+    pub notifications: Vec<NotificationRequest>,
+}
+
+impl PayloadOutcome {
+    /// 以任意可转换为响应的值创建结果。
+    pub fn new(response: impl IntoResponse) -> Self {
+        Self {
+            response: response.into_response(),
+            notifications: Vec::new(),
+        }
+    }
+
+    /// 附加一条通知，响应返回后由服务通过通知后端显示。
+    pub fn with_notification(mut self, notification: Notification, on_action: ActionHandler) -> Self {
+        self.notifications.push(NotificationRequest { notification, on_action });
+        self
+    }
+}
+
+/// 载荷处理器。
+pub trait PayloadHandler: Send + Sync + 'static {
+    /// 路由路径，例如 `/upload`
+    fn path(&self) -> &str;
+
+    /// 请求方法
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    /// 能力标识，出现在 `/info` 与 mDNS TXT 记录中
+    fn capability(&self) -> &str;
+
+    /// 是否为 JSON 接口并在配置跨域来源后允许网页直接调用
+    fn cross_origin(&self) -> bool {
+        false
+    }
+
+    /// 处理请求。`request` 的请求体尚未读取，处理器可按需流式消费。
+    ///
+    /// # Arguments
+    /// * `ctx` - 上下文
+    /// * `request` - 原始请求
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome>;
+}
+
+/// 为处理器生成 axum 路由。
+pub(crate) fn method_router(handler: Arc<dyn PayloadHandler>) -> MethodRouter<AppState> {
+    let filter = MethodFilter::try_from(handler.method()).unwrap_or(MethodFilter::POST);
+
+    on(filter, move |State(state): State<AppState>, request: Request| {
+        let handler = handler.clone();
+        async move { dispatch(state, handler, request).await }
+    })
+}
+
+/// 调用处理器并通过通知队列在后台显示其请求的通知。
+async fn dispatch(state: AppState, handler: Arc<dyn PayloadHandler>, request: Request) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Ok(RequestingDevice(paired)) = RequestingDevice::from_request_parts(&mut parts, &state).await;
+    let request = Request::from_parts(parts, body);
+
+    let device = DeviceContext {
+        remote_addr: request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0),
+        device_id: request
+            .extensions()
+            .get::<AuthenticatedDevice>()
+            .map(|device| device.0.clone()),
+        device_name: paired.as_ref().map(|device| device.name.clone()),
+        settings: paired.map(|device| device.settings).unwrap_or_default(),
+    };
+    let origin = device.origin();
+    let timings = Arc::new(Mutex::new(Timings::start()));
+    let ctx = PayloadContext {
+        device,
+        state: state.clone(),
+        timings: timings.clone(),
+    };
+
+    let mut outcome = handler.handle(ctx, request).await;
+    let mut timings = Some(timings.lock().unwrap().clone());
+
+    // 非桌面模式没有可以显示通知的会话，内容已写入历史或保存目录
+    if state.mode != RunMode::Desktop && !outcome.notifications.is_empty() {
+        tracing::debug!("Dropping {} notifications in {} mode", outcome.notifications.len(), state.mode.as_str());
+        outcome.notifications.clear();
+    }
+
+    for mut request in outcome.notifications {
+        if let Some(kind) = request.notification.urgent {
+            let paused = crate::schedule::scheduled_pause(&state.schedule.read().unwrap()).is_some();
+            let flashing = state
+                .attention
+                .on_urgent(kind, request.notification.expires_in, paused, Instant::now());
+            // 用户点击任意按钮即视为已处理，停止闪烁
+            if let Some(id) = flashing {
+                let attention = state.attention.clone();
+                let on_action = request.on_action;
+                request.on_action = Arc::new(move |arguments: &str| {
+                    attention.acknowledge(id);
+                    on_action(arguments);
+                });
+            }
+        }
+
+        // 耗时只跟随第一条通知统计到显示完成
+        let timings = timings.take().map(|mut timings| {
+            timings.mark(Stage::Enqueued);
+            timings
+        });
+        state
+            .notifications
+            .submit(handler.capability().to_string(), origin.clone(), request, timings);
+    }
+
+    if let Some(timings) = timings {
+        state.timings.finish(handler.capability(), &timings);
+    }
+
+    outcome.response
+}
@@ -0,0 +1,429 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 内容策略模块。
+ * 按接口（能力标识）配置允许的类型、大小上限与允许的操作，可按内容类型和设备进一步覆盖。
+ * 所有接口都在这里统一判定，判定本身不依赖任何外部状态。
+ */
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+
+/// 内容策略，未配置的项不做限制。
+///
+/// 同一项按以下顺序取第一个有配置的值，越具体越优先：
+/// 设备的类型规则 > 设备的接口规则 > 接口的类型规则 > 接口规则 > 全局配置。
+/// 禁止类型在所有层级上累加，命中任意一层即拒绝。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContentPolicy {
+    /// 全局大小上限（字节）
+    pub max_bytes: Option<u64>,
+    /// 全局禁止的类型
+    pub denied_types: Vec<String>,
+    /// 按能力标识配置的接口规则，例如 `photo`、`clipboard`
+    pub endpoints: HashMap<String, EndpointRule>,
+    /// 按设备标识覆盖的接口规则
+    pub devices: HashMap<String, HashMap<String, EndpointRule>>,
+}
+
+/// 单个接口的规则。
+///
+/// 类型模式可以是 MIME（`image/png`、`image/*`）、以点开头的扩展名（`.exe`）或 `*`。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EndpointRule {
+    /// 允许的类型，未配置时不限
+    pub allowed_types: Option<Vec<String>>,
+    /// 禁止的类型
+    pub denied_types: Vec<String>,
+    /// 大小上限（字节）
+    pub max_bytes: Option<u64>,
+    /// 允许的操作，例如 `save`、`copy`、`auto_save`，未配置时不限
+    pub allowed_actions: Option<Vec<String>>,
+    /// 按类型细分的规则，按顺序取第一条匹配的
+    pub types: Vec<TypeRule>,
+}
+
+/// 针对部分类型的规则。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TypeRule {
+    /// 适用的类型模式
+    pub types: Vec<String>,
+    /// 大小上限（字节）
+    pub max_bytes: Option<u64>,
+    /// 允许的操作
+    pub allowed_actions: Option<Vec<String>>,
+}
+
+/// 待判定的内容。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContentInfo<'a> {
+    /// MIME 类型
+    pub mime: Option<&'a str>,
+    /// 文件名或扩展名
+    pub file_name: Option<&'a str>,
+    /// 内容大小（字节）
+    pub size: u64,
+}
+
+/// 违反策略的原因。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// 类型被禁止或不在允许列表中
+    TypeNotAllowed,
+    /// 超过大小上限
+    TooLarge { limit: u64 },
+    /// 操作不被允许
+    ActionNotAllowed { action: String },
+}
+
+impl PolicyViolation {
+    /// 机器可读的原因标识。
+    pub fn reason(&self) -> &'static str {
+        match self {
+            PolicyViolation::TypeNotAllowed => "type_not_allowed",
+            PolicyViolation::TooLarge { .. } => "too_large",
+            PolicyViolation::ActionNotAllowed { .. } => "action_not_allowed",
+        }
+    }
+
+    /// 对应的 HTTP 状态码。
+    pub fn status(&self) -> StatusCode {
+        match self {
+            PolicyViolation::TypeNotAllowed => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PolicyViolation::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            PolicyViolation::ActionNotAllowed { .. } => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl IntoResponse for PolicyViolation {
+    fn into_response(self) -> Response {
+        let mut body = json!({ "error": "policy", "reason": self.reason() });
+        match &self {
+            PolicyViolation::TooLarge { limit } => body["limit"] = json!(limit),
+            PolicyViolation::ActionNotAllowed { action } => body["action"] = json!(action),
+            PolicyViolation::TypeNotAllowed => {}
+        }
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+impl ContentPolicy {
+    /// 判定内容是否可以被接收。
+    ///
+    /// # Arguments
+    /// * `capability` - 接口的能力标识
+    /// * `device_id` - 已配对设备的标识
+    /// * `content` - 待判定的内容
+    pub fn check(&self, capability: &str, device_id: Option<&str>, content: &ContentInfo) -> Result<(), PolicyViolation> {
+        let rules = self.rules(capability, device_id);
+
+        let denied = self
+            .denied_types
+            .iter()
+            .chain(rules.iter().flat_map(|rule| rule.denied_types.iter()))
+            .any(|pattern| type_matches(pattern, content));
+        let allowed = rules
+            .iter()
+            .find_map(|rule| rule.allowed_types.as_ref())
+            .is_none_or(|patterns| patterns.iter().any(|pattern| type_matches(pattern, content)));
+        if denied || !allowed {
+            return Err(PolicyViolation::TypeNotAllowed);
+        }
+
+        let limit = rules
+            .iter()
+            .find_map(|rule| {
+                rule.type_rule(content)
+                    .and_then(|type_rule| type_rule.max_bytes)
+                    .or(rule.max_bytes)
+            })
+            .or(self.max_bytes);
+        match limit {
+            Some(limit) if content.size > limit => Err(PolicyViolation::TooLarge { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// 判定对内容执行某个操作是否被允许。
+    ///
+    /// # Arguments
+    /// * `capability` - 接口的能力标识
+    /// * `device_id` - 已配对设备的标识
+    /// * `content` - 操作的内容
+    /// * `action` - 操作名称，与审计记录中的操作一致
+    pub fn check_action(
+        &self,
+        capability: &str,
+        device_id: Option<&str>,
+        content: &ContentInfo,
+        action: &str,
+    ) -> Result<(), PolicyViolation> {
+        let allowed = self
+            .rules(capability, device_id)
+            .iter()
+            .find_map(|rule| {
+                rule.type_rule(content)
+                    .and_then(|type_rule| type_rule.allowed_actions.as_ref())
+                    .or(rule.allowed_actions.as_ref())
+            })
+            .is_none_or(|actions| actions.iter().any(|allowed| allowed == action));
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(PolicyViolation::ActionNotAllowed { action: action.to_string() })
+        }
+    }
+
+    /// 适用的接口规则，越具体的越靠前。
+    fn rules(&self, capability: &str, device_id: Option<&str>) -> Vec<&EndpointRule> {
+        let device_rule = device_id
+            .and_then(|id| self.devices.get(id))
+            .and_then(|rules| rules.get(capability));
+        device_rule.into_iter().chain(self.endpoints.get(capability)).collect()
+    }
+}
+
+impl EndpointRule {
+    /// 第一条匹配内容类型的细分规则。
+    fn type_rule(&self, content: &ContentInfo) -> Option<&TypeRule> {
+        self.types
+            .iter()
+            .find(|rule| rule.types.iter().any(|pattern| type_matches(pattern, content)))
+    }
+}
+
+/// 判断类型模式是否匹配内容，均不区分大小写。
+fn type_matches(pattern: &str, content: &ContentInfo) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    if pattern == "*" {
+        return true;
+    }
+
+    if let Some(extension) = pattern.strip_prefix('.') {
+        return content
+            .file_name
+            .and_then(|name| Path::new(name).extension())
+            .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case(extension));
+    }
+
+    let Some(mime) = content.mime.map(|mime| mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase()) else {
+        return false;
+    };
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime.split('/').next() == Some(prefix),
+        None => mime == pattern,
+    }
+}
+
+/// 策略与违规计数，由所有接口共享。配置重新载入时替换策略，违规计数保留。
+#[derive(Default)]
+pub(crate) struct PolicyEngine {
+    policy: RwLock<ContentPolicy>,
+    violations: Mutex<HashMap<&'static str, u64>>,
+}
+
+impl PolicyEngine {
+    pub(crate) fn new(policy: ContentPolicy) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+            violations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> RwLockReadGuard<'_, ContentPolicy> {
+        self.policy.read().unwrap()
+    }
+
+    /// 替换正在使用的策略。
+    pub(crate) fn set_policy(&self, policy: ContentPolicy) {
+        *self.policy.write().unwrap() = policy;
+    }
+
+    /// 判定内容是否可以被接收，违规时计数并记录日志。
+    pub(crate) fn check(&self, capability: &str, device_id: Option<&str>, content: &ContentInfo) -> Result<(), PolicyViolation> {
+        self.policy()
+            .check(capability, device_id, content)
+            .inspect_err(|violation| self.record(capability, device_id, violation))
+    }
+
+    /// 判定操作是否被允许，违规时计数并记录日志。
+    pub(crate) fn check_action(
+        &self,
+        capability: &str,
+        device_id: Option<&str>,
+        content: &ContentInfo,
+        action: &str,
+    ) -> Result<(), PolicyViolation> {
+        self.policy()
+            .check_action(capability, device_id, content, action)
+            .inspect_err(|violation| self.record(capability, device_id, violation))
+    }
+
+    /// 各原因的违规次数。
+    pub(crate) fn violations(&self) -> HashMap<String, u64> {
+        self.violations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(reason, count)| (reason.to_string(), *count))
+            .collect()
+    }
+
+    fn record(&self, capability: &str, device_id: Option<&str>, violation: &PolicyViolation) {
+        tracing::warn!(
+            target: "fastsync::audit",
+            "Policy violation on {} from {}: {:?}",
+            capability,
+            device_id.unwrap_or("unpaired device"),
+            violation
+        );
+        *self.violations.lock().unwrap().entry(violation.reason()).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    /// 各层级都有配置的策略：全局 1000 字节并禁止 `.exe`；照片接口 500 字节、只收图片、
+    /// 禁止 GIF，HEIC 放宽到 2000 字节且只能保存；平板上的照片接口放宽到 5000 字节并允许视频，
+    /// 但 PNG 收紧到 10 字节。
+    fn policy() -> ContentPolicy {
+        let photo = EndpointRule {
+            allowed_types: Some(strings(&["image/*"])),
+            denied_types: strings(&["image/gif"]),
+            max_bytes: Some(500),
+            allowed_actions: Some(strings(&["save", "copy"])),
+            types: vec![TypeRule {
+                types: strings(&["image/heic"]),
+                max_bytes: Some(2000),
+                allowed_actions: Some(strings(&["save"])),
+            }],
+        };
+        let tablet_photo = EndpointRule {
+            allowed_types: Some(strings(&["image/*", "video/*"])),
+            max_bytes: Some(5000),
+            types: vec![TypeRule {
+                types: strings(&["image/png"]),
+                max_bytes: Some(10),
+                allowed_actions: None,
+            }],
+            ..EndpointRule::default()
+        };
+        ContentPolicy {
+            max_bytes: Some(1000),
+            denied_types: strings(&[".exe"]),
+            endpoints: HashMap::from([("photo".to_string(), photo)]),
+            devices: HashMap::from([("tablet".to_string(), HashMap::from([("photo".to_string(), tablet_photo)]))]),
+        }
+    }
+
+    fn content<'a>(mime: Option<&'a str>, file_name: Option<&'a str>, size: u64) -> ContentInfo<'a> {
+        ContentInfo { mime, file_name, size }
+    }
+
+    #[test]
+    fn most_specific_rule_wins() {
+        let policy = policy();
+        let too_large = |limit| Err(PolicyViolation::TooLarge { limit });
+        let cases = [
+            // 接口规则优先于全局上限
+            ("photo", None, Some("image/png"), None, 400, Ok(())),
+            ("photo", None, Some("image/png"), None, 600, too_large(500)),
+            // 类型规则优先于接口规则
+            ("photo", None, Some("image/heic"), None, 1500, Ok(())),
+            ("photo", None, Some("image/heic"), None, 2500, too_large(2000)),
+            // 未配置的接口使用全局上限
+            ("clipboard", None, Some("text/plain"), None, 1200, too_large(1000)),
+            ("clipboard", None, Some("text/plain"), None, 900, Ok(())),
+            // 设备规则优先于接口规则，其他设备不受影响
+            ("photo", Some("tablet"), Some("image/jpeg"), None, 4000, Ok(())),
+            ("photo", Some("phone"), Some("image/jpeg"), None, 600, too_large(500)),
+            // 设备的类型规则最优先
+            ("photo", Some("tablet"), Some("image/png"), None, 20, too_large(10)),
+            // 设备的接口规则优先于接口的类型规则
+            ("photo", Some("tablet"), Some("image/heic"), None, 4500, Ok(())),
+            // 允许的类型取最具体的一层
+            ("photo", None, Some("video/mp4"), None, 10, Err(PolicyViolation::TypeNotAllowed)),
+            ("photo", Some("tablet"), Some("video/mp4"), None, 10, Ok(())),
+            // 禁止类型在所有层级累加，设备放宽不了接口与全局的禁止
+            ("photo", Some("tablet"), Some("image/gif"), None, 10, Err(PolicyViolation::TypeNotAllowed)),
+            ("clipboard", None, None, Some("setup.EXE"), 10, Err(PolicyViolation::TypeNotAllowed)),
+            // 类型先于大小判定
+            ("photo", None, Some("image/gif"), None, 9999, Err(PolicyViolation::TypeNotAllowed)),
+        ];
+        for (capability, device, mime, file_name, size, expected) in cases {
+            assert_eq!(
+                policy.check(capability, device, &content(mime, file_name, size)),
+                expected,
+                "{} from {:?}: {:?} {:?} {} bytes",
+                capability,
+                device,
+                mime,
+                file_name,
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn actions_follow_the_same_precedence() {
+        let policy = policy();
+        let cases = [
+            ("photo", None, "image/png", "copy", true),
+            ("photo", None, "image/png", "auto_save", false),
+            // 类型规则收紧接口规则
+            ("photo", None, "image/heic", "save", true),
+            ("photo", None, "image/heic", "copy", false),
+            // 设备规则未配置操作时沿用接口规则
+            ("photo", Some("tablet"), "image/png", "copy", true),
+            ("photo", Some("tablet"), "image/png", "auto_save", false),
+            // 没有任何规则配置操作时不限
+            ("clipboard", None, "text/plain", "auto_apply", true),
+        ];
+        for (capability, device, mime, action, allowed) in cases {
+            let result = policy.check_action(capability, device, &content(Some(mime), None, 1), action);
+            assert_eq!(result.is_ok(), allowed, "{} {} on {} from {:?}", action, mime, capability, device);
+        }
+    }
+
+    #[test]
+    fn type_patterns() {
+        let cases = [
+            ("*", None, None, true),
+            ("image/*", Some("image/png"), None, true),
+            ("image/*", Some("video/mp4"), None, false),
+            ("IMAGE/PNG", Some("image/png; charset=binary"), None, true),
+            (".pdf", None, Some("report.PDF"), true),
+            (".pdf", Some("application/pdf"), None, false),
+            ("image/png", None, Some("a.png"), false),
+        ];
+        for (pattern, mime, file_name, expected) in cases {
+            assert_eq!(
+                type_matches(pattern, &content(mime, file_name, 0)),
+                expected,
+                "{} against {:?} {:?}",
+                pattern,
+                mime,
+                file_name
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 二维码识别模块。
+ * 手机上截下二维码发到电脑时，识别出其中的网址，通知中提供"打开链接"直接用浏览器打开。
+ * 识别只是附加功能，超时或失败时静默跳过，不会拖慢普通照片的通知。
+ */
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use image::ImageReader;
+
+/// 识别的时限，超过后放弃。
+pub const DETECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 识别前将图片缩小到该边长以内，手机截图不受影响，大尺寸照片不必逐像素扫描。
+const MAX_DIMENSION: u32 = 2048;
+
+/// 在图片中查找二维码，返回第一个内容为网址的二维码。
+///
+/// # Arguments
+/// * `path` - 图片路径
+///
+/// # Returns
+/// 二维码中的网址，没有找到时为 None
+pub fn detect_link(path: &Path) -> anyhow::Result<Option<String>> {
+    let mut image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    if image.width() > MAX_DIMENSION || image.height() > MAX_DIMENSION {
+        image = image.thumbnail(MAX_DIMENSION, MAX_DIMENSION);
+    }
+    let luma = image.to_luma8();
+
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(luma.width() as usize, luma.height() as usize, |x, y| {
+        luma.get_pixel(x as u32, y as u32).0[0]
+    });
+    let link = prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content.trim().to_string())
+        .find(|content| is_link(content));
+    Ok(link)
+}
+
+/// 在后台线程识别二维码，超过时限或失败时返回 None。
+///
+/// # Arguments
+/// * `path` - 图片路径
+pub(crate) async fn find_link(path: PathBuf) -> Option<String> {
+    let detected = tokio::time::timeout(DETECT_TIMEOUT, tokio::task::spawn_blocking(move || detect_link(&path))).await;
+    match detected {
+        Ok(Ok(Ok(link))) => link,
+        Ok(Ok(Err(e))) => {
+            tracing::debug!("QR detection skipped: {:#}", e);
+            None
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("QR detection task failed: {:?}", e);
+            None
+        }
+        Err(_) => {
+            tracing::debug!("QR detection timed out after {:?}", DETECT_TIMEOUT);
+            None
+        }
+    }
+}
+
+/// 是否为可交给浏览器打开的网址，只接受 http 与 https。
+fn is_link(content: &str) -> bool {
+    let lower = content.to_ascii_lowercase();
+    let rest = lower.strip_prefix("https://").or_else(|| lower.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty()) && !content.chars().any(|c| c.is_whitespace() || c.is_control())
+}
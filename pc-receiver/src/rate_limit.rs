@@ -0,0 +1,171 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 请求频率限制模块。
+ * 手机端脚本出错反复推送时，通知与剪贴板会被大量重复内容淹没。上传、短信与剪贴板接口
+ * 按设备（未携带设备令牌时按来源 IP）分别限制每分钟的请求数，超过后返回 429 与建议的重试时间。
+ * 每个来源使用一个令牌桶，允许短时间内突发到每分钟的上限；跟踪的来源数量有上限，
+ * 扫描端口的随机来源不会让内存无限增长。
+ */
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::auth::AuthenticatedDevice;
+use crate::config::RateLimitConfig;
+use crate::handlers::pair::retry_later;
+use crate::state::AppState;
+
+/// 同时跟踪的来源数上限，超过后先丢弃已回满的令牌桶，仍超过时丢弃最早回满的。
+const MAX_TRACKED: usize = 1024;
+
+/// 受频率限制的接口类别，各类别的额度相互独立。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    /// 图片与视频上传，包括分块上传的开始
+    Upload,
+    /// 单条与批量短信
+    Sms,
+    /// 剪贴板推送
+    Clipboard,
+}
+
+impl RateClass {
+    /// 按请求的方法与路径确定类别，查询类的请求不受限制。
+    ///
+    /// # Arguments
+    /// * `method` - 请求方法
+    /// * `path` - 请求路径
+    pub fn of(method: &Method, path: &str) -> Option<Self> {
+        if method == Method::GET {
+            return None;
+        }
+        match path {
+            "/upload" | "/upload/raw" | "/upload/file" | "/upload/init" => Some(RateClass::Upload),
+            "/sms" | "/sms/batch" => Some(RateClass::Sms),
+            "/clipboard" => Some(RateClass::Clipboard),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateClass::Upload => "upload",
+            RateClass::Sms => "sms",
+            RateClass::Clipboard => "clipboard",
+        }
+    }
+
+    /// 该类别每分钟的请求数上限，为 0 时不限制。
+    pub fn per_minute(&self, limits: &RateLimitConfig) -> u32 {
+        match self {
+            RateClass::Upload => limits.uploads_per_min,
+            RateClass::Sms => limits.sms_per_min,
+            RateClass::Clipboard => limits.clipboard_per_min,
+        }
+    }
+}
+
+/// 某个来源在某一类别上的令牌桶。
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// 不再请求时令牌回满的时刻，此后与从未请求过的来源等价
+    full_at: Instant,
+}
+
+/// 按来源与接口类别计数的令牌桶集合。
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<(RateClass, String), Bucket>>,
+}
+
+impl RateLimiter {
+    /// 申请一次请求。
+    ///
+    /// # Arguments
+    /// * `class` - 接口类别
+    /// * `client` - 设备标识或来源 IP
+    /// * `per_minute` - 每分钟的请求数上限，为 0 时不限制
+    /// * `now` - 当前时间
+    ///
+    /// # Returns
+    /// 额度用尽时返回建议的重试时间
+    pub fn acquire(&self, class: RateClass, client: &str, per_minute: u32, now: Instant) -> Result<(), Duration> {
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(per_minute);
+        // 每秒补充的令牌数
+        let rate = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let key = (class, client.to_string());
+        if !buckets.contains_key(&key) && buckets.len() >= MAX_TRACKED {
+            buckets.retain(|_, bucket| bucket.full_at > now);
+            if buckets.len() >= MAX_TRACKED {
+                let oldest = buckets.iter().min_by_key(|(_, bucket)| bucket.full_at).map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    buckets.remove(&oldest);
+                }
+            }
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            full_at: now,
+        });
+        // 上限调低后已积累的令牌随之减少
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+        let result = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        };
+        bucket.full_at = now + Duration::from_secs_f64((capacity - bucket.tokens) / rate);
+        result
+    }
+}
+
+/// 请求的来源：携带设备令牌时为 `device:<设备标识>`，否则为 `ip:<来源 IP>`。须在令牌校验之后调用。
+pub(crate) fn client_id(request: &Request) -> String {
+    match request.extensions().get::<AuthenticatedDevice>() {
+        Some(AuthenticatedDevice(device_id)) => format!("device:{}", device_id),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or_else(|| "unknown".to_string(), |ConnectInfo(addr)| format!("ip:{}", addr.ip())),
+    }
+}
+
+/// 超过所在类别的每分钟额度时返回 429，响应头 `Retry-After` 为建议的重试秒数。
+/// 位于令牌校验之后，携带设备令牌的请求按设备计数，其余按来源 IP 计数。
+pub(crate) async fn rate_limit_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(class) = RateClass::of(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let per_minute = class.per_minute(&state.config.read().unwrap().rate_limit);
+    let client = client_id(&request);
+
+    if let Err(retry_after) = state.rate_limiter.acquire(class, &client, per_minute, Instant::now()) {
+        tracing::warn!(
+            "Rate limit of {} {} requests per minute exceeded by {}",
+            per_minute,
+            class.as_str(),
+            client
+        );
+        return retry_later(StatusCode::TOO_MANY_REQUESTS, "rate_limited", retry_after);
+    }
+    next.run(request).await
+}
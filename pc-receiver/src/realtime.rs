@@ -0,0 +1,163 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 实时消息模块。
+ * 已配对的手机可以通过 `GET /ws` 保持一条 WebSocket 连接，电脑剪贴板变化、待发送的短信回复
+ * 与“查找手机”等事件经这条连接直接推送，不必轮询。每台设备只保留最新的一条连接，
+ * 网络切换后手机重连时旧连接随即关闭，旧连接结束时也不会误删新连接的登记。
+ */
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use crate::clipboard::ClipboardSnapshot;
+use crate::sms_history::SmsReply;
+
+/// 每条连接待发送消息的队列长度，推送在队列已满时丢弃，避免卡住的连接拖慢其他设备。
+const OUTGOING_CAPACITY: usize = 64;
+
+/// 新连接取代同一设备的旧连接。
+pub(crate) const CLOSE_REPLACED: (u16, &str) = (4000, "replaced");
+/// 设备被解除配对。
+pub(crate) const CLOSE_REVOKED: (u16, &str) = (1008, "revoked");
+/// 服务正在关闭。
+pub(crate) const CLOSE_SHUTDOWN: (u16, &str) = (1001, "shutdown");
+
+/// 电脑推送给手机的消息，`type` 字段区分种类。
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ServerMessage {
+    /// 连接建立后的第一条消息
+    Hello {
+        /// 本次连接的标识
+        session: u64,
+        device: String,
+        protocol: u32,
+        /// 电脑剪贴板当前的变化序号，与 `GET /clipboard` 相同
+        clipboard_seq: u64,
+    },
+    /// 电脑剪贴板中的文本发生变化
+    Clipboard(ClipboardSnapshot),
+    /// 用户在短信通知中输入了回复，等待手机发送
+    SmsReply(SmsReply),
+    /// 让手机响铃以便找到它
+    FindPhone,
+    /// 手机发来的消息的处理结果，状态码与响应体与对应的 REST 接口相同
+    Response {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<Value>,
+        status: u16,
+        body: Value,
+    },
+    /// 无法识别的消息
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<Value>,
+        error: &'static str,
+        message: String,
+    },
+}
+
+/// 发往连接的内容。
+#[derive(Debug)]
+pub(crate) enum Outgoing {
+    Message(ServerMessage),
+    /// 以指定的关闭码与原因结束连接
+    Close(u16, &'static str),
+}
+
+/// 一条已登记的连接。
+struct Session {
+    id: u64,
+    sender: mpsc::Sender<Outgoing>,
+}
+
+/// 各设备当前的 WebSocket 连接。
+#[derive(Default)]
+pub(crate) struct Sessions {
+    next_id: AtomicU64,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Sessions {
+    /// 登记设备的新连接，关闭该设备仍在登记中的旧连接。
+    ///
+    /// # Arguments
+    /// * `device` - 设备标识
+    ///
+    /// # Returns
+    /// 连接标识、发送端与该连接待发送消息的接收端
+    pub(crate) fn register(&self, device: &str) -> (u64, mpsc::Sender<Outgoing>, mpsc::Receiver<Outgoing>) {
+        let (sender, receiver) = mpsc::channel(OUTGOING_CAPACITY);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let previous = self.sessions.lock().unwrap().insert(
+            device.to_string(),
+            Session {
+                id,
+                sender: sender.clone(),
+            },
+        );
+        if let Some(previous) = previous {
+            tracing::info!("Session {} of device {} replaced by session {}", previous.id, device, id);
+            let _ = previous.sender.try_send(Outgoing::Close(CLOSE_REPLACED.0, CLOSE_REPLACED.1));
+        }
+        (id, sender, receiver)
+    }
+
+    /// 连接结束时取消登记，设备已有更新的连接时保留新连接。
+    ///
+    /// # Arguments
+    /// * `device` - 设备标识
+    /// * `id` - 结束的连接标识
+    pub(crate) fn unregister(&self, device: &str, id: u64) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.get(device).is_some_and(|session| session.id == id) {
+            sessions.remove(device);
+        }
+    }
+
+    /// 推送给某台设备。
+    ///
+    /// # Returns
+    /// 设备是否在线并已放入发送队列
+    pub(crate) fn send(&self, device: &str, message: ServerMessage) -> bool {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(device)
+            .is_some_and(|session| session.sender.try_send(Outgoing::Message(message)).is_ok())
+    }
+
+    /// 推送给所有在线的设备。
+    ///
+    /// # Returns
+    /// 放入发送队列的连接数
+    pub(crate) fn broadcast(&self, message: ServerMessage) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .filter(|session| session.sender.try_send(Outgoing::Message(message.clone())).is_ok())
+            .count()
+    }
+
+    /// 关闭某台设备的连接。
+    pub(crate) fn close(&self, device: &str, (code, reason): (u16, &'static str)) {
+        if let Some(session) = self.sessions.lock().unwrap().remove(device) {
+            let _ = session.sender.try_send(Outgoing::Close(code, reason));
+        }
+    }
+
+    /// 关闭所有连接。
+    pub(crate) fn close_all(&self, (code, reason): (u16, &'static str)) {
+        for (_, session) in self.sessions.lock().unwrap().drain() {
+            let _ = session.sender.try_send(Outgoing::Close(code, reason));
+        }
+    }
+
+    /// 设备当前是否保持着连接。
+    pub(crate) fn is_connected(&self, device: &str) -> bool {
+        self.sessions.lock().unwrap().contains_key(device)
+    }
+}
@@ -0,0 +1,351 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 分块续传模块。
+ * 大文件在信号不好的 Wi-Fi 下常在接近完成时中断，手机端先登记文件的大小与哈希，
+ * 再按偏移逐块上传，中断后查询已收到的字节数并从该处继续。
+ * 已收到的内容写入临时目录，超过续传时限未再上传的会话连同文件一起清理。
+ */
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 默认续传时限，超过该时间未收到新的分块时放弃上传。
+pub const DEFAULT_RESUME_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// 清理过期会话的间隔。
+pub const COLLECT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 临时文件名前缀，启动后由清理任务识别上次运行遗留的文件。
+const PART_PREFIX: &str = "fastsync_upload_";
+
+/// 临时文件扩展名。
+const PART_EXTENSION: &str = "part";
+
+/// 一次分块上传。
+struct UploadSession {
+    device: Option<String>,
+    file_name: String,
+    size: u64,
+    /// 手机端登记的 SHA-256（小写）
+    expected_hash: String,
+    /// 手机上的拍摄时间（毫秒时间戳）
+    captured_at: Option<i64>,
+    path: PathBuf,
+    /// 已写入文件的字节数
+    received: u64,
+    /// 已写入内容的哈希状态，分块按顺序写入，完成时无需重新读取文件
+    hasher: Sha256,
+    updated_at: Instant,
+    /// 正在写入分块，期间拒绝同一会话的其他请求
+    writing: bool,
+}
+
+/// 会话进度。
+#[derive(Debug, Clone)]
+pub(crate) struct UploadProgress {
+    pub id: String,
+    pub size: u64,
+    pub received: u64,
+}
+
+/// 获准写入的分块，写入结束后必须交回 `ResumableUploads::release`。
+pub(crate) struct ChunkSlot {
+    pub path: PathBuf,
+    /// 分块在文件中的起始位置
+    pub offset: u64,
+    /// 本次最多可写入的字节数
+    pub remaining: u64,
+    pub hasher: Sha256,
+}
+
+/// 已全部收到的上传，会话随之结束。
+pub(crate) struct FinishedUpload {
+    pub path: PathBuf,
+    pub file_name: String,
+    pub size: u64,
+    pub expected_hash: String,
+    /// 实际收到内容的 SHA-256
+    pub hash: String,
+    pub captured_at: Option<i64>,
+}
+
+/// 会话操作被拒绝的原因。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SessionError {
+    /// 会话不存在、已过期或属于其他设备
+    NotFound,
+    /// 同一会话正在写入其他分块
+    Busy,
+    /// 分块的偏移与已收到的字节数不一致
+    OffsetMismatch { received: u64 },
+    /// 尚未收到全部内容
+    Incomplete { received: u64 },
+}
+
+/// 进行中的分块上传，由所有请求共享。
+pub(crate) struct ResumableUploads {
+    window: RwLock<Duration>,
+    dir: PathBuf,
+    sessions: Mutex<HashMap<String, UploadSession>>,
+}
+
+impl ResumableUploads {
+    /// # Arguments
+    /// * `window` - 续传时限
+    /// * `dir` - 存放已收到内容的目录
+    pub(crate) fn new(window: Duration, dir: PathBuf) -> Self {
+        Self {
+            window: RwLock::new(window),
+            dir,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 续传时限。
+    pub(crate) fn window(&self) -> Duration {
+        *self.window.read().unwrap()
+    }
+
+    /// 修改续传时限，进行中的上传在下一次清理时按新的时限判断是否过期。
+    pub(crate) fn set_window(&self, window: Duration) {
+        *self.window.write().unwrap() = window;
+    }
+
+    /// 登记新的上传并创建空的临时文件。同一设备登记相同的文件时返回原会话，手机重启后也能续传。
+    ///
+    /// # Arguments
+    /// * `device` - 发起上传的设备
+    /// * `file_name` - 原始文件名
+    /// * `size` - 文件大小
+    /// * `hash` - 文件的 SHA-256
+    /// * `captured_at` - 手机上的拍摄时间
+    /// * `now` - 当前时间
+    pub(crate) fn begin(
+        &self,
+        device: Option<&str>,
+        file_name: &str,
+        size: u64,
+        hash: &str,
+        captured_at: Option<i64>,
+        now: Instant,
+    ) -> anyhow::Result<UploadProgress> {
+        let hash = hash.to_ascii_lowercase();
+        let mut sessions = self.sessions.lock().unwrap();
+        let existing = sessions
+            .iter_mut()
+            .find(|(_, session)| session.device.as_deref() == device && session.expected_hash == hash && session.size == size);
+        if let Some((id, session)) = existing {
+            session.updated_at = now;
+            return Ok(UploadProgress {
+                id: id.clone(),
+                size,
+                received: session.received,
+            });
+        }
+
+        let id = hex::encode(rand::random::<[u8; 16]>());
+        let path = self.dir.join(format!("{}{}.{}", PART_PREFIX, id, PART_EXTENSION));
+        std::fs::File::create(&path)?;
+        sessions.insert(
+            id.clone(),
+            UploadSession {
+                device: device.map(str::to_string),
+                file_name: file_name.to_string(),
+                size,
+                expected_hash: hash,
+                captured_at,
+                path,
+                received: 0,
+                hasher: Sha256::new(),
+                updated_at: now,
+                writing: false,
+            },
+        );
+        Ok(UploadProgress { id, size, received: 0 })
+    }
+
+    /// 查询会话已收到的字节数。
+    ///
+    /// # Arguments
+    /// * `id` - 会话标识
+    /// * `device` - 发起查询的设备，只能查询自己的会话
+    pub(crate) fn progress(&self, id: &str, device: Option<&str>) -> Result<UploadProgress, SessionError> {
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get(id)
+            .filter(|session| session.device.as_deref() == device)
+            .ok_or(SessionError::NotFound)?;
+        Ok(UploadProgress {
+            id: id.to_string(),
+            size: session.size,
+            received: session.received,
+        })
+    }
+
+    /// 申请在指定偏移写入分块，偏移必须等于已收到的字节数。
+    ///
+    /// # Arguments
+    /// * `id` - 会话标识
+    /// * `device` - 发起上传的设备
+    /// * `offset` - 分块的起始位置
+    /// * `now` - 当前时间
+    pub(crate) fn claim(&self, id: &str, device: Option<&str>, offset: u64, now: Instant) -> Result<ChunkSlot, SessionError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(id)
+            .filter(|session| session.device.as_deref() == device)
+            .ok_or(SessionError::NotFound)?;
+        if session.writing {
+            return Err(SessionError::Busy);
+        }
+        if offset != session.received {
+            return Err(SessionError::OffsetMismatch { received: session.received });
+        }
+        session.writing = true;
+        session.updated_at = now;
+        Ok(ChunkSlot {
+            path: session.path.clone(),
+            offset,
+            remaining: session.size - session.received,
+            hasher: session.hasher.clone(),
+        })
+    }
+
+    /// 交回写入结束的分块，记录实际写入的字节数。
+    ///
+    /// # Arguments
+    /// * `id` - 会话标识
+    /// * `written` - 本次写入的字节数，写入中断时只计已写入的部分
+    /// * `hasher` - 包含本次写入内容的哈希状态
+    /// * `now` - 当前时间
+    ///
+    /// # Returns
+    /// 会话已收到的字节数
+    pub(crate) fn release(&self, id: &str, written: u64, hasher: Sha256, now: Instant) -> Option<u64> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(id)?;
+        session.received += written;
+        session.hasher = hasher;
+        session.updated_at = now;
+        session.writing = false;
+        Some(session.received)
+    }
+
+    /// 结束已收到全部内容的会话，临时文件交由调用方处理。
+    ///
+    /// # Arguments
+    /// * `id` - 会话标识
+    /// * `device` - 发起请求的设备
+    pub(crate) fn finish(&self, id: &str, device: Option<&str>) -> Result<FinishedUpload, SessionError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get(id)
+            .filter(|session| session.device.as_deref() == device)
+            .ok_or(SessionError::NotFound)?;
+        if session.writing {
+            return Err(SessionError::Busy);
+        }
+        if session.received < session.size {
+            return Err(SessionError::Incomplete { received: session.received });
+        }
+        let session = sessions.remove(id).ok_or(SessionError::NotFound)?;
+        Ok(FinishedUpload {
+            path: session.path,
+            file_name: session.file_name,
+            size: session.size,
+            expected_hash: session.expected_hash,
+            hash: hex::encode(session.hasher.finalize()),
+            captured_at: session.captured_at,
+        })
+    }
+
+    /// 退出时删除全部会话与临时文件。
+    ///
+    /// # Returns
+    /// 删除的文件数
+    pub(crate) fn discard_all(&self) -> usize {
+        let paths: Vec<PathBuf> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, session)| session.path)
+            .collect();
+        paths.into_iter().filter(|path| remove_part(path)).count()
+    }
+
+    /// 删除超过续传时限的会话与临时文件，以及上次运行遗留的临时文件。
+    ///
+    /// # Arguments
+    /// * `now` - 当前时间
+    ///
+    /// # Returns
+    /// 删除的文件数
+    pub(crate) fn collect_garbage(&self, now: Instant) -> usize {
+        let window = self.window();
+        let (expired, live) = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let mut expired = Vec::new();
+            sessions.retain(|id, session| {
+                let keep = session.writing || now.duration_since(session.updated_at) < window;
+                if !keep {
+                    tracing::info!("Resumable upload {} expired after {} bytes", id, session.received);
+                    expired.push(session.path.clone());
+                }
+                keep
+            });
+            let live: Vec<PathBuf> = sessions.values().map(|session| session.path.clone()).collect();
+            (expired, live)
+        };
+
+        let mut count = 0;
+        for path in expired {
+            if remove_part(&path) {
+                count += 1;
+            }
+        }
+
+        // 会话只保存在内存中，重启前未完成的上传无法续传，文件超过时限后删除
+        if let Ok(entries) = std::fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !is_part_file(&path) || live.contains(&path) {
+                    continue;
+                }
+                let stale = entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_some_and(|age| age >= window);
+                if stale && remove_part(&path) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+/// 是否为分块上传的临时文件。
+fn is_part_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    name.starts_with(PART_PREFIX) && path.extension().is_some_and(|extension| extension == PART_EXTENSION)
+}
+
+/// 删除临时文件，文件已不存在时视为未删除。
+fn remove_part(path: &Path) -> bool {
+    match std::fs::remove_file(path) {
+        Ok(()) => true,
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to delete stale upload {:?}: {:?}", path, e);
+            }
+            false
+        }
+    }
+}
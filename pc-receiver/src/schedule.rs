@@ -0,0 +1,337 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 计划暂停模块。
+ * 在配置的时段内拒绝接收任何内容（与只静音通知的免打扰不同），
+ * 返回 503 与恢复时间，手机端可暂存后在时段结束后重试。
+ */
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Datelike, Duration, Local, LocalResult, NaiveDateTime, NaiveTime, Offset, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use crate::state::AppState;
+
+/// 一个每周重复的暂停时段。
+///
+/// 结束时间不晚于开始时间时视为跨越午夜，例如周五 22:00 到周六 06:00 在配置文件中写作
+/// `[[pause_schedule]]` 段下的 `days = ["fri"]`、`start = "22:00:00"`、`end = "06:00:00"`。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PauseWindow {
+    /// 时段开始的星期，例如 `["mon", "tue"]`
+    pub days: Vec<Weekday>,
+    /// 开始时间（本地时间）
+    pub start: NaiveTime,
+    /// 结束时间（本地时间）
+    pub end: NaiveTime,
+}
+
+/// 计划暂停时段集合。
+#[derive(Debug, Clone, Default)]
+pub struct PauseSchedule {
+    windows: Vec<PauseWindow>,
+}
+
+impl PauseSchedule {
+    /// 创建计划暂停，空列表表示从不暂停。
+    ///
+    /// # Arguments
+    /// * `windows` - 暂停时段，允许相互重叠
+    pub fn new(windows: Vec<PauseWindow>) -> anyhow::Result<Self> {
+        if let Some(window) = windows.iter().find(|window| window.days.is_empty()) {
+            anyhow::bail!("Pause window {}-{} has no days", window.start, window.end);
+        }
+        Ok(Self { windows })
+    }
+
+    /// 配置的暂停时段。
+    pub fn windows(&self) -> &[PauseWindow] {
+        &self.windows
+    }
+
+    /// 是否没有配置任何时段。
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// 判断给定时刻是否处于暂停中。
+    ///
+    /// # Arguments
+    /// * `now` - 本地时间
+    ///
+    /// # Returns
+    /// 暂停中时返回恢复时间。相互重叠或首尾相接的时段合并计算，返回最终的恢复时间
+    pub fn paused_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let intervals = self.intervals_around(now);
+
+        let mut until = intervals
+            .iter()
+            .filter(|(start, end)| *start <= now && now < *end)
+            .map(|(_, end)| *end)
+            .max()?;
+
+        // 不断向后合并与当前暂停相连的时段
+        while let Some(end) = intervals
+            .iter()
+            .filter(|(start, end)| *start <= until && *end > until)
+            .map(|(_, end)| *end)
+            .max()
+        {
+            until = end;
+        }
+        Some(until)
+    }
+
+    /// 下一次暂停开始的时间，当前正在暂停时返回 None。
+    ///
+    /// # Arguments
+    /// * `now` - 本地时间
+    pub fn next_pause(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.paused_until(now).is_some() {
+            return None;
+        }
+        self.intervals_around(now)
+            .into_iter()
+            .map(|(start, _)| start)
+            .filter(|start| *start > now)
+            .min()
+    }
+
+    /// 展开前一天到之后一周内的所有具体时段。
+    fn intervals_around(&self, now: NaiveDateTime) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let today = now.date();
+        let mut intervals = Vec::new();
+
+        for offset in -1..=7 {
+            let date = today + Duration::days(offset);
+            for window in self.windows.iter().filter(|window| window.days.contains(&date.weekday())) {
+                let start = date.and_time(window.start);
+                let end = if window.end > window.start {
+                    date.and_time(window.end)
+                } else {
+                    (date + Duration::days(1)).and_time(window.end)
+                };
+                intervals.push((start, end));
+            }
+        }
+        intervals
+    }
+}
+
+/// 计划暂停期间拒绝所有载荷请求。
+pub(crate) async fn pause_guard(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(until) = scheduled_pause(&state.schedule.read().unwrap()) else {
+        return next.run(request).await;
+    };
+
+    tracing::info!("Rejected {} during scheduled pause until {}", request.uri().path(), until.to_rfc3339());
+    let retry_after = (until - Local::now()).num_seconds().max(1);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        Json(json!({ "error": "scheduled_pause", "until": until.to_rfc3339() })),
+    )
+        .into_response()
+}
+
+/// 按当前本地时间判断是否处于计划暂停中。
+///
+/// # Returns
+/// 暂停中时返回恢复时间
+pub(crate) fn scheduled_pause(schedule: &PauseSchedule) -> Option<DateTime<Local>> {
+    if schedule.is_empty() {
+        return None;
+    }
+    scheduled_pause_at(schedule, Local::now())
+}
+
+/// 判断给定时刻是否处于计划暂停中，时段按 `now` 所在时区的本地时间计算。
+///
+/// # Arguments
+/// * `schedule` - 计划暂停
+/// * `now` - 当前时刻
+///
+/// # Returns
+/// 暂停中时返回恢复时间
+fn scheduled_pause_at<Tz: TimeZone>(schedule: &PauseSchedule, now: DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let until = schedule.paused_until(now.naive_local())?;
+    Some(resolve_local(&now.timezone(), until))
+}
+
+/// 将本地时间换算为时区中的时刻。
+/// 夏令时结束时重复的时刻取较早的一个；夏令时开始时跳过的时刻不存在，按跳变前的偏移换算，
+/// 即顺延跳过的时长，例如 02:30 在 02:00 跳到 03:00 的当天为 03:30。
+fn resolve_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time,
+        LocalResult::None => {
+            // 跳变不超过数小时，三小时前的偏移即为跳变前的偏移
+            let offset = tz
+                .offset_from_local_datetime(&(local - Duration::hours(3)))
+                .earliest()
+                .map(|offset| offset.fix())
+                .unwrap_or_else(|| tz.offset_from_utc_datetime(&local).fix());
+            let utc = local - Duration::seconds(offset.local_minus_utc().into());
+            tz.from_utc_datetime(&utc)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, MappedLocalTime, NaiveDate};
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> PauseWindow {
+        PauseWindow {
+            days: days.to_vec(),
+            start: NaiveTime::parse_from_str(start, "%H:%M").unwrap(),
+            end: NaiveTime::parse_from_str(end, "%H:%M").unwrap(),
+        }
+    }
+
+    #[test]
+    fn window_crosses_midnight() {
+        // 2026-10-16 为周五
+        let schedule = PauseSchedule::new(vec![window(&[Weekday::Fri], "22:00", "06:00")]).unwrap();
+        let cases = [
+            (at("2026-10-16", "21:59"), None),
+            (at("2026-10-16", "22:00"), Some(at("2026-10-17", "06:00"))),
+            (at("2026-10-16", "23:59"), Some(at("2026-10-17", "06:00"))),
+            (at("2026-10-17", "00:00"), Some(at("2026-10-17", "06:00"))),
+            (at("2026-10-17", "05:59"), Some(at("2026-10-17", "06:00"))),
+            (at("2026-10-17", "06:00"), None),
+            // 时段只属于周五，周四晚上不暂停
+            (at("2026-10-15", "23:00"), None),
+        ];
+        for (now, expected) in cases {
+            assert_eq!(schedule.paused_until(now), expected, "at {}", now);
+        }
+        assert_eq!(schedule.next_pause(at("2026-10-16", "21:59")), Some(at("2026-10-16", "22:00")));
+        assert_eq!(schedule.next_pause(at("2026-10-17", "06:00")), Some(at("2026-10-23", "22:00")));
+    }
+
+    #[test]
+    fn sunday_window_wraps_into_monday() {
+        let schedule = PauseSchedule::new(vec![window(&[Weekday::Sun], "23:00", "01:00")]).unwrap();
+        assert_eq!(schedule.paused_until(at("2026-10-19", "00:30")), Some(at("2026-10-19", "01:00")));
+        assert_eq!(schedule.paused_until(at("2026-10-19", "01:00")), None);
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_windows_merge() {
+        let schedule = PauseSchedule::new(vec![
+            window(&[Weekday::Mon], "09:00", "12:00"),
+            window(&[Weekday::Mon], "11:00", "14:00"),
+            window(&[Weekday::Mon], "14:00", "15:00"),
+            window(&[Weekday::Mon], "16:00", "17:00"),
+        ])
+        .unwrap();
+        let cases = [
+            (at("2026-10-19", "08:59"), None),
+            (at("2026-10-19", "09:00"), Some(at("2026-10-19", "15:00"))),
+            (at("2026-10-19", "11:30"), Some(at("2026-10-19", "15:00"))),
+            (at("2026-10-19", "14:30"), Some(at("2026-10-19", "15:00"))),
+            (at("2026-10-19", "15:00"), None),
+            (at("2026-10-19", "16:00"), Some(at("2026-10-19", "17:00"))),
+        ];
+        for (now, expected) in cases {
+            assert_eq!(schedule.paused_until(now), expected, "at {}", now);
+        }
+        assert_eq!(schedule.next_pause(at("2026-10-19", "10:00")), None);
+        assert_eq!(schedule.next_pause(at("2026-10-19", "15:00")), Some(at("2026-10-19", "16:00")));
+    }
+
+    #[test]
+    fn empty_days_are_rejected() {
+        assert!(PauseSchedule::new(vec![window(&[], "22:00", "06:00")]).is_err());
+    }
+
+    /// 2026-03-29 当地 02:00 从 +01:00 跳到 +02:00 的时区，02:00 到 03:00 不存在。
+    #[derive(Debug, Clone, Copy)]
+    struct SpringForward;
+
+    impl SpringForward {
+        fn gap() -> (NaiveDateTime, NaiveDateTime) {
+            (at("2026-03-29", "02:00"), at("2026-03-29", "03:00"))
+        }
+
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(3600).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(7200).unwrap()
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> MappedLocalTime<FixedOffset> {
+            let (start, end) = Self::gap();
+            if *local < start {
+                LocalResult::Single(Self::winter())
+            } else if *local < end {
+                LocalResult::None
+            } else {
+                LocalResult::Single(Self::summer())
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < at("2026-03-29", "01:00") {
+                Self::winter()
+            } else {
+                Self::summer()
+            }
+        }
+    }
+
+    #[test]
+    fn pause_ending_in_a_dst_gap_is_shifted_forward() {
+        // 2026-03-29 为周日，时段在不存在的 02:30 结束
+        let schedule = PauseSchedule::new(vec![window(&[Weekday::Sat], "22:00", "02:30")]).unwrap();
+        let now = SpringForward.from_local_datetime(&at("2026-03-29", "01:30")).unwrap();
+
+        let until = scheduled_pause_at(&schedule, now).expect("still paused before the gap");
+        assert_eq!(until.naive_local(), at("2026-03-29", "03:30"));
+        assert_eq!(until.naive_utc(), at("2026-03-29", "01:30"));
+    }
+
+    #[test]
+    fn times_outside_a_gap_resolve_normally() {
+        let cases = [
+            (at("2026-03-29", "01:59"), at("2026-03-29", "00:59")),
+            (at("2026-03-29", "02:00"), at("2026-03-29", "01:00")),
+            (at("2026-03-29", "03:00"), at("2026-03-29", "01:00")),
+            (at("2026-03-29", "04:00"), at("2026-03-29", "02:00")),
+        ];
+        for (local, utc) in cases {
+            assert_eq!(resolve_local(&SpringForward, local).naive_utc(), utc, "local {}", local);
+        }
+    }
+}
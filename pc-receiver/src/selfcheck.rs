@@ -0,0 +1,294 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 启动自检模块。
+ * 启动后依次检查配置文件、端口监听、mDNS 广播、系统通知设置、本机回环请求与保存目录，
+ * 汇总为一份报告供 `/diagnose` 与托盘查看，只有存在问题时才弹出一次通知。
+ * 每项检查都只依赖传入的结果或接口，可以单独替换。
+ */
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::notifier::Notifier;
+use crate::storage::StorageMonitor;
+
+/// 回环请求的超时时间。
+const LOOPBACK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 单项检查的结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// 正常
+    Ok,
+    /// 未启用或无法判断，不影响接收
+    Skipped,
+    /// 存在问题
+    Failed,
+}
+
+/// 单项检查。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    /// 检查项标识
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// 详细说明，写入日志与报告
+    pub detail: String,
+    /// 失败时通知中显示的简短原因，例如“通知被禁用”
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub problem: Option<String>,
+}
+
+impl CheckResult {
+    pub fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Ok, detail: detail.into(), problem: None }
+    }
+
+    pub fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Skipped, detail: detail.into(), problem: None }
+    }
+
+    pub fn failed(name: &'static str, problem: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Failed,
+            detail: detail.into(),
+            problem: Some(problem.into()),
+        }
+    }
+}
+
+/// 启动自检报告。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StartupReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl StartupReport {
+    pub fn new(checks: Vec<CheckResult>) -> Self {
+        Self { checks }
+    }
+
+    /// 失败的检查项。
+    pub fn problems(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| check.status == CheckStatus::Failed)
+    }
+
+    /// 所有检查均未失败。
+    pub fn is_healthy(&self) -> bool {
+        self.problems().next().is_none()
+    }
+
+    /// 需要弹出的通知标题，一切正常时为 None。
+    pub fn alert_title(&self) -> Option<String> {
+        let problems: Vec<&str> = self.problems().filter_map(|check| check.problem.as_deref()).collect();
+        (!problems.is_empty()).then(|| format!("FastSync 启动异常: {}", problems.join("、")))
+    }
+
+    /// 托盘状态窗口中显示的逐项结果。
+    pub fn describe(&self) -> String {
+        self.checks
+            .iter()
+            .map(|check| {
+                let mark = match check.status {
+                    CheckStatus::Ok => "✓",
+                    CheckStatus::Skipped => "-",
+                    CheckStatus::Failed => "✗",
+                };
+                format!("{} {}: {}", mark, check.name, check.detail)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// 端口监听结果。任一地址无法监听时服务不会运行，这里只记录实际地址。
+///
+/// # Arguments
+/// * `addrs` - 实际监听的地址
+/// * `fallback_from` - 配置的端口被占用而改用其他端口时为配置的端口
+pub fn check_bind(addrs: &[SocketAddr], fallback_from: Option<u16>) -> CheckResult {
+    let addrs: Vec<String> = addrs.iter().map(SocketAddr::to_string).collect();
+    let mut detail = format!("listening on {}", addrs.join(", "));
+    if let Some(port) = fallback_from {
+        detail.push_str(&format!(" (port {} was in use)", port));
+    }
+    CheckResult::ok("bind", detail)
+}
+
+/// mDNS 广播注册结果。
+///
+/// # Arguments
+/// * `result` - 注册结果，未启用广播时为 None
+pub fn check_mdns<T>(result: Option<&anyhow::Result<T>>) -> CheckResult {
+    match result {
+        None => CheckResult::skipped("mdns", "disabled"),
+        Some(Ok(_)) => CheckResult::ok("mdns", "registered"),
+        Some(Err(e)) => CheckResult::failed("mdns", "局域网广播失败", format!("{:#}", e)),
+    }
+}
+
+/// 配置文件能否读取并通过检查。有误时服务使用默认设置运行。
+///
+/// # Arguments
+/// * `path` - 配置文件路径
+pub fn check_config(path: &Path) -> CheckResult {
+    match crate::config::load_config(path) {
+        Ok(_) => CheckResult::ok("config", path.display().to_string()),
+        Err(e) => CheckResult::failed("config", "配置文件有误", format!("{:#}", e)),
+    }
+}
+
+/// 系统是否允许本应用弹出通知。
+pub fn check_notifications(notifier: &dyn Notifier) -> CheckResult {
+    match notifier.is_enabled() {
+        Ok(true) => CheckResult::ok("notifications", "enabled"),
+        Ok(false) => CheckResult::failed("notifications", "通知被禁用", "disabled in system settings"),
+        Err(e) => CheckResult::failed("notifications", "通知不可用", format!("{:#}", e)),
+    }
+}
+
+/// 向本机发起一次 `/health` 请求，确认服务可以被访问。
+///
+/// # Arguments
+/// * `local_addr` - 实际监听地址，监听所有网卡时经回环地址访问
+pub async fn check_loopback(local_addr: SocketAddr) -> CheckResult {
+    let target = loopback_target(local_addr);
+    let request = async { request_health(tokio::net::TcpStream::connect(target).await?).await };
+    loopback_result(tokio::time::timeout(LOOPBACK_TIMEOUT, request).await)
+}
+
+/// 经 HTTPS 向本机发起一次 `/health` 请求，只信任本机的自签名证书。
+///
+/// # Arguments
+/// * `local_addr` - 实际监听地址
+/// * `certificate` - 监听端口使用的证书
+#[cfg(feature = "tls")]
+pub(crate) async fn check_loopback_https(local_addr: SocketAddr, certificate: &crate::tls::ServerCertificate) -> CheckResult {
+    let target = loopback_target(local_addr);
+    let connector = match certificate.connector() {
+        Ok(connector) => connector,
+        Err(e) => return CheckResult::failed("loopback", "本机无法访问服务", format!("{:#}", e)),
+    };
+    let request = async {
+        let stream = tokio::net::TcpStream::connect(target).await?;
+        let stream = connector.connect("localhost".try_into()?, stream).await?;
+        request_health(stream).await
+    };
+    loopback_result(tokio::time::timeout(LOOPBACK_TIMEOUT, request).await)
+}
+
+/// 监听所有网卡时经回环地址访问。
+pub(crate) fn loopback_target(local_addr: SocketAddr) -> SocketAddr {
+    match local_addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), local_addr.port()),
+        IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), local_addr.port()),
+        _ => local_addr,
+    }
+}
+
+/// 发送 `/health` 请求并返回响应的状态行。
+async fn request_health<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S) -> anyhow::Result<String> {
+    stream
+        .write_all(b"GET /health HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+        .await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    Ok(String::from_utf8_lossy(&response).lines().next().unwrap_or("").to_string())
+}
+
+/// 回环请求的结果，超时与非 200 响应均计为失败。
+fn loopback_result(result: Result<anyhow::Result<String>, tokio::time::error::Elapsed>) -> CheckResult {
+    match result {
+        Ok(Ok(status)) if status.contains(" 200 ") => CheckResult::ok("loopback", status),
+        Ok(Ok(status)) => CheckResult::failed("loopback", "本机无法访问服务", format!("unexpected response: {}", status)),
+        Ok(Err(e)) => CheckResult::failed("loopback", "本机无法访问服务", format!("{:#}", e)),
+        Err(_) => CheckResult::failed("loopback", "本机无法访问服务", format!("timed out after {:?}", LOOPBACK_TIMEOUT)),
+    }
+}
+
+/// 目录是否可写并留有足够空间。
+///
+/// # Arguments
+/// * `name` - 检查项标识
+/// * `storage` - 记录预检结果的监视器，托盘警告沿用同一结果
+/// * `dir` - 目录
+pub(crate) fn check_storage(name: &'static str, storage: &StorageMonitor, dir: &Path) -> CheckResult {
+    match storage.check(dir, 0) {
+        Ok(()) => CheckResult::ok(name, dir.display().to_string()),
+        Err(issue) => CheckResult::failed(name, "保存目录不可用", format!("{}: {}", dir.display(), issue.describe())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::MockNotifier;
+
+    #[test]
+    fn passing_and_skipped_checks_raise_no_alert() {
+        let report = StartupReport::new(vec![
+            CheckResult::ok("bind", "listening on 0.0.0.0:3000"),
+            CheckResult::skipped("mdns", "disabled"),
+            CheckResult::ok("loopback", "HTTP/1.1 200 OK"),
+        ]);
+        assert!(report.is_healthy());
+        assert_eq!(report.problems().count(), 0);
+        assert_eq!(report.alert_title(), None);
+        assert_eq!(report.describe(), "✓ bind: listening on 0.0.0.0:3000\n- mdns: disabled\n✓ loopback: HTTP/1.1 200 OK");
+    }
+
+    #[test]
+    fn failures_are_combined_into_one_alert() {
+        let report = StartupReport::new(vec![
+            CheckResult::failed("config", "配置文件有误", "expected `=`"),
+            CheckResult::ok("bind", "listening on 0.0.0.0:3000"),
+            CheckResult::failed("mdns", "局域网广播失败", "no interfaces"),
+            CheckResult::failed("notifications", "通知被禁用", "disabled in system settings"),
+        ]);
+        assert!(!report.is_healthy());
+        let failed: Vec<&str> = report.problems().map(|check| check.name).collect();
+        assert_eq!(failed, ["config", "mdns", "notifications"]);
+        assert_eq!(
+            report.alert_title().as_deref(),
+            Some("FastSync 启动异常: 配置文件有误、局域网广播失败、通知被禁用")
+        );
+    }
+
+    #[test]
+    fn individual_checks_map_their_inputs() {
+        let cases = [
+            (check_mdns::<()>(None), CheckStatus::Skipped),
+            (check_mdns(Some(&Ok(()))), CheckStatus::Ok),
+            (check_mdns::<()>(Some(&Err(anyhow::anyhow!("no interfaces")))), CheckStatus::Failed),
+            (loopback_result(Ok(Ok("HTTP/1.1 200 OK".to_string()))), CheckStatus::Ok),
+            (loopback_result(Ok(Ok("HTTP/1.1 503 Service Unavailable".to_string()))), CheckStatus::Failed),
+            (loopback_result(Ok(Err(anyhow::anyhow!("connection refused")))), CheckStatus::Failed),
+        ];
+        for (check, status) in cases {
+            assert_eq!(check.status, status, "{:?}", check);
+            assert_eq!(check.problem.is_some(), status == CheckStatus::Failed, "{:?}", check);
+        }
+
+        let notifier = MockNotifier::new();
+        assert_eq!(check_notifications(&notifier).status, CheckStatus::Ok);
+        notifier.set_enabled(false);
+        assert_eq!(check_notifications(&notifier).problem.as_deref(), Some("通知被禁用"));
+    }
+
+    #[test]
+    fn unspecified_addresses_are_checked_through_loopback() {
+        let cases = [
+            ("0.0.0.0:3000", "127.0.0.1:3000"),
+            ("[::]:3000", "[::1]:3000"),
+            ("192.168.1.5:3000", "192.168.1.5:3000"),
+        ];
+        for (local, target) in cases {
+            assert_eq!(loopback_target(local.parse().unwrap()), target.parse().unwrap(), "{}", local);
+        }
+    }
+}
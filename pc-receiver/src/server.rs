@@ -0,0 +1,1753 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 服务构建与生命周期模块。
+ * 对外提供 `FastSyncServer::builder()`，供独立程序或其他应用嵌入使用。
+ */
+use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post, put},
+    Extension, Router,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tower_http::catch_panic::CatchPanicLayer;
+use fastsync_core::dedup::{self, ClipboardPushes, RecentUploads};
+use fastsync_core::timeline::ClockSkew;
+use crate::access_log;
+use crate::admission::{self, Pipeline, PipelineLimits};
+use crate::attention::{Attention, AttentionConfig, DisplayWaker, SystemDisplayWaker};
+use crate::audit::{self, AuditLog, AuditRecord};
+use crate::body_limit::{self, BodyLimits};
+use crate::burst::BurstTracker;
+use crate::auth;
+use crate::config::{self, AccessToken, BindAddress, Config, ConfigFile, RateLimitConfig};
+use crate::clipboard::{self, ClipboardBackend, ClipboardHistory, ClipboardWatch, NullClipboard};
+use crate::compression;
+use crate::decode_pool::DecodePool;
+use crate::cors::{self, CorsConfig};
+use crate::events::{EventBus, EventHandler, ServerEvent};
+use crate::handlers;
+use crate::idempotency::{self, IdempotencyStore};
+use crate::image_format::{self, ImageFormat};
+use crate::listen;
+use crate::metadata::MetadataStripping;
+use crate::notifier::queue::NotificationQueue;
+use crate::notifier::{self, Notifier, NullNotifier};
+use crate::devices::{self, DeviceRegistry};
+use crate::pairing::{GuestAccess, PairedDevice, PairingAttempt, PairingStore, PairingThrottle, PairingUri};
+use crate::payload::{self, PayloadHandler};
+use crate::policy::{ContentPolicy, PolicyEngine};
+use crate::rate_limit::{self, RateLimiter};
+use crate::realtime::{ServerMessage, Sessions, CLOSE_SHUTDOWN};
+use crate::resumable::{self, ResumableUploads};
+use crate::schedule::{self, PauseSchedule};
+use crate::sms_code::CodeExtractor;
+use crate::sms_filter::SmsFilter;
+use crate::sms_history::{self, SmsHistory, SmsRetention};
+use crate::selfcheck::{self, StartupReport};
+use crate::state::{AppState, AutoSave, RunMode};
+use crate::storage::StorageMonitor;
+use crate::suppression::{MissedDigest, MissedTracker, NotificationStateProbe, SuppressionSource, SystemNotificationState};
+use crate::temp_files;
+use crate::thumbnails::PhotoIndex;
+use crate::timings::{LastItem, TimingStats};
+use crate::versioning;
+
+/// 默认监听端口。
+pub const DEFAULT_PORT: u16 = 3000;
+
+/// 查询系统通知抑制状态的间隔。
+const SUPPRESSION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 检查配置文件是否被修改的间隔。
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 关闭时等待进行中请求完成的最长时间，超时后不再等待，保证进程按时退出。
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `FastSyncServer` 构建器。
+pub struct FastSyncServerBuilder {
+    config: Config,
+    config_file: Option<PathBuf>,
+    mode: RunMode,
+    notifier: Option<Arc<dyn Notifier>>,
+    clipboard: Option<Arc<dyn ClipboardBackend>>,
+    audit_log: Option<PathBuf>,
+    sms_history: Option<PathBuf>,
+    devices_file: Option<PathBuf>,
+    port_hint: Option<PathBuf>,
+    /// 直接传入的验证码提取器，优先于配置中的 `[sms.codes]`
+    sms_codes: Option<CodeExtractor>,
+    display_waker: Arc<dyn DisplayWaker>,
+    notification_state: Arc<dyn NotificationStateProbe>,
+    event_handlers: Vec<EventHandler>,
+    handlers: Vec<Arc<dyn PayloadHandler>>,
+    #[cfg(feature = "tls")]
+    tls_dir: Option<PathBuf>,
+    #[cfg(feature = "tls")]
+    certificate_dir: Option<PathBuf>,
+}
+
+impl FastSyncServerBuilder {
+    fn new() -> Self {
+        Self {
+            config: Config::default(),
+            config_file: None,
+            mode: RunMode::Desktop,
+            notifier: None,
+            clipboard: None,
+            audit_log: audit::default_audit_path(),
+            sms_history: sms_history::default_sms_history_path(),
+            devices_file: devices::default_devices_path(),
+            port_hint: listen::default_port_hint_path(),
+            sms_codes: None,
+            display_waker: Arc::new(SystemDisplayWaker),
+            notification_state: Arc::new(SystemNotificationState),
+            event_handlers: Vec::new(),
+            handlers: vec![
+                Arc::new(handlers::photo::PhotoHandler),
+                Arc::new(handlers::photo::RawUploadHandler),
+                Arc::new(handlers::photo::FileUploadHandler),
+                Arc::new(handlers::resumable::ResumableCompleteHandler),
+                Arc::new(handlers::sms::SmsHandler),
+                Arc::new(handlers::sms::SmsBatchHandler),
+                Arc::new(handlers::clipboard::ClipboardHandler),
+            ],
+            #[cfg(feature = "tls")]
+            tls_dir: crate::tls::default_tls_dir(),
+            #[cfg(feature = "tls")]
+            certificate_dir: crate::tls::default_certificate_dir(),
+        }
+    }
+
+    /// 使用配置文件中的设置，应在其他设置之前调用，之后的设置覆盖配置中的值。
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 运行中监视配置文件，文件修改后或调用 `reload_config` 时重新载入，
+    /// 端口、请求体上限等需重启的设置除外。文件有误时沿用当前配置，弹出通知说明原因。
+    pub fn config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// 设置监听端口，传入 0 时由系统分配。
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.server.port = port;
+        self
+    }
+
+    /// 端口被占用时是否依次改用其后的 10 个端口，仍被占用时由系统分配，默认开启。
+    /// 只影响未单独指定端口的监听地址，实际端口通过 `local_addr` 获取。
+    pub fn port_fallback(mut self, enabled: bool) -> Self {
+        self.config.server.port_fallback = enabled;
+        self
+    }
+
+    /// 设置监听地址，默认为 `0.0.0.0`，使用 `port` 设置的端口。
+    /// 启用 mTLS 或明文 HTTP 端口时，这些端口监听同一地址。
+    pub fn bind(mut self, addr: IpAddr) -> Self {
+        self.config.server.bind = vec![BindAddress::new(addr)];
+        self
+    }
+
+    /// 同时监听多个地址，所有地址提供同一套路由，例如只监听回环地址与 Tailscale 网卡，
+    /// 或以 `[::]` 同时接受 IPv4 与 IPv6。未写端口的地址使用 `port` 设置的端口。
+    pub fn bind_addresses(mut self, addrs: impl IntoIterator<Item = BindAddress>) -> Self {
+        self.config.server.bind = addrs.into_iter().collect();
+        self
+    }
+
+    /// 监听端口是否使用 HTTPS，启用 `tls` 特性时默认开启，未启用该特性时始终为明文 HTTP。
+    pub fn https(mut self, enabled: bool) -> Self {
+        self.config.server.https = enabled;
+        self
+    }
+
+    /// 启用 HTTPS 时另开明文 HTTP 端口，供不支持 HTTPS 的旧版手机端使用，传入 0 时由系统分配。
+    pub fn http_port(mut self, port: u16) -> Self {
+        self.config.server.http_port = Some(port);
+        self
+    }
+
+    /// 设置运行模式。非桌面模式下未显式指定的后端均为空实现。
+    pub fn mode(mut self, mode: RunMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 是否通过 mDNS 广播服务，启用 `mdns` 特性时默认开启。
+    pub fn mdns(mut self, enabled: bool) -> Self {
+        self.config.server.mdns = enabled;
+        self
+    }
+
+    /// 使用自定义通知后端。
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// 使用自定义剪贴板后端。
+    pub fn clipboard(mut self, clipboard: Arc<dyn ClipboardBackend>) -> Self {
+        self.clipboard = Some(clipboard);
+        self
+    }
+
+    /// 设置图片自动保存目录并在启动时启用自动保存。
+    /// 未设置时桌面模式默认使用保存对话框，可通过 `set_auto_save` 切换到默认目录。
+    pub fn auto_save_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.photos.auto_save_dir = Some(dir.into());
+        self
+    }
+
+    /// 设置图片通知中“快速保存”写入的目录，未设置时与默认的自动保存目录相同。
+    pub fn quick_save_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.photos.quick_save_dir = Some(dir.into());
+        self
+    }
+
+    /// 连续上传的聚合窗口：同一设备在窗口内开始上传的图片只更新同一条“收到 N 张图片”的通知，
+    /// 默认 3 秒，设为 0 时每张图片单独通知。
+    pub fn burst_window(mut self, window: Duration) -> Self {
+        self.config.photos.burst_window = window;
+        self
+    }
+
+    /// 在图片通知上显示“复制图片路径”按钮，点击后图片移动到 received 目录。
+    pub fn photo_path_actions(mut self, enabled: bool) -> Self {
+        self.config.photos.path_actions = enabled;
+        self
+    }
+
+    /// 是否丢弃同一设备在 10 分钟内重复上传的相同图片，默认开启。
+    /// 需要反复发送同一张截图时可以关闭。
+    pub fn deduplicate_uploads(mut self, enabled: bool) -> Self {
+        self.config.photos.deduplicate = enabled;
+        self
+    }
+
+    /// 是否丢弃复制时间早于同一设备最近一次推送的剪贴板内容，默认开启。
+    /// 手机时钟不可靠、推送顺序因此被打乱时可以关闭；复制时间与内容都相同的重发仍会丢弃。
+    pub fn clipboard_ordering(mut self, enabled: bool) -> Self {
+        self.config.clipboard.ordering = enabled;
+        self
+    }
+
+    /// 在内存中保留的手机剪贴板条数，默认 50，为 0 时不保留。本机可通过 `GET /clipboard/history` 查看。
+    pub fn clipboard_history(mut self, capacity: usize) -> Self {
+        self.config.clipboard.history = capacity;
+        self
+    }
+
+    /// 手机剪贴板文本超过该字节数时另存为临时文件，通知中提供“另存为文件”，默认 10 KB，为 0 时不提供。
+    pub fn clipboard_file_threshold(mut self, bytes: usize) -> Self {
+        self.config.clipboard.file_threshold = bytes;
+        self
+    }
+
+    /// 是否隐藏疑似密码、令牌、私钥、银行卡号或 OTP 密钥的手机剪贴板文本，默认关闭。
+    /// 开启后这类内容的通知不显示文本、不记入剪贴板历史、不自动同步，需点击复制。
+    pub fn clipboard_secret_filter(mut self, enabled: bool) -> Self {
+        self.config.clipboard.secret_filter = enabled;
+        self
+    }
+
+    /// 设置敏感内容复制后自动清空剪贴板的延迟，默认 30 秒，为 0 时不清空。期间复制过其他内容时不清空。
+    pub fn clipboard_secret_clear(mut self, delay: Duration) -> Self {
+        self.config.clipboard.secret_clear = delay;
+        self
+    }
+
+    /// 上传、短信与剪贴板接口每分钟的请求数上限，按设备或来源 IP 分别计数，为 0 时不限制。
+    /// 默认分别为 120、30 与 60 次，配置文件重新载入后立即生效。
+    pub fn rate_limits(mut self, limits: RateLimitConfig) -> Self {
+        self.config.rate_limit = limits;
+        self
+    }
+
+    /// 幂等键的记录有效期，默认 10 分钟，为 0 时不处理幂等键。上传、短信与剪贴板请求携带
+    /// `Idempotency-Key` 请求头（或 `request_id`）时，有效期内以相同的键重试直接返回第一次的响应。
+    pub fn idempotency_ttl(mut self, ttl: Duration) -> Self {
+        self.config.server.idempotency_ttl = ttl;
+        self
+    }
+
+    /// 分块上传的续传时限，超过该时间未收到新分块的上传连同已收到的内容一起删除，默认 1 小时。
+    pub fn resume_window(mut self, window: Duration) -> Self {
+        self.config.uploads.resume_window = window;
+        self
+    }
+
+    /// 临时图片的保留时长，启动时与之后每小时删除超过该时长的文件，默认 24 小时。
+    pub fn temp_max_age(mut self, max_age: Duration) -> Self {
+        self.config.uploads.temp_max_age = max_age;
+        self
+    }
+
+    /// 复制图片时同时解码的图片数，连续点击多条通知的"复制"时其余任务按顺序排队，默认 2。
+    pub fn decode_workers(mut self, workers: usize) -> Self {
+        self.config.photos.decode_workers = workers;
+        self
+    }
+
+    /// 收到的 JPEG 在保存之前移除 GPS 信息或整个 EXIF，默认不处理。
+    pub fn strip_metadata(mut self, mode: MetadataStripping) -> Self {
+        self.config.photos.strip_metadata = mode;
+        self
+    }
+
+    /// 通过保存对话框或自动保存写入 HEIC 图片时转换为 JPEG，方便没有 HEIC 解码器的电脑打开。
+    /// 需启用 `heic` 特性，否则仍保存原始文件。
+    pub fn transcode_heic(mut self, enabled: bool) -> Self {
+        self.config.photos.heic_to_jpeg = enabled;
+        self
+    }
+
+    /// 识别图片文字时使用的语言，例如 `zh-Hans-CN`、`en-US`，需已安装对应的 Windows 语言包。
+    /// 默认跟随系统语言。
+    pub fn ocr_language(mut self, language: impl Into<String>) -> Self {
+        self.config.photos.ocr_language = Some(language.into());
+        self
+    }
+
+    /// 通用文件模式：`/upload` 也接收无法识别为图片的文件，通知只提供另存，不会自动保存。
+    /// 默认只接收图片，其他内容返回 415；可接收的图片类型由内容策略的 `allowed_types` 限定。
+    pub fn accept_generic_files(mut self, enabled: bool) -> Self {
+        self.config.photos.accept_files = enabled;
+        self
+    }
+
+    /// 设置操作审计记录文件，默认为本地数据目录下的 `FastSync/audit.jsonl`。
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log = Some(path.into());
+        self
+    }
+
+    /// 设置已配对设备文件，默认为漫游配置目录下的 `FastSync/devices.json`（Windows 上位于 `%APPDATA%`）。
+    pub fn devices_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.devices_file = Some(path.into());
+        self
+    }
+
+    /// 设置记录实际监听端口的文件，默认为漫游配置目录下的 `FastSync/port.json`（Windows 上位于 `%APPDATA%`）。
+    /// 下次启动时优先使用记录的端口，端口曾被占用而改用其他端口时，手机缓存的地址在重启后仍然可用。
+    pub fn port_hint_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.port_hint = Some(path.into());
+        self
+    }
+
+    /// 设置短信历史数据库文件，默认为漫游配置目录下的 `FastSync/sms_history.db`（Windows 上位于 `%APPDATA%`）。
+    pub fn sms_history(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sms_history = Some(path.into());
+        self
+    }
+
+    /// 短信历史的保留上限，默认 5000 条、30 天。
+    pub fn sms_retention(mut self, retention: SmsRetention) -> Self {
+        self.config.sms.history_rows = retention.max_rows;
+        self.config.sms.history_days = retention.max_age_days;
+        self
+    }
+
+    /// 设置短信通知的过滤规则，默认不过滤。配置文件重新载入后以文件中的 `[sms.filter]` 为准。
+    pub fn sms_filter(mut self, filter: SmsFilter) -> Self {
+        self.config.sms.filter = filter;
+        self
+    }
+
+    /// 在指定端口上启用双向 TLS，传入 0 时由系统分配。未启用 `tls` 特性时忽略。
+    /// 启用后载荷接口只在该端口上提供，配对时签发客户端证书，
+    /// 普通 HTTP 端口仅保留配对与状态查询。
+    pub fn mtls(mut self, port: u16) -> Self {
+        self.config.server.mtls_port = Some(port);
+        self
+    }
+
+    /// 设置 mTLS CA 存放目录，默认为本地数据目录下的 `FastSync/tls`。
+    #[cfg(feature = "tls")]
+    pub fn tls_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.tls_dir = Some(dir.into());
+        self
+    }
+
+    /// 设置 HTTPS 证书存放目录，默认为漫游配置目录下的 `FastSync`。
+    #[cfg(feature = "tls")]
+    pub fn certificate_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.certificate_dir = Some(dir.into());
+        self
+    }
+
+    /// 设置内容策略，默认不做限制。
+    pub fn policy(mut self, policy: ContentPolicy) -> Self {
+        self.config.policy = policy;
+        self
+    }
+
+    /// 设置计划暂停时段，时段内所有载荷请求返回 503，与通知免打扰相互独立。
+    pub fn pause_schedule(mut self, schedule: PauseSchedule) -> Self {
+        self.config.pause_schedule = schedule.windows().to_vec();
+        self
+    }
+
+    /// 设置短信的过期时间，默认 10 分钟。手机端补发的短信在手机上收到后超过该时长才到达时，
+    /// 只写入短信历史而不弹通知；为 0 时不限制。没有时间戳的短信不受影响。
+    pub fn sms_stale_after(mut self, stale_after: Duration) -> Self {
+        self.config.sms.stale_after = stale_after;
+        self
+    }
+
+    /// 设置验证码通知在通知中心保留的时长，默认 30 秒。验证码很快失效，普通短信仍保留 60 秒。
+    pub fn sms_code_expiry(mut self, expiry: Duration) -> Self {
+        self.config.sms.code_expiry = expiry;
+        self
+    }
+
+    /// 设置短信的去重窗口，默认 2 分钟。同一设备在窗口内重发的相同短信（发送方、原文与手机上的收到时间相同）
+    /// 直接返回成功，不再写入短信历史与弹出通知；为 0 时不去重。
+    pub fn sms_dedup_window(mut self, window: Duration) -> Self {
+        self.config.sms.dedup_window = window;
+        self
+    }
+
+    /// 设置短信验证码的提取规则，手机端发送的 `code` 为空时从短信原文中提取。
+    /// 未设置时使用内置规则加上配置中的 `[sms.codes]`。
+    pub fn sms_code_rules(mut self, extractor: CodeExtractor) -> Self {
+        self.sms_codes = Some(extractor);
+        self
+    }
+
+    /// 收到带验证码的短信时不等点击直接复制验证码，通知只保留“忽略”。剪贴板会被静默改写，默认关闭，
+    /// 可通过 `set_auto_copy_codes` 在运行时切换。
+    pub fn auto_copy_codes(mut self, enabled: bool) -> Self {
+        self.config.sms.auto_copy_codes = enabled;
+        self
+    }
+
+    /// 收到手机剪贴板时不等确认直接写入剪贴板，通知中提供“撤销”恢复之前的内容。默认关闭，
+    /// 先弹通知由用户确认；可通过 `set_auto_apply_clipboard` 在运行时切换。
+    pub fn auto_apply_clipboard(mut self, enabled: bool) -> Self {
+        self.config.clipboard.auto_apply = enabled;
+        self
+    }
+
+    /// 设置处理管线的上限，超过后载荷接口返回 503 与建议的重试时间。
+    pub fn pipeline_limits(mut self, limits: PipelineLimits) -> Self {
+        self.config.pipeline = limits;
+        self
+    }
+
+    /// 请求体大小上限，默认图片上传 200 MB、JSON 接口 10 MB。
+    /// 超过时返回 413，响应中的 `limit` 为允许的最大字节数。
+    pub fn body_limits(mut self, limits: BodyLimits) -> Self {
+        self.config.server.max_upload = limits.upload;
+        self.config.server.max_json = limits.json;
+        self
+    }
+
+    /// 设置数据接口共用的访问令牌，默认只接受配对设备的令牌。
+    pub fn access_token(mut self, token: impl Into<String>) -> Self {
+        self.config.server.token = Some(AccessToken::from(token.into()));
+        self
+    }
+
+    /// 是否拒绝未携带令牌的请求，默认拒绝。
+    pub fn require_token(mut self, required: bool) -> Self {
+        self.config.server.require_token = required;
+        self
+    }
+
+    /// 设置紧急通知的提醒方式，默认不唤醒显示器。
+    pub fn attention(mut self, config: AttentionConfig) -> Self {
+        self.config.attention = config;
+        self
+    }
+
+    /// 替换唤醒显示器的实现。
+    pub fn display_waker(mut self, waker: Arc<dyn DisplayWaker>) -> Self {
+        self.display_waker = waker;
+        self
+    }
+
+    /// 替换查询系统通知抑制状态（专注模式、全屏等）的实现。
+    pub fn notification_state(mut self, probe: Arc<dyn NotificationStateProbe>) -> Self {
+        self.notification_state = probe;
+        self
+    }
+
+    /// 设置跨域配置，默认只允许同源访问。配置有误时记录错误并按同源处理。
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        self.config.cors = cors;
+        self
+    }
+
+    /// 注册事件回调，回调在处理请求的线程上同步执行，应尽快返回。
+    pub fn on_event<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&ServerEvent) + Send + Sync + 'static,
+    {
+        self.event_handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// 注册载荷处理器，其能力标识会自动出现在 `/info` 与 mDNS TXT 记录中。
+    /// 与已注册处理器路径和方法均相同时替换原处理器。
+    pub fn handler(mut self, handler: impl PayloadHandler) -> Self {
+        self.handlers
+            .retain(|h| h.path() != handler.path() || h.method() != handler.method());
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// 构建服务，此时尚未监听端口。
+    pub fn build(self) -> FastSyncServer {
+        let desktop = self.mode == RunMode::Desktop;
+
+        image_format::register_decoders();
+        if self.config.photos.heic_to_jpeg && !ImageFormat::Heic.is_decodable() {
+            tracing::warn!("HEIC to JPEG conversion requested, but this build lacks the heic feature; originals will be saved");
+        }
+
+        let notifier = self.notifier.unwrap_or_else(|| {
+            if desktop {
+                notifier::default_notifier()
+            } else {
+                Arc::new(NullNotifier)
+            }
+        });
+        let clipboard = self.clipboard.unwrap_or_else(|| {
+            if desktop {
+                clipboard::default_clipboard()
+            } else {
+                Arc::new(NullClipboard)
+            }
+        });
+        // 非桌面模式没有保存对话框，始终自动保存
+        let auto_save_dir = self.config.photos.auto_save_dir.clone();
+        let auto_save = AutoSave::new(
+            auto_save_dir.clone().unwrap_or_else(handlers::photo::default_auto_save_dir),
+            auto_save_dir.is_some() || !desktop,
+        );
+
+        if !desktop {
+            tracing::warn!(
+                "Running in {} mode: there is no interactive desktop, so toasts, clipboard and tray are disabled",
+                self.mode.as_str()
+            );
+            tracing::warn!("Received photos are auto-saved to {:?}; SMS and clipboard items are only logged", auto_save.dir());
+            let config = &self.config;
+            let desktop_only = [
+                ("sms.auto_copy_codes", config.sms.auto_copy_codes),
+                ("clipboard.auto_apply", config.clipboard.auto_apply),
+                ("attention.wake_display", !config.attention.wake_display.is_empty()),
+            ];
+            for (name, _) in desktop_only.iter().filter(|(_, enabled)| *enabled) {
+                tracing::warn!("{} is ignored in {} mode", name, self.mode.as_str());
+            }
+        }
+
+        let mut features: Vec<String> = Vec::new();
+        let mut endpoints: Vec<String> = Vec::new();
+        for handler in &self.handlers {
+            let capability = handler.capability().to_string();
+            if !features.contains(&capability) {
+                features.push(capability);
+            }
+            endpoints.push(format!("{} {}", handler.method(), handler.path()));
+        }
+        // 图片接口同样接收视频；所有数据接口都支持令牌鉴权
+        if features.iter().any(|feature| feature == "photo") {
+            features.push("video".to_string());
+        }
+        features.push("auth".to_string());
+
+        #[cfg(feature = "tls")]
+        let tls = self.config.server.mtls_port.and_then(|_| {
+            let dir = self.tls_dir.as_deref()?;
+            crate::tls::TlsAuthority::load_or_create(dir)
+                .map(Arc::new)
+                .map_err(|e| tracing::error!("Failed to load mTLS CA from {:?}: {:?}", dir, e))
+                .ok()
+        });
+        #[cfg(feature = "tls")]
+        let https = self.config.server.https.then(|| {
+            let dir = self.certificate_dir.as_deref()?;
+            crate::tls::ServerCertificate::load_or_create(dir)
+                .map(Arc::new)
+                .map_err(|e| tracing::error!("Failed to load HTTPS certificate from {:?}: {:?}", dir, e))
+                .ok()
+        }).flatten();
+        #[cfg(not(feature = "tls"))]
+        if self.config.server.https {
+            tracing::warn!("HTTPS requested but the tls feature is disabled; serving plain HTTP");
+        }
+        #[cfg(not(feature = "tls"))]
+        if self.config.server.mtls_port.is_some() {
+            tracing::warn!("mTLS requested but the tls feature is disabled; ignoring server.mtls_port");
+        }
+
+        // 配置已在载入时校验，直接传入构建器的配置有误时记录错误并使用默认值
+        let sms_codes = self.sms_codes.unwrap_or_else(|| {
+            CodeExtractor::new(self.config.sms.codes.clone()).unwrap_or_else(|e| {
+                tracing::error!("{:#}, using the built-in SMS code rules", e);
+                CodeExtractor::default()
+            })
+        });
+        let pause_schedule = PauseSchedule::new(self.config.pause_schedule.clone()).unwrap_or_else(|e| {
+            tracing::error!("{:#}, running without a pause schedule", e);
+            PauseSchedule::default()
+        });
+
+        let cors = self.config.cors.layer().unwrap_or_else(|e| {
+            tracing::error!("Invalid CORS configuration, falling back to same-origin only: {:#}", e);
+            None
+        });
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let devices = Arc::new(DeviceRegistry::open(self.devices_file));
+        let sms_history = Arc::new(SmsHistory::open(self.sms_history, self.config.sms.retention()));
+        let audit = Arc::new(AuditLog::new(self.audit_log).with_history(sms_history.clone()));
+        let timings = Arc::new(TimingStats::default());
+        let missed = Arc::new(MissedTracker::default());
+
+        let state = AppState {
+            mode: self.mode,
+            notifications: Arc::new(NotificationQueue::new(
+                notifier.clone(),
+                audit.clone(),
+                shutdown_rx,
+                timings.clone(),
+                missed.clone(),
+            )),
+            notifier,
+            clipboard,
+            clipboard_watch: Arc::new(ClipboardWatch::new()),
+            sessions: Arc::new(Sessions::default()),
+            clipboard_history: Arc::new(ClipboardHistory::new(self.config.clipboard.history)),
+            decode_pool: Arc::new(DecodePool::new(self.config.photos.decode_workers)),
+            auto_save: Arc::new(auto_save),
+            bursts: Arc::new(BurstTracker::default()),
+            events: EventBus::new(self.event_handlers),
+            features: Arc::new(features),
+            endpoints: Arc::new(endpoints),
+            started_at: std::time::Instant::now(),
+            pairing: Arc::new(PairingStore::new(devices.clone())),
+            devices,
+            audit,
+            photos: Arc::new(PhotoIndex::default()),
+            uploads: Arc::new(RecentUploads::new(self.config.photos.deduplicate, dedup::DEDUP_TTL)),
+            recent_sms: Arc::new(RecentUploads::new(!self.config.sms.dedup_window.is_zero(), self.config.sms.dedup_window)),
+            clipboard_pushes: Arc::new(ClipboardPushes::new(self.config.clipboard.ordering)),
+            resumable: Arc::new(ResumableUploads::new(self.config.uploads.resume_window, std::env::temp_dir())),
+            policy: Arc::new(PolicyEngine::new(self.config.policy.clone())),
+            schedule: Arc::new(RwLock::new(pause_schedule)),
+            sms_codes: Arc::new(RwLock::new(sms_codes)),
+            sms_history,
+            body_limits: self.config.server.body_limits(),
+            pipeline: Arc::new(Pipeline::new(self.config.pipeline)),
+            storage: Arc::new(StorageMonitor::default()),
+            clock: Arc::new(ClockSkew::default()),
+            attention: Arc::new(Attention::new(self.config.attention.clone(), self.display_waker)),
+            timings,
+            missed,
+            startup: Arc::new(Mutex::new(None)),
+            cors: Arc::new(RwLock::new(cors)),
+            config: Arc::new(RwLock::new(self.config.clone())),
+            auth_failures: Arc::new(Mutex::new(PairingThrottle::new(crate::auth::AUTH_THROTTLE))),
+            rate_limiter: Arc::new(RateLimiter::default()),
+            idempotency: Arc::new(IdempotencyStore::default()),
+            #[cfg(feature = "tls")]
+            tls,
+        };
+
+        FastSyncServer {
+            bind: self.config.server.bind.clone(),
+            port: self.config.server.port,
+            port_fallback: self.config.server.port_fallback,
+            port_hint: self.port_hint,
+            #[cfg(feature = "tls")]
+            http_port: self.config.server.http_port,
+            config_file: self.config_file.map(|path| Arc::new(ConfigFile::new(path))),
+            mdns: self.config.server.mdns,
+            handlers: self.handlers,
+            state,
+            shutdown_tx,
+            local_addrs: Mutex::new(Vec::new()),
+            http_addr: Mutex::new(None),
+            task: Mutex::new(None),
+            notification_state: self.notification_state,
+            #[cfg(feature = "tls")]
+            mtls_port: self.config.server.mtls_port,
+            #[cfg(feature = "tls")]
+            https,
+        }
+    }
+}
+
+/// FastSync 接收服务句柄。
+pub struct FastSyncServer {
+    /// 配置的监听地址，至少一个
+    bind: Vec<BindAddress>,
+    /// 配置的端口，未写端口的地址使用
+    port: u16,
+    port_fallback: bool,
+    /// 记录实际监听端口的文件
+    port_hint: Option<PathBuf>,
+    /// 启用 HTTPS 时另开的明文 HTTP 端口
+    #[cfg(feature = "tls")]
+    http_port: Option<u16>,
+    /// 运行中监视的配置文件
+    config_file: Option<Arc<ConfigFile>>,
+    mdns: bool,
+    handlers: Vec<Arc<dyn PayloadHandler>>,
+    state: AppState,
+    shutdown_tx: watch::Sender<bool>,
+    /// 实际监听的地址，顺序与配置相同
+    local_addrs: Mutex<Vec<SocketAddr>>,
+    /// 明文 HTTP 端口实际监听的地址
+    http_addr: Mutex<Option<SocketAddr>>,
+    task: Mutex<Option<JoinHandle<()>>>,
+    notification_state: Arc<dyn NotificationStateProbe>,
+    #[cfg(feature = "tls")]
+    mtls_port: Option<u16>,
+    /// HTTPS 服务端证书，未启用 HTTPS 或加载失败时为 None
+    #[cfg(feature = "tls")]
+    https: Option<Arc<crate::tls::ServerCertificate>>,
+}
+
+impl FastSyncServer {
+    /// 创建构建器。
+    pub fn builder() -> FastSyncServerBuilder {
+        FastSyncServerBuilder::new()
+    }
+
+    /// 构建包含全部路由的 axum Router，便于嵌入已有的 HTTP 服务。
+    pub fn router(&self) -> Router {
+        build_router(self.state.clone(), &self.handlers)
+    }
+
+    /// 订阅服务事件。
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerEvent> {
+        self.state.events.subscribe()
+    }
+
+    /// 实际监听的第一个地址，未启动时为 None。
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addrs().first().copied()
+    }
+
+    /// 配置的端口。端口被占用时实际监听的端口可能不同，以 `local_addr` 为准。
+    pub fn configured_port(&self) -> u16 {
+        self.port
+    }
+
+    /// 实际监听的所有地址，未启动时为空。
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.local_addrs.lock().map(|addrs| addrs.clone()).unwrap_or_default()
+    }
+
+    /// 只监听特定网卡时手机应访问的主机，例如 Tailscale 地址，IPv6 地址带方括号。
+    /// 监听所有网卡或未启动时为 None，由调用方选择局域网 IP。
+    pub fn listen_host(&self) -> Option<String> {
+        let addrs = self.local_addrs();
+        if addrs.is_empty() || addrs.iter().any(|addr| addr.ip().is_unspecified()) {
+            return None;
+        }
+        let ip = addrs.iter().map(SocketAddr::ip).find(|ip| !ip.is_loopback()).unwrap_or(addrs[0].ip());
+        Some(match ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        })
+    }
+
+    /// 启用 HTTPS 时另开的明文 HTTP 端口实际监听的地址，未开启时为 None。
+    pub fn http_addr(&self) -> Option<SocketAddr> {
+        self.http_addr.lock().ok().and_then(|addr| *addr)
+    }
+
+    /// 监听端口使用的 HTTPS 证书的 SHA-256 指纹（64 个十六进制字符），明文 HTTP 时为 None。
+    pub fn https_fingerprint(&self) -> Option<String> {
+        #[cfg(feature = "tls")]
+        return self.https.as_ref().map(|certificate| certificate.fingerprint().to_string());
+        #[cfg(not(feature = "tls"))]
+        None
+    }
+
+    /// 手机与浏览器访问监听端口使用的地址，例如 `https://192.168.1.5:3000`。
+    ///
+    /// # Arguments
+    /// * `host` - 本机 IP 或主机名
+    pub fn base_url(&self, host: &str) -> String {
+        let port = self.local_addr().map(|addr| addr.port()).unwrap_or(self.port);
+        let scheme = if self.https_fingerprint().is_some() { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, host, port)
+    }
+
+    /// 签发一次性令牌并生成配对二维码载荷，令牌 5 分钟内有效且只能兑换一次。
+    ///
+    /// # Arguments
+    /// * `base_url` - 手机访问接收端使用的地址
+    pub fn pairing_uri(&self, base_url: impl Into<String>) -> PairingUri {
+        let name = hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "FastSync".into());
+
+        #[cfg(feature = "tls")]
+        let fingerprint = self.state.tls.as_ref().map(|authority| authority.fingerprint());
+        #[cfg(not(feature = "tls"))]
+        let fingerprint = None;
+
+        PairingUri {
+            base_url: base_url.into(),
+            token: self.state.pairing.issue_one_time(),
+            fingerprint,
+            certificate: self.https_fingerprint(),
+            name,
+        }
+    }
+
+    /// 生成新的 6 位配对 PIN，5 分钟内有效，旧 PIN 随即失效。
+    pub fn pairing_pin(&self) -> String {
+        self.state.pairing.issue_pin()
+    }
+
+    /// 临时开放浏览器上传，签发 10 分钟内有效的访客令牌。
+    /// 访客打开 `http://<地址>/#<令牌>` 即可上传，页面会显示与返回值相同的校验表情。
+    pub fn allow_browser_upload(&self) -> GuestAccess {
+        let access = self.state.pairing.issue_guest();
+        tracing::info!(target: "fastsync::audit", "Browser upload allowed for {} minutes", access.expires_in.as_secs() / 60);
+        access
+    }
+
+    /// 最近的 PIN 配对尝试，最新的在前。
+    pub fn pairing_attempts(&self) -> Vec<PairingAttempt> {
+        self.state.pairing.attempts()
+    }
+
+    /// 最近的操作审计记录，最新的在前。
+    pub fn audit_records(&self, limit: usize) -> Vec<AuditRecord> {
+        self.state.audit.recent(limit)
+    }
+
+    /// 操作审计记录文件路径。
+    pub fn audit_log_path(&self) -> Option<PathBuf> {
+        self.state.audit.path().map(PathBuf::from)
+    }
+
+    /// 本机浏览器查看剪贴板历史的地址，服务未启动或启用 mTLS 时为 None。
+    pub fn clipboard_history_url(&self) -> Option<String> {
+        self.history_base_url(self.local_addr()?)
+            .map(|base| format!("{}/{}/clipboard/history", base, versioning::CURRENT_VERSION))
+    }
+
+    /// 本机浏览器查看最近操作记录的地址，托盘的“操作记录”打开此地址。
+    pub fn audit_url(&self) -> Option<String> {
+        self.history_base_url(self.local_addr()?)
+            .map(|base| format!("{}/{}/audit?limit=200", base, versioning::CURRENT_VERSION))
+    }
+
+    /// 处于计划暂停中时返回恢复时间。
+    pub fn scheduled_pause(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        schedule::scheduled_pause(&self.state.schedule.read().unwrap())
+    }
+
+    /// 下一次计划暂停的开始时间，当前正在暂停或未配置时为 None。
+    pub fn next_scheduled_pause(&self) -> Option<chrono::NaiveDateTime> {
+        self.state.schedule.read().unwrap().next_pause(chrono::Local::now().naive_local())
+    }
+
+    /// 是否有未处理的紧急通知，托盘据此闪烁图标。
+    pub fn attention_pending(&self) -> bool {
+        self.state.attention.is_flashing(std::time::Instant::now())
+    }
+
+    /// 保存目录存在的问题，托盘据此显示警告。
+    pub fn storage_warning(&self) -> Option<String> {
+        self.state.storage.warning()
+    }
+
+    /// 是否正在自动保存收到的图片。
+    pub fn auto_save_enabled(&self) -> bool {
+        self.state.auto_save.is_enabled()
+    }
+
+    /// 自动保存目录，未启用自动保存时也会返回。
+    pub fn auto_save_dir(&self) -> PathBuf {
+        self.state.auto_save.dir()
+    }
+
+    /// 切换图片自动保存。非桌面模式没有保存对话框，不能关闭。
+    ///
+    /// # Arguments
+    /// * `enabled` - 是否自动保存
+    pub fn set_auto_save(&self, enabled: bool) {
+        if !enabled && self.state.mode != RunMode::Desktop {
+            tracing::warn!("Auto-save cannot be disabled in {} mode", self.state.mode.as_str());
+            return;
+        }
+        self.state.auto_save.set_enabled(enabled);
+        tracing::info!("Auto-save {} ({:?})", if enabled { "enabled" } else { "disabled" }, self.state.auto_save.dir());
+        // 启用时立即预检目录，不可用时托盘随即显示警告；关闭后不再提示该目录的问题
+        if enabled {
+            let _ = self.state.storage.check(&self.state.auto_save.dir(), 0);
+        } else {
+            self.state.storage.forget(&self.state.auto_save.dir());
+        }
+    }
+
+    /// 是否自动复制短信验证码。
+    pub fn auto_copy_codes_enabled(&self) -> bool {
+        self.state.config.read().unwrap().sms.auto_copy_codes
+    }
+
+    /// 切换短信验证码的自动复制。
+    ///
+    /// # Arguments
+    /// * `enabled` - 收到验证码时是否直接写入剪贴板
+    pub fn set_auto_copy_codes(&self, enabled: bool) {
+        self.state.config.write().unwrap().sms.auto_copy_codes = enabled;
+        tracing::info!("Auto-copy of SMS verification codes {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    /// 是否直接写入收到的手机剪贴板。
+    pub fn auto_apply_clipboard_enabled(&self) -> bool {
+        self.state.config.read().unwrap().clipboard.auto_apply
+    }
+
+    /// 切换手机剪贴板的自动写入。
+    ///
+    /// # Arguments
+    /// * `enabled` - 收到手机剪贴板时是否不等确认直接写入
+    pub fn set_auto_apply_clipboard(&self, enabled: bool) {
+        self.state.config.write().unwrap().clipboard.auto_apply = enabled;
+        tracing::info!("Auto-apply of phone clipboard {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    /// 立即重新读取配置文件并应用，端口、请求体上限等需重启的设置除外，托盘开关随之变为文件中的值。
+    /// 结果以通知告知，文件有误时沿用当前配置并在通知中说明原因。可以在托盘线程中调用。
+    ///
+    /// # Returns
+    /// 是否应用了新的配置，未设置配置文件时为 false
+    pub fn reload_config(&self) -> bool {
+        let Some(file) = &self.config_file else {
+            return false;
+        };
+        reload_config_file(&self.state, file, true)
+    }
+
+    /// 正在使用的配置。
+    pub fn config(&self) -> Config {
+        self.state.config.read().unwrap().clone()
+    }
+
+    /// 数据接口共用的访问令牌。
+    pub fn access_token(&self) -> Option<String> {
+        let config = self.state.config.read().unwrap();
+        config.server.token.as_ref().map(|token| token.as_str().to_string())
+    }
+
+    /// 生成新的访问令牌并写入配置文件，使用旧令牌的手机需重新配对或填写新令牌。
+    ///
+    /// # Returns
+    /// 新的令牌
+    pub fn regenerate_access_token(&self) -> anyhow::Result<String> {
+        let token = AccessToken::generate();
+        if let Some(file) = &self.config_file {
+            config::store_token(file.path(), &token)?;
+        }
+        self.state.config.write().unwrap().server.token = Some(token.clone());
+        tracing::info!("Regenerated access token");
+        Ok(token.as_str().to_string())
+    }
+
+    /// 多次重试仍未能显示、转入操作记录的通知数。
+    pub fn notification_display_failures(&self) -> u64 {
+        self.state.notifications.failures()
+    }
+
+    /// 最近一个内容从接收到通知显示的各阶段耗时。
+    pub fn last_item_timings(&self) -> Option<LastItem> {
+        self.state.timings.last()
+    }
+
+    /// 内容策略的违规次数，按原因统计。
+    pub fn policy_violations(&self) -> HashMap<String, u64> {
+        self.state.policy.violations()
+    }
+
+    /// 已配对的设备。
+    pub fn devices(&self) -> Vec<PairedDevice> {
+        self.state.devices.list()
+    }
+
+    /// 解除设备配对，该设备的令牌立即失效。
+    ///
+    /// # Returns
+    /// 设备是否存在
+    pub fn revoke_device(&self, id: &str) -> bool {
+        self.state.revoke_device(id)
+    }
+
+    /// 设备当前是否通过 WebSocket 保持连接。
+    pub fn is_device_connected(&self, id: &str) -> bool {
+        self.state.sessions.is_connected(id)
+    }
+
+    /// 让保持 WebSocket 连接的手机响铃。
+    ///
+    /// # Returns
+    /// 设备是否在线并已收到指令
+    pub fn find_phone(&self, id: &str) -> bool {
+        let sent = self.state.sessions.send(id, ServerMessage::FindPhone);
+        tracing::info!("Find phone sent to {}: {}", id, if sent { "delivered" } else { "not connected" });
+        sent
+    }
+
+    /// 设置是否自动保存某台设备发来的图片，None 时沿用全局开关。
+    ///
+    /// # Returns
+    /// 设备是否存在
+    pub fn set_device_auto_save(&self, id: &str, enabled: Option<bool>) -> bool {
+        self.state.devices.update_settings(id, |settings| settings.auto_save = enabled)
+    }
+
+    /// 监听所有配置的地址并在当前 tokio 运行时中开始服务，任一地址无法监听时返回错误。
+    ///
+    /// # Returns
+    /// 实际监听的第一个地址
+    pub async fn start(&self) -> anyhow::Result<SocketAddr> {
+        tracing::info!("Effective configuration: {:?}", self.state.config.read().unwrap());
+        let hint = self.port_hint.as_deref().and_then(listen::load_port_hint);
+        let candidates = listen::candidate_ports(self.port, hint, self.port_fallback);
+        let (listeners, port) = listen::bind_with_fallback(&self.bind, &candidates)?;
+        let local_addrs = listeners
+            .iter()
+            .map(tokio::net::TcpListener::local_addr)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let local_addr = local_addrs[0];
+        self.record_port(port);
+
+        #[cfg(feature = "tls")]
+        let (app, mtls_tasks) = self.start_mtls(self.router())?;
+        #[cfg(not(feature = "tls"))]
+        let (app, mtls_tasks): (Router, Vec<JoinHandle<()>>) = (self.router(), Vec::new());
+
+        #[cfg(feature = "tls")]
+        let (plain, https_tasks) = self.start_https(listeners, app.clone())?;
+        #[cfg(not(feature = "tls"))]
+        let (plain, https_tasks): (Vec<tokio::net::TcpListener>, Vec<JoinHandle<()>>) = (listeners, Vec::new());
+        let fingerprint = self.https_fingerprint();
+        tracing::info!(
+            "Server listening on {} ({})",
+            format_addrs(&local_addrs),
+            if fingerprint.is_some() { "HTTPS" } else { "HTTP" }
+        );
+
+        // 启动时预检保存目录，问题只记录为托盘警告与自检结果，不阻止启动
+        let fallback_from = (self.port != 0 && port != self.port).then_some(self.port);
+        let mut checks = vec![selfcheck::check_bind(&local_addrs, fallback_from)];
+        if let Some(file) = &self.config_file {
+            checks.push(selfcheck::check_config(file.path()));
+        }
+        if let Some(dir) = self.state.auto_save.target() {
+            checks.push(selfcheck::check_storage("auto_save_dir", &self.state.storage, &dir));
+        }
+        checks.push(selfcheck::check_storage("temp_dir", &self.state.storage, &std::env::temp_dir()));
+
+        // 只监听回环地址时局域网中的手机无法访问，不必广播
+        let loopback_only = local_addrs.iter().all(|addr| addr.ip().is_loopback());
+        if self.mdns && loopback_only {
+            tracing::info!("Listening on loopback addresses only, skipping mDNS broadcast");
+        }
+        #[cfg(feature = "mdns")]
+        let mdns = (self.mdns && !loopback_only).then(|| {
+            let http_port = self.http_addr().map(|addr| addr.port());
+            let ips = advertised_ips(&local_addrs);
+            crate::mdns::start_mdns_broadcast(local_addr.port(), &ips, &self.state.features, fingerprint.as_deref(), http_port)
+                .inspect_err(|e| tracing::error!("mDNS broadcast failed: {:?}", e))
+        });
+        #[cfg(not(feature = "mdns"))]
+        let mdns = (self.mdns && !loopback_only).then(|| -> anyhow::Result<()> {
+            tracing::warn!("mDNS broadcast requested but the mdns feature is disabled");
+            Err(anyhow::anyhow!("the mdns feature is disabled"))
+        });
+        checks.push(if self.mdns && loopback_only {
+            selfcheck::CheckResult::skipped("mdns", "listening on loopback addresses only")
+        } else {
+            selfcheck::check_mdns(mdns.as_ref())
+        });
+
+        // 收到关闭信号后立即注销 mDNS，不必等进行中的请求结束
+        #[cfg(feature = "mdns")]
+        let mdns_stopped = {
+            let service = mdns.and_then(Result::ok);
+            let mut shutdown_rx = self.shutdown_tx.subscribe();
+            tokio::spawn(async move {
+                let _ = shutdown_rx.wait_for(|stop| *stop).await;
+                if let Some(service) = service {
+                    let _ = tokio::task::spawn_blocking(move || service.stop()).await;
+                }
+            })
+        };
+
+        let state = self.state.clone();
+        let mut drain_rx = self.shutdown_tx.subscribe();
+        let mut tasks: Vec<JoinHandle<()>> = https_tasks.into_iter().chain(mtls_tasks).collect();
+        for listener in plain {
+            tasks.push(serve_plain(listener, app.clone(), self.shutdown_tx.subscribe()));
+        }
+
+        let task = tokio::spawn(async move {
+            let serving = async {
+                for task in &mut tasks {
+                    let _ = task.await;
+                }
+            };
+            let deadline = async {
+                let _ = drain_rx.wait_for(|stop| *stop).await;
+                tokio::time::sleep(DRAIN_TIMEOUT).await;
+            };
+            tokio::select! {
+                _ = serving => {}
+                _ = deadline => tracing::warn!("In-flight requests did not finish within {:?}, stopping anyway", DRAIN_TIMEOUT),
+            }
+            for task in &tasks {
+                task.abort();
+            }
+
+            #[cfg(feature = "mdns")]
+            let _ = mdns_stopped.await;
+            let events = state.events.clone();
+            let _ = tokio::task::spawn_blocking(move || cleanup_on_exit(&state)).await;
+            tracing::info!("Server stopped");
+            events.emit(ServerEvent::Stopped);
+        });
+
+        if let Ok(mut slot) = self.task.lock() {
+            *slot = Some(task);
+        }
+        if let Ok(mut slot) = self.local_addrs.lock() {
+            *slot = local_addrs;
+        }
+
+        if self.state.mode == RunMode::Desktop {
+            self.start_suppression_poller(self.history_base_url(local_addr));
+            self.start_clipboard_watcher();
+        }
+        self.start_upload_collector();
+        self.start_temp_cleaner();
+        self.start_config_watcher();
+        self.finish_self_check(checks, local_addr);
+
+        self.state.events.emit(ServerEvent::Started { addr: local_addr });
+        Ok(local_addr)
+    }
+
+    /// 记录未写端口的地址实际使用的端口，供下次启动时优先使用。
+    /// 配置的端口被占用而改用其他端口时记录警告；所有地址都写明了端口或端口由系统分配时不记录。
+    ///
+    /// # Arguments
+    /// * `port` - 实际使用的端口
+    fn record_port(&self, port: u16) {
+        if self.bind.iter().all(|addr| addr.port.is_some()) || self.port == 0 {
+            return;
+        }
+        if port == self.port {
+            tracing::info!("Listening on the configured port {}", port);
+        } else {
+            tracing::warn!("Port {} is in use, listening on port {} instead", self.port, port);
+        }
+        if let Some(path) = self.port_hint.as_deref().filter(|_| self.port_fallback) {
+            listen::store_port_hint(path, listen::PortHint { configured: self.port, port });
+        }
+    }
+
+    /// 在后台完成需要服务已运行的自检项，保存报告，存在问题时弹出一次通知。
+    ///
+    /// # Arguments
+    /// * `checks` - 启动过程中已完成的检查
+    /// * `local_addr` - 实际监听地址
+    fn finish_self_check(&self, mut checks: Vec<selfcheck::CheckResult>, local_addr: SocketAddr) {
+        let state = self.state.clone();
+        #[cfg(feature = "tls")]
+        let https = self.https.clone();
+        tokio::spawn(async move {
+            if state.mode == RunMode::Desktop {
+                let notifier = state.notifier.clone();
+                let check = tokio::task::spawn_blocking(move || selfcheck::check_notifications(notifier.as_ref())).await;
+                checks.extend(check.ok());
+            }
+            #[cfg(feature = "tls")]
+            let loopback = match &https {
+                Some(certificate) => selfcheck::check_loopback_https(local_addr, certificate).await,
+                None => selfcheck::check_loopback(local_addr).await,
+            };
+            #[cfg(not(feature = "tls"))]
+            let loopback = selfcheck::check_loopback(local_addr).await;
+            checks.push(loopback);
+
+            let report = StartupReport::new(checks);
+            for check in report.problems() {
+                tracing::warn!("Startup check {} failed: {}", check.name, check.detail);
+            }
+            let alert = report.alert_title();
+            *state.startup.lock().unwrap() = Some(report);
+
+            let Some(title) = alert.filter(|_| state.mode == RunMode::Desktop) else {
+                return;
+            };
+            let mut notification = notifier::Notification::new("startup_check", &title);
+            notification.body.push("点击托盘图标查看自检详情".to_string());
+            let notifier = state.notifier.clone();
+            let shown = tokio::task::spawn_blocking(move || notifier.show(notification, Arc::new(|_: &str| {}))).await;
+            if let Ok(Err(e)) = shown {
+                tracing::warn!("Failed to show startup check alert: {:?}", e);
+            }
+        });
+    }
+
+    /// 启动自检报告，自检尚未完成时为 None。
+    pub fn startup_report(&self) -> Option<StartupReport> {
+        self.state.startup.lock().unwrap().clone()
+    }
+
+    /// 设置接收端自身的免打扰，期间不弹出通知，关闭时汇总期间收到的内容。
+    pub async fn set_do_not_disturb(&self, enabled: bool) {
+        let now = chrono::Utc::now().timestamp_millis();
+        if let Some(digest) = self.state.missed.set(SuppressionSource::DoNotDisturb, enabled, now) {
+            let addr = self.local_addr().unwrap_or(self.bind[0].with_default_port(self.port));
+            let url = history_url(self.history_base_url(addr), &digest);
+            self.state.notifications.show_digest(&digest, url).await;
+        }
+    }
+
+    /// 开启免打扰一段时间，期间仍接收并记录内容但不弹出通知，到期后汇总期间收到的内容。
+    /// 不需要异步运行时，可以在托盘线程中调用；到期由通知抑制轮询处理，最多晚 5 秒。
+    ///
+    /// # Arguments
+    /// * `duration` - 持续时间，为 None 时直到手动关闭
+    pub fn set_do_not_disturb_for(&self, duration: Option<Duration>) {
+        let now = chrono::Utc::now().timestamp_millis();
+        let until = duration.map(|duration| now + duration.as_millis() as i64);
+        self.state.missed.set_do_not_disturb_until(until, now);
+        tracing::info!("Do-not-disturb enabled for {:?}", duration);
+    }
+
+    /// 提前结束免打扰，由通知抑制轮询在 5 秒内关闭并汇总，可以在托盘线程中调用。
+    pub fn end_do_not_disturb(&self) {
+        if self.state.missed.do_not_disturb().is_some() {
+            let now = chrono::Utc::now().timestamp_millis();
+            self.state.missed.set_do_not_disturb_until(Some(now), now);
+        }
+    }
+
+    /// 免打扰是否开启。
+    pub fn do_not_disturb_active(&self) -> bool {
+        self.state.missed.do_not_disturb().is_some()
+    }
+
+    /// 免打扰的结束时间，未开启或直到手动关闭时为 None。
+    pub fn do_not_disturb_until(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        self.state
+            .missed
+            .do_not_disturb()
+            .flatten()
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|until| until.with_timezone(&chrono::Local))
+    }
+
+    /// 当前抑制通知的来源。
+    pub fn notification_suppression(&self) -> Vec<SuppressionSource> {
+        self.state.missed.active()
+    }
+
+    /// 定期检查配置文件的修改时间，修改后重新载入。只在文件有误或有需重启的修改时弹出通知。
+    fn start_config_watcher(&self) {
+        let Some(file) = self.config_file.clone() else {
+            return;
+        };
+        let state = self.state.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown_rx.wait_for(|stop| *stop) => return,
+                }
+                if file.changed() {
+                    reload_config_file(&state, &file, false);
+                }
+            }
+        });
+    }
+
+    /// 定期查询系统通知抑制状态并检查免打扰是否到期，抑制全部解除时汇总期间错过的内容。
+    fn start_suppression_poller(&self, base_url: Option<String>) {
+        let probe = self.notification_state.clone();
+        let state = self.state.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SUPPRESSION_POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown_rx.wait_for(|stop| *stop) => return,
+                }
+                let now = chrono::Utc::now().timestamp_millis();
+                if let Some(digest) = state.missed.expire_do_not_disturb(now) {
+                    let url = history_url(base_url.clone(), &digest);
+                    state.notifications.show_digest(&digest, url).await;
+                }
+                if let Some(digest) = state.missed.set_system(probe.suppressed_by(), now) {
+                    let url = history_url(base_url.clone(), &digest);
+                    state.notifications.show_digest(&digest, url).await;
+                }
+            }
+        });
+    }
+
+    /// 定期读取电脑剪贴板中的文本，记录变化供手机端拉取。
+    fn start_clipboard_watcher(&self) {
+        let state = self.state.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(clipboard::POLL_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown_rx.wait_for(|stop| *stop) => return,
+                }
+                // 剪贴板为空或不是文本时读取失败，视为没有变化
+                let backend = state.clipboard.clone();
+                if let Ok(Ok(text)) = tokio::task::spawn_blocking(move || backend.get_text()).await {
+                    if state.clipboard_watch.observe(text, chrono::Utc::now().timestamp_millis()) {
+                        tracing::debug!("PC clipboard changed, seq {}", state.clipboard_watch.latest().seq);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 定期删除超过续传时限的分块上传，启动时先清理上次运行遗留的文件。
+    fn start_upload_collector(&self) {
+        let uploads = self.state.resumable.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(resumable::COLLECT_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown_rx.wait_for(|stop| *stop) => return,
+                }
+                let uploads = uploads.clone();
+                let removed = tokio::task::spawn_blocking(move || uploads.collect_garbage(std::time::Instant::now())).await;
+                if let Ok(removed @ 1..) = removed {
+                    tracing::info!("Removed {} stale resumable uploads", removed);
+                }
+            }
+        });
+    }
+
+    /// 定期删除超过保留时长的临时图片，启动时先清理上次运行遗留的文件。每次清理时读取保留时长。
+    fn start_temp_cleaner(&self) {
+        let config = self.state.config.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(temp_files::CLEAN_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown_rx.wait_for(|stop| *stop) => return,
+                }
+                let max_age = config.read().unwrap().uploads.temp_max_age;
+                let _ = tokio::task::spawn_blocking(move || temp_files::clean_temp_files(max_age)).await;
+            }
+        });
+    }
+
+    /// 本机浏览器访问操作记录的地址前缀，启用 mTLS 时普通端口不提供操作记录，返回 None。
+    /// 监听所有网卡时经回环地址访问，只监听特定网卡时使用该网卡的地址。
+    ///
+    /// # Arguments
+    /// * `local_addr` - 监听端口实际监听的地址
+    fn history_base_url(&self, local_addr: SocketAddr) -> Option<String> {
+        #[cfg(feature = "tls")]
+        if self.mtls_port.is_some() {
+            return None;
+        }
+        // 自签名证书在浏览器中会先显示警告，开启了明文端口时优先使用
+        if let Some(addr) = self.http_addr() {
+            return Some(format!("http://{}", selfcheck::loopback_target(addr)));
+        }
+        let scheme = if self.https_fingerprint().is_some() { "https" } else { "http" };
+        Some(format!("{}://{}", scheme, selfcheck::loopback_target(local_addr)))
+    }
+
+    /// 附加端口（mTLS 与明文 HTTP）监听的地址：与监听端口相同的各个 IP 上的指定端口。
+    #[cfg(feature = "tls")]
+    fn secondary_addrs(&self, port: u16) -> Vec<SocketAddr> {
+        let mut addrs: Vec<SocketAddr> = Vec::new();
+        for addr in self.bind.iter().map(|addr| SocketAddr::new(addr.ip, port)) {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+        addrs
+    }
+
+    /// 启用 mTLS 时在独立端口上提供完整路由，普通端口换成只含配对接口的路由。
+    ///
+    /// # Returns
+    /// 普通端口使用的路由与各地址上的 mTLS 服务任务
+    #[cfg(feature = "tls")]
+    fn start_mtls(&self, app: Router) -> anyhow::Result<(Router, Vec<JoinHandle<()>>)> {
+        let Some(port) = self.mtls_port else {
+            return Ok((app, Vec::new()));
+        };
+        let Some(authority) = self.state.tls.clone() else {
+            anyhow::bail!("mTLS requested but the CA could not be loaded");
+        };
+
+        let listeners = listen::bind_listeners(&self.secondary_addrs(port))?;
+        let mut tasks = Vec::with_capacity(listeners.len());
+        for listener in listeners {
+            tracing::info!("mTLS listening on {}", listener.local_addr()?);
+            // 在服务任务开始前记录端口，启动后立即配对的手机也能拿到正确的端口
+            authority.set_port(listener.local_addr()?.port());
+            let (authority, app, state) = (authority.clone(), app.clone(), self.state.clone());
+            let shutdown_rx = self.shutdown_tx.subscribe();
+            tasks.push(tokio::spawn(async move {
+                if let Err(e) = crate::tls::serve(listener, authority, app, state, shutdown_rx).await {
+                    tracing::error!("mTLS server error: {:?}", e);
+                }
+            }));
+        }
+        Ok((build_pairing_router(self.state.clone()), tasks))
+    }
+
+    /// 启用 HTTPS 时在各监听地址上以 TLS 提供路由，并按配置另开明文 HTTP 端口。
+    ///
+    /// # Returns
+    /// 需以明文 HTTP 提供服务的监听器与 HTTPS 服务任务，未启用 HTTPS 时原样返回监听器
+    #[cfg(feature = "tls")]
+    fn start_https(
+        &self,
+        listeners: Vec<tokio::net::TcpListener>,
+        app: Router,
+    ) -> anyhow::Result<(Vec<tokio::net::TcpListener>, Vec<JoinHandle<()>>)> {
+        if !self.state.config.read().unwrap().server.https {
+            return Ok((listeners, Vec::new()));
+        }
+        let Some(certificate) = self.https.clone() else {
+            anyhow::bail!("HTTPS requested but the certificate could not be loaded");
+        };
+
+        let plain = match self.http_port {
+            Some(port) => {
+                let plain = listen::bind_listeners(&self.secondary_addrs(port))?;
+                let addrs = plain
+                    .iter()
+                    .map(tokio::net::TcpListener::local_addr)
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                tracing::warn!(
+                    "Plain HTTP listening on {} for older clients; traffic on this port is not encrypted",
+                    format_addrs(&addrs)
+                );
+                if let Ok(mut slot) = self.http_addr.lock() {
+                    *slot = addrs.first().copied();
+                }
+                plain
+            }
+            None => Vec::new(),
+        };
+
+        let tasks = listeners
+            .into_iter()
+            .map(|listener| {
+                let (certificate, app) = (certificate.clone(), app.clone());
+                let shutdown_rx = self.shutdown_tx.subscribe();
+                tokio::spawn(async move {
+                    if let Err(e) = crate::tls::serve_https(listener, certificate, app, shutdown_rx).await {
+                        tracing::error!("HTTPS server error: {:?}", e);
+                    }
+                })
+            })
+            .collect();
+        Ok((plain, tasks))
+    }
+
+    /// 请求优雅关闭：停止接受新连接并注销 mDNS，进行中的请求最多等待 `DRAIN_TIMEOUT`，
+    /// 随后删除未完成的分块上传与不再使用的临时文件。可在任意线程调用，配合 `wait` 等待完成。
+    pub fn shutdown(&self) {
+        let _ = self.shutdown_tx.send(true);
+        self.state.clipboard_watch.stop();
+        self.state.sessions.close_all(CLOSE_SHUTDOWN);
+    }
+
+    /// 等待服务完全停止。未启动时立即返回。
+    pub async fn wait(&self) {
+        let task = self.task.lock().ok().and_then(|mut slot| slot.take());
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+    }
+}
+
+/// 在一个监听器上以明文 HTTP 提供路由，直到收到关闭信号且进行中的请求完成。
+fn serve_plain(listener: tokio::net::TcpListener, app: Router, mut shutdown_rx: watch::Receiver<bool>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let shutdown = async move {
+            let _ = shutdown_rx.wait_for(|stop| *stop).await;
+        };
+        if let Err(e) = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown)
+            .await
+        {
+            tracing::error!("Server error: {:?}", e);
+        }
+    })
+}
+
+/// mDNS 中广播的 IP：监听所有网卡时为空，由广播模块选择局域网 IP；否则为监听的非回环地址。
+#[cfg(feature = "mdns")]
+fn advertised_ips(local_addrs: &[SocketAddr]) -> Vec<IpAddr> {
+    if local_addrs.iter().any(|addr| addr.ip().is_unspecified()) {
+        return Vec::new();
+    }
+    let mut ips: Vec<IpAddr> = Vec::new();
+    for ip in local_addrs.iter().map(SocketAddr::ip).filter(|ip| !ip.is_loopback()) {
+        if !ips.contains(&ip) {
+            ips.push(ip);
+        }
+    }
+    ips
+}
+
+fn format_addrs(addrs: &[SocketAddr]) -> String {
+    addrs.iter().map(SocketAddr::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// 退出前清理：会话只保存在内存中，未完成的分块上传无法在重启后续传，一并删除；
+/// 通知仍在显示的临时文件保留给下次启动时的定期清理。
+fn cleanup_on_exit(state: &AppState) {
+    let uploads = state.resumable.discard_all();
+    let report = temp_files::clean_temp_files(Duration::ZERO);
+    tracing::info!(
+        "Removed {} unfinished uploads and {} temp files ({} bytes) on exit",
+        uploads,
+        report.files,
+        report.bytes
+    );
+}
+
+/// 重新读取配置文件，保留需重启的设置后替换正在使用的配置，并以通知告知结果。
+///
+/// # Arguments
+/// * `file` - 配置文件
+/// * `confirm` - 成功时也弹出通知，用于托盘中的手动重新载入
+///
+/// # Returns
+/// 是否应用了新的配置
+fn reload_config_file(state: &AppState, file: &ConfigFile, confirm: bool) -> bool {
+    let mut config = match file.load() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{:#}, keeping the current config", e);
+            show_config_notice(state, "配置文件有误，仍使用之前的设置", format!("{:#}", e));
+            return false;
+        }
+    };
+    let running = state.config.read().unwrap().clone();
+    let pending = config.keep_restart_settings(&running);
+    state.apply_config(&running, &config);
+    *state.config.write().unwrap() = config;
+    tracing::info!("Reloaded config from {:?}", file.path());
+
+    if !pending.is_empty() {
+        tracing::warn!("Config changes to {} take effect after a restart", pending.join(", "));
+        show_config_notice(state, "配置已重新载入", format!("以下设置需重启后生效: {}", pending.join("、")));
+    } else if confirm {
+        show_config_notice(state, "配置已重新载入", file.path().display().to_string());
+    }
+    true
+}
+
+/// 弹出配置载入结果的通知。可能在托盘线程中调用，因此在单独的线程中显示。
+fn show_config_notice(state: &AppState, title: &str, detail: String) {
+    if state.mode != RunMode::Desktop {
+        return;
+    }
+    let mut notification = notifier::Notification::new("config", title);
+    notification.body.push(detail);
+    let notifier = state.notifier.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = notifier.show(notification, Arc::new(|_: &str| {})) {
+            tracing::warn!("Failed to show config notice: {:?}", e);
+        }
+    });
+}
+
+/// 按错过内容的时段筛选操作记录的地址。
+fn history_url(base_url: Option<String>, digest: &MissedDigest) -> Option<String> {
+    base_url.map(|base| {
+        format!(
+            "{}/{}/audit?since={}&until={}",
+            base,
+            versioning::CURRENT_VERSION,
+            digest.since,
+            digest.until
+        )
+    })
+}
+
+/// 注册所有路由：接口按版本嵌套，上传页面不带版本前缀。
+fn build_router(state: AppState, payload_handlers: &[Arc<dyn PayloadHandler>]) -> Router {
+    let pages = Router::new()
+        .route("/", get(handlers::web::index))
+        .route("/app.js", get(handlers::web::app_js))
+        .route("/app.css", get(handlers::web::app_css));
+    with_versions(state.clone(), pages, build_v1_router(state, payload_handlers))
+}
+
+/// 第 1 版接口，路径不含版本前缀，由 `with_versions` 挂载。
+fn build_v1_router(state: AppState, payload_handlers: &[Arc<dyn PayloadHandler>]) -> Router {
+    let mut payload_routes = Router::new();
+    let mut json_routes = Router::new();
+    for handler in payload_handlers {
+        let route = payload::method_router(handler.clone());
+        if handler.cross_origin() {
+            json_routes = json_routes.route(handler.path(), route);
+        } else {
+            payload_routes = payload_routes.route(handler.path(), route);
+        }
+    }
+    let limits = state.body_limits;
+    // 最后加入的层最先执行：先校验令牌再判断计划暂停，未授权的请求看不到暂停时段
+    let payload_routes = payload_routes
+        .route("/upload/init", post(handlers::resumable::init))
+        .route("/upload/chunk/:id", put(handlers::resumable::chunk))
+        .route("/upload/status/:id", get(handlers::resumable::status))
+        .route("/sms/outbox", get(handlers::sms::outbox))
+        .route("/sms/outbox/:id/ack", post(handlers::sms::ack_reply))
+        .layer(DefaultBodyLimit::max(limits.upload))
+        .route_layer(middleware::from_fn_with_state(limits.upload, body_limit::body_limit_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), admission::admission_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit::rate_limit_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), idempotency::idempotency_guard))
+        .route_layer(middleware::from_fn_with_state(limits.upload, compression::request_decompression))
+        .route_layer(middleware::from_fn_with_state(state.clone(), schedule::pause_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::device_auth));
+    let json_routes = json_routes
+        .route_layer(middleware::from_fn_with_state(limits.json, body_limit::body_limit_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), admission::admission_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit::rate_limit_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), idempotency::idempotency_guard))
+        .route_layer(middleware::from_fn_with_state(limits.json, compression::request_decompression))
+        .route_layer(middleware::from_fn_with_state(state.clone(), schedule::pause_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::device_auth))
+        .route("/health", get(handlers::health::health))
+        .route("/info", get(handlers::info::info))
+        .route("/ping", get(handlers::ping::ping))
+        .route("/pair", post(handlers::pair::pair_pin))
+        .route("/pair/qr", post(handlers::pair::pair_qr))
+        .route("/pair/request", post(handlers::pair::request_pairing))
+        .route("/pair/status/:id", get(handlers::pair::pair_status))
+        .route("/guest", get(handlers::web::guest))
+        .layer(DefaultBodyLimit::max(limits.json));
+    // 长轮询不计入准入控制的处理中请求
+    let clipboard_routes = Router::new()
+        .route("/clipboard", get(handlers::clipboard::current))
+        .route_layer(middleware::from_fn_with_state(state.clone(), schedule::pause_guard))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::device_auth));
+    let photo_routes = Router::new()
+        .route("/photos/:id/thumb", get(handlers::thumbnail::thumbnail))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::device_auth));
+    let device_routes = Router::new()
+        .route("/devices", get(handlers::devices::list_devices))
+        .route("/devices/:id", delete(handlers::devices::revoke_device))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::admin_auth));
+    // 历史与统计可能有数百 KB，按客户端的 Accept-Encoding 压缩
+    let history_routes = Router::new()
+        .route("/audit", get(handlers::audit::audit))
+        .route("/sms/history", get(handlers::sms::history))
+        .route("/clipboard/history", get(handlers::clipboard::history))
+        .route("/clipboard/history/:id", get(handlers::clipboard::history_entry))
+        .route("/metrics", get(handlers::diagnose::metrics))
+        .route_layer(middleware::from_fn(compression::response_compression));
+
+    let routes = payload_routes
+        .merge(with_cors(state.clone(), json_routes))
+        .merge(with_cors(state.clone(), clipboard_routes))
+        .merge(photo_routes)
+        .merge(device_routes)
+        .merge(history_routes)
+        .route("/diagnose", get(handlers::diagnose::diagnose))
+        .with_state(state.clone())
+        .layer(CatchPanicLayer::custom(handlers::response::panic_response));
+
+    // WebSocket 上的消息作为请求交给上面的路由处理，与 REST 接口经过同样的校验与限制，并各自记录访问日志
+    let loopback = routes
+        .clone()
+        .layer(middleware::from_fn_with_state(state.clone(), access_log::access_log));
+    let realtime = Router::new()
+        .route("/ws", get(handlers::ws::upgrade))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::device_auth))
+        .layer(Extension(handlers::ws::Loopback(loopback)))
+        .with_state(state)
+        .layer(CatchPanicLayer::custom(handlers::response::panic_response));
+    routes.merge(realtime)
+}
+
+/// 把第 1 版接口挂在 `/v1` 下，无前缀的旧路径作为已弃用的别名，并加上协议版本响应头与访问日志。
+/// 以后的版本在这里另行嵌套，与旧版本共存。
+///
+/// # Arguments
+/// * `state` - 应用共享状态
+/// * `base` - 不带版本前缀的路由，例如上传页面
+/// * `v1` - 第 1 版接口
+fn with_versions(state: AppState, base: Router, v1: Router) -> Router {
+    base.nest(&format!("/{}", versioning::CURRENT_VERSION), v1.clone())
+        .merge(v1.layer(middleware::from_fn(versioning::deprecated_alias)))
+        .fallback(versioning::unknown_version)
+        .layer(middleware::from_fn(versioning::protocol_header))
+        .layer(middleware::from_fn_with_state(state, access_log::access_log))
+}
+
+/// 启用 mTLS 时普通 HTTP 端口上的路由，只保留配对与状态查询。
+#[cfg(feature = "tls")]
+fn build_pairing_router(state: AppState) -> Router {
+    let routes = Router::new()
+        .route("/health", get(handlers::health::health))
+        .route("/info", get(handlers::info::info))
+        .route("/ping", get(handlers::ping::ping))
+        .route("/pair", post(handlers::pair::pair_pin))
+        .route("/pair/qr", post(handlers::pair::pair_qr))
+        .route("/pair/request", post(handlers::pair::request_pairing))
+        .route("/pair/status/:id", get(handlers::pair::pair_status));
+    let routes = with_cors(state.clone(), routes)
+        .with_state(state.clone())
+        .layer(CatchPanicLayer::custom(handlers::response::panic_response));
+    with_versions(state, Router::new(), routes)
+}
+
+/// 为允许跨域的 JSON 接口加上 CORS 层，未配置来源时保持同源限制。
+fn with_cors(state: AppState, routes: Router<AppState>) -> Router<AppState> {
+    routes.layer(middleware::from_fn_with_state(state, cors::cors_guard))
+}
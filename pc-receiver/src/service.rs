@@ -0,0 +1,236 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * Windows 服务模块。
+ * 负责服务的安装/卸载/启停，以及服务进程内的 HTTP 服务生命周期。
+ * 服务运行在会话 0，无法访问交互式桌面，因此通知、剪贴板与托盘均被禁用。
+ */
+use std::ffi::{OsStr, OsString};
+use std::sync::Arc;
+use std::time::Duration;
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult},
+    service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
+};
+use fastsync::{FastSyncServer, RunMode};
+use crate::cli::{ServiceCommand, SERVICE_FLAG};
+
+pub const SERVICE_NAME: &str = "FastSync";
+const SERVICE_DISPLAY_NAME: &str = "FastSync Receiver";
+const SERVICE_DESCRIPTION: &str = "接收手机推送的图片、短信与剪贴板，并自动保存图片";
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// 处理 `service <command>` 子命令。
+///
+/// # Arguments
+/// * `command` - 由命令行解析出的操作
+///
+/// # Returns
+/// 进程退出码
+pub fn handle_command(command: ServiceCommand) -> i32 {
+    let result = match command {
+        ServiceCommand::Install => install(),
+        ServiceCommand::Uninstall => uninstall(),
+        ServiceCommand::Start => start(),
+        ServiceCommand::Stop => stop(),
+    };
+
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            tracing::error!("Service command failed: {:?}", e);
+            1
+        }
+    }
+}
+
+/// 将日志写入 `%ProgramData%\FastSync\service.log`，服务进程没有可见的控制台。
+pub fn init_logging() {
+    let log_dir = std::env::var_os("ProgramData")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("FastSync");
+    let _ = std::fs::create_dir_all(&log_dir);
+
+    match std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_dir.join("service.log"))
+    {
+        Ok(file) => tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(std::sync::Mutex::new(file))
+            .init(),
+        Err(_) => tracing_subscriber::fmt::init(),
+    }
+}
+
+/// 连接服务控制管理器并进入服务主函数，阻塞直到服务停止。
+pub fn run() -> windows_service::Result<()> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+}
+
+/// 服务主函数，由服务控制管理器调用。
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        tracing::error!("Service exited with error: {:?}", e);
+    }
+}
+
+/// 注册控制处理器并运行 HTTP 服务，收到停止请求后走优雅关闭流程。
+fn run_service() -> anyhow::Result<()> {
+    // 服务以 LocalSystem 运行，其个人图片目录对用户不可见
+    let save_dir = std::env::var_os("PUBLIC")
+        .map(|p| std::path::PathBuf::from(p).join("Pictures"))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("FastSync");
+
+    let server = Arc::new(
+        FastSyncServer::builder()
+            .mode(RunMode::Service)
+            .auto_save_dir(save_dir)
+            .build(),
+    );
+
+    let control_server = server.clone();
+    let event_handler = move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            tracing::info!("Stop requested by service control manager");
+            control_server.shutdown();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    };
+
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)?;
+    status_handle.set_service_status(service_status(ServiceState::Running))?;
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
+    let result = rt.block_on(async {
+        server.start().await?;
+        server.wait().await;
+        anyhow::Ok(())
+    });
+
+    status_handle.set_service_status(service_status(ServiceState::Stopped))?;
+    result
+}
+
+/// 构造上报给服务控制管理器的状态。
+fn service_status(state: ServiceState) -> ServiceStatus {
+    ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted: if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        },
+        exit_code: ServiceExitCode::Win32(0),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    }
+}
+
+/// 以自动启动方式安装服务，启动参数为 `--service`。
+fn install() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+
+    let service_info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe()?,
+        launch_arguments: vec![OsString::from(SERVICE_FLAG)],
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+
+    let service = manager.create_service(&service_info, ServiceAccess::CHANGE_CONFIG)?;
+    service.set_description(SERVICE_DESCRIPTION)?;
+
+    tracing::info!("Service {} installed", SERVICE_NAME);
+    Ok(())
+}
+
+/// 停止并删除服务。
+fn uninstall() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(
+        SERVICE_NAME,
+        ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+    )?;
+
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop()?;
+    }
+    service.delete()?;
+
+    tracing::info!("Service {} uninstalled", SERVICE_NAME);
+    Ok(())
+}
+
+/// 启动已安装的服务。
+fn start() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::START)?;
+    service.start::<&OsStr>(&[])?;
+
+    tracing::info!("Service {} started", SERVICE_NAME);
+    Ok(())
+}
+
+/// 停止正在运行的服务。
+fn stop() -> anyhow::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::STOP)?;
+    service.stop()?;
+
+    tracing::info!("Service {} stopped", SERVICE_NAME);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Invocation;
+
+    #[test]
+    fn only_a_running_service_accepts_stop() {
+        let cases = [
+            (ServiceState::Running, ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN),
+            (ServiceState::StartPending, ServiceControlAccept::empty()),
+            (ServiceState::Stopped, ServiceControlAccept::empty()),
+        ];
+        for (state, accepted) in cases {
+            let status = service_status(state);
+            assert_eq!(status.current_state, state);
+            assert_eq!(status.controls_accepted, accepted, "{:?}", state);
+            assert_eq!(status.exit_code, ServiceExitCode::Win32(0));
+        }
+    }
+
+    #[test]
+    fn installed_launch_arguments_start_the_service_process() {
+        assert!(matches!(Invocation::try_parse_from(["pc-receiver", SERVICE_FLAG]), Ok(Invocation::RunService)));
+    }
+}
@@ -0,0 +1,261 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 短信验证码识别模块。
+ * 部分转发短信的手机端不识别验证码，`code` 字段始终为空，此时由接收端从短信原文中提取。
+ * 只取靠近“验证码”“code”等关键词的 4 到 8 位数字，`G-123456` 这类带字母前缀的格式取数字部分；
+ * 与其他数字以 `-`、`/`、`.`、`:` 相连的数字（日期、时间、金额、分段的电话号码）、后接年月日、元等单位的数字
+ * 以及短信签名中的数字不作为验证码。
+ * 内置规则之外可以在配置文件的 `[sms.codes]` 中追加关键词与正则表达式。
+ */
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 内置的验证码关键词，英文关键词不区分大小写。
+const KEYWORDS: [&str; 15] = [
+    "验证码", "校验码", "确认码", "动态码", "动态密码", "短信码", "授权码", "认证码", "安全码", "驗證碼",
+    "verification", "code", "otp", "passcode", "pin",
+];
+
+/// 验证码与最近的关键词之间最多相隔的字符数。
+const MAX_KEYWORD_DISTANCE: usize = 40;
+
+/// 验证码的位数范围。
+const CODE_DIGITS: std::ops::RangeInclusive<usize> = 4..=8;
+
+/// 紧跟在数字之后时说明该数字不是验证码的单位。
+const UNITS: &str = "年月日号时分秒元%位个次天";
+
+/// 紧挨在数字之前时说明该数字不是验证码的文字。
+const PREFIXES: [&str; 5] = ["尾号", "¥", "￥", "$", "No."];
+
+/// 验证码识别的补充规则，在内置规则之外追加。
+///
+/// ```toml
+/// [sms.codes]
+/// keywords = ["激活码"]
+/// patterns = ['口令[:：]\s*(?P<code>[A-Z0-9]{6})']
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CodeRules {
+    /// 追加的关键词
+    pub keywords: Vec<String>,
+    /// 追加的正则表达式，优先于内置规则且不要求靠近关键词；
+    /// 验证码取名为 `code` 的分组，没有时取第一个分组，再没有时取整个匹配
+    pub patterns: Vec<String>,
+}
+
+/// 从短信原文中提取验证码。
+#[derive(Debug, Clone)]
+pub struct CodeExtractor {
+    /// 小写的关键词
+    keywords: Vec<String>,
+    patterns: Vec<Regex>,
+    digits: Regex,
+}
+
+impl Default for CodeExtractor {
+    fn default() -> Self {
+        Self::new(CodeRules::default()).expect("built-in rules are valid")
+    }
+}
+
+impl CodeExtractor {
+    /// 以内置规则加上补充规则创建。
+    ///
+    /// # Arguments
+    /// * `rules` - 补充规则
+    ///
+    /// # Returns
+    /// 提取器，补充的正则表达式无效时报错
+    pub fn new(rules: CodeRules) -> anyhow::Result<Self> {
+        let patterns = rules
+            .patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid code pattern {:?}: {}", pattern, e)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let keywords = KEYWORDS
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .chain(rules.keywords)
+            .map(|keyword| keyword.to_lowercase())
+            .filter(|keyword| !keyword.is_empty())
+            .collect();
+        Ok(Self {
+            keywords,
+            patterns,
+            digits: Regex::new("[0-9]+").expect("digit pattern is valid"),
+        })
+    }
+
+    /// 从短信原文中提取验证码。
+    ///
+    /// # Arguments
+    /// * `content` - 短信原文
+    ///
+    /// # Returns
+    /// 识别出的验证码，没有可信的候选时为 None
+    pub fn extract(&self, content: &str) -> Option<String> {
+        for pattern in &self.patterns {
+            if let Some(captures) = pattern.captures(content) {
+                let code = captures
+                    .name("code")
+                    .or_else(|| captures.get(1))
+                    .or_else(|| captures.get(0))
+                    .map(|code| code.as_str().trim().to_string())
+                    .filter(|code| !code.is_empty());
+                if code.is_some() {
+                    return code;
+                }
+            }
+        }
+
+        let keywords = self.keyword_spans(content);
+        if keywords.is_empty() {
+            return None;
+        }
+        self.digits
+            .find_iter(content)
+            .filter(|candidate| CODE_DIGITS.contains(&candidate.len()) && is_standalone(content, candidate.start(), candidate.end()))
+            .filter_map(|candidate| {
+                let distance = keywords
+                    .iter()
+                    .map(|&(start, end)| char_gap(content, (start, end), (candidate.start(), candidate.end())))
+                    .min()?;
+                (distance <= MAX_KEYWORD_DISTANCE).then_some((distance, candidate.as_str()))
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, code)| code.to_string())
+    }
+
+    /// 原文中所有关键词出现的位置（字节范围）。英文关键词需是完整的单词，`pin` 不会匹配 `shopping`。
+    fn keyword_spans(&self, content: &str) -> Vec<(usize, usize)> {
+        // 只转换 ASCII 大小写，字节位置与原文一致
+        let lower = content.to_ascii_lowercase();
+        let mut spans = Vec::new();
+        for keyword in &self.keywords {
+            for (start, matched) in lower.match_indices(keyword.as_str()) {
+                let end = start + matched.len();
+                let before = lower[..start].chars().next_back();
+                let after = lower[end..].chars().next();
+                let starts_word = !keyword.starts_with(|c: char| c.is_ascii_alphabetic())
+                    || !before.is_some_and(|c| c.is_ascii_alphabetic());
+                let ends_word = !keyword.ends_with(|c: char| c.is_ascii_alphabetic())
+                    || !after.is_some_and(|c| c.is_ascii_alphabetic());
+                if starts_word && ends_word {
+                    spans.push((start, end));
+                }
+            }
+        }
+        spans
+    }
+}
+
+/// 数字是否独立出现：前后不是字母或数字，不与其他数字以分隔符相连，不在短信签名中，也没有表示日期、金额等的前后缀。
+fn is_standalone(content: &str, start: usize, end: usize) -> bool {
+    let before = &content[..start];
+    let after = &content[end..];
+    let mut preceding = before.chars().rev();
+    let mut following = after.chars();
+    let previous = preceding.next();
+    let next = following.next();
+
+    if previous.is_some_and(|c| c.is_ascii_alphanumeric()) || next.is_some_and(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    // 2024-05-01、12:30、13.50、138-0013-8000 这类分段的数字
+    let separator = |c: Option<char>| c.is_some_and(|c| "-/.:".contains(c));
+    if separator(previous) && preceding.next().is_some_and(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    if separator(next) && following.next().is_some_and(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    if next.is_some_and(|c| UNITS.contains(c)) {
+        return false;
+    }
+    // 【12306】这类短信签名
+    if previous == Some('【') && next == Some('】') {
+        return false;
+    }
+    !PREFIXES.iter().any(|prefix| before.trim_end().ends_with(prefix))
+}
+
+/// 两个字节范围之间相隔的字符数，重叠时为 0。
+fn char_gap(content: &str, a: (usize, usize), b: (usize, usize)) -> usize {
+    let (first, second) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    if second.0 <= first.1 {
+        return 0;
+    }
+    content[first.1..second.0].chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_codes_from_a_corpus() {
+        let extractor = CodeExtractor::default();
+        let cases = [
+            // 中文
+            ("【淘宝】您的验证码是 482916，5分钟内有效，请勿泄露。", Some("482916")),
+            ("【招商银行】验证码：7351，您正在进行网上支付，如非本人操作请致电95555。", Some("7351")),
+            ("您的动态密码为12345678，有效期10分钟。", Some("12345678")),
+            ("校验码 093812（请勿告诉他人）", Some("093812")),
+            ("【12306】您的确认码 6631，请在30分钟内完成支付。", Some("6631")),
+            ("您的驗證碼為 208833。", Some("208833")),
+            // 英文
+            ("G-482913 is your Google verification code.", Some("482913")),
+            ("Your Microsoft code is 4821. Don't share it.", Some("4821")),
+            ("Use OTP 902113 to log in. Valid for 10 minutes.", Some("902113")),
+            ("Your PIN: 5530", Some("5530")),
+            ("Your passcode is 77812391", Some("77812391")),
+            // 金额、日期、时间、电话号码与签名中的数字不是验证码
+            ("您尾号8831的账户于2024-05-01 12:30支出人民币1500.00元，验证码见另一条短信。", None),
+            ("验证码服务费 ¥2000 已扣除", None),
+            ("【95588】验证码请勿泄露", None),
+            ("Code expires on 2026/10/15", None),
+            ("验证码已发送至 138-0013-8000", None),
+            ("您本月已使用验证码 1200次", None),
+            ("验证码有效期至2026年", None),
+            // 位数不在范围内或没有关键词
+            ("您的验证码是 123", None),
+            ("您的验证码是 123456789", None),
+            ("您的快递 482916 已到达驿站", None),
+            ("Your order 482916 has shipped", None),
+            // 英文关键词需是完整的单词
+            ("Thanks for shopping, order 482916", None),
+            ("Barcode 482916", None),
+            // 多个候选时取离关键词最近的一个
+            ("订单 20261015 支付确认，验证码 4431，有效期 5 分钟。", Some("4431")),
+            ("2233 is not it; your code is 8841", Some("8841")),
+            ("验证码 6620，如非本人操作请拨打 10086 或发送 0000 至 95555", Some("6620")),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(extractor.extract(content).as_deref(), expected, "{}", content);
+        }
+    }
+
+    #[test]
+    fn extra_rules_are_applied() {
+        let extractor = CodeExtractor::new(CodeRules {
+            keywords: vec!["激活码".into()],
+            patterns: vec![r"口令[:：]\s*(?P<code>[A-Z0-9]{6})".into()],
+        })
+        .unwrap();
+        let cases = [
+            ("您的激活码 5521", Some("5521")),
+            ("今日口令：AB12CD，验证码 1234", Some("AB12CD")),
+            ("验证码 1234", Some("1234")),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(extractor.extract(content).as_deref(), expected, "{}", content);
+        }
+
+        let invalid = CodeExtractor::new(CodeRules { keywords: Vec::new(), patterns: vec!["(".into()] });
+        assert!(invalid.is_err());
+    }
+}
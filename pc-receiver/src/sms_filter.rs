@@ -0,0 +1,182 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 短信通知过滤模块。
+ * 按发送方号码、号码前缀与原文中的关键词（例如营销短信的“退订回T”）决定收到的短信是否弹出通知。
+ * 默认屏蔽名单模式：命中屏蔽规则的短信不弹通知；也可切换为白名单模式，只有命中放行规则的短信弹通知。
+ * 被过滤的短信照常返回 200，并按配置写入短信历史。
+ * 未被过滤的短信再按弹出方式决定是否静默：静默通知不弹横幅、不发声，只进入通知中心。
+ * 文件中还可以为号码设置显示名称，手机端未提供联系人名称时通知标题显示该名称。
+ * 规则写在配置文件的 `[sms.filter]` 中，配置重新载入后立即生效，无需重启服务。
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 过滤模式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    /// 命中屏蔽规则的短信不弹通知
+    #[default]
+    Block,
+    /// 只有命中放行规则的短信弹通知
+    Allow,
+}
+
+/// 短信通知的弹出方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PopupMode {
+    /// 所有短信都弹出通知并播放提示音
+    Loud,
+    /// 所有短信都静默进入通知中心
+    Quiet,
+    /// 带验证码的短信弹出通知，其余静默
+    #[default]
+    Smart,
+}
+
+impl PopupMode {
+    /// 判定通知是否静默。
+    ///
+    /// # Arguments
+    /// * `has_code` - 短信是否带验证码
+    pub fn is_quiet(&self, has_code: bool) -> bool {
+        match self {
+            PopupMode::Loud => false,
+            PopupMode::Quiet => true,
+            PopupMode::Smart => !has_code,
+        }
+    }
+}
+
+/// 一组匹配规则，任一条命中即视为命中。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SenderRules {
+    /// 完整的发送方号码，忽略空格与连字符
+    pub senders: Vec<String>,
+    /// 发送方号码前缀，例如营销短信常用的 `1069`
+    pub sender_prefixes: Vec<String>,
+    /// 原文中包含的文字，例如 `退订回T`
+    pub content: Vec<String>,
+}
+
+impl SenderRules {
+    fn matches(&self, sender: &str, content: &str) -> bool {
+        self.senders.iter().any(|rule| normalize(rule) == sender)
+            || self
+                .sender_prefixes
+                .iter()
+                .any(|prefix| !prefix.is_empty() && sender.starts_with(&normalize(prefix)))
+            || self.content.iter().any(|text| !text.is_empty() && content.contains(text.as_str()))
+    }
+}
+
+/// 短信通知的过滤规则。
+///
+/// ```toml
+/// [sms.filter]
+/// mode = "block"
+/// popup = "smart"
+/// block = { sender_prefixes = ["1069"], content = ["退订回T", "回TD退订"] }
+/// allow = { senders = ["95588"] }
+/// aliases = { "95588" = "工商银行" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmsFilter {
+    pub mode: FilterMode,
+    /// 屏蔽规则，只在屏蔽名单模式下生效
+    pub block: SenderRules,
+    /// 放行规则：白名单模式下只有命中的短信弹通知，屏蔽名单模式下作为屏蔽规则的例外
+    pub allow: SenderRules,
+    /// 带验证码的短信总是弹通知
+    pub always_show_codes: bool,
+    /// 被过滤的短信仍写入短信历史
+    pub record_suppressed: bool,
+    /// 未被过滤的短信如何弹出通知
+    pub popup: PopupMode,
+    /// 号码到显示名称的映射，号码忽略空格与连字符
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for SmsFilter {
+    fn default() -> Self {
+        Self {
+            mode: FilterMode::Block,
+            block: SenderRules::default(),
+            allow: SenderRules::default(),
+            always_show_codes: true,
+            record_suppressed: true,
+            popup: PopupMode::default(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+/// 短信被过滤的原因。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suppression {
+    /// 命中屏蔽规则
+    Blocked,
+    /// 白名单模式下未命中放行规则
+    NotAllowed,
+}
+
+impl Suppression {
+    /// 响应与审计记录中使用的标识。
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Suppression::Blocked => "blocked",
+            Suppression::NotAllowed => "not_allowed",
+        }
+    }
+}
+
+impl SmsFilter {
+    /// 判定短信是否弹出通知。
+    ///
+    /// # Arguments
+    /// * `sender` - 发送方
+    /// * `content` - 短信原文
+    /// * `code` - 验证码，可能为空
+    ///
+    /// # Returns
+    /// 需要过滤时返回原因
+    pub fn check(&self, sender: &str, content: &str, code: &str) -> Option<Suppression> {
+        if self.always_show_codes && !code.is_empty() {
+            return None;
+        }
+        let sender = normalize(sender);
+        if self.allow.matches(&sender, content) {
+            return None;
+        }
+        match self.mode {
+            FilterMode::Allow => Some(Suppression::NotAllowed),
+            FilterMode::Block => self.block.matches(&sender, content).then_some(Suppression::Blocked),
+        }
+    }
+
+    /// 查找号码的显示名称。
+    ///
+    /// # Arguments
+    /// * `sender` - 发送方号码
+    ///
+    /// # Returns
+    /// 配置了非空名称时返回该名称
+    pub fn alias(&self, sender: &str) -> Option<&str> {
+        let sender = normalize(sender);
+        self.aliases
+            .iter()
+            .find(|(number, _)| normalize(number) == sender)
+            .map(|(_, name)| name.trim())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// 去掉号码中的空格与连字符。
+fn normalize(number: &str) -> String {
+    number.chars().filter(|c| !c.is_whitespace() && *c != '-').collect()
+}
@@ -0,0 +1,353 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 短信历史模块。
+ * 通知过期后短信即无处可查，收到的每条短信（发送方、原文、验证码与时间）写入本地 SQLite 数据库，
+ * 通过 `GET /sms/history` 查询。数据库按条数与天数上限淘汰旧记录，打开失败时只记录日志，不影响接收短信。
+ * 在通知中输入的短信回复也保存在同一数据库中，等待手机端通过 `GET /sms/outbox` 取走后发送，
+ * 重启后仍然保留，24 小时内未被取走的回复作废。
+ * 操作审计记录同样写入该数据库，与短信使用相同的保留上限。
+ */
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use fastsync_core::timeline::CaptureTime;
+use crate::audit::AuditRecord;
+
+/// 短信历史的保留上限，两者任一超出即删除最旧的记录。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmsRetention {
+    /// 最多保留的条数
+    pub max_rows: usize,
+    /// 最多保留的天数，按到达时间计算
+    pub max_age_days: u32,
+}
+
+impl Default for SmsRetention {
+    fn default() -> Self {
+        Self {
+            max_rows: 5000,
+            max_age_days: 30,
+        }
+    }
+}
+
+/// 一条短信历史记录。
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsRecord {
+    pub id: i64,
+    pub sender: String,
+    pub content: String,
+    /// 手机端发送或接收端提取的验证码
+    pub code: Option<String>,
+    /// 短信在手机上收到的时间（毫秒时间戳，已校正）
+    pub captured_at: i64,
+    /// 到达接收端的时间（毫秒时间戳）
+    pub received_at: i64,
+    /// 来源设备
+    pub device: Option<String>,
+}
+
+/// 回复在待发送队列中保留的毫秒数，超过后不再交给手机端。
+const REPLY_TTL_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// 一条待手机端发送的短信回复。
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsReply {
+    pub id: i64,
+    /// 收件人，即原短信的发送方
+    pub to: String,
+    pub body: String,
+    /// 输入回复的时间（毫秒时间戳）
+    pub created_at: i64,
+}
+
+/// 查询条件。
+#[derive(Debug, Clone, Default)]
+pub struct SmsHistoryFilter {
+    pub limit: usize,
+    pub offset: usize,
+    /// 只返回该发送方的短信
+    pub sender: Option<String>,
+}
+
+/// 短信历史数据库。
+pub struct SmsHistory {
+    connection: Option<Mutex<Connection>>,
+    retention: RwLock<SmsRetention>,
+}
+
+impl SmsHistory {
+    /// 打开或创建数据库，失败时记录日志并不再保存历史。
+    ///
+    /// # Arguments
+    /// * `path` - 数据库文件路径，为 None 时不保存历史
+    /// * `retention` - 保留上限
+    pub fn open(path: Option<PathBuf>, retention: SmsRetention) -> Self {
+        let connection = path.as_deref().and_then(|path| {
+            open_database(path)
+                .map_err(|e| tracing::error!("Failed to open SMS history {:?}, history is disabled: {:#}", path, e))
+                .ok()
+        });
+        Self {
+            connection: connection.map(Mutex::new),
+            retention: RwLock::new(retention),
+        }
+    }
+
+    /// 写入一条短信并淘汰超出上限的旧记录，失败只记录日志，不影响调用方。
+    ///
+    /// # Arguments
+    /// * `sender` - 发送方
+    /// * `content` - 短信原文
+    /// * `code` - 验证码，为空时不记录
+    /// * `capture` - 短信的捕获与到达时间
+    /// * `device` - 来源设备
+    pub fn record(&self, sender: &str, content: &str, code: &str, capture: CaptureTime, device: Option<&str>) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+        let connection = connection.lock().unwrap();
+        let code = (!code.is_empty()).then_some(code);
+        let result = connection
+            .execute(
+                "INSERT INTO sms (sender, content, code, captured_at, received_at, device) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![sender, content, code, capture.captured_at, capture.received_at, device],
+            )
+            .and_then(|_| self.prune(&connection, capture.received_at));
+        if let Err(e) = result {
+            tracing::error!("Failed to write SMS history: {:?}", e);
+        }
+    }
+
+    /// 修改保留上限，下一次写入时按新的上限删除旧记录。
+    pub(crate) fn set_retention(&self, retention: SmsRetention) {
+        *self.retention.write().unwrap() = retention;
+    }
+
+    /// 按保留上限删除旧记录。
+    ///
+    /// # Returns
+    /// 删除的条数
+    fn prune(&self, connection: &Connection, now: i64) -> rusqlite::Result<usize> {
+        let retention = *self.retention.read().unwrap();
+        let cutoff = now - i64::from(retention.max_age_days) * 24 * 60 * 60 * 1000;
+        let expired = connection.execute("DELETE FROM sms WHERE received_at < ?1", params![cutoff])?;
+        let overflow = connection.execute(
+            "DELETE FROM sms WHERE id <= (SELECT id FROM sms ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+            params![retention.max_rows as i64],
+        )?;
+        if expired + overflow > 0 {
+            tracing::debug!("Pruned {} old SMS history rows", expired + overflow);
+        }
+        Ok(expired + overflow)
+    }
+
+    /// 按捕获时间倒序查询短信，最新的在前。
+    ///
+    /// # Arguments
+    /// * `filter` - 分页与发送方条件
+    pub fn query(&self, filter: &SmsHistoryFilter) -> anyhow::Result<Vec<SmsRecord>> {
+        let Some(connection) = &self.connection else {
+            return Ok(Vec::new());
+        };
+        let connection = connection.lock().unwrap();
+        let mut statement = connection.prepare_cached(
+            "SELECT id, sender, content, code, captured_at, received_at, device FROM sms \
+             WHERE ?1 IS NULL OR sender = ?1 ORDER BY captured_at DESC, id DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let records = statement
+            .query_map(params![filter.sender, filter.limit as i64, filter.offset as i64], |row| {
+                Ok(SmsRecord {
+                    id: row.get(0)?,
+                    sender: row.get(1)?,
+                    content: row.get(2)?,
+                    code: row.get(3)?,
+                    captured_at: row.get(4)?,
+                    received_at: row.get(5)?,
+                    device: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// 写入一条操作审计记录并淘汰超出上限的旧记录，失败只记录日志，不影响调用方。
+    pub fn record_audit(&self, record: &AuditRecord) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+        let connection = connection.lock().unwrap();
+        let result = serde_json::to_string(record)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                connection.execute(
+                    "INSERT INTO audit (item_id, action, timestamp, record) VALUES (?1, ?2, ?3, ?4)",
+                    params![record.item_id, record.action, record.timestamp, json],
+                )?;
+                Ok(())
+            })
+            .and_then(|()| Ok(self.prune_audit(&connection, record.timestamp)?));
+        if let Err(e) = result {
+            tracing::error!("Failed to write audit record to SMS history: {:?}", e);
+        }
+    }
+
+    /// 按保留上限删除旧的审计记录。
+    fn prune_audit(&self, connection: &Connection, now: i64) -> rusqlite::Result<usize> {
+        let retention = *self.retention.read().unwrap();
+        let cutoff = now - i64::from(retention.max_age_days) * 24 * 60 * 60 * 1000;
+        let expired = connection.execute("DELETE FROM audit WHERE timestamp < ?1", params![cutoff])?;
+        let overflow = connection.execute(
+            "DELETE FROM audit WHERE id <= (SELECT id FROM audit ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+            params![retention.max_rows as i64],
+        )?;
+        Ok(expired + overflow)
+    }
+
+    /// 最近写入的审计记录，最新的在前。
+    ///
+    /// # Arguments
+    /// * `limit` - 最多返回的条数
+    pub fn audit_records(&self, limit: usize) -> anyhow::Result<Vec<AuditRecord>> {
+        let Some(connection) = &self.connection else {
+            return Ok(Vec::new());
+        };
+        let connection = connection.lock().unwrap();
+        let mut statement = connection.prepare_cached("SELECT record FROM audit ORDER BY id DESC LIMIT ?1")?;
+        let rows = statement
+            .query_map(params![limit as i64], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        // 无法解析的行来自更新的版本，跳过而不是让整个查询失败
+        Ok(rows.iter().filter_map(|json| serde_json::from_str(json).ok()).collect())
+    }
+
+    /// 将一条回复加入待发送队列。
+    ///
+    /// # Arguments
+    /// * `to` - 收件人
+    /// * `body` - 回复内容
+    /// * `now` - 当前时间（毫秒时间戳）
+    ///
+    /// # Returns
+    /// 回复标识，数据库不可用时报错
+    pub fn enqueue_reply(&self, to: &str, body: &str, now: i64) -> anyhow::Result<i64> {
+        let Some(connection) = &self.connection else {
+            anyhow::bail!("SMS history is disabled, replies cannot be queued");
+        };
+        let connection = connection.lock().unwrap();
+        expire_replies(&connection, now)?;
+        connection.execute(
+            "INSERT INTO outbox (recipient, body, created_at) VALUES (?1, ?2, ?3)",
+            params![to, body, now],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    /// 尚未发送且未过期的回复，最早的在前。
+    ///
+    /// # Arguments
+    /// * `now` - 当前时间（毫秒时间戳）
+    pub fn pending_replies(&self, now: i64) -> anyhow::Result<Vec<SmsReply>> {
+        let Some(connection) = &self.connection else {
+            return Ok(Vec::new());
+        };
+        let connection = connection.lock().unwrap();
+        expire_replies(&connection, now)?;
+        let mut statement = connection
+            .prepare_cached("SELECT id, recipient, body, created_at FROM outbox WHERE sent_at IS NULL ORDER BY id")?;
+        let replies = statement
+            .query_map([], |row| {
+                Ok(SmsReply {
+                    id: row.get(0)?,
+                    to: row.get(1)?,
+                    body: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(replies)
+    }
+
+    /// 标记回复已由手机端发送，重复确认不报错。
+    ///
+    /// # Arguments
+    /// * `id` - 回复标识
+    /// * `now` - 当前时间（毫秒时间戳）
+    ///
+    /// # Returns
+    /// 回复不存在或已过期时为 false
+    pub fn ack_reply(&self, id: i64, now: i64) -> anyhow::Result<bool> {
+        let Some(connection) = &self.connection else {
+            return Ok(false);
+        };
+        let connection = connection.lock().unwrap();
+        expire_replies(&connection, now)?;
+        let updated = connection.execute(
+            "UPDATE outbox SET sent_at = COALESCE(sent_at, ?2) WHERE id = ?1",
+            params![id, now],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+/// 删除超过保留时长的回复，未发送就过期的记录日志。
+fn expire_replies(connection: &Connection, now: i64) -> rusqlite::Result<()> {
+    let cutoff = now - REPLY_TTL_MS;
+    let unsent: Vec<String> = connection
+        .prepare_cached("SELECT recipient FROM outbox WHERE created_at < ?1 AND sent_at IS NULL")?
+        .query_map(params![cutoff], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for recipient in unsent {
+        tracing::warn!("Reply to {} expired before the phone picked it up", recipient);
+    }
+    connection.execute("DELETE FROM outbox WHERE created_at < ?1", params![cutoff])?;
+    Ok(())
+}
+
+/// 打开数据库并建表。
+fn open_database(path: &Path) -> anyhow::Result<Connection> {
+    use anyhow::Context;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let connection = Connection::open(path)?;
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sms (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             sender TEXT NOT NULL,
+             content TEXT NOT NULL,
+             code TEXT,
+             captured_at INTEGER NOT NULL,
+             received_at INTEGER NOT NULL,
+             device TEXT
+         );
+         CREATE INDEX IF NOT EXISTS sms_captured_at ON sms (captured_at);
+         CREATE INDEX IF NOT EXISTS sms_sender ON sms (sender);
+         CREATE TABLE IF NOT EXISTS outbox (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             recipient TEXT NOT NULL,
+             body TEXT NOT NULL,
+             created_at INTEGER NOT NULL,
+             sent_at INTEGER
+         );
+         CREATE TABLE IF NOT EXISTS audit (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             item_id TEXT NOT NULL,
+             action TEXT NOT NULL,
+             timestamp INTEGER NOT NULL,
+             record TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS audit_item_id ON audit (item_id);",
+    )?;
+    Ok(connection)
+}
+
+/// 默认的短信历史数据库路径：`<漫游配置目录>/FastSync/sms_history.db`，Windows 上即 `%APPDATA%\FastSync`。
+pub fn default_sms_history_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("FastSync").join("sms_history.db"))
+}
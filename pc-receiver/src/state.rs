@@ -0,0 +1,281 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ */
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tower_http::cors::CorsLayer;
+use fastsync_core::dedup::{ClipboardPushes, RecentUploads, DEDUP_TTL};
+use fastsync_core::timeline::ClockSkew;
+use crate::admission::Pipeline;
+use crate::attention::Attention;
+use crate::audit::AuditLog;
+use crate::body_limit::BodyLimits;
+use crate::config::Config;
+use crate::clipboard::{ClipboardBackend, ClipboardHistory, ClipboardWatch};
+use crate::burst::BurstTracker;
+use crate::decode_pool::DecodePool;
+use crate::devices::DeviceRegistry;
+use crate::events::{EventBus, ServerEvent};
+use crate::idempotency::IdempotencyStore;
+use crate::notifier::queue::NotificationQueue;
+use crate::notifier::Notifier;
+use crate::pairing::{PairingStore, PairingThrottle};
+use crate::policy::PolicyEngine;
+use crate::realtime::{Outgoing, Sessions, CLOSE_REVOKED};
+use crate::rate_limit::RateLimiter;
+use crate::resumable::ResumableUploads;
+use crate::schedule::PauseSchedule;
+use crate::sms_code::CodeExtractor;
+use crate::sms_history::SmsHistory;
+use crate::selfcheck::StartupReport;
+use crate::storage::StorageMonitor;
+use crate::suppression::MissedTracker;
+use crate::thumbnails::PhotoIndex;
+use crate::timings::TimingStats;
+
+/// 程序运行模式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    /// 带托盘与通知的桌面模式
+    Desktop,
+    /// 无桌面会话的纯接收模式，图片直接落盘
+    Headless,
+    /// 作为 Windows 服务运行，能力与 Headless 相同
+    Service,
+}
+
+impl RunMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunMode::Desktop => "desktop",
+            RunMode::Headless => "headless",
+            RunMode::Service => "service",
+        }
+    }
+}
+
+/// 图片自动保存设置。保存目录随配置重新载入更新，是否启用可在运行时切换。
+pub(crate) struct AutoSave {
+    dir: RwLock<PathBuf>,
+    enabled: AtomicBool,
+}
+
+impl AutoSave {
+    pub(crate) fn new(dir: PathBuf, enabled: bool) -> Self {
+        Self {
+            dir: RwLock::new(dir),
+            enabled: AtomicBool::new(enabled),
+        }
+    }
+
+    /// 保存目录。
+    pub(crate) fn dir(&self) -> PathBuf {
+        self.dir.read().unwrap().clone()
+    }
+
+    pub(crate) fn set_dir(&self, dir: PathBuf) {
+        *self.dir.write().unwrap() = dir;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 启用时返回保存目录，未启用时图片由通知中的保存对话框处理。
+    pub(crate) fn target(&self) -> Option<PathBuf> {
+        self.target_for(None)
+    }
+
+    /// 按设备单独的设置决定是否自动保存，未单独设置时沿用全局开关。
+    pub(crate) fn target_for(&self, device_setting: Option<bool>) -> Option<PathBuf> {
+        device_setting.unwrap_or_else(|| self.is_enabled()).then(|| self.dir())
+    }
+}
+
+/// 应用共享状态，由所有路由处理器共享。
+#[derive(Clone)]
+pub(crate) struct AppState {
+    pub mode: RunMode,
+    pub notifier: Arc<dyn Notifier>,
+    /// 处理器请求的通知经由该队列显示，失败时重试
+    pub notifications: Arc<NotificationQueue>,
+    pub clipboard: Arc<dyn ClipboardBackend>,
+    /// 电脑剪贴板的变化记录，手机端通过 `GET /clipboard` 拉取
+    pub clipboard_watch: Arc<ClipboardWatch>,
+    /// 各设备的 WebSocket 连接，用于推送剪贴板变化、短信回复与查找手机
+    pub sessions: Arc<Sessions>,
+    /// 最近收到的手机剪贴板，通知消失后仍可找回
+    pub clipboard_history: Arc<ClipboardHistory>,
+    /// 复制图片时的解码线程池
+    pub decode_pool: Arc<DecodePool>,
+    /// 图片自动保存设置，启用时收到的图片直接写入保存目录
+    pub auto_save: Arc<AutoSave>,
+    /// 各设备连续上传的图片分组，分组内只更新同一条通知
+    pub bursts: Arc<BurstTracker>,
+    pub events: EventBus,
+    /// 已注册处理器的能力标识，以及与处理器无关的 `video`、`auth`
+    pub features: Arc<Vec<String>>,
+    /// 已注册处理器的接口，形如 `POST /upload`
+    pub endpoints: Arc<Vec<String>>,
+    /// 服务构建的时间，`/health` 据此计算运行时长
+    pub started_at: Instant,
+    pub pairing: Arc<PairingStore>,
+    /// 已配对设备登记表
+    pub devices: Arc<DeviceRegistry>,
+    pub audit: Arc<AuditLog>,
+    /// 最近收到的图片，供手机拉取缩略图
+    pub photos: Arc<PhotoIndex>,
+    /// 最近接收的内容哈希，用于丢弃重试造成的重复上传
+    pub uploads: Arc<RecentUploads>,
+    /// 最近接收的短信，用于丢弃手机端超时重发的短信
+    pub recent_sms: Arc<RecentUploads>,
+    /// 各设备最近一次剪贴板推送，用于丢弃重发与迟到的旧内容
+    pub clipboard_pushes: Arc<ClipboardPushes>,
+    /// 进行中的分块上传
+    pub resumable: Arc<ResumableUploads>,
+    pub policy: Arc<PolicyEngine>,
+    /// 计划暂停时段，期间拒绝所有载荷，配置重新载入时替换
+    pub schedule: Arc<RwLock<PauseSchedule>>,
+    /// 手机端未识别验证码时从短信原文中提取，配置重新载入时替换
+    pub sms_codes: Arc<RwLock<CodeExtractor>>,
+    /// 收到的短信历史
+    pub sms_history: Arc<SmsHistory>,
+    /// 请求体大小上限，启动后不再改变
+    pub body_limits: BodyLimits,
+    /// 准入控制使用的管线计数
+    pub pipeline: Arc<Pipeline>,
+    /// 保存目录最近一次预检的结果
+    pub storage: Arc<StorageMonitor>,
+    /// 各设备的时钟偏差估计
+    pub clock: Arc<ClockSkew>,
+    /// 紧急通知的唤醒与闪烁状态
+    pub attention: Arc<Attention>,
+    /// 各处理阶段的耗时统计
+    pub timings: Arc<TimingStats>,
+    /// 通知抑制来源与期间错过的内容
+    pub missed: Arc<MissedTracker>,
+    /// 启动自检报告，自检完成前为 None
+    pub startup: Arc<Mutex<Option<StartupReport>>>,
+    /// 允许跨域的 JSON 接口使用的 CORS 层，未配置来源时为 None，配置重新载入时替换
+    pub cors: Arc<RwLock<Option<CorsLayer>>>,
+    /// 正在使用的配置，配置文件重新载入或托盘切换开关时更新，处理器每次使用时读取
+    pub config: Arc<RwLock<Config>>,
+    /// 各 IP 携带错误令牌访问数据接口的失败计数
+    pub auth_failures: Arc<Mutex<PairingThrottle>>,
+    /// 各设备与来源 IP 调用上传、短信与剪贴板接口的频率
+    pub rate_limiter: Arc<RateLimiter>,
+    /// 最近使用过的幂等键与第一次请求的响应
+    pub idempotency: Arc<IdempotencyStore>,
+    /// 本机 CA，启用 mTLS 时配对响应中附带客户端证书
+    #[cfg(feature = "tls")]
+    pub tls: Option<Arc<crate::tls::TlsAuthority>>,
+}
+
+impl AppState {
+    /// 把重新载入的配置应用到各组件，只替换与正在使用的配置不同的部分，违规计数、去重记录等运行状态保留。
+    /// 配置已在载入时校验，仍无法应用的项记录错误并沿用原来的设置。
+    ///
+    /// # Arguments
+    /// * `running` - 正在使用的配置
+    /// * `config` - 重新载入的配置
+    pub(crate) fn apply_config(&self, running: &Config, config: &Config) {
+        if config.policy != running.policy {
+            self.policy.set_policy(config.policy.clone());
+        }
+        if config.pause_schedule != running.pause_schedule {
+            match PauseSchedule::new(config.pause_schedule.clone()) {
+                Ok(schedule) => *self.schedule.write().unwrap() = schedule,
+                Err(e) => tracing::error!("{:#}, keeping the current pause schedule", e),
+            }
+        }
+        if config.sms.codes != running.sms.codes {
+            match CodeExtractor::new(config.sms.codes.clone()) {
+                Ok(extractor) => *self.sms_codes.write().unwrap() = extractor,
+                Err(e) => tracing::error!("{:#}, keeping the current SMS code rules", e),
+            }
+        }
+        if config.cors != running.cors {
+            match config.cors.layer() {
+                Ok(cors) => *self.cors.write().unwrap() = cors,
+                Err(e) => tracing::error!("Invalid CORS configuration, keeping the current one: {:#}", e),
+            }
+        }
+        if config.attention != running.attention {
+            self.attention.reconfigure(config.attention.clone());
+        }
+        if config.pipeline != running.pipeline {
+            self.pipeline.set_limits(config.pipeline);
+        }
+        // 临时图片的保留时长由定期清理每次读取
+        if config.uploads.resume_window != running.uploads.resume_window {
+            self.resumable.set_window(config.uploads.resume_window);
+        }
+        if config.sms.dedup_window != running.sms.dedup_window {
+            self.recent_sms.configure(!config.sms.dedup_window.is_zero(), config.sms.dedup_window);
+        }
+        if config.sms.retention() != running.sms.retention() {
+            self.sms_history.set_retention(config.sms.retention());
+        }
+        if config.photos.deduplicate != running.photos.deduplicate {
+            self.uploads.configure(config.photos.deduplicate, DEDUP_TTL);
+        }
+        if config.photos.decode_workers != running.photos.decode_workers {
+            self.decode_pool.set_max_workers(config.photos.decode_workers);
+        }
+        if config.photos.auto_save_dir != running.photos.auto_save_dir {
+            self.storage.forget(&self.auto_save.dir());
+            let dir = config.photos.auto_save_dir.clone();
+            let enabled = dir.is_some() || self.mode != RunMode::Desktop;
+            self.auto_save.set_dir(dir.unwrap_or_else(crate::handlers::photo::default_auto_save_dir));
+            self.auto_save.set_enabled(enabled);
+            if enabled {
+                let _ = self.storage.check(&self.auto_save.dir(), 0);
+            }
+        }
+        if config.clipboard.history != running.clipboard.history {
+            self.clipboard_history.set_capacity(config.clipboard.history);
+        }
+        if config.clipboard.ordering != running.clipboard.ordering {
+            self.clipboard_pushes.set_enforce_order(config.clipboard.ordering);
+        }
+    }
+
+    /// 解除设备配对，发出事件并记录审计日志。
+    ///
+    /// # Returns
+    /// 设备是否存在
+    pub(crate) fn revoke_device(&self, id: &str) -> bool {
+        match self.devices.revoke(id) {
+            Some(device) => {
+                tracing::info!(target: "fastsync::audit", "Device revoked: {} ({})", device.name, device.id);
+                self.sessions.close(&device.id, CLOSE_REVOKED);
+                self.events.emit(ServerEvent::DeviceRevoked { id: device.id, name: device.name });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 登记设备的 WebSocket 连接。
+    /// 令牌校验通过后、连接登记前设备被解除配对时，`revoke_device` 找不到这条连接，这里撤回登记；
+    /// 登记之后的解除配对则由 `revoke_device` 关闭连接。
+    ///
+    /// # Returns
+    /// 设备仍处于配对状态时返回连接标识、发送端与接收端
+    pub(crate) fn register_session(&self, device: &str) -> Option<(u64, mpsc::Sender<Outgoing>, mpsc::Receiver<Outgoing>)> {
+        let (session, sender, receiver) = self.sessions.register(device);
+        if self.devices.get(device).is_none() {
+            self.sessions.unregister(device, session);
+            return None;
+        }
+        Some((session, sender, receiver))
+    }
+}
@@ -0,0 +1,282 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 保存目录预检模块。
+ * 在启动时与接收上传前检查自动保存目录和临时目录是否存在、可写并留有足够空间，
+ * 避免磁盘已满或目录被删除时静默丢失内容。
+ */
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 写入内容后至少保留的剩余空间。
+const HEADROOM_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 预检失败的原因。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageIssue {
+    /// 目录不存在且无法创建
+    Missing,
+    /// 无法在目录中创建文件
+    NotWritable(String),
+    /// 剩余空间不足以容纳内容与预留空间
+    InsufficientSpace { available: u64, required: u64 },
+}
+
+impl StorageIssue {
+    /// 机器可读的原因标识。
+    pub fn reason(&self) -> &'static str {
+        match self {
+            StorageIssue::Missing => "missing_directory",
+            StorageIssue::NotWritable(_) => "not_writable",
+            StorageIssue::InsufficientSpace { .. } => "insufficient_space",
+        }
+    }
+
+    /// 托盘上显示的说明。
+    pub fn describe(&self) -> String {
+        match self {
+            StorageIssue::Missing => "目录不存在".to_string(),
+            StorageIssue::NotWritable(_) => "目录不可写".to_string(),
+            StorageIssue::InsufficientSpace { available, .. } => {
+                format!("磁盘空间不足（剩余 {} MB）", available / 1024 / 1024)
+            }
+        }
+    }
+}
+
+impl IntoResponse for StorageIssue {
+    fn into_response(self) -> Response {
+        let mut body = json!({ "error": "storage", "reason": self.reason() });
+        if let StorageIssue::InsufficientSpace { available, required } = &self {
+            body["available"] = json!(available);
+            body["required"] = json!(required);
+        }
+        (StatusCode::INSUFFICIENT_STORAGE, Json(body)).into_response()
+    }
+}
+
+/// 检查目录能否写入指定大小的内容，目录不存在时尝试创建。
+///
+/// # Arguments
+/// * `dir` - 目标目录
+/// * `incoming` - 即将写入的字节数，启动检查时为 0
+pub fn preflight(dir: &Path, incoming: u64) -> Result<(), StorageIssue> {
+    if !dir.is_dir() && std::fs::create_dir_all(dir).is_err() {
+        return Err(StorageIssue::Missing);
+    }
+
+    // 只读属性、权限与被占用的情况都以实际创建文件为准
+    let probe = dir.join(format!(".fastsync_probe_{}", hex::encode(rand::random::<[u8; 4]>())));
+    let written = std::fs::File::create(&probe).and_then(|mut file| file.write_all(b"0"));
+    let _ = std::fs::remove_file(&probe);
+    if let Err(e) = written {
+        return Err(StorageIssue::NotWritable(e.to_string()));
+    }
+
+    let required = incoming.saturating_add(HEADROOM_BYTES);
+    match available_space(dir) {
+        Some(available) if available < required => Err(StorageIssue::InsufficientSpace { available, required }),
+        _ => Ok(()),
+    }
+}
+
+/// 目录所在磁盘对当前用户可用的剩余空间，无法获取时返回 None。
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(all(windows, any(feature = "notifications", feature = "clipboard")))]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    unsafe { GetDiskFreeSpaceExW(PCWSTR(wide.as_ptr()), Some(&mut available), None, None) }.ok()?;
+    Some(available)
+}
+
+#[cfg(not(any(unix, all(windows, any(feature = "notifications", feature = "clipboard")))))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// 各保存目录最近一次预检的结果，供托盘显示警告。
+#[derive(Default)]
+pub(crate) struct StorageMonitor {
+    issues: Mutex<BTreeMap<PathBuf, StorageIssue>>,
+}
+
+impl StorageMonitor {
+    /// 预检目录并记录结果，状态变化时输出日志。
+    pub(crate) fn check(&self, dir: &Path, incoming: u64) -> Result<(), StorageIssue> {
+        let result = preflight(dir, incoming);
+        let mut issues = self.issues.lock().unwrap();
+        match &result {
+            Ok(()) => {
+                if issues.remove(dir).is_some() {
+                    tracing::info!("Storage at {:?} is usable again", dir);
+                }
+            }
+            Err(issue) => {
+                if issues.get(dir) != Some(issue) {
+                    tracing::warn!("Storage preflight failed for {:?}: {:?}", dir, issue);
+                }
+                issues.insert(dir.to_path_buf(), issue.clone());
+            }
+        }
+        result
+    }
+
+    /// 不再使用某个目录，清除其预检问题。
+    pub(crate) fn forget(&self, dir: &Path) {
+        self.issues.lock().unwrap().remove(dir);
+    }
+
+    /// 当前存在问题的目录说明，没有问题时返回 None。
+    pub(crate) fn warning(&self) -> Option<String> {
+        let issues = self.issues.lock().unwrap();
+        let lines: Vec<String> = issues
+            .iter()
+            .map(|(dir, issue)| format!("{}: {}", dir.display(), issue.describe()))
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 本测试独占的空目录。
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fastsync-storage-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn writable_directory_passes_without_leaving_probes() {
+        let dir = scratch("writable");
+        assert_eq!(preflight(&dir, 0), Ok(()));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_directory_is_created_or_reported() {
+        let dir = scratch("missing");
+        let nested = dir.join("a").join("b");
+        assert_eq!(preflight(&nested, 0), Ok(()));
+        assert!(nested.is_dir());
+
+        // 路径上有同名文件，目录无法创建
+        let file = dir.join("file");
+        std::fs::write(&file, "x").unwrap();
+        assert_eq!(preflight(&file.join("child"), 0), Err(StorageIssue::Missing));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_directory_is_not_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch("read-only");
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+        let result = preflight(&dir, 0);
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // root 不受权限位限制
+        if unsafe { libc::geteuid() } != 0 {
+            assert!(matches!(result, Err(StorageIssue::NotWritable(_))), "{:?}", result);
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let result = preflight(Path::new("/proc"), 0);
+            assert!(matches!(result, Err(StorageIssue::NotWritable(_))), "{:?}", result);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn oversized_content_needs_more_space() {
+        let dir = scratch("space");
+        let result = preflight(&dir, u64::MAX);
+        std::fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(StorageIssue::InsufficientSpace { available, required }) => {
+                assert_eq!(required, u64::MAX);
+                assert!(available < required);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn monitor_tracks_issues_until_resolved_or_forgotten() {
+        let dir = scratch("monitor");
+        let blocker = dir.join("saves");
+        std::fs::write(&blocker, "x").unwrap();
+        let broken = blocker.join("photos");
+        let healthy = dir.join("ok");
+
+        let monitor = StorageMonitor::default();
+        assert_eq!(monitor.check(&healthy, 0), Ok(()));
+        assert_eq!(monitor.warning(), None);
+
+        assert_eq!(monitor.check(&broken, 0), Err(StorageIssue::Missing));
+        assert_eq!(monitor.check(&broken, 0), Err(StorageIssue::Missing));
+        assert_eq!(monitor.warning(), Some(format!("{}: 目录不存在", broken.display())));
+
+        // 目录恢复可用后警告自动清除
+        std::fs::remove_file(&blocker).unwrap();
+        assert_eq!(monitor.check(&broken, 0), Ok(()));
+        assert_eq!(monitor.warning(), None);
+
+        // 不再使用的目录不再提示
+        let other = healthy.join("file");
+        std::fs::write(&other, "x").unwrap();
+        assert!(monitor.check(&other.join("x"), 0).is_err());
+        assert!(monitor.warning().is_some());
+        monitor.forget(&other.join("x"));
+        assert_eq!(monitor.warning(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn issues_describe_themselves() {
+        let space = StorageIssue::InsufficientSpace { available: 10 * 1024 * 1024, required: 80 * 1024 * 1024 };
+        let cases = [
+            (StorageIssue::Missing, "missing_directory", "目录不存在"),
+            (StorageIssue::NotWritable("denied".into()), "not_writable", "目录不可写"),
+            (space.clone(), "insufficient_space", "磁盘空间不足（剩余 10 MB）"),
+        ];
+        for (issue, reason, description) in cases {
+            assert_eq!(issue.reason(), reason, "{:?}", issue);
+            assert_eq!(issue.describe(), description, "{:?}", issue);
+        }
+        assert_eq!(space.into_response().status(), StatusCode::INSUFFICIENT_STORAGE);
+    }
+}
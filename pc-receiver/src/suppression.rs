@@ -0,0 +1,407 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 通知抑制模块。
+ * 专注模式、全屏应用与演示模式期间系统不会弹出通知，这段时间收到的内容记为“错过”，
+ * 所有抑制来源都解除后汇总成一条通知。多个来源可能重叠，只有全部解除才算结束。
+ * 系统状态由 `NotificationStateProbe` 查询。接收端自身的免打扰可以设定结束时间，到期后由轮询解除。
+ */
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+/// 汇总通知中最多列出的内容标题数。
+const MAX_DIGEST_TITLES: usize = 3;
+
+/// 抑制通知的来源。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionSource {
+    /// 系统专注模式（专注助手、安静时间）
+    Focus,
+    /// 全屏游戏或应用
+    FullScreen,
+    /// 演示模式
+    Presentation,
+    /// 接收端自身的免打扰，期间不弹出通知
+    DoNotDisturb,
+}
+
+impl SuppressionSource {
+    /// 是否由系统状态决定，系统轮询只更新这些来源。
+    pub fn is_system(&self) -> bool {
+        !matches!(self, SuppressionSource::DoNotDisturb)
+    }
+}
+
+/// 查询系统当前是否在抑制通知。
+pub trait NotificationStateProbe: Send + Sync {
+    /// 当前抑制通知的系统来源，正常接收通知时返回 None。
+    fn suppressed_by(&self) -> Option<SuppressionSource>;
+}
+
+/// 当前平台的系统状态查询，非 Windows 平台上始终视为正常接收通知。
+pub struct SystemNotificationState;
+
+#[cfg(all(windows, feature = "notifications"))]
+impl NotificationStateProbe for SystemNotificationState {
+    fn suppressed_by(&self) -> Option<SuppressionSource> {
+        use windows::Win32::UI::Shell::{
+            SHQueryUserNotificationState, QUNS_APP, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME,
+            QUNS_RUNNING_D3D_FULL_SCREEN,
+        };
+
+        match unsafe { SHQueryUserNotificationState() } {
+            Ok(QUNS_QUIET_TIME) => Some(SuppressionSource::Focus),
+            Ok(QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_APP) => Some(SuppressionSource::FullScreen),
+            Ok(QUNS_PRESENTATION_MODE) => Some(SuppressionSource::Presentation),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::debug!("SHQueryUserNotificationState failed: {:?}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(all(windows, feature = "notifications")))]
+impl NotificationStateProbe for SystemNotificationState {
+    fn suppressed_by(&self) -> Option<SuppressionSource> {
+        None
+    }
+}
+
+/// 一次抑制期间错过的内容汇总。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissedDigest {
+    /// 错过的内容数
+    pub count: usize,
+    /// 抑制开始时间（毫秒时间戳）
+    pub since: i64,
+    /// 抑制结束时间（毫秒时间戳）
+    pub until: i64,
+    /// 最早错过的几条内容的通知标题
+    pub titles: Vec<String>,
+    /// 按通知分组统计的错过条数，例如 `sms`、`photo`
+    pub kinds: BTreeMap<String, usize>,
+}
+
+impl MissedDigest {
+    /// 按类型描述错过的内容，例如“3 条短信和 1 张图片”。
+    pub fn describe(&self) -> String {
+        // 按显示顺序合并分组，连拍汇总也算图片
+        let mut counts: BTreeMap<(usize, &str), usize> = BTreeMap::new();
+        for (group, &count) in &self.kinds {
+            let kind = match group.as_str() {
+                "sms" => (0, "条短信"),
+                "clipboard" => (1, "条剪贴板"),
+                "photo" | "photo_burst" => (2, "张图片"),
+                "video" => (3, "个视频"),
+                "file" => (4, "个文件"),
+                _ => (5, "项其他内容"),
+            };
+            *counts.entry(kind).or_default() += count;
+        }
+        let parts: Vec<String> = counts.iter().map(|((_, unit), count)| format!("{} {}", count, unit)).collect();
+        match parts.as_slice() {
+            [] => format!("{} 项", self.count),
+            [only] => only.clone(),
+            [init @ .., last] => format!("{}和 {}", init.join("、"), last),
+        }
+    }
+}
+
+#[derive(Default)]
+struct MissedState {
+    active: BTreeSet<SuppressionSource>,
+    /// 本次抑制开始的时间，未抑制时为 None
+    since: Option<i64>,
+    count: usize,
+    titles: Vec<String>,
+    kinds: BTreeMap<String, usize>,
+    /// 免打扰的结束时间（毫秒时间戳），为 None 时直到手动关闭
+    dnd_until: Option<i64>,
+}
+
+impl MissedState {
+    /// 在来源集合变化后判断抑制是否开始或结束。
+    fn transition(&mut self, was_suppressed: bool, now: i64) -> Option<MissedDigest> {
+        match (was_suppressed, !self.active.is_empty()) {
+            (false, true) => {
+                tracing::info!("Notifications suppressed by {:?}", self.active);
+                self.since = Some(now);
+                None
+            }
+            (true, false) => {
+                tracing::info!("Notification suppression lifted, {} items missed", self.count);
+                let since = self.since.take().unwrap_or(now);
+                let count = std::mem::take(&mut self.count);
+                let titles = std::mem::take(&mut self.titles);
+                let kinds = std::mem::take(&mut self.kinds);
+                (count > 0).then_some(MissedDigest { count, since, until: now, titles, kinds })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 抑制来源与错过的内容，由所有接口共享。只依赖传入的时间，不执行任何显示。
+#[derive(Default)]
+pub(crate) struct MissedTracker {
+    state: Mutex<MissedState>,
+}
+
+impl MissedTracker {
+    /// 设置某个来源是否在抑制通知。
+    ///
+    /// # Arguments
+    /// * `source` - 抑制来源
+    /// * `active` - 是否正在抑制
+    /// * `now` - 当前时间（毫秒时间戳）
+    ///
+    /// # Returns
+    /// 所有来源都已解除且期间有错过的内容时返回汇总，错过列表随之清空
+    pub(crate) fn set(&self, source: SuppressionSource, active: bool, now: i64) -> Option<MissedDigest> {
+        let mut state = self.state.lock().unwrap();
+        let was_suppressed = !state.active.is_empty();
+        if active {
+            state.active.insert(source);
+        } else {
+            state.active.remove(&source);
+        }
+        if source == SuppressionSource::DoNotDisturb {
+            state.dnd_until = None;
+        }
+        state.transition(was_suppressed, now)
+    }
+
+    /// 开启免打扰并设定结束时间，已开启时只更新结束时间。
+    ///
+    /// # Arguments
+    /// * `until` - 结束时间（毫秒时间戳），为 None 时直到手动关闭
+    /// * `now` - 当前时间（毫秒时间戳）
+    pub(crate) fn set_do_not_disturb_until(&self, until: Option<i64>, now: i64) {
+        let mut state = self.state.lock().unwrap();
+        let was_suppressed = !state.active.is_empty();
+        state.active.insert(SuppressionSource::DoNotDisturb);
+        state.dnd_until = until;
+        state.transition(was_suppressed, now);
+    }
+
+    /// 免打扰已到结束时间时关闭。
+    ///
+    /// # Arguments
+    /// * `now` - 当前时间（毫秒时间戳）
+    ///
+    /// # Returns
+    /// 关闭后所有来源都已解除且期间有错过的内容时返回汇总
+    pub(crate) fn expire_do_not_disturb(&self, now: i64) -> Option<MissedDigest> {
+        let expired = self.state.lock().unwrap().dnd_until.is_some_and(|until| until <= now);
+        if !expired {
+            return None;
+        }
+        tracing::info!("Do-not-disturb period ended");
+        self.set(SuppressionSource::DoNotDisturb, false, now)
+    }
+
+    /// 免打扰是否开启及其结束时间。
+    ///
+    /// # Returns
+    /// 未开启时为 None，开启时为结束时间（毫秒时间戳），直到手动关闭时为 `Some(None)`
+    pub(crate) fn do_not_disturb(&self) -> Option<Option<i64>> {
+        let state = self.state.lock().unwrap();
+        state.active.contains(&SuppressionSource::DoNotDisturb).then_some(state.dnd_until)
+    }
+
+    /// 用一次系统查询的结果替换所有系统来源，系统在两种抑制状态之间切换时不会提前汇总。
+    ///
+    /// # Arguments
+    /// * `suppressed_by` - 系统当前的抑制来源
+    /// * `now` - 当前时间（毫秒时间戳）
+    pub(crate) fn set_system(&self, suppressed_by: Option<SuppressionSource>, now: i64) -> Option<MissedDigest> {
+        let mut state = self.state.lock().unwrap();
+        let was_suppressed = !state.active.is_empty();
+        state.active.retain(|source| !source.is_system());
+        state.active.extend(suppressed_by.filter(SuppressionSource::is_system));
+        state.transition(was_suppressed, now)
+    }
+
+    /// 当前的抑制来源。
+    pub(crate) fn active(&self) -> Vec<SuppressionSource> {
+        self.state.lock().unwrap().active.iter().copied().collect()
+    }
+
+    /// 是否由接收端自身的免打扰抑制，此时不调用通知后端。
+    pub(crate) fn holds_display(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .active
+            .contains(&SuppressionSource::DoNotDisturb)
+    }
+
+    /// 抑制期间收到内容时记为错过。
+    ///
+    /// # Arguments
+    /// * `group` - 内容的通知分组，汇总时按分组统计
+    /// * `title` - 内容的通知标题
+    ///
+    /// # Returns
+    /// 是否处于抑制期间
+    pub(crate) fn record(&self, group: &str, title: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.active.is_empty() {
+            return false;
+        }
+        state.count += 1;
+        *state.kinds.entry(group.to_string()).or_default() += 1;
+        if state.titles.len() < MAX_DIGEST_TITLES {
+            state.titles.push(title.to_string());
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 脚本中的一步操作。
+    enum Step {
+        System(Option<SuppressionSource>),
+        DoNotDisturb(bool),
+        DoNotDisturbUntil(i64),
+        Expire,
+        Receive(&'static str, &'static str),
+    }
+
+    /// 按脚本依次执行，每步附带执行时刻与期望的结果：收到内容时为是否记为错过，其余为是否汇总。
+    fn run(script: &[(i64, Step, bool)]) -> Vec<MissedDigest> {
+        let tracker = MissedTracker::default();
+        let mut digests = Vec::new();
+        for (index, (now, step, expected)) in script.iter().enumerate() {
+            let now = *now;
+            let (happened, digest) = match step {
+                Step::System(source) => {
+                    let digest = tracker.set_system(*source, now);
+                    (digest.is_some(), digest)
+                }
+                Step::DoNotDisturb(active) => {
+                    let digest = tracker.set(SuppressionSource::DoNotDisturb, *active, now);
+                    (digest.is_some(), digest)
+                }
+                Step::DoNotDisturbUntil(until) => {
+                    tracker.set_do_not_disturb_until(Some(*until), now);
+                    (false, None)
+                }
+                Step::Expire => {
+                    let digest = tracker.expire_do_not_disturb(now);
+                    (digest.is_some(), digest)
+                }
+                Step::Receive(group, title) => (tracker.record(group, title), None),
+            };
+            assert_eq!(happened, *expected, "step {} at {}", index, now);
+            digests.extend(digest);
+        }
+        digests
+    }
+
+    #[test]
+    fn overlapping_sources_flush_once_all_are_lifted() {
+        let digests = run(&[
+            (0, Step::Receive("sms", "未抑制"), false),
+            (1000, Step::System(Some(SuppressionSource::Focus)), false),
+            (1100, Step::Receive("sms", "短信 1"), true),
+            (2000, Step::DoNotDisturbUntil(4000), false),
+            (2100, Step::Receive("photo", "图片"), true),
+            // 系统来源解除，免打扰仍在
+            (3000, Step::System(None), false),
+            (3100, Step::Receive("sms", "短信 2"), true),
+            (3500, Step::Expire, false),
+            (4000, Step::Expire, true),
+            (4100, Step::Receive("sms", "已恢复"), false),
+        ]);
+        assert_eq!(
+            digests,
+            [MissedDigest {
+                count: 3,
+                since: 1000,
+                until: 4000,
+                titles: vec!["短信 1".to_string(), "图片".to_string(), "短信 2".to_string()],
+                kinds: BTreeMap::from([("photo".to_string(), 1), ("sms".to_string(), 2)]),
+            }]
+        );
+        assert_eq!(digests[0].describe(), "2 条短信和 1 张图片");
+    }
+
+    #[test]
+    fn switching_between_system_sources_does_not_flush() {
+        let digests = run(&[
+            (1000, Step::System(Some(SuppressionSource::Focus)), false),
+            (1100, Step::Receive("sms", "a"), true),
+            (2000, Step::System(Some(SuppressionSource::FullScreen)), false),
+            (2100, Step::Receive("sms", "b"), true),
+            (3000, Step::System(None), true),
+        ]);
+        assert_eq!(digests.len(), 1);
+        assert_eq!((digests[0].count, digests[0].since, digests[0].until), (2, 1000, 3000));
+    }
+
+    #[test]
+    fn nothing_missed_means_no_digest() {
+        let digests = run(&[
+            (1000, Step::DoNotDisturb(true), false),
+            (2000, Step::DoNotDisturb(false), false),
+            // 下一次抑制重新计数
+            (3000, Step::DoNotDisturb(true), false),
+            (3100, Step::Receive("clipboard", "剪贴板"), true),
+            (4000, Step::DoNotDisturb(false), true),
+        ]);
+        assert_eq!(digests.len(), 1);
+        assert_eq!((digests[0].count, digests[0].since), (1, 3000));
+    }
+
+    #[test]
+    fn only_do_not_disturb_holds_the_display() {
+        let tracker = MissedTracker::default();
+        tracker.set_system(Some(SuppressionSource::Presentation), 0);
+        assert!(!tracker.holds_display());
+        tracker.set(SuppressionSource::DoNotDisturb, true, 0);
+        assert!(tracker.holds_display());
+        assert_eq!(tracker.do_not_disturb(), Some(None));
+        assert_eq!(tracker.active(), [SuppressionSource::Presentation, SuppressionSource::DoNotDisturb]);
+    }
+
+    #[test]
+    fn digest_lists_the_first_titles_only() {
+        let tracker = MissedTracker::default();
+        tracker.set(SuppressionSource::DoNotDisturb, true, 0);
+        for title in ["1", "2", "3", "4", "5"] {
+            tracker.record("sms", title);
+        }
+        let digest = tracker.set(SuppressionSource::DoNotDisturb, false, 1).unwrap();
+        assert_eq!(digest.count, 5);
+        assert_eq!(digest.titles, ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn describe_groups_kinds_in_display_order() {
+        let cases: [(&[(&str, usize)], &str); 4] = [
+            (&[("sms", 3)], "3 条短信"),
+            (&[("photo", 1), ("photo_burst", 2), ("sms", 1)], "1 条短信和 3 张图片"),
+            (&[("file", 1), ("video", 1), ("clipboard", 2), ("location", 1)], "2 条剪贴板、1 个视频、1 个文件和 1 项其他内容"),
+            (&[], "0 项"),
+        ];
+        for (kinds, expected) in cases {
+            let digest = MissedDigest {
+                count: kinds.iter().map(|(_, count)| count).sum(),
+                since: 0,
+                until: 0,
+                titles: Vec::new(),
+                kinds: kinds.iter().map(|(group, count)| (group.to_string(), *count)).collect(),
+            };
+            assert_eq!(digest.describe(), expected, "{:?}", kinds);
+        }
+    }
+}
@@ -0,0 +1,253 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 临时图片清理模块。
+ * 收到的图片与通知预览写入系统临时目录，通知过期或用户点击按钮后立即删除，
+ * 遗漏的文件（例如程序退出时通知仍在显示）由定期清理按修改时间删除。
+ * 只处理以 `fastsync_` 开头、扩展名为支持的图片或视频格式、`.bin` 或 `.txt` 的文件，不会误删其他程序的文件。
+ */
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime};
+use crate::image_format::ImageFormat;
+use crate::video::VideoFormat;
+
+/// 默认保留时长，超过该时间的临时图片会被定期清理删除。
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 定期清理的间隔。
+pub const CLEAN_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 通用文件模式下收到的非图片文件的扩展名。
+pub(crate) const FILE_EXTENSION: &str = "bin";
+
+/// 另存为文件的较长剪贴板文本的扩展名。
+pub(crate) const TEXT_EXTENSION: &str = "txt";
+
+/// 本程序创建的临时文件的文件名前缀。
+const PREFIX: &str = "fastsync_";
+
+/// 通知仍在显示、不能被清理的临时图片。
+static RETAINED: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// 一次清理的结果。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// 删除的文件数
+    pub files: usize,
+    /// 释放的字节数
+    pub bytes: u64,
+}
+
+/// 是否为本程序在临时目录中创建的文件：位于临时目录，文件名以 `fastsync_` 开头，
+/// 扩展名为支持的图片或视频格式、通用文件的 `.bin` 或剪贴板文本的 `.txt`。
+pub fn is_temp_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+    let known = path.extension().and_then(|extension| extension.to_str()).is_some_and(|extension| {
+        extension == FILE_EXTENSION
+            || extension == TEXT_EXTENSION
+            || ImageFormat::from_extension(extension).is_some()
+            || VideoFormat::from_extension(extension).is_some()
+    });
+    name.starts_with(PREFIX) && known && path.parent() == Some(std::env::temp_dir().as_path())
+}
+
+/// 在通知显示期间保留临时文件，超时后自动解除。
+///
+/// # Arguments
+/// * `path` - 临时文件路径
+/// * `expires_in` - 通知的保留时长
+pub(crate) fn retain(path: &Path, expires_in: Duration) {
+    RETAINED.lock().unwrap().insert(path.to_path_buf());
+
+    let path = path.to_path_buf();
+    std::thread::spawn(move || {
+        std::thread::sleep(expires_in);
+        RETAINED.lock().unwrap().remove(&path);
+    });
+}
+
+/// 解除保留，例如文件已移出临时目录。
+pub(crate) fn release(path: &Path) {
+    RETAINED.lock().unwrap().remove(path);
+}
+
+/// 临时文件是否因通知仍在显示而被保留。
+pub(crate) fn is_retained(path: &Path) -> bool {
+    RETAINED.lock().unwrap().contains(path)
+}
+
+/// 删除通知对应的临时文件，已移动或已删除的文件直接跳过。
+///
+/// # Arguments
+/// * `paths` - 通知登记的临时文件
+pub(crate) fn discard(paths: &[PathBuf]) {
+    let mut report = CleanupReport::default();
+    for path in paths.iter().filter(|path| is_temp_file(path)) {
+        if let Some(bytes) = remove(path) {
+            report.files += 1;
+            report.bytes += bytes;
+        }
+        release(path);
+    }
+    if report.files > 0 {
+        tracing::debug!("Discarded {} temp files ({} bytes)", report.files, report.bytes);
+    }
+}
+
+/// 删除超过保留时长的临时图片，跳过通知仍在显示的文件。
+///
+/// # Arguments
+/// * `max_age` - 保留时长，按文件的修改时间计算
+///
+/// # Returns
+/// 删除的文件数与释放的字节数
+pub fn clean_temp_files(max_age: Duration) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) else {
+        return report;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // 不跟随符号链接，只处理普通文件
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_file()) || !is_temp_file(&path) || is_retained(&path) {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age >= max_age);
+        if !stale {
+            continue;
+        }
+        if let Some(bytes) = remove(&path) {
+            report.files += 1;
+            report.bytes += bytes;
+        }
+    }
+
+    if report.files > 0 {
+        tracing::info!("Cleaned up {} old temp files, reclaimed {} bytes", report.files, report.bytes);
+    }
+    report
+}
+
+/// 删除文件。
+///
+/// # Returns
+/// 删除的文件大小，文件不存在或删除失败时为 None
+fn remove(path: &Path) -> Option<u64> {
+    let bytes = std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+    match std::fs::remove_file(path) {
+        Ok(()) => Some(bytes),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!("Failed to delete temp file {:?}: {:?}", path, e);
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在临时目录中创建 5 字节的文件，修改时间设为 `age` 之前。
+    fn create(name: String, age: Duration) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, "12345").unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+        path
+    }
+
+    /// 本程序格式的临时文件。
+    fn temp_file(name: &str, age: Duration) -> PathBuf {
+        create(format!("{}test_{}_{}", PREFIX, std::process::id(), name), age)
+    }
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn recognizes_only_own_temp_files() {
+        let temp = std::env::temp_dir();
+        let cases = [
+            ("fastsync_1.png", true),
+            ("fastsync_1.mp4", true),
+            ("fastsync_1.bin", true),
+            ("fastsync_1.txt", true),
+            ("fastsync_1.exe", false),
+            ("fastsync_1", false),
+            ("other_1.png", false),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(is_temp_file(&temp.join(name)), expected, "{}", name);
+        }
+        assert!(!is_temp_file(&temp.join("nested").join("fastsync_1.png")));
+    }
+
+    #[test]
+    fn cleans_only_stale_files() {
+        let stale = temp_file("stale.png", 2 * HOUR);
+        let fresh = temp_file("fresh.png", Duration::ZERO);
+        let foreign = create(format!("other_{}_stale.png", std::process::id()), 2 * HOUR);
+
+        let report = clean_temp_files(HOUR);
+        assert!(report.files >= 1 && report.bytes >= 5, "{:?}", report);
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(foreign.exists());
+        std::fs::remove_file(&fresh).unwrap();
+        std::fs::remove_file(&foreign).unwrap();
+    }
+
+    #[test]
+    fn retained_files_are_skipped_until_they_expire() {
+        let path = temp_file("retained.png", 2 * HOUR);
+        retain(&path, Duration::from_millis(100));
+        assert!(is_retained(&path));
+        clean_temp_files(HOUR);
+        assert!(path.exists());
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!is_retained(&path));
+        clean_temp_files(HOUR);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn released_files_can_be_cleaned_again() {
+        let path = temp_file("released.png", 2 * HOUR);
+        retain(&path, HOUR);
+        release(&path);
+        assert!(!is_retained(&path));
+        clean_temp_files(HOUR);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn discard_removes_listed_temp_files_only() {
+        let retained = temp_file("discard_retained.png", Duration::ZERO);
+        let plain = temp_file("discard_plain.bin", Duration::ZERO);
+        retain(&retained, HOUR);
+        // 已移出临时目录的文件与不属于本程序的文件不受影响
+        let dir = std::env::temp_dir().join(format!("fastsync-discard-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let moved = dir.join("fastsync_moved.png");
+        std::fs::write(&moved, "x").unwrap();
+        let missing = std::env::temp_dir().join(format!("{}test_{}_missing.png", PREFIX, std::process::id()));
+
+        discard(&[retained.clone(), plain.clone(), moved.clone(), missing]);
+        assert!(!retained.exists());
+        assert!(!is_retained(&retained));
+        assert!(!plain.exists());
+        assert!(moved.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
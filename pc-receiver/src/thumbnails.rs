@@ -0,0 +1,156 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 缩略图模块。
+ * 记录最近收到的图片及其内容哈希，手机可据此拉取一张小尺寸缩略图确认接收端收到的内容未损坏。
+ * 缩略图按需生成并缓存，生成次数按时间窗口限制。
+ */
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, RgbaImage};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::handlers::photo::decode_image;
+
+/// 缩略图最长边的像素数。
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// 缩略图的 JPEG 质量。
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// 最多记录的图片数，超过时丢弃最早的记录。
+const MAX_PHOTOS: usize = 256;
+
+/// 最多缓存的缩略图数。
+const MAX_CACHED: usize = 32;
+
+/// 生成次数的统计窗口与窗口内的上限。
+const GENERATION_WINDOW: Duration = Duration::from_secs(60);
+const MAX_GENERATIONS: usize = 30;
+
+/// 一张已收到的图片。
+struct StoredPhoto {
+    /// 图片文件路径，通知按钮移动文件后随之更新
+    path: Arc<Mutex<PathBuf>>,
+    /// 原始内容的 SHA-256，作为缩略图的 ETag
+    hash: String,
+    /// 上传图片的设备，只有该设备可以读取缩略图
+    device: Option<String>,
+}
+
+/// 查询到的图片。
+pub(crate) struct PhotoEntry {
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+/// 最近收到的图片与缩略图缓存，由所有接口共享。
+#[derive(Default)]
+pub(crate) struct PhotoIndex {
+    photos: Mutex<HashMap<String, StoredPhoto>>,
+    /// 按收到顺序排列的图片标识，用于淘汰最早的记录
+    order: Mutex<VecDeque<String>>,
+    cache: Mutex<VecDeque<(String, Arc<Vec<u8>>)>>,
+    generations: Mutex<VecDeque<Instant>>,
+}
+
+impl PhotoIndex {
+    /// 记录一张收到的图片。
+    ///
+    /// # Arguments
+    /// * `id` - 图片标识，与审计记录中的标识一致
+    /// * `path` - 图片文件路径
+    /// * `hash` - 图片内容的 SHA-256，接收时边写入边计算
+    /// * `device` - 上传图片的设备
+    pub(crate) fn register(&self, id: &str, path: Arc<Mutex<PathBuf>>, hash: String, device: Option<String>) {
+        let mut photos = self.photos.lock().unwrap();
+        let mut order = self.order.lock().unwrap();
+        photos.insert(id.to_string(), StoredPhoto { path, hash, device });
+        order.push_back(id.to_string());
+        while order.len() > MAX_PHOTOS {
+            if let Some(oldest) = order.pop_front() {
+                photos.remove(&oldest);
+            }
+        }
+    }
+
+    /// 查询图片，文件已被清理或移走时删除记录。
+    ///
+    /// # Arguments
+    /// * `id` - 图片标识
+    /// * `device` - 发起请求的设备，与上传设备不一致时视为不存在
+    pub(crate) fn lookup(&self, id: &str, device: Option<&str>) -> Option<PhotoEntry> {
+        let mut photos = self.photos.lock().unwrap();
+        let photo = photos.get(id)?;
+        if photo.device.is_some() && photo.device.as_deref() != device {
+            return None;
+        }
+
+        let path = photo.path.lock().unwrap().clone();
+        if !path.is_file() {
+            tracing::debug!("Photo {} is gone from {:?}", id, path);
+            photos.remove(id);
+            self.cache.lock().unwrap().retain(|(cached, _)| cached != id);
+            return None;
+        }
+        Some(PhotoEntry {
+            path,
+            hash: photo.hash.clone(),
+        })
+    }
+
+    /// 已缓存的缩略图。
+    pub(crate) fn cached(&self, id: &str) -> Option<Arc<Vec<u8>>> {
+        let cache = self.cache.lock().unwrap();
+        cache.iter().find(|(cached, _)| cached == id).map(|(_, thumb)| thumb.clone())
+    }
+
+    /// 缓存生成的缩略图，超过上限时丢弃最早的缓存。
+    pub(crate) fn store(&self, id: &str, thumbnail: Arc<Vec<u8>>) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|(cached, _)| cached != id);
+        cache.push_back((id.to_string(), thumbnail));
+        while cache.len() > MAX_CACHED {
+            cache.pop_front();
+        }
+    }
+
+    /// 申请生成一张缩略图。
+    ///
+    /// # Returns
+    /// 窗口内已达上限时返回建议的重试时间
+    pub(crate) fn acquire_generation(&self, now: Instant) -> Result<(), Duration> {
+        let mut generations = self.generations.lock().unwrap();
+        while generations
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= GENERATION_WINDOW)
+        {
+            generations.pop_front();
+        }
+        if generations.len() >= MAX_GENERATIONS {
+            let oldest = generations.front().copied().unwrap_or(now);
+            return Err(GENERATION_WINDOW.saturating_sub(now.duration_since(oldest)));
+        }
+        generations.push_back(now);
+        Ok(())
+    }
+}
+
+/// 生成最长边不超过 `THUMBNAIL_SIZE` 的 JPEG 缩略图，沿用复制图片时的解码路径。
+///
+/// # Arguments
+/// * `data` - 原始图片内容
+pub fn make_thumbnail(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let (decoded, _) = decode_image(data)?;
+    let image = RgbaImage::from_raw(decoded.width as u32, decoded.height as u32, decoded.rgba)
+        .ok_or_else(|| anyhow::anyhow!("Decoded image has an unexpected size"))?;
+    let thumbnail = DynamicImage::ImageRgba8(image)
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8();
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, THUMBNAIL_QUALITY).encode_image(&thumbnail)?;
+    Ok(jpeg)
+}
@@ -0,0 +1,247 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 处理耗时模块。
+ * 每个收到的内容从请求到达开始计时，依次记录接收完成、解码、预览、入队与通知显示的时间，
+ * 处理结束时输出一行汇总，并按阶段累计直方图供 `/metrics` 与 `/diagnose` 使用。
+ */
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 直方图的桶上限（毫秒）。
+const BUCKETS_MS: [u64; 11] = [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// 处理阶段，按发生顺序排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// 请求体接收完成
+    Received,
+    /// 图片解码完成
+    Decoded,
+    /// 预览文件写入完成
+    Preview,
+    /// 通知进入显示队列
+    Enqueued,
+    /// 通知显示完成
+    Shown,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Received => "received",
+            Stage::Decoded => "decoded",
+            Stage::Preview => "preview",
+            Stage::Enqueued => "enqueued",
+            Stage::Shown => "shown",
+        }
+    }
+}
+
+/// 单个内容的阶段时间，随内容在处理器与通知队列之间传递。
+#[derive(Debug, Clone)]
+pub struct Timings {
+    start: Instant,
+    marks: Vec<(Stage, Duration)>,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl Timings {
+    /// 以当前时间为起点开始计时。
+    pub fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            marks: Vec::new(),
+        }
+    }
+
+    /// 记录到达某个阶段，同一阶段只记录第一次。
+    pub fn mark(&mut self, stage: Stage) {
+        if !self.marks.iter().any(|(marked, _)| *marked == stage) {
+            self.marks.push((stage, self.start.elapsed()));
+        }
+    }
+
+    /// 开始计时的时间，即请求开始处理的时间。
+    pub fn started_at(&self) -> Instant {
+        self.start
+    }
+
+    /// 已记录的阶段及其距起点的时间，按记录顺序排列。
+    pub fn marks(&self) -> &[(Stage, Duration)] {
+        &self.marks
+    }
+
+    /// 各阶段相对上一阶段的耗时。
+    pub fn stage_durations(&self) -> Vec<(Stage, Duration)> {
+        let mut previous = Duration::ZERO;
+        self.marks
+            .iter()
+            .map(|(stage, at)| {
+                let duration = at.saturating_sub(previous);
+                previous = *at;
+                (*stage, duration)
+            })
+            .collect()
+    }
+
+    /// 一行汇总，例如 `photo received=12ms preview=3ms enqueued=0ms shown=240ms total=255ms`。
+    ///
+    /// # Arguments
+    /// * `item_type` - 内容类型
+    pub fn summary(&self, item_type: &str) -> String {
+        let mut line = item_type.to_string();
+        for (stage, duration) in self.stage_durations() {
+            let _ = write!(line, " {}={}ms", stage.as_str(), duration.as_millis());
+        }
+        let total = self.marks.last().map(|(_, at)| *at).unwrap_or_default();
+        let _ = write!(line, " total={}ms", total.as_millis());
+        line
+    }
+}
+
+/// 一个阶段的耗时直方图。
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// 各桶（含 +Inf）的计数，非累积
+    counts: [u64; BUCKETS_MS.len() + 1],
+    sum: Duration,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let ms = duration.as_millis() as u64;
+        let index = BUCKETS_MS.iter().position(|bucket| ms <= *bucket).unwrap_or(BUCKETS_MS.len());
+        self.counts[index] += 1;
+        self.sum += duration;
+    }
+}
+
+/// 最近一个内容的阶段耗时。
+#[derive(Debug, Clone, Serialize)]
+pub struct LastItem {
+    pub item_type: String,
+    /// 各阶段相对上一阶段的耗时（毫秒）
+    pub stages: Vec<(Stage, u64)>,
+    pub total_ms: u64,
+}
+
+/// 所有内容的阶段统计，由所有接口共享。
+#[derive(Default)]
+pub(crate) struct TimingStats {
+    histograms: Mutex<BTreeMap<Stage, Histogram>>,
+    last: Mutex<Option<LastItem>>,
+}
+
+impl TimingStats {
+    /// 内容处理结束，输出汇总并计入统计。
+    pub(crate) fn finish(&self, item_type: &str, timings: &Timings) {
+        tracing::info!("Timings: {}", timings.summary(item_type));
+
+        let stages = timings.stage_durations();
+        {
+            let mut histograms = self.histograms.lock().unwrap();
+            for (stage, duration) in &stages {
+                histograms.entry(*stage).or_default().observe(*duration);
+            }
+        }
+        *self.last.lock().unwrap() = Some(LastItem {
+            item_type: item_type.to_string(),
+            stages: stages.iter().map(|(stage, d)| (*stage, d.as_millis() as u64)).collect(),
+            total_ms: timings.marks().last().map(|(_, at)| at.as_millis() as u64).unwrap_or(0),
+        });
+    }
+
+    /// 最近一个内容的阶段耗时。
+    pub(crate) fn last(&self) -> Option<LastItem> {
+        self.last.lock().unwrap().clone()
+    }
+
+    /// 以 Prometheus 文本格式输出各阶段直方图。
+    pub(crate) fn render_prometheus(&self, out: &mut String) {
+        let histograms = self.histograms.lock().unwrap();
+        out.push_str("# HELP fastsync_stage_duration_seconds Time spent in each receive pipeline stage.\n");
+        out.push_str("# TYPE fastsync_stage_duration_seconds histogram\n");
+        for (stage, histogram) in histograms.iter() {
+            let mut cumulative = 0;
+            for (bucket, count) in BUCKETS_MS.iter().zip(histogram.counts.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "fastsync_stage_duration_seconds_bucket{{stage=\"{}\",le=\"{}\"}} {}",
+                    stage.as_str(),
+                    *bucket as f64 / 1000.0,
+                    cumulative
+                );
+            }
+            cumulative += histogram.counts[BUCKETS_MS.len()];
+            let _ = writeln!(out, "fastsync_stage_duration_seconds_bucket{{stage=\"{}\",le=\"+Inf\"}} {}", stage.as_str(), cumulative);
+            let _ = writeln!(out, "fastsync_stage_duration_seconds_sum{{stage=\"{}\"}} {}", stage.as_str(), histogram.sum.as_secs_f64());
+            let _ = writeln!(out, "fastsync_stage_duration_seconds_count{{stage=\"{}\"}} {}", stage.as_str(), cumulative);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 各阶段间隔固定时间的计时，不依赖真实的耗时。
+    fn timings(marks: &[(Stage, u64)]) -> Timings {
+        let mut timings = Timings::start();
+        timings.marks = marks.iter().map(|(stage, ms)| (*stage, Duration::from_millis(*ms))).collect();
+        timings
+    }
+
+    #[test]
+    fn marks_keep_arrival_order_and_ignore_repeats() {
+        let mut timings = Timings::start();
+        for stage in [Stage::Received, Stage::Preview, Stage::Received, Stage::Enqueued, Stage::Shown, Stage::Shown] {
+            timings.mark(stage);
+        }
+        let stages: Vec<Stage> = timings.marks().iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, [Stage::Received, Stage::Preview, Stage::Enqueued, Stage::Shown]);
+        assert!(timings.marks().windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn durations_are_relative_to_the_previous_stage() {
+        let timings = timings(&[(Stage::Received, 12), (Stage::Preview, 15), (Stage::Enqueued, 15), (Stage::Shown, 255)]);
+        let durations: Vec<(Stage, u64)> = timings
+            .stage_durations()
+            .into_iter()
+            .map(|(stage, duration)| (stage, duration.as_millis() as u64))
+            .collect();
+        assert_eq!(durations, [(Stage::Received, 12), (Stage::Preview, 3), (Stage::Enqueued, 0), (Stage::Shown, 240)]);
+        assert_eq!(timings.summary("photo"), "photo received=12ms preview=3ms enqueued=0ms shown=240ms total=255ms");
+    }
+
+    #[test]
+    fn finished_items_feed_the_histograms() {
+        let stats = TimingStats::default();
+        stats.finish("sms", &timings(&[(Stage::Received, 1), (Stage::Enqueued, 2), (Stage::Shown, 300)]));
+        stats.finish("sms", &timings(&[(Stage::Received, 7), (Stage::Enqueued, 7)]));
+
+        let last = stats.last().unwrap();
+        assert_eq!(last.item_type, "sms");
+        assert_eq!(last.stages, [(Stage::Received, 7), (Stage::Enqueued, 0)]);
+        assert_eq!(last.total_ms, 7);
+
+        let mut out = String::new();
+        stats.render_prometheus(&mut out);
+        assert!(out.contains("fastsync_stage_duration_seconds_count{stage=\"received\"} 2"), "{}", out);
+        assert!(out.contains("fastsync_stage_duration_seconds_count{stage=\"shown\"} 1"), "{}", out);
+        assert!(out.contains("fastsync_stage_duration_seconds_bucket{stage=\"shown\",le=\"0.25\"} 0"), "{}", out);
+        assert!(out.contains("fastsync_stage_duration_seconds_bucket{stage=\"shown\",le=\"0.5\"} 1"), "{}", out);
+    }
+}
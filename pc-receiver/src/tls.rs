@@ -0,0 +1,415 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * TLS 模块。
+ * HTTPS：首次运行时在配置目录生成自签名服务端证书，主端口以此提供 HTTPS，
+ * 证书的 SHA-256 指纹写入 mDNS TXT 记录与配对二维码，手机端据此固定证书。
+ * 双向 TLS：每台电脑首次启用时生成独立的 CA，配对成功后用它给手机签发客户端证书，
+ * 证书 URI SAN 中携带设备标识，mTLS 端口据此识别设备而无需令牌。
+ */
+use anyhow::Context;
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose, SanType,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use crate::auth::AuthenticatedDevice;
+use crate::state::AppState;
+
+/// 客户端证书 URI SAN 前缀，后接设备标识。
+const DEVICE_SAN_PREFIX: &str = "urn:fastsync:device:";
+
+/// 配对时签发给手机的客户端证书。
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientCertificate {
+    /// PEM 格式的客户端证书
+    pub client_cert: String,
+    /// PEM 格式的 PKCS#8 私钥
+    pub client_key: String,
+    /// PEM 格式的 CA 证书，手机端用它校验服务端证书
+    pub ca_cert: String,
+    /// mTLS 监听端口
+    pub port: u16,
+}
+
+/// HTTPS 使用的自签名服务端证书。
+pub(crate) struct ServerCertificate {
+    cert_der: CertificateDer<'static>,
+    key_der: Vec<u8>,
+    fingerprint: String,
+}
+
+impl ServerCertificate {
+    /// 从目录加载证书，不存在时生成并写入 `https.pem` 与 `https.key`。
+    /// 证书长期有效且不随 IP 变化重新生成，手机端固定的指纹因此保持不变。
+    ///
+    /// # Arguments
+    /// * `dir` - 证书存放目录
+    pub(crate) fn load_or_create(dir: &Path) -> anyhow::Result<Self> {
+        let cert_path = dir.join("https.pem");
+        let key_path = dir.join("https.key");
+
+        let (cert_der, key) = if cert_path.exists() && key_path.exists() {
+            let cert_der = pem_to_der(&std::fs::read_to_string(&cert_path)?)?;
+            let key = KeyPair::from_pem(&std::fs::read_to_string(&key_path)?)?;
+            (cert_der, key)
+        } else {
+            let hostname = hostname::get()
+                .map(|h| h.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "FastSync".into());
+
+            let mut params = CertificateParams::new(vec!["localhost".to_string(), hostname.clone()])?;
+            params.distinguished_name = DistinguishedName::new();
+            params.distinguished_name.push(DnType::CommonName, format!("FastSync ({})", hostname));
+            params.subject_alt_names.push(SanType::IpAddress([127, 0, 0, 1].into()));
+            params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+            params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+            params.not_before = rcgen::date_time_ymd(2024, 1, 1);
+            params.not_after = rcgen::date_time_ymd(2099, 12, 31);
+
+            let key = KeyPair::generate()?;
+            let cert = params.self_signed(&key)?;
+
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&cert_path, cert.pem())?;
+            write_private(&key_path, &key.serialize_pem())?;
+            tracing::info!("Generated self-signed HTTPS certificate at {:?}", cert_path);
+            (cert.der().clone(), key)
+        };
+
+        Ok(Self {
+            fingerprint: hex::encode(Sha256::digest(&cert_der)),
+            cert_der,
+            key_der: key.serialize_der(),
+        })
+    }
+
+    /// 证书 DER 的 SHA-256 指纹，64 个小写十六进制字符。
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// 只信任本证书的客户端，用于启动自检的回环请求。
+    pub(crate) fn connector(&self) -> anyhow::Result<TlsConnector> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.cert_der.clone())?;
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    /// 不要求客户端证书的 rustls 配置。
+    fn server_config(&self) -> anyhow::Result<Arc<ServerConfig>> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![self.cert_der.clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(self.key_der.clone())),
+            )?;
+        Ok(Arc::new(config))
+    }
+}
+
+/// 本机 CA，负责签发服务端与客户端证书。
+pub(crate) struct TlsAuthority {
+    ca_params: CertificateParams,
+    ca_key: KeyPair,
+    ca_pem: String,
+    ca_der: CertificateDer<'static>,
+    /// mTLS 实际监听端口，启动后写入
+    port: AtomicU16,
+}
+
+impl TlsAuthority {
+    /// 从目录加载 CA，不存在时生成并写入 `ca.pem` 与 `ca.key`。
+    ///
+    /// # Arguments
+    /// * `dir` - CA 存放目录
+    pub(crate) fn load_or_create(dir: &Path) -> anyhow::Result<Self> {
+        let cert_path = dir.join("ca.pem");
+        let key_path = dir.join("ca.key");
+
+        if cert_path.exists() && key_path.exists() {
+            let ca_pem = std::fs::read_to_string(&cert_path)?;
+            let ca_key = KeyPair::from_pem(&std::fs::read_to_string(&key_path)?)?;
+            let ca_params = CertificateParams::from_ca_cert_pem(&ca_pem)?;
+            let ca_der = pem_to_der(&ca_pem)?;
+            return Ok(Self { ca_params, ca_key, ca_pem, ca_der, port: AtomicU16::new(0) });
+        }
+
+        let hostname = hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "FastSync".into());
+
+        let mut ca_params = CertificateParams::new(Vec::<String>::new())?;
+        ca_params.distinguished_name = DistinguishedName::new();
+        ca_params.distinguished_name.push(DnType::CommonName, format!("FastSync CA ({})", hostname));
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        ca_params.not_before = rcgen::date_time_ymd(2024, 1, 1);
+        ca_params.not_after = rcgen::date_time_ymd(2099, 12, 31);
+
+        let ca_key = KeyPair::generate()?;
+        let ca_cert = ca_params.clone().self_signed(&ca_key)?;
+        let ca_pem = ca_cert.pem();
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(&cert_path, &ca_pem)?;
+        write_private(&key_path, &ca_key.serialize_pem())?;
+        tracing::info!("Generated mTLS CA at {:?}", cert_path);
+
+        Ok(Self { ca_params, ca_key, ca_der: ca_cert.der().clone(), ca_pem, port: AtomicU16::new(0) })
+    }
+
+    /// CA 证书指纹，即 DER 的 SHA-256 前 8 字节，写入配对二维码供手机端核对。
+    pub(crate) fn fingerprint(&self) -> String {
+        hex::encode(&Sha256::digest(&self.ca_der)[..8])
+    }
+
+    /// 记录 mTLS 实际监听端口，之后签发的客户端证书中带上该端口。
+    pub(crate) fn set_port(&self, port: u16) {
+        self.port.store(port, Ordering::Relaxed);
+    }
+
+    /// 为设备签发客户端证书。
+    ///
+    /// # Arguments
+    /// * `device_id` - 设备标识，写入证书 URI SAN
+    /// * `device_name` - 设备名称，写入证书 CN
+    pub(crate) fn issue_client_cert(&self, device_id: &str, device_name: &str) -> anyhow::Result<ClientCertificate> {
+        let mut params = CertificateParams::new(Vec::<String>::new())?;
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, if device_name.is_empty() { device_id } else { device_name });
+        params.subject_alt_names = vec![SanType::URI(format!("{}{}", DEVICE_SAN_PREFIX, device_id).try_into()?)];
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+
+        let key = KeyPair::generate()?;
+        let cert = params.signed_by(&key, &self.issuer()?, &self.ca_key)?;
+
+        Ok(ClientCertificate {
+            client_cert: cert.pem(),
+            client_key: key.serialize_pem(),
+            ca_cert: self.ca_pem.clone(),
+            port: self.port.load(Ordering::Relaxed),
+        })
+    }
+
+    /// 签发服务端证书并构造要求客户端证书的 rustls 配置。
+    fn server_config(&self) -> anyhow::Result<Arc<ServerConfig>> {
+        let mut names = vec!["localhost".to_string()];
+        if let Ok(hostname) = hostname::get() {
+            names.push(hostname.to_string_lossy().to_string());
+        }
+
+        let mut params = CertificateParams::new(names)?;
+        params.distinguished_name = DistinguishedName::new();
+        params.distinguished_name.push(DnType::CommonName, "FastSync");
+        params.subject_alt_names.push(SanType::IpAddress([127, 0, 0, 1].into()));
+        if let Ok(interfaces) = local_ip_address::list_afinet_netifas() {
+            for (_, ip) in interfaces {
+                if !ip.is_loopback() {
+                    params.subject_alt_names.push(SanType::IpAddress(ip));
+                }
+            }
+        }
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+
+        let key = KeyPair::generate()?;
+        let cert = params.signed_by(&key, &self.issuer()?, &self.ca_key)?;
+
+        let mut roots = RootCertStore::empty();
+        roots.add(self.ca_der.clone())?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()?;
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![cert.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )?;
+        Ok(Arc::new(config))
+    }
+
+    /// 用持久化的 CA 参数重建签发者，签名结果只依赖 CA 名称与密钥。
+    fn issuer(&self) -> anyhow::Result<rcgen::Certificate> {
+        Ok(self.ca_params.clone().self_signed(&self.ca_key)?)
+    }
+}
+
+/// 默认 HTTPS 证书存放目录：`<漫游配置目录>/FastSync`，Windows 上即 `%APPDATA%\FastSync`。
+pub(crate) fn default_certificate_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("FastSync"))
+}
+
+/// 默认 CA 存放目录，位于本地数据目录下的 `FastSync/tls`。
+pub(crate) fn default_tls_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("FastSync").join("tls"))
+}
+
+/// 在 mTLS 端口上接受连接，握手时要求由本机 CA 签发的客户端证书，
+/// 握手后按证书中的设备标识确认设备仍处于配对状态。
+///
+/// # Arguments
+/// * `listener` - 已绑定的监听器
+/// * `authority` - 本机 CA
+/// * `app` - 完整路由
+/// * `state` - 应用共享状态
+/// * `shutdown_rx` - 关闭信号
+pub(crate) async fn serve(
+    listener: TcpListener,
+    authority: Arc<TlsAuthority>,
+    app: Router,
+    state: AppState,
+    shutdown_rx: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let acceptor = TlsAcceptor::from(authority.server_config().context("Failed to build mTLS config")?);
+
+    accept_loop(listener, acceptor, "mTLS", shutdown_rx, move |connection, remote_addr| {
+        let device_id = connection
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| device_id_from_cert(cert));
+        let Some(device_id) = device_id.filter(|id| state.devices.get(id).is_some()) else {
+            tracing::warn!("mTLS client {} presented a certificate for an unpaired device", remote_addr);
+            return None;
+        };
+        Some(app.clone().layer(Extension(AuthenticatedDevice(device_id))))
+    })
+    .await
+}
+
+/// 在主端口上以自签名证书提供 HTTPS，鉴权与明文 HTTP 相同。
+///
+/// # Arguments
+/// * `listener` - 已绑定的监听器
+/// * `certificate` - 服务端证书
+/// * `app` - 主端口路由
+/// * `shutdown_rx` - 关闭信号
+pub(crate) async fn serve_https(
+    listener: TcpListener,
+    certificate: Arc<ServerCertificate>,
+    app: Router,
+    shutdown_rx: watch::Receiver<bool>,
+) -> anyhow::Result<()> {
+    let acceptor = TlsAcceptor::from(certificate.server_config().context("Failed to build HTTPS config")?);
+    accept_loop(listener, acceptor, "HTTPS", shutdown_rx, move |_, _| Some(app.clone())).await
+}
+
+/// 接受 TLS 连接直到收到关闭信号，每个连接在独立任务中握手并提供服务。
+/// 收到关闭信号后关闭空闲连接，等待进行中的请求完成后返回，等待时长由调用方限制。
+///
+/// # Arguments
+/// * `label` - 日志中的监听器名称
+/// * `route` - 握手完成后决定该连接使用的路由，返回 None 时关闭连接
+async fn accept_loop<F>(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    label: &'static str,
+    mut shutdown_rx: watch::Receiver<bool>,
+    route: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&rustls::ServerConnection, SocketAddr) -> Option<Router> + Clone + Send + 'static,
+{
+    let graceful = GracefulShutdown::new();
+    loop {
+        let (stream, remote_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("{} accept failed: {:?}", label, e);
+                    continue;
+                }
+            },
+            _ = shutdown_rx.wait_for(|stop| *stop) => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let route = route.clone();
+        let watcher = graceful.watcher();
+        let mut handshake_rx = shutdown_rx.clone();
+        tokio::spawn(async move {
+            // 握手未完成的连接不会被优雅关闭，收到关闭信号时直接放弃
+            let accepted = tokio::select! {
+                accepted = acceptor.accept(stream) => accepted,
+                _ = handshake_rx.wait_for(|stop| *stop) => return,
+            };
+            let stream = match accepted {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::warn!("{} handshake with {} rejected: {}", label, remote_addr, e);
+                    return;
+                }
+            };
+
+            let Some(app) = route(stream.get_ref().1, remote_addr) else {
+                return;
+            };
+            let app = app.layer(Extension(ConnectInfo(remote_addr)));
+            let connection = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), TowerToHyperService::new(app))
+                .into_owned();
+            if let Err(e) = watcher.watch(connection).await {
+                tracing::debug!("{} connection from {} closed: {:?}", label, remote_addr, e);
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+    Ok(())
+}
+
+/// 从客户端证书的 URI SAN 中取出设备标识。
+fn device_id_from_cert(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert).ok()?;
+    let san = cert.subject_alternative_name().ok()??;
+    san.value.general_names.iter().find_map(|name| match name {
+        x509_parser::extensions::GeneralName::URI(uri) => uri.strip_prefix(DEVICE_SAN_PREFIX).map(str::to_string),
+        _ => None,
+    })
+}
+
+/// 解析单个 PEM 证书。
+fn pem_to_der(pem: &str) -> anyhow::Result<CertificateDer<'static>> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())?;
+    Ok(CertificateDer::from(pem.contents))
+}
+
+/// 写入私钥文件，非 Windows 平台上限制为仅本人可读。
+fn write_private(path: &Path, contents: &str) -> anyhow::Result<()> {
+    std::fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
@@ -3,7 +3,7 @@
  * @Date: 2026-02-19
  */
 use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, Submenu},
     MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent,
 };
 use tao::{
@@ -11,16 +11,43 @@ use tao::{
     event_loop::{ControlFlow, EventLoopBuilder},
 };
 use local_ip_address::list_afinet_netifas;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use fastsync::{FastSyncServer, ServerEvent, DRAIN_TIMEOUT};
+
+/// 刷新托盘提示（计划暂停状态）的间隔。
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 紧急通知未处理时托盘图标的闪烁间隔。
+const FLASH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 退出时等待服务停止的最长时间，在请求排空时限之外留出注销 mDNS 与清理临时文件的时间。
+const QUIT_TIMEOUT: Duration = DRAIN_TIMEOUT.saturating_add(Duration::from_secs(5));
+
+/// 设备子菜单中的操作。
+enum DeviceAction {
+    /// 切换该设备的图片自动保存，附带菜单项以读取勾选状态
+    AutoSave(String, CheckMenuItem),
+    /// 让保持连接的手机响铃
+    FindPhone(String),
+    Revoke(String),
+}
 
 #[derive(Debug)]
 enum UserEvent {
     TrayIconEvent(tray_icon::TrayIconEvent),
     MenuEvent(tray_icon::menu::MenuEvent),
+    /// 服务已停止（托盘退出或 Ctrl-C）
+    ServerStopped,
 }
 
 /// 运行系统托盘事件循环。
 /// 该函数会阻塞当前线程，直到应用程序退出。
-pub fn run_event_loop() {
+///
+/// # Arguments
+/// * `server` - 正在运行的接收服务，用于生成配对二维码
+pub fn run_event_loop(server: Arc<FastSyncServer>) {
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
     let proxy = event_loop.create_proxy();
 
@@ -34,41 +61,304 @@ pub fn run_event_loop() {
         let _ = proxy_clone.send_event(UserEvent::MenuEvent(event));
     }));
 
+    let mut server_events = server.subscribe();
+    std::thread::spawn(move || loop {
+        match server_events.blocking_recv() {
+            Ok(ServerEvent::Stopped) | Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                let _ = proxy.send_event(UserEvent::ServerStopped);
+                return;
+            }
+            _ => {}
+        }
+    });
+
     let tray_menu = Menu::new();
+    let pair_i = MenuItem::new("配对二维码", true, None);
+    let pin_i = MenuItem::new("配对 PIN", true, None);
+    let guest_i = MenuItem::new("允许浏览器上传 (10 分钟)", true, None);
+    let connection_i = MenuItem::new("连接信息", true, None);
+    let devices_menu = Submenu::new("已配对设备", true);
+    let token_menu = Submenu::new("访问令牌", true);
+    let token_show_i = MenuItem::new("查看", true, None);
+    let token_regenerate_i = MenuItem::new("重新生成", true, None);
+    token_menu.append(&token_show_i).unwrap();
+    token_menu.append(&token_regenerate_i).unwrap();
+    let dnd_menu = Submenu::new("免打扰", true);
+    let dnd_15_i = MenuItem::new("15 分钟", true, None);
+    let dnd_60_i = MenuItem::new("1 小时", true, None);
+    let dnd_manual_i = MenuItem::new("直到手动关闭", true, None);
+    let dnd_off_i = MenuItem::new("关闭免打扰", server.do_not_disturb_active(), None);
+    dnd_menu.append(&dnd_15_i).unwrap();
+    dnd_menu.append(&dnd_60_i).unwrap();
+    dnd_menu.append(&dnd_manual_i).unwrap();
+    dnd_menu.append(&dnd_off_i).unwrap();
+    let auto_save_i = CheckMenuItem::new("自动保存图片", true, server.auto_save_enabled(), None);
+    let auto_copy_i = CheckMenuItem::new("自动复制验证码", true, server.auto_copy_codes_enabled(), None);
+    let auto_apply_i = CheckMenuItem::new("自动同步剪贴板", true, server.auto_apply_clipboard_enabled(), None);
+    let clipboard_history_i = MenuItem::new("剪贴板历史", true, None);
+    let audit_i = MenuItem::new("操作记录", true, None);
+    let reload_config_i = MenuItem::new("重新载入配置", true, None);
     let quit_i = MenuItem::new("退出", true, None);
+    tray_menu.append(&pair_i).unwrap();
+    tray_menu.append(&pin_i).unwrap();
+    tray_menu.append(&guest_i).unwrap();
+    tray_menu.append(&connection_i).unwrap();
+    tray_menu.append(&devices_menu).unwrap();
+    tray_menu.append(&token_menu).unwrap();
+    tray_menu.append(&dnd_menu).unwrap();
+    tray_menu.append(&auto_save_i).unwrap();
+    tray_menu.append(&auto_copy_i).unwrap();
+    tray_menu.append(&auto_apply_i).unwrap();
+    tray_menu.append(&clipboard_history_i).unwrap();
+    tray_menu.append(&audit_i).unwrap();
+    tray_menu.append(&reload_config_i).unwrap();
     tray_menu.append(&quit_i).unwrap();
 
+    let mut device_items = refresh_devices_menu(&devices_menu, &server);
+
     let icon = load_icon(include_bytes!("../icon.ico")).expect("Failed to load icon data");
+    // 闪烁时与正常图标交替显示的透明图标
+    let blank_icon = tray_icon::Icon::from_rgba(vec![0; 16 * 16 * 4], 16, 16).expect("Failed to create blank icon");
+    let mut icon_hidden = false;
 
+    let mut tooltip = status_tooltip(&server);
     let mut tray_icon = Some(
         TrayIconBuilder::new()
             .with_menu(Box::new(tray_menu))
-            .with_tooltip("FastSync Server")
-            .with_icon(icon)
+            .with_tooltip(&tooltip)
+            .with_icon(icon.clone())
             .build()
             .unwrap(),
     );
 
-    let current_ip = get_best_local_ip().unwrap_or_else(|| "Unknown".into());
+    let lan_ip = get_best_local_ip().unwrap_or_else(|| "Unknown".into());
+    // 点击“退出”后等待服务停止的截止时间
+    let mut quit_deadline: Option<Instant> = None;
 
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+        *control_flow = ControlFlow::WaitUntil(Instant::now() + STATUS_REFRESH_INTERVAL);
+
+        // 计划暂停与免打扰按时间自动切换，定期刷新提示文字
+        dnd_off_i.set_enabled(server.do_not_disturb_active());
+        // 配置文件重新载入后开关可能已改变
+        auto_copy_i.set_checked(server.auto_copy_codes_enabled());
+        auto_apply_i.set_checked(server.auto_apply_clipboard_enabled());
+        let status = status_tooltip(&server);
+        if status != tooltip {
+            if let Some(icon) = &tray_icon {
+                let _ = icon.set_tooltip(Some(&status));
+            }
+            tooltip = status;
+        }
+
+        // 有未处理的紧急通知时闪烁，处理或过期后恢复正常图标
+        if server.attention_pending() {
+            *control_flow = ControlFlow::WaitUntil(Instant::now() + FLASH_INTERVAL);
+            if let Some(tray) = &tray_icon {
+                icon_hidden = !icon_hidden;
+                let next = if icon_hidden { blank_icon.clone() } else { icon.clone() };
+                let _ = tray.set_icon(Some(next));
+            }
+        } else if icon_hidden {
+            if let Some(tray) = &tray_icon {
+                let _ = tray.set_icon(Some(icon.clone()));
+            }
+            icon_hidden = false;
+        }
+
+        if let Some(deadline) = quit_deadline {
+            if Instant::now() >= deadline {
+                tracing::warn!("Server did not stop within {:?}, exiting anyway", QUIT_TIMEOUT);
+                *control_flow = ControlFlow::Exit;
+                return;
+            }
+            *control_flow = ControlFlow::WaitUntil(deadline);
+        }
 
         match event {
+            Event::UserEvent(UserEvent::ServerStopped) => {
+                tray_icon.take();
+                *control_flow = ControlFlow::Exit;
+            }
             Event::UserEvent(UserEvent::MenuEvent(event)) => {
                 if event.id == quit_i.id() {
-                    tray_icon.take(); 
-                    *control_flow = ControlFlow::Exit;
+                    // 先移除托盘图标，服务停止后由 ServerStopped 结束事件循环；服务未启动时直接退出
+                    tray_icon.take();
+                    if server.local_addr().is_none() {
+                        *control_flow = ControlFlow::Exit;
+                    } else if quit_deadline.is_none() {
+                        server.shutdown();
+                        let deadline = Instant::now() + QUIT_TIMEOUT;
+                        quit_deadline = Some(deadline);
+                        *control_flow = ControlFlow::WaitUntil(deadline);
+                    }
+                } else if event.id == pair_i.id() {
+                    let uri = server.pairing_uri(server.base_url(&access_host(&server, &lan_ip)));
+                    if let Err(e) = show_qr(&uri.to_string(), "fastsync_pair_qr.png") {
+                        tracing::error!("Failed to show pairing QR code: {:?}", e);
+                    }
+                } else if event.id == pin_i.id() {
+                    let msg = format!("配对 PIN: {}\n请在 5 分钟内于手机端输入", server.pairing_pin());
+                    std::thread::spawn(move || {
+                        rfd::MessageDialog::new()
+                            .set_title("FastSync 配对")
+                            .set_description(&msg)
+                            .show();
+                    });
+                } else if event.id == guest_i.id() {
+                    let access = server.allow_browser_upload();
+                    let url = format!("{}/#{}", server.base_url(&access_host(&server, &lan_ip)), access.token);
+                    if let Err(e) = show_qr(&url, "fastsync_guest_qr.png") {
+                        tracing::error!("Failed to show guest QR code: {:?}", e);
+                    }
+                    let msg = format!(
+                        "扫描二维码或在浏览器中打开:\n{}\n\n校验表情: {}\n请让访客确认页面上的表情一致，{} 分钟内有效",
+                        url,
+                        access.emoji,
+                        access.expires_in.as_secs() / 60
+                    );
+                    std::thread::spawn(move || {
+                        rfd::MessageDialog::new()
+                            .set_title("FastSync 浏览器上传")
+                            .set_description(&msg)
+                            .show();
+                    });
+                } else if event.id == connection_i.id() {
+                    let msg = connection_info(&server, &access_host(&server, &lan_ip));
+                    std::thread::spawn(move || {
+                        rfd::MessageDialog::new()
+                            .set_title("FastSync 连接信息")
+                            .set_description(&msg)
+                            .show();
+                    });
+                } else if event.id == token_show_i.id() {
+                    let msg = match server.access_token() {
+                        Some(token) => format!("访问令牌:\n{}\n\n配对时会自动下发，也可以在手机端手动填写", token),
+                        None => "尚未设置访问令牌，只接受已配对设备的令牌".to_string(),
+                    };
+                    std::thread::spawn(move || {
+                        rfd::MessageDialog::new()
+                            .set_title("FastSync 访问令牌")
+                            .set_description(&msg)
+                            .show();
+                    });
+                } else if event.id == token_regenerate_i.id() {
+                    let server = server.clone();
+                    std::thread::spawn(move || {
+                        let confirmed = rfd::MessageDialog::new()
+                            .set_title("FastSync 访问令牌")
+                            .set_description("重新生成后，使用旧令牌的手机需要重新配对或填写新令牌。是否继续？")
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                        if confirmed != rfd::MessageDialogResult::Yes {
+                            return;
+                        }
+                        let msg = match server.regenerate_access_token() {
+                            Ok(token) => format!("新的访问令牌:\n{}", token),
+                            Err(e) => {
+                                tracing::error!("Failed to regenerate access token: {:?}", e);
+                                format!("重新生成失败: {:#}", e)
+                            }
+                        };
+                        rfd::MessageDialog::new()
+                            .set_title("FastSync 访问令牌")
+                            .set_description(&msg)
+                            .show();
+                    });
+                } else if event.id == dnd_15_i.id() {
+                    server.set_do_not_disturb_for(Some(std::time::Duration::from_secs(15 * 60)));
+                } else if event.id == dnd_60_i.id() {
+                    server.set_do_not_disturb_for(Some(std::time::Duration::from_secs(60 * 60)));
+                } else if event.id == dnd_manual_i.id() {
+                    server.set_do_not_disturb_for(None);
+                } else if event.id == dnd_off_i.id() {
+                    server.end_do_not_disturb();
+                } else if event.id == auto_save_i.id() {
+                    // 菜单项点击后已自行切换勾选状态
+                    server.set_auto_save(auto_save_i.is_checked());
+                    auto_save_i.set_checked(server.auto_save_enabled());
+                } else if event.id == auto_copy_i.id() {
+                    server.set_auto_copy_codes(auto_copy_i.is_checked());
+                } else if event.id == auto_apply_i.id() {
+                    server.set_auto_apply_clipboard(auto_apply_i.is_checked());
+                } else if event.id == clipboard_history_i.id() {
+                    match server.clipboard_history_url() {
+                        Some(url) => {
+                            if let Err(e) = open_with_default_app(&url) {
+                                tracing::error!("Failed to open clipboard history: {:?}", e);
+                            }
+                        }
+                        None => tracing::info!("Clipboard history is not available on this port"),
+                    }
+                } else if event.id == audit_i.id() {
+                    // 端口不提供操作记录时退回 JSON Lines 文件
+                    let target = server.audit_url().map(std::ffi::OsString::from).or_else(|| {
+                        server
+                            .audit_log_path()
+                            .filter(|path| path.exists())
+                            .map(std::path::PathBuf::into_os_string)
+                    });
+                    match target {
+                        Some(target) => {
+                            if let Err(e) = open_with_default_app(&target) {
+                                tracing::error!("Failed to open audit log: {:?}", e);
+                            }
+                        }
+                        None => tracing::info!("No audit records yet"),
+                    }
+                } else if event.id == reload_config_i.id() {
+                    server.reload_config();
+                } else if let Some(action) = device_items.get(&event.id) {
+                    match action {
+                        DeviceAction::AutoSave(device_id, item) => {
+                            server.set_device_auto_save(device_id, Some(item.is_checked()));
+                        }
+                        DeviceAction::FindPhone(device_id) => {
+                            server.find_phone(device_id);
+                        }
+                        DeviceAction::Revoke(device_id) => {
+                            server.revoke_device(device_id);
+                        }
+                    }
+                    device_items = refresh_devices_menu(&devices_menu, &server);
                 }
             }
             Event::UserEvent(UserEvent::TrayIconEvent(event)) => {
+                // 鼠标移入托盘图标时刷新设备列表，保证菜单弹出时是最新状态
+                if let TrayIconEvent::Enter { .. } = event {
+                    device_items = refresh_devices_menu(&devices_menu, &server);
+                }
                 match event {
                     TrayIconEvent::Click {
                         button: MouseButton::Left,
                         button_state: MouseButtonState::Up,
                         ..
                     } => {
-                        let msg = format!("FastSync 运行中 - IP: {}", current_ip);
+                        let mut msg = format!("FastSync 运行中 - IP: {}", access_host(&server, &lan_ip));
+                        if let Some(until) = server.scheduled_pause() {
+                            msg.push_str(&format!("\n已按计划暂停接收，{} 恢复", until.format("%m-%d %H:%M")));
+                        } else if let Some(next) = server.next_scheduled_pause() {
+                            msg.push_str(&format!("\n下次计划暂停: {}", next.format("%m-%d %H:%M")));
+                        }
+                        if server.auto_save_enabled() {
+                            msg.push_str(&format!("\n图片自动保存到: {}", server.auto_save_dir().display()));
+                        }
+                        if let Some(warning) = server.storage_warning() {
+                            msg.push_str(&format!("\n\n⚠ 保存目录不可用:\n{}", warning));
+                        }
+                        if let Some(report) = server.startup_report() {
+                            msg.push_str(&format!("\n\n启动自检:\n{}", report.describe()));
+                        }
+                        let attempts = server.pairing_attempts();
+                        if !attempts.is_empty() {
+                            msg.push_str("\n\n最近配对尝试:");
+                            for attempt in attempts.iter().take(10) {
+                                let time = chrono::DateTime::from_timestamp_millis(attempt.timestamp)
+                                    .map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M:%S").to_string())
+                                    .unwrap_or_default();
+                                msg.push_str(&format!("\n{}  {}  {}", time, attempt.ip, attempt.outcome));
+                            }
+                        }
 
                         std::thread::spawn(move || {
                             rfd::MessageDialog::new()
@@ -87,6 +377,59 @@ pub fn run_event_loop() {
 
 use anyhow::Context;
 
+/// 托盘提示文字，计划暂停期间显示恢复时间，免打扰期间显示结束时间，保存目录不可用或启动自检失败时显示警告。
+fn status_tooltip(server: &FastSyncServer) -> String {
+    let mut tooltip = match server.scheduled_pause() {
+        Some(until) => format!("FastSync Server - 已按计划暂停，{} 恢复", until.format("%H:%M")),
+        None => "FastSync Server".to_string(),
+    };
+    match server.do_not_disturb_until() {
+        Some(until) => tooltip.push_str(&format!("\n免打扰中，{} 结束", until.format("%H:%M"))),
+        None if server.do_not_disturb_active() => tooltip.push_str("\n免打扰中"),
+        None => {}
+    }
+    if let Some(addr) = server.local_addr() {
+        match server.configured_port() {
+            port if port != 0 && port != addr.port() => {
+                tooltip.push_str(&format!("\n端口 {} 被占用，已改用 {}", port, addr.port()))
+            }
+            _ => tooltip.push_str(&format!("\n端口 {}", addr.port())),
+        }
+    }
+    if server.storage_warning().is_some() {
+        tooltip.push_str("\n⚠ 保存目录不可用");
+    }
+    if server.startup_report().is_some_and(|report| !report.is_healthy()) {
+        tooltip.push_str("\n⚠ 启动自检异常");
+    }
+    tooltip
+}
+
+/// “连接信息”对话框的内容：访问地址、HTTPS 或明文 HTTP、证书指纹与兼容用的明文端口。
+///
+/// # Arguments
+/// * `ip` - 本机局域网 IP
+fn connection_info(server: &FastSyncServer, ip: &str) -> String {
+    let mut info = format!("地址: {}\n", server.base_url(ip));
+    match server.https_fingerprint() {
+        Some(fingerprint) => {
+            // 每两个字符一组，分两行显示
+            let pairs: Vec<&str> = (0..fingerprint.len())
+                .step_by(2)
+                .map(|i| &fingerprint[i..(i + 2).min(fingerprint.len())])
+                .collect();
+            let (first, second) = pairs.split_at(pairs.len() / 2);
+            info.push_str("模式: HTTPS（自签名证书）\n");
+            info.push_str(&format!("证书指纹 (SHA-256):\n{}\n{}\n", first.join(":").to_uppercase(), second.join(":").to_uppercase()));
+        }
+        None => info.push_str("模式: HTTP（未加密）\n"),
+    }
+    if let Some(addr) = server.http_addr() {
+        info.push_str(&format!("\n兼容旧版手机端的明文 HTTP 端口: {}（内容不加密）", addr.port()));
+    }
+    info
+}
+
 /// 加载图标数据。
 ///
 /// # Arguments
@@ -104,6 +447,101 @@ fn load_icon(bytes: &[u8]) -> anyhow::Result<tray_icon::Icon> {
         .context("Failed to create tray icon from RGBA data")
 }
 
+/// 用当前已配对的设备重建“已配对设备”子菜单，每台设备显示在线状态或最近访问时间，
+/// 可单独设置自动保存、让在线的手机响铃或解除配对。
+///
+/// # Returns
+/// 菜单项标识到设备操作的映射
+fn refresh_devices_menu(menu: &Submenu, server: &FastSyncServer) -> HashMap<MenuId, DeviceAction> {
+    while menu.remove_at(0).is_some() {}
+
+    let devices = server.devices();
+    if devices.is_empty() {
+        let _ = menu.append(&MenuItem::new("暂无已配对设备", false, None));
+        return HashMap::new();
+    }
+
+    let mut items = HashMap::new();
+    for device in devices {
+        let connected = server.is_device_connected(&device.id);
+        let last_seen = device
+            .last_seen
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|t| format!("最近 {}", t.with_timezone(&chrono::Local).format("%m-%d %H:%M")))
+            .unwrap_or_else(|| "尚未连接".to_string());
+        let status = if connected { "在线".to_string() } else { last_seen };
+        let submenu = Submenu::new(format!("{}（{}）", device.name, status), true);
+
+        let auto_save = device.settings.auto_save.unwrap_or_else(|| server.auto_save_enabled());
+        let auto_save_i = CheckMenuItem::new("自动保存图片", true, auto_save, None);
+        let find_i = MenuItem::new("查找手机", connected, None);
+        let revoke_i = MenuItem::new("解除配对", true, None);
+        let _ = submenu.append(&auto_save_i);
+        let _ = submenu.append(&find_i);
+        let _ = submenu.append(&revoke_i);
+        let _ = menu.append(&submenu);
+
+        items.insert(auto_save_i.id().clone(), DeviceAction::AutoSave(device.id.clone(), auto_save_i));
+        items.insert(find_i.id().clone(), DeviceAction::FindPhone(device.id.clone()));
+        items.insert(revoke_i.id().clone(), DeviceAction::Revoke(device.id));
+    }
+    items
+}
+
+/// 将载荷渲染为二维码图片，并用系统默认的图片查看器打开。
+///
+/// # Arguments
+/// * `payload` - 二维码载荷
+/// * `file_name` - 临时图片文件名，需以 `fastsync_` 开头
+fn show_qr(payload: &str, file_name: &str) -> anyhow::Result<()> {
+    const SCALE: u32 = 8;
+    const QUIET_ZONE: u32 = 4;
+
+    let code = qrcode::QrCode::new(payload.as_bytes())?;
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let size = (width + QUIET_ZONE * 2) * SCALE;
+
+    let image = image::GrayImage::from_fn(size, size, |x, y| {
+        let (mx, my) = (x / SCALE, y / SCALE);
+        let dark = mx >= QUIET_ZONE
+            && my >= QUIET_ZONE
+            && mx < width + QUIET_ZONE
+            && my < width + QUIET_ZONE
+            && colors[((my - QUIET_ZONE) * width + (mx - QUIET_ZONE)) as usize] == qrcode::Color::Dark;
+        image::Luma([if dark { 0 } else { 255 }])
+    });
+
+    // 以 fastsync_ 开头，超过保留时长后由 clean_temp_files 清理
+    let path = std::env::temp_dir().join(file_name);
+    image.save(&path)?;
+
+    open_with_default_app(&path)?;
+    tracing::info!("QR code written to {:?}", path);
+    Ok(())
+}
+
+/// 用系统默认程序打开文件或网址。
+fn open_with_default_app(target: impl AsRef<std::ffi::OsStr>) -> anyhow::Result<()> {
+    #[cfg(windows)]
+    let opener = "explorer";
+    #[cfg(target_os = "macos")]
+    let opener = "open";
+    #[cfg(not(any(windows, target_os = "macos")))]
+    let opener = "xdg-open";
+
+    std::process::Command::new(opener).arg(target).spawn()?;
+    Ok(())
+}
+
+/// 手机访问本机使用的主机：只监听特定网卡（例如 Tailscale）时为该网卡的地址，否则为局域网 IP。
+///
+/// # Arguments
+/// * `lan_ip` - 启动时选出的局域网 IP
+fn access_host(server: &FastSyncServer, lan_ip: &str) -> String {
+    server.listen_host().unwrap_or_else(|| lan_ip.to_string())
+}
+
 /// 获取局域网 IP 地址。
 /// 优先 192.168.x.x，其次 10.x.x.x 或 172.x.x.x，
 /// 并排除常见的虚拟网卡名称。
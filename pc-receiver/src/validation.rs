@@ -0,0 +1,163 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 载荷校验模块。
+ * JSON 接口反序列化失败时返回结构化的错误，指明出错的字段与期望的类型，
+ * 手机端无需猜测是哪个字段导致请求被拒绝。
+ */
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::handlers::response::UploadResponse;
+
+/// 按载荷结构校验的 JSON 请求体，校验失败时返回 `PayloadRejection`。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidJson<T>(pub T);
+
+/// 字段级的校验错误。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// 出错字段的路径，例如 `timestamp`、`items[0].name`
+    pub field: String,
+    /// missing_field / invalid_type / invalid_value / invalid_length / unknown_variant / unknown_field / invalid
+    pub reason: &'static str,
+    /// 期望的类型或取值
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    /// 原始错误信息
+    pub message: String,
+}
+
+impl FieldError {
+    /// 由反序列化错误的路径与信息生成字段错误。
+    ///
+    /// # Arguments
+    /// * `path` - 出错位置的路径，根位置为 `.`
+    /// * `message` - 不含行列号的错误信息
+    fn new(path: &str, message: &str) -> Self {
+        let path = if path == "." { "" } else { path };
+        let reason = [
+            ("missing field", "missing_field"),
+            ("invalid type", "invalid_type"),
+            ("invalid value", "invalid_value"),
+            ("invalid length", "invalid_length"),
+            ("unknown variant", "unknown_variant"),
+            ("unknown field", "unknown_field"),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| message.starts_with(prefix))
+        .map(|(_, reason)| reason)
+        .unwrap_or("invalid");
+
+        // 缺少字段时路径指向所在的对象，字段名只出现在信息中
+        let field = match (reason, backticked(message)) {
+            ("missing_field", Some(name)) if path.is_empty() => name.to_string(),
+            ("missing_field", Some(name)) => format!("{}.{}", path, name),
+            _ => path.to_string(),
+        };
+        let expected = message
+            .split_once(", expected ")
+            .map(|(_, expected)| expected.to_string());
+
+        Self {
+            field,
+            reason,
+            expected,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// 信息中第一个用反引号括起的名称。
+fn backticked(message: &str) -> Option<&str> {
+    let start = message.find('`')? + 1;
+    let end = start + message[start..].find('`')?;
+    Some(&message[start..end])
+}
+
+/// JSON 请求体被拒绝的原因。
+#[derive(Debug)]
+pub enum PayloadRejection {
+    /// 未声明为 JSON
+    UnsupportedMediaType,
+    /// 读取请求体失败，例如超过大小限制
+    Body { status: StatusCode, message: String },
+    /// 不是合法的 JSON
+    Syntax { line: usize, column: usize, message: String },
+    /// JSON 合法但不符合载荷结构
+    Invalid(FieldError),
+}
+
+/// 与上传接口共用同一套错误响应，映射见 `UploadResponse` 的 `From<PayloadRejection>`。
+impl IntoResponse for PayloadRejection {
+    fn into_response(self) -> Response {
+        UploadResponse::from(self).into_response()
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = PayloadRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(request.headers()) {
+            return Err(PayloadRejection::UnsupportedMediaType);
+        }
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(|rejection| PayloadRejection::Body {
+                status: rejection.status(),
+                message: rejection.body_text(),
+            })?;
+        parse(&bytes).map(ValidJson)
+    }
+}
+
+/// 按载荷结构解析 JSON，失败时给出出错字段的路径。
+///
+/// # Arguments
+/// * `bytes` - 请求体
+pub fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, PayloadRejection> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        rejection(&path, e.into_inner())
+    })?;
+    deserializer.end().map_err(|e| rejection(".", e))?;
+    Ok(value)
+}
+
+fn rejection(path: &str, error: serde_json::Error) -> PayloadRejection {
+    // serde_json 的信息末尾带有行列号，单独返回
+    let message = error.to_string();
+    let position = format!(" at line {} column {}", error.line(), error.column());
+    let message = message.strip_suffix(&position).unwrap_or(&message);
+    match error.classify() {
+        serde_json::error::Category::Data => PayloadRejection::Invalid(FieldError::new(path, message)),
+        _ => PayloadRejection::Syntax {
+            line: error.line(),
+            column: error.column(),
+            message: message.to_string(),
+        },
+    }
+}
+
+/// 请求是否声明为 JSON（`application/json` 或 `application/*+json`）。
+fn is_json(headers: &HeaderMap) -> bool {
+    let Some(content_type) = headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
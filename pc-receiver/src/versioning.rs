@@ -0,0 +1,96 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 接口版本模块。
+ * 接口挂在 `/v1` 之下，每个版本是一个独立嵌套的路由，以后的 `/v2` 可以使用不同的请求格式并与之共存。
+ * 旧版手机端使用的无前缀路径保留为 `/v1` 的别名，每次使用时记录警告并在响应中标记为已弃用。
+ * 所有响应带有 `X-FastSync-Protocol` 响应头；访问不存在的版本时返回 404 与支持的版本列表。
+ */
+use axum::{
+    extract::{OriginalUri, Request},
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use crate::PROTOCOL_VERSION;
+
+/// 当前版本的路径前缀。
+pub const CURRENT_VERSION: &str = "v1";
+
+/// 支持的版本，`/info` 与版本不存在时的错误中返回。
+pub const SUPPORTED_VERSIONS: &[&str] = &[CURRENT_VERSION];
+
+/// 携带协议版本的响应头。
+pub const PROTOCOL_HEADER: &str = "x-fastsync-protocol";
+
+/// 手机端在选择版本前访问的发现接口，无前缀路径不视为已弃用。
+const DISCOVERY_PATHS: &[&str] = &["/health", "/info", "/ping"];
+
+/// 路径的第一段是否为版本前缀，例如 `v1`、`v2`。
+fn version_segment(path: &str) -> Option<&str> {
+    let segment = path.trim_start_matches('/').split('/').next()?;
+    let digits = segment.strip_prefix('v')?;
+    (!digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())).then_some(segment)
+}
+
+/// 去掉当前版本前缀后的路径，用于按接口分类。
+///
+/// # Arguments
+/// * `path` - 请求路径
+pub fn unversioned(path: &str) -> &str {
+    path.strip_prefix('/')
+        .and_then(|rest| rest.strip_prefix(CURRENT_VERSION))
+        .filter(|rest| rest.starts_with('/'))
+        .unwrap_or(path)
+}
+
+/// 为所有响应加上协议版本响应头。
+pub(crate) async fn protocol_header(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(HeaderName::from_static(PROTOCOL_HEADER), HeaderValue::from(PROTOCOL_VERSION));
+    response
+}
+
+/// 无前缀的旧路径：记录警告，并通过 `Deprecation` 与 `Link` 响应头告知新的路径。
+pub(crate) async fn deprecated_alias(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if DISCOVERY_PATHS.contains(&path.as_str()) {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        "Deprecated path {} {} used, clients should switch to /{}{}",
+        request.method(),
+        path,
+        CURRENT_VERSION,
+        path
+    );
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!("</{}{}>; rel=\"successor-version\"", CURRENT_VERSION, path)) {
+        response.headers_mut().insert(HeaderName::from_static("link"), link);
+    }
+    response
+}
+
+/// 未匹配任何路由的请求。路径以不支持的版本开头时返回 JSON 错误与支持的版本，其余保持空的 404。
+pub(crate) async fn unknown_version(OriginalUri(uri): OriginalUri) -> Response {
+    match version_segment(uri.path()) {
+        Some(version) if !SUPPORTED_VERSIONS.contains(&version) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "unsupported_version",
+                "version": version,
+                "supported": SUPPORTED_VERSIONS,
+            })),
+        )
+            .into_response(),
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 视频格式识别模块。
+ * 手机录屏等短视频与图片走同一条上传通道，按文件头识别 MP4 / WebM / MKV，不做任何解码，
+ * 通知中只提供保存与打开。启用 `video-thumbnails` 特性时通过系统的缩略图提供程序取一帧作为通知大图，
+ * 否则通知只有文字与应用图标。
+ */
+use image::DynamicImage;
+use serde::Serialize;
+use std::path::Path;
+
+/// 当前构建能否生成视频缩略图。
+pub const THUMBNAILS: bool = cfg!(all(windows, feature = "video-thumbnails"));
+
+/// 支持接收的视频格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoFormat {
+    Mp4,
+    WebM,
+    /// MKV
+    Matroska,
+}
+
+impl VideoFormat {
+    /// 所有支持的格式。
+    pub const ALL: [VideoFormat; 3] = [VideoFormat::Mp4, VideoFormat::WebM, VideoFormat::Matroska];
+
+    /// 文件扩展名（不含点）。
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::WebM => "webm",
+            VideoFormat::Matroska => "mkv",
+        }
+    }
+
+    /// MIME 类型。
+    pub fn mime(&self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "video/mp4",
+            VideoFormat::WebM => "video/webm",
+            VideoFormat::Matroska => "video/x-matroska",
+        }
+    }
+
+    /// 由扩展名反查格式，忽略大小写，`m4v` 视为 MP4。
+    pub fn from_extension(extension: &str) -> Option<VideoFormat> {
+        let extension = extension.to_ascii_lowercase();
+        match extension.as_str() {
+            "m4v" => Some(VideoFormat::Mp4),
+            _ => Self::ALL.into_iter().find(|format| format.extension() == extension),
+        }
+    }
+
+    /// 由 MIME 类型反查格式，忽略大小写与参数。
+    pub fn from_mime(mime: &str) -> Option<VideoFormat> {
+        let mime = mime.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "video/matroska" => Some(VideoFormat::Matroska),
+            _ => Self::ALL.into_iter().find(|format| format.mime() == mime),
+        }
+    }
+
+    /// 由文件路径的扩展名反查格式。
+    pub fn from_path(path: &Path) -> Option<VideoFormat> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+    }
+}
+
+/// ISO 媒体容器中表示 MP4 视频的主品牌。
+const MP4_BRANDS: [&[u8; 4]; 10] = [
+    b"isom", b"iso2", b"iso4", b"iso5", b"iso6", b"mp41", b"mp42", b"avc1", b"M4V ", b"dash",
+];
+
+/// EBML 文件头的魔数，WebM 与 MKV 共用。
+const EBML_MAGIC: [u8; 4] = [0x1A, 0x45, 0xDF, 0xA3];
+
+/// 根据文件头识别视频格式。
+///
+/// # Arguments
+/// * `header` - 文件开头的字节，区分 WebM 与 MKV 需要读到 EBML 头中的 DocType
+///
+/// # Returns
+/// 识别出的格式，无法识别时为 None
+pub fn detect_video_format(header: &[u8]) -> Option<VideoFormat> {
+    if header.len() >= 12 && &header[4..8] == b"ftyp" && MP4_BRANDS.iter().any(|brand| &header[8..12] == *brand) {
+        return Some(VideoFormat::Mp4);
+    }
+    if header.starts_with(&EBML_MAGIC) {
+        let webm = header.windows(4).any(|window| window == b"webm");
+        return Some(if webm { VideoFormat::WebM } else { VideoFormat::Matroska });
+    }
+    None
+}
+
+/// 通过系统缩略图提供程序取视频的一帧。
+///
+/// # Arguments
+/// * `path` - 视频文件路径，扩展名需与格式一致
+/// * `size` - 缩略图最长边的像素数
+///
+/// # Returns
+/// 缩略图；系统只能给出文件图标时返回错误
+#[cfg(all(windows, feature = "video-thumbnails"))]
+pub fn thumbnail(path: &Path, size: u32) -> anyhow::Result<DynamicImage> {
+    use windows::core::HSTRING;
+    use windows::Storage::FileProperties::{ThumbnailMode, ThumbnailType};
+    use windows::Storage::StorageFile;
+    use windows::Storage::Streams::DataReader;
+
+    let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(path.as_os_str()))?.get()?;
+    let thumbnail = file
+        .GetThumbnailAsyncOverloadDefaultOptions(ThumbnailMode::VideosView, size)?
+        .get()?;
+    if thumbnail.Type()? == ThumbnailType::Icon {
+        anyhow::bail!("No thumbnail provider for {:?}", path);
+    }
+
+    let length = u32::try_from(thumbnail.Size()?)?;
+    let reader = DataReader::CreateDataReader(&thumbnail.GetInputStreamAt(0)?)?;
+    reader.LoadAsync(length)?.get()?;
+    let mut data = vec![0u8; length as usize];
+    reader.ReadBytes(&mut data)?;
+
+    let image = image::load_from_memory(&data)?;
+    tracing::debug!("Video thumbnail for {:?}: {}x{}", path, image.width(), image.height());
+    Ok(image)
+}
+
+/// 未启用 `video-thumbnails` 特性或非 Windows 平台时没有缩略图。
+#[cfg(not(all(windows, feature = "video-thumbnails")))]
+pub fn thumbnail(_path: &Path, _size: u32) -> anyhow::Result<DynamicImage> {
+    anyhow::bail!("Video thumbnails need the video-thumbnails feature on Windows")
+}
@@ -0,0 +1,102 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 准入控制：处理中的请求达到上限后返回 503 与 Retry-After，降到低水位以下才恢复接收。
+ */
+mod common;
+
+use axum::body::{Body, Bytes};
+use axum::http::{header, Method, StatusCode};
+use common::{authorized, json_request, Harness, BOUNDARY};
+use fastsync::PipelineLimits;
+use futures::channel::mpsc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const MAX_QUEUE_DEPTH: usize = 8;
+
+/// 请求体迟迟不发送完的上传，关闭发送端前一直计为处理中。
+struct StalledUpload {
+    body: mpsc::UnboundedSender<Result<Bytes, std::io::Error>>,
+    response: tokio::task::JoinHandle<StatusCode>,
+}
+
+impl StalledUpload {
+    fn start(harness: &Harness) -> Self {
+        let (body, chunks) = mpsc::unbounded();
+        let request = authorized(Method::POST, "/v1/upload")
+            .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY))
+            .header(header::CONTENT_LENGTH, 1024)
+            .body(Body::from_stream(chunks))
+            .unwrap();
+        let request = common::from(request, common::PHONE);
+        let router = harness.router.clone();
+        let response = tokio::spawn(async move { router.oneshot(request).await.unwrap().status() });
+        Self { body, response }
+    }
+
+    /// 中断请求体，请求随即结束。
+    async fn finish(self) {
+        drop(self.body);
+        self.response.await.unwrap();
+    }
+}
+
+async fn queue_depth(harness: &Harness) -> u64 {
+    harness.get("/v1/health").await.json()["queue_depth"].as_u64().unwrap()
+}
+
+/// 等待排队深度达到 `expected`，最多 5 秒，返回最后读到的深度。
+async fn wait_for_depth(harness: &Harness, expected: u64) -> u64 {
+    for _ in 0..100 {
+        let depth = queue_depth(harness).await;
+        if depth == expected {
+            return depth;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    queue_depth(harness).await
+}
+
+async fn probe(harness: &Harness) -> common::TestResponse {
+    harness.send(json_request("/v1/clipboard", r#"{"text":"probe"}"#)).await
+}
+
+#[tokio::test]
+async fn saturated_pipeline_rejects_until_drained_below_the_low_water_mark() {
+    let harness = Harness::with(|builder, _| {
+        builder.pipeline_limits(PipelineLimits {
+            max_queue_depth: MAX_QUEUE_DEPTH,
+            ..PipelineLimits::default()
+        })
+    });
+
+    let mut uploads = Vec::new();
+    for _ in 0..MAX_QUEUE_DEPTH {
+        uploads.push(StalledUpload::start(&harness));
+    }
+    assert_eq!(wait_for_depth(&harness, MAX_QUEUE_DEPTH as u64).await, MAX_QUEUE_DEPTH as u64);
+
+    let response = probe(&harness).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json()["error"], "overloaded");
+    let retry_after: u64 = response.header("retry-after").unwrap().parse().unwrap();
+    assert!(retry_after >= 1, "{}", retry_after);
+
+    // 低水位为上限的四分之三：降到上限以下但仍高于低水位时继续拒绝
+    uploads.pop().unwrap().finish().await;
+    assert_eq!(queue_depth(&harness).await, MAX_QUEUE_DEPTH as u64 - 1);
+    assert_eq!(probe(&harness).await.status, StatusCode::SERVICE_UNAVAILABLE);
+
+    uploads.pop().unwrap().finish().await;
+    assert_eq!(queue_depth(&harness).await, (MAX_QUEUE_DEPTH * 3 / 4) as u64);
+    let response = probe(&harness).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert_eq!(response.header("retry-after"), None);
+
+    for upload in uploads {
+        upload.finish().await;
+    }
+    assert_eq!(wait_for_depth(&harness, 0).await, 0);
+}
@@ -0,0 +1,105 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 操作审计：自动写入剪贴板、自动复制验证码与通知按钮都留下记录，失败时附带错误，
+ * 记录同时写入短信历史数据库，重启后仍可通过 `GET /audit` 查到。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use common::{authorized, from, wait_until, Harness, LOCAL};
+use fastsync::{AuditRecord, ClipboardWrite, MockNotifier, RecordingClipboard};
+use serde_json::json;
+use std::sync::Arc;
+
+/// 本机查询的最近操作记录，最早的在前。
+async fn audit(harness: &Harness) -> Vec<AuditRecord> {
+    let request = from(authorized(Method::GET, "/v1/audit?limit=500").body(Body::empty()).unwrap(), LOCAL);
+    let response = harness.send(request).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let mut records: Vec<AuditRecord> = serde_json::from_slice(&response.body).unwrap();
+    records.reverse();
+    records
+}
+
+/// 通知标签中的内容标识，与审计记录的 `item_id` 相同。
+fn item_id(tag: &str) -> &str {
+    tag.split_once('_').map(|(_, id)| id).unwrap()
+}
+
+#[tokio::test]
+async fn auto_apply_is_audited_including_failures() {
+    let harness = Harness::with(|builder, _| builder.auto_apply_clipboard(true));
+
+    // 剪贴板被占用：退回确认通知，失败原因写入记录
+    harness.clipboard.fail_next(1);
+    let first = harness.post_json("/v1/clipboard", json!({ "text": "first" })).await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert!(first.json().get("applied").is_none(), "{:?}", first.json());
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+    let tag = harness.notifier.shown()[0].tag.clone();
+    assert!(harness.notifier.click(&tag, "copy_clipboard"));
+
+    let second = harness.post_json("/v1/clipboard", json!({ "text": "second" })).await;
+    assert_eq!(second.json()["applied"], true);
+
+    let records = audit(&harness).await;
+    let actions: Vec<(&str, bool)> = records
+        .iter()
+        .map(|record| (record.action.as_str(), record.error.is_some()))
+        .collect();
+    assert_eq!(actions, vec![("auto_apply", true), ("copy", false), ("auto_apply", false)]);
+    // 同一条内容的失败与之后的手动复制属于同一条目
+    assert_eq!(records[0].item_id, item_id(&tag));
+    assert_eq!(records[1].item_id, item_id(&tag));
+    assert!(records.iter().all(|record| record.item_type == "clipboard"));
+    assert_eq!(
+        harness.clipboard.writes(),
+        vec![ClipboardWrite::Text("first".to_string()), ClipboardWrite::Text("second".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn auto_copy_code_failure_is_audited() {
+    let harness = Harness::with(|builder, _| builder.auto_copy_codes(true));
+
+    harness.clipboard.fail_next(1);
+    let sms = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "验证码 7731", "code": "7731" })).await;
+    assert_eq!(sms.status, StatusCode::OK);
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+    let notification = harness.notifier.shown().remove(0);
+    // 未能自动复制时通知中仍提供复制按钮
+    assert_eq!(notification.title, "验证码: 7731");
+    assert!(harness.notifier.click(&notification.tag, "ignore"));
+
+    let records = audit(&harness).await;
+    assert_eq!(records.len(), 2, "{:?}", records);
+    assert_eq!(records[0].action, "auto_copy_code");
+    assert!(records[0].error.as_deref().unwrap().contains("simulated clipboard failure"));
+    assert_eq!(records[1].action, "ignore");
+    assert!(records.iter().all(|record| record.item_id == item_id(&notification.tag)));
+}
+
+#[tokio::test]
+async fn records_survive_a_restart_through_the_history_database() {
+    let harness = Harness::with(|builder, _| builder.auto_copy_codes(true));
+    let sms = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "验证码 5521", "code": "5521" })).await;
+    assert_eq!(sms.status, StatusCode::OK);
+    assert_eq!(audit(&harness).await.len(), 1);
+
+    let database = rusqlite::Connection::open(harness.dir.path().join("sms_history.db")).unwrap();
+    let action: String = database.query_row("SELECT action FROM audit", [], |row| row.get(0)).unwrap();
+    assert_eq!(action, "auto_copy_code");
+
+    // 没有 JSON Lines 文件时从数据库载入
+    let restarted = common::builder(&harness.dir)
+        .notifier(Arc::new(MockNotifier::new()))
+        .clipboard(Arc::new(RecordingClipboard::new()))
+        .audit_log(harness.dir.path().join("missing.jsonl"))
+        .build();
+    let records = restarted.audit_records(10);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].action, "auto_copy_code");
+}
@@ -0,0 +1,107 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 令牌校验：缺少或错误的令牌返回 401，同一 IP 反复出错后被锁定；设备管理接口只接受本机请求与共用的访问令牌。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{authorized, from, json_request, Harness, LOCAL, TOKEN};
+
+fn clipboard(authorization: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/clipboard")
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    request.body(Body::from(r#"{"text":"hello"}"#)).unwrap()
+}
+
+#[tokio::test]
+async fn missing_or_wrong_tokens_are_rejected() {
+    let harness = Harness::new();
+    let cases = [
+        (None, "missing token"),
+        (Some("Basic aW50ZWdyYXRpb24="), "missing token"),
+        (Some("Bearer not-the-token"), "unknown token"),
+        (Some("Bearer "), "unknown token"),
+    ];
+    for (i, (authorization, error)) in cases.into_iter().enumerate() {
+        // 每个用例使用独立的来源，互不累计失败次数
+        let source = format!("192.168.1.{}:50000", 100 + i);
+        let response = harness.send(from(clipboard(authorization), &source)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "{:?}", authorization);
+        assert_eq!(response.json()["error"], error, "{:?}", authorization);
+    }
+    assert!(harness.clipboard.writes().is_empty());
+
+    let response = harness.send(clipboard(Some(&format!("Bearer {}", TOKEN)))).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+}
+
+#[tokio::test]
+async fn repeated_failures_lock_out_the_source() {
+    let harness = Harness::new();
+    const GUESSER: &str = "192.168.1.66:50000";
+    for attempt in 1..=5 {
+        let response = harness.send(from(clipboard(Some("Bearer guess")), GUESSER)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "attempt {}", attempt);
+    }
+
+    // 锁定期间即使令牌正确也被拒绝
+    let response = harness.send(from(json_request("/v1/clipboard", r#"{"text":"hello"}"#), GUESSER)).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.json()["error"], "locked");
+    assert_eq!(response.header("retry-after"), Some("5"));
+
+    // 其他来源不受影响
+    let response = harness.send(json_request("/v1/clipboard", r#"{"text":"hello"}"#)).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+}
+
+#[tokio::test]
+async fn device_tokens_cannot_manage_devices() {
+    let harness = Harness::new();
+    let (phone, phone_token) = harness.pair("Pixel 8").await;
+    let (tablet, _) = harness.pair("iPad").await;
+    let as_device = |method: Method, path: &str| {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, format!("Bearer {}", phone_token))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let cases = [
+        (Method::GET, "/v1/devices".to_string()),
+        (Method::DELETE, format!("/v1/devices/{}", tablet)),
+        (Method::DELETE, format!("/v1/devices/{}", phone)),
+    ];
+    for (method, path) in cases {
+        let response = harness.send(as_device(method.clone(), &path)).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN, "{} {}", method, path);
+        assert_eq!(response.json()["error"], "forbidden", "{} {}", method, path);
+    }
+    assert_eq!(harness.server.devices().len(), 2);
+
+    // 设备令牌仍可用于数据接口
+    let response = harness.send(as_device(Method::GET, "/v1/info")).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = harness.send(Request::builder().uri("/v1/devices").body(Body::empty()).unwrap()).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = harness.send(from(Request::builder().uri("/v1/devices").body(Body::empty()).unwrap(), LOCAL)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response = harness.get("/v1/devices").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["devices"].as_array().unwrap().len(), 2);
+
+    let request = authorized(Method::DELETE, &format!("/v1/devices/{}", tablet)).body(Body::empty()).unwrap();
+    assert!(harness.send(request).await.status.is_success());
+    assert_eq!(harness.server.devices().len(), 1);
+}
@@ -0,0 +1,86 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 通过公开的构建器在进程内构建服务，使用记录型后端。
+ */
+mod common;
+
+use axum::http::StatusCode;
+use common::{builder, Harness, TestDir};
+use fastsync::{MockNotifier, RecordingClipboard, ServerEvent};
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn router_serves_health_under_v1() {
+    let harness = Harness::new();
+
+    let health = harness.get("/v1/health").await;
+    assert_eq!(health.status, StatusCode::OK);
+    let body = health.json();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["mode"], "desktop");
+    assert_eq!(health.header("x-fastsync-protocol"), Some(fastsync::PROTOCOL_VERSION.to_string().as_str()));
+}
+
+#[tokio::test]
+async fn events_reach_callbacks_and_subscribers() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let harness = Harness::with(move |builder, _| {
+        builder.on_event(move |event| {
+            if let ServerEvent::ClipboardReceived { text } = event {
+                sink.lock().unwrap().push(text.clone());
+            }
+        })
+    });
+    let mut events = harness.server.subscribe();
+
+    let response = harness.post_json("/v1/clipboard", json!({ "text": "hello" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(*seen.lock().unwrap(), vec!["hello".to_string()]);
+    assert!(matches!(events.try_recv(), Ok(ServerEvent::ClipboardReceived { text }) if text == "hello"));
+
+    // 通知经由构建器传入的后端显示
+    assert!(common::wait_until(|| harness.notifier.shown().len() == 1).await);
+    assert!(harness.notifier.click(&harness.notifier.shown()[0].tag, "copy_clipboard"));
+    assert!(!harness.clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn started_server_answers_health_and_stops() {
+    let dir = TestDir::new();
+    let server = builder(&dir)
+        .bind(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .notifier(Arc::new(MockNotifier::new()))
+        .clipboard(Arc::new(RecordingClipboard::new()))
+        .build();
+    let mut events = server.subscribe();
+
+    let addr = server.start().await.unwrap();
+    assert_eq!(server.local_addr(), Some(addr));
+    assert!(matches!(events.recv().await, Ok(ServerEvent::Started { addr: started }) if started == addr));
+
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /v1/health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("\"status\":\"ok\""), "{}", response);
+
+    server.shutdown();
+    server.wait().await;
+    loop {
+        match events.recv().await {
+            Ok(ServerEvent::Stopped) => break,
+            Ok(_) => continue,
+            Err(e) => panic!("no Stopped event: {:?}", e),
+        }
+    }
+}
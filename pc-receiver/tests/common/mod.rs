@@ -0,0 +1,289 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 集成测试共用的辅助函数。
+ * 服务通过公开的构建器使用记录型的通知与剪贴板后端，所有数据文件写入独立的临时目录，
+ * 请求直接交给路由处理，不监听端口。
+ */
+#![allow(dead_code)]
+
+use axum::body::{Body, Bytes};
+use axum::extract::ConnectInfo;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use fastsync::{FastSyncServer, FastSyncServerBuilder, MockNotifier, RecordingClipboard, RunMode};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+/// 测试使用的共用访问令牌。
+pub const TOKEN: &str = "integration-test-token";
+
+/// 局域网中手机的地址。
+pub const PHONE: &str = "192.168.1.20:50000";
+
+/// 本机地址。
+pub const LOCAL: &str = "127.0.0.1:50000";
+
+static DIR_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 测试结束时删除的临时目录。
+pub struct TestDir(PathBuf);
+
+impl TestDir {
+    pub fn new() -> Self {
+        let name = format!(
+            "fastsync-test-{}-{}",
+            std::process::id(),
+            DIR_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// 目录下的文件，不含子目录。
+    pub fn files(&self, dir: &str) -> Vec<PathBuf> {
+        match std::fs::read_dir(self.0.join(dir)) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 所有数据文件都写入 `dir` 的构建器：不广播 mDNS、不使用 HTTPS，数据接口使用 `TOKEN`。
+pub fn builder(dir: &TestDir) -> FastSyncServerBuilder {
+    let root = dir.path();
+    FastSyncServer::builder()
+        .port(0)
+        .mdns(false)
+        .https(false)
+        .access_token(TOKEN)
+        .audit_log(root.join("audit.jsonl"))
+        .sms_history(root.join("sms_history.db"))
+        .devices_file(root.join("devices.json"))
+        .port_hint_file(root.join("port.json"))
+        .quick_save_dir(root.join("quick"))
+}
+
+/// 使用记录型后端的服务。
+pub struct Harness {
+    pub server: FastSyncServer,
+    pub router: Router,
+    pub notifier: Arc<MockNotifier>,
+    pub clipboard: Arc<RecordingClipboard>,
+    pub dir: TestDir,
+}
+
+impl Harness {
+    /// 以桌面模式构建服务。
+    pub fn new() -> Self {
+        Self::with(|builder, _| builder)
+    }
+
+    /// 在默认设置之后调整构建器，第二个参数为本次测试的临时目录。
+    pub fn with(configure: impl FnOnce(FastSyncServerBuilder, &Path) -> FastSyncServerBuilder) -> Self {
+        let dir = TestDir::new();
+        let notifier = Arc::new(MockNotifier::new());
+        let clipboard = Arc::new(RecordingClipboard::new());
+        let builder = builder(&dir)
+            .mode(RunMode::Desktop)
+            .notifier(notifier.clone())
+            .clipboard(clipboard.clone());
+        let server = configure(builder, dir.path()).build();
+        let router = server.router();
+        Self { server, router, notifier, clipboard, dir }
+    }
+
+    /// 发送请求，未设置来源地址时视为来自局域网中的手机。
+    pub async fn send(&self, mut request: Request<Body>) -> TestResponse {
+        if request.extensions().get::<ConnectInfo<SocketAddr>>().is_none() {
+            request.extensions_mut().insert(ConnectInfo(PHONE.parse::<SocketAddr>().unwrap()));
+        }
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        TestResponse { status, headers, body }
+    }
+
+    /// 携带访问令牌 POST JSON。
+    pub async fn post_json(&self, path: &str, body: serde_json::Value) -> TestResponse {
+        self.send(json_request(path, &body.to_string())).await
+    }
+
+    /// 经扫码配对登记一台设备。
+    ///
+    /// # Returns
+    /// 设备标识与设备令牌
+    pub async fn pair(&self, device_name: &str) -> (String, String) {
+        let token = self.server.pairing_uri("http://192.168.1.5:3000").token;
+        let body = serde_json::json!({ "token": token, "device_name": device_name }).to_string();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/v1/pair/qr")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let response = self.send(request).await;
+        assert_eq!(response.status, StatusCode::OK, "pairing failed: {:?}", response.body);
+        let device_token = response.json()["device_token"].as_str().unwrap().to_string();
+        let device = self
+            .server
+            .devices()
+            .into_iter()
+            .find(|device| device.name == device_name)
+            .expect("paired device is listed");
+        (device.id, device_token)
+    }
+
+    /// 在本机的随机端口上提供路由，用于需要真实连接的 WebSocket 测试。
+    pub async fn serve(&self) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let service = self.router.clone().into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, service).await });
+        addr
+    }
+
+    /// 携带访问令牌 GET。
+    pub async fn get(&self, path: &str) -> TestResponse {
+        self.send(authorized(Method::GET, path).body(Body::empty()).unwrap()).await
+    }
+}
+
+/// 携带访问令牌的请求构建器。
+pub fn authorized(method: Method, path: &str) -> axum::http::request::Builder {
+    Request::builder()
+        .method(method)
+        .uri(path)
+        .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+}
+
+/// 携带访问令牌、请求体为原样文本的 JSON 请求，用于发送格式有误的 JSON。
+pub fn json_request(path: &str, body: &str) -> Request<Body> {
+    authorized(Method::POST, path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// 设置请求的来源地址。
+pub fn from(mut request: Request<Body>, addr: &str) -> Request<Body> {
+    request.extensions_mut().insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+    request
+}
+
+/// multipart/form-data 请求体的分隔符。
+pub const BOUNDARY: &str = "fastsync-test-boundary";
+
+/// multipart/form-data 中的一个字段。
+pub struct Part<'a> {
+    pub name: &'a str,
+    pub file_name: Option<&'a str>,
+    pub content_type: Option<&'a str>,
+    pub data: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    /// 文本字段。
+    pub fn text(name: &'a str, value: &'a str) -> Self {
+        Self { name, file_name: None, content_type: None, data: value.as_bytes() }
+    }
+
+    /// 文件字段。
+    pub fn file(name: &'a str, file_name: &'a str, content_type: &'a str, data: &'a [u8]) -> Self {
+        Self { name, file_name: Some(file_name), content_type: Some(content_type), data }
+    }
+}
+
+/// 构造 multipart/form-data 请求体。
+pub fn multipart_body(parts: &[Part]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for part in parts {
+        body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+        let disposition = match part.file_name {
+            Some(file_name) => format!("Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n", part.name, file_name),
+            None => format!("Content-Disposition: form-data; name=\"{}\"\r\n", part.name),
+        };
+        body.extend_from_slice(disposition.as_bytes());
+        if let Some(content_type) = part.content_type {
+            body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+        body.extend_from_slice(part.data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+/// 携带访问令牌的 multipart/form-data 请求。
+pub fn multipart_request(path: &str, parts: &[Part]) -> Request<Body> {
+    let body = multipart_body(parts);
+    authorized(Method::POST, path)
+        .header(header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY))
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// 携带访问令牌、以 `data` 字段上传一个文件的请求。
+pub fn upload_request(path: &str, file_name: &str, content_type: &str, data: &[u8]) -> Request<Body> {
+    multipart_request(path, &[Part::file("data", file_name, content_type, data)])
+}
+
+/// 编码为 PNG 的纯色小图。
+pub fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = image::RgbaImage::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+/// 读取完毕的响应。
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("response is not JSON ({}): {}", e, String::from_utf8_lossy(&self.body)))
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// 等待后台任务满足条件，最多 5 秒。
+pub async fn wait_until(mut condition: impl FnMut() -> bool) -> bool {
+    for _ in 0..100 {
+        if condition() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    condition()
+}
@@ -0,0 +1,94 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 压缩的请求体：经过令牌、幂等与大小限制后由处理器边读边解压，解压后超过上限返回 413。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{authorized, multipart_body, png, wait_until, Harness, Part, BOUNDARY};
+use fastsync::BodyLimits;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use std::io::Write;
+
+const LIMIT: usize = 1024 * 1024;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gzip_request(path: &str, content_type: &str, data: &[u8]) -> Request<Body> {
+    let body = gzip(data);
+    authorized(Method::POST, path)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_ENCODING, "gzip")
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn harness() -> Harness {
+    Harness::with(|builder, _| builder.body_limits(BodyLimits { upload: LIMIT, json: LIMIT }))
+}
+
+#[tokio::test]
+async fn compressed_json_is_decoded_and_deduplicated() {
+    let harness = harness();
+    let body = json!({ "text": "压缩的剪贴板".repeat(1000), "request_id": "clip-1" }).to_string();
+
+    let first = harness.send(gzip_request("/v1/clipboard", "application/json", body.as_bytes())).await;
+    assert_eq!(first.status, StatusCode::OK, "{:?}", first.body);
+    // JSON 中的 request_id 在解压后读取，重试时重放第一次的响应
+    let retry = harness.send(gzip_request("/v1/clipboard", "application/json", body.as_bytes())).await;
+    assert_eq!(retry.status, StatusCode::OK);
+    assert_eq!(retry.header("idempotent-replayed"), Some("true"));
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+}
+
+#[tokio::test]
+async fn compressed_upload_is_stored() {
+    let harness = harness();
+    let image = png(8, 8);
+    let form = multipart_body(&[Part::file("data", "a.png", "image/png", &image)]);
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+
+    let response = harness.send(gzip_request("/v1/upload", &content_type, &form)).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert_eq!(response.json()["bytes"], image.len() as u64);
+}
+
+#[tokio::test]
+async fn bodies_expanding_beyond_the_limit_are_rejected() {
+    let harness = harness();
+
+    let text = json!({ "text": "0".repeat(4 * LIMIT) }).to_string();
+    let response = harness.send(gzip_request("/v1/clipboard", "application/json", text.as_bytes())).await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE, "{:?}", response.body);
+
+    let image = vec![0u8; 4 * LIMIT];
+    let form = multipart_body(&[Part::file("data", "a.png", "image/png", &image)]);
+    let content_type = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let response = harness.send(gzip_request("/v1/upload", &content_type, &form)).await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE, "{:?}", response.body);
+    assert_eq!(response.json()["limit"], LIMIT);
+    assert!(harness.notifier.shown().is_empty());
+}
+
+#[tokio::test]
+async fn invalid_compressed_data_is_rejected_before_the_handler() {
+    let harness = harness();
+    let request = authorized(Method::POST, "/v1/clipboard")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(Body::from(r#"{"text":"not compressed"}"#))
+        .unwrap();
+    let response = harness.send(request).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.json()["error"], "decode_failed");
+}
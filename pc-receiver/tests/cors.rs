@@ -0,0 +1,84 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 跨域：配置文件中允许的来源、预检请求与 Authorization 请求头。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{Harness, TOKEN};
+use fastsync::Config;
+
+const ALLOWED: &str = "http://localhost:5173";
+
+/// 从配置文件读取跨域设置的服务。
+fn harness_with_config_file() -> Harness {
+    Harness::with(|builder, dir| {
+        let path = dir.join("config.toml");
+        std::fs::write(&path, format!("[cors]\nallowed_origins = [\"{}\"]\n", ALLOWED)).unwrap();
+        builder.cors(Config::load_or_create(&path).unwrap().cors)
+    })
+}
+
+/// 浏览器为携带令牌的 JSON 请求发出的预检请求。
+fn preflight(path: &str, origin: &str, headers: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri(path)
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, headers)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn preflight_from_an_allowed_origin_permits_authorization() {
+    let harness = harness_with_config_file();
+    let response = harness.send(preflight("/v1/clipboard", ALLOWED, "authorization,content-type")).await;
+
+    assert!(response.status.is_success(), "{}", response.status);
+    assert_eq!(response.header("access-control-allow-origin"), Some(ALLOWED));
+    let methods = response.header("access-control-allow-methods").unwrap_or_default().to_ascii_lowercase();
+    assert!(methods.contains("post"), "{}", methods);
+    let headers = response.header("access-control-allow-headers").unwrap_or_default().to_ascii_lowercase();
+    assert!(headers.contains("authorization"), "{}", headers);
+    assert!(headers.contains("content-type"), "{}", headers);
+}
+
+#[tokio::test]
+async fn preflight_from_another_origin_is_not_allowed() {
+    let harness = harness_with_config_file();
+    for origin in ["http://evil.example", "http://localhost:5174", "https://localhost:5173"] {
+        let response = harness.send(preflight("/v1/clipboard", origin, "authorization")).await;
+        assert_eq!(response.header("access-control-allow-origin"), None, "{}", origin);
+    }
+}
+
+#[tokio::test]
+async fn actual_request_from_an_allowed_origin_exposes_the_request_id() {
+    let harness = harness_with_config_file();
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/clipboard")
+        .header(header::ORIGIN, ALLOWED)
+        .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"text":"hello"}"#))
+        .unwrap();
+    let response = harness.send(request).await;
+
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert_eq!(response.header("access-control-allow-origin"), Some(ALLOWED));
+    let exposed = response.header("access-control-expose-headers").unwrap_or_default();
+    assert!(exposed.contains("x-request-id"), "{}", exposed);
+}
+
+#[tokio::test]
+async fn without_configured_origins_no_cors_headers_are_sent() {
+    let harness = Harness::new();
+    let response = harness.send(preflight("/v1/clipboard", ALLOWED, "authorization")).await;
+    assert_eq!(response.header("access-control-allow-origin"), None);
+}
@@ -0,0 +1,101 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 文档中的错误标识：无法解析的 multipart 与 JSON 返回 400 `decode_failed`，处理器 panic 返回 500 `internal_error`。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{header, Method, StatusCode};
+use common::{authorized, json_request, multipart_body, png, upload_request, Harness, Part, BOUNDARY};
+use fastsync::{PayloadContext, PayloadHandler, PayloadOutcome};
+use futures::future::BoxFuture;
+
+/// 以指定的 Content-Type 发送原样的请求体。
+fn raw_multipart(path: &str, content_type: &str, body: Vec<u8>) -> Request<Body> {
+    authorized(Method::POST, path)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// 处理请求时 panic 的处理器。
+struct PanickingHandler;
+
+impl PayloadHandler for PanickingHandler {
+    fn path(&self) -> &str {
+        "/explode"
+    }
+
+    fn capability(&self) -> &str {
+        "explode"
+    }
+
+    fn handle(&self, _ctx: PayloadContext, _request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move { panic!("handler bug") })
+    }
+}
+
+#[tokio::test]
+async fn malformed_multipart_is_a_decode_failure() {
+    let harness = Harness::new();
+    let image = png(8, 8);
+    let complete = multipart_body(&[Part::file("data", "a.png", "image/png", &image)]);
+    let with_boundary = format!("multipart/form-data; boundary={}", BOUNDARY);
+    let cases = [
+        // 请求体在文件内容中间被截断，没有结束分隔符
+        ("truncated", with_boundary.clone(), complete[..complete.len() / 2].to_vec()),
+        // 缺少结束分隔符
+        ("no closing boundary", with_boundary.clone(), complete[..complete.len() - BOUNDARY.len() - 6].to_vec()),
+        // Content-Type 中没有分隔符
+        ("no boundary parameter", "multipart/form-data".to_string(), complete.clone()),
+        // 请求体中没有声明的分隔符
+        ("wrong boundary", "multipart/form-data; boundary=other".to_string(), complete.clone()),
+        ("garbage", with_boundary.clone(), b"\x00\x01 not multipart at all".to_vec()),
+    ];
+    for path in ["/v1/upload", "/v1/upload/file"] {
+        for (name, content_type, body) in &cases {
+            let response = harness.send(raw_multipart(path, content_type, body.clone())).await;
+            assert_eq!(response.status, StatusCode::BAD_REQUEST, "{} {}: {:?}", path, name, response.body);
+            let json = response.json();
+            assert_eq!(json["error"], "decode_failed", "{} {}: {}", path, name, json);
+            assert_eq!(json["ok"], false, "{} {}", path, name);
+        }
+    }
+    assert_eq!(harness.notifier.attempts(), 0);
+    assert!(harness.dir.files("quick").is_empty());
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_decode_failure() {
+    let harness = Harness::new();
+    let cases = [
+        ("/v1/sms", "{\"sender\": \"10690\", \"content\": "),
+        ("/v1/sms", "not json"),
+        ("/v1/clipboard", "{\"text\": \"a\""),
+        ("/v1/clipboard", "{'text': 'a'}"),
+    ];
+    for (path, body) in cases {
+        let response = harness.send(json_request(path, body)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{} {:?}", path, body);
+        assert_eq!(response.json()["error"], "decode_failed", "{} {:?}", path, body);
+    }
+    assert!(harness.clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn handler_panic_is_an_internal_error() {
+    let harness = Harness::with(|builder, _| builder.handler(PanickingHandler));
+
+    let response = harness.send(json_request("/v1/explode", "{}")).await;
+    assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+    let json = response.json();
+    assert_eq!(json["error"], "internal_error", "{}", json);
+    assert_eq!(json["ok"], false, "{}", json);
+
+    // 只影响这一个请求，服务照常处理后续请求
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &png(8, 8))).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+}
@@ -0,0 +1,96 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 无桌面模式：不调用通知与剪贴板后端，图片直接落盘，短信与剪贴板只写入历史。
+ * 以 Windows 服务运行时同样没有桌面会话。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use common::{from, png, upload_request, Harness, LOCAL};
+use fastsync::RunMode;
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::test]
+async fn headless_mode_saves_and_records_without_desktop_backends() {
+    let harness = Harness::with(|builder, dir| builder.mode(RunMode::Headless).auto_save_dir(dir.join("saved")));
+
+    let health = harness.get("/v1/health").await;
+    assert_eq!(health.status, StatusCode::OK);
+    assert_eq!(health.json()["mode"], "headless");
+
+    let sms = harness
+        .post_json("/v1/sms", json!({ "sender": "95588", "content": "您的验证码是 482913，5 分钟内有效" }))
+        .await;
+    assert_eq!(sms.status, StatusCode::OK, "{:?}", sms.json());
+    assert_eq!(sms.json()["ok"], true);
+
+    let clipboard = harness.post_json("/v1/clipboard", json!({ "text": "from the phone" })).await;
+    assert_eq!(clipboard.status, StatusCode::OK, "{:?}", clipboard.json());
+
+    let image = png(4, 3);
+    let upload = harness.send(upload_request("/v1/upload", "shot.png", "image/png", &image)).await;
+    assert_eq!(upload.status, StatusCode::OK, "{:?}", upload.json());
+    assert_eq!(upload.json()["bytes"], image.len() as u64);
+
+    // 图片写入自动保存目录
+    let saved = harness.dir.files("saved");
+    assert_eq!(saved.len(), 1, "{:?}", saved);
+    assert_eq!(std::fs::read(&saved[0]).unwrap(), image);
+
+    // 短信写入短信历史，剪贴板写入剪贴板历史
+    let request = from(common::authorized(Method::GET, "/v1/sms/history").body(Body::empty()).unwrap(), LOCAL);
+    let history = harness.send(request).await;
+    assert_eq!(history.status, StatusCode::OK);
+    assert_eq!(history.json()[0]["sender"], "95588");
+    let request = from(common::authorized(Method::GET, "/v1/clipboard/history").body(Body::empty()).unwrap(), LOCAL);
+    let history = harness.send(request).await;
+    assert_eq!(history.status, StatusCode::OK);
+    assert!(history.body.windows(14).any(|window| window == b"from the phone"));
+
+    // 通知在后台提交，稍等片刻确认没有调用后端
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(harness.notifier.attempts(), 0, "{:?}", harness.notifier.shown());
+    assert!(harness.clipboard.writes().is_empty(), "{:?}", harness.clipboard.writes());
+}
+
+#[tokio::test]
+async fn headless_mode_ignores_auto_copy_and_auto_apply() {
+    let harness = Harness::with(|builder, dir| {
+        builder
+            .mode(RunMode::Headless)
+            .auto_save_dir(dir.join("saved"))
+            .auto_copy_codes(true)
+            .auto_apply_clipboard(true)
+    });
+
+    let sms = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "验证码 7731", "code": "7731" })).await;
+    assert_eq!(sms.status, StatusCode::OK);
+    let clipboard = harness.post_json("/v1/clipboard", json!({ "text": "apply me" })).await;
+    assert_eq!(clipboard.status, StatusCode::OK);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(harness.notifier.attempts(), 0);
+    assert!(harness.clipboard.writes().is_empty(), "{:?}", harness.clipboard.writes());
+}
+
+#[tokio::test]
+async fn service_mode_reports_no_desktop_capabilities() {
+    let harness = Harness::with(|builder, dir| builder.mode(RunMode::Service).auto_save_dir(dir.join("saved")));
+
+    assert_eq!(harness.get("/v1/health").await.json()["mode"], "service");
+    let info = harness.get("/v1/info").await.json();
+    assert_eq!(info["mode"], "service");
+    let capabilities = info["capabilities"].as_object().unwrap();
+    assert!(!capabilities.is_empty());
+    assert!(capabilities.values().all(|value| value == false), "{:?}", capabilities);
+
+    let clipboard = harness.post_json("/v1/clipboard", json!({ "text": "from the phone" })).await;
+    assert_eq!(clipboard.status, StatusCode::OK, "{:?}", clipboard.json());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(harness.notifier.attempts(), 0);
+    assert!(harness.clipboard.writes().is_empty(), "{:?}", harness.clipboard.writes());
+}
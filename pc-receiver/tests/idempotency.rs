@@ -0,0 +1,95 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 幂等键：相同的键重试时重放第一次的响应，只落盘与通知一次；相同的键配上不同的内容被拒绝。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{json_request, png, upload_request, wait_until, Harness, TestDir};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn with_key(mut request: Request<Body>, key: &str) -> Request<Body> {
+    request.headers_mut().insert("idempotency-key", key.parse().unwrap());
+    request
+}
+
+/// 收到的上传在临时目录中的文件，不含通知预览。
+fn temp_uploads(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("fastsync_") && !name.starts_with("fastsync_preview_")
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn retried_upload_is_stored_and_notified_once() {
+    // 本进程中只有这个测试写入临时文件，改用独立的临时目录以便计数
+    let temp = TestDir::new();
+    std::env::set_var("TMPDIR", temp.path());
+    let harness = Harness::new();
+    let image = png(8, 8);
+
+    let first = harness.send(with_key(upload_request("/v1/upload", "a.png", "image/png", &image), "upload-1")).await;
+    assert_eq!(first.status, StatusCode::OK, "{:?}", first.body);
+    assert_eq!(first.header("idempotent-replayed"), None);
+    let retry = harness.send(with_key(upload_request("/v1/upload", "a.png", "image/png", &image), "upload-1")).await;
+    assert_eq!(retry.status, StatusCode::OK);
+    assert_eq!(retry.header("idempotent-replayed"), Some("true"));
+    assert_eq!(retry.body, first.body);
+
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(harness.notifier.attempts(), 1);
+    assert_eq!(temp_uploads(temp.path()).len(), 1, "{:?}", temp_uploads(temp.path()));
+
+    // 相同的键配上不同的图片不会重放第一次的结果
+    let other = harness.send(with_key(upload_request("/v1/upload", "b.png", "image/png", &png(16, 16)), "upload-1")).await;
+    assert_eq!(other.status, StatusCode::UNPROCESSABLE_ENTITY, "{:?}", other.body);
+    assert_eq!(other.json()["error"], "idempotency_key_reused");
+    assert_eq!(other.header("idempotent-replayed"), None);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(harness.notifier.attempts(), 1);
+    assert_eq!(temp_uploads(temp.path()).len(), 1);
+}
+
+#[tokio::test]
+async fn json_retries_are_checked_against_the_first_body() {
+    let harness = Harness::new();
+    let sms = json!({ "sender": "10690", "content": "你好" }).to_string();
+    for _ in 0..2 {
+        let response = harness.send(with_key(json_request("/v1/sms", &sms), "sms-1")).await;
+        assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    }
+    let changed = json!({ "sender": "10690", "content": "再见" }).to_string();
+    let response = harness.send(with_key(json_request("/v1/sms", &changed), "sms-1")).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json()["error"], "idempotency_key_reused");
+
+    // 请求体中的 request_id 同样有效
+    let cases = [
+        ("first", StatusCode::OK, None),
+        ("first", StatusCode::OK, Some("true")),
+        ("second", StatusCode::UNPROCESSABLE_ENTITY, None),
+    ];
+    for (text, status, replayed) in cases {
+        let body = json!({ "text": text, "request_id": "clip-1" }).to_string();
+        let mut request = json_request("/v1/clipboard", &body);
+        request.headers_mut().insert(header::CONTENT_LENGTH, body.len().into());
+        let response = harness.send(request).await;
+        assert_eq!(response.status, status, "{}: {:?}", text, response.body);
+        assert_eq!(response.header("idempotent-replayed"), replayed, "{}", text);
+    }
+    assert!(wait_until(|| harness.notifier.shown().len() == 2).await);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(harness.notifier.attempts(), 2, "{:?}", harness.notifier.shown());
+}
@@ -0,0 +1,182 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 双向 TLS：配对时签发的客户端证书可以在 mTLS 端口上免令牌访问数据接口，
+ * 其他 CA 签发的证书在握手时被拒绝，设备解除配对后证书随即失效。
+ */
+#![cfg(feature = "tls")]
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::Harness;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::{ClientConfig, RootCertStore};
+use serde_json::{json, Value};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+/// 配对响应中的客户端证书与 mTLS 端口。
+struct Issued {
+    device_id: String,
+    ca_cert: String,
+    client_cert: String,
+    client_key: String,
+    port: u16,
+}
+
+async fn start() -> Harness {
+    let harness = Harness::with(|builder, dir| {
+        builder
+            .bind(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .mtls(0)
+            .tls_dir(dir.join("tls"))
+    });
+    harness.server.start().await.unwrap();
+    harness
+}
+
+async fn pair(harness: &Harness) -> Issued {
+    let token = harness.server.pairing_uri("http://127.0.0.1:3000").token;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/pair/qr")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "token": token, "device_name": "Pixel" }).to_string()))
+        .unwrap();
+    let response = harness.send(request).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    let mtls = &body["mtls"];
+    let text = |value: &Value| value.as_str().unwrap().to_string();
+    Issued {
+        device_id: text(&body["device_id"]),
+        ca_cert: text(&mtls["ca_cert"]),
+        client_cert: text(&mtls["client_cert"]),
+        client_key: text(&mtls["client_key"]),
+        port: mtls["port"].as_u64().unwrap() as u16,
+    }
+}
+
+/// 用给定的客户端证书连接 mTLS 端口，信任配对时下发的 CA。
+async fn connect(issued: &Issued, client_cert: &str, client_key: &str) -> std::io::Result<TlsStream<TcpStream>> {
+    let mut roots = RootCertStore::empty();
+    roots.add(CertificateDer::from_pem_slice(issued.ca_cert.as_bytes()).unwrap()).unwrap();
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_client_auth_cert(
+            vec![CertificateDer::from_pem_slice(client_cert.as_bytes()).unwrap()],
+            PrivateKeyDer::from_pem_slice(client_key.as_bytes()).unwrap(),
+        )
+        .unwrap();
+    let stream = TcpStream::connect(SocketAddr::from(([127, 0, 0, 1], issued.port))).await?;
+    let name = ServerName::try_from("localhost").unwrap();
+    TlsConnector::from(Arc::new(config)).connect(name, stream).await
+}
+
+/// 在同一连接上发送不带令牌的 `POST /v1/clipboard`，返回状态码与响应体。
+/// 连接被服务端关闭时返回错误。
+async fn post_clipboard(stream: &mut TlsStream<TcpStream>, text: &str) -> std::io::Result<(u16, Value)> {
+    let body = json!({ "text": text }).to_string();
+    let request = format!(
+        "POST /v1/clipboard HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut received = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        received.extend_from_slice(&chunk[..read]);
+        let Some(end) = received.windows(4).position(|window| window == b"\r\n\r\n") else {
+            continue;
+        };
+        let head = String::from_utf8_lossy(&received[..end]).to_ascii_lowercase();
+        let length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map_or(0, |value| value.trim().parse().unwrap());
+        if received.len() >= end + 4 + length {
+            let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+            let body = serde_json::from_slice(&received[end + 4..end + 4 + length]).unwrap_or(Value::Null);
+            return Ok((status, body));
+        }
+    }
+}
+
+#[tokio::test]
+async fn certificate_issued_at_pairing_is_accepted() {
+    let harness = start().await;
+    let issued = pair(&harness).await;
+    assert_ne!(issued.port, 0);
+
+    let mut stream = connect(&issued, &issued.client_cert, &issued.client_key).await.unwrap();
+    let (status, body) = post_clipboard(&mut stream, "over mtls").await.unwrap();
+    assert_eq!(status, 200, "{:?}", body);
+    assert_eq!(body["ok"], true);
+    harness.server.shutdown();
+}
+
+#[tokio::test]
+async fn certificate_from_another_ca_is_rejected() {
+    let harness = start().await;
+    let issued = pair(&harness).await;
+
+    // 另一个 CA 签发、带有同一设备标识的证书
+    let mut ca_params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let ca_key = rcgen::KeyPair::generate().unwrap();
+    let ca = ca_params.self_signed(&ca_key).unwrap();
+    let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    let san = format!("urn:fastsync:device:{}", issued.device_id);
+    params.subject_alt_names = vec![rcgen::SanType::URI(san.try_into().unwrap())];
+    params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+    let key = rcgen::KeyPair::generate().unwrap();
+    let forged = params.signed_by(&key, &ca, &ca_key).unwrap();
+
+    // TLS 1.3 中服务端在客户端完成握手后才校验证书，拒绝表现为之后的读写失败
+    let rejected = match connect(&issued, &forged.pem(), &key.serialize_pem()).await {
+        Ok(mut stream) => post_clipboard(&mut stream, "forged").await.is_err(),
+        Err(_) => true,
+    };
+    assert!(rejected);
+    assert!(harness.clipboard.writes().is_empty());
+    harness.server.shutdown();
+}
+
+#[tokio::test]
+async fn certificate_of_a_revoked_device_is_rejected() {
+    let harness = start().await;
+    let issued = pair(&harness).await;
+
+    // 已建立的连接上的下一个请求返回 revoked
+    let mut open = connect(&issued, &issued.client_cert, &issued.client_key).await.unwrap();
+    assert_eq!(post_clipboard(&mut open, "before").await.unwrap().0, 200);
+    assert!(harness.server.revoke_device(&issued.device_id));
+    let (status, body) = post_clipboard(&mut open, "after").await.unwrap();
+    assert_eq!(status, 401);
+    assert_eq!(body["error"], "revoked");
+
+    // 新连接在握手后即被关闭
+    let refused = match connect(&issued, &issued.client_cert, &issued.client_key).await {
+        Ok(mut stream) => post_clipboard(&mut stream, "after").await.is_err(),
+        Err(_) => true,
+    };
+    assert!(refused);
+    harness.server.shutdown();
+}
@@ -0,0 +1,44 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 扫码配对：二维码中的一次性令牌只能兑换一次，兑换得到的设备令牌可访问数据接口。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::Harness;
+use fastsync::PairingUri;
+use serde_json::json;
+
+#[tokio::test]
+async fn pairing_uri_token_is_single_use() {
+    let harness = Harness::new();
+    let uri = harness.server.pairing_uri("http://192.168.1.5:3000");
+    let parsed: PairingUri = uri.to_string().parse().unwrap();
+    assert_eq!(parsed.token, uri.token);
+
+    let body = json!({ "token": parsed.token, "device_name": "Pixel" });
+    let paired = harness.post_json("/v1/pair/qr", body.clone()).await;
+    assert_eq!(paired.status, StatusCode::OK);
+    let device_token = paired.json()["device_token"].as_str().unwrap().to_string();
+
+    let reused = harness.post_json("/v1/pair/qr", body).await;
+    assert_eq!(reused.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(reused.json()["error"], "used");
+
+    let unknown = harness.post_json("/v1/pair/qr", json!({ "token": "forged", "device_name": "Pixel" })).await;
+    assert_eq!(unknown.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown.json()["error"], "invalid");
+
+    // 兑换得到的设备令牌可以访问数据接口
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/clipboard")
+        .header(header::AUTHORIZATION, format!("Bearer {}", device_token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"text":"paired"}"#))
+        .unwrap();
+    assert_eq!(harness.send(request).await.status, StatusCode::OK);
+}
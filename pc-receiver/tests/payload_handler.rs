@@ -0,0 +1,90 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 第三方载荷处理器：注册后与内置处理器一样经过令牌校验，出现在 `/info` 中并可请求通知。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{Method, StatusCode};
+use axum::Json;
+use common::{json_request, Harness};
+use fastsync::{Notification, PayloadContext, PayloadHandler, PayloadOutcome, ServerEvent};
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// 接收自行车码表发来的位置。
+struct LocationHandler;
+
+impl PayloadHandler for LocationHandler {
+    fn path(&self) -> &str {
+        "/location"
+    }
+
+    fn capability(&self) -> &str {
+        "location"
+    }
+
+    fn handle(&self, ctx: PayloadContext, request: Request) -> BoxFuture<'static, PayloadOutcome> {
+        Box::pin(async move {
+            let body = axum::body::to_bytes(request.into_body(), 1024).await.unwrap();
+            let location: Value = serde_json::from_slice(&body).unwrap();
+            ctx.emit(ServerEvent::Custom { capability: "location".to_string(), data: location.clone() });
+
+            let mut notification = Notification::new("location", "收到位置");
+            notification.body.push(format!("{}, {}", location["lat"], location["lon"]));
+            PayloadOutcome::new(Json(json!({ "ok": true, "device": ctx.device.origin() })))
+                .with_notification(notification, Arc::new(|_: &str| {}))
+        })
+    }
+}
+
+#[tokio::test]
+async fn registered_handler_is_reachable_through_the_router() {
+    let harness = Harness::with(|builder, _| builder.handler(LocationHandler));
+    let mut events = harness.server.subscribe();
+
+    let response = harness.post_json("/v1/location", json!({ "lat": 31.23, "lon": 121.47 })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!({ "ok": true, "device": "192.168.1.20" }));
+
+    assert!(matches!(
+        events.try_recv(),
+        Ok(ServerEvent::Custom { capability, data }) if capability == "location" && data["lat"] == 31.23
+    ));
+    assert!(common::wait_until(|| harness.notifier.shown().len() == 1).await);
+    assert_eq!(harness.notifier.shown()[0].body, vec!["31.23, 121.47".to_string()]);
+
+    // 旧的无前缀路径同样可用
+    let response = harness.send(json_request("/location", r#"{"lat":0,"lon":0}"#)).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn registered_handler_requires_a_token() {
+    let harness = Harness::with(|builder, _| builder.handler(LocationHandler));
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/v1/location")
+        .body(Body::from(r#"{"lat":0,"lon":0}"#))
+        .unwrap();
+    let response = harness.send(request).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(harness.notifier.attempts(), 0);
+}
+
+#[tokio::test]
+async fn registered_handler_is_advertised_in_info() {
+    let harness = Harness::with(|builder, _| builder.handler(LocationHandler));
+
+    let info = harness.get("/v1/info").await.json();
+    let features: Vec<&str> = info["features"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+    assert!(features.contains(&"location"), "{:?}", features);
+    assert!(features.contains(&"photo"), "{:?}", features);
+    let endpoints: Vec<&str> = info["endpoints"].as_array().unwrap().iter().filter_map(Value::as_str).collect();
+    assert!(endpoints.contains(&"POST /location"), "{:?}", endpoints);
+}
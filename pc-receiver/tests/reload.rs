@@ -0,0 +1,149 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 配置重新载入：内容策略、计划暂停、验证码规则、跨域、去重与剪贴板历史立即生效，端口等监听设置需重启。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{from, png, upload_request, wait_until, Harness, LOCAL, TOKEN};
+use fastsync::Config;
+use serde_json::json;
+use std::path::PathBuf;
+
+const ORIGIN: &str = "http://localhost:5173";
+
+/// 与测试构建器一致的监听设置，重新载入时没有需重启的修改。
+fn server_section(port: u16) -> String {
+    format!("[server]\nport = {}\nmdns = false\nhttps = false\ntoken = \"{}\"\n", port, TOKEN)
+}
+
+/// 从配置文件启动的服务与配置文件路径。
+fn harness_with_config_file() -> (Harness, PathBuf) {
+    let mut config_path = PathBuf::new();
+    let harness = Harness::with(|builder, dir| {
+        let path = dir.join("config.toml");
+        std::fs::write(&path, server_section(0)).unwrap();
+        config_path = path.clone();
+        builder.config(Config::load_or_create(&path).unwrap()).config_file(path)
+    });
+    (harness, config_path)
+}
+
+fn preflight() -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/v1/clipboard")
+        .header(header::ORIGIN, ORIGIN)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn clipboard_history(harness: &Harness) -> usize {
+    let request = Request::builder().uri("/v1/clipboard/history").body(Body::empty()).unwrap();
+    let response = harness.send(from(request, LOCAL)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    response.json().as_array().unwrap().len()
+}
+
+/// 发送同一条短信并等待其通知，返回通知标题。
+async fn send_sms(harness: &Harness, content: &str) -> String {
+    let shown = harness.notifier.shown().len();
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": content })).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert!(wait_until(|| harness.notifier.shown().len() > shown).await, "no notification for {}", content);
+    harness.notifier.shown().last().unwrap().title.clone()
+}
+
+#[tokio::test]
+async fn reloaded_settings_apply_to_the_next_request() {
+    let (harness, path) = harness_with_config_file();
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &png(4, 4))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(harness.send(preflight()).await.header("access-control-allow-origin"), None);
+    assert!(!send_sms(&harness, "您的激活码 5521").await.contains("验证码"));
+
+    let config = format!(
+        r#"{}
+[sms]
+dedup_secs = 0
+
+[sms.codes]
+keywords = ["激活码"]
+
+[clipboard]
+history = 1
+
+[cors]
+allowed_origins = ["{}"]
+
+[policy.endpoints.photo]
+max_bytes = 10
+"#,
+        server_section(0),
+        ORIGIN
+    );
+    std::fs::write(&path, config).unwrap();
+    assert!(harness.server.reload_config());
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &png(8, 8))).await;
+    assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE, "{:?}", response.body);
+    assert_eq!(response.json()["reason"], "too_large");
+
+    assert_eq!(harness.send(preflight()).await.header("access-control-allow-origin"), Some(ORIGIN));
+
+    // 新的关键词立即用于提取验证码；去重关闭后相同的短信再次通知
+    assert_eq!(send_sms(&harness, "您的激活码 5521").await, "验证码: 5521");
+    assert_eq!(send_sms(&harness, "您的激活码 5521").await, "验证码: 5521");
+
+    for text in ["first", "second"] {
+        let response = harness.post_json("/v1/clipboard", json!({ "text": text })).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+    assert!(wait_until(|| harness.server.last_item_timings().is_some_and(|last| last.item_type == "clipboard")).await);
+    assert_eq!(clipboard_history(&harness).await, 1);
+
+    // 没有需重启的修改
+    let notices: Vec<_> = harness.notifier.shown().into_iter().filter(|n| n.tag == "config").collect();
+    assert!(notices.iter().all(|n| !n.body.join("").contains("需重启")), "{:?}", notices);
+}
+
+#[tokio::test]
+async fn pause_schedule_is_replaced_on_reload() {
+    let (harness, path) = harness_with_config_file();
+    let all_day = format!(
+        "{}
+[[pause_schedule]]
+days = [\"mon\", \"tue\", \"wed\", \"thu\", \"fri\", \"sat\", \"sun\"]\nstart = \"00:00:00\"\nend = \"23:59:59\"\n",
+        server_section(0)
+    );
+    std::fs::write(&path, all_day).unwrap();
+    assert!(harness.server.reload_config());
+    assert!(harness.server.scheduled_pause().is_some() || harness.server.next_scheduled_pause().is_some());
+
+    std::fs::write(&path, server_section(0)).unwrap();
+    assert!(harness.server.reload_config());
+    assert_eq!(harness.server.scheduled_pause(), None);
+    assert_eq!(harness.server.next_scheduled_pause(), None);
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "你好" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn listener_changes_wait_for_a_restart() {
+    let (harness, path) = harness_with_config_file();
+    std::fs::write(&path, format!("{}\n[clipboard]\nhistory = 1\n", server_section(4000))).unwrap();
+    assert!(harness.server.reload_config());
+
+    let config = harness.server.config();
+    assert_eq!(config.server.port, 0);
+    assert_eq!(config.clipboard.history, 1);
+    assert!(wait_until(|| harness.notifier.shown().iter().any(|n| n.tag == "config")).await);
+    let notice = harness.notifier.shown().into_iter().find(|n| n.tag == "config").unwrap();
+    assert!(notice.body.join("").contains("server.port"), "{:?}", notice.body);
+}
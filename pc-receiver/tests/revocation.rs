@@ -0,0 +1,84 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 解除配对：设备的 WebSocket 连接随即以 1008 关闭，与解除配对同时进行的连接不会保留下来。
+ */
+mod common;
+
+use common::Harness;
+use futures::StreamExt;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn connect(addr: SocketAddr, device_token: &str) -> Result<Socket, Error> {
+    let mut request = format!("ws://{}/v1/ws", addr).into_client_request().unwrap();
+    request
+        .headers_mut()
+        .insert("authorization", format!("Bearer {}", device_token).parse().unwrap());
+    tokio_tungstenite::connect_async(request).await.map(|(socket, _)| socket)
+}
+
+/// 读到关闭帧为止，返回关闭码。
+async fn close_code(socket: &mut Socket) -> Option<CloseCode> {
+    let read = async {
+        while let Some(frame) = socket.next().await {
+            match frame {
+                Ok(Message::Close(frame)) => return frame.map(|frame| frame.code),
+                Ok(_) => continue,
+                Err(_) => return None,
+            }
+        }
+        None
+    };
+    tokio::time::timeout(Duration::from_secs(5), read).await.expect("socket was not closed")
+}
+
+#[tokio::test]
+async fn revoking_closes_the_open_session() {
+    let harness = Harness::new();
+    let (device_id, device_token) = harness.pair("Pixel").await;
+    let addr = harness.serve().await;
+
+    let mut socket = connect(addr, &device_token).await.unwrap();
+    let hello = socket.next().await.unwrap().unwrap();
+    assert!(hello.to_text().unwrap().contains("\"hello\""));
+    assert!(harness.server.is_device_connected(&device_id));
+
+    assert!(harness.server.revoke_device(&device_id));
+    assert_eq!(close_code(&mut socket).await, Some(CloseCode::Policy));
+    assert!(!harness.server.is_device_connected(&device_id));
+
+    // 之后携带同一令牌的连接在升级前即被拒绝
+    assert!(matches!(
+        connect(addr, &device_token).await,
+        Err(Error::Http(response)) if response.status() == 401
+    ));
+}
+
+#[tokio::test]
+async fn session_racing_a_revoke_does_not_survive() {
+    let harness = Harness::new();
+    let addr = harness.serve().await;
+
+    for round in 0..20 {
+        let (device_id, device_token) = harness.pair(&format!("Pixel {}", round)).await;
+        let connecting = tokio::spawn(async move { connect(addr, &device_token).await });
+        // 令牌校验与连接登记之间留有空隙，解除配对可能落在其中任意位置
+        tokio::time::sleep(Duration::from_micros(round * 50)).await;
+        assert!(harness.server.revoke_device(&device_id));
+
+        match connecting.await.unwrap() {
+            Ok(mut socket) => assert_eq!(close_code(&mut socket).await, Some(CloseCode::Policy), "round {}", round),
+            Err(Error::Http(response)) => assert_eq!(response.status(), 401, "round {}", round),
+            Err(e) => panic!("round {}: {}", round, e),
+        }
+        assert!(!harness.server.is_device_connected(&device_id), "round {}", round);
+    }
+}
@@ -0,0 +1,53 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 计划暂停与令牌校验的先后顺序。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use chrono::{NaiveTime, Weekday};
+use common::{authorized, json_request, Harness};
+use fastsync::{PauseSchedule, PauseWindow};
+
+/// 全天暂停的服务：开始与结束时间相同视为跨越整整一天。
+fn paused() -> Harness {
+    let window = PauseWindow {
+        days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun],
+        start: NaiveTime::MIN,
+        end: NaiveTime::MIN,
+    };
+    Harness::with(|builder, _| builder.pause_schedule(PauseSchedule::new(vec![window]).unwrap()))
+}
+
+#[tokio::test]
+async fn unauthenticated_requests_are_rejected_before_the_pause() {
+    let harness = paused();
+    let cases = [
+        (Method::POST, "/v1/clipboard"),
+        (Method::POST, "/v1/upload"),
+        (Method::GET, "/v1/clipboard"),
+    ];
+    for (method, path) in cases {
+        let request = Request::builder().method(method.clone()).uri(path).body(Body::empty()).unwrap();
+        let response = harness.send(request).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "{} {}", method, path);
+        assert_eq!(response.header("retry-after"), None, "{} {}", method, path);
+        assert!(!String::from_utf8_lossy(&response.body).contains("scheduled_pause"), "{} {}", method, path);
+    }
+}
+
+#[tokio::test]
+async fn authenticated_requests_see_the_pause() {
+    let harness = paused();
+    let response = harness.send(json_request("/v1/clipboard", r#"{"text":"hello"}"#)).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json()["error"], "scheduled_pause");
+    assert!(response.header("retry-after").is_some());
+
+    let response = harness.send(authorized(Method::GET, "/v1/clipboard").body(Body::empty()).unwrap()).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(harness.clipboard.writes().is_empty());
+}
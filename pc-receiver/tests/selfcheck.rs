@@ -0,0 +1,64 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 启动自检：一切正常时不弹出通知，多项失败合并为一条通知。
+ */
+mod common;
+
+use common::{wait_until, Harness};
+use fastsync::CheckStatus;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// 只监听回环地址的服务。
+fn loopback_only() -> Harness {
+    Harness::with(|builder, _| builder.bind(IpAddr::V4(Ipv4Addr::LOCALHOST)))
+}
+
+/// 启动服务并等待自检完成。
+async fn start(harness: &Harness) {
+    harness.server.start().await.unwrap();
+    assert!(wait_until(|| harness.server.startup_report().is_some()).await, "self check did not finish");
+}
+
+#[tokio::test]
+async fn healthy_start_shows_no_alert() {
+    let harness = loopback_only();
+    start(&harness).await;
+    let report = harness.server.startup_report().unwrap();
+
+    assert!(report.is_healthy(), "{}", report.describe());
+    assert_eq!(report.alert_title(), None);
+    let loopback = report.checks.iter().find(|check| check.name == "loopback").unwrap();
+    assert_eq!(loopback.status, CheckStatus::Ok, "{}", loopback.detail);
+    assert!(harness.notifier.shown().iter().all(|notification| notification.tag != "startup_check"));
+
+    harness.server.shutdown();
+}
+
+#[tokio::test]
+async fn several_failures_are_reported_in_one_notification() {
+    let harness = Harness::with(|builder, dir| {
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "[server\nport = 3000\n").unwrap();
+        builder.bind(IpAddr::V4(Ipv4Addr::LOCALHOST)).config_file(path)
+    });
+    harness.notifier.set_enabled(false);
+    start(&harness).await;
+
+    let report = harness.server.startup_report().unwrap();
+    let failed: Vec<&str> = report.problems().map(|check| check.name).collect();
+    assert_eq!(failed, ["config", "notifications"], "{}", report.describe());
+
+    assert!(wait_until(|| harness.notifier.shown().iter().any(|notification| notification.tag == "startup_check")).await);
+    let alerts: Vec<_> = harness
+        .notifier
+        .shown()
+        .into_iter()
+        .filter(|notification| notification.tag == "startup_check")
+        .collect();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].title, "FastSync 启动异常: 配置文件有误、通知被禁用");
+
+    harness.server.shutdown();
+}
@@ -0,0 +1,45 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 短信通知：每条短信使用独立的标签，连续收到的验证码通知不会互相替换。
+ */
+mod common;
+
+use axum::http::StatusCode;
+use common::{wait_until, Harness};
+use fastsync::ClipboardWrite;
+use serde_json::json;
+
+#[tokio::test]
+async fn consecutive_codes_keep_separate_notifications() {
+    let harness = Harness::new();
+    for (sender, code) in [("10690001", "482916"), ("10690002", "735102")] {
+        let content = format!("您的验证码是 {}，5分钟内有效。", code);
+        let response = harness
+            .post_json("/v1/sms", json!({ "sender": sender, "content": content, "code": code }))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    }
+    assert!(wait_until(|| harness.notifier.shown().len() == 2).await);
+
+    let shown = harness.notifier.shown();
+    let tags: Vec<_> = shown.iter().map(|notification| notification.tag.clone()).collect();
+    assert_ne!(tags[0], tags[1]);
+    assert!(tags.iter().all(|tag| tag.starts_with("sms_")), "{:?}", tags);
+    assert!(shown.iter().all(|notification| notification.group == "sms"));
+
+    // 先到的通知仍可复制自己的验证码
+    assert!(harness.notifier.click(&tags[0], "copy_code"));
+    assert!(harness.notifier.click(&tags[1], "copy_code"));
+    let copied: Vec<_> = harness
+        .clipboard
+        .writes()
+        .into_iter()
+        .map(|write| match write {
+            ClipboardWrite::Text(text) | ClipboardWrite::SensitiveText(text) => text,
+            other => panic!("unexpected clipboard write {:?}", other),
+        })
+        .collect();
+    assert_eq!(copied, ["482916", "735102"]);
+}
@@ -0,0 +1,100 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 上传内容的类型以文件头为准：伪装成图片的文本、多格式混合文件与空内容。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, StatusCode};
+use common::{authorized, png, upload_request, wait_until, Harness};
+
+/// 以原始请求体上传。
+fn raw_request(content_type: &str, data: &[u8]) -> axum::http::Request<Body> {
+    authorized(Method::PUT, "/v1/upload/raw")
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(data.to_vec()))
+        .unwrap()
+}
+
+/// 完整的 GIF 之后是一段 HTML，按文件头应视为 GIF。
+fn gif_html_polyglot() -> Vec<u8> {
+    let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 255, 255]));
+    let mut data = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut data, image::ImageFormat::Gif).unwrap();
+    let mut data = data.into_inner();
+    data.extend_from_slice(b"<html><script>alert(1)</script></html>");
+    data
+}
+
+#[tokio::test]
+async fn text_declared_as_an_image_is_rejected() {
+    let harness = Harness::new();
+    let text = b"just some text, not an image";
+    let cases = [
+        ("a.png", "image/png"),
+        ("a.jpg", "image/jpeg"),
+        ("a.heic", "image/heic"),
+    ];
+    for (file_name, content_type) in cases {
+        let response = harness.send(upload_request("/v1/upload", file_name, content_type, text)).await;
+        assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", file_name);
+        assert_eq!(response.json()["error"], "unsupported_format", "{}", file_name);
+
+        let response = harness.send(raw_request(content_type, text)).await;
+        assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "raw {}", content_type);
+    }
+    assert_eq!(harness.notifier.attempts(), 0);
+}
+
+#[tokio::test]
+async fn polyglot_is_stored_by_its_sniffed_type() {
+    let harness = Harness::new();
+    let response = harness
+        .send(upload_request("/v1/upload", "evil.html", "text/html", &gif_html_polyglot()))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+    // 预览使用识别出的扩展名，声明的 .html 不会被系统按网页打开
+    let hero = harness.notifier.shown()[0].hero_image.clone().expect("image notification has a preview");
+    assert_eq!(hero.extension().and_then(|extension| extension.to_str()), Some("gif"), "{:?}", hero);
+}
+
+#[tokio::test]
+async fn image_bytes_behind_another_header_are_rejected() {
+    let harness = Harness::new();
+    let mut data = b"<html><body>".to_vec();
+    data.extend_from_slice(&png(4, 4));
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &data)).await;
+    assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let response = harness.send(raw_request("image/png", &data)).await;
+    assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn declared_type_is_corrected_from_the_header() {
+    let harness = Harness::new();
+    let response = harness.send(upload_request("/v1/upload", "a.gif", "image/gif", &png(4, 4))).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+    let hero = harness.notifier.shown()[0].hero_image.clone().unwrap();
+    assert_eq!(hero.extension().and_then(|extension| extension.to_str()), Some("png"), "{:?}", hero);
+}
+
+#[tokio::test]
+async fn empty_content_is_rejected_on_every_upload_endpoint() {
+    let harness = Harness::new();
+    for path in ["/v1/upload", "/v1/upload/file"] {
+        let response = harness.send(upload_request(path, "a.png", "image/png", b"")).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", path);
+        assert_eq!(response.json()["error"], "missing_field", "{}", path);
+    }
+    let response = harness.send(raw_request("image/png", b"")).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.json()["error"], "missing_field");
+    assert_eq!(harness.notifier.attempts(), 0);
+}
@@ -0,0 +1,48 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 免打扰：期间收到的内容不弹出通知而记为错过，关闭时汇总成一条通知。
+ */
+mod common;
+
+use axum::http::StatusCode;
+use common::{wait_until, Harness};
+use serde_json::json;
+
+#[tokio::test]
+async fn missed_items_are_held_then_flushed_as_one_digest() {
+    let harness = Harness::new();
+    harness.server.set_do_not_disturb(true).await;
+
+    for (sender, content) in [("10690", "第一条"), ("95588", "第二条")] {
+        let response = harness.post_json("/v1/sms", json!({ "sender": sender, "content": content })).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+    let response = harness.post_json("/v1/clipboard", json!({ "text": "hello" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    assert!(wait_until(|| harness.server.audit_records(10).iter().filter(|record| record.action == "missed").count() == 3).await);
+    assert_eq!(harness.notifier.attempts(), 0, "held notifications must not reach the backend");
+
+    harness.server.set_do_not_disturb(false).await;
+    let shown = harness.notifier.shown();
+    assert_eq!(shown.len(), 1, "{:?}", shown);
+    assert_eq!(shown[0].tag, "missed_digest");
+    assert_eq!(shown[0].title, "免打扰期间收到 2 条短信和 1 条剪贴板");
+    assert_eq!(shown[0].body.len(), 3);
+
+    // 免打扰结束后照常显示
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "第三条" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(wait_until(|| harness.notifier.shown().len() == 2).await);
+    assert_ne!(harness.notifier.shown()[1].tag, "missed_digest");
+}
+
+#[tokio::test]
+async fn turning_do_not_disturb_off_without_missed_items_shows_nothing() {
+    let harness = Harness::new();
+    harness.server.set_do_not_disturb(true).await;
+    harness.server.set_do_not_disturb(false).await;
+    assert_eq!(harness.notifier.attempts(), 0);
+}
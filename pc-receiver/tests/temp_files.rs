@@ -0,0 +1,84 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 收到的图片在临时目录中的去向：点击路径类按钮后移入 `received/`，点击其他按钮后按钮照常执行、临时文件随即删除。
+ */
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{png, upload_request, wait_until, Harness, TestDir};
+use fastsync::ClipboardWrite;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 目录中收到的上传，不含通知预览。
+fn uploads(dir: &Path) -> Vec<PathBuf> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| !path.file_name().unwrap().to_string_lossy().starts_with("fastsync_preview_"))
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 上传图片并返回其通知的标签。
+async fn upload(harness: &Harness, image: &[u8]) -> String {
+    let before = harness.notifier.shown().len();
+    let response = harness.send(upload_request("/v1/upload", "photo.png", "image/png", image)).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert!(wait_until(|| harness.notifier.shown().len() == before + 1).await);
+    harness.notifier.shown()[before].tag.clone()
+}
+
+#[tokio::test]
+async fn temp_images_follow_the_clicked_action() {
+    // 本进程中只有这个测试，临时目录与数据目录改为独立目录以便计数
+    let temp = TestDir::new();
+    let data = TestDir::new();
+    std::env::set_var("TMPDIR", temp.path());
+    std::env::set_var("XDG_DATA_HOME", data.path());
+    let received = data.path().join("FastSync").join("received");
+    // 每张图片单独通知，不合并为连拍
+    let harness = Harness::with(|builder, _| builder.photo_path_actions(true).burst_window(Duration::ZERO));
+
+    // 复制为文件：临时文件移入 received/，剪贴板中是移动后的路径
+    let tag = upload(&harness, &png(8, 8)).await;
+    let temp_image = uploads(temp.path());
+    assert_eq!(temp_image.len(), 1, "{:?}", temp_image);
+    assert!(harness.notifier.click(&tag, "copy_file"));
+    assert!(wait_until(|| !harness.clipboard.writes().is_empty()).await);
+    let kept = uploads(&received);
+    assert_eq!(kept.len(), 1, "{:?}", kept);
+    assert_eq!(harness.clipboard.writes(), vec![ClipboardWrite::Files(kept.clone())]);
+    assert!(!temp_image[0].exists());
+    assert_eq!(std::fs::read(&kept[0]).unwrap(), png(8, 8));
+
+    // 复制路径：同样移入 received/，另起文件名
+    let tag = upload(&harness, &png(9, 9)).await;
+    assert!(harness.notifier.click(&tag, "copy_path"));
+    assert!(wait_until(|| harness.clipboard.writes().len() == 2).await);
+    assert_eq!(uploads(&received).len(), 2);
+    assert!(uploads(temp.path()).is_empty(), "{:?}", uploads(temp.path()));
+
+    // 复制图片：按钮执行后临时文件被删除，不进入 received/
+    let tag = upload(&harness, &png(10, 10)).await;
+    assert_eq!(uploads(temp.path()).len(), 1);
+    assert!(harness.notifier.click(&tag, "copy"));
+    assert!(wait_until(|| harness.clipboard.writes().len() == 3).await);
+    assert!(matches!(harness.clipboard.writes()[2], ClipboardWrite::Image { width: 10, height: 10 }));
+    assert!(wait_until(|| uploads(temp.path()).is_empty()).await, "{:?}", uploads(temp.path()));
+    assert_eq!(uploads(&received).len(), 2);
+
+    // 快速保存：写入快速保存目录，临时文件同样删除
+    let tag = upload(&harness, &png(11, 11)).await;
+    assert!(harness.notifier.click(&tag, "quick_save"));
+    assert!(wait_until(|| uploads(&harness.dir.path().join("quick")).len() == 1).await);
+    assert!(wait_until(|| uploads(temp.path()).is_empty()).await, "{:?}", uploads(temp.path()));
+    assert_eq!(uploads(&received).len(), 2);
+}
@@ -0,0 +1,86 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 缩略图：ETag 与 If-None-Match、未知或其他设备的图片。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{png, upload_request, Harness};
+
+/// 携带设备令牌的请求。
+fn with_token(mut request: Request<Body>, token: &str) -> Request<Body> {
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+    request
+}
+
+/// 携带设备令牌请求缩略图。
+fn thumb(token: &str, id: &str, if_none_match: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().method(Method::GET).uri(format!("/v1/photos/{}/thumb", id));
+    if let Some(tag) = if_none_match {
+        request = request.header(header::IF_NONE_MATCH, tag);
+    }
+    with_token(request.body(Body::empty()).unwrap(), token)
+}
+
+/// 以设备令牌上传一张图片，返回图片标识与内容哈希。
+async fn upload(harness: &Harness, token: &str) -> (String, String) {
+    let response = harness
+        .send(with_token(upload_request("/v1/upload", "a.png", "image/png", &png(64, 48)), token))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    let body = response.json();
+    (body["id"].as_str().unwrap().to_string(), body["hash"].as_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn etag_revalidation_returns_not_modified() {
+    let harness = Harness::new();
+    let (_, token) = harness.pair("Pixel").await;
+    let (id, hash) = upload(&harness, &token).await;
+
+    let response = harness.send(thumb(&token, &id, None)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.header("content-type"), Some("image/jpeg"));
+    let etag = response.header("etag").unwrap().to_string();
+    assert_eq!(etag, format!("\"{}\"", hash));
+    assert!(image::load_from_memory(&response.body).is_ok());
+
+    let cases = [
+        (etag.clone(), StatusCode::NOT_MODIFIED),
+        (format!("\"other\", {}", etag), StatusCode::NOT_MODIFIED),
+        ("*".to_string(), StatusCode::NOT_MODIFIED),
+        ("\"other\"".to_string(), StatusCode::OK),
+    ];
+    for (tag, expected) in cases {
+        let response = harness.send(thumb(&token, &id, Some(&tag))).await;
+        assert_eq!(response.status, expected, "If-None-Match: {}", tag);
+        assert_eq!(response.header("etag"), Some(etag.as_str()), "If-None-Match: {}", tag);
+        if expected == StatusCode::NOT_MODIFIED {
+            assert!(response.body.is_empty(), "If-None-Match: {}", tag);
+        }
+    }
+}
+
+#[tokio::test]
+async fn unknown_or_foreign_photos_are_not_found() {
+    let harness = Harness::new();
+    let (_, owner) = harness.pair("Pixel").await;
+    let (_, other) = harness.pair("iPad").await;
+    let (id, _) = upload(&harness, &owner).await;
+
+    let response = harness.send(thumb(&owner, "0000000000000000", None)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.json()["error"], "not_found");
+
+    // 其他设备上传的图片同样视为不存在
+    let response = harness.send(thumb(&other, &id, None)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let request = Request::builder().uri(format!("/v1/photos/{}/thumb", id)).body(Body::empty()).unwrap();
+    assert_eq!(harness.send(request).await.status, StatusCode::UNAUTHORIZED);
+}
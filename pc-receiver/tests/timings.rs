@@ -0,0 +1,53 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 处理耗时：各接口的内容按发生顺序记录阶段，通知显示后计入最近一个内容的耗时。
+ */
+mod common;
+
+use axum::http::StatusCode;
+use common::{png, upload_request, wait_until, Harness};
+use fastsync::Stage;
+use serde_json::json;
+
+/// 等待 `item_type` 的内容处理结束，返回其阶段。
+async fn finished_stages(harness: &Harness, item_type: &str) -> Vec<Stage> {
+    assert!(
+        wait_until(|| harness.server.last_item_timings().is_some_and(|last| last.item_type == item_type)).await,
+        "{} never finished",
+        item_type
+    );
+    harness.server.last_item_timings().unwrap().stages.into_iter().map(|(stage, _)| stage).collect()
+}
+
+#[tokio::test]
+async fn stages_are_recorded_in_order() {
+    let harness = Harness::new();
+
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "你好" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(finished_stages(&harness, "sms").await, [Stage::Received, Stage::Enqueued, Stage::Shown]);
+
+    let response = harness.post_json("/v1/clipboard", json!({ "text": "hello" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(finished_stages(&harness, "clipboard").await, [Stage::Received, Stage::Enqueued, Stage::Shown]);
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &png(4, 4))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        finished_stages(&harness, "photo").await,
+        [Stage::Received, Stage::Preview, Stage::Enqueued, Stage::Shown]
+    );
+}
+
+#[tokio::test]
+async fn diverted_notification_finishes_without_the_shown_stage() {
+    let harness = Harness::new();
+    harness.notifier.fail_next(usize::MAX);
+    harness.server.shutdown();
+
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "你好" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(finished_stages(&harness, "sms").await, [Stage::Received, Stage::Enqueued]);
+}
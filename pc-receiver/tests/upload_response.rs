@@ -0,0 +1,85 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 上传接口共用的响应结构：`ok`、`error`、收到的字节数 `bytes` 与内容的 SHA-256 `hash`。
+ */
+mod common;
+
+use axum::http::StatusCode;
+use common::{json_request, multipart_request, png, upload_request, Harness, Part};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+fn sha256(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// 检查响应的状态码与共用字段，`hash` 为 None 时响应中不应出现该字段。
+fn assert_envelope(response: &common::TestResponse, status: StatusCode, error: Option<&str>, bytes: usize, hash: Option<&str>) -> Value {
+    assert_eq!(response.status, status, "{}", String::from_utf8_lossy(&response.body));
+    let json = response.json();
+    assert_eq!(json["ok"], error.is_none(), "{}", json);
+    assert_eq!(json["error"].as_str(), error, "{}", json);
+    assert_eq!(json["bytes"], bytes, "{}", json);
+    assert_eq!(json["hash"].as_str(), hash, "{}", json);
+    json
+}
+
+#[tokio::test]
+async fn accepted_image_reports_bytes_and_hash() {
+    let harness = Harness::new();
+    let image = png(8, 8);
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", &image)).await;
+    let json = assert_envelope(&response, StatusCode::OK, None, image.len(), Some(&sha256(&image)));
+    assert!(json["id"].as_str().is_some_and(|id| !id.is_empty()), "{}", json);
+}
+
+#[tokio::test]
+async fn rejected_uploads_use_the_same_shape() {
+    let harness = Harness::new();
+    let image = png(8, 8);
+    let text = b"definitely not an image";
+
+    let response = harness.send(multipart_request("/v1/upload", &[Part::text("note", "hello")])).await;
+    assert_envelope(&response, StatusCode::BAD_REQUEST, Some("missing_field"), 0, None);
+
+    let response = harness.send(upload_request("/v1/upload", "a.png", "image/png", text)).await;
+    assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(response.json()["error"], "unsupported_format");
+    assert_eq!(response.json()["ok"], false);
+
+    let mut request = upload_request("/v1/upload", "a.png", "image/png", &image);
+    request
+        .headers_mut()
+        .insert("x-content-sha256", sha256(b"something else").parse().unwrap());
+    let response = harness.send(request).await;
+    // 校验和不一致时仍报告实际收到的内容，手机端据此判断是否被截断
+    assert_envelope(&response, StatusCode::UNPROCESSABLE_ENTITY, Some("checksum_mismatch"), image.len(), Some(&sha256(&image)));
+}
+
+#[tokio::test]
+async fn json_endpoints_hash_the_text() {
+    let harness = Harness::new();
+
+    let response = harness.post_json("/v1/clipboard", json!({ "text": "hello" })).await;
+    assert_envelope(&response, StatusCode::OK, None, 5, Some(&sha256(b"hello")));
+
+    let response = harness.post_json("/v1/sms", json!({ "sender": "10690", "content": "你好" })).await;
+    assert_envelope(&response, StatusCode::OK, None, "你好".len(), Some(&sha256("你好".as_bytes())));
+}
+
+#[tokio::test]
+async fn json_rejections_are_mapped_once_for_every_endpoint() {
+    let harness = Harness::new();
+    // 同一请求体在载荷接口与配对接口上得到相同的错误
+    for path in ["/v1/sms", "/v1/pair"] {
+        let response = harness.send(json_request(path, "{")).await;
+        assert_envelope(&response, StatusCode::BAD_REQUEST, Some("decode_failed"), 0, None);
+
+        let response = harness.send(json_request(path, "[1, 2]")).await;
+        let json = assert_envelope(&response, StatusCode::UNPROCESSABLE_ENTITY, Some("invalid_payload"), 0, None);
+        assert_eq!(json["field"], "[0]", "{}", path);
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * JSON 载荷校验：经路由向 /sms 与 /clipboard 发送格式有误的请求体，检查出错的字段与期望的类型。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, StatusCode};
+use common::{authorized, json_request, Harness};
+use serde_json::Value;
+
+#[tokio::test]
+async fn invalid_payloads_name_the_field() {
+    let harness = Harness::new();
+    // 路径、请求体、字段、原因、期望
+    let cases = [
+        ("/v1/sms", r#"{"sender":1,"content":"x"}"#, "sender", "invalid_type", Some("a string")),
+        ("/v1/sms", r#"{"content":"x"}"#, "sender", "missing_field", None),
+        ("/v1/sms", r#"{"sender":"10690"}"#, "content", "missing_field", None),
+        ("/v1/sms", r#"{"sender":"10690","content":"x","captured_at":1.5}"#, "captured_at", "invalid_type", Some("i64")),
+        ("/v1/sms", r#"{"sender":"10690","content":"x","timestamp":"now"}"#, "timestamp", "invalid_type", Some("i64")),
+        ("/v1/sms", r#"{"sender":"10690","content":null}"#, "content", "invalid_type", Some("a string")),
+        ("/v1/sms", "42", "", "invalid_type", Some("struct SmsPayload")),
+        ("/v1/clipboard", r#"{"text":5}"#, "text", "invalid_type", Some("a string")),
+        ("/v1/clipboard", r#"{"text":"a","timestamp":"yesterday"}"#, "timestamp", "invalid_type", Some("i64")),
+        ("/v1/clipboard", r#"{"text":"a","html":[]}"#, "html", "invalid_type", Some("a string")),
+        ("/v1/clipboard", r#""hello""#, "", "invalid_type", Some("struct ClipboardPayload")),
+    ];
+    for (path, body, field, reason, expected) in cases {
+        let response = harness.send(json_request(path, body)).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY, "{} {}", path, body);
+        let json = response.json();
+        assert_eq!(json["ok"], false, "{} {}", path, body);
+        assert_eq!(json["error"], "invalid_payload", "{} {}", path, body);
+        assert_eq!(json["field"], field, "{} {}", path, body);
+        assert_eq!(json["reason"], reason, "{} {}", path, body);
+        assert_eq!(json["expected"].as_str(), expected, "{} {}", path, body);
+        assert!(json["message"].as_str().is_some_and(|message| !message.is_empty()), "{} {}", path, body);
+    }
+    assert!(harness.notifier.shown().is_empty());
+    assert!(harness.clipboard.writes().is_empty());
+}
+
+#[tokio::test]
+async fn syntax_errors_report_the_position() {
+    let harness = Harness::new();
+    let cases = [
+        ("/v1/sms", "{\"sender\":", 1, 10),
+        ("/v1/sms", "{\"sender\":\"10690\",\n\"content\":\"x\",}", 2, 15),
+        ("/v1/clipboard", "{\"text\":\"a\"} trailing", 1, 14),
+        ("/v1/clipboard", "", 1, 0),
+    ];
+    for (path, body, line, column) in cases {
+        let response = harness.send(json_request(path, body)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{} {:?}", path, body);
+        let json = response.json();
+        assert_eq!(json["error"], "decode_failed", "{} {:?}", path, body);
+        assert_eq!((json["line"].as_u64(), json["column"].as_u64()), (Some(line), Some(column)), "{} {:?}", path, body);
+        assert_eq!(json.get("field"), None, "{} {:?}", path, body);
+    }
+}
+
+#[tokio::test]
+async fn non_json_content_type_is_rejected() {
+    let harness = Harness::new();
+    for path in ["/v1/sms", "/v1/clipboard"] {
+        let request = authorized(Method::POST, path)
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body(Body::from(r#"{"text":"a"}"#))
+            .unwrap();
+        let response = harness.send(request).await;
+        assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE, "{}", path);
+        assert_eq!(response.json()["expected"], Value::from("application/json"), "{}", path);
+    }
+}
@@ -0,0 +1,123 @@
+/*
+ * @Author: DuoDuoJuZi
+ * @Date: 2026-10-15
+ *
+ * 浏览器上传页面：静态资源的安全响应头、访客令牌与任意文件上传。
+ */
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{png, upload_request, wait_until, Harness};
+
+/// 不带令牌的 GET 请求。
+fn anonymous_get(path: &str) -> Request<Body> {
+    Request::builder().method(Method::GET).uri(path).body(Body::empty()).unwrap()
+}
+
+/// 携带访客令牌、以 `data` 字段上传一个文件的请求。
+fn guest_upload(token: &str, path: &str, file_name: &str, content_type: &str, data: &[u8]) -> Request<Body> {
+    let mut request = upload_request(path, file_name, content_type, data);
+    request
+        .headers_mut()
+        .insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn static_assets_carry_security_headers() {
+    let harness = Harness::new();
+    let cases = [
+        ("/", "text/html; charset=utf-8"),
+        ("/app.js", "text/javascript; charset=utf-8"),
+        ("/app.css", "text/css; charset=utf-8"),
+    ];
+    for (path, content_type) in cases {
+        let response = harness.send(anonymous_get(path)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", path);
+        assert_eq!(response.header("content-type"), Some(content_type), "{}", path);
+        let csp = response.header("content-security-policy").unwrap_or_default();
+        assert!(csp.contains("default-src 'none'"), "{}: {}", path, csp);
+        assert!(csp.contains("script-src 'self'"), "{}: {}", path, csp);
+        assert!(csp.contains("frame-ancestors 'none'"), "{}: {}", path, csp);
+        assert_eq!(response.header("x-content-type-options"), Some("nosniff"), "{}", path);
+        assert_eq!(response.header("referrer-policy"), Some("no-referrer"), "{}", path);
+        assert!(!response.body.is_empty(), "{}", path);
+    }
+
+    let page = String::from_utf8(harness.send(anonymous_get("/")).await.body.to_vec()).unwrap();
+    assert!(page.contains("app.js"), "{}", page);
+}
+
+#[tokio::test]
+async fn guest_token_is_checked_by_the_guest_endpoint() {
+    let harness = Harness::new();
+    let access = harness.server.allow_browser_upload();
+
+    let request = Request::builder()
+        .uri("/v1/guest")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access.token))
+        .body(Body::empty())
+        .unwrap();
+    let response = harness.send(request).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["emoji"], access.emoji.as_str());
+    let expires_in = body["expires_in"].as_u64().unwrap();
+    assert!(expires_in > 0 && expires_in <= access.expires_in.as_secs(), "{}", expires_in);
+
+    for authorization in [None, Some("Bearer not-a-guest-token")] {
+        let mut request = Request::builder().uri("/v1/guest");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        let response = harness.send(request.body(Body::empty()).unwrap()).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "{:?}", authorization);
+    }
+}
+
+#[tokio::test]
+async fn file_endpoint_accepts_files_the_photo_endpoint_rejects() {
+    let harness = Harness::new();
+    let document = b"%PDF-1.7\nnot an image at all\n";
+
+    let response = harness.send(upload_request("/v1/upload", "report.pdf", "application/pdf", document)).await;
+    assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let response = harness.send(upload_request("/v1/upload/file", "report.pdf", "application/pdf", document)).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert_eq!(response.json()["bytes"], document.len());
+    assert!(wait_until(|| harness.notifier.shown().len() == 1).await);
+
+    // 图片经文件接口上传时仍按图片处理
+    let image = png(4, 4);
+    let response = harness.send(upload_request("/v1/upload/file", "a.png", "image/png", &image)).await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+    assert!(wait_until(|| harness.notifier.shown().len() == 2).await);
+}
+
+#[tokio::test]
+async fn guest_can_upload_but_not_manage_devices() {
+    let harness = Harness::new();
+    let access = harness.server.allow_browser_upload();
+
+    let response = harness
+        .send(guest_upload(&access.token, "/v1/upload/file", "notes.txt", "text/plain", b"hello"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{:?}", response.body);
+
+    let request = Request::builder()
+        .uri("/v1/devices")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access.token))
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(harness.send(request).await.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn file_upload_is_advertised_in_info() {
+    let harness = Harness::new();
+    let info = harness.get("/v1/info").await.json();
+    let endpoints: Vec<&str> = info["endpoints"].as_array().unwrap().iter().filter_map(|value| value.as_str()).collect();
+    assert!(endpoints.contains(&"POST /upload/file"), "{:?}", endpoints);
+}
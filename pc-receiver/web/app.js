@@ -0,0 +1,192 @@
+"use strict";
+
+// 访客令牌放在 URL 的 # 之后，不会出现在请求与服务端日志中
+const guestToken = decodeURIComponent(location.hash.slice(1));
+
+// 手动输入的访问令牌只保存在当前标签页，关闭后需要重新输入
+const TOKEN_KEY = "fastsync-token";
+
+let token = guestToken || sessionStorage.getItem(TOKEN_KEY) || "";
+
+const $ = (id) => document.getElementById(id);
+
+function showResult(message) {
+  $("result").textContent = message;
+}
+
+// 显示接口返回的 JSON，不是 JSON 时原样显示
+function showResponse(body) {
+  let text = body;
+  try {
+    text = JSON.stringify(JSON.parse(body), null, 2);
+  } catch (error) {
+    // 保留原文
+  }
+  $("response").textContent = text;
+  $("response").hidden = !text;
+}
+
+function authHeaders() {
+  return token ? { Authorization: "Bearer " + token } : {};
+}
+
+function showPanels(status) {
+  $("status").textContent = status;
+  $("login").hidden = true;
+  $("panels").hidden = false;
+}
+
+function showLogin(status) {
+  $("status").textContent = status;
+  $("panels").hidden = true;
+  $("login").hidden = false;
+  $("token").focus();
+}
+
+// 令牌被拒绝：访客链接失效需重新扫码，手动输入的令牌重新输入
+function rejectToken() {
+  if (guestToken) {
+    $("panels").hidden = true;
+    $("status").textContent = "链接已失效，请在电脑托盘中重新开启浏览器上传";
+    return;
+  }
+  token = "";
+  sessionStorage.removeItem(TOKEN_KEY);
+  showLogin("令牌无效，请重新输入");
+}
+
+async function verifyGuest() {
+  const response = await fetch("/v1/guest", { headers: authHeaders() });
+  if (!response.ok) {
+    $("status").textContent = "链接已失效，请在电脑托盘中重新开启浏览器上传";
+    return;
+  }
+
+  const info = await response.json();
+  $("emoji").textContent = info.emoji;
+  $("emoji").hidden = false;
+  $("hint").hidden = false;
+  showPanels("已连接，剩余 " + Math.ceil(info.expires_in / 60) + " 分钟");
+}
+
+async function verify() {
+  if (guestToken) {
+    await verifyGuest();
+    return;
+  }
+
+  const response = await fetch("/v1/info");
+  const info = response.ok ? await response.json() : { auth_required: true };
+  if (!info.auth_required) {
+    showPanels("已连接到 " + info.hostname);
+  } else if (token) {
+    showPanels("已使用访问令牌连接到 " + info.hostname);
+  } else {
+    showLogin("请输入访问令牌，或扫描电脑托盘中“允许浏览器上传”显示的二维码");
+  }
+}
+
+function uploadFile(file) {
+  return new Promise((resolve, reject) => {
+    const form = new FormData();
+    form.append("data", file, file.name);
+
+    const request = new XMLHttpRequest();
+    // 文件接口同样处理图片，其他文件在电脑上提供另存
+    request.open("POST", "/v1/upload/file");
+    for (const [name, value] of Object.entries(authHeaders())) {
+      request.setRequestHeader(name, value);
+    }
+    request.upload.onprogress = (event) => {
+      if (event.lengthComputable) {
+        $("progress").value = (event.loaded / event.total) * 100;
+        showResult("正在发送 " + file.name + " " + Math.floor((event.loaded / event.total) * 100) + "%");
+      }
+    };
+    request.onload = () => resolve({ status: request.status, body: request.responseText });
+    request.onerror = () => reject(new Error("网络错误"));
+    request.send(form);
+  });
+}
+
+async function uploadFiles(files) {
+  $("progress").hidden = false;
+  for (const file of files) {
+    $("progress").value = 0;
+    showResult("正在发送 " + file.name);
+    try {
+      const { status, body } = await uploadFile(file);
+      showResponse(body);
+      if (status === 401) {
+        rejectToken();
+        break;
+      }
+      if (status !== 200) {
+        showResult(file.name + " 发送失败: HTTP " + status);
+        break;
+      }
+      showResult("已发送 " + file.name);
+    } catch (error) {
+      showResult(file.name + " 发送失败: " + error.message);
+      break;
+    }
+  }
+  $("progress").hidden = true;
+  $("file").value = "";
+}
+
+async function sendText() {
+  const text = $("text").value;
+  if (!text) {
+    return;
+  }
+
+  const response = await fetch("/v1/clipboard", {
+    method: "POST",
+    headers: { ...authHeaders(), "Content-Type": "application/json" },
+    body: JSON.stringify({ text: text, timestamp: Date.now() }),
+  });
+  showResponse(await response.text());
+  if (response.ok) {
+    $("text").value = "";
+    showResult("文字已发送");
+  } else if (response.status === 401) {
+    rejectToken();
+  } else {
+    showResult("文字发送失败: HTTP " + response.status);
+  }
+}
+
+$("login").addEventListener("submit", (event) => {
+  event.preventDefault();
+  token = $("token").value.trim();
+  if (!token) {
+    return;
+  }
+  sessionStorage.setItem(TOKEN_KEY, token);
+  $("token").value = "";
+  showResult("");
+  verify().catch((error) => {
+    $("status").textContent = "连接失败: " + error.message;
+  });
+});
+$("file").addEventListener("change", (event) => uploadFiles(event.target.files));
+$("send-text").addEventListener("click", () => {
+  sendText().catch((error) => showResult("文字发送失败: " + error.message));
+});
+
+const drop = $("drop");
+drop.addEventListener("dragover", (event) => {
+  event.preventDefault();
+  drop.classList.add("over");
+});
+drop.addEventListener("dragleave", () => drop.classList.remove("over"));
+drop.addEventListener("drop", (event) => {
+  event.preventDefault();
+  drop.classList.remove("over");
+  uploadFiles(event.dataTransfer.files);
+});
+
+verify().catch((error) => {
+  $("status").textContent = "连接失败: " + error.message;
+});